@@ -10,6 +10,7 @@
 #include <string>
 #include <thread>
 #include <vector>
+#include <algorithm>
 #include <cstring>
 #include <sstream>
 #include <random>
@@ -135,6 +136,8 @@ struct whisper_params {
 
     std::string language        = "en";
     std::string prompt          = "";
+    // Newline-separated words and phrases the decoder may never produce
+    std::string suppress_tokens = "";
     std::string font_path       = "/System/Library/Fonts/Supplemental/Courier New Bold.ttf";
     std::string model           = "models/ggml-base.en.bin";
 
@@ -548,6 +551,132 @@ void get_req_parameters(const Request & req, whisper_params & params)
     {
         params.suppress_nst = parse_str_to_bool(req.get_file_value("suppress_nst").content);
     }
+    if (req.has_file("no_speech_thold"))
+    {
+        params.no_speech_thold = std::stof(req.get_file_value("no_speech_thold").content);
+    }
+    if (req.has_file("suppress_tokens"))
+    {
+        params.suppress_tokens = req.get_file_value("suppress_tokens").content;
+    }
+}
+
+// Tokenizing the prompt is repeated work when every request sends the same
+// one, so the tokens are kept until the prompt or the model changes
+struct prompt_token_cache {
+    bool valid = false;
+    std::string text;
+    std::vector<whisper_token> tokens;
+};
+
+// Compute backend decoding runs on: the GPU backend this build was compiled
+// with when the context was created with use_gpu, otherwise the CPU
+std::string compute_backend(bool use_gpu) {
+    if (!use_gpu) {
+        return "cpu";
+    }
+    std::string info = whisper_print_system_info();
+    std::transform(info.begin(), info.end(), info.begin(), ::tolower);
+    // Older builds print "METAL = 1", newer ones list the backend by name
+    const auto has_backend = [&info](const std::string & name) {
+        return info.find(name + " = 1") != std::string::npos || info.find(name + " :") != std::string::npos;
+    };
+    if (has_backend("metal")) {
+        return "metal";
+    }
+    if (has_backend("cuda")) {
+        return "cuda";
+    }
+    return "cpu";
+}
+
+const std::vector<whisper_token> & cached_prompt_tokens(struct whisper_context * ctx, prompt_token_cache & cache, const std::string & prompt)
+{
+    if (cache.valid && cache.text == prompt) {
+        return cache.tokens;
+    }
+
+    cache.tokens.resize(whisper_n_text_ctx(ctx));
+    int n_tokens = 0;
+    if (!prompt.empty()) {
+        n_tokens = whisper_tokenize(ctx, prompt.c_str(), cache.tokens.data(), cache.tokens.size());
+        if (n_tokens < 0) {
+            cache.tokens.resize(-n_tokens);
+            n_tokens = whisper_tokenize(ctx, prompt.c_str(), cache.tokens.data(), cache.tokens.size());
+        }
+    }
+    cache.tokens.resize(std::max(n_tokens, 0));
+    cache.text  = prompt;
+    cache.valid = true;
+    return cache.tokens;
+}
+
+std::vector<whisper_token> tokenize(struct whisper_context * ctx, const std::string & text)
+{
+    std::vector<whisper_token> tokens(text.size() + 8);
+    int n_tokens = whisper_tokenize(ctx, text.c_str(), tokens.data(), tokens.size());
+    if (n_tokens < 0) {
+        tokens.resize(-n_tokens);
+        n_tokens = whisper_tokenize(ctx, text.c_str(), tokens.data(), tokens.size());
+    }
+    tokens.resize(std::max(n_tokens, 0));
+    return tokens;
+}
+
+// Banned phrases as token sequences, each both as written and word-initial
+// (with a leading space), since whisper tokenizes the two differently. Kept
+// until the list or the model changes, like the prompt tokens
+struct suppress_token_cache {
+    bool valid = false;
+    std::string text;
+    std::vector<std::vector<whisper_token>> sequences;
+};
+
+const std::vector<std::vector<whisper_token>> & cached_suppress_sequences(struct whisper_context * ctx, suppress_token_cache & cache, const std::string & phrases)
+{
+    if (cache.valid && cache.text == phrases) {
+        return cache.sequences;
+    }
+
+    cache.sequences.clear();
+    std::istringstream lines(phrases);
+    std::string phrase;
+    while (std::getline(lines, phrase)) {
+        if (phrase.empty()) {
+            continue;
+        }
+        for (const auto & variant : { phrase, " " + phrase }) {
+            auto tokens = tokenize(ctx, variant);
+            if (!tokens.empty() && std::find(cache.sequences.begin(), cache.sequences.end(), tokens) == cache.sequences.end()) {
+                cache.sequences.push_back(std::move(tokens));
+            }
+        }
+    }
+    cache.text  = phrases;
+    cache.valid = true;
+    return cache.sequences;
+}
+
+// Logits filter that rules out the last token of a banned sequence whenever
+// the tokens decoded so far end with the rest of it, so the phrase can't be
+// completed; single-token bans are ruled out everywhere
+void suppress_banned_sequences(struct whisper_context * /*ctx*/, struct whisper_state * /*state*/,
+                               const whisper_token_data * tokens, int n_tokens, float * logits, void * user_data)
+{
+    const auto & sequences = *static_cast<const std::vector<std::vector<whisper_token>> *>(user_data);
+    for (const auto & sequence : sequences) {
+        const int n_prefix = sequence.size() - 1;
+        if (n_prefix > n_tokens) {
+            continue;
+        }
+        bool prefix_matches = true;
+        for (int i = 0; i < n_prefix && prefix_matches; ++i) {
+            prefix_matches = tokens[n_tokens - n_prefix + i].id == sequence[i];
+        }
+        if (prefix_matches) {
+            logits[sequence.back()] = -INFINITY;
+        }
+    }
 }
 
 }  // namespace
@@ -557,6 +686,8 @@ int main(int argc, char ** argv) {
     server_params sparams;
 
     std::mutex whisper_mutex;
+    prompt_token_cache prompt_cache;
+    suppress_token_cache suppress_cache;
 
     if (whisper_params_parse(argc, argv, params, sparams) == false) {
         whisper_print_usage(argc, argv, params, sparams);
@@ -645,8 +776,9 @@ int main(int argc, char ** argv) {
         fflush(stderr);
         return 3;
     }
-    fprintf(stderr, "[INFO] Successfully initialized whisper context\n");
+    fprintf(stderr, "[INFO] Successfully initialized whisper context on %s\n", compute_backend(cparams.use_gpu).c_str());
     fflush(stderr);
+    std::string loaded_model = params.model;
     // initialize openvino encoder. this has no effect on whisper.cpp builds that don't have OpenVINO configured
     whisper_ctx_init_openvino_encoder(ctx, nullptr, params.openvino_encode_device.c_str(), nullptr);
 
@@ -877,7 +1009,9 @@ int main(int argc, char ** argv) {
 
             wparams.tdrz_enable      = params.tinydiarize; // [TDRZ]
 
-            wparams.initial_prompt   = params.prompt.c_str();
+            const auto & prompt_tokens = cached_prompt_tokens(ctx, prompt_cache, params.prompt);
+            wparams.prompt_tokens    = prompt_tokens.empty() ? nullptr : prompt_tokens.data();
+            wparams.prompt_n_tokens  = prompt_tokens.size();
 
             wparams.greedy.best_of        = params.best_of;
             wparams.beam_search.beam_size = params.beam_size;
@@ -1075,6 +1209,16 @@ int main(int argc, char ** argv) {
             return;
         }
 
+        // Per-chunk decoding options (language, prompt, beam size, temperature
+        // schedule, no-speech threshold, suppressed tokens) over the
+        // command-line defaults
+        whisper_params chunk_params = params;
+        get_req_parameters(req, chunk_params);
+        if (chunk_params.language != "auto" && whisper_lang_id(chunk_params.language.c_str()) == -1) {
+            res.set_content("{\"error\":\"unknown language\"}", "application/json");
+            return;
+        }
+
         auto audio_file = req.get_file_value("audio");
         const float* audio_data = reinterpret_cast<const float*>(audio_file.content.c_str());
         int n_samples = audio_file.content.size() / sizeof(float);
@@ -1091,11 +1235,32 @@ int main(int argc, char ** argv) {
         // Only process if we have enough audio data
         if (audio_buffer.size() >= min_samples) {
             // Run inference
-            whisper_full_params wparams = whisper_full_default_params(WHISPER_SAMPLING_GREEDY);
+            const bool beam_search = chunk_params.beam_size > 1;
+            whisper_full_params wparams = whisper_full_default_params(
+                beam_search ? WHISPER_SAMPLING_BEAM_SEARCH : WHISPER_SAMPLING_GREEDY);
             wparams.print_progress = false;
             wparams.print_special = params.print_special;
-            wparams.language = params.language.c_str();
+            wparams.language = chunk_params.language.c_str();
             wparams.n_threads = params.n_threads;
+            wparams.no_speech_thold = chunk_params.no_speech_thold;
+            if (beam_search) {
+                wparams.beam_search.beam_size = chunk_params.beam_size;
+            }
+            wparams.temperature = chunk_params.temperature;
+            wparams.temperature_inc = chunk_params.temperature_inc;
+            wparams.translate = chunk_params.translate;
+
+            // Tokenized once and reused until the prompt changes
+            const auto & prompt_tokens = cached_prompt_tokens(ctx, prompt_cache, chunk_params.prompt);
+            wparams.prompt_tokens = prompt_tokens.empty() ? nullptr : prompt_tokens.data();
+            wparams.prompt_n_tokens = prompt_tokens.size();
+
+            wparams.suppress_nst = chunk_params.suppress_nst;
+            const auto & suppress_sequences = cached_suppress_sequences(ctx, suppress_cache, chunk_params.suppress_tokens);
+            if (!suppress_sequences.empty()) {
+                wparams.logits_filter_callback = suppress_banned_sequences;
+                wparams.logits_filter_callback_user_data = const_cast<std::vector<std::vector<whisper_token>> *>(&suppress_sequences);
+            }
             
             if (whisper_full(ctx, wparams, audio_buffer.data(), audio_buffer.size()) != 0) {
                 res.set_content("{\"error\":\"failed to process audio\"}", "application/json");
@@ -1104,6 +1269,9 @@ int main(int argc, char ** argv) {
 
             // Get transcription
             const int n_segments = whisper_full_n_segments(ctx);
+            // Translated text is flagged with the language it was spoken in
+            const std::string spoken_language = whisper_lang_str(whisper_full_lang_id(ctx));
+            const bool translated = chunk_params.translate && spoken_language != "en";
             
             for (int i = 0; i < n_segments; ++i) {
                 const char* text = whisper_full_get_segment_text(ctx, i);
@@ -1114,6 +1282,26 @@ int main(int argc, char ** argv) {
                 segment["text"] = text;
                 segment["t0"] = t0;
                 segment["t1"] = t1;
+                segment["no_speech_prob"] = whisper_full_get_segment_no_speech_prob(ctx, i);
+                if (translated) {
+                    segment["translated_from"] = spoken_language;
+                }
+
+                // Mean log probability of the text tokens, for hallucination filtering
+                const int n_tokens = whisper_full_n_tokens(ctx, i);
+                float total_logprob = 0.0f;
+                int n_text_tokens = 0;
+                for (int j = 0; j < n_tokens; ++j) {
+                    const whisper_token_data token = whisper_full_get_token_data(ctx, i, j);
+                    if (token.id >= whisper_token_eot(ctx)) {
+                        continue;
+                    }
+                    total_logprob += token.plog;
+                    ++n_text_tokens;
+                }
+                if (n_text_tokens > 0) {
+                    segment["avg_logprob"] = total_logprob / n_text_tokens;
+                }
                 response["segments"].push_back(segment);
             }
 
@@ -1130,9 +1318,67 @@ int main(int argc, char ** argv) {
         res.set_content(response.dump(), "application/json");
     });
 
+    // Language identification only: scores a chunk against the languages in
+    // the comma-separated "candidates" field (all languages when absent),
+    // without decoding it or adding it to the stream buffer
+    svr.Post(sparams.request_path + "/detect", [&](const Request &req, Response &res) {
+        std::lock_guard<std::mutex> lock(whisper_mutex);
+
+        if (!req.has_file("audio")) {
+            res.set_content("{\"error\":\"no audio data\"}", "application/json");
+            return;
+        }
+
+        auto audio_file = req.get_file_value("audio");
+        const float* audio_data = reinterpret_cast<const float*>(audio_file.content.c_str());
+        int n_samples = audio_file.content.size() / sizeof(float);
+
+        if (whisper_pcm_to_mel(ctx, audio_data, n_samples, params.n_threads) != 0) {
+            res.set_content("{\"error\":\"failed to compute spectrogram\"}", "application/json");
+            return;
+        }
+        std::vector<float> probs(whisper_lang_max_id() + 1, 0.0f);
+        const int lang_id = whisper_lang_auto_detect(ctx, 0, params.n_threads, probs.data());
+        if (lang_id < 0) {
+            res.set_content("{\"error\":\"failed to detect language\"}", "application/json");
+            return;
+        }
+
+        json response;
+        response["language"] = whisper_lang_str(lang_id);
+        response["probabilities"] = json::object();
+        if (req.has_file("candidates")) {
+            std::stringstream candidates(req.get_file_value("candidates").content);
+            std::string code;
+            while (std::getline(candidates, code, ',')) {
+                const int id = whisper_lang_id(code.c_str());
+                if (id >= 0) {
+                    response["probabilities"][code] = probs[id];
+                }
+            }
+        } else {
+            for (int id = 0; id <= whisper_lang_max_id(); ++id) {
+                response["probabilities"][whisper_lang_str(id)] = probs[id];
+            }
+        }
+        res.set_content(response.dump(), "application/json");
+    });
+
+    // The device the loaded model runs on
+    svr.Get(sparams.request_path + "/device", [&](const Request &, Response &res) {
+        std::lock_guard<std::mutex> lock(whisper_mutex);
+        json response;
+        response["backend"] = compute_backend(cparams.use_gpu);
+        response["use_gpu"] = cparams.use_gpu;
+        response["model"] = loaded_model;
+        response["system_info"] = whisper_print_system_info();
+        res.set_content(response.dump(), "application/json");
+    });
+
+    // Loads "model", or reloads the current one when only "use_gpu" is given
     svr.Post(sparams.request_path + "/load", [&](const Request &req, Response &res){
         std::lock_guard<std::mutex> lock(whisper_mutex);
-        if (!req.has_file("model"))
+        if (!req.has_file("model") && !req.has_file("use_gpu"))
         {
             fprintf(stderr, "[ERROR] No 'model' field in the request\n");
             fflush(stderr);
@@ -1140,7 +1386,11 @@ int main(int argc, char ** argv) {
             res.set_content(error_resp, "application/json");
             return;
         }
-        std::string model = req.get_file_value("model").content;
+        std::string model = req.has_file("model") ? req.get_file_value("model").content : loaded_model;
+        if (req.has_file("use_gpu"))
+        {
+            cparams.use_gpu = req.get_file_value("use_gpu").content != "false";
+        }
         if (!is_file_exist(model.c_str()))
         {
             fprintf(stderr, "[ERROR] 'model': %s not found!\n", model.c_str());
@@ -1152,6 +1402,8 @@ int main(int argc, char ** argv) {
 
         // clean up
         whisper_free(ctx);
+        prompt_cache.valid = false;
+        suppress_cache.valid = false;
 
         // whisper init
         ctx = whisper_init_from_file_with_params(model.c_str(), cparams);
@@ -1165,6 +1417,9 @@ int main(int argc, char ** argv) {
 
         // initialize openvino encoder. this has no effect on whisper.cpp builds that don't have OpenVINO configured
         whisper_ctx_init_openvino_encoder(ctx, nullptr, params.openvino_encode_device.c_str(), nullptr);
+        loaded_model = model;
+        fprintf(stderr, "[INFO] Loaded %s on %s\n", model.c_str(), compute_backend(cparams.use_gpu).c_str());
+        fflush(stderr);
 
         const std::string success = "Load was successful!";
         res.set_content(success, "application/text");
@@ -1,5 +1,232 @@
+/// Commands the webview can invoke. Declared here so each gets an
+/// `allow-<command>` permission and windows only reach the ones their
+/// capability grants; keep in sync with `app_handler!` in lib.rs.
+const APP_COMMANDS: &[&str] = &[
+    "start_recording",
+    "stop_recording",
+    "is_recording",
+    "read_audio_file",
+    "save_transcript",
+    "is_paused",
+    "pause_recording",
+    "resume_recording",
+    "toggle_mute",
+    "insert_marker",
+    "get_session_markers",
+    "get_hotkey_bindings",
+    "set_hotkey_binding",
+    "get_pipeline_status",
+    "get_notification_settings",
+    "set_notification_settings",
+    "report_summary_ready",
+    "audio_preflight",
+    "check_audio_permission",
+    "request_audio_permission",
+    "open_permission_settings",
+    "get_audio_devices",
+    "get_preview_device",
+    "start_level_metering",
+    "stop_level_metering",
+    "get_preview_vad",
+    "get_noise_settings",
+    "set_noise_settings",
+    "reset_noise_profile",
+    "get_conference_settings",
+    "set_conference_settings",
+    "get_conference_session",
+    "get_time_stretch_settings",
+    "set_time_stretch_settings",
+    "test_device",
+    "get_audio_settings",
+    "set_audio_settings",
+    "replay_session",
+    "get_replay_settings",
+    "set_replay_settings",
+    "start_experiment",
+    "stop_experiment",
+    "get_experiment_status",
+    "get_governor_status",
+    "get_governor_settings",
+    "set_governor_settings",
+    "start_companion_server",
+    "stop_companion_server",
+    "list_remote_devices",
+    "join_federation",
+    "leave_federation",
+    "get_federation_status",
+    "attach_external_track",
+    "subscribe_transcript",
+    "unsubscribe_transcript",
+    "open_caption_window",
+    "close_caption_window",
+    "get_caption_settings",
+    "set_caption_settings",
+    "list_speaker_display",
+    "set_speaker_display",
+    "remove_speaker_display",
+    "start_local_api",
+    "stop_local_api",
+    "get_local_api_info",
+    "get_event_schema",
+    "set_secret",
+    "delete_secret",
+    "list_secret_names",
+    "set_api_key",
+    "has_api_key",
+    "get_export_settings",
+    "set_export_settings",
+    "export_meeting",
+    "get_export_status",
+    "export_meeting_to_folder",
+    "export_masked_audio",
+    "preview_export_name",
+    "export_subtitles",
+    "set_research_export_consent",
+    "export_speaker_embeddings",
+    "get_auto_export_rules",
+    "set_auto_export_rules",
+    "get_auto_export_history",
+    "regenerate_derived",
+    "save_derived",
+    "get_stored_transcript",
+    "list_stored_meetings",
+    "search_meetings",
+    "set_meeting_metadata",
+    "list_meeting_tags",
+    "start_bulk_operation",
+    "cancel_bulk_operation",
+    "list_bulk_operations",
+    "get_storage_settings",
+    "set_storage_settings",
+    "verify_meeting_integrity",
+    "get_integrity_settings",
+    "set_integrity_settings",
+    "get_diarization_quality",
+    "recluster_meeting",
+    "get_speaker_constraints",
+    "set_speaker_constraints",
+    "list_speaker_profiles",
+    "rename_speaker_profile",
+    "merge_speaker_profiles",
+    "delete_speaker_profile",
+    "get_speaker_profile_settings",
+    "set_speaker_profile_settings",
+    "record_voice_enrollment",
+    "cancel_voice_enrollment",
+    "list_enrolled_speakers",
+    "get_merged_transcript",
+    "list_transcript_versions",
+    "diff_transcript",
+    "apply_transcript_diff",
+    "submit_segment_feedback",
+    "get_meeting_feedback",
+    "get_quality_report",
+    "start_session",
+    "stop_session",
+    "get_active_session",
+    "get_bilingual_settings",
+    "set_bilingual_settings",
+    "search_transcripts",
+    "get_session_transcript",
+    "get_whisper_decode_options",
+    "set_whisper_decode_options",
+    "start_call",
+    "set_call_caller",
+    "lookup_caller",
+    "get_active_call",
+    "get_softphone_settings",
+    "set_softphone_settings",
+    "quick_transcribe",
+    "get_hallucination_settings",
+    "set_hallucination_settings",
+    "get_language_id_settings",
+    "set_language_id_settings",
+    "get_watch_folder_settings",
+    "set_watch_folder_settings",
+    "get_preroll_settings",
+    "set_preroll_settings",
+    "list_calendar_events",
+    "refresh_calendar",
+    "arm_calendar_event",
+    "get_scheduled_recording",
+    "get_calendar_settings",
+    "set_calendar_settings",
+    "get_pending_meeting_end",
+    "keep_meeting_going",
+    "get_meeting_end_settings",
+    "set_meeting_end_settings",
+    "get_compute_device",
+    "get_compute_device_settings",
+    "set_compute_device_settings",
+    "get_deepgram_settings",
+    "set_deepgram_settings",
+    "list_transcription_engines",
+    "get_transcription_settings",
+    "set_transcription_settings",
+    "get_openai_settings",
+    "set_openai_settings",
+    "get_assemblyai_settings",
+    "set_assemblyai_settings",
+    "start_calibration",
+    "run_calibration_capture",
+    "analyze_calibration",
+    "apply_calibration",
+    "cancel_calibration",
+    "get_azure_settings",
+    "set_azure_settings",
+    "list_whisper_cpp_models",
+    "list_whisper_cpp_catalog",
+    "list_models",
+    "download_model",
+    "verify_model",
+    "delete_model",
+    "check_local_models",
+    "get_model_settings",
+    "set_model_settings",
+    "import_screenpipe",
+    "get_meeting_timeline",
+    "get_pipeline_metrics",
+    "get_metrics_settings",
+    "set_metrics_settings",
+    "get_interview_kit_settings",
+    "set_interview_kit_settings",
+    "get_chapter_previews",
+    "get_itn_settings",
+    "set_itn_settings",
+    "list_failed_segments",
+    "retry_failed_segments",
+    "get_retry_settings",
+    "set_retry_settings",
+    "get_request_limit_settings",
+    "set_request_limit_settings",
+    "get_connectivity_status",
+    "get_connectivity_settings",
+    "set_connectivity_settings",
+    "get_assurance_review",
+    "get_assurance_settings",
+    "set_assurance_settings",
+    "get_usage_stats",
+    "get_usage_settings",
+    "set_usage_settings",
+    "get_session_names",
+    "get_name_bias_settings",
+    "set_name_bias_settings",
+    "get_meeting_quality",
+    "check_readiness",
+    "get_encryption_settings",
+    "set_encryption_settings",
+    "get_whisper_cpp_settings",
+    "set_whisper_cpp_settings",
+    "start_soak_test",
+    "stop_soak_test",
+];
+
 fn main() {
     #[cfg(target_os = "macos")]
     println!("cargo:rustc-link-lib=framework=AVFoundation");
-    tauri_build::build()
+    tauri_build::try_build(
+        tauri_build::Attributes::new()
+            .app_manifest(tauri_build::AppManifest::new().commands(APP_COMMANDS)),
+    )
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,16 @@
+#![no_main]
+
+use app_lib::audio::audio_processing::resample;
+use app_lib::resample_audio;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (u32, u32, Vec<f32>)| {
+    let (from, to, mut samples) = input;
+    // Keep individual runs fast; long buffers don't exercise anything new
+    samples.truncate(1 << 14);
+    if let Ok(output) = resample(&samples, from, to) {
+        assert!(output.iter().all(|s| s.is_finite()));
+    }
+    let output = resample_audio(&samples, from, to);
+    assert!(output.iter().all(|s| s.is_finite()));
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use app_lib::audio::text_merge::{
+    cleanup_overlap, cleanup_overlap_heuristic, longest_common_word_substring,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (&str, &str)| {
+    let (previous, current) = input;
+    let _ = longest_common_word_substring(previous, current);
+    let _ = cleanup_overlap(previous, current);
+    let _ = cleanup_overlap_heuristic(previous, current);
+});
@@ -0,0 +1,283 @@
+use std::sync::Mutex;
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+use tauri::{command, AppHandle, Runtime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use crate::{add_marker, check_marker_link, get_session_markers, is_recording};
+
+const DEFAULT_API_PORT: u16 = 5180;
+/// Requests larger than this (headers plus body) are rejected.
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalApiInfo {
+    pub port: u16,
+    /// Bearer token external tools must send in the `Authorization` header.
+    pub token: String,
+}
+
+struct LocalApiServer {
+    info: LocalApiInfo,
+    shutdown: oneshot::Sender<()>,
+}
+
+static SERVER: Lazy<Mutex<Option<LocalApiServer>>> = Lazy::new(|| Mutex::new(None));
+
+/// Body of `POST /v1/annotations`.
+#[derive(Debug, Deserialize)]
+struct AnnotationRequest {
+    label: String,
+    /// Name of the tool posting the annotation, e.g. "jira".
+    source: Option<String>,
+    link: Option<String>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn json(status: u16, body: Value) -> Self {
+        Self { status, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, json!({ "error": message.into() }))
+    }
+}
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|w| w == b"\r\n\r\n").map(|pos| pos + 4)
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Request, Response> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(end) = find_header_end(&buffer) {
+            break end;
+        }
+        if buffer.len() > MAX_REQUEST_BYTES {
+            return Err(Response::error(413, "Request headers too large"));
+        }
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| Response::error(400, e.to_string()))?;
+        if read == 0 {
+            return Err(Response::error(400, "Connection closed mid-request"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0usize;
+    let mut authorization = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "Invalid Content-Length"))?
+            }
+            "authorization" => authorization = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    if header_end + content_length > MAX_REQUEST_BYTES {
+        return Err(Response::error(413, "Request body too large"));
+    }
+
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < content_length {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| Response::error(400, e.to_string()))?;
+        if read == 0 {
+            return Err(Response::error(400, "Connection closed mid-body"));
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+
+    Ok(Request {
+        method,
+        path,
+        authorization,
+        body,
+    })
+}
+
+async fn write_response(stream: &mut TcpStream, response: Response) {
+    let body = response.body.to_string();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        body.len()
+    );
+    if let Err(e) = stream.write_all(head.as_bytes()).await {
+        debug!("Failed to write API response: {}", e);
+        return;
+    }
+    let _ = stream.write_all(body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+fn route<R: Runtime>(app: &AppHandle<R>, request: &Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/v1/session") => Response::json(
+            200,
+            json!({ "recording": is_recording(), "markers": get_session_markers() }),
+        ),
+        ("POST", "/v1/annotations") => {
+            let annotation: AnnotationRequest = match serde_json::from_slice(&request.body) {
+                Ok(annotation) => annotation,
+                Err(e) => return Response::error(400, format!("Invalid annotation: {}", e)),
+            };
+            if annotation.label.trim().is_empty() {
+                return Response::error(400, "Annotation label cannot be empty");
+            }
+            if let Err(e) = annotation.link.as_deref().map_or(Ok(()), check_marker_link) {
+                return Response::error(400, e);
+            }
+            if !is_recording() {
+                return Response::error(409, "No recording in progress");
+            }
+            match add_marker(
+                app,
+                Some(annotation.label.trim().to_string()),
+                Some(annotation.source.unwrap_or_else(|| "external".to_string())),
+                annotation.link,
+            ) {
+                Ok(marker) => Response::json(201, json!(marker)),
+                Err(e) => Response::error(409, e),
+            }
+        }
+        _ => Response::error(404, format!("No route for {} {}", request.method, request.path)),
+    }
+}
+
+/// Compare in constant time so the token can't be guessed byte by byte
+/// from response timing.
+fn authorized(authorization: Option<&str>, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    authorization.is_some_and(|given| bool::from(given.as_bytes().ct_eq(expected.as_bytes())))
+}
+
+async fn handle_connection<R: Runtime>(app: AppHandle<R>, mut stream: TcpStream, token: String) {
+    let response = match read_request(&mut stream).await {
+        Ok(request) if !authorized(request.authorization.as_deref(), &token) => {
+            Response::error(401, "Missing or invalid bearer token")
+        }
+        Ok(request) => route(&app, &request),
+        Err(response) => response,
+    };
+    write_response(&mut stream, response).await;
+}
+
+/// Serve the local HTTP API on the loopback interface so external tools
+/// (issue trackers, screen-share helpers, scripts) can annotate the live session.
+#[command]
+pub async fn start_local_api<R: Runtime>(app: AppHandle<R>, port: Option<u16>) -> Result<LocalApiInfo, String> {
+    if let Some(server) = SERVER.lock().map_err(|e| e.to_string())?.as_ref() {
+        return Ok(server.info.clone());
+    }
+
+    let port = port.unwrap_or(DEFAULT_API_PORT);
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+    let info = LocalApiInfo {
+        port,
+        token: generate_token(),
+    };
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+    let token = info.token.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(app.clone(), stream, token.clone()));
+                    }
+                    Err(e) => warn!("Failed to accept API connection: {}", e),
+                },
+                _ = &mut shutdown_rx => break,
+            }
+        }
+        info!("Local API stopped");
+    });
+
+    info!("Local API listening on 127.0.0.1:{}", port);
+    *SERVER.lock().map_err(|e| e.to_string())? = Some(LocalApiServer {
+        info: info.clone(),
+        shutdown: shutdown_tx,
+    });
+    Ok(info)
+}
+
+#[command]
+pub fn stop_local_api() -> Result<(), String> {
+    if let Some(server) = SERVER.lock().map_err(|e| e.to_string())?.take() {
+        let _ = server.shutdown.send(());
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_local_api_info() -> Option<LocalApiInfo> {
+    SERVER
+        .lock()
+        .ok()
+        .and_then(|server| server.as_ref().map(|s| s.info.clone()))
+}
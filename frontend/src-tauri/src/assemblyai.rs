@@ -0,0 +1,359 @@
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::transcription::{encode_wav, AudioInput, Transcript, TranscriptionEngine, TranscriptionResult, LIVE_EVENT};
+use crate::live_corrections::CorrectionTracker;
+use crate::{dual_stream, name_bias, secrets, settings, storage, usage, TranscriptSegment};
+
+pub const API_KEY_SECRET: &str = "assemblyai-api-key";
+const API_BASE: &str = "https://api.assemblyai.com/v2";
+const REALTIME_ENDPOINT: &str = "wss://streaming.assemblyai.com/v3/ws";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Give up on a chunk the service hasn't finished after this long.
+const POLL_TIMEOUT: Duration = Duration::from_secs(120);
+/// The realtime API rejects audio messages shorter than this.
+const MIN_REALTIME_MS: u32 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssemblyAiSettings {
+    /// Stream each capture device to the realtime API for live captions.
+    pub streaming: bool,
+    /// Ask for speaker labels on chunk transcription.
+    pub speaker_labels: bool,
+    /// Hint for how many speakers to expect, when known.
+    pub speakers_expected: Option<u32>,
+    pub language_code: Option<String>,
+}
+
+impl Default for AssemblyAiSettings {
+    fn default() -> Self {
+        Self {
+            streaming: false,
+            speaker_labels: true,
+            speakers_expected: None,
+            language_code: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    upload_url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TranscriptRequest {
+    audio_url: String,
+    speaker_labels: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speakers_expected: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_code: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    word_boost: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptStatus {
+    id: String,
+    status: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    utterances: Option<Vec<Utterance>>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Utterance {
+    /// Milliseconds.
+    start: u64,
+    end: u64,
+    text: String,
+    speaker: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RealtimeMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    transcript: String,
+    #[serde(default)]
+    end_of_turn: bool,
+    /// Numbers turns; every partial and formatted version of a turn shares it.
+    #[serde(default)]
+    turn_order: u64,
+    #[serde(default)]
+    words: Vec<RealtimeWord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RealtimeWord {
+    /// Milliseconds since the stream opened.
+    start: u64,
+    end: u64,
+    #[serde(default)]
+    confidence: f32,
+}
+
+/// AssemblyAI's pre-recorded API with speaker labels, one job per chunk.
+#[derive(Default)]
+pub struct AssemblyAiEngine {
+    client: reqwest::Client,
+}
+
+impl AssemblyAiEngine {
+    pub const ID: &'static str = "assemblyai";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn transcribe_chunk(&self, audio: &AudioInput) -> Result<Transcript, String> {
+        let config = settings::get().assemblyai;
+        let api_key = secrets::require(API_KEY_SECRET)?;
+
+        let upload: UploadResponse = self
+            .client
+            .post(format!("{}/upload", API_BASE))
+            .header("Authorization", &api_key)
+            .body(encode_wav(audio)?)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("AssemblyAI upload failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid AssemblyAI upload response: {}", e))?;
+
+        let request = TranscriptRequest {
+            audio_url: upload.upload_url,
+            speaker_labels: config.speaker_labels,
+            speakers_expected: config.speakers_expected.filter(|_| config.speaker_labels),
+            language_code: audio.language.clone().or(config.language_code),
+            word_boost: name_bias::names(),
+        };
+        let mut job: TranscriptStatus = self
+            .client
+            .post(format!("{}/transcript", API_BASE))
+            .header("Authorization", &api_key)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("AssemblyAI request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid AssemblyAI response: {}", e))?;
+
+        let deadline = tokio::time::Instant::now() + POLL_TIMEOUT;
+        while job.status != "completed" {
+            if job.status == "error" {
+                return Err(format!(
+                    "AssemblyAI transcription failed: {}",
+                    job.error.unwrap_or_default()
+                ));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!("AssemblyAI job {} timed out", job.id));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+            job = self
+                .client
+                .get(format!("{}/transcript/{}", API_BASE, job.id))
+                .header("Authorization", &api_key)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("AssemblyAI poll failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid AssemblyAI response: {}", e))?;
+        }
+
+        let mut segments: Vec<TranscriptSegment> = job
+            .utterances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|u| TranscriptSegment {
+                text: u.text,
+                t0: u.start as f32 / 1000.0,
+                t1: u.end as f32 / 1000.0,
+                speaker: Some(u.speaker),
+                no_speech_prob: None,
+                avg_logprob: None,
+                language: None,
+                translated_from: None,
+            })
+            .collect();
+        let text = job.text.unwrap_or_default();
+        if segments.is_empty() && !text.trim().is_empty() {
+            let duration = audio.samples.len() as f32 / audio.sample_rate.max(1) as f32;
+            segments.push(TranscriptSegment {
+                text,
+                t0: 0.0,
+                t1: duration,
+                speaker: None,
+                no_speech_prob: None,
+                avg_logprob: None,
+                language: None,
+                translated_from: None,
+            });
+        }
+        Ok(Transcript {
+            segments,
+            buffer_size_ms: 0,
+            engine: Self::ID.to_string(),
+        })
+    }
+}
+
+impl TranscriptionEngine for AssemblyAiEngine {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn name(&self) -> &str {
+        "AssemblyAI"
+    }
+
+    fn is_cloud(&self) -> bool {
+        true
+    }
+
+    fn health_url(&self) -> Option<String> {
+        Some(API_BASE.to_string())
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>> {
+        Box::pin(self.transcribe_chunk(audio))
+    }
+}
+
+fn to_pcm16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// Stream `device` to the realtime API until the capture loop drops the
+/// sending side of `audio_rx`. Each turn is emitted as it grows and once
+/// more when it ends.
+pub async fn run_live<R: Runtime>(
+    app: AppHandle<R>,
+    device: String,
+    sample_rate: u32,
+    mut audio_rx: mpsc::UnboundedReceiver<Vec<f32>>,
+) -> Result<(), String> {
+    let api_key = secrets::require(API_KEY_SECRET)?;
+    let url = format!(
+        "{}?sample_rate={}&encoding=pcm_s16le&format_turns=true",
+        REALTIME_ENDPOINT, sample_rate
+    );
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| format!("Invalid AssemblyAI URL: {}", e))?;
+    let auth = HeaderValue::from_str(&api_key).map_err(|e| format!("Invalid API key: {}", e))?;
+    request.headers_mut().insert("Authorization", auth);
+
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))?;
+    info!("AssemblyAI realtime stream open for {} at {} Hz", device, sample_rate);
+    let (mut sink, mut stream) = socket.split();
+
+    let min_samples = (sample_rate * MIN_REALTIME_MS / 1000) as usize;
+    let sender = tokio::spawn(async move {
+        let mut pending: Vec<f32> = Vec::new();
+        while let Some(samples) = audio_rx.recv().await {
+            pending.extend(samples);
+            if pending.len() < min_samples {
+                continue;
+            }
+            if let Err(e) = sink.send(Message::Binary(to_pcm16(&pending))).await {
+                warn!("Failed to send audio to AssemblyAI: {}", e);
+                return;
+            }
+            usage::record_streamed(AssemblyAiEngine::ID, pending.len() as f64 / sample_rate.max(1) as f64);
+            pending.clear();
+        }
+        let _ = sink.send(Message::Text(r#"{"type":"Terminate"}"#.to_string())).await;
+    });
+
+    let mut corrections = CorrectionTracker::default();
+    while let Some(message) = stream.next().await {
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(e) => {
+                sender.abort();
+                return Err(format!("Connection error: {}", e));
+            }
+        };
+        let message: RealtimeMessage = match serde_json::from_str(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Unexpected AssemblyAI message: {}", e);
+                continue;
+            }
+        };
+        match message.kind.as_str() {
+            "Turn" if !message.transcript.trim().is_empty() => {}
+            "Termination" => break,
+            _ => continue,
+        }
+        let start = message.words.first().map(|w| w.start).unwrap_or(0);
+        let end = message.words.last().map(|w| w.end).unwrap_or(start);
+        let confidence = if message.words.is_empty() {
+            0.0
+        } else {
+            message.words.iter().map(|w| w.confidence).sum::<f32>() / message.words.len() as f32
+        };
+        let result = TranscriptionResult {
+            device: device.clone(),
+            text: message.transcript,
+            is_final: message.end_of_turn,
+            start: start as f32 / 1000.0,
+            end: end as f32 / 1000.0,
+            confidence,
+            speaker: None,
+            session_id: storage::current_session(),
+            language: None,
+            translated_from: None,
+            engine: AssemblyAiEngine::ID.to_string(),
+        };
+        if let Err(e) = app.emit(LIVE_EVENT, &result) {
+            error!("Failed to emit live transcription: {}", e);
+        }
+        dual_stream::push(&app, &result);
+        corrections.publish(&app, message.turn_order, &result);
+    }
+
+    sender.abort();
+    info!("AssemblyAI realtime stream closed for {}", device);
+    Ok(())
+}
+
+#[command]
+pub fn get_assemblyai_settings() -> AssemblyAiSettings {
+    settings::get().assemblyai
+}
+
+#[command]
+pub fn set_assemblyai_settings(assemblyai_settings: AssemblyAiSettings) -> Result<AssemblyAiSettings, String> {
+    settings::update(|s| s.assemblyai = assemblyai_settings).map(|s| s.assemblyai)
+}
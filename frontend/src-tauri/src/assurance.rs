@@ -0,0 +1,267 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use log::{debug, error, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::transcription::{self, AudioInput, WhisperEngine};
+use crate::{encryption, settings, storage, TranscriptSegment, WHISPER_ENDPOINT, WHISPER_SAMPLE_RATE};
+
+/// Both hypotheses of every checked segment, one JSON object per line, in
+/// the meeting's storage.
+pub const REVIEW_FILE: &str = "assurance.jsonl";
+/// Emitted with an [`AssuranceSegment`] whenever one is flagged.
+pub const FLAGGED_EVENT: &str = "assurance-flagged";
+
+/// High-assurance mode: every chunk is also transcribed by a second engine,
+/// and segments where the two disagree are flagged for human review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssuranceSettings {
+    pub enabled: bool,
+    /// Engine giving the second opinion; it should differ from the active one.
+    pub secondary_engine: String,
+    /// Segments whose disagreement reaches this share of words are flagged.
+    pub review_threshold: f32,
+}
+
+impl Default for AssuranceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secondary_engine: WhisperEngine::ID.to_string(),
+            review_threshold: 0.25,
+        }
+    }
+}
+
+/// One segment as transcribed by both engines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssuranceSegment {
+    /// Seconds from the start of the recording.
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub primary_engine: String,
+    pub primary_text: String,
+    pub secondary_engine: String,
+    /// What the second engine heard over the same stretch of audio.
+    pub secondary_text: String,
+    /// Word edit distance over the longer hypothesis: 0 when they agree,
+    /// 1 when they share nothing.
+    pub disagreement: f32,
+    pub flagged: bool,
+}
+
+/// Serializes appends to review files from concurrent checks.
+static REVIEW_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+pub fn enabled() -> bool {
+    settings::get().assurance.enabled
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Word-level edit distance between two hypotheses, over the longer one.
+pub fn disagreement(a: &str, b: &str) -> f32 {
+    let (a, b) = (words(a), words(b));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, word) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, other) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(word != other);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()] as f32 / longest as f32
+}
+
+/// Text of the `secondary` segments that mostly fall within `from..to`.
+fn overlapping(secondary: &[TranscriptSegment], from: f32, to: f32) -> String {
+    secondary
+        .iter()
+        .filter(|segment| {
+            let middle = (segment.t0 + segment.t1) / 2.0;
+            middle >= from && middle < to
+        })
+        .map(|segment| segment.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Pair each primary segment with what the second engine heard over the same
+/// time; the stretches between primary segments go to their neighbours, so
+/// words only one engine heard still count against it.
+pub fn compare(
+    offset_secs: f64,
+    primary_engine: &str,
+    primary: &[TranscriptSegment],
+    secondary_engine: &str,
+    secondary: &[TranscriptSegment],
+    threshold: f32,
+) -> Vec<AssuranceSegment> {
+    let primary: Vec<&TranscriptSegment> = primary.iter().filter(|s| !s.text.trim().is_empty()).collect();
+    primary
+        .iter()
+        .enumerate()
+        .map(|(index, segment)| {
+            let from = if index == 0 { f32::MIN } else { segment.t0 };
+            let to = primary.get(index + 1).map_or(f32::MAX, |next| next.t0);
+            let secondary_text = overlapping(secondary, from, to);
+            let score = disagreement(&segment.text, &secondary_text);
+            AssuranceSegment {
+                start_secs: offset_secs + segment.t0 as f64,
+                end_secs: offset_secs + segment.t1 as f64,
+                primary_engine: primary_engine.to_string(),
+                primary_text: segment.text.trim().to_string(),
+                secondary_engine: secondary_engine.to_string(),
+                secondary_text,
+                disagreement: score,
+                flagged: score >= threshold,
+            }
+        })
+        .collect()
+}
+
+fn append<R: Runtime>(app: &AppHandle<R>, meeting_id: &str, segments: &[AssuranceSegment]) -> Result<(), String> {
+    let dir = storage::meeting_dir(app, meeting_id)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create meeting storage: {}", e))?;
+    let _guard = REVIEW_LOCK.lock().map_err(|_| "Review file lock poisoned".to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(REVIEW_FILE))
+        .map_err(|e| format!("Failed to open {}: {}", REVIEW_FILE, e))?;
+    for segment in segments {
+        let line = serde_json::to_string(segment).map_err(|e| format!("Failed to serialize review: {}", e))?;
+        let line = encryption::seal_text(&line)?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", REVIEW_FILE, e))?;
+    }
+    Ok(())
+}
+
+/// Transcribe a chunk the primary engine already transcribed with the second
+/// engine, and record both. Runs in the background so the live pipeline never
+/// waits for the second opinion.
+pub fn check<R: Runtime>(
+    app: AppHandle<R>,
+    offset_secs: f64,
+    samples: Vec<f32>,
+    primary_engine: String,
+    primary: Vec<TranscriptSegment>,
+) {
+    let config = settings::get().assurance;
+    let Some(meeting_id) = storage::current_session() else {
+        return;
+    };
+    if config.secondary_engine == primary_engine {
+        debug!("Primary engine is already {}, no second opinion", primary_engine);
+        return;
+    }
+    let secondary = match transcription::engine(&config.secondary_engine) {
+        Some(engine) => engine,
+        None if config.secondary_engine == WhisperEngine::ID => Arc::new(WhisperEngine::new(WHISPER_ENDPOINT)),
+        None => {
+            warn!("Assurance engine '{}' is not registered", config.secondary_engine);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let input = AudioInput {
+            samples,
+            sample_rate: WHISPER_SAMPLE_RATE,
+            language: None,
+            device: None,
+        };
+        // Budget, connectivity and request limits apply as for the primary engine
+        let second = match transcription::transcribe_with(secondary.clone(), &input).await {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                // Nothing to compare; the segments stay unchecked
+                warn!("{} failed on an assurance check: {}", secondary.name(), e);
+                return;
+            }
+        };
+        if second.engine == primary_engine {
+            debug!("{} was unavailable and fell back to the primary engine, no second opinion", secondary.name());
+            return;
+        }
+        let segments = compare(
+            offset_secs,
+            &primary_engine,
+            &primary,
+            &second.engine,
+            &second.segments,
+            config.review_threshold,
+        );
+        if let Err(e) = append(&app, &meeting_id, &segments) {
+            error!("Failed to store assurance review: {}", e);
+        }
+        for segment in segments.into_iter().filter(|segment| segment.flagged) {
+            if let Err(e) = app.emit(FLAGGED_EVENT, segment) {
+                error!("Failed to emit flagged segment: {}", e);
+            }
+        }
+    });
+}
+
+/// The segments of a meeting checked by two engines, or only those flagged
+/// for review.
+#[command]
+pub fn get_assurance_review<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    flagged_only: Option<bool>,
+) -> Result<Vec<AssuranceSegment>, String> {
+    let path = storage::meeting_dir(&app, &meeting_id)?.join(REVIEW_FILE);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", REVIEW_FILE, e)),
+    };
+    let mut segments: Vec<AssuranceSegment> = content
+        .lines()
+        .filter_map(|line| encryption::open_text(line).ok())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .filter(|segment: &AssuranceSegment| segment.flagged || !flagged_only.unwrap_or(false))
+        .collect();
+    segments.sort_by(|a, b| a.start_secs.total_cmp(&b.start_secs));
+    Ok(segments)
+}
+
+#[command]
+pub fn get_assurance_settings() -> AssuranceSettings {
+    settings::get().assurance
+}
+
+#[command]
+pub fn set_assurance_settings(assurance_settings: AssuranceSettings) -> Result<AssuranceSettings, String> {
+    if !(0.0..=1.0).contains(&assurance_settings.review_threshold) {
+        return Err("Review threshold must be between 0 and 1".to_string());
+    }
+    if assurance_settings.secondary_engine != WhisperEngine::ID
+        && transcription::engine(&assurance_settings.secondary_engine).is_none()
+    {
+        return Err(format!("Unknown transcription engine '{}'", assurance_settings.secondary_engine));
+    }
+    settings::update(|s| s.assurance = assurance_settings).map(|s| s.assurance)
+}
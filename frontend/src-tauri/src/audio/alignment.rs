@@ -0,0 +1,142 @@
+use realfft::num_complex::Complex32;
+use realfft::RealFftPlanner;
+use serde::Serialize;
+
+/// Envelope resolution for the coarse whole-file search.
+const ENVELOPE_FRAME_MS: u32 = 10;
+/// Sample-level refinement searches this far either side of the coarse offset.
+const REFINE_WINDOW_MS: u32 = 20;
+/// Length of the excerpt used for sample-level refinement.
+const REFINE_EXCERPT_SECS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Alignment {
+    /// Where the track starts relative to the reference, in milliseconds.
+    /// Negative when the track started recording first.
+    pub offset_ms: i64,
+    /// Normalized correlation at the chosen offset, 0.0..=1.0.
+    pub confidence: f32,
+}
+
+/// Circular cross-correlation via FFT: `result[k] = sum_i a[i + k] * b[i]`.
+/// Index `n - k` holds negative lag `-k`.
+fn cross_correlate(a: &[f32], b: &[f32]) -> Vec<f32> {
+    let n = (a.len() + b.len()).next_power_of_two();
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(n);
+    let inverse = planner.plan_fft_inverse(n);
+
+    let mut a_padded = a.to_vec();
+    a_padded.resize(n, 0.0);
+    let mut b_padded = b.to_vec();
+    b_padded.resize(n, 0.0);
+
+    let mut a_spectrum = forward.make_output_vec();
+    let mut b_spectrum = forward.make_output_vec();
+    if forward.process(&mut a_padded, &mut a_spectrum).is_err()
+        || forward.process(&mut b_padded, &mut b_spectrum).is_err()
+    {
+        return Vec::new();
+    }
+
+    let mut product: Vec<Complex32> = a_spectrum
+        .iter()
+        .zip(&b_spectrum)
+        .map(|(x, y)| x * y.conj())
+        .collect();
+    // The DC and Nyquist bins of a real signal's spectrum must be purely real
+    if let Some(first) = product.first_mut() {
+        first.im = 0.0;
+    }
+    if let Some(last) = product.last_mut() {
+        last.im = 0.0;
+    }
+
+    let mut output = inverse.make_output_vec();
+    if inverse.process(&mut product, &mut output).is_err() {
+        return Vec::new();
+    }
+    let scale = n as f32;
+    output.iter_mut().for_each(|v| *v /= scale);
+    output
+}
+
+fn lag_of(index: usize, n: usize) -> i64 {
+    if index > n / 2 {
+        index as i64 - n as i64
+    } else {
+        index as i64
+    }
+}
+
+fn norm(values: &[f32]) -> f32 {
+    values.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+/// Zero-mean RMS envelope, one value per `frame_len` samples.
+fn envelope(samples: &[f32], frame_len: usize) -> Vec<f32> {
+    let env: Vec<f32> = samples
+        .chunks(frame_len.max(1))
+        .map(|frame| (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt())
+        .collect();
+    let mean = env.iter().sum::<f32>() / env.len().max(1) as f32;
+    env.into_iter().map(|v| v - mean).collect()
+}
+
+/// Best lag of `b` within `a`, restricted to `range`, with its normalized score.
+fn best_lag(a: &[f32], b: &[f32], range: std::ops::RangeInclusive<i64>) -> Option<(i64, f32)> {
+    let correlation = cross_correlate(a, b);
+    let n = correlation.len();
+    let denominator = norm(a) * norm(b);
+    if n == 0 || denominator == 0.0 {
+        return None;
+    }
+    correlation
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (lag_of(index, n), *value))
+        .filter(|(lag, _)| range.contains(lag))
+        .max_by(|x, y| x.1.total_cmp(&y.1))
+        .map(|(lag, value)| (lag, (value / denominator).clamp(0.0, 1.0)))
+}
+
+/// Find where `track` sits in `reference` (both mono at `sample_rate`):
+/// a coarse search over energy envelopes, refined at sample level.
+pub fn align(reference: &[f32], track: &[f32], sample_rate: u32) -> Option<Alignment> {
+    if reference.is_empty() || track.is_empty() || sample_rate == 0 {
+        return None;
+    }
+
+    let frame_len = (sample_rate * ENVELOPE_FRAME_MS / 1000) as usize;
+    let reference_env = envelope(reference, frame_len);
+    let track_env = envelope(track, frame_len);
+    let max_lag = reference_env.len() as i64;
+    let min_lag = -(track_env.len() as i64);
+    let (coarse_frames, coarse_score) = best_lag(&reference_env, &track_env, min_lag..=max_lag)?;
+    let coarse = coarse_frames * frame_len as i64;
+
+    // Refine on a slice of the track that overlaps the reference at the coarse offset
+    let window = (sample_rate * REFINE_WINDOW_MS / 1000) as i64;
+    let excerpt_len = (sample_rate * REFINE_EXCERPT_SECS) as usize;
+    let track_start = (-coarse).max(0) as usize;
+    let excerpt_end = (track_start + excerpt_len).min(track.len());
+    let excerpt = track.get(track_start..excerpt_end).unwrap_or_default();
+    let reference_start = (coarse + track_start as i64 - window).max(0) as usize;
+    let reference_end = (reference_start + excerpt.len() + 2 * window as usize).min(reference.len());
+
+    let refined = reference
+        .get(reference_start..reference_end)
+        .filter(|slice| !slice.is_empty() && !excerpt.is_empty())
+        .and_then(|slice| best_lag(slice, excerpt, 0..=2 * window))
+        .map(|(lag, score)| (reference_start as i64 + lag - track_start as i64, score));
+
+    let (offset_samples, confidence) = match refined {
+        Some((offset, score)) if score >= coarse_score * 0.5 => (offset, score.max(coarse_score)),
+        _ => (coarse, coarse_score),
+    };
+
+    Some(Alignment {
+        offset_ms: offset_samples * 1000 / sample_rate as i64,
+        confidence,
+    })
+}
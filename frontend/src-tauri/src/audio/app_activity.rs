@@ -0,0 +1,255 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+/// How often the OS is asked which applications are playing audio.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Keep enough history to cover a chunk still waiting on transcription.
+const HISTORY: Duration = Duration::from_secs(600);
+
+/// Applications playing audio at one point in time.
+#[derive(Debug, Clone)]
+struct ActivitySample {
+    at: Instant,
+    apps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppActivity {
+    pub app_name: String,
+    /// Fraction of polls in the window where the app was playing audio.
+    pub share: f32,
+}
+
+static TIMELINE: Lazy<Mutex<VecDeque<ActivitySample>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Applications currently playing audio, or `None` where the OS offers no
+/// per-application view of the output mix.
+#[cfg(target_os = "linux")]
+pub fn active_audio_apps() -> Option<Vec<String>> {
+    // Uncorked sink inputs are streams actively playing to an output
+    let output = std::process::Command::new("pactl")
+        .args(["list", "sink-inputs"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+
+    let mut apps = Vec::new();
+    let mut corked = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("Sink Input #") {
+            corked = false;
+        } else if let Some(value) = line.strip_prefix("Corked:") {
+            corked = value.trim() == "yes";
+        } else if let Some(value) = line.strip_prefix("application.name = ") {
+            let name = value.trim_matches('"').to_string();
+            if !corked && !apps.contains(&name) {
+                apps.push(name);
+            }
+        }
+    }
+    Some(apps)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn active_audio_apps() -> Option<Vec<String>> {
+    // macOS and Windows expose per-app output only through APIs we don't link yet
+    // (ScreenCaptureKit / WASAPI session meters); attribution falls back to "System audio".
+    None
+}
+
+/// Poll the OS for audio-producing apps while `is_running` is set.
+pub fn start_monitor(is_running: Arc<AtomicBool>) {
+    if active_audio_apps().is_none() {
+        info!("Per-application audio activity is not available on this platform");
+        return;
+    }
+    if let Ok(mut timeline) = TIMELINE.lock() {
+        timeline.clear();
+    }
+
+    tokio::spawn(async move {
+        while is_running.load(Ordering::SeqCst) {
+            let apps = tokio::task::spawn_blocking(active_audio_apps)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            if let Ok(mut timeline) = TIMELINE.lock() {
+                let now = Instant::now();
+                while timeline
+                    .front()
+                    .map_or(false, |sample| now.duration_since(sample.at) > HISTORY)
+                {
+                    timeline.pop_front();
+                }
+                timeline.push_back(ActivitySample { at: now, apps });
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        debug!("Audio app monitor stopped");
+    });
+}
+
+/// Apps that were playing audio between `start` and `end`, most active first.
+pub fn activity_between(start: Instant, end: Instant) -> Vec<AppActivity> {
+    let Ok(timeline) = TIMELINE.lock() else {
+        return Vec::new();
+    };
+    // Widen to at least one poll so short segments still see a sample
+    let start = start.checked_sub(POLL_INTERVAL).unwrap_or(start);
+    let samples: Vec<&ActivitySample> = timeline
+        .iter()
+        .filter(|sample| sample.at >= start && sample.at <= end)
+        .collect();
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for sample in &samples {
+        for app in &sample.apps {
+            *counts.entry(app.as_str()).or_default() += 1;
+        }
+    }
+    let mut activity: Vec<AppActivity> = counts
+        .into_iter()
+        .map(|(app, count)| AppActivity {
+            app_name: app.to_string(),
+            share: count as f32 / samples.len() as f32,
+        })
+        .collect();
+    activity.sort_by(|a, b| b.share.total_cmp(&a.share));
+    activity
+}
+
+/// The app most likely responsible for system audio between `start` and `end`.
+pub fn dominant_app(start: Instant, end: Instant) -> Option<String> {
+    activity_between(start, end)
+        .into_iter()
+        .next()
+        .map(|activity| activity.app_name)
+}
+
+/// Where a stretch of the mixed session audio mostly came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AudioSource {
+    Microphone,
+    System,
+    /// A companion phone or federated machine, by device name.
+    Remote(String),
+    /// A speaker identified by re-clustering stored voice embeddings.
+    Speaker(String),
+}
+
+impl AudioSource {
+    pub fn label(&self) -> String {
+        match self {
+            AudioSource::Microphone => "Microphone".to_string(),
+            AudioSource::System => "System audio".to_string(),
+            AudioSource::Remote(device) => device.clone(),
+            AudioSource::Speaker(name) => name.clone(),
+        }
+    }
+}
+
+/// Tracks, per block of mixed samples, which input was loudest, so transcript
+/// segments can be attributed to their source.
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    /// Runs of (sample count, dominant source).
+    runs: Vec<(usize, AudioSource)>,
+    len: usize,
+}
+
+impl SourceMap {
+    pub fn push(&mut self, samples: usize, source: AudioSource) {
+        if samples == 0 {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some((count, dominant)) if *dominant == source => *count += samples,
+            _ => self.runs.push((samples, source)),
+        }
+        self.len += samples;
+    }
+
+    /// Split off the runs covering the first `samples` samples.
+    pub fn take_front(&mut self, samples: usize) -> SourceMap {
+        let mut front = SourceMap::default();
+        let mut remaining = samples.min(self.len);
+        while remaining > 0 {
+            let (count, source) = &mut self.runs[0];
+            let taken = (*count).min(remaining);
+            front.push(taken, source.clone());
+            *count -= taken;
+            if *count == 0 {
+                self.runs.remove(0);
+            }
+            self.len -= taken;
+            remaining -= taken;
+        }
+        front
+    }
+
+    /// Stretch the runs over `len` samples, for when the samples they
+    /// describe were resampled.
+    pub fn rescale(&mut self, len: usize) {
+        let old_len = self.len;
+        if old_len == 0 || old_len == len {
+            return;
+        }
+        let runs = std::mem::take(&mut self.runs);
+        self.len = 0;
+        let (mut position, mut scaled) = (0, 0);
+        for (count, source) in runs {
+            position += count;
+            let end = (position as u128 * len as u128 / old_len as u128) as usize;
+            self.push(end - scaled, source);
+            scaled = end;
+        }
+    }
+
+    /// The source that dominated most of the samples in `[from, to)`.
+    pub fn dominant_source(&self, from: usize, to: usize) -> Option<AudioSource> {
+        self.shares(from, to).into_iter().next().map(|(source, _)| source)
+    }
+
+    /// Fraction of `[from, to)` each source dominated, largest first.
+    pub fn shares(&self, from: usize, to: usize) -> Vec<(AudioSource, f32)> {
+        let to = to.min(self.len);
+        if from >= to {
+            return Vec::new();
+        }
+        let mut totals: HashMap<&AudioSource, usize> = HashMap::new();
+        let mut position = 0;
+        for (count, source) in &self.runs {
+            let run_start = position;
+            let run_end = position + count;
+            position = run_end;
+            let overlap_start = run_start.max(from);
+            let overlap_end = run_end.min(to);
+            if overlap_end > overlap_start {
+                *totals.entry(source).or_default() += overlap_end - overlap_start;
+            }
+            if position >= to {
+                break;
+            }
+        }
+        let span = (to - from) as f32;
+        let mut shares: Vec<(AudioSource, f32)> = totals
+            .into_iter()
+            .map(|(source, count)| (source.clone(), count as f32 / span))
+            .collect();
+        shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+        shares
+    }
+}
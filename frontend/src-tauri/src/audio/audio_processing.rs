@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::Utc;
 use log::debug;
 use realfft::num_complex::{Complex32, ComplexFloat};
@@ -124,7 +124,28 @@ pub fn audio_to_mono(audio: &[f32], channels: u16) -> Vec<f32> {
     mono_samples
 }
 
+/// Largest up- or down-sampling ratio `resample` accepts; anything beyond this
+/// is a misreported device rate rather than real audio.
+pub const MAX_RESAMPLE_RATIO: f64 = 64.0;
+
 pub fn resample(input: &[f32], from_sample_rate: u32, to_sample_rate: u32) -> Result<Vec<f32>> {
+    if from_sample_rate == 0 || to_sample_rate == 0 {
+        return Err(anyhow!(
+            "Invalid sample rates: {} -> {}",
+            from_sample_rate, to_sample_rate
+        ));
+    }
+    let ratio = to_sample_rate as f64 / from_sample_rate as f64;
+    if !(1.0 / MAX_RESAMPLE_RATIO..=MAX_RESAMPLE_RATIO).contains(&ratio) {
+        return Err(anyhow!(
+            "Resample ratio {} -> {} is out of range",
+            from_sample_rate, to_sample_rate
+        ));
+    }
+    if input.is_empty() {
+        return Ok(Vec::new());
+    }
+
     debug!("Resampling audio");
     let params = SincInterpolationParameters {
         sinc_len: 256,
@@ -134,19 +155,106 @@ pub fn resample(input: &[f32], from_sample_rate: u32, to_sample_rate: u32) -> Re
         window: WindowFunction::BlackmanHarris2,
     };
 
-    let mut resampler = SincFixedIn::<f32>::new(
-        to_sample_rate as f64 / from_sample_rate as f64,
-        2.0,
-        params,
-        input.len(),
-        1,
-    )?;
+    let mut resampler = SincFixedIn::<f32>::new(ratio, 2.0, params, input.len(), 1)?;
 
-    let waves_in = vec![input.to_vec()];
+    // A single NaN/Inf would smear across the whole sinc window
+    let waves_in = vec![sanitize_samples(input)];
     debug!("Performing resampling");
     let waves_out = resampler.process(&waves_in, None)?;
     debug!("Resampling complete");
-    Ok(waves_out.into_iter().next().unwrap())
+    waves_out
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Resampler produced no output channel"))
+}
+
+/// Resamples a continuous stream that arrives in pieces of any length, such
+/// as network packets or device buffers. Unlike [`resample`], the filter
+/// state carries over from one call to the next, so piece boundaries don't
+/// click.
+pub struct StreamResampler {
+    from_rate: u32,
+    to_rate: u32,
+    /// `None` when the rates match.
+    resampler: Option<SincFixedIn<f32>>,
+    pending: Vec<f32>,
+}
+
+impl StreamResampler {
+    pub fn new(from_sample_rate: u32, to_sample_rate: u32) -> Result<Self> {
+        if from_sample_rate == 0 || to_sample_rate == 0 {
+            return Err(anyhow!(
+                "Invalid sample rates: {} -> {}",
+                from_sample_rate, to_sample_rate
+            ));
+        }
+        let ratio = to_sample_rate as f64 / from_sample_rate as f64;
+        if !(1.0 / MAX_RESAMPLE_RATIO..=MAX_RESAMPLE_RATIO).contains(&ratio) {
+            return Err(anyhow!(
+                "Resample ratio {} -> {} is out of range",
+                from_sample_rate, to_sample_rate
+            ));
+        }
+        let resampler = if from_sample_rate == to_sample_rate {
+            None
+        } else {
+            let params = SincInterpolationParameters {
+                sinc_len: 256,
+                f_cutoff: 0.95,
+                interpolation: SincInterpolationType::Linear,
+                oversampling_factor: 256,
+                window: WindowFunction::BlackmanHarris2,
+            };
+            // 10 ms blocks keep the added latency low
+            let block = (from_sample_rate as usize / 100).max(1);
+            Some(SincFixedIn::<f32>::new(ratio, 1.0, params, block, 1)?)
+        };
+        Ok(Self {
+            from_rate: from_sample_rate,
+            to_rate: to_sample_rate,
+            resampler,
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn from_rate(&self) -> u32 {
+        self.from_rate
+    }
+
+    pub fn to_rate(&self) -> u32 {
+        self.to_rate
+    }
+
+    /// Resample the next piece of the stream. Input short of a whole block
+    /// is held until the next call.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let resampler = match self.resampler.as_mut() {
+            Some(resampler) => resampler,
+            None => return sanitize_samples(input),
+        };
+        self.pending.extend(input.iter().map(|&s| if s.is_finite() { s } else { 0.0 }));
+        let mut output = Vec::new();
+        loop {
+            let needed = resampler.input_frames_next();
+            if self.pending.len() < needed {
+                break;
+            }
+            match resampler.process(&[&self.pending[..needed]], None) {
+                Ok(waves_out) => output.extend(waves_out.into_iter().next().unwrap_or_default()),
+                Err(e) => debug!("Dropping block the resampler rejected: {}", e),
+            }
+            self.pending.drain(..needed);
+        }
+        output
+    }
+}
+
+/// Replace non-finite samples (from broken drivers or bad decodes) with silence.
+pub fn sanitize_samples(input: &[f32]) -> Vec<f32> {
+    input
+        .iter()
+        .map(|&s| if s.is_finite() { s } else { 0.0 })
+        .collect()
 }
 
 pub fn write_audio_to_file(
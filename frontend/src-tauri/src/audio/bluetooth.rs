@@ -0,0 +1,87 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+use log::{info, warn};
+use serde::Serialize;
+
+use super::core::{AudioDevice, DeviceType};
+
+/// Bluetooth hands-free profile (HFP/HSP) caps capture at 8 or 16 kHz.
+const HFP_MAX_SAMPLE_RATE: u32 = 16000;
+
+const BLUETOOTH_HINTS: [&str; 8] = [
+    "airpods",
+    "bluetooth",
+    "hands-free",
+    "handsfree",
+    "headset",
+    "buds",
+    "jabra",
+    "bose",
+];
+
+const BUILTIN_MIC_HINTS: [&str; 5] = [
+    "macbook",
+    "built-in",
+    "internal",
+    "microphone array",
+    "realtek",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileWarning {
+    pub device: String,
+    pub sample_rate: u32,
+    pub bluetooth: bool,
+    pub message: String,
+}
+
+pub fn is_bluetooth_device(device: &AudioDevice) -> bool {
+    let name = device.name.to_lowercase();
+    BLUETOOTH_HINTS.iter().any(|hint| name.contains(hint))
+}
+
+/// Detect a capture stream that came up at hands-free quality.
+pub fn check_capture_profile(device: &AudioDevice, sample_rate: u32) -> Option<ProfileWarning> {
+    if device.device_type != DeviceType::Input || sample_rate > HFP_MAX_SAMPLE_RATE {
+        return None;
+    }
+
+    let bluetooth = is_bluetooth_device(device);
+    let message = if bluetooth {
+        format!(
+            "{} switched to the Bluetooth hands-free profile ({} Hz). Transcription accuracy will drop; use the built-in microphone for capture and keep the headset for playback.",
+            device.name, sample_rate
+        )
+    } else {
+        format!(
+            "{} is capturing at only {} Hz; transcription accuracy may suffer.",
+            device.name, sample_rate
+        )
+    };
+    warn!("{}", message);
+
+    Some(ProfileWarning {
+        device: device.to_string(),
+        sample_rate,
+        bluetooth,
+        message,
+    })
+}
+
+/// Find a non-Bluetooth built-in microphone to capture from instead of a headset.
+pub fn find_builtin_input() -> Option<AudioDevice> {
+    let host = cpal::default_host();
+    let devices = host.input_devices().ok()?;
+    for device in devices {
+        if let Ok(name) = device.name() {
+            let candidate = AudioDevice::new(name, DeviceType::Input);
+            let lower = candidate.name.to_lowercase();
+            if !is_bluetooth_device(&candidate)
+                && BUILTIN_MIC_HINTS.iter().any(|hint| lower.contains(hint))
+            {
+                info!("Found built-in microphone: {}", candidate);
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+
+/// Gaps shorter than this are callback jitter, not lost audio.
+const GAP_TOLERANCE_SECS: f64 = 0.05;
+/// Never zero-fill more than one second for a single gap.
+const MAX_CONCEALED_SECS: f64 = 1.0;
+/// Measure the delivered rate over windows of this length.
+const RATE_WINDOW_SECS: f64 = 2.0;
+/// Relative deviation from the negotiated rate that triggers renegotiation.
+const RATE_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Discontinuity {
+    /// The device skipped audio; `missing_frames` were zero-filled.
+    Gap { missing_frames: usize },
+    /// The device is delivering at a different rate than it negotiated.
+    RateChanged { expected: u32, measured: u32 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlitchEvent {
+    pub device: String,
+    pub discontinuity: Discontinuity,
+    /// Position in the stream (in frames) where the glitch was detected.
+    pub at_frame: u64,
+}
+
+/// Tracks capture timestamps against sample counts for one input stream.
+pub struct ContinuityTracker {
+    sample_rate: u32,
+    last: Option<(cpal::StreamInstant, usize)>,
+    window_start: Option<cpal::StreamInstant>,
+    window_frames: u64,
+    total_frames: u64,
+}
+
+impl ContinuityTracker {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            last: None,
+            window_start: None,
+            window_frames: 0,
+            total_frames: 0,
+        }
+    }
+
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    /// Observe a buffer of `frames` captured at `capture`.
+    pub fn observe(&mut self, capture: cpal::StreamInstant, frames: usize) -> Option<Discontinuity> {
+        let rate = self.sample_rate as f64;
+        let mut result = None;
+
+        if let Some((previous, previous_frames)) = self.last {
+            // Timestamps going backwards means the stream restarted; just resync
+            if let Some(delta) = capture.duration_since(&previous) {
+                let gap = delta.as_secs_f64() - previous_frames as f64 / rate;
+                if gap > GAP_TOLERANCE_SECS {
+                    let missing = (gap.min(MAX_CONCEALED_SECS) * rate) as usize;
+                    result = Some(Discontinuity::Gap {
+                        missing_frames: missing,
+                    });
+                }
+            }
+        }
+        self.last = Some((capture, frames));
+        self.total_frames += frames as u64;
+
+        // A gap skews the rate estimate, so restart the window after one
+        if result.is_some() {
+            self.window_start = None;
+        }
+
+        match self.window_start {
+            None => {
+                self.window_start = Some(capture);
+                self.window_frames = frames as u64;
+            }
+            Some(start) => {
+                let elapsed = capture
+                    .duration_since(&start)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+                if elapsed >= RATE_WINDOW_SECS {
+                    // Frames delivered before this buffer span `elapsed`
+                    let measured = self.window_frames as f64 / elapsed;
+                    if ((measured - rate) / rate).abs() > RATE_TOLERANCE && result.is_none() {
+                        result = Some(Discontinuity::RateChanged {
+                            expected: self.sample_rate,
+                            measured: measured.round() as u32,
+                        });
+                    }
+                    self.window_start = Some(capture);
+                    self.window_frames = frames as u64;
+                } else {
+                    self.window_frames += frames as u64;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Zero-fill a gap in front of `samples` so downstream timing stays aligned.
+pub fn conceal(discontinuity: &Discontinuity, samples: Vec<f32>) -> Vec<f32> {
+    match discontinuity {
+        Discontinuity::Gap { missing_frames } => {
+            let mut concealed = vec![0.0; *missing_frames];
+            concealed.extend(samples);
+            concealed
+        }
+        Discontinuity::RateChanged { .. } => samples,
+    }
+}
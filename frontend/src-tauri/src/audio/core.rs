@@ -1,4 +1,5 @@
 use super::audio_processing::audio_to_mono; 
+use super::continuity::{conceal, ContinuityTracker, GlitchEvent};
 use anyhow::{anyhow, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::StreamError;
@@ -23,6 +24,8 @@ lazy_static! {
 #[derive(Clone, Debug, PartialEq)]
 pub enum AudioTranscriptionEngine {
     Deepgram,
+    AzureSpeech,
+    WhisperCpp,
     WhisperTiny,
     WhisperDistilLargeV3,
     WhisperLargeV3Turbo,
@@ -33,6 +36,8 @@ impl fmt::Display for AudioTranscriptionEngine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AudioTranscriptionEngine::Deepgram => write!(f, "Deepgram"),
+            AudioTranscriptionEngine::AzureSpeech => write!(f, "AzureSpeech"),
+            AudioTranscriptionEngine::WhisperCpp => write!(f, "WhisperCpp"),
             AudioTranscriptionEngine::WhisperTiny => write!(f, "WhisperTiny"),
             AudioTranscriptionEngine::WhisperDistilLargeV3 => write!(f, "WhisperLarge"),
             AudioTranscriptionEngine::WhisperLargeV3Turbo => write!(f, "WhisperLargeV3Turbo"),
@@ -383,6 +388,7 @@ pub struct AudioStream {
     pub device: Arc<AudioDevice>,
     pub device_config: cpal::SupportedStreamConfig,
     transmitter: Arc<tokio::sync::broadcast::Sender<Vec<f32>>>,
+    glitches: Arc<tokio::sync::broadcast::Sender<GlitchEvent>>,
     stream_control: mpsc::Sender<StreamControl>,
     stream_thread: Option<Arc<tokio::sync::Mutex<Option<thread::JoinHandle<()>>>>>,
     is_disconnected: Arc<AtomicBool>,
@@ -400,6 +406,8 @@ impl AudioStream {
         info!("Initializing audio stream for device: {}", device.to_string());
         let (tx, _) = broadcast::channel::<Vec<f32>>(1000);
         let tx_clone = tx.clone();
+        let (glitch_tx, _) = broadcast::channel::<GlitchEvent>(64);
+        let glitch_tx_clone = glitch_tx.clone();
         
         // Get device and config with improved error handling
         let (cpal_audio_device, config) = match get_device_and_config(&device).await {
@@ -497,16 +505,35 @@ impl AudioStream {
                 }
             };
 
+            // Detect gaps and rate drift before samples reach VAD/STT; gaps are zero-filled
+            let mut tracker = ContinuityTracker::new(config.sample_rate().0);
+            let glitch_device = device_name.clone();
+            let mut deliver = move |mono: Vec<f32>, info: &cpal::InputCallbackInfo| {
+                let mono = match tracker.observe(info.timestamp().capture, mono.len()) {
+                    Some(discontinuity) => {
+                        warn!("audio discontinuity on {}: {:?}", glitch_device, discontinuity);
+                        let concealed = conceal(&discontinuity, mono);
+                        let _ = glitch_tx.send(GlitchEvent {
+                            device: glitch_device.clone(),
+                            discontinuity,
+                            at_frame: tracker.total_frames(),
+                        });
+                        concealed
+                    }
+                    None => mono,
+                };
+                debug!("Received audio chunk: {} samples", mono.len());
+                if let Err(e) = tx.send(mono) {
+                    error!("Failed to send audio data: {}", e);
+                }
+            };
+
             let stream = match config.sample_format() {
                 cpal::SampleFormat::F32 => {
                     match cpal_audio_device.build_input_stream(
                         &config.into(),
-                        move |data: &[f32], _: &_| {
-                            let mono = audio_to_mono(data, channels);
-                            debug!("Received audio chunk: {} samples", mono.len());
-                            if let Err(e) = tx.send(mono) {
-                                error!("Failed to send audio data: {}", e);
-                            }
+                        move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                            deliver(audio_to_mono(data, channels), info);
                         },
                         error_callback.clone(),
                         None,
@@ -521,12 +548,8 @@ impl AudioStream {
                 cpal::SampleFormat::I16 => {
                     match cpal_audio_device.build_input_stream(
                         &config.into(),
-                        move |data: &[i16], _: &_| {
-                            let mono = audio_to_mono(bytemuck::cast_slice(data), channels);
-                            debug!("Received audio chunk: {} samples", mono.len());
-                            if let Err(e) = tx.send(mono) {
-                                error!("Failed to send audio data: {}", e);
-                            }
+                        move |data: &[i16], info: &cpal::InputCallbackInfo| {
+                            deliver(audio_to_mono(bytemuck::cast_slice(data), channels), info);
                         },
                         error_callback.clone(),
                         None,
@@ -541,12 +564,8 @@ impl AudioStream {
                 cpal::SampleFormat::I32 => {
                     match cpal_audio_device.build_input_stream(
                         &config.into(),
-                        move |data: &[i32], _: &_| {
-                            let mono = audio_to_mono(bytemuck::cast_slice(data), channels);
-                            debug!("Received audio chunk: {} samples", mono.len());
-                            if let Err(e) = tx.send(mono) {
-                                error!("Failed to send audio data: {}", e);
-                            }
+                        move |data: &[i32], info: &cpal::InputCallbackInfo| {
+                            deliver(audio_to_mono(bytemuck::cast_slice(data), channels), info);
                         },
                         error_callback.clone(),
                         None,
@@ -561,12 +580,8 @@ impl AudioStream {
                 cpal::SampleFormat::I8 => {
                     match cpal_audio_device.build_input_stream(
                         &config.into(),
-                        move |data: &[i8], _: &_| {
-                            let mono = audio_to_mono(bytemuck::cast_slice(data), channels);
-                            debug!("Received audio chunk: {} samples", mono.len());
-                            if let Err(e) = tx.send(mono) {
-                                error!("Failed to send audio data: {}", e);
-                            }
+                        move |data: &[i8], info: &cpal::InputCallbackInfo| {
+                            deliver(audio_to_mono(bytemuck::cast_slice(data), channels), info);
                         },
                         error_callback.clone(),
                         None,
@@ -614,6 +629,7 @@ impl AudioStream {
             device,
             device_config: config,
             transmitter: Arc::new(tx_clone),
+            glitches: Arc::new(glitch_tx_clone),
             stream_control: stream_control_tx,
             stream_thread: Some(stream_thread),
             is_disconnected,
@@ -624,6 +640,11 @@ impl AudioStream {
         self.transmitter.subscribe()
     }
 
+    /// Discontinuities (gaps, rate changes) detected on this stream.
+    pub fn subscribe_glitches(&self) -> broadcast::Receiver<GlitchEvent> {
+        self.glitches.subscribe()
+    }
+
     pub async fn stop(&self) -> Result<()> {
         // Mark as disconnected first
         self.is_disconnected.store(true, Ordering::Release);
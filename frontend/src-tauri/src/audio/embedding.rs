@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use realfft::{RealFftPlanner, RealToComplex};
+
+const FRAME_MS: u32 = 25;
+const HOP_MS: u32 = 10;
+const BANDS: usize = 24;
+const MIN_HZ: f32 = 80.0;
+const MAX_HZ: f32 = 7600.0;
+/// Segments shorter than this carry too little voice to compare.
+pub const MIN_SEGMENT_MS: u32 = 400;
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// FFT plan, window and mel band edges for one sample rate. Building these
+/// dominates the cost of embedding a short segment, so they're built once.
+struct MelFilterbank {
+    frame: usize,
+    hop: usize,
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    edges: Vec<usize>,
+}
+
+static FILTERBANKS: Lazy<Mutex<HashMap<u32, Arc<MelFilterbank>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl MelFilterbank {
+    fn new(sample_rate: u32) -> Self {
+        let frame = (sample_rate * FRAME_MS / 1000) as usize;
+        let hop = (sample_rate * HOP_MS / 1000) as usize;
+        let n = frame.next_power_of_two();
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(n);
+        let bin_hz = sample_rate as f32 / n as f32;
+        let max_hz = MAX_HZ.min(sample_rate as f32 / 2.0);
+        let (low, high) = (hz_to_mel(MIN_HZ), hz_to_mel(max_hz));
+        let edges = (0..=BANDS + 1)
+            .map(|i| (mel_to_hz(low + (high - low) * i as f32 / (BANDS + 1) as f32) / bin_hz) as usize)
+            .collect();
+        let window = (0..frame)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / frame as f32).cos())
+            .collect();
+        Self {
+            frame,
+            hop,
+            fft,
+            window,
+            edges,
+        }
+    }
+}
+
+fn filterbank(sample_rate: u32) -> Arc<MelFilterbank> {
+    let mut filterbanks = match FILTERBANKS.lock() {
+        Ok(filterbanks) => filterbanks,
+        Err(_) => return Arc::new(MelFilterbank::new(sample_rate)),
+    };
+    filterbanks
+        .entry(sample_rate)
+        .or_insert_with(|| Arc::new(MelFilterbank::new(sample_rate)))
+        .clone()
+}
+
+/// Lightweight voice signature of a stretch of speech: the mean log mel
+/// spectrum of its louder frames, with overall level removed and scaled to
+/// unit length so two signatures compare by dot product.
+pub fn voice_embedding(samples: &[f32], sample_rate: u32) -> Option<Vec<f32>> {
+    if sample_rate == 0 || samples.len() < (sample_rate * MIN_SEGMENT_MS / 1000) as usize {
+        return None;
+    }
+    let bank = filterbank(sample_rate);
+    let (frame, hop, fft, window, edges) = (bank.frame, bank.hop, &bank.fft, &bank.window, &bank.edges);
+    if frame == 0 || hop == 0 {
+        return None;
+    }
+
+    let mut frames: Vec<(f32, Vec<f32>)> = Vec::new();
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    for start in (0..=samples.len() - frame).step_by(hop) {
+        input.iter_mut().for_each(|v| *v = 0.0);
+        for (i, sample) in samples[start..start + frame].iter().enumerate() {
+            input[i] = sample * window[i];
+        }
+        if fft.process(&mut input, &mut spectrum).is_err() {
+            return None;
+        }
+        let power: Vec<f32> = spectrum.iter().map(|c| c.norm_sqr()).collect();
+        let bands: Vec<f32> = (0..BANDS)
+            .map(|b| {
+                let (from, to) = (edges[b], edges[b + 2].min(power.len() - 1).max(edges[b] + 1));
+                let energy: f32 = power[from..to].iter().sum();
+                (energy + 1e-10).ln()
+            })
+            .collect();
+        let energy: f32 = power.iter().sum();
+        frames.push((energy, bands));
+    }
+
+    // Only frames above the median energy, so pauses don't dominate
+    let mut energies: Vec<f32> = frames.iter().map(|(e, _)| *e).collect();
+    energies.sort_by(|a, b| a.total_cmp(b));
+    let median = *energies.get(energies.len() / 2)?;
+    let voiced: Vec<&Vec<f32>> = frames.iter().filter(|(e, _)| *e >= median).map(|(_, b)| b).collect();
+
+    let mut embedding = vec![0.0f32; BANDS];
+    for bands in &voiced {
+        for (sum, value) in embedding.iter_mut().zip(bands.iter()) {
+            *sum += value;
+        }
+    }
+    let level = embedding.iter().sum::<f32>() / BANDS as f32;
+    embedding.iter_mut().for_each(|v| *v -= level);
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return None;
+    }
+    embedding.iter_mut().for_each(|v| *v /= norm);
+    Some(embedding)
+}
+
+/// Cosine similarity of two unit-length embeddings.
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use log::info;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use super::stft::{OverlapAdd, HOP};
+use super::vad::EnergyVad;
+use crate::settings;
+
+/// Reverberation is assumed to start this long after the direct sound.
+const LATE_REVERB_DELAY_MS: u32 = 50;
+/// Never attenuate a bin below this fraction of its amplitude.
+const DEREVERB_FLOOR: f32 = 0.15;
+/// Ceiling for the boosted signal before soft limiting kicks in.
+const LIMIT: f32 = 0.95;
+
+/// Tuning for conference-room mode, where a laptop in the middle of a table
+/// picks up everyone from a distance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConferenceSettings {
+    /// Extra microphone gain, soft-limited to avoid clipping.
+    pub gain_db: f32,
+    /// Suppress late reverberation from the room.
+    pub dereverb: bool,
+    /// Assumed reverberation time of the room.
+    pub reverb_time_ms: u32,
+    /// Keeps speech segments open through the longer pauses of distant talkers.
+    pub vad_hangover_frames: usize,
+    /// Segments below this share are low confidence, instead of the stricter default,
+    /// since every in-room voice arrives through the same microphone.
+    pub low_confidence_share: f32,
+}
+
+impl Default for ConferenceSettings {
+    fn default() -> Self {
+        Self {
+            gain_db: 12.0,
+            dereverb: true,
+            reverb_time_ms: 500,
+            vad_hangover_frames: 30,
+            low_confidence_share: 0.5,
+        }
+    }
+}
+
+/// A recording session running in conference-room mode.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConferenceSession {
+    /// How many people are in the room, as given when the session started.
+    pub participants: Option<usize>,
+    pub settings: ConferenceSettings,
+}
+
+static ACTIVE: Lazy<Mutex<Option<ConferenceSession>>> = Lazy::new(|| Mutex::new(None));
+
+/// Start conference-room mode for the session being started, or clear it.
+pub fn begin(enabled: bool, participants: Option<usize>) {
+    let session = enabled.then(|| ConferenceSession {
+        participants: participants.filter(|n| *n > 0),
+        settings: settings::get().conference,
+    });
+    if let Some(session) = &session {
+        info!("Conference-room mode on ({:?} participants)", session.participants);
+    }
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = session;
+    }
+}
+
+pub fn end() {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = None;
+    }
+}
+
+pub fn active() -> Option<ConferenceSession> {
+    ACTIVE.lock().ok().and_then(|active| active.clone())
+}
+
+/// Lengthen the VAD hangover while conference-room mode is on.
+pub fn tune_vad(mut vad: EnergyVad) -> EnergyVad {
+    if let Some(session) = active() {
+        vad.hangover_frames = vad.hangover_frames.max(session.settings.vad_hangover_frames);
+    }
+    vad
+}
+
+/// Gain and dereverberation for the room microphone.
+pub struct FarFieldProcessor {
+    gain: f32,
+    dereverb: Option<Dereverb>,
+}
+
+impl FarFieldProcessor {
+    /// `None` unless the current session is in conference-room mode.
+    pub fn for_session(sample_rate: u32) -> Option<Self> {
+        let session = active()?;
+        let config = session.settings;
+        Some(Self {
+            gain: 10f32.powf(config.gain_db / 20.0),
+            dereverb: config.dereverb.then(|| Dereverb::new(sample_rate, config.reverb_time_ms)),
+        })
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let boosted: Vec<f32> = samples.iter().map(|s| soft_limit(s * self.gain)).collect();
+        match self.dereverb.as_mut() {
+            Some(dereverb) => dereverb.process(&boosted),
+            None => boosted,
+        }
+    }
+}
+
+fn soft_limit(sample: f32) -> f32 {
+    if sample.abs() <= LIMIT {
+        sample
+    } else {
+        sample.signum() * (LIMIT + (1.0 - LIMIT) * ((sample.abs() - LIMIT) / (1.0 - LIMIT)).tanh())
+    }
+}
+
+/// Spectral suppression of late reverberation: the reverberant power in each
+/// bin is predicted from the power a few frames earlier, decayed by the
+/// room's reverberation time, and subtracted.
+struct Dereverb {
+    stft: OverlapAdd,
+    history: VecDeque<Vec<f32>>,
+    delay_frames: usize,
+    decay: f32,
+}
+
+impl Dereverb {
+    fn new(sample_rate: u32, reverb_time_ms: u32) -> Self {
+        let hop_ms = HOP as f32 * 1000.0 / sample_rate.max(1) as f32;
+        let delay_frames = ((LATE_REVERB_DELAY_MS as f32 / hop_ms).round() as usize).max(1);
+        // Power falls by 60 dB over the reverberation time
+        let delay_secs = delay_frames as f32 * hop_ms / 1000.0;
+        let rt60 = reverb_time_ms.max(1) as f32 / 1000.0;
+        let decay = 10f32.powf(-6.0 * delay_secs / rt60);
+        Self {
+            stft: OverlapAdd::default(),
+            history: VecDeque::with_capacity(delay_frames + 1),
+            delay_frames,
+            decay,
+        }
+    }
+
+    fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let (history, delay_frames, decay) = (&mut self.history, self.delay_frames, self.decay);
+        self.stft.process(samples, |spectrum| {
+            let power: Vec<f32> = spectrum.iter().map(|bin| bin.norm_sqr()).collect();
+            if history.len() >= delay_frames {
+                if let Some(earlier) = history.front() {
+                    for ((bin, current), previous) in spectrum.iter_mut().zip(&power).zip(earlier) {
+                        let reverb = decay * previous;
+                        let gain = if *current > 0.0 {
+                            (1.0 - reverb / current).max(0.0).sqrt().max(DEREVERB_FLOOR)
+                        } else {
+                            DEREVERB_FLOOR
+                        };
+                        *bin *= gain;
+                    }
+                }
+            }
+            history.push_back(power);
+            while history.len() > delay_frames {
+                history.pop_front();
+            }
+        })
+    }
+}
+
+#[command]
+pub fn get_conference_settings() -> ConferenceSettings {
+    settings::get().conference
+}
+
+#[command]
+pub fn set_conference_settings(conference_settings: ConferenceSettings) -> Result<ConferenceSettings, String> {
+    settings::update(|s| s.conference = conference_settings).map(|s| s.conference)
+}
+
+#[command]
+pub fn get_conference_session() -> Option<ConferenceSession> {
+    active()
+}
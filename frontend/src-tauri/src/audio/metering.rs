@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::{broadcast, Mutex};
+
+use super::core::{parse_audio_device, AudioStream};
+
+/// Shared with the webview's WASM mic preview.
+pub use meetingly_vad::{compute_levels, to_db};
+
+/// Level events are emitted at ~10 Hz per device.
+const METER_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioLevel {
+    pub device: String,
+    pub rms: f32,
+    pub peak: f32,
+    pub rms_db: f32,
+    pub peak_db: f32,
+}
+
+impl AudioLevel {
+    pub fn from_samples(device: &str, samples: &[f32]) -> Self {
+        let (rms, peak) = compute_levels(samples);
+        AudioLevel {
+            device: device.to_string(),
+            rms,
+            peak,
+            rms_db: to_db(rms),
+            peak_db: to_db(peak),
+        }
+    }
+}
+
+struct Meter {
+    is_running: Arc<AtomicBool>,
+    streams: Vec<Arc<AudioStream>>,
+}
+
+static METER: Lazy<Mutex<Option<Meter>>> = Lazy::new(|| Mutex::new(None));
+
+/// Open a dedicated stream per device and emit `audio-level` events, independent
+/// of the recording/transcription pipeline. Replaces any running meter.
+#[command]
+pub async fn start_level_metering<R: Runtime>(
+    app: AppHandle<R>,
+    devices: Vec<String>,
+) -> Result<(), String> {
+    stop_level_metering().await?;
+
+    let is_running = Arc::new(AtomicBool::new(true));
+    let mut streams = Vec::new();
+
+    for name in devices {
+        let opened = match parse_audio_device(&name) {
+            Ok(device) => {
+                let device = Arc::new(device);
+                AudioStream::from_device(device.clone(), is_running.clone())
+                    .await
+                    .map(|stream| (device, stream))
+                    .map_err(|e| format!("Failed to open {} for metering: {}", device, e))
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        let (device, stream) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                // The devices opened so far aren't in `METER` yet, so nothing else can stop them
+                release(&is_running, streams).await;
+                return Err(e);
+            }
+        };
+        let stream = Arc::new(stream);
+        let mut receiver = stream.subscribe().await;
+        streams.push(stream);
+
+        let app = app.clone();
+        let is_running = is_running.clone();
+        let device_name = device.to_string();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(METER_INTERVAL);
+            let mut window: Vec<f32> = Vec::new();
+            while is_running.load(Ordering::SeqCst) {
+                tokio::select! {
+                    chunk = receiver.recv() => match chunk {
+                        Ok(samples) => window.extend(samples),
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = ticker.tick() => {
+                        if window.is_empty() {
+                            continue;
+                        }
+                        let level = AudioLevel::from_samples(&device_name, &window);
+                        window.clear();
+                        if let Err(e) = app.emit("audio-level", level) {
+                            error!("Failed to emit audio level: {}", e);
+                        }
+                    }
+                }
+            }
+            info!("Level metering stopped for {}", device_name);
+        });
+    }
+
+    *METER.lock().await = Some(Meter {
+        is_running,
+        streams,
+    });
+    Ok(())
+}
+
+#[command]
+pub async fn stop_level_metering() -> Result<(), String> {
+    if let Some(meter) = METER.lock().await.take() {
+        release(&meter.is_running, meter.streams).await;
+    }
+    Ok(())
+}
+
+async fn release(is_running: &AtomicBool, streams: Vec<Arc<AudioStream>>) {
+    is_running.store(false, Ordering::SeqCst);
+    for stream in streams {
+        if let Err(e) = stream.stop().await {
+            error!("Failed to stop metering stream {}: {}", stream.device, e);
+        }
+    }
+}
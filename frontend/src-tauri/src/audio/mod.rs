@@ -3,6 +3,23 @@ pub mod core;
 pub mod audio_processing;
 pub mod encode;
 pub mod ffmpeg;
+pub mod permissions;
+pub mod metering;
+pub mod vad;
+pub mod bluetooth;
+pub mod continuity;
+pub mod text_merge;
+pub mod app_activity;
+pub mod remote;
+pub mod decode;
+pub mod alignment;
+pub mod embedding;
+pub mod noise;
+pub mod stft;
+pub mod farfield;
+pub mod playback;
+pub mod voice_mask;
+pub mod time_stretch;
 
 pub use core::{
     default_input_device, default_output_device, get_device_and_config, list_audio_devices,
@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use super::metering::{compute_levels, to_db};
+use super::stft::{window, OverlapAdd, FFT_SIZE};
+use super::farfield;
+use super::vad::EnergyVad;
+use crate::settings;
+
+/// Weight of each new quiet frame in the running noise estimate.
+const LEARN_RATE: f32 = 0.02;
+/// A profile needs this many quiet frames before it is trusted.
+const MIN_PROFILE_FRAMES: u64 = 50;
+/// Never attenuate a bin below this fraction of its amplitude, which keeps
+/// subtraction from producing "musical" noise.
+const SPECTRAL_FLOOR: f32 = 0.1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseSettings {
+    /// Learn each device's background noise from the quiet stretches of a session.
+    pub learn: bool,
+    /// Subtract the learned noise spectrum from a device before mixing.
+    pub spectral_subtraction: bool,
+    /// Speech must be this far above the learned noise floor.
+    pub vad_margin_db: f32,
+    /// Learned profiles by device name.
+    pub profiles: BTreeMap<String, NoiseProfile>,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            learn: true,
+            spectral_subtraction: false,
+            vad_margin_db: 10.0,
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    pub sample_rate: u32,
+    /// RMS level of the device while nobody is speaking.
+    pub floor_db: f32,
+    /// Mean power per FFT bin while nobody is speaking.
+    pub spectrum: Vec<f32>,
+    /// Quiet frames the profile was learned from, across sessions.
+    pub frames: u64,
+    pub updated_at: String,
+}
+
+impl NoiseProfile {
+    fn is_trained(&self) -> bool {
+        self.frames >= MIN_PROFILE_FRAMES
+    }
+}
+
+/// A profile being refined during the current session, plus capture blocks
+/// too short to make up a full frame yet.
+struct Learner {
+    profile: NoiseProfile,
+    pending: Vec<f32>,
+}
+
+/// Written back to settings by `save_learned`.
+static LEARNING: Lazy<Mutex<HashMap<String, Learner>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn profile(device: &str) -> Option<NoiseProfile> {
+    if let Some(profile) = LEARNING
+        .lock()
+        .ok()
+        .and_then(|learning| learning.get(device).map(|l| l.profile.clone()))
+    {
+        return Some(profile);
+    }
+    settings::get().noise.profiles.get(device).cloned()
+}
+
+/// VAD tuned to `device`: the threshold sits `vad_margin_db` above its
+/// learned noise floor, and never below the default. Conference-room mode
+/// lengthens the hangover.
+pub fn vad_for(device: &str) -> EnergyVad {
+    let mut vad = EnergyVad::default();
+    if let Some(profile) = profile(device).filter(NoiseProfile::is_trained) {
+        let margin = settings::get().noise.vad_margin_db;
+        vad.threshold_db = vad.threshold_db.max(profile.floor_db + margin);
+    }
+    farfield::tune_vad(vad)
+}
+
+fn power_spectrum(frame: &[f32], window: &[f32], planner: &mut RealFftPlanner<f32>) -> Option<Vec<f32>> {
+    let fft = planner.plan_fft_forward(FFT_SIZE);
+    let mut input: Vec<f32> = frame.iter().zip(window).map(|(s, w)| s * w).collect();
+    let mut spectrum = fft.make_output_vec();
+    fft.process(&mut input, &mut spectrum).ok()?;
+    Some(spectrum.iter().map(|c| c.norm_sqr()).collect())
+}
+
+/// Fold the quiet frames of a block of `device` audio into its noise profile.
+pub fn observe(device: &str, samples: &[f32], sample_rate: u32) {
+    let config = settings::get().noise;
+    if !config.learn || samples.is_empty() {
+        return;
+    }
+    let vad = vad_for(device);
+    let window = window();
+    let mut planner = RealFftPlanner::<f32>::new();
+    let Ok(mut learning) = LEARNING.lock() else {
+        return;
+    };
+    let learner = learning.entry(device.to_string()).or_insert_with(|| Learner {
+        profile: config
+            .profiles
+            .get(device)
+            .filter(|p| p.sample_rate == sample_rate)
+            .cloned()
+            .unwrap_or(NoiseProfile {
+                sample_rate,
+                floor_db: vad.threshold_db - config.vad_margin_db,
+                spectrum: vec![0.0; FFT_SIZE / 2 + 1],
+                frames: 0,
+                updated_at: String::new(),
+            }),
+        pending: Vec::new(),
+    });
+    let entry = &mut learner.profile;
+    if entry.sample_rate != sample_rate {
+        // The device was renegotiated; start over at the new rate
+        *entry = NoiseProfile {
+            sample_rate,
+            floor_db: entry.floor_db,
+            spectrum: vec![0.0; FFT_SIZE / 2 + 1],
+            frames: 0,
+            updated_at: String::new(),
+        };
+    }
+
+    learner.pending.extend_from_slice(samples);
+    let whole = learner.pending.len() / FFT_SIZE * FFT_SIZE;
+    for frame in learner.pending[..whole].chunks_exact(FFT_SIZE) {
+        let (rms, _) = compute_levels(frame);
+        let level = to_db(rms);
+        if level >= vad.threshold_db {
+            continue;
+        }
+        let Some(power) = power_spectrum(frame, &window, &mut planner) else {
+            continue;
+        };
+        // Average plainly until the profile is trained, then track slowly
+        let rate = if entry.frames < MIN_PROFILE_FRAMES {
+            1.0 / (entry.frames + 1) as f32
+        } else {
+            LEARN_RATE
+        };
+        for (estimate, value) in entry.spectrum.iter_mut().zip(&power) {
+            *estimate += rate * (value - *estimate);
+        }
+        entry.floor_db += rate * (level - entry.floor_db);
+        entry.frames += 1;
+    }
+    learner.pending.drain(..whole);
+}
+
+/// Persist what this session learned. Called when recording stops.
+pub fn save_learned() {
+    let learned: Vec<(String, NoiseProfile)> = match LEARNING.lock() {
+        Ok(mut learning) => learning
+            .drain()
+            .map(|(device, learner)| (device, learner.profile))
+            .filter(|(_, p)| p.frames > 0)
+            .collect(),
+        Err(_) => return,
+    };
+    if learned.is_empty() {
+        return;
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let count = learned.len();
+    let result = settings::update(|s| {
+        for (device, mut profile) in learned {
+            profile.updated_at = now.clone();
+            s.noise.profiles.insert(device, profile);
+        }
+    });
+    match result {
+        Ok(_) => info!("Saved noise profiles for {} device(s)", count),
+        Err(e) => warn!("Failed to save noise profiles: {}", e),
+    }
+}
+
+/// Streaming spectral subtraction for one device. Output lags input by half
+/// an FFT frame; blocks may come in any size.
+pub struct Denoiser {
+    device: String,
+    stft: OverlapAdd,
+}
+
+impl Denoiser {
+    /// `None` unless subtraction is enabled.
+    pub fn for_device(device: &str) -> Option<Self> {
+        if !settings::get().noise.spectral_subtraction {
+            return None;
+        }
+        Some(Self {
+            device: device.to_string(),
+            stft: OverlapAdd::default(),
+        })
+    }
+
+    pub fn process(&mut self, samples: &[f32], sample_rate: u32) -> Vec<f32> {
+        let Some(noise) = profile(&self.device).filter(|p| p.is_trained() && p.sample_rate == sample_rate) else {
+            return samples.to_vec();
+        };
+        self.stft.process(samples, |spectrum| {
+            for (bin, noise_power) in spectrum.iter_mut().zip(&noise.spectrum) {
+                let power = bin.norm_sqr();
+                let gain = if power > 0.0 {
+                    (1.0 - noise_power / power).max(0.0).sqrt().max(SPECTRAL_FLOOR)
+                } else {
+                    SPECTRAL_FLOOR
+                };
+                *bin *= gain;
+            }
+        })
+    }
+}
+
+#[command]
+pub fn get_noise_settings() -> NoiseSettings {
+    settings::get().noise
+}
+
+#[command]
+pub fn set_noise_settings(noise_settings: NoiseSettings) -> Result<NoiseSettings, String> {
+    settings::update(|s| s.noise = noise_settings).map(|s| s.noise)
+}
+
+/// Forget a device's learned noise, e.g. after moving it to a different room.
+#[command]
+pub fn reset_noise_profile(device: String) -> Result<NoiseSettings, String> {
+    if let Ok(mut learning) = LEARNING.lock() {
+        learning.remove(&device);
+    }
+    settings::update(|s| {
+        s.noise.profiles.remove(&device);
+    })
+    .map(|s| s.noise)
+}
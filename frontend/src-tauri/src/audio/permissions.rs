@@ -0,0 +1,315 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+use tauri_plugin_opener::OpenerExt;
+
+use super::core::{
+    default_input_device, default_output_device, get_device_and_config, trigger_audio_permission,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    Microphone,
+    /// System audio capture (ScreenCaptureKit on macOS).
+    ScreenAudio,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    NotDetermined,
+    /// Blocked by policy (MDM, parental controls); the user can't change it.
+    Restricted,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionStatus {
+    pub kind: PermissionKind,
+    pub state: PermissionState,
+    pub reason: Option<String>,
+    /// Deep link to the OS settings pane that controls this permission.
+    pub settings_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceAccessCheck {
+    pub device: Option<String>,
+    pub ok: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioPreflightReport {
+    pub permissions: Vec<PermissionStatus>,
+    pub input: DeviceAccessCheck,
+    pub output: DeviceAccessCheck,
+    pub ready: bool,
+}
+
+fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+    #[cfg(target_os = "macos")]
+    {
+        Some(match kind {
+            PermissionKind::Microphone => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone"
+            }
+            PermissionKind::ScreenAudio => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"
+            }
+        })
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        match kind {
+            PermissionKind::Microphone => Some("ms-settings:privacy-microphone"),
+            PermissionKind::ScreenAudio => None,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = kind;
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{PermissionKind, PermissionState};
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    fn microphone_state() -> PermissionState {
+        // AVAuthorizationStatus: 0 notDetermined, 1 restricted, 2 denied, 3 authorized
+        let status: isize = unsafe {
+            let media_type: *mut Object =
+                msg_send![class!(NSString), stringWithUTF8String: b"soun\0".as_ptr()];
+            msg_send![class!(AVCaptureDevice), authorizationStatusForMediaType: media_type]
+        };
+        match status {
+            0 => PermissionState::NotDetermined,
+            1 => PermissionState::Restricted,
+            2 => PermissionState::Denied,
+            3 => PermissionState::Granted,
+            _ => PermissionState::Unknown,
+        }
+    }
+
+    pub fn check(kind: PermissionKind) -> (PermissionState, Option<String>) {
+        match kind {
+            PermissionKind::Microphone => {
+                let state = microphone_state();
+                let reason = match state {
+                    PermissionState::Denied => Some(
+                        "Microphone access is turned off for this app in System Settings > Privacy & Security > Microphone"
+                            .to_string(),
+                    ),
+                    PermissionState::Restricted => Some(
+                        "Microphone access is restricted by a device management profile".to_string(),
+                    ),
+                    PermissionState::NotDetermined => {
+                        Some("Microphone access has not been requested yet".to_string())
+                    }
+                    _ => None,
+                };
+                (state, reason)
+            }
+            PermissionKind::ScreenAudio => {
+                if unsafe { CGPreflightScreenCaptureAccess() } {
+                    (PermissionState::Granted, None)
+                } else {
+                    (
+                        PermissionState::Denied,
+                        Some(
+                            "Screen & System Audio Recording is not allowed for this app; system audio will be silent"
+                                .to_string(),
+                        ),
+                    )
+                }
+            }
+        }
+    }
+
+    pub fn request_screen_audio() -> bool {
+        unsafe { CGRequestScreenCaptureAccess() }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{PermissionKind, PermissionState};
+    use std::process::Command;
+
+    const CONSENT_KEY: &str =
+        r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+
+    fn consent_value(hive: &str) -> Option<String> {
+        let output = Command::new("reg")
+            .args(["query", &format!(r"{}\{}", hive, CONSENT_KEY), "/v", "Value"])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with("Value"))
+            .and_then(|line| line.split_whitespace().last())
+            .map(|value| value.to_string())
+    }
+
+    pub fn check(kind: PermissionKind) -> (PermissionState, Option<String>) {
+        match kind {
+            PermissionKind::Microphone => {
+                if consent_value("HKLM").as_deref() == Some("Deny") {
+                    return (
+                        PermissionState::Restricted,
+                        Some("Microphone access is disabled for this device in Windows privacy settings".to_string()),
+                    );
+                }
+                match consent_value("HKCU").as_deref() {
+                    Some("Allow") => (PermissionState::Granted, None),
+                    Some("Deny") => (
+                        PermissionState::Denied,
+                        Some("\"Let apps access your microphone\" is turned off in Windows privacy settings".to_string()),
+                    ),
+                    _ => (PermissionState::Unknown, None),
+                }
+            }
+            // WASAPI loopback capture doesn't require a permission
+            PermissionKind::ScreenAudio => (PermissionState::Granted, None),
+        }
+    }
+
+    pub fn request_screen_audio() -> bool {
+        true
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::{PermissionKind, PermissionState};
+
+    // No OS-level audio permission on Linux; device access is checked separately
+    pub fn check(_kind: PermissionKind) -> (PermissionState, Option<String>) {
+        (PermissionState::Granted, None)
+    }
+
+    pub fn request_screen_audio() -> bool {
+        true
+    }
+}
+
+fn permission_status(kind: PermissionKind) -> PermissionStatus {
+    let (state, reason) = platform::check(kind);
+    PermissionStatus {
+        kind,
+        state,
+        reason,
+        settings_url: settings_url(kind).map(|url| url.to_string()),
+    }
+}
+
+async fn check_device(input: bool) -> DeviceAccessCheck {
+    let device = if input {
+        default_input_device()
+    } else {
+        default_output_device()
+    };
+    let device = match device {
+        Ok(device) => device,
+        Err(e) => {
+            return DeviceAccessCheck {
+                device: None,
+                ok: false,
+                reason: Some(e.to_string()),
+            }
+        }
+    };
+
+    match get_device_and_config(&device).await {
+        Ok(_) => DeviceAccessCheck {
+            device: Some(device.to_string()),
+            ok: true,
+            reason: None,
+        },
+        Err(e) => DeviceAccessCheck {
+            device: Some(device.to_string()),
+            ok: false,
+            reason: Some(e.to_string()),
+        },
+    }
+}
+
+/// Check OS permissions and that the default devices can actually be opened,
+/// so an empty transcript can be explained before recording starts.
+#[command]
+pub async fn audio_preflight() -> Result<AudioPreflightReport, String> {
+    let permissions = vec![
+        permission_status(PermissionKind::Microphone),
+        permission_status(PermissionKind::ScreenAudio),
+    ];
+    let input = check_device(true).await;
+    let output = check_device(false).await;
+
+    let ready = input.ok
+        && output.ok
+        && permissions
+            .iter()
+            .all(|p| matches!(p.state, PermissionState::Granted | PermissionState::Unknown));
+
+    if !ready {
+        warn!(
+            "Audio preflight failed: permissions={:?}, input={:?}, output={:?}",
+            permissions, input, output
+        );
+    }
+
+    Ok(AudioPreflightReport {
+        permissions,
+        input,
+        output,
+        ready,
+    })
+}
+
+#[command]
+pub fn check_audio_permission(kind: PermissionKind) -> PermissionStatus {
+    permission_status(kind)
+}
+
+/// Trigger the OS permission prompt and return the resulting status.
+#[command]
+pub fn request_audio_permission(kind: PermissionKind) -> Result<PermissionStatus, String> {
+    info!("Requesting {:?} permission", kind);
+    match kind {
+        PermissionKind::Microphone => {
+            trigger_audio_permission().map_err(|e| e.to_string())?;
+        }
+        PermissionKind::ScreenAudio => {
+            platform::request_screen_audio();
+        }
+    }
+    Ok(permission_status(kind))
+}
+
+#[command]
+pub fn open_permission_settings<R: Runtime>(
+    app: AppHandle<R>,
+    kind: PermissionKind,
+) -> Result<(), String> {
+    let url = settings_url(kind)
+        .ok_or_else(|| format!("No settings pane controls {:?} on this platform", kind))?;
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| format!("Failed to open settings: {}", e))
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use log::{error, info};
+
+/// Extra time the stream stays open so the device drains its buffer.
+const DRAIN_MS: u64 = 300;
+
+/// Play mono `samples` on the default output device, blocking until done.
+/// Streams aren't `Send` on every platform, so call this from a blocking task.
+pub fn play_blocking(samples: &[f32], sample_rate: u32) -> Result<()> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or_else(|| anyhow!("No default output device found"))?;
+    let config = device.default_output_config()?;
+    let channels = config.channels() as usize;
+    let output_rate = config.sample_rate().0;
+    let samples = Arc::new(crate::resample_audio(samples, sample_rate, output_rate));
+    let position = Arc::new(AtomicUsize::new(0));
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => {
+            let (samples, position) = (samples.clone(), position.clone());
+            device.build_output_stream(
+                &config.into(),
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    for frame in data.chunks_mut(channels) {
+                        let index = position.fetch_add(1, Ordering::Relaxed);
+                        frame.fill(samples.get(index).copied().unwrap_or(0.0));
+                    }
+                },
+                |err| error!("Error in playback stream: {}", err),
+                None,
+            )?
+        }
+        cpal::SampleFormat::I16 => {
+            let (samples, position) = (samples.clone(), position.clone());
+            device.build_output_stream(
+                &config.into(),
+                move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
+                    for frame in data.chunks_mut(channels) {
+                        let index = position.fetch_add(1, Ordering::Relaxed);
+                        let sample = samples.get(index).copied().unwrap_or(0.0);
+                        frame.fill((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+                    }
+                },
+                |err| error!("Error in playback stream: {}", err),
+                None,
+            )?
+        }
+        format => return Err(anyhow!("Unsupported output sample format: {:?}", format)),
+    };
+
+    info!("Playing {} samples on {}", samples.len(), device.name().unwrap_or_default());
+    stream.play()?;
+    let duration_ms = samples.len() as u64 * 1000 / output_rate.max(1) as u64;
+    std::thread::sleep(Duration::from_millis(duration_ms + DRAIN_MS));
+    Ok(())
+}
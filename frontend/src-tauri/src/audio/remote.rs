@@ -0,0 +1,289 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use audiopus::coder::Decoder;
+use audiopus::{Channels, SampleRate};
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::core::{AudioDevice, DeviceType};
+use super::metering::compute_levels;
+use super::audio_processing::StreamResampler;
+
+/// Companion apps send 48 kHz mono Opus; decoded audio is published at this rate.
+pub const REMOTE_SAMPLE_RATE: u32 = 48000;
+const DEFAULT_COMPANION_PORT: u16 = 8765;
+/// Largest Opus frame (120 ms at 48 kHz).
+const MAX_FRAME_SAMPLES: usize = 5760;
+/// Suffix that marks remote devices in device lists.
+const REMOTE_SUFFIX: &str = "(remote)";
+/// Remote audio buffered ahead of the microphone beyond this is dropped, so
+/// a device that bursts after a stall doesn't stay behind for the meeting.
+const MAX_REMOTE_LAG_MS: u64 = 500;
+
+/// First message a companion must send after connecting.
+#[derive(Debug, Deserialize)]
+struct Hello {
+    token: String,
+    device_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompanionServerInfo {
+    pub port: u16,
+    /// Pairing token the phone must present; shown to the user (e.g. as a QR code).
+    pub token: String,
+}
+
+/// Decoded audio from a remote device.
+#[derive(Debug, Clone)]
+pub struct RemoteAudio {
+    pub device: AudioDevice,
+    pub samples: Vec<f32>,
+}
+
+/// Mixes remote devices into the microphone side of the capture. Each
+/// device's packets are resampled as one continuous stream and queued in its
+/// own buffer, then played out as the microphone delivers samples, so packets
+/// that arrive together are laid end to end instead of on top of each other.
+#[derive(Default)]
+pub struct RemoteMixer {
+    streams: HashMap<String, RemoteStream>,
+}
+
+struct RemoteStream {
+    resampler: StreamResampler,
+    buffer: VecDeque<f32>,
+}
+
+impl RemoteMixer {
+    /// Queue a packet behind the earlier ones from the same device, at the
+    /// capture's `sample_rate`.
+    pub fn push(&mut self, audio: RemoteAudio, sample_rate: u32) {
+        // Buffered audio is at the old rate once the capture rate changes
+        let stale = self
+            .streams
+            .get(&audio.device.name)
+            .map_or(true, |stream| stream.resampler.to_rate() != sample_rate);
+        if stale {
+            match StreamResampler::new(REMOTE_SAMPLE_RATE, sample_rate) {
+                Ok(resampler) => {
+                    let stream = RemoteStream {
+                        resampler,
+                        buffer: VecDeque::new(),
+                    };
+                    self.streams.insert(audio.device.name.clone(), stream);
+                }
+                Err(e) => {
+                    warn!("Can't mix {} at {} Hz: {}", audio.device, sample_rate, e);
+                    return;
+                }
+            }
+        }
+        let Some(stream) = self.streams.get_mut(&audio.device.name) else {
+            return;
+        };
+        stream.buffer.extend(stream.resampler.process(&audio.samples));
+        let max_len = (sample_rate as u64 * MAX_REMOTE_LAG_MS / 1000) as usize;
+        if stream.buffer.len() > max_len {
+            let excess = stream.buffer.len() - max_len;
+            stream.buffer.drain(..excess);
+        }
+    }
+
+    /// Add each device's next `mic.len()` samples to `mic`. Returns the
+    /// loudest device and its RMS over the mixed span.
+    pub fn mix_into(&mut self, mic: &mut [f32]) -> Option<(String, f32)> {
+        let mut loudest: Option<(String, f32)> = None;
+        for (device, stream) in self.streams.iter_mut() {
+            let take = stream.buffer.len().min(mic.len());
+            if take == 0 {
+                continue;
+            }
+            let samples: Vec<f32> = stream.buffer.drain(..take).collect();
+            let (rms, _) = compute_levels(&samples);
+            if loudest.as_ref().map_or(true, |(_, loudest)| rms > *loudest) {
+                loudest = Some((device.clone(), rms));
+            }
+            for (mixed, sample) in mic.iter_mut().zip(samples) {
+                *mixed = (*mixed + sample).clamp(-1.0, 1.0);
+            }
+        }
+        loudest
+    }
+}
+
+struct CompanionServer {
+    info: CompanionServerInfo,
+    shutdown: oneshot::Sender<()>,
+}
+
+static SERVER: Lazy<Mutex<Option<CompanionServer>>> = Lazy::new(|| Mutex::new(None));
+static CONNECTED: Lazy<Mutex<HashMap<SocketAddr, AudioDevice>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static REMOTE_AUDIO: Lazy<broadcast::Sender<RemoteAudio>> = Lazy::new(|| broadcast::channel(512).0);
+
+/// Audio from every connected remote device, as 48 kHz mono.
+pub fn subscribe() -> broadcast::Receiver<RemoteAudio> {
+    REMOTE_AUDIO.subscribe()
+}
+
+/// True while the companion server is accepting connections.
+pub fn is_serving() -> bool {
+    SERVER.lock().map(|server| server.is_some()).unwrap_or(false)
+}
+
+/// Remote devices currently streaming to this instance.
+pub fn connected_devices() -> Vec<AudioDevice> {
+    CONNECTED
+        .lock()
+        .map(|connected| connected.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect()
+}
+
+async fn handle_connection<R: Runtime>(app: AppHandle<R>, stream: TcpStream, addr: SocketAddr, token: String) {
+    let mut socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("WebSocket handshake with {} failed: {}", addr, e);
+            return;
+        }
+    };
+
+    let hello = match socket.next().await {
+        Some(Ok(Message::Text(text))) => serde_json::from_str::<Hello>(&text).ok(),
+        _ => None,
+    };
+    let hello = match hello {
+        Some(hello) if hello.token == token => hello,
+        _ => {
+            warn!("Rejected companion connection from {}: bad or missing token", addr);
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+
+    let mut decoder = match Decoder::new(SampleRate::Hz48000, Channels::Mono) {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            error!("Failed to create Opus decoder: {}", e);
+            return;
+        }
+    };
+
+    let device = AudioDevice::new(
+        format!("{} {}", hello.device_name.trim(), REMOTE_SUFFIX),
+        DeviceType::Input,
+    );
+    info!("Remote microphone connected: {} from {}", device, addr);
+    if let Ok(mut connected) = CONNECTED.lock() {
+        connected.insert(addr, device.clone());
+    }
+    let _ = app.emit("remote-device-connected", device.to_string());
+    let _ = socket.send(Message::Text("{\"status\":\"ok\"}".to_string())).await;
+
+    let mut pcm = vec![0f32; MAX_FRAME_SAMPLES];
+    while let Some(message) = socket.next().await {
+        match message {
+            Ok(Message::Binary(packet)) => match decoder.decode_float(Some(&packet), &mut pcm, false) {
+                Ok(decoded) => {
+                    // No subscribers just means nobody is recording right now
+                    let _ = REMOTE_AUDIO.send(RemoteAudio {
+                        device: device.clone(),
+                        samples: pcm[..decoded].to_vec(),
+                    });
+                }
+                Err(e) => warn!("Dropping undecodable Opus packet from {}: {}", device, e),
+            },
+            Ok(Message::Ping(payload)) => {
+                let _ = socket.send(Message::Pong(payload)).await;
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Remote device {} connection error: {}", device, e);
+                break;
+            }
+        }
+    }
+
+    info!("Remote microphone disconnected: {}", device);
+    if let Ok(mut connected) = CONNECTED.lock() {
+        connected.remove(&addr);
+    }
+    let _ = app.emit("remote-device-disconnected", device.to_string());
+}
+
+/// Listen on the LAN for companion apps streaming Opus audio over WebSocket.
+/// Connected phones show up as input devices and are mixed into the live session.
+#[command]
+pub async fn start_companion_server<R: Runtime>(
+    app: AppHandle<R>,
+    port: Option<u16>,
+) -> Result<CompanionServerInfo, String> {
+    if let Some(server) = SERVER.lock().map_err(|e| e.to_string())?.as_ref() {
+        return Ok(server.info.clone());
+    }
+
+    let port = port.unwrap_or(DEFAULT_COMPANION_PORT);
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+    let info = CompanionServerInfo {
+        port,
+        token: generate_token(),
+    };
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+    let token = info.token.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        tokio::spawn(handle_connection(app.clone(), stream, addr, token.clone()));
+                    }
+                    Err(e) => warn!("Failed to accept companion connection: {}", e),
+                },
+                _ = &mut shutdown_rx => break,
+            }
+        }
+        info!("Companion server stopped");
+    });
+
+    info!("Companion server listening on port {}", port);
+    *SERVER.lock().map_err(|e| e.to_string())? = Some(CompanionServer {
+        info: info.clone(),
+        shutdown: shutdown_tx,
+    });
+    Ok(info)
+}
+
+#[command]
+pub fn stop_companion_server() -> Result<(), String> {
+    if let Some(server) = SERVER.lock().map_err(|e| e.to_string())?.take() {
+        let _ = server.shutdown.send(());
+    }
+    Ok(())
+}
+
+#[command]
+pub fn list_remote_devices() -> Vec<String> {
+    connected_devices().iter().map(|d| d.to_string()).collect()
+}
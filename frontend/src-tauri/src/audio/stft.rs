@@ -0,0 +1,78 @@
+use realfft::num_complex::Complex32;
+use realfft::RealFftPlanner;
+
+/// FFT size for streaming spectral processing; frames overlap by half.
+pub const FFT_SIZE: usize = 512;
+pub const HOP: usize = FFT_SIZE / 2;
+
+/// Square root of a periodic Hann window: applied on analysis and synthesis
+/// it overlap-adds to unity at 50% overlap.
+pub fn window() -> Vec<f32> {
+    (0..FFT_SIZE)
+        .map(|i| (0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FFT_SIZE as f32).cos()).sqrt())
+        .collect()
+}
+
+/// Streaming short-time Fourier processing: each windowed frame's spectrum
+/// is handed to a callback to modify, then resynthesized by overlap-add.
+/// Output lags input by `HOP` samples; blocks may come in any size.
+pub struct OverlapAdd {
+    window: Vec<f32>,
+    planner: RealFftPlanner<f32>,
+    pending: Vec<f32>,
+    overlap: Vec<f32>,
+}
+
+impl Default for OverlapAdd {
+    fn default() -> Self {
+        Self {
+            window: window(),
+            planner: RealFftPlanner::new(),
+            pending: Vec::new(),
+            overlap: vec![0.0; HOP],
+        }
+    }
+}
+
+impl OverlapAdd {
+    pub fn process<F>(&mut self, samples: &[f32], mut modify: F) -> Vec<f32>
+    where
+        F: FnMut(&mut [Complex32]),
+    {
+        self.pending.extend_from_slice(samples);
+        let forward = self.planner.plan_fft_forward(FFT_SIZE);
+        let inverse = self.planner.plan_fft_inverse(FFT_SIZE);
+        let mut output = Vec::with_capacity(samples.len());
+
+        while self.pending.len() >= FFT_SIZE {
+            let mut input: Vec<f32> = self.pending[..FFT_SIZE]
+                .iter()
+                .zip(&self.window)
+                .map(|(s, w)| s * w)
+                .collect();
+            let mut spectrum = forward.make_output_vec();
+            if forward.process(&mut input, &mut spectrum).is_err() {
+                return samples.to_vec();
+            }
+            modify(&mut spectrum);
+            // DC and Nyquist bins of a real signal must stay real
+            if let Some(first) = spectrum.first_mut() {
+                first.im = 0.0;
+            }
+            if let Some(last) = spectrum.last_mut() {
+                last.im = 0.0;
+            }
+            let mut frame = inverse.make_output_vec();
+            if inverse.process(&mut spectrum, &mut frame).is_err() {
+                return samples.to_vec();
+            }
+            for (i, value) in frame.iter_mut().enumerate() {
+                *value *= self.window[i] / FFT_SIZE as f32;
+            }
+            output.extend(self.overlap.iter().zip(&frame[..HOP]).map(|(a, b)| a + b));
+            self.overlap.copy_from_slice(&frame[HOP..]);
+            self.pending.drain(..HOP);
+        }
+        output
+    }
+}
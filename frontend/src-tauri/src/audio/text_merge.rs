@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+/// Inputs longer than this are truncated to the tail of the previous text and
+/// the head of the current one; overlaps only ever occur at the boundary.
+pub const MAX_MERGE_WORDS: usize = 2000;
+
+/// Optimized function to find longest common word substring between two texts
+/// Uses rolling hash and suffix array approach for better performance
+/// Returned indices are word positions in `split_whitespace()` of each input.
+pub fn longest_common_word_substring(s1: &str, s2: &str) -> Option<(usize, usize)> {
+    // Early termination for empty strings
+    if s1.is_empty() || s2.is_empty() {
+        return None;
+    }
+
+    // Preprocess words once with optimized string handling
+    let s1_words = preprocess_words(s1);
+    let s2_words = preprocess_words(s2);
+
+    // Bound the work on pathological inputs: only the end of s1 can overlap the start of s2
+    let s1_offset = s1_words.len().saturating_sub(MAX_MERGE_WORDS);
+    let s1_words = &s1_words[s1_offset..];
+    let s2_words = &s2_words[..s2_words.len().min(MAX_MERGE_WORDS)];
+
+    let s1_len = s1_words.len();
+    let s2_len = s2_words.len();
+
+    // Early termination for very short texts
+    if s1_len < 2 || s2_len < 2 {
+        return None;
+    }
+
+    // For small inputs, use the simpler approach
+    let best = if s1_len * s2_len < 1000 {
+        find_common_substring_simple(s1_words, s2_words)
+    } else {
+        // For larger inputs, use optimized rolling hash approach
+        find_common_substring_optimized(s1_words, s2_words)
+    };
+
+    best.map(|(i, j)| (i + s1_offset, j))
+}
+
+/// Preprocess text into cleaned words vector with minimal allocations.
+/// Punctuation-only tokens become empty strings (which never match) rather than
+/// being dropped, so positions stay aligned with `split_whitespace()`.
+fn preprocess_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            // Remove punctuation and convert to lowercase in one pass
+            word.chars()
+                .filter(|c| !c.is_ascii_punctuation())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .collect()
+}
+
+fn words_match(a: &str, b: &str) -> bool {
+    !a.is_empty() && a == b
+}
+
+/// Simple O(n*m) approach for small inputs
+fn find_common_substring_simple(s1_words: &[String], s2_words: &[String]) -> Option<(usize, usize)> {
+    let mut max_len = 0;
+    let mut best_match = None;
+
+    // Use sliding window approach to reduce comparisons
+    for i in 0..s1_words.len() {
+        for j in 0..s2_words.len() {
+            let mut len = 0;
+            let mut ii = i;
+            let mut jj = j;
+
+            // Extend the match as far as possible
+            while ii < s1_words.len() && jj < s2_words.len() && words_match(&s1_words[ii], &s2_words[jj]) {
+                len += 1;
+                ii += 1;
+                jj += 1;
+            }
+
+            if len > max_len {
+                max_len = len;
+                best_match = Some((i, j));
+            }
+        }
+    }
+
+    best_match
+}
+
+/// Optimized approach using suffix arrays and LCP for large inputs
+fn find_common_substring_optimized(s1_words: &[String], s2_words: &[String]) -> Option<(usize, usize)> {
+    // Create a hash map for word positions to speed up lookups
+    let mut s2_positions: HashMap<&String, Vec<usize>> = HashMap::new();
+    for (idx, word) in s2_words.iter().enumerate() {
+        if !word.is_empty() {
+            s2_positions.entry(word).or_insert_with(Vec::new).push(idx);
+        }
+    }
+
+    let mut max_len = 0;
+    let mut best_match = None;
+
+    // For each word in s1, find all matching positions in s2
+    for (i, word) in s1_words.iter().enumerate() {
+        if let Some(positions) = s2_positions.get(word) {
+            for &j in positions {
+                // Check how far the match extends
+                let mut len = 0;
+                let mut ii = i;
+                let mut jj = j;
+
+                while ii < s1_words.len() && jj < s2_words.len() && words_match(&s1_words[ii], &s2_words[jj]) {
+                    len += 1;
+                    ii += 1;
+                    jj += 1;
+                }
+
+                if len > max_len {
+                    max_len = len;
+                    best_match = Some((i, j));
+                }
+            }
+        }
+    }
+
+    best_match
+}
+
+/// Trim the overlap between `previous` and `current`, returning the new
+/// previous text (up to the overlap) and the new current text (after it).
+pub fn cleanup_overlap(previous: &str, current: &str) -> Option<(String, String)> {
+    // Early termination for empty or very short texts
+    if previous.is_empty() || current.is_empty() {
+        return None;
+    }
+
+    let (prev_idx, cur_idx) = longest_common_word_substring(previous, current)?;
+
+    // Use iterators and avoid intermediate collections
+    let new_prev = previous
+        .split_whitespace()
+        .take(prev_idx)
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let new_cur = current
+        .split_whitespace()
+        .skip(cur_idx)
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    // Only return if we actually have meaningful content
+    if !new_prev.is_empty() || !new_cur.is_empty() {
+        Some((new_prev, new_cur))
+    } else {
+        None
+    }
+}
+
+/// Heuristic-based overlap detection for large texts
+pub fn cleanup_overlap_heuristic(prev: &str, curr: &str) -> Option<(String, String)> {
+    // Look for overlaps in the last 20% of previous and first 20% of current
+    let prev_words: Vec<&str> = prev.split_whitespace().collect();
+    let curr_words: Vec<&str> = curr.split_whitespace().collect();
+
+    if prev_words.is_empty() || curr_words.is_empty() {
+        return None;
+    }
+
+    let search_window = std::cmp::min(prev_words.len() / 5, 50); // Max 50 words
+    let prev_start = prev_words.len().saturating_sub(search_window);
+    let curr_end = std::cmp::min(search_window, curr_words.len());
+
+    // Find the longest match in the search window
+    let mut best_match = None;
+    let mut max_len = 0;
+
+    for i in prev_start..prev_words.len() {
+        for j in 0..curr_end {
+            if prev_words[i] == curr_words[j] {
+                let mut len = 1;
+                let mut pi = i + 1;
+                let mut ci = j + 1;
+
+                while pi < prev_words.len() && ci < curr_words.len() && prev_words[pi] == curr_words[ci] {
+                    len += 1;
+                    pi += 1;
+                    ci += 1;
+                }
+
+                if len > max_len && len >= 3 { // Require at least 3 words for overlap
+                    max_len = len;
+                    best_match = Some((i, j));
+                }
+            }
+        }
+    }
+
+    let (prev_idx, curr_idx) = best_match?;
+    let new_prev = prev_words[..prev_idx].join(" ");
+    let new_curr = curr_words[curr_idx + max_len..].join(" ");
+    Some((new_prev, new_curr))
+}
@@ -0,0 +1,181 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::settings;
+use crate::transcription::Transcript;
+
+/// Analysis frame for the syllable estimate.
+const ENVELOPE_FRAME_MS: usize = 10;
+/// Frames averaged into the envelope; syllable nuclei are 50-200 ms apart.
+const ENVELOPE_SMOOTHING: usize = 5;
+/// A peak has to rise this far above the valley before it to count.
+const PEAK_PROMINENCE: f32 = 0.25;
+/// WSOLA frame, overlapped by half, and how far a frame may move to line up.
+const WSOLA_FRAME_MS: usize = 25;
+const WSOLA_TOLERANCE_MS: usize = 6;
+
+/// Slow down chunks of very fast speech before transcription, since whisper
+/// drops and merges words when people speak quickly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeStretchSettings {
+    pub enabled: bool,
+    /// Playback speed of stretched audio; 0.9 makes it 10% longer.
+    pub speed: f32,
+    /// Estimated syllables per second of speech above which a chunk is stretched.
+    pub syllables_per_sec: f32,
+}
+
+impl Default for TimeStretchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speed: 0.9,
+            syllables_per_sec: 6.5,
+        }
+    }
+}
+
+/// Syllables per second of voiced audio, counted as prominent peaks of the
+/// smoothed energy envelope. Conversational speech runs at 4-5.
+pub fn syllable_rate(samples: &[f32], sample_rate: u32) -> f32 {
+    let frame = (sample_rate as usize * ENVELOPE_FRAME_MS / 1000).max(1);
+    let energy: Vec<f32> = samples
+        .chunks(frame)
+        .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+        .collect();
+    let envelope: Vec<f32> = (0..energy.len())
+        .map(|i| {
+            let window = &energy[i.saturating_sub(ENVELOPE_SMOOTHING / 2)..(i + ENVELOPE_SMOOTHING / 2 + 1).min(energy.len())];
+            window.iter().sum::<f32>() / window.len() as f32
+        })
+        .collect();
+    let loudest = envelope.iter().copied().fold(0.0, f32::max);
+    if loudest <= f32::EPSILON {
+        return 0.0;
+    }
+    // Frames within 30 dB of the loudest are speech
+    let voiced_floor = loudest * 0.03;
+    let voiced = envelope.iter().filter(|e| **e > voiced_floor).count();
+
+    let mut peaks = 0;
+    let mut valley = f32::MAX;
+    for i in 1..envelope.len().saturating_sub(1) {
+        let level = envelope[i];
+        valley = valley.min(level);
+        let is_peak = level > envelope[i - 1] && level >= envelope[i + 1];
+        if is_peak && level > voiced_floor && level - valley > PEAK_PROMINENCE * level {
+            peaks += 1;
+            valley = level;
+        }
+    }
+    let voiced_secs = voiced as f32 * ENVELOPE_FRAME_MS as f32 / 1000.0;
+    if voiced_secs < 1.0 {
+        return 0.0;
+    }
+    peaks as f32 / voiced_secs
+}
+
+fn hann(len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / len as f32).cos())
+        .collect()
+}
+
+/// Change the tempo of `samples` by `speed` without changing its pitch, using
+/// waveform-similarity overlap-add: each frame is taken from near where the
+/// tempo says it should be, shifted to line up with the previous frame's
+/// natural continuation.
+pub fn wsola(samples: &[f32], sample_rate: u32, speed: f32) -> Vec<f32> {
+    let frame = (sample_rate as usize * WSOLA_FRAME_MS / 1000).max(4) & !1;
+    let hop_out = frame / 2;
+    let tolerance = sample_rate as usize * WSOLA_TOLERANCE_MS / 1000;
+    if samples.len() < frame + tolerance || speed <= 0.0 || (speed - 1.0).abs() < f32::EPSILON {
+        return samples.to_vec();
+    }
+    let window = hann(frame);
+    let hop_in = hop_out as f32 * speed;
+    let mut output = vec![0.0; (samples.len() as f32 / speed) as usize + frame];
+
+    let mut previous = 0usize;
+    let mut k = 0usize;
+    loop {
+        let nominal = (k as f32 * hop_in).round() as usize;
+        let position = if k == 0 {
+            0
+        } else {
+            // What would follow the previous frame if nothing were stretched
+            let natural = previous + hop_out;
+            if natural + frame > samples.len() {
+                break;
+            }
+            let target = &samples[natural..natural + frame];
+            let from = nominal.saturating_sub(tolerance);
+            let to = (nominal + tolerance).min(samples.len() - frame);
+            if from > to {
+                break;
+            }
+            (from..=to)
+                .map(|start| {
+                    let similarity: f32 = samples[start..start + frame]
+                        .iter()
+                        .zip(target)
+                        .step_by(2)
+                        .map(|(x, y)| x * y)
+                        .sum();
+                    (similarity, start)
+                })
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, start)| start)
+                .unwrap_or(nominal)
+        };
+        let out_start = k * hop_out;
+        if position + frame > samples.len() || out_start + frame > output.len() {
+            break;
+        }
+        for i in 0..frame {
+            output[out_start + i] += samples[position + i] * window[i];
+        }
+        previous = position;
+        k += 1;
+    }
+    output.truncate((k * hop_out + hop_out).min(output.len()));
+    output
+}
+
+/// The speed to stretch a chunk to, when it's fast enough to need it.
+pub fn speed_for(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let config = settings::get().time_stretch;
+    if !config.enabled || !(0.5..1.0).contains(&config.speed) {
+        return None;
+    }
+    let rate = syllable_rate(samples, sample_rate);
+    if rate <= config.syllables_per_sec {
+        return None;
+    }
+    debug!("Stretching chunk at {:.1} syllables/s to {:.2}x", rate, config.speed);
+    Some(config.speed)
+}
+
+/// Map timestamps of a transcript of audio stretched to `speed` back onto the
+/// original audio.
+pub fn restore_timestamps(transcript: &mut Transcript, speed: f32) {
+    for segment in &mut transcript.segments {
+        segment.t0 *= speed;
+        segment.t1 *= speed;
+    }
+}
+
+#[command]
+pub fn get_time_stretch_settings() -> TimeStretchSettings {
+    settings::get().time_stretch
+}
+
+#[command]
+pub fn set_time_stretch_settings(time_stretch_settings: TimeStretchSettings) -> Result<TimeStretchSettings, String> {
+    if !(0.5..1.0).contains(&time_stretch_settings.speed) {
+        return Err("Stretch speed must be at least 0.5 and below 1.0".to_string());
+    }
+    settings::update(|s| s.time_stretch = time_stretch_settings).map(|s| s.time_stretch)
+}
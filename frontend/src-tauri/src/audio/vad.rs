@@ -0,0 +1,14 @@
+use tauri::command;
+
+use super::noise;
+
+/// The detector lives in the `meetingly-vad` crate, which is also compiled
+/// to WASM for the webview's mic preview.
+pub use meetingly_vad::EnergyVad;
+
+/// VAD parameters the webview's preview should use for `device`, tuned
+/// exactly as the recording pipeline tunes them.
+#[command]
+pub fn get_preview_vad(device: String) -> EnergyVad {
+    noise::vad_for(&device)
+}
@@ -0,0 +1,90 @@
+use std::f32::consts::PI;
+
+use realfft::num_complex::Complex32;
+use serde::{Deserialize, Serialize};
+
+use super::stft::{OverlapAdd, FFT_SIZE, HOP};
+
+/// Pitch shifting applied to exported audio so recordings can be shared
+/// without exposing who was speaking. The stored recording is never changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceMaskSettings {
+    pub enabled: bool,
+    /// Shift in semitones; negative lowers voices. A few semitones either way
+    /// keeps speech intelligible while making voices hard to recognize.
+    pub semitones: f32,
+}
+
+impl Default for VoiceMaskSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            semitones: 4.0,
+        }
+    }
+}
+
+/// Largest shift accepted; beyond this speech stops being intelligible.
+pub const MAX_SEMITONES: f32 = 12.0;
+
+fn wrap(phase: f32) -> f32 {
+    (phase + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Phase vocoder shifting every frequency by a fixed ratio. Each bin's true
+/// frequency is estimated from its phase advance between frames, moved to
+/// the bin it lands on after scaling, and resynthesized with a running phase.
+struct PitchShift {
+    ratio: f32,
+    last_phase: Vec<f32>,
+    phase: Vec<f32>,
+}
+
+impl PitchShift {
+    fn new(ratio: f32) -> Self {
+        let bins = FFT_SIZE / 2 + 1;
+        Self {
+            ratio,
+            last_phase: vec![0.0; bins],
+            phase: vec![0.0; bins],
+        }
+    }
+
+    fn shift(&mut self, spectrum: &mut [Complex32]) {
+        let bins = spectrum.len();
+        // Phase a bin-centred sinusoid advances by over one hop
+        let advance = 2.0 * PI * HOP as f32 / FFT_SIZE as f32;
+        let mut magnitude = vec![0.0f32; bins];
+        let mut frequency = vec![0.0f32; bins];
+        for (bin, value) in spectrum.iter().enumerate() {
+            let phase = value.arg();
+            let deviation = wrap(phase - self.last_phase[bin] - bin as f32 * advance);
+            self.last_phase[bin] = phase;
+            let target = (bin as f32 * self.ratio).round() as usize;
+            if target < bins {
+                magnitude[target] += value.norm();
+                frequency[target] = (bin as f32 + deviation / advance) * self.ratio;
+            }
+        }
+        for (bin, value) in spectrum.iter_mut().enumerate() {
+            self.phase[bin] = wrap(self.phase[bin] + frequency[bin] * advance);
+            *value = Complex32::from_polar(magnitude[bin], self.phase[bin]);
+        }
+    }
+}
+
+/// `samples` with every voice shifted by `semitones`, same length and rate.
+pub fn mask(samples: &[f32], semitones: f32) -> Vec<f32> {
+    let semitones = semitones.clamp(-MAX_SEMITONES, MAX_SEMITONES);
+    if semitones == 0.0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let mut shifter = PitchShift::new(2f32.powf(semitones / 12.0));
+    let mut stft = OverlapAdd::default();
+    // Pad so the last frames come out, then drop the overlap-add latency
+    let mut padded = samples.to_vec();
+    padded.resize(samples.len() + FFT_SIZE, 0.0);
+    let output = stft.process(&padded, |spectrum| shifter.shift(spectrum));
+    output.into_iter().skip(HOP).take(samples.len()).collect()
+}
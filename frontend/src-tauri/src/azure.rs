@@ -0,0 +1,241 @@
+use futures_util::future::BoxFuture;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::transcription::{encode_wav, AudioInput, Transcript, TranscriptionEngine};
+use crate::{secrets, settings, TranscriptSegment};
+
+pub const API_KEY_SECRET: &str = "azure-speech-key";
+const API_VERSION: &str = "2024-11-15";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AzureSpeechSettings {
+    /// Azure region of the Speech resource, e.g. `westeurope`.
+    pub region: String,
+    /// Custom or private endpoint of the resource, used instead of the region's
+    /// public endpoint, e.g. `https://my-speech.cognitiveservices.azure.com`.
+    pub endpoint: Option<String>,
+    /// ISO-639-1 code or Azure locale; detected automatically when `None`.
+    pub language: Option<String>,
+    /// Label speakers within each chunk.
+    pub diarization: bool,
+    pub max_speakers: u32,
+}
+
+impl Default for AzureSpeechSettings {
+    fn default() -> Self {
+        Self {
+            region: "eastus".to_string(),
+            endpoint: None,
+            language: None,
+            diarization: false,
+            max_speakers: 4,
+        }
+    }
+}
+
+impl AzureSpeechSettings {
+    fn base_url(&self) -> String {
+        match self.endpoint.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!("https://{}.api.cognitive.microsoft.com", self.region.trim()),
+        }
+    }
+}
+
+/// Azure locales to try when detecting the language of a chunk.
+const DETECTION_LOCALES: [&str; 4] = ["en-US", "de-DE", "fr-FR", "es-ES"];
+
+/// Azure locale for an ISO-639-1 code, or the value itself when it already
+/// names a locale such as `en-GB`.
+pub fn azure_locale(language: &str) -> Option<String> {
+    let language = language.trim();
+    if language.contains('-') {
+        return Some(language.to_string());
+    }
+    let locale = match language.to_lowercase().as_str() {
+        "ar" => "ar-SA",
+        "ca" => "ca-ES",
+        "cs" => "cs-CZ",
+        "da" => "da-DK",
+        "de" => "de-DE",
+        "el" => "el-GR",
+        "en" => "en-US",
+        "es" => "es-ES",
+        "fi" => "fi-FI",
+        "fr" => "fr-FR",
+        "he" => "he-IL",
+        "hi" => "hi-IN",
+        "hu" => "hu-HU",
+        "id" => "id-ID",
+        "it" => "it-IT",
+        "ja" => "ja-JP",
+        "ko" => "ko-KR",
+        "nb" | "no" => "nb-NO",
+        "nl" => "nl-NL",
+        "pl" => "pl-PL",
+        "pt" => "pt-BR",
+        "ro" => "ro-RO",
+        "ru" => "ru-RU",
+        "sk" => "sk-SK",
+        "sv" => "sv-SE",
+        "th" => "th-TH",
+        "tr" => "tr-TR",
+        "uk" => "uk-UA",
+        "vi" => "vi-VN",
+        "zh" => "zh-CN",
+        _ => return None,
+    };
+    Some(locale.to_string())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Definition {
+    locales: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diarization: Option<Diarization>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Diarization {
+    max_speakers: u32,
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    #[serde(default)]
+    phrases: Vec<Phrase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Phrase {
+    offset_milliseconds: u64,
+    duration_milliseconds: u64,
+    text: String,
+    #[serde(default)]
+    speaker: Option<u32>,
+}
+
+/// Azure AI Speech fast transcription, which recognizes a whole chunk in one
+/// request and returns every phrase in it.
+#[derive(Default)]
+pub struct AzureSpeechEngine {
+    client: reqwest::Client,
+}
+
+impl AzureSpeechEngine {
+    pub const ID: &'static str = "azure";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn transcribe_chunk(&self, audio: &AudioInput) -> Result<Transcript, String> {
+        let config = settings::get().azure;
+        let api_key = secrets::require(API_KEY_SECRET)?;
+
+        let locales = match audio.language.as_deref().or(config.language.as_deref()) {
+            Some(language) => vec![azure_locale(language)
+                .ok_or_else(|| format!("No Azure locale for language '{}'", language))?],
+            None => DETECTION_LOCALES.iter().map(|l| l.to_string()).collect(),
+        };
+        let definition = Definition {
+            locales,
+            diarization: config.diarization.then_some(Diarization {
+                max_speakers: config.max_speakers.max(2),
+                enabled: true,
+            }),
+        };
+        let definition = serde_json::to_string(&definition).map_err(|e| e.to_string())?;
+        let file = Part::bytes(encode_wav(audio)?)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| format!("Failed to build upload: {}", e))?;
+        let form = Form::new().part("audio", file).text("definition", definition);
+
+        let url = format!(
+            "{}/speechtotext/transcriptions:transcribe?api-version={}",
+            config.base_url(),
+            API_VERSION
+        );
+        let response = self
+            .client
+            .post(url)
+            .header("Ocp-Apim-Subscription-Key", api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("Azure Speech request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Azure Speech returned {}: {}", status, body));
+        }
+        let parsed: TranscriptionResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Azure Speech response: {}", e))?;
+
+        let segments = parsed
+            .phrases
+            .into_iter()
+            .filter(|phrase| !phrase.text.trim().is_empty())
+            .map(|phrase| TranscriptSegment {
+                text: phrase.text,
+                t0: phrase.offset_milliseconds as f32 / 1000.0,
+                t1: (phrase.offset_milliseconds + phrase.duration_milliseconds) as f32 / 1000.0,
+                speaker: phrase.speaker.map(|speaker| speaker.to_string()),
+                no_speech_prob: None,
+                avg_logprob: None,
+                language: None,
+                translated_from: None,
+            })
+            .collect();
+        Ok(Transcript {
+            segments,
+            buffer_size_ms: 0,
+            engine: Self::ID.to_string(),
+        })
+    }
+}
+
+impl TranscriptionEngine for AzureSpeechEngine {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn name(&self) -> &str {
+        "Azure AI Speech"
+    }
+
+    fn is_cloud(&self) -> bool {
+        true
+    }
+
+    fn health_url(&self) -> Option<String> {
+        Some(settings::get().azure.base_url())
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>> {
+        Box::pin(self.transcribe_chunk(audio))
+    }
+}
+
+#[command]
+pub fn get_azure_settings() -> AzureSpeechSettings {
+    settings::get().azure
+}
+
+#[command]
+pub fn set_azure_settings(azure_settings: AzureSpeechSettings) -> Result<AzureSpeechSettings, String> {
+    if let Some(language) = azure_settings.language.as_deref() {
+        azure_locale(language).ok_or_else(|| format!("No Azure locale for language '{}'", language))?;
+    }
+    settings::update(|s| s.azure = azure_settings).map(|s| s.azure)
+}
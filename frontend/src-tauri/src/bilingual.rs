@@ -0,0 +1,127 @@
+use std::sync::Mutex;
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::{language_id, settings};
+
+/// How one of a bilingual meeting's languages is decoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageProfile {
+    /// Whisper language code, e.g. `"en"` or `"es"`.
+    pub code: String,
+    /// Names and terms in this language, given to the decoder as its prompt.
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+}
+
+/// For meetings that alternate between two languages: each chunk's language
+/// is identified first and the chunk decoded with that language forced,
+/// instead of leaving whisper to guess it while decoding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BilingualSettings {
+    pub enabled: bool,
+    pub languages: Vec<LanguageProfile>,
+    /// A chunk identified with less confidence than this keeps the previous
+    /// chunk's language; short or noisy chunks are often misidentified.
+    pub min_confidence: f32,
+}
+
+impl Default for BilingualSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            languages: Vec::new(),
+            min_confidence: 0.5,
+        }
+    }
+}
+
+/// Language of the last chunk decoded in bilingual mode.
+static CURRENT_LANGUAGE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Forget the language carried over between chunks, at the start of a recording.
+pub fn reset() {
+    if let Ok(mut current) = CURRENT_LANGUAGE.lock() {
+        *current = None;
+    }
+}
+
+async fn identify<'a>(
+    samples: &[f32],
+    client: &reqwest::Client,
+    endpoint: &str,
+    config: &'a BilingualSettings,
+) -> Result<(&'a LanguageProfile, f32), String> {
+    let candidates: Vec<String> = config.languages.iter().map(|l| l.code.clone()).collect();
+    let probabilities = language_id::detect(samples, client, endpoint, &candidates).await?;
+    let probability = |l: &LanguageProfile| probabilities.get(&l.code).copied().unwrap_or(0.0);
+
+    let (best, confidence) = config
+        .languages
+        .iter()
+        .map(|l| (l, probability(l)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .ok_or_else(|| "No languages configured".to_string())?;
+    let mut current = CURRENT_LANGUAGE.lock().map_err(|e| e.to_string())?;
+    let chosen = match current.as_deref() {
+        Some(previous) if confidence < config.min_confidence => {
+            config.languages.iter().find(|l| l.code == previous).unwrap_or(best)
+        }
+        _ => best,
+    };
+    debug!(
+        "Identified chunk as {} ({:.2}), decoding as {}",
+        best.code, confidence, chosen.code
+    );
+    *current = Some(chosen.code.clone());
+    Ok((chosen, probability(chosen)))
+}
+
+/// In bilingual mode, the language a chunk is identified as, whose code and
+/// vocabulary it should be decoded with, and the model's confidence in it.
+/// `None` when the mode is off or identification failed, leaving the
+/// server's own language settings.
+pub async fn language_for(
+    samples: &[f32],
+    client: &reqwest::Client,
+    endpoint: &str,
+) -> Option<(LanguageProfile, f32)> {
+    let config = settings::get().bilingual;
+    if !config.enabled || config.languages.len() < 2 {
+        return None;
+    }
+    match identify(samples, client, endpoint, &config).await {
+        Ok((profile, confidence)) => Some((profile.clone(), confidence)),
+        Err(e) => {
+            warn!("{}; decoding without a forced language", e);
+            None
+        }
+    }
+}
+
+#[command]
+pub fn get_bilingual_settings() -> BilingualSettings {
+    settings::get().bilingual
+}
+
+#[command]
+pub fn set_bilingual_settings(mut bilingual_settings: BilingualSettings) -> Result<BilingualSettings, String> {
+    for language in &mut bilingual_settings.languages {
+        language.code = language.code.trim().to_lowercase();
+    }
+    bilingual_settings.languages.retain(|l| !l.code.is_empty());
+    if bilingual_settings.enabled {
+        let [first, second] = bilingual_settings.languages.as_slice() else {
+            return Err("Bilingual mode needs exactly two languages".to_string());
+        };
+        if first.code == second.code {
+            return Err("Bilingual mode needs two different languages".to_string());
+        }
+    }
+    bilingual_settings.min_confidence = bilingual_settings.min_confidence.clamp(0.0, 1.0);
+    settings::update(|s| s.bilingual = bilingual_settings).map(|s| s.bilingual)
+}
@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::DateTime;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::export::naming::ExportMeeting;
+use crate::export::rules::{render_document, DocumentFormat, FinishedMeeting};
+use crate::export::{export_to_folder, ArtifactKind, ExportArtifact};
+use crate::{encryption, settings};
+use crate::storage::{self, MeetingFilter, MeetingManifest, DERIVED_DIR};
+
+pub const PROGRESS_EVENT: &str = "bulk-operation-progress";
+/// Emitted per meeting for `BulkAction::Resummarize`; summaries are generated
+/// in the webview, which saves the result with `save_derived`.
+pub const SUMMARY_REQUESTED_EVENT: &str = "summary-requested";
+/// Derived file holding a meeting's summary, when one was saved.
+pub const SUMMARY_FILE: &str = "summary.md";
+
+/// Which meetings a bulk operation covers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum BulkSelection {
+    Ids {
+        meeting_ids: Vec<String>,
+    },
+    /// Every meeting `search_meetings` returns for this filter.
+    Search {
+        filter: MeetingFilter,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkAction {
+    Tag {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    /// Write each meeting's document to `folder` (or the default export folder).
+    Export {
+        #[serde(default)]
+        folder: Option<String>,
+        #[serde(default)]
+        format: DocumentFormat,
+    },
+    Resummarize,
+    Archive,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemFailure {
+    pub meeting_id: String,
+    pub error: String,
+}
+
+/// Progress of one bulk operation, as sent with `PROGRESS_EVENT`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkJob {
+    pub id: String,
+    pub action: BulkAction,
+    pub state: JobState,
+    pub total: usize,
+    pub done: usize,
+    pub failures: Vec<ItemFailure>,
+    #[serde(skip)]
+    meeting_ids: Vec<String>,
+    #[serde(skip)]
+    cancel_requested: bool,
+}
+
+static JOBS: Lazy<Mutex<HashMap<String, BulkJob>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Jobs run one at a time, in the order they were started.
+static QUEUE: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+fn snapshot(job_id: &str) -> Option<BulkJob> {
+    JOBS.lock().ok()?.get(job_id).cloned()
+}
+
+fn update_job<R: Runtime>(app: &AppHandle<R>, job_id: &str, change: impl FnOnce(&mut BulkJob)) {
+    let job = JOBS.lock().ok().and_then(|mut jobs| {
+        let job = jobs.get_mut(job_id)?;
+        change(job);
+        Some(job.clone())
+    });
+    if let Some(job) = job {
+        let _ = app.emit(PROGRESS_EVENT, &job);
+    }
+}
+
+fn resolve<R: Runtime>(app: &AppHandle<R>, selection: BulkSelection) -> Result<Vec<String>, String> {
+    let mut ids = match selection {
+        BulkSelection::Ids { meeting_ids } => meeting_ids,
+        BulkSelection::Search { filter } => storage::search_meetings(app.clone(), filter)?
+            .into_iter()
+            .map(|manifest| manifest.id)
+            .collect(),
+    };
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(id.clone()));
+    Ok(ids)
+}
+
+fn duration_secs(manifest: &MeetingManifest) -> u64 {
+    let parse = |time: &str| DateTime::parse_from_rfc3339(time).ok();
+    match (
+        parse(&manifest.created_at),
+        manifest.derived_at.as_deref().and_then(parse),
+    ) {
+        (Some(start), Some(end)) => (end - start).num_seconds().max(0) as u64,
+        _ => 0,
+    }
+}
+
+fn export_one(dir: &Path, folder: &Path, format: DocumentFormat) -> Result<(), String> {
+    let manifest = storage::read_manifest(dir)?;
+    let meeting = FinishedMeeting {
+        duration_secs: duration_secs(&manifest),
+        transcript: storage::read_transcript(dir)?,
+        summary: encryption::read_to_string(dir.join(DERIVED_DIR).join(SUMMARY_FILE)).ok(),
+        meeting: ExportMeeting {
+            id: manifest.id,
+            title: manifest.name,
+            started_at: Some(manifest.created_at),
+            tags: manifest.tags,
+            metadata: manifest.metadata,
+        },
+    };
+    let mut artifacts = vec![render_document(&meeting, format)];
+    if let Some(summary) = &meeting.summary {
+        artifacts.push(ExportArtifact {
+            kind: ArtifactKind::Summary,
+            file_name: SUMMARY_FILE.to_string(),
+            content: Some(summary.clone()),
+            path: None,
+        });
+    }
+    export_to_folder(&meeting.meeting, &artifacts, folder).map(|_| ())
+}
+
+fn run_item<R: Runtime>(app: &AppHandle<R>, action: &BulkAction, meeting_id: &str) -> Result<(), String> {
+    match action {
+        BulkAction::Tag { add, remove } => storage::update_tags(app, meeting_id, add, remove).map(|_| ()),
+        BulkAction::Export { folder, format } => {
+            let folder = folder
+                .clone()
+                .or_else(|| settings::get().export.folder)
+                .ok_or_else(|| "No export folder configured".to_string())?;
+            export_one(&storage::meeting_dir(app, meeting_id)?, Path::new(&folder), *format)
+        }
+        BulkAction::Resummarize => {
+            let dir = storage::meeting_dir(app, meeting_id)?;
+            storage::read_transcript(&dir)?;
+            app.emit(SUMMARY_REQUESTED_EVENT, meeting_id)
+                .map_err(|e| format!("Failed to request summary: {}", e))
+        }
+        BulkAction::Archive => storage::archive_meeting(app, meeting_id).map(|_| ()),
+        BulkAction::Delete => storage::delete_meeting(app, meeting_id),
+    }
+}
+
+async fn run_job<R: Runtime>(app: AppHandle<R>, job_id: String) {
+    let _turn = QUEUE.lock().await;
+    let Some(job) = snapshot(&job_id) else { return };
+    if job.cancel_requested {
+        update_job(&app, &job_id, |job| job.state = JobState::Cancelled);
+        return;
+    }
+    update_job(&app, &job_id, |job| job.state = JobState::Running);
+
+    for meeting_id in &job.meeting_ids {
+        if snapshot(&job_id).map(|job| job.cancel_requested).unwrap_or(true) {
+            info!("Bulk operation {} cancelled", job_id);
+            update_job(&app, &job_id, |job| job.state = JobState::Cancelled);
+            return;
+        }
+        // Archive, delete and export touch the filesystem; keep them off the async workers
+        let result = {
+            let app = app.clone();
+            let action = job.action.clone();
+            let meeting_id = meeting_id.clone();
+            tokio::task::spawn_blocking(move || run_item(&app, &action, &meeting_id))
+                .await
+                .unwrap_or_else(|e| Err(format!("Bulk operation task failed: {}", e)))
+        };
+        if let Err(e) = &result {
+            warn!("Bulk operation {} failed for meeting {}: {}", job_id, meeting_id, e);
+        }
+        update_job(&app, &job_id, |job| {
+            job.done += 1;
+            if let Err(error) = result {
+                job.failures.push(ItemFailure {
+                    meeting_id: meeting_id.clone(),
+                    error,
+                });
+            }
+        });
+    }
+
+    update_job(&app, &job_id, |job| job.state = JobState::Completed);
+    if let Some(job) = snapshot(&job_id) {
+        info!(
+            "Bulk operation {} finished: {} meetings, {} failed",
+            job_id,
+            job.total,
+            job.failures.len()
+        );
+    }
+}
+
+/// Queue `action` over the selected meetings. Progress is reported through
+/// `PROGRESS_EVENT`; returns the job as queued.
+#[command]
+pub fn start_bulk_operation<R: Runtime>(
+    app: AppHandle<R>,
+    selection: BulkSelection,
+    action: BulkAction,
+) -> Result<BulkJob, String> {
+    let meeting_ids = resolve(&app, selection)?;
+    if meeting_ids.is_empty() {
+        return Err("No meetings selected".to_string());
+    }
+    let job = BulkJob {
+        id: format!("bulk-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")),
+        action,
+        state: JobState::Queued,
+        total: meeting_ids.len(),
+        done: 0,
+        failures: Vec::new(),
+        meeting_ids,
+        cancel_requested: false,
+    };
+    JOBS.lock()
+        .map_err(|e| format!("Failed to lock bulk jobs: {}", e))?
+        .insert(job.id.clone(), job.clone());
+    info!("Queued bulk operation {} over {} meetings", job.id, job.total);
+    let _ = app.emit(PROGRESS_EVENT, &job);
+    tauri::async_runtime::spawn(run_job(app, job.id.clone()));
+    Ok(job)
+}
+
+/// Stop a bulk operation before its next meeting.
+#[command]
+pub fn cancel_bulk_operation(job_id: String) -> Result<(), String> {
+    let mut jobs = JOBS.lock().map_err(|e| format!("Failed to lock bulk jobs: {}", e))?;
+    let job = jobs
+        .get_mut(&job_id)
+        .ok_or_else(|| format!("No bulk operation {}", job_id))?;
+    job.cancel_requested = true;
+    Ok(())
+}
+
+#[command]
+pub fn list_bulk_operations() -> Vec<BulkJob> {
+    let mut jobs: Vec<BulkJob> = JOBS
+        .lock()
+        .map(|jobs| jobs.values().cloned().collect())
+        .unwrap_or_default();
+    jobs.sort_by(|a, b| b.id.cmp(&a.id));
+    jobs
+}
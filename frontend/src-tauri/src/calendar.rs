@@ -0,0 +1,783 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::session;
+use crate::storage;
+use crate::{is_recording, settings, transcript_stream};
+
+/// Tag given to every session recorded for a calendar event.
+pub const CALENDAR_TAG: &str = "calendar";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+/// Events further ahead than this aren't listed.
+const LOOKAHEAD_DAYS: i64 = 7;
+/// Periods of a recurrence rule looked through for occurrences, enough for a
+/// daily meeting that started decades ago.
+const MAX_RECURRENCE_PERIODS: i64 = 50_000;
+
+/// What happens when an armed event starts while another is being recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Keep the current session and run it until the later event ends.
+    #[default]
+    Extend,
+    /// Stop the current session and start one for the new event.
+    Split,
+}
+
+/// Record selected events from ICS calendar feeds: capture starts shortly
+/// before each armed event and stops when it ends, or earlier once nothing
+/// has been said for a while.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarSettings {
+    pub enabled: bool,
+    /// ICS feed URLs (`https://` or `webcal://`), e.g. a calendar's secret address.
+    pub feeds: Vec<String>,
+    /// Seconds before an event's start that capture begins.
+    pub lead_secs: u64,
+    /// Stop early after this many seconds without any transcribed speech.
+    pub silence_stop_secs: Option<u64>,
+    pub overlap: OverlapPolicy,
+    /// Ids of the events to record.
+    pub armed: BTreeSet<String>,
+}
+
+impl Default for CalendarSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            feeds: Vec::new(),
+            lead_secs: 60,
+            silence_stop_secs: Some(300),
+            overlap: OverlapPolicy::Extend,
+            armed: BTreeSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarEvent {
+    /// The event's UID and start time; stable across feed refreshes and
+    /// distinct for each occurrence of a repeated event.
+    pub id: String,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub armed: bool,
+    /// Display names of the organizer and attendees, where the feed has them.
+    pub attendees: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledRecording {
+    pub session_id: String,
+    /// Events the session covers, the first of which started it.
+    pub event_ids: Vec<String>,
+    pub stops_at: DateTime<Utc>,
+    #[serde(skip)]
+    started: Instant,
+}
+
+static EVENTS: Lazy<Mutex<Vec<CalendarEvent>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static SCHEDULED: Lazy<Mutex<Option<ScheduledRecording>>> = Lazy::new(|| Mutex::new(None));
+/// Events whose start has been handled, so each is acted on once.
+static HANDLED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static LAST_HEARD: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Undo RFC 5545 line folding.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end_matches('\r').to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// The display name (`CN`) of an `ATTENDEE`/`ORGANIZER`, unless it's just the
+/// address.
+fn common_name(params: &str) -> Option<String> {
+    let name = params
+        .split(';')
+        .find_map(|param| param.get(..3).filter(|key| key.eq_ignore_ascii_case("CN=")).map(|_| &param[3..]))?;
+    let name = unescape(name.trim().trim_matches('"')).trim().to_string();
+    (!name.is_empty() && !name.contains('@')).then_some(name)
+}
+
+/// Where a time in the feed is: UTC (`Z`), the IANA zone of its `TZID`, or
+/// else the computer's own zone.
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Utc,
+    Named(Tz),
+    Local,
+}
+
+impl Zone {
+    fn resolve(self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Utc => Some(naive.and_utc()),
+            Zone::Named(tz) => tz.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+            Zone::Local => Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// The IANA zone of a `TZID`, also when it has a vendor prefix such as
+/// `/mozilla.org/20050126_1/America/New_York`. Windows zone names, as in
+/// some Outlook feeds, aren't known.
+fn named_zone(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim().trim_matches('"');
+    std::iter::once(tzid)
+        .chain(tzid.match_indices('/').map(|(index, _)| &tzid[index + 1..]))
+        .find_map(|name| name.parse::<Tz>().ok())
+}
+
+fn param<'a>(params: &'a str, key: &str) -> Option<&'a str> {
+    params.split(';').find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.eq_ignore_ascii_case(key).then_some(value)
+    })
+}
+
+/// A `DTSTART`/`DTEND`/`EXDATE` value as written, and the zone it's in.
+/// All-day dates are not recordable and yield `None`.
+fn parse_local(params: &str, value: &str) -> Option<(NaiveDateTime, Zone)> {
+    if param(params, "VALUE").is_some_and(|kind| kind.eq_ignore_ascii_case("DATE")) {
+        return None;
+    }
+    let naive = NaiveDateTime::parse_from_str(value.get(..15)?, "%Y%m%dT%H%M%S").ok()?;
+    let zone = match param(params, "TZID") {
+        _ if value.ends_with('Z') => Zone::Utc,
+        Some(tzid) => named_zone(tzid).map(Zone::Named).unwrap_or_else(|| {
+            debug!("Unknown time zone {}, reading the time as local", tzid);
+            Zone::Local
+        }),
+        None => Zone::Local,
+    };
+    Some((naive, zone))
+}
+
+fn parse_time(params: &str, value: &str) -> Option<DateTime<Utc>> {
+    let (naive, zone) = parse_local(params, value)?;
+    zone.resolve(naive)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The parts of an `RRULE` that are expanded. Rarer parts (`BYSETPOS`,
+/// `BYWEEKNO`, `BYHOUR`, ...) are ignored, as are sub-daily frequencies.
+#[derive(Debug)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: i64,
+    count: Option<usize>,
+    until: Option<DateTime<Utc>>,
+    /// Weekdays, with their ordinal within the month for monthly and yearly
+    /// rules, e.g. `2TU` or `-1FR`.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, negative ones counting from its end.
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+}
+
+fn parse_weekday(entry: &str) -> Option<(Option<i32>, Weekday)> {
+    let entry = entry.trim();
+    let (ordinal, day) = entry.split_at(entry.len().checked_sub(2)?);
+    let day = match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    match ordinal {
+        "" => Some((None, day)),
+        ordinal => Some((Some(ordinal.parse().ok().filter(|n| *n != 0)?), day)),
+    }
+}
+
+/// An `UNTIL` date or time. A date includes the whole day.
+fn parse_until(value: &str, zone: Zone) -> Option<DateTime<Utc>> {
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return zone.resolve(date.and_hms_opt(23, 59, 59)?);
+    }
+    let naive = NaiveDateTime::parse_from_str(value.get(..15)?, "%Y%m%dT%H%M%S").ok()?;
+    if value.ends_with('Z') {
+        Some(naive.and_utc())
+    } else {
+        zone.resolve(naive)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|next| next.pred_opt())
+        .map_or(28, |last| last.day())
+}
+
+impl Recurrence {
+    fn parse(rule: &str, zone: Zone) -> Option<Self> {
+        let mut frequency = None;
+        let mut recurrence = Recurrence {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+        for part in rule.split(';') {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        "YEARLY" => Some(Frequency::Yearly),
+                        _ => None,
+                    }
+                }
+                "INTERVAL" => recurrence.interval = value.trim().parse().ok().filter(|n| *n > 0).unwrap_or(1),
+                "COUNT" => recurrence.count = value.trim().parse().ok(),
+                "UNTIL" => recurrence.until = parse_until(value.trim(), zone),
+                "BYDAY" => recurrence.by_day = value.split(',').filter_map(parse_weekday).collect(),
+                "BYMONTHDAY" => {
+                    recurrence.by_month_day = value.split(',').filter_map(|day| day.trim().parse().ok()).collect()
+                }
+                "BYMONTH" => recurrence.by_month = value.split(',').filter_map(|month| month.trim().parse().ok()).collect(),
+                _ => {}
+            }
+        }
+        recurrence.frequency = frequency?;
+        Some(recurrence)
+    }
+
+    /// Days of `month` the rule picks, or `default_day` when it names none.
+    fn days_of_month(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let length = days_in_month(year, month);
+        if !self.by_month_day.is_empty() {
+            return self
+                .by_month_day
+                .iter()
+                .filter_map(|&day| {
+                    let day = if day < 0 { length as i32 + day + 1 } else { day };
+                    NaiveDate::from_ymd_opt(year, month, u32::try_from(day).ok()?)
+                })
+                .collect();
+        }
+        if self.by_day.is_empty() {
+            return NaiveDate::from_ymd_opt(year, month, default_day).into_iter().collect();
+        }
+        let mut dates = Vec::new();
+        for &(ordinal, weekday) in &self.by_day {
+            let matching: Vec<NaiveDate> = (1..=length)
+                .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+                .filter(|date| date.weekday() == weekday)
+                .collect();
+            match ordinal {
+                None => dates.extend(matching),
+                Some(n) if n > 0 => dates.extend(matching.get(n as usize - 1)),
+                Some(n) => dates.extend(
+                    matching
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .and_then(|index| matching.get(index)),
+                ),
+            }
+        }
+        dates
+    }
+
+    /// Dates the rule picks in the period `index` periods after the one
+    /// `first` falls in.
+    fn dates_in_period(&self, first: NaiveDate, index: i64) -> Vec<NaiveDate> {
+        let step = index * self.interval;
+        let mut dates = match self.frequency {
+            Frequency::Daily => {
+                let date = first + chrono::Duration::days(step);
+                let weekday_matches =
+                    self.by_day.is_empty() || self.by_day.iter().any(|(_, weekday)| *weekday == date.weekday());
+                weekday_matches.then_some(date).into_iter().collect()
+            }
+            Frequency::Weekly => {
+                let monday = first - chrono::Duration::days(first.weekday().num_days_from_monday() as i64)
+                    + chrono::Duration::weeks(step);
+                let weekdays: Vec<Weekday> = match self.by_day.is_empty() {
+                    true => vec![first.weekday()],
+                    false => self.by_day.iter().map(|(_, weekday)| *weekday).collect(),
+                };
+                weekdays
+                    .into_iter()
+                    .map(|weekday| monday + chrono::Duration::days(weekday.num_days_from_monday() as i64))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let months = first.year() as i64 * 12 + first.month0() as i64 + step;
+                let Ok(year) = i32::try_from(months.div_euclid(12)) else {
+                    return Vec::new();
+                };
+                self.days_of_month(year, months.rem_euclid(12) as u32 + 1, first.day())
+            }
+            Frequency::Yearly => {
+                let Ok(year) = i32::try_from(first.year() as i64 + step) else {
+                    return Vec::new();
+                };
+                let months = match self.by_month.is_empty() {
+                    true => vec![first.month()],
+                    false => self.by_month.clone(),
+                };
+                months
+                    .into_iter()
+                    .filter(|month| (1..=12).contains(month))
+                    .flat_map(|month| self.days_of_month(year, month, first.day()))
+                    .collect()
+            }
+        };
+        dates.retain(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()));
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    /// Starts of the event from `start` up to `horizon`, as wall-clock times
+    /// in its zone so they keep their time of day across DST changes.
+    fn expand(&self, start: NaiveDateTime, zone: Zone, horizon: DateTime<Utc>) -> Vec<NaiveDateTime> {
+        let mut starts = Vec::new();
+        for index in 0..MAX_RECURRENCE_PERIODS {
+            for date in self.dates_in_period(start.date(), index) {
+                let occurrence = date.and_time(start.time());
+                if occurrence < start {
+                    continue;
+                }
+                // Skipped, like a time that falls in a DST gap
+                let Some(at) = zone.resolve(occurrence) else {
+                    continue;
+                };
+                if at >= horizon || self.until.is_some_and(|until| at > until) {
+                    return starts;
+                }
+                starts.push(occurrence);
+                if self.count.is_some_and(|count| starts.len() >= count) {
+                    return starts;
+                }
+            }
+        }
+        starts
+    }
+}
+
+/// One `VEVENT` as read from a feed.
+#[derive(Default)]
+struct RawEvent {
+    fields: BTreeMap<String, (String, String)>,
+    attendees: Vec<String>,
+    /// Occurrences of a recurring event that were cancelled.
+    exdates: Vec<DateTime<Utc>>,
+}
+
+impl RawEvent {
+    fn uid(&self) -> String {
+        self.fields.get("UID").map(|(_, uid)| uid.clone()).unwrap_or_default()
+    }
+
+    fn time(&self, name: &str) -> Option<(NaiveDateTime, Zone)> {
+        self.fields.get(name).and_then(|(params, value)| parse_local(params, value))
+    }
+
+    /// The event's occurrences that start before `horizon`. Occurrences in
+    /// `overrides` are left out, since they're listed as events of their own.
+    fn occurrences(self, overrides: &HashSet<(String, DateTime<Utc>)>, horizon: DateTime<Utc>) -> Vec<CalendarEvent> {
+        let (Some((start, zone)), Some((end, end_zone))) = (self.time("DTSTART"), self.time("DTEND")) else {
+            return Vec::new();
+        };
+        let (Some(first), Some(first_end)) = (zone.resolve(start), end_zone.resolve(end)) else {
+            return Vec::new();
+        };
+        if self.fields.get("STATUS").is_some_and(|(_, status)| status.eq_ignore_ascii_case("CANCELLED")) {
+            return Vec::new();
+        }
+        let uid = self.uid();
+        let title = self
+            .fields
+            .get("SUMMARY")
+            .map(|(_, summary)| unescape(summary))
+            .unwrap_or_else(|| "Calendar event".to_string());
+        let length = first_end - first;
+        let recurrence = match self.fields.contains_key("RECURRENCE-ID") {
+            true => None,
+            false => self.fields.get("RRULE").and_then(|(_, rule)| Recurrence::parse(rule, zone)),
+        };
+        let starts = match &recurrence {
+            Some(recurrence) => recurrence.expand(start, zone, horizon),
+            None => vec![start],
+        };
+        starts
+            .into_iter()
+            .filter_map(|start| zone.resolve(start))
+            .filter(|start| !self.exdates.contains(start))
+            .filter(|start| recurrence.is_none() || !overrides.contains(&(uid.clone(), *start)))
+            .map(|start| CalendarEvent {
+                id: format!("{}@{}", uid, start.timestamp()),
+                title: title.clone(),
+                start,
+                end: start + length,
+                armed: false,
+                attendees: self.attendees.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Timed events in an ICS document that start before `horizon`, with
+/// recurring events expanded into their occurrences.
+fn parse_ics(ics: &str, horizon: DateTime<Utc>) -> Vec<CalendarEvent> {
+    let mut raw = Vec::new();
+    let mut current: Option<RawEvent> = None;
+    for line in unfold(ics) {
+        match line.as_str() {
+            "BEGIN:VEVENT" => current = Some(RawEvent::default()),
+            "END:VEVENT" => raw.extend(current.take()),
+            _ => {
+                let Some(event) = current.as_mut() else {
+                    continue;
+                };
+                let Some((key, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let (name, params) = key.split_once(';').unwrap_or((key, ""));
+                // Repeated once per person, and the name's case matters
+                if name.eq_ignore_ascii_case("ATTENDEE") || name.eq_ignore_ascii_case("ORGANIZER") {
+                    if let Some(person) = common_name(params).filter(|person| !event.attendees.contains(person)) {
+                        event.attendees.push(person);
+                    }
+                    continue;
+                }
+                // Repeated, and each may list several times
+                if name.eq_ignore_ascii_case("EXDATE") {
+                    event.exdates.extend(value.split(',').filter_map(|time| parse_time(params, time.trim())));
+                    continue;
+                }
+                event.fields.insert(name.to_uppercase(), (params.to_string(), value.to_string()));
+            }
+        }
+    }
+
+    // Occurrences moved or changed on their own replace the generated ones
+    let overrides: HashSet<(String, DateTime<Utc>)> = raw
+        .iter()
+        .filter_map(|event| {
+            let (params, value) = event.fields.get("RECURRENCE-ID")?;
+            Some((event.uid(), parse_time(params, value)?))
+        })
+        .collect();
+    raw.into_iter()
+        .flat_map(|event| event.occurrences(&overrides, horizon))
+        .collect()
+}
+
+async fn fetch(feed: &str, horizon: DateTime<Utc>) -> Result<Vec<CalendarEvent>, String> {
+    let url = match feed.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => feed.to_string(),
+    };
+    let body = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch calendar: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read calendar: {}", e))?;
+    Ok(parse_ics(&body, horizon))
+}
+
+/// Upcoming events from every feed. The cached list is kept when no feed
+/// could be read.
+async fn refresh() -> Result<Vec<CalendarEvent>, String> {
+    let config = settings::get().calendar;
+    let now = Utc::now();
+    let horizon = now + chrono::Duration::days(LOOKAHEAD_DAYS);
+    let mut events = Vec::new();
+    let mut last_error = None;
+    for feed in &config.feeds {
+        match fetch(feed, horizon).await {
+            Ok(found) => events.extend(found.into_iter().filter(|e| e.end > now && e.start < horizon)),
+            Err(e) => {
+                warn!("{}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+    // Keep the last good list when no feed could be read
+    if let (true, Some(e)) = (events.is_empty(), last_error) {
+        return Err(e);
+    }
+    events.sort_by_key(|event| event.start);
+    events.dedup_by(|a, b| a.id == b.id);
+    if let Ok(mut cached) = EVENTS.lock() {
+        *cached = events.clone();
+    }
+    Ok(with_armed(events, &config))
+}
+
+fn with_armed(mut events: Vec<CalendarEvent>, config: &CalendarSettings) -> Vec<CalendarEvent> {
+    for event in &mut events {
+        event.armed = config.armed.contains(&event.id);
+    }
+    events
+}
+
+async fn begin<R: Runtime>(app: &AppHandle<R>, event: &CalendarEvent) -> Result<(), String> {
+    let session = session::start_session(app.clone(), Some(event.title.clone()), None, None, None).await?;
+    storage::update_tags(app, &session.id, &[CALENDAR_TAG.to_string()], &[])?;
+    let metadata = BTreeMap::from([
+        ("calendar_event".to_string(), event.id.clone()),
+        ("scheduled_start".to_string(), event.start.to_rfc3339()),
+        ("scheduled_end".to_string(), event.end.to_rfc3339()),
+    ]);
+    storage::merge_metadata(app, &session.id, metadata)?;
+    info!("Recording calendar event '{}' as session {}", event.title, session.id);
+    if let Ok(mut scheduled) = SCHEDULED.lock() {
+        *scheduled = Some(ScheduledRecording {
+            session_id: session.id,
+            event_ids: vec![event.id.clone()],
+            stops_at: event.end,
+            started: Instant::now(),
+        });
+    }
+    Ok(())
+}
+
+async fn finish<R: Runtime>(app: &AppHandle<R>, recording: ScheduledRecording, reason: &str) {
+    info!("Stopping scheduled session {} ({})", recording.session_id, reason);
+    if let Err(e) = session::stop_session(app.clone(), recording.session_id).await {
+        warn!("Failed to stop scheduled session: {}", e);
+    }
+    if let Ok(mut scheduled) = SCHEDULED.lock() {
+        *scheduled = None;
+    }
+}
+
+async fn tick<R: Runtime>(app: &AppHandle<R>, config: &CalendarSettings) {
+    let now = Utc::now();
+    let scheduled = SCHEDULED.lock().ok().and_then(|scheduled| scheduled.clone());
+    if let Some(recording) = scheduled {
+        if !is_recording() {
+            // Stopped from the UI or a hotkey
+            if let Ok(mut scheduled) = SCHEDULED.lock() {
+                *scheduled = None;
+            }
+        } else if now >= recording.stops_at {
+            finish(app, recording, "event ended").await;
+        } else if let Some(limit) = config.silence_stop_secs.map(Duration::from_secs) {
+            let last_heard = LAST_HEARD.lock().ok().and_then(|heard| *heard);
+            let quiet_since = last_heard.map_or(recording.started, |heard| heard.max(recording.started));
+            if quiet_since.elapsed() >= limit {
+                finish(app, recording, "no speech").await;
+            }
+        }
+    }
+
+    let lead = chrono::Duration::seconds(config.lead_secs as i64);
+    let due: Vec<CalendarEvent> = EVENTS
+        .lock()
+        .map(|events| {
+            events
+                .iter()
+                .filter(|event| config.armed.contains(&event.id) && event.start - lead <= now && now < event.end)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    for event in due {
+        let first_time = HANDLED.lock().map(|mut handled| handled.insert(event.id.clone())).unwrap_or(false);
+        if !first_time {
+            continue;
+        }
+        let current = SCHEDULED.lock().ok().and_then(|scheduled| scheduled.clone());
+        match (current, config.overlap) {
+            (None, _) if is_recording() => {
+                info!("Already recording, not starting a session for '{}'", event.title);
+            }
+            (None, _) => {
+                if let Err(e) = begin(app, &event).await {
+                    error!("Failed to start scheduled recording: {}", e);
+                    retry_later(&event);
+                }
+            }
+            (Some(_), OverlapPolicy::Extend) => {
+                if let Ok(mut scheduled) = SCHEDULED.lock() {
+                    if let Some(recording) = scheduled.as_mut() {
+                        info!("'{}' overlaps session {}, extending it", event.title, recording.session_id);
+                        recording.event_ids.push(event.id.clone());
+                        recording.stops_at = recording.stops_at.max(event.end);
+                    }
+                }
+            }
+            (Some(recording), OverlapPolicy::Split) => {
+                finish(app, recording, "next event starting").await;
+                if let Err(e) = begin(app, &event).await {
+                    error!("Failed to start scheduled recording: {}", e);
+                    retry_later(&event);
+                }
+            }
+        }
+    }
+}
+
+/// Let the next check try `event` again, e.g. once a busy device is free.
+fn retry_later(event: &CalendarEvent) {
+    if let Ok(mut handled) = HANDLED.lock() {
+        handled.remove(&event.id);
+    }
+}
+
+/// Refresh the feeds periodically and record armed events.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut updates = transcript_stream::subscribe();
+        loop {
+            match updates.recv().await {
+                Ok(_) => {
+                    if let Ok(mut heard) = LAST_HEARD.lock() {
+                        *heard = Some(Instant::now());
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        let mut last_refresh: Option<Instant> = None;
+        loop {
+            interval.tick().await;
+            let config = settings::get().calendar;
+            if !config.enabled {
+                continue;
+            }
+            if last_refresh.map_or(true, |at| at.elapsed() >= REFRESH_INTERVAL) {
+                last_refresh = Some(Instant::now());
+                if let Err(e) = refresh().await {
+                    warn!("{}", e);
+                }
+            }
+            tick(&app, &config).await;
+        }
+    });
+}
+
+/// The calendar event under way at `at`, the most recently started when
+/// several overlap.
+pub fn event_at(at: DateTime<Utc>) -> Option<CalendarEvent> {
+    let events = EVENTS.lock().ok()?;
+    events
+        .iter()
+        .filter(|event| event.start <= at && at < event.end)
+        .max_by_key(|event| event.start)
+        .cloned()
+}
+
+/// Upcoming events as of the last refresh.
+#[command]
+pub fn list_calendar_events() -> Vec<CalendarEvent> {
+    let events = EVENTS.lock().map(|events| events.clone()).unwrap_or_default();
+    with_armed(events, &settings::get().calendar)
+}
+
+#[command]
+pub async fn refresh_calendar() -> Result<Vec<CalendarEvent>, String> {
+    refresh().await
+}
+
+/// Opt an event in to, or out of, scheduled recording.
+#[command]
+pub fn arm_calendar_event(event_id: String, armed: bool) -> Result<CalendarSettings, String> {
+    let known = EVENTS
+        .lock()
+        .map(|events| events.iter().any(|event| event.id == event_id))
+        .unwrap_or(false);
+    if armed && !known {
+        return Err("Unknown calendar event; refresh the calendar and try again".to_string());
+    }
+    let now = Utc::now().timestamp();
+    settings::update(|s| {
+        if armed {
+            s.calendar.armed.insert(event_id);
+        } else {
+            s.calendar.armed.remove(&event_id);
+        }
+        // Forget events that started over a day ago
+        s.calendar.armed.retain(|id| {
+            id.rsplit_once('@')
+                .and_then(|(_, start)| start.parse::<i64>().ok())
+                .map_or(false, |start| start > now - 86_400)
+        });
+    })
+    .map(|s| s.calendar)
+}
+
+#[command]
+pub fn get_scheduled_recording() -> Option<ScheduledRecording> {
+    SCHEDULED.lock().ok().and_then(|scheduled| scheduled.clone())
+}
+
+#[command]
+pub fn get_calendar_settings() -> CalendarSettings {
+    settings::get().calendar
+}
+
+#[command]
+pub fn set_calendar_settings(mut calendar_settings: CalendarSettings) -> Result<CalendarSettings, String> {
+    calendar_settings.feeds = calendar_settings
+        .feeds
+        .iter()
+        .map(|feed| feed.trim().to_string())
+        .filter(|feed| !feed.is_empty())
+        .collect();
+    if let Some(feed) = calendar_settings
+        .feeds
+        .iter()
+        .find(|feed| !["https://", "http://", "webcal://"].iter().any(|scheme| feed.starts_with(scheme)))
+    {
+        return Err(format!("'{}' is not a calendar feed URL", feed));
+    }
+    settings::update(|s| s.calendar = calendar_settings).map(|s| s.calendar)
+}
@@ -0,0 +1,553 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast;
+
+use crate::audio::alignment::{align, Alignment};
+use crate::audio::decode::decode_audio_file;
+use crate::audio::metering::AudioLevel;
+use crate::audio::{bluetooth, default_input_device, default_output_device, playback, AudioDevice, AudioStream};
+use crate::transcription::{self, AudioInput};
+use crate::{is_recording, resample_audio, settings, WHISPER_SAMPLE_RATE};
+
+pub const PROGRESS_EVENT: &str = "calibration-progress";
+pub const TEST_PHRASE: &str = "Seven bright yellow birds sang quietly while the quick brown fox jumped over the lazy dog";
+/// Silence captured before playback, to measure the room's noise floor.
+const LEAD_IN_MS: u64 = 1000;
+/// Capture continues this long after playback for slow output paths.
+const TAIL_MS: u64 = 1500;
+/// Below this alignment confidence the test signal wasn't heard on a device.
+const MIN_CONFIDENCE: f32 = 0.3;
+/// Speaker output reaching the mic above this level will be transcribed twice.
+const BLEED_WARNING_DB: f32 = -20.0;
+const WER_WARNING: f32 = 0.3;
+const LATENCY_WARNING_MS: i64 = 250;
+const CHIRP_SECS: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationStep {
+    Prepare,
+    Capture,
+    Analyze,
+    Transcribe,
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationProgress {
+    pub step: CalibrationStep,
+    /// 0.0 to 1.0 within the step.
+    pub progress: f32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationStatus {
+    pub step: CalibrationStep,
+    pub mic_device: String,
+    pub system_device: String,
+    /// The spoken phrase, or `None` when no speech synthesizer was found and a
+    /// tone sweep is played instead (accuracy is then not measured).
+    pub phrase: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Settings the calibration suggests; only the fields that are `Some` change.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecommendedSettings {
+    pub vad_margin_db: Option<f32>,
+    pub spectral_subtraction: Option<bool>,
+    pub prefer_builtin_mic_on_bluetooth: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationReport {
+    pub mic_device: String,
+    pub system_device: String,
+    /// From the start of playback until the phrase reaches the microphone.
+    pub latency_ms: Option<i64>,
+    /// From the start of playback until the phrase appears in the system capture.
+    pub loopback_latency_ms: Option<i64>,
+    /// Level of the phrase on the microphone relative to the system capture;
+    /// `None` when the microphone didn't hear the speakers.
+    pub bleed_db: Option<f32>,
+    pub noise_floor_db: f32,
+    /// Signal-to-noise ratio of the phrase on the microphone.
+    pub snr_db: Option<f32>,
+    pub engine: String,
+    /// Time the engine took to transcribe the system capture.
+    pub transcription_ms: Option<u64>,
+    pub system_transcript: Option<String>,
+    pub system_word_error_rate: Option<f32>,
+    pub mic_transcript: Option<String>,
+    pub mic_word_error_rate: Option<f32>,
+    pub recommended: RecommendedSettings,
+    /// Advice that no setting can apply, e.g. to use headphones.
+    pub notes: Vec<String>,
+}
+
+struct Session {
+    mic_device: Arc<AudioDevice>,
+    system_device: Arc<AudioDevice>,
+    phrase: Option<String>,
+    /// The test signal at the whisper sample rate.
+    reference: Vec<f32>,
+    capture: Option<Capture>,
+    report: Option<CalibrationReport>,
+}
+
+/// Both devices at the whisper sample rate, from the same starting instant.
+struct Capture {
+    mic: Vec<f32>,
+    system: Vec<f32>,
+    playback_offset_ms: i64,
+}
+
+static SESSION: Lazy<Mutex<Option<Session>>> = Lazy::new(|| Mutex::new(None));
+
+fn emit_progress<R: Runtime>(app: &AppHandle<R>, step: CalibrationStep, progress: f32, message: &str) {
+    let event = CalibrationProgress {
+        step,
+        progress,
+        message: message.to_string(),
+    };
+    if let Err(e) = app.emit(PROGRESS_EVENT, event) {
+        error!("Failed to emit calibration progress: {}", e);
+    }
+}
+
+fn take_session() -> Result<Session, String> {
+    SESSION
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| "No calibration in progress; start one first".to_string())
+}
+
+fn put_session(session: Session) {
+    if let Ok(mut slot) = SESSION.lock() {
+        *slot = Some(session);
+    }
+}
+
+fn status(session: &Session, step: CalibrationStep) -> CalibrationStatus {
+    CalibrationStatus {
+        step,
+        mic_device: session.mic_device.to_string(),
+        system_device: session.system_device.to_string(),
+        phrase: session.phrase.clone(),
+        duration_ms: session.reference.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn tts_commands(phrase: &str, path: &Path) -> Vec<Command> {
+    let mut say = Command::new("say");
+    say.arg("-o").arg(path).arg("--data-format=LEI16@22050").arg(phrase);
+    vec![say]
+}
+
+#[cfg(target_os = "windows")]
+fn tts_commands(phrase: &str, path: &Path) -> Vec<Command> {
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+         $s.SetOutputToWaveFile('{}'); $s.Speak('{}'); $s.Dispose()",
+        path.display(),
+        phrase.replace('\'', "''")
+    );
+    let mut powershell = Command::new("powershell");
+    powershell.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    vec![powershell]
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn tts_commands(phrase: &str, path: &Path) -> Vec<Command> {
+    ["espeak-ng", "espeak"]
+        .iter()
+        .map(|program| {
+            let mut command = Command::new(program);
+            command.arg("-w").arg(path).arg(phrase);
+            command
+        })
+        .collect()
+}
+
+/// Speak `phrase` with the platform's speech synthesizer.
+fn synthesize(phrase: &str) -> Option<Vec<f32>> {
+    let path = std::env::temp_dir().join(format!("meetingly-calibration-{}.wav", std::process::id()));
+    let rendered = tts_commands(phrase, &path).into_iter().any(|mut command| {
+        matches!(command.output(), Ok(output) if output.status.success())
+    });
+    let decoded = rendered.then(|| decode_audio_file(&path));
+    let _ = std::fs::remove_file(&path);
+    match decoded {
+        Some(Ok((samples, rate))) if !samples.is_empty() => Some(resample_audio(&samples, rate, WHISPER_SAMPLE_RATE)),
+        Some(Err(e)) => {
+            warn!("Failed to read synthesized phrase: {}", e);
+            None
+        }
+        _ => None,
+    }
+}
+
+/// A logarithmic sweep across the speech band, for when no synthesizer exists.
+fn chirp() -> Vec<f32> {
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    let (start, end) = (300.0f32, 3400.0f32);
+    let k = (end / start).ln() / CHIRP_SECS;
+    (0..(CHIRP_SECS * rate) as usize)
+        .map(|i| {
+            let t = i as f32 / rate;
+            let phase = 2.0 * std::f32::consts::PI * start * ((k * t).exp() - 1.0) / k;
+            0.5 * phase.sin()
+        })
+        .collect()
+}
+
+/// Everything a capture stream sends until `deadline`.
+pub(crate) async fn collect(mut receiver: broadcast::Receiver<Vec<f32>>, deadline: tokio::time::Instant) -> Vec<f32> {
+    let mut samples = Vec::new();
+    while let Ok(chunk) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+        match chunk {
+            Ok(chunk) => samples.extend(chunk),
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(_) => break,
+        }
+    }
+    samples
+}
+
+/// Normalized words for accuracy scoring.
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Word error rate of `hypothesis` against `reference`: word-level edit
+/// distance over the reference length.
+pub fn word_error_rate(reference: &str, hypothesis: &str) -> f32 {
+    let reference = words(reference);
+    let hypothesis = words(hypothesis);
+    if reference.is_empty() {
+        return if hypothesis.is_empty() { 0.0 } else { 1.0 };
+    }
+    let mut previous: Vec<usize> = (0..=hypothesis.len()).collect();
+    for (i, expected) in reference.iter().enumerate() {
+        let mut current = vec![i + 1; hypothesis.len() + 1];
+        for (j, heard) in hypothesis.iter().enumerate() {
+            let substitution = previous[j] + usize::from(expected != heard);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[hypothesis.len()] as f32 / reference.len() as f32
+}
+
+fn heard(alignment: Option<Alignment>) -> Option<Alignment> {
+    alignment.filter(|a| a.confidence >= MIN_CONFIDENCE)
+}
+
+fn window(samples: &[f32], start_ms: i64, len: usize) -> &[f32] {
+    let start = (start_ms.max(0) as usize * WHISPER_SAMPLE_RATE as usize / 1000).min(samples.len());
+    &samples[start..(start + len).min(samples.len())]
+}
+
+/// Transcribe `samples` with the active engine, returning the text and how long it took.
+async fn transcribe_text(samples: &[f32]) -> Option<(String, u64)> {
+    let input = AudioInput {
+        samples: samples.to_vec(),
+        sample_rate: WHISPER_SAMPLE_RATE,
+        language: None,
+        device: None,
+    };
+    let started = Instant::now();
+    match transcription::transcribe(&input).await {
+        Ok(transcript) => {
+            let text = transcript
+                .segments
+                .iter()
+                .map(|segment| segment.text.trim())
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some((text, started.elapsed().as_millis() as u64))
+        }
+        Err(e) => {
+            warn!("Calibration transcription failed: {}", e);
+            None
+        }
+    }
+}
+
+fn recommend(report: &mut CalibrationReport, mic_device: &AudioDevice) {
+    let config = settings::get();
+    if let Some(snr) = report.snr_db {
+        // Leave half the measured headroom between the noise floor and speech
+        let margin = (snr / 2.0).clamp(4.0, 15.0).round();
+        if (margin - config.noise.vad_margin_db).abs() >= 1.0 {
+            report.recommended.vad_margin_db = Some(margin);
+        }
+        if snr < 20.0 && !config.noise.spectral_subtraction {
+            report.recommended.spectral_subtraction = Some(true);
+        }
+    }
+    if bluetooth::is_bluetooth_device(mic_device) && !config.audio.prefer_builtin_mic_on_bluetooth {
+        report.recommended.prefer_builtin_mic_on_bluetooth = Some(true);
+    }
+
+    if let Some(bleed) = report.bleed_db.filter(|bleed| *bleed > BLEED_WARNING_DB) {
+        report.notes.push(format!(
+            "Speaker output reaches the microphone at {:.0} dB; use headphones to avoid transcribing remote speakers twice",
+            bleed
+        ));
+    }
+    if report.latency_ms.is_some_and(|latency| latency > LATENCY_WARNING_MS) {
+        report.notes.push("Audio output latency is high; Bluetooth speakers often add delay".to_string());
+    }
+    if report.system_word_error_rate.is_some_and(|wer| wer > WER_WARNING) {
+        report.notes.push(format!(
+            "{} misheard the test phrase; consider another transcription engine or model",
+            report.engine
+        ));
+    }
+    if report.system_word_error_rate.is_none() && report.system_transcript.is_none() {
+        report.notes.push("System audio capture didn't pick up the test phrase".to_string());
+    }
+}
+
+/// Step 1: pick the devices a recording would use and render the test signal.
+#[command]
+pub async fn start_calibration<R: Runtime>(app: AppHandle<R>) -> Result<CalibrationStatus, String> {
+    if is_recording() {
+        return Err("Stop recording before calibrating".to_string());
+    }
+    emit_progress(&app, CalibrationStep::Prepare, 0.0, "Preparing test phrase");
+
+    let mut mic_device = default_input_device().map_err(|e| e.to_string())?;
+    if settings::get().audio.prefer_builtin_mic_on_bluetooth && bluetooth::is_bluetooth_device(&mic_device) {
+        if let Some(builtin) = bluetooth::find_builtin_input() {
+            mic_device = builtin;
+        }
+    }
+    let system_device = default_output_device().map_err(|e| e.to_string())?;
+
+    let spoken = tokio::task::spawn_blocking(|| synthesize(TEST_PHRASE))
+        .await
+        .map_err(|e| e.to_string())?;
+    let (phrase, reference) = match spoken {
+        Some(samples) => (Some(TEST_PHRASE.to_string()), samples),
+        None => {
+            warn!("No speech synthesizer available, calibrating with a tone sweep");
+            (None, chirp())
+        }
+    };
+
+    let session = Session {
+        mic_device: Arc::new(mic_device),
+        system_device: Arc::new(system_device),
+        phrase,
+        reference,
+        capture: None,
+        report: None,
+    };
+    let status = status(&session, CalibrationStep::Prepare);
+    put_session(session);
+    emit_progress(&app, CalibrationStep::Prepare, 1.0, "Test phrase ready");
+    info!("Calibration started with {} and {}", status.mic_device, status.system_device);
+    Ok(status)
+}
+
+/// Step 2: play the test signal through the speakers while capturing both devices.
+#[command]
+pub async fn run_calibration_capture<R: Runtime>(app: AppHandle<R>) -> Result<CalibrationStatus, String> {
+    if is_recording() {
+        return Err("Stop recording before calibrating".to_string());
+    }
+    let mut session = take_session()?;
+    let result = capture(&app, &session).await;
+    match result {
+        Ok(capture) => {
+            session.capture = Some(capture);
+            session.report = None;
+            let status = status(&session, CalibrationStep::Capture);
+            put_session(session);
+            emit_progress(&app, CalibrationStep::Capture, 1.0, "Capture complete");
+            Ok(status)
+        }
+        Err(e) => {
+            put_session(session);
+            Err(e)
+        }
+    }
+}
+
+async fn capture<R: Runtime>(app: &AppHandle<R>, session: &Session) -> Result<Capture, String> {
+    emit_progress(app, CalibrationStep::Capture, 0.0, "Opening devices");
+    let is_running = Arc::new(AtomicBool::new(true));
+    let mic_stream = AudioStream::from_device(session.mic_device.clone(), is_running.clone())
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", session.mic_device, e))?;
+    let system_stream = match AudioStream::from_device(session.system_device.clone(), is_running.clone()).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            is_running.store(false, Ordering::SeqCst);
+            let _ = mic_stream.stop().await;
+            return Err(format!("Failed to open {}: {}", session.system_device, e));
+        }
+    };
+    let mic_rate = mic_stream.device_config.sample_rate().0;
+    let system_rate = system_stream.device_config.sample_rate().0;
+
+    let duration_ms = session.reference.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64;
+    let started = tokio::time::Instant::now();
+    let deadline = started + Duration::from_millis(LEAD_IN_MS + duration_ms + TAIL_MS);
+    let mic = tokio::spawn(collect(mic_stream.subscribe().await, deadline));
+    let system = tokio::spawn(collect(system_stream.subscribe().await, deadline));
+
+    emit_progress(app, CalibrationStep::Capture, 0.1, "Measuring background noise");
+    tokio::time::sleep(Duration::from_millis(LEAD_IN_MS)).await;
+    emit_progress(app, CalibrationStep::Capture, 0.3, "Playing test phrase");
+    let playback_offset_ms = started.elapsed().as_millis() as i64;
+    let reference = session.reference.clone();
+    let played = tokio::task::spawn_blocking(move || playback::play_blocking(&reference, WHISPER_SAMPLE_RATE)).await;
+
+    let mic = mic.await.unwrap_or_default();
+    let system = system.await.unwrap_or_default();
+    is_running.store(false, Ordering::SeqCst);
+    for stream in [&mic_stream, &system_stream] {
+        if let Err(e) = stream.stop().await {
+            error!("Error stopping calibration stream: {}", e);
+        }
+    }
+    match played {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(format!("Failed to play test phrase: {}", e)),
+        Err(e) => return Err(e.to_string()),
+    }
+
+    Ok(Capture {
+        mic: resample_audio(&mic, mic_rate, WHISPER_SAMPLE_RATE),
+        system: resample_audio(&system, system_rate, WHISPER_SAMPLE_RATE),
+        playback_offset_ms,
+    })
+}
+
+/// Step 3: measure latency, bleed and accuracy, and work out recommendations.
+#[command]
+pub async fn analyze_calibration<R: Runtime>(app: AppHandle<R>) -> Result<CalibrationReport, String> {
+    let session = take_session()?;
+    let capture = match session.capture.as_ref() {
+        Some(capture) => capture,
+        None => {
+            put_session(session);
+            return Err("Run the calibration capture first".to_string());
+        }
+    };
+
+    emit_progress(&app, CalibrationStep::Analyze, 0.0, "Measuring latency");
+    let reference = &session.reference;
+    let mic_alignment = heard(align(&capture.mic, reference, WHISPER_SAMPLE_RATE));
+    let system_alignment = heard(align(&capture.system, reference, WHISPER_SAMPLE_RATE));
+
+    // Where the phrase sits in each capture, assuming no delay when it wasn't heard
+    let mic_start = mic_alignment.map_or(capture.playback_offset_ms, |a| a.offset_ms);
+    let system_start = system_alignment.map_or(capture.playback_offset_ms, |a| a.offset_ms);
+    let mic_phrase = window(&capture.mic, mic_start, reference.len());
+    let system_phrase = window(&capture.system, system_start, reference.len());
+
+    let lead_in = capture.playback_offset_ms as usize * WHISPER_SAMPLE_RATE as usize / 1000;
+    let noise_floor_db = AudioLevel::from_samples("", window(&capture.mic, 0, lead_in)).rms_db;
+    let mic_level_db = AudioLevel::from_samples("", mic_phrase).rms_db;
+    let system_level_db = AudioLevel::from_samples("", system_phrase).rms_db;
+    emit_progress(&app, CalibrationStep::Analyze, 1.0, "Latency measured");
+
+    let mut report = CalibrationReport {
+        mic_device: session.mic_device.to_string(),
+        system_device: session.system_device.to_string(),
+        latency_ms: mic_alignment.map(|a| a.offset_ms - capture.playback_offset_ms),
+        loopback_latency_ms: system_alignment.map(|a| a.offset_ms - capture.playback_offset_ms),
+        bleed_db: mic_alignment.and(system_alignment).map(|_| mic_level_db - system_level_db),
+        noise_floor_db,
+        snr_db: mic_alignment.map(|_| mic_level_db - noise_floor_db),
+        engine: transcription::active().name().to_string(),
+        transcription_ms: None,
+        system_transcript: None,
+        system_word_error_rate: None,
+        mic_transcript: None,
+        mic_word_error_rate: None,
+        recommended: RecommendedSettings::default(),
+        notes: Vec::new(),
+    };
+
+    if let Some(phrase) = session.phrase.as_deref() {
+        emit_progress(&app, CalibrationStep::Transcribe, 0.0, "Transcribing system audio");
+        if system_alignment.is_some() {
+            if let Some((text, elapsed_ms)) = transcribe_text(system_phrase).await {
+                report.system_word_error_rate = Some(word_error_rate(phrase, &text));
+                report.system_transcript = Some(text);
+                report.transcription_ms = Some(elapsed_ms);
+            }
+        }
+        emit_progress(&app, CalibrationStep::Transcribe, 0.5, "Transcribing microphone");
+        if mic_alignment.is_some() {
+            if let Some((text, _)) = transcribe_text(mic_phrase).await {
+                report.mic_word_error_rate = Some(word_error_rate(phrase, &text));
+                report.mic_transcript = Some(text);
+            }
+        }
+        emit_progress(&app, CalibrationStep::Transcribe, 1.0, "Transcription scored");
+    }
+
+    recommend(&mut report, &session.mic_device);
+    let mut session = session;
+    session.report = Some(report.clone());
+    put_session(session);
+    emit_progress(&app, CalibrationStep::Done, 1.0, "Calibration complete");
+    info!(
+        "Calibration: latency {:?} ms, bleed {:?} dB, WER {:?}",
+        report.latency_ms, report.bleed_db, report.system_word_error_rate
+    );
+    Ok(report)
+}
+
+/// Step 4: apply the recommended settings and end the calibration.
+#[command]
+pub fn apply_calibration() -> Result<RecommendedSettings, String> {
+    let session = take_session()?;
+    let Some(report) = session.report else {
+        put_session(session);
+        return Err("Analyze the calibration before applying it".to_string());
+    };
+    let recommended = report.recommended;
+    let applied = recommended.clone();
+    settings::update(move |s| {
+        if let Some(margin) = recommended.vad_margin_db {
+            s.noise.vad_margin_db = margin;
+        }
+        if let Some(enabled) = recommended.spectral_subtraction {
+            s.noise.spectral_subtraction = enabled;
+        }
+        if let Some(prefer) = recommended.prefer_builtin_mic_on_bluetooth {
+            s.audio.prefer_builtin_mic_on_bluetooth = prefer;
+        }
+    })?;
+    info!("Applied calibration settings: {:?}", applied);
+    Ok(applied)
+}
+
+#[command]
+pub fn cancel_calibration() {
+    if let Ok(mut slot) = SESSION.lock() {
+        *slot = None;
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use log::{debug, error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, WebviewUrl, WebviewWindowBuilder};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::speakers::{self, SpeakerDisplay};
+use crate::{settings, transcript_stream};
+
+const CAPTION_WINDOW: &str = "captions";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// Appearance of the caption overlay; pushed to the window whenever it changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionSettings {
+    pub font_size: u32,
+    /// Opaque black background instead of the translucent default.
+    pub high_contrast: bool,
+    pub background_opacity: f32,
+    /// Caption lines kept on screen.
+    pub max_lines: usize,
+    pub show_speaker: bool,
+    pub position: CaptionPosition,
+}
+
+impl Default for CaptionSettings {
+    fn default() -> Self {
+        Self {
+            font_size: 28,
+            high_contrast: false,
+            background_opacity: 0.7,
+            max_lines: 2,
+            show_speaker: true,
+            position: CaptionPosition::Bottom,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptionLine {
+    pub speaker: String,
+    pub display: SpeakerDisplay,
+    pub text: String,
+}
+
+/// Keeps the last few lines of the live transcript for the overlay.
+#[derive(Debug, Default)]
+struct CaptionAssembler {
+    lines: VecDeque<CaptionLine>,
+}
+
+impl CaptionAssembler {
+    fn push(&mut self, line: CaptionLine, max_lines: usize) -> Vec<CaptionLine> {
+        self.lines.push_back(line);
+        while self.lines.len() > max_lines.max(1) {
+            self.lines.pop_front();
+        }
+        self.lines.iter().cloned().collect()
+    }
+}
+
+static FEED: Lazy<Mutex<Option<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+
+fn start_feed<R: Runtime>(app: &AppHandle<R>) {
+    let Ok(mut feed) = FEED.lock() else {
+        return;
+    };
+    if feed.is_some() {
+        return;
+    }
+
+    let app = app.clone();
+    let mut updates = transcript_stream::subscribe();
+    *feed = Some(tauri::async_runtime::spawn(async move {
+        let mut assembler = CaptionAssembler::default();
+        loop {
+            let update = match updates.recv().await {
+                Ok(update) => update,
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Caption feed lagged, skipped {} updates", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let max_lines = settings::get().captions.max_lines;
+            let speaker = transcript_stream::speaker_of(&update);
+            let lines = assembler.push(
+                CaptionLine {
+                    display: update
+                        .speaker_display
+                        .clone()
+                        .unwrap_or_else(|| speakers::display_for(&speaker)),
+                    speaker,
+                    text: update.text.clone(),
+                },
+                max_lines,
+            );
+            if let Err(e) = app.emit_to(CAPTION_WINDOW, "caption-update", lines) {
+                error!("Failed to send captions to overlay: {}", e);
+            }
+        }
+    }));
+}
+
+fn stop_feed() {
+    if let Some(task) = FEED.lock().ok().and_then(|mut feed| feed.take()) {
+        task.abort();
+    }
+}
+
+/// Show the always-on-top caption overlay. It is fed straight from the
+/// backend, so captions stay visible when the main window is hidden.
+#[command]
+pub fn open_caption_window<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(CAPTION_WINDOW) {
+        window.show().map_err(|e| e.to_string())?;
+        start_feed(&app);
+        return Ok(());
+    }
+
+    let config = settings::get().captions;
+    let height = (config.font_size as f64 * 1.6 * config.max_lines.max(1) as f64 + 32.0).max(80.0);
+    let window = WebviewWindowBuilder::new(&app, CAPTION_WINDOW, WebviewUrl::App("captions".into()))
+        .title("Live captions")
+        .inner_size(900.0, height)
+        .always_on_top(true)
+        .decorations(false)
+        .transparent(true)
+        .skip_taskbar(true)
+        .resizable(true)
+        .focused(false)
+        .build()
+        .map_err(|e| format!("Failed to open caption window: {}", e))?;
+
+    if let Ok(Some(monitor)) = window.current_monitor() {
+        let size = monitor.size();
+        let scale = monitor.scale_factor();
+        let width = size.width as f64 / scale;
+        let y = match config.position {
+            CaptionPosition::Top => 40.0,
+            CaptionPosition::Bottom => size.height as f64 / scale - height - 80.0,
+        };
+        let _ = window.set_position(tauri::LogicalPosition::new((width - 900.0) / 2.0, y));
+    }
+
+    let closing_app = app.clone();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Destroyed = event {
+            stop_feed();
+            debug!("Caption window closed");
+            let _ = closing_app.emit("caption-window-closed", ());
+        }
+    });
+
+    start_feed(&app);
+    info!("Caption window opened");
+    Ok(())
+}
+
+#[command]
+pub fn close_caption_window<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    stop_feed();
+    if let Some(window) = app.get_webview_window(CAPTION_WINDOW) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_caption_settings() -> CaptionSettings {
+    settings::get().captions
+}
+
+#[command]
+pub fn set_caption_settings<R: Runtime>(
+    app: AppHandle<R>,
+    caption_settings: CaptionSettings,
+) -> Result<CaptionSettings, String> {
+    if !(8..=120).contains(&caption_settings.font_size) {
+        return Err("Caption font size must be between 8 and 120".to_string());
+    }
+    let updated = settings::update(|s| s.captions = caption_settings)?.captions;
+    if let Err(e) = app.emit_to(CAPTION_WINDOW, "caption-settings", &updated) {
+        debug!("Caption window not open to receive settings: {}", e);
+    }
+    Ok(updated)
+}
@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::paragraphs::{paragraphs, Paragraph};
+use crate::recluster::read_derived;
+use crate::storage;
+use crate::transcript_stream::speaker_of;
+use crate::TranscriptUpdate;
+
+/// Chapters supplied from outside the backend (e.g. an LLM outline saved by
+/// the webview), used instead of detection when present.
+pub const CHAPTERS_FILE: &str = "chapters.json";
+/// Derived file caching each chapter with its representative quote.
+pub const PREVIEWS_FILE: &str = "chapter_previews.json";
+/// Detected chapters are at least this long.
+const MIN_CHAPTER_SECS: f64 = 180.0;
+/// Paragraphs compared on either side of a candidate boundary.
+const WINDOW: usize = 4;
+/// A boundary needs the vocabulary on either side to overlap less than this.
+const MAX_BOUNDARY_SIMILARITY: f64 = 0.15;
+/// Quotes are whole lines of a readable length.
+const MIN_QUOTE_WORDS: usize = 6;
+const MAX_QUOTE_WORDS: usize = 60;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "all", "also", "an", "and", "are", "as", "at", "be", "because", "but", "by", "can", "could", "do",
+    "does", "for", "from", "get", "go", "going", "got", "had", "has", "have", "he", "her", "here", "him", "his", "i",
+    "if", "in", "into", "is", "it", "its", "just", "know", "like", "me", "mean", "my", "no", "not", "of", "okay", "on",
+    "one", "or", "our", "out", "really", "right", "say", "see", "she", "so", "some", "that", "the", "their", "them",
+    "then", "there", "they", "think", "this", "to", "um", "uh", "up", "us", "was", "we", "well", "were", "what",
+    "when", "which", "who", "will", "with", "would", "yeah", "yes", "you", "your",
+];
+
+/// A chapter boundary as supplied in [`CHAPTERS_FILE`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterOutline {
+    #[serde(default)]
+    pub title: Option<String>,
+    pub start_secs: f64,
+}
+
+/// The line chosen to stand for a chapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    pub speaker: String,
+    pub text: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterPreview {
+    pub title: Option<String>,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub quote: Option<Quote>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedPreviews {
+    /// The meeting's `derived_at` when computed; see `timeline`.
+    derived_at: Option<String>,
+    chapters: Vec<ChapterPreview>,
+}
+
+fn bounds(timestamp: &str) -> (f64, f64) {
+    let (start, end) = match timestamp.split_once(" - ") {
+        Some((start, end)) => (start.trim().parse().unwrap_or(0.0), end.trim().parse().unwrap_or(0.0)),
+        None => (timestamp.trim().parse().unwrap_or(0.0), 0.0),
+    };
+    (start, f64::max(start, end))
+}
+
+fn content_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| word.chars().count() > 2 && !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(word, x)| b.get(word).map(|y| x * y)).sum();
+    let norm = |v: &HashMap<String, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+    let (na, nb) = (norm(a), norm(b));
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        dot / (na * nb)
+    }
+}
+
+fn bag(paragraphs: &[Paragraph]) -> HashMap<String, f64> {
+    let mut counts = HashMap::new();
+    for paragraph in paragraphs {
+        for word in content_words(&paragraph.text) {
+            *counts.entry(word).or_default() += 1.0;
+        }
+    }
+    counts
+}
+
+/// Split the meeting where the vocabulary of the paragraphs before and after
+/// a point stops overlapping, keeping every chapter `MIN_CHAPTER_SECS` long.
+pub fn detect(updates: &[TranscriptUpdate]) -> Vec<ChapterOutline> {
+    let turns = paragraphs(updates);
+    let Some(first) = turns.first() else {
+        return Vec::new();
+    };
+    let mut chapters = vec![ChapterOutline {
+        title: None,
+        start_secs: bounds(&first.timestamp).0,
+    }];
+    let mut candidates: Vec<(usize, f64)> = (WINDOW..turns.len().saturating_sub(WINDOW - 1))
+        .map(|at| (at, cosine(&bag(&turns[at - WINDOW..at]), &bag(&turns[at..(at + WINDOW).min(turns.len())]))))
+        .filter(|(_, similarity)| *similarity < MAX_BOUNDARY_SIMILARITY)
+        .collect();
+    // Strongest topic shifts first, then drop any too close to one already taken
+    candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let end = turns.last().map(|turn| bounds(&turn.timestamp).1).unwrap_or(0.0);
+    let mut starts: Vec<f64> = vec![chapters[0].start_secs];
+    for (at, _) in candidates {
+        let start = bounds(&turns[at].timestamp).0;
+        if end - start >= MIN_CHAPTER_SECS && starts.iter().all(|s| (start - s).abs() >= MIN_CHAPTER_SECS) {
+            starts.push(start);
+        }
+    }
+    starts.sort_by(f64::total_cmp);
+    chapters.extend(starts.into_iter().skip(1).map(|start_secs| ChapterOutline {
+        title: None,
+        start_secs,
+    }));
+    chapters
+}
+
+/// The line of `lines` carrying the most information: rare words weigh more,
+/// and a long line has to earn its length.
+fn representative(lines: &[&TranscriptUpdate], idf: &HashMap<String, f64>) -> Option<Quote> {
+    lines
+        .iter()
+        .filter_map(|update| {
+            let words = update.text.split_whitespace().count();
+            if !(MIN_QUOTE_WORDS..=MAX_QUOTE_WORDS).contains(&words) {
+                return None;
+            }
+            let distinct: HashSet<String> = content_words(&update.text).into_iter().collect();
+            let information: f64 = distinct.iter().filter_map(|word| idf.get(word)).sum();
+            Some((information / (words as f64).sqrt(), update))
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, update)| Quote {
+            speaker: speaker_of(update),
+            text: update.text.trim().to_string(),
+            timestamp: update.timestamp.clone(),
+        })
+}
+
+/// Each chapter of `outline` with the quote that best represents it.
+pub fn previews(updates: &[TranscriptUpdate], outline: &[ChapterOutline]) -> Vec<ChapterPreview> {
+    let lines: Vec<&TranscriptUpdate> = updates.iter().filter(|update| !update.text.trim().is_empty()).collect();
+    let mut document_frequency: HashMap<String, usize> = HashMap::new();
+    for update in &lines {
+        for word in content_words(&update.text).into_iter().collect::<HashSet<_>>() {
+            *document_frequency.entry(word).or_default() += 1;
+        }
+    }
+    let idf: HashMap<String, f64> = document_frequency
+        .into_iter()
+        .map(|(word, count)| (word, (lines.len() as f64 / count as f64).ln()))
+        .collect();
+    let meeting_end = lines.iter().map(|update| bounds(&update.timestamp).1).fold(0.0, f64::max);
+
+    let mut outline = outline.to_vec();
+    outline.sort_by(|a, b| a.start_secs.total_cmp(&b.start_secs));
+    outline
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            let end_secs = outline.get(index + 1).map(|next| next.start_secs).unwrap_or(meeting_end);
+            let within: Vec<&TranscriptUpdate> = lines
+                .iter()
+                .copied()
+                .filter(|update| {
+                    let start = bounds(&update.timestamp).0;
+                    start >= chapter.start_secs && (start < end_secs || index + 1 == outline.len())
+                })
+                .collect();
+            ChapterPreview {
+                title: chapter.title.clone(),
+                start_secs: chapter.start_secs,
+                end_secs: end_secs.max(chapter.start_secs),
+                quote: representative(&within, &idf),
+            }
+        })
+        .collect()
+}
+
+/// A stored meeting's chapters, each previewed by its most informative
+/// quote. Chapters come from `chapters.json` when the meeting has one and are
+/// detected from the transcript otherwise; the result is cached until the
+/// transcript is regenerated or `refresh` is set.
+#[command]
+pub fn get_chapter_previews<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    refresh: Option<bool>,
+) -> Result<Vec<ChapterPreview>, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    let manifest = storage::read_manifest(&dir)?;
+    if !refresh.unwrap_or(false) {
+        if let Some(cached) = read_derived::<CachedPreviews>(&dir, PREVIEWS_FILE) {
+            if cached.derived_at == manifest.derived_at {
+                return Ok(cached.chapters);
+            }
+        }
+    }
+
+    let transcript = storage::read_transcript(&dir)?;
+    let outline = read_derived::<Vec<ChapterOutline>>(&dir, CHAPTERS_FILE)
+        .filter(|outline| !outline.is_empty())
+        .unwrap_or_else(|| detect(&transcript));
+    let chapters = previews(&transcript, &outline);
+    let cache = CachedPreviews {
+        derived_at: manifest.derived_at,
+        chapters,
+    };
+    let content =
+        serde_json::to_string_pretty(&cache).map_err(|e| format!("Failed to serialize chapter previews: {}", e))?;
+    storage::save_derived(app, meeting_id.clone(), PREVIEWS_FILE.to_string(), content)?;
+    info!("Previewed {} chapters of meeting {}", cache.chapters.len(), meeting_id);
+    Ok(cache.chapters)
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use log::{info, warn};
+use reqwest::multipart::Form;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::{settings, WHISPER_ENDPOINT};
+
+/// How long startup keeps trying to reach a whisper server that isn't up yet.
+const APPLY_ATTEMPTS: u32 = 12;
+const APPLY_RETRY: Duration = Duration::from_secs(5);
+
+/// Where the local whisper server runs the model. By default it uses the
+/// platform's GPU backend (Metal on macOS, CUDA elsewhere) when it was built
+/// with one and falls back to the CPU otherwise.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComputeDeviceSettings {
+    /// Run on the CPU even when a GPU is available, e.g. to rule out driver
+    /// problems or leave the GPU to other work.
+    pub force_cpu: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeBackend {
+    Metal,
+    Cuda,
+    Cpu,
+}
+
+/// The GPU backend to expect on this platform.
+pub fn preferred_backend() -> ComputeBackend {
+    if cfg!(target_os = "macos") {
+        ComputeBackend::Metal
+    } else {
+        ComputeBackend::Cuda
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceResponse {
+    backend: ComputeBackend,
+    use_gpu: bool,
+    model: String,
+    system_info: String,
+}
+
+/// What the whisper server is actually running on.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComputeDevice {
+    pub backend: ComputeBackend,
+    /// The GPU backend expected on this platform.
+    pub preferred: ComputeBackend,
+    /// Whether the server was asked to use the GPU; `backend` is `cpu` despite
+    /// this when the server was built without GPU support.
+    pub gpu_requested: bool,
+    pub model: String,
+    pub system_info: String,
+}
+
+fn server_url() -> &'static str {
+    WHISPER_ENDPOINT.trim_end_matches("/stream")
+}
+
+async fn query(client: &reqwest::Client) -> Result<ComputeDevice, String> {
+    let device: DeviceResponse = client
+        .get(format!("{}/device", server_url()))
+        .send()
+        .await
+        .map_err(|e| format!("Whisper server unreachable: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid device response: {}", e))?;
+    Ok(ComputeDevice {
+        backend: device.backend,
+        preferred: preferred_backend(),
+        gpu_requested: device.use_gpu,
+        model: device.model,
+        system_info: device.system_info,
+    })
+}
+
+/// Reload the server's model on the GPU or the CPU, if it isn't already.
+async fn apply(client: &reqwest::Client, config: &ComputeDeviceSettings) -> Result<ComputeDevice, String> {
+    let current = query(client).await?;
+    if current.gpu_requested != config.force_cpu {
+        return Ok(current);
+    }
+    info!("Reloading whisper model on {}", if config.force_cpu { "the CPU" } else { "the GPU" });
+    let form = Form::new().text("use_gpu", (!config.force_cpu).to_string());
+    let body = client
+        .post(format!("{}/load", server_url()))
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Whisper server unreachable: {}", e))?
+        .text()
+        .await
+        .unwrap_or_default();
+    if body.contains("error") {
+        return Err(format!("Whisper server failed to switch device: {}", body));
+    }
+    let device = query(client).await?;
+    if !config.force_cpu && device.backend == ComputeBackend::Cpu {
+        warn!("Whisper server has no {:?} support; running on the CPU", preferred_backend());
+    }
+    Ok(device)
+}
+
+/// Apply the CPU override once the whisper server is up.
+pub fn init() {
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        for _ in 0..APPLY_ATTEMPTS {
+            match apply(&client, &settings::get().compute_device).await {
+                Ok(device) => {
+                    info!("Whisper running on {:?}", device.backend);
+                    return;
+                }
+                Err(_) => tokio::time::sleep(APPLY_RETRY).await,
+            }
+        }
+        warn!("Whisper server not reachable; compute device left as is");
+    });
+}
+
+/// The device transcription is running on, so users can check the GPU is used.
+#[command]
+pub async fn get_compute_device() -> Result<ComputeDevice, String> {
+    query(&reqwest::Client::new()).await
+}
+
+#[command]
+pub fn get_compute_device_settings() -> ComputeDeviceSettings {
+    settings::get().compute_device
+}
+
+/// Save the override and move the loaded model accordingly; if the server is
+/// down it's applied when the app next starts.
+#[command]
+pub async fn set_compute_device_settings(
+    compute_device_settings: ComputeDeviceSettings,
+) -> Result<ComputeDeviceSettings, String> {
+    let config = settings::update(|s| s.compute_device = compute_device_settings).map(|s| s.compute_device)?;
+    if let Err(e) = apply(&reqwest::Client::new(), &config).await {
+        warn!("{}", e);
+    }
+    Ok(config)
+}
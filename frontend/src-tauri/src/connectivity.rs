@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::pipeline::{self, PipelineEvent};
+use crate::settings;
+use crate::transcription::{self, TranscriptionEngine, TranscriptionError};
+
+/// How long a reachability probe waits for any answer from the engine's API.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Switch from a cloud engine to a local one while its API can't be reached,
+/// instead of failing and falling back on every chunk, and back once it can.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectivitySettings {
+    pub auto_switch: bool,
+    /// Consecutive network failures of a cloud engine before going offline.
+    pub failures_before_offline: u32,
+    /// While offline, check this often whether the API is reachable again.
+    pub probe_interval_secs: u64,
+}
+
+impl Default for ConnectivitySettings {
+    fn default() -> Self {
+        Self {
+            auto_switch: true,
+            failures_before_offline: 3,
+            probe_interval_secs: 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectivityStatus {
+    /// The cloud engine switched away from, while offline.
+    pub offline_from: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+static STATUS: Lazy<Mutex<ConnectivityStatus>> = Lazy::new(|| Mutex::new(ConnectivityStatus::default()));
+
+/// Whether cloud engines are being skipped for lack of connectivity.
+pub fn offline() -> bool {
+    STATUS.lock().map(|status| status.offline_from.is_some()).unwrap_or(false)
+}
+
+/// Count a cloud engine's result towards going offline.
+pub fn observe(engine: &dyn TranscriptionEngine, result: Result<(), &str>) {
+    let config = settings::get().connectivity;
+    let Ok(mut status) = STATUS.lock() else {
+        return;
+    };
+    match result {
+        Ok(()) => status.consecutive_failures = 0,
+        Err(e) => {
+            let kind = TranscriptionError::classify(e);
+            if !matches!(kind, TranscriptionError::Network { .. } | TranscriptionError::EngineTimeout { .. }) {
+                return;
+            }
+            status.consecutive_failures += 1;
+            debug!("{} network failure {} in a row", engine.name(), status.consecutive_failures);
+            if config.auto_switch
+                && status.offline_from.is_none()
+                && status.consecutive_failures >= config.failures_before_offline.max(1)
+            {
+                status.offline_from = Some(engine.id().to_string());
+                let local = transcription::local_engine_id();
+                warn!("{} unreachable, switching to {} until it's back", engine.name(), local);
+                pipeline::publish(PipelineEvent::EngineSwitched {
+                    from: engine.id().to_string(),
+                    to: local,
+                    online: false,
+                });
+            }
+        }
+    }
+}
+
+fn go_online() {
+    let Some(from) = STATUS.lock().ok().and_then(|mut status| {
+        status.consecutive_failures = 0;
+        status.offline_from.take()
+    }) else {
+        return;
+    };
+    info!("{} reachable again, switching back", from);
+    pipeline::publish(PipelineEvent::EngineSwitched {
+        from: transcription::local_engine_id(),
+        to: from,
+        online: true,
+    });
+}
+
+/// Whether the engine's API answers at all; any HTTP response will do.
+pub(crate) async fn reachable(client: &reqwest::Client, engine: &dyn TranscriptionEngine) -> bool {
+    let Some(url) = engine.health_url() else {
+        return true;
+    };
+    let client = engine.health_client().unwrap_or_else(|| client.clone());
+    client.get(url).timeout(PROBE_TIMEOUT).send().await.is_ok()
+}
+
+/// While offline, probe the engine switched away from until it is reachable.
+pub fn init() {
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        loop {
+            let interval = settings::get().connectivity.probe_interval_secs;
+            tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+            let Some(id) = STATUS.lock().ok().and_then(|status| status.offline_from.clone()) else {
+                continue;
+            };
+            match transcription::engine(&id) {
+                Some(engine) if !reachable(&client, engine.as_ref()).await => {}
+                _ => go_online(),
+            }
+        }
+    });
+}
+
+#[command]
+pub fn get_connectivity_status() -> ConnectivityStatus {
+    STATUS.lock().map(|status| status.clone()).unwrap_or_default()
+}
+
+#[command]
+pub fn get_connectivity_settings() -> ConnectivitySettings {
+    settings::get().connectivity
+}
+
+#[command]
+pub fn set_connectivity_settings(connectivity_settings: ConnectivitySettings) -> Result<ConnectivitySettings, String> {
+    let updated = settings::update(|s| s.connectivity = connectivity_settings).map(|s| s.connectivity)?;
+    if !updated.auto_switch {
+        go_online();
+    }
+    Ok(updated)
+}
@@ -0,0 +1,628 @@
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures_util::future::BoxFuture;
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::Connector;
+
+use crate::transcription::{encode_wav, AudioInput, Transcript, TranscriptionEngine, TranscriptionResult, LIVE_EVENT};
+use crate::live_corrections::CorrectionTracker;
+use crate::{dual_stream, name_bias, secrets, settings, storage, usage, TranscriptSegment};
+
+const HOSTED_ENDPOINT: &str = "https://api.deepgram.com";
+pub const API_KEY_SECRET: &str = "deepgram-api-key";
+/// Deepgram closes a live stream that gets neither audio nor a KeepAlive for
+/// about ten seconds.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(4);
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// Failed connections in a row before live captions give up.
+const MAX_RECONNECT_ATTEMPTS: u32 = 8;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeepgramSettings {
+    /// Stream each capture device to Deepgram for low-latency live captions,
+    /// alongside the regular chunked transcription.
+    pub streaming: bool,
+    #[serde(flatten)]
+    pub options: DeepgramOptions,
+    /// BCP-47 code; Deepgram's default when `None`.
+    pub language: Option<String>,
+    /// Base URL of a self-hosted Deepgram, used instead of the hosted API,
+    /// e.g. `https://deepgram.corp.example:8080`. Live streaming uses the
+    /// matching `ws`/`wss` URL.
+    pub endpoint: Option<String>,
+    /// PEM file with the CA certificate(s) the endpoint's certificate is
+    /// signed by, trusted in addition to the system's.
+    pub ca_certificate: Option<String>,
+    /// Skip certificate verification, e.g. for a test deployment with a
+    /// self-signed certificate.
+    pub accept_invalid_certs: bool,
+}
+
+impl DeepgramSettings {
+    fn self_hosted(&self) -> Option<&str> {
+        self.endpoint.as_deref().map(str::trim).filter(|e| !e.is_empty())
+    }
+
+    /// The listen endpoint of the hosted or self-hosted API.
+    fn listen_url(&self) -> Result<Url, String> {
+        let base = self.self_hosted().unwrap_or(HOSTED_ENDPOINT);
+        let url = Url::parse(&format!("{}/v1/listen", base.trim_end_matches('/')))
+            .map_err(|e| format!("Invalid Deepgram endpoint '{}': {}", base, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Deepgram endpoint '{}' must be an http or https URL", base));
+        }
+        Ok(url)
+    }
+
+    fn live_listen_url(&self) -> Result<Url, String> {
+        let mut url = self.listen_url()?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme)
+            .map_err(|_| format!("Can't stream to Deepgram endpoint {}", url))?;
+        Ok(url)
+    }
+
+    fn ca_pem(&self) -> Result<Option<Vec<u8>>, String> {
+        let Some(path) = self.ca_certificate.as_deref().map(str::trim).filter(|p| !p.is_empty()) else {
+            return Ok(None);
+        };
+        fs::read(path)
+            .map(Some)
+            .map_err(|e| format!("Failed to read CA certificate {}: {}", path, e))
+    }
+
+    /// HTTP client trusting the configured CA certificate.
+    fn http_client(&self) -> Result<reqwest::Client, String> {
+        let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(pem) = self.ca_pem()? {
+            let certificate =
+                reqwest::Certificate::from_pem(&pem).map_err(|e| format!("Invalid CA certificate: {}", e))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder.build().map_err(|e| format!("Failed to create Deepgram client: {}", e))
+    }
+
+    /// TLS settings for the live stream, when they differ from the system's.
+    fn tls_connector(&self) -> Result<Option<Connector>, String> {
+        let pem = self.ca_pem()?;
+        if pem.is_none() && !self.accept_invalid_certs {
+            return Ok(None);
+        }
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(pem) = pem {
+            let certificate =
+                native_tls::Certificate::from_pem(&pem).map_err(|e| format!("Invalid CA certificate: {}", e))?;
+            builder.add_root_certificate(certificate);
+        }
+        let connector = builder.build().map_err(|e| format!("Failed to set up TLS: {}", e))?;
+        Ok(Some(Connector::NativeTls(connector)))
+    }
+
+    /// The API key; self-hosted deployments may not check one.
+    fn api_key(&self) -> Result<Option<String>, String> {
+        match self.self_hosted() {
+            Some(_) => secrets::get(API_KEY_SECRET),
+            None => secrets::require(API_KEY_SECRET).map(Some),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.options.validate()?;
+        self.listen_url()?;
+        self.http_client()?;
+        self.tls_connector()?;
+        Ok(())
+    }
+}
+
+/// Client for the batch API, rebuilt when the endpoint's TLS settings change.
+static CLIENT: Lazy<Mutex<Option<((Option<String>, bool), reqwest::Client)>>> = Lazy::new(|| Mutex::new(None));
+
+fn client(config: &DeepgramSettings) -> Result<reqwest::Client, String> {
+    let key = (config.ca_certificate.clone(), config.accept_invalid_certs);
+    let mut cached = CLIENT.lock().map_err(|_| "Deepgram client lock poisoned".to_string())?;
+    if let Some((cached_key, client)) = cached.as_ref() {
+        if *cached_key == key {
+            return Ok(client.clone());
+        }
+    }
+    let client = config.http_client()?;
+    *cached = Some((key, client.clone()));
+    Ok(client)
+}
+
+/// Request options passed through to Deepgram, for both chunked and live
+/// transcription.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeepgramOptions {
+    /// e.g. `nova-2`, or `nova-3` for better accuracy at a higher price.
+    pub model: String,
+    /// Tier of older models, e.g. `enhanced`; newer models have none.
+    pub tier: Option<String>,
+    /// Format dates, times, amounts and the like for readability.
+    pub smart_format: bool,
+    pub punctuate: bool,
+    /// Write numbers as digits.
+    pub numerals: bool,
+    /// Project jargon to recognize more readily, as `term` or `term:boost`
+    /// (e.g. `Kubernetes:2`). Nova-3 takes them as key terms, without boost.
+    pub keywords: Vec<String>,
+    /// Kinds of content to redact from the text, e.g. `pii`, `pci`, `numbers`.
+    pub redact: Vec<String>,
+}
+
+impl Default for DeepgramOptions {
+    fn default() -> Self {
+        Self {
+            model: "nova-2".to_string(),
+            tier: None,
+            smart_format: false,
+            punctuate: true,
+            numerals: false,
+            keywords: Vec::new(),
+            redact: Vec::new(),
+        }
+    }
+}
+
+impl DeepgramOptions {
+    fn validate(&self) -> Result<(), String> {
+        if self.model.trim().is_empty() {
+            return Err("Deepgram needs a model".to_string());
+        }
+        for keyword in &self.keywords {
+            if let Some((term, boost)) = keyword.rsplit_once(':') {
+                if term.trim().is_empty() || boost.trim().parse::<f32>().is_err() {
+                    return Err(format!("Invalid Deepgram keyword '{}', expected term or term:boost", keyword));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn append_to(&self, url: &mut Url) {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("model", self.model.trim());
+        if let Some(tier) = self.tier.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            query.append_pair("tier", tier);
+        }
+        query.append_pair("punctuate", &self.punctuate.to_string());
+        if self.smart_format {
+            query.append_pair("smart_format", "true");
+        }
+        if self.numerals {
+            query.append_pair("numerals", "true");
+        }
+        let key_terms = self.model.trim().starts_with("nova-3");
+        for keyword in self.keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+            if key_terms {
+                let term = keyword.rsplit_once(':').map_or(keyword, |(term, _)| term);
+                query.append_pair("keyterm", term.trim());
+            } else {
+                query.append_pair("keywords", keyword);
+            }
+        }
+        // Names of the people in the meeting, spelled as in the calendar
+        let boost = settings::get().name_bias.keyword_boost;
+        for word in name_bias::words() {
+            if key_terms {
+                query.append_pair("keyterm", &word);
+            } else {
+                query.append_pair("keywords", &format!("{}:{}", word, boost));
+            }
+        }
+        for kind in self.redact.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+            query.append_pair("redact", kind);
+        }
+    }
+}
+
+/// Options for the recording in progress, when it was started with its own.
+static SESSION_OPTIONS: Lazy<Mutex<Option<DeepgramOptions>>> = Lazy::new(|| Mutex::new(None));
+
+/// Use `options` instead of the configured ones until the recording ends.
+pub fn begin_session(options: Option<DeepgramOptions>) -> Result<(), String> {
+    if let Some(options) = &options {
+        options.validate()?;
+        info!("Deepgram options for this recording: model {}", options.model);
+    }
+    if let Ok(mut session) = SESSION_OPTIONS.lock() {
+        *session = options;
+    }
+    Ok(())
+}
+
+pub fn end_session() {
+    if let Ok(mut session) = SESSION_OPTIONS.lock() {
+        *session = None;
+    }
+}
+
+/// Options of the current recording, or the configured ones.
+pub fn options() -> DeepgramOptions {
+    SESSION_OPTIONS
+        .lock()
+        .ok()
+        .and_then(|session| session.clone())
+        .unwrap_or_else(|| settings::get().deepgram.options)
+}
+
+#[derive(Debug, Deserialize)]
+struct LiveResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    is_final: bool,
+    #[serde(default)]
+    start: f32,
+    #[serde(default)]
+    duration: f32,
+    channel: Option<LiveChannel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiveChannel {
+    alternatives: Vec<LiveAlternative>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiveAlternative {
+    transcript: String,
+    #[serde(default)]
+    confidence: f32,
+}
+
+/// Stream `device` to Deepgram's live API until the capture loop drops the
+/// sending side of `audio_rx`. A dropped connection is reopened with
+/// backoff; audio captured meanwhile waits in `audio_rx` and is sent then.
+pub async fn run_live<R: Runtime>(
+    app: AppHandle<R>,
+    device: String,
+    sample_rate: u32,
+    mut audio_rx: mpsc::UnboundedReceiver<Vec<f32>>,
+) -> Result<(), String> {
+    let config = settings::get().deepgram;
+    let api_key = config.api_key()?;
+    let mut corrections = CorrectionTracker::default();
+    // Seconds of audio sent on earlier connections. Deepgram times each
+    // connection from zero, so later ones are shifted by this.
+    let mut sent_secs = 0.0;
+    let mut failures = 0;
+    loop {
+        let mut connection = LiveConnection {
+            device: &device,
+            sample_rate,
+            offset: sent_secs as f32,
+            sent_secs: &mut sent_secs,
+            corrections: &mut corrections,
+        };
+        match connection.run(&app, &config, api_key.as_deref(), &mut audio_rx).await {
+            Ok(LiveEnd::Finished) => return Ok(()),
+            Ok(LiveEnd::Dropped) => {
+                failures = 0;
+                warn!("Deepgram closed the live stream for {}, reconnecting", device);
+            }
+            Err(e) => {
+                failures += 1;
+                if failures >= MAX_RECONNECT_ATTEMPTS {
+                    return Err(e);
+                }
+                warn!("Deepgram live stream for {}: {}", device, e);
+            }
+        }
+        let delay = RECONNECT_INITIAL_DELAY.saturating_mul(1 << failures.min(8)).min(RECONNECT_MAX_DELAY);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+fn live_url(config: &DeepgramSettings, sample_rate: u32) -> Result<String, String> {
+    let mut url = config.live_listen_url()?;
+    url.query_pairs_mut()
+        .append_pair("encoding", "linear16")
+        .append_pair("sample_rate", &sample_rate.to_string())
+        .append_pair("channels", "1")
+        .append_pair("interim_results", "true");
+    options().append_to(&mut url);
+    if let Some(language) = &config.language {
+        url.query_pairs_mut().append_pair("language", language);
+    }
+    Ok(url.to_string())
+}
+
+fn to_pcm16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// How a live connection ended.
+enum LiveEnd {
+    /// The capture loop stopped and Deepgram finalized what it had.
+    Finished,
+    /// Deepgram closed the connection mid-recording.
+    Dropped,
+}
+
+struct LiveConnection<'a> {
+    device: &'a str,
+    sample_rate: u32,
+    /// Seconds added to this connection's timestamps.
+    offset: f32,
+    sent_secs: &'a mut f64,
+    corrections: &'a mut CorrectionTracker,
+}
+
+impl LiveConnection<'_> {
+    /// Stream until the capture loop stops or the connection drops, sending
+    /// a KeepAlive whenever there's been no audio for a while, e.g. while
+    /// recording is paused.
+    async fn run<R: Runtime>(
+        &mut self,
+        app: &AppHandle<R>,
+        config: &DeepgramSettings,
+        api_key: Option<&str>,
+        audio_rx: &mut mpsc::UnboundedReceiver<Vec<f32>>,
+    ) -> Result<LiveEnd, String> {
+        let mut request = live_url(config, self.sample_rate)?
+            .into_client_request()
+            .map_err(|e| format!("Invalid Deepgram URL: {}", e))?;
+        if let Some(api_key) = api_key {
+            let auth =
+                HeaderValue::from_str(&format!("Token {}", api_key)).map_err(|e| format!("Invalid API key: {}", e))?;
+            request.headers_mut().insert("Authorization", auth);
+        }
+
+        let connector = config.tls_connector()?;
+        let (socket, _) = tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector)
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+        info!("Deepgram live stream open for {} at {} Hz", self.device, self.sample_rate);
+        let (mut sink, mut stream) = socket.split();
+
+        let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+        let mut last_sent = Instant::now();
+        let mut closing = false;
+        loop {
+            tokio::select! {
+                samples = audio_rx.recv(), if !closing => {
+                    let Some(samples) = samples else {
+                        // The capture loop dropped its handle: let Deepgram finalize what it has
+                        let _ = sink.send(Message::Text(r#"{"type":"CloseStream"}"#.to_string())).await;
+                        closing = true;
+                        continue;
+                    };
+                    sink.send(Message::Binary(to_pcm16(&samples)))
+                        .await
+                        .map_err(|e| format!("Failed to send audio: {}", e))?;
+                    let secs = samples.len() as f64 / self.sample_rate.max(1) as f64;
+                    *self.sent_secs += secs;
+                    usage::record_streamed(DeepgramEngine::ID, secs);
+                    last_sent = Instant::now();
+                }
+                _ = keepalive.tick(), if !closing => {
+                    if last_sent.elapsed() >= KEEPALIVE_INTERVAL {
+                        sink.send(Message::Text(r#"{"type":"KeepAlive"}"#.to_string()))
+                            .await
+                            .map_err(|e| format!("Failed to send KeepAlive: {}", e))?;
+                        last_sent = Instant::now();
+                    }
+                }
+                message = stream.next() => {
+                    match message {
+                        Some(Ok(Message::Text(text))) => self.handle(app, &text),
+                        Some(Ok(Message::Close(_))) | None => break,
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(format!("Connection error: {}", e)),
+                    }
+                }
+            }
+        }
+
+        info!("Deepgram live stream closed for {}", self.device);
+        Ok(if closing { LiveEnd::Finished } else { LiveEnd::Dropped })
+    }
+
+    fn handle<R: Runtime>(&mut self, app: &AppHandle<R>, text: &str) {
+        let response: LiveResponse = match serde_json::from_str(text) {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Unexpected Deepgram message: {}", e);
+                return;
+            }
+        };
+        if response.kind != "Results" {
+            return;
+        }
+        let Some(alternative) = response.channel.and_then(|c| c.alternatives.into_iter().next()) else {
+            return;
+        };
+        if alternative.transcript.trim().is_empty() {
+            return;
+        }
+        let start = self.offset + response.start;
+        // Interim results for a stretch of audio share its start until it's final
+        let key = (start * 1000.0).round() as u64;
+        let result = TranscriptionResult {
+            device: self.device.to_string(),
+            text: alternative.transcript,
+            is_final: response.is_final,
+            start,
+            end: start + response.duration,
+            confidence: alternative.confidence,
+            speaker: None,
+            session_id: storage::current_session(),
+            language: None,
+            translated_from: None,
+            engine: DeepgramEngine::ID.to_string(),
+        };
+        if let Err(e) = app.emit(LIVE_EVENT, &result) {
+            error!("Failed to emit live transcription: {}", e);
+        }
+        dual_stream::push(app, &result);
+        self.corrections.publish(app, key, &result);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    results: BatchResults,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResults {
+    #[serde(default)]
+    utterances: Vec<Utterance>,
+    #[serde(default)]
+    channels: Vec<LiveChannel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Utterance {
+    start: f32,
+    end: f32,
+    transcript: String,
+    #[serde(default)]
+    speaker: Option<u32>,
+}
+
+/// Deepgram's pre-recorded API, one request per chunk.
+#[derive(Default)]
+pub struct DeepgramEngine;
+
+impl DeepgramEngine {
+    pub const ID: &'static str = "deepgram";
+
+    pub fn new() -> Self {
+        Self
+    }
+
+    async fn transcribe_chunk(&self, audio: &AudioInput) -> Result<Transcript, String> {
+        let config = settings::get().deepgram;
+        let api_key = config.api_key()?;
+        let mut url = config.listen_url()?;
+        url.query_pairs_mut()
+            .append_pair("utterances", "true")
+            .append_pair("diarize", "true");
+        options().append_to(&mut url);
+        if let Some(language) = audio.language.as_ref().or(config.language.as_ref()) {
+            url.query_pairs_mut().append_pair("language", language);
+        }
+
+        let mut request = client(&config)?.post(url);
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Token {}", api_key));
+        }
+        let response = request
+            .header("Content-Type", "audio/wav")
+            .body(encode_wav(audio)?)
+            .send()
+            .await
+            .map_err(|e| format!("Deepgram request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Deepgram returned {}: {}", status, body));
+        }
+        let parsed: BatchResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Deepgram response: {}", e))?;
+
+        let mut segments: Vec<TranscriptSegment> = parsed
+            .results
+            .utterances
+            .into_iter()
+            .map(|u| TranscriptSegment {
+                text: u.transcript,
+                t0: u.start,
+                t1: u.end,
+                speaker: u.speaker.map(|n| n.to_string()),
+                no_speech_prob: None,
+                avg_logprob: None,
+                language: None,
+                translated_from: None,
+            })
+            .collect();
+        if segments.is_empty() {
+            let duration = audio.samples.len() as f32 / audio.sample_rate.max(1) as f32;
+            let text = parsed
+                .results
+                .channels
+                .into_iter()
+                .next()
+                .and_then(|c| c.alternatives.into_iter().next())
+                .map(|a| a.transcript)
+                .unwrap_or_default();
+            if !text.trim().is_empty() {
+                segments.push(TranscriptSegment {
+                    text,
+                    t0: 0.0,
+                    t1: duration,
+                    speaker: None,
+                    no_speech_prob: None,
+                    avg_logprob: None,
+                    language: None,
+                    translated_from: None,
+                });
+            }
+        }
+        Ok(Transcript {
+            segments,
+            buffer_size_ms: 0,
+            engine: Self::ID.to_string(),
+        })
+    }
+}
+
+impl TranscriptionEngine for DeepgramEngine {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn name(&self) -> &str {
+        "Deepgram"
+    }
+
+    fn is_cloud(&self) -> bool {
+        true
+    }
+
+    fn health_url(&self) -> Option<String> {
+        settings::get().deepgram.listen_url().ok().map(String::from)
+    }
+
+    fn health_client(&self) -> Option<reqwest::Client> {
+        client(&settings::get().deepgram).ok()
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>> {
+        Box::pin(self.transcribe_chunk(audio))
+    }
+}
+
+#[command]
+pub fn get_deepgram_settings() -> DeepgramSettings {
+    settings::get().deepgram
+}
+
+#[command]
+pub fn set_deepgram_settings(deepgram_settings: DeepgramSettings) -> Result<DeepgramSettings, String> {
+    deepgram_settings.validate()?;
+    settings::update(|s| s.deepgram = deepgram_settings).map(|s| s.deepgram)
+}
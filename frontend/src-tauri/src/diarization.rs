@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::audio::app_activity::{AudioSource, SourceMap};
+use crate::audio::farfield;
+use crate::encryption;
+use crate::storage::{self, DERIVED_DIR};
+
+pub const QUALITY_FILE: &str = "diarization_quality.json";
+/// Segments whose dominant speaker held less of the span than this are low confidence.
+const LOW_CONFIDENCE_SHARE: f32 = 0.7;
+/// Below this overall score, speaker labels should be double-checked.
+const REVIEW_SCORE: f32 = 0.6;
+/// Above this proportion of low-confidence segments, re-clustering is suggested.
+const RECLUSTER_LOW_RATIO: f32 = 0.25;
+/// An engine speaker takes a local identity's name once that identity holds
+/// this share of the time they overlapped...
+const ADOPT_SHARE: f32 = 0.6;
+/// ...over at least this many seconds.
+const ADOPT_MIN_SECS: f32 = 3.0;
+
+/// How much to trust a meeting's speaker labels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiarizationQuality {
+    /// 0.0 (labels unusable) to 1.0 (every segment cleanly attributed).
+    pub score: f32,
+    pub segments: usize,
+    pub speakers: usize,
+    /// Mean share of each segment held by its assigned speaker.
+    pub mean_confidence: f32,
+    /// Mean gap between the assigned speaker and the runner-up; low values
+    /// mean speakers were talking over each other or hard to tell apart.
+    pub separation: f32,
+    pub low_confidence_ratio: f32,
+    pub needs_review: bool,
+    pub suggest_recluster: bool,
+}
+
+#[derive(Default)]
+struct QualityTracker {
+    confidences: Vec<f32>,
+    margins: Vec<f32>,
+    speakers: HashSet<String>,
+}
+
+static TRACKER: Lazy<Mutex<QualityTracker>> = Lazy::new(|| Mutex::new(QualityTracker::default()));
+/// Seconds an engine speaker overlapped one local attribution.
+#[derive(Default)]
+struct Overlap {
+    secs: f32,
+    /// The attribution is a person recognized by voice rather than a side of
+    /// the call (microphone, an app, a remote device).
+    identity: bool,
+}
+
+/// Per engine speaker label, how long each local attribution was heard with it.
+static ENGINE_SPEAKERS: Lazy<Mutex<HashMap<String, HashMap<String, Overlap>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn reset() {
+    if let Ok(mut tracker) = TRACKER.lock() {
+        *tracker = QualityTracker::default();
+    }
+    if let Ok(mut speakers) = ENGINE_SPEAKERS.lock() {
+        speakers.clear();
+    }
+}
+
+/// The local identity `engine_speaker` is, by overlap voting: the identity
+/// holding most of their time, unless another engine speaker overlapped it
+/// for longer.
+fn adopted_identity(speakers: &HashMap<String, HashMap<String, Overlap>>, engine_speaker: &str) -> Option<String> {
+    let votes = speakers.get(engine_speaker)?;
+    let total: f32 = votes.values().map(|overlap| overlap.secs).sum();
+    let (identity, overlap) = votes
+        .iter()
+        .filter(|(_, overlap)| overlap.identity)
+        .max_by(|a, b| a.1.secs.total_cmp(&b.1.secs).then_with(|| b.0.cmp(a.0)))?;
+    if total < ADOPT_MIN_SECS || overlap.secs < total * ADOPT_SHARE {
+        return None;
+    }
+    let claimed_by_other = speakers.iter().any(|(other, votes)| {
+        other != engine_speaker && votes.get(identity).is_some_and(|theirs| theirs.secs > overlap.secs)
+    });
+    (!claimed_by_other).then(|| identity.clone())
+}
+
+/// Reconcile a speaker label from a diarizing engine with the local
+/// attribution of `secs` of the same audio. The engine tells voices apart;
+/// locally they're either recognized by voice (`identity`) or only known by
+/// the side of the call they came from. An engine speaker who mostly overlaps
+/// one recognized voice carries that name, so speakers keep their labels when
+/// the engine changes mid-meeting; anyone else is labeled with the side they
+/// have mostly been heard from.
+pub fn reconcile(engine_speaker: &str, local: &str, identity: bool, secs: f32) -> AudioSource {
+    let Ok(mut speakers) = ENGINE_SPEAKERS.lock() else {
+        return AudioSource::Speaker(format!("Speaker {}", engine_speaker));
+    };
+    let votes = speakers.entry(engine_speaker.to_string()).or_default();
+    if !local.is_empty() {
+        let overlap = votes.entry(local.to_string()).or_default();
+        overlap.secs += secs.max(0.0);
+        overlap.identity = identity;
+    }
+    if let Some(identity) = adopted_identity(&speakers, engine_speaker) {
+        return AudioSource::Speaker(identity);
+    }
+    let side = speakers.get(engine_speaker).and_then(|votes| {
+        votes
+            .iter()
+            .filter(|(_, overlap)| !overlap.identity)
+            .max_by(|a, b| a.1.secs.total_cmp(&b.1.secs).then_with(|| b.0.cmp(a.0)))
+            .map(|(side, _)| side.clone())
+    });
+    match side {
+        Some(side) => AudioSource::Speaker(format!("Speaker {} ({})", engine_speaker, side)),
+        None => AudioSource::Speaker(format!("Speaker {}", engine_speaker)),
+    }
+}
+
+/// Record how cleanly the samples `[from, to)` of a chunk were attributed to `speaker`.
+pub fn observe(sources: &SourceMap, from: usize, to: usize, speaker: &str) {
+    let shares = sources.shares(from, to);
+    let Some(&(_, top)) = shares.first() else {
+        return;
+    };
+    let runner_up = shares.get(1).map(|(_, share)| *share).unwrap_or(0.0);
+    if let Ok(mut tracker) = TRACKER.lock() {
+        tracker.confidences.push(top);
+        tracker.margins.push(top - runner_up);
+        tracker.speakers.insert(speaker.to_string());
+    }
+}
+
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+}
+
+fn summarize(tracker: &QualityTracker) -> Option<DiarizationQuality> {
+    if tracker.confidences.is_empty() {
+        return None;
+    }
+    let segments = tracker.confidences.len();
+    // Conference-room sessions hear everyone through one microphone, so
+    // shares are judged less strictly there
+    let conference = farfield::active();
+    let low_share = conference
+        .as_ref()
+        .map(|session| session.settings.low_confidence_share)
+        .unwrap_or(LOW_CONFIDENCE_SHARE);
+    let low = tracker.confidences.iter().filter(|c| **c < low_share).count();
+    let low_confidence_ratio = low as f32 / segments as f32;
+    let mean_confidence = mean(&tracker.confidences);
+    let separation = mean(&tracker.margins);
+    let score = (0.4 * mean_confidence + 0.3 * separation + 0.3 * (1.0 - low_confidence_ratio)).clamp(0.0, 1.0);
+    Some(DiarizationQuality {
+        score,
+        segments,
+        speakers: tracker.speakers.len(),
+        mean_confidence,
+        separation,
+        low_confidence_ratio,
+        needs_review: score < REVIEW_SCORE,
+        // A room holding more people than the sources heard needs re-clustering
+        suggest_recluster: (low_confidence_ratio > RECLUSTER_LOW_RATIO && tracker.speakers.len() > 1)
+            || conference
+                .and_then(|session| session.participants)
+                .is_some_and(|participants| participants > tracker.speakers.len()),
+    })
+}
+
+/// Score the session that just ended, store it with the meeting and tell the UI.
+pub fn finish<R: Runtime>(app: &AppHandle<R>) -> Option<DiarizationQuality> {
+    let quality = TRACKER.lock().ok().and_then(|tracker| summarize(&tracker))?;
+    info!(
+        "Diarization quality {:.2} over {} segments ({:.0}% low confidence)",
+        quality.score,
+        quality.segments,
+        quality.low_confidence_ratio * 100.0
+    );
+
+    if let Some(meeting_id) = storage::current_session() {
+        let stored = serde_json::to_string_pretty(&quality)
+            .map_err(|e| e.to_string())
+            .and_then(|content| storage::save_derived(app.clone(), meeting_id, QUALITY_FILE.to_string(), content));
+        if let Err(e) = stored {
+            warn!("Failed to store diarization quality: {}", e);
+        }
+    }
+    let _ = app.emit("diarization-quality", &quality);
+    Some(quality)
+}
+
+#[command]
+pub fn get_diarization_quality<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<Option<DiarizationQuality>, String> {
+    let path = storage::meeting_dir(&app, &meeting_id)?
+        .join(DERIVED_DIR)
+        .join(QUALITY_FILE);
+    match encryption::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Invalid diarization quality file: {}", e)),
+        Err(_) => Ok(None),
+    }
+}
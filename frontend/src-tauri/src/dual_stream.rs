@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use log::{debug, error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::transcription::TranscriptionResult;
+
+pub const MERGED_EVENT: &str = "session-transcript";
+/// Derived file the unified transcript of a session is stored as.
+pub const MERGED_FILE: &str = "session_transcript.json";
+/// Final results are held this long behind the newest one, so a remote
+/// utterance arriving late can still cancel its echo on the microphone.
+const HOLD_SECS: f32 = 2.0;
+/// Speaker output reaches the microphone within this much of the original.
+const ECHO_WINDOW_SECS: f32 = 1.5;
+/// Share of a microphone utterance's words also in the remote one to count as echo.
+const ECHO_WORD_OVERLAP: f32 = 0.6;
+
+/// Which side of the call an utterance came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// The microphone: people in the room.
+    Local,
+    /// System audio: people on the other end of the call.
+    Remote,
+}
+
+/// One final utterance on the unified session timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Utterance {
+    pub origin: Origin,
+    pub device: String,
+    pub text: String,
+    /// Seconds since the streams opened.
+    pub start: f32,
+    pub end: f32,
+    pub confidence: f32,
+    pub speaker: Option<String>,
+}
+
+/// Merges the live microphone and system-audio streams of one recording.
+struct Merger {
+    devices: HashMap<String, Origin>,
+    /// Finals not yet old enough to be released, in arrival order.
+    pending: Vec<Utterance>,
+    merged: Vec<Utterance>,
+    echoes_dropped: usize,
+}
+
+static MERGER: Lazy<Mutex<Option<Merger>>> = Lazy::new(|| Mutex::new(None));
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Fraction of `echo`'s words found in `original`, counting repeats.
+fn word_overlap(echo: &str, original: &str) -> f32 {
+    let echo = words(echo);
+    if echo.is_empty() {
+        return 0.0;
+    }
+    let mut available: HashMap<String, usize> = HashMap::new();
+    for word in words(original) {
+        *available.entry(word).or_default() += 1;
+    }
+    let shared = echo
+        .iter()
+        .filter(|word| match available.get_mut(*word) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        })
+        .count();
+    shared as f32 / echo.len() as f32
+}
+
+impl Merger {
+    /// Whether `utterance` repeats remote speech heard around the same time,
+    /// looking at everything merged, held, and in the batch being released.
+    fn is_echo(&self, utterance: &Utterance, batch: &[Utterance]) -> bool {
+        utterance.origin == Origin::Local
+            && self.merged.iter().chain(&self.pending).chain(batch).any(|other| {
+                other.origin == Origin::Remote
+                    && utterance.start < other.end + ECHO_WINDOW_SECS
+                    && other.start < utterance.end + ECHO_WINDOW_SECS
+                    && word_overlap(&utterance.text, &other.text) >= ECHO_WORD_OVERLAP
+            })
+    }
+
+    /// Release pending utterances that ended before `until`, in start order,
+    /// dropping microphone echoes of remote speech.
+    fn release(&mut self, until: f32) -> Vec<Utterance> {
+        let (mut ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|utterance| utterance.end <= until);
+        self.pending = pending;
+        ready.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        let mut released = Vec::new();
+        for utterance in ready.iter() {
+            if self.is_echo(utterance, &ready) {
+                debug!("Dropping microphone echo: {}", utterance.text);
+                self.echoes_dropped += 1;
+                continue;
+            }
+            self.merged.push(utterance.clone());
+            released.push(utterance.clone());
+        }
+        released
+    }
+}
+
+fn emit<R: Runtime>(app: &AppHandle<R>, utterances: Vec<Utterance>) {
+    for utterance in utterances {
+        if let Err(e) = app.emit(MERGED_EVENT, &utterance) {
+            error!("Failed to emit session transcript: {}", e);
+        }
+    }
+}
+
+/// Begin merging the live streams of a recording: `local_device` is the
+/// microphone, `remote_device` the system-audio loopback.
+pub fn start_session(local_device: String, remote_device: String) {
+    if let Ok(mut merger) = MERGER.lock() {
+        *merger = Some(Merger {
+            devices: HashMap::from([(local_device, Origin::Local), (remote_device, Origin::Remote)]),
+            pending: Vec::new(),
+            merged: Vec::new(),
+            echoes_dropped: 0,
+        });
+    }
+}
+
+/// Feed a live result from either stream. Interim results are ignored.
+pub fn push<R: Runtime>(app: &AppHandle<R>, result: &TranscriptionResult) {
+    if !result.is_final || result.text.trim().is_empty() {
+        return;
+    }
+    let released = MERGER.lock().ok().and_then(|mut merger| {
+        let merger = merger.as_mut()?;
+        let origin = *merger.devices.get(&result.device)?;
+        merger.pending.push(Utterance {
+            origin,
+            device: result.device.clone(),
+            text: result.text.trim().to_string(),
+            start: result.start,
+            end: result.end.max(result.start),
+            confidence: result.confidence,
+            speaker: result.speaker.clone(),
+        });
+        let newest = merger.pending.iter().map(|u| u.end).fold(f32::MIN, f32::max);
+        Some(merger.release(newest - HOLD_SECS))
+    });
+    if let Some(released) = released {
+        emit(app, released);
+    }
+}
+
+/// Release everything still held and end the session. Returns the unified
+/// transcript, or `None` when no session was being merged.
+pub fn finish<R: Runtime>(app: &AppHandle<R>) -> Option<Vec<Utterance>> {
+    let (released, merged, echoes) = MERGER.lock().ok().and_then(|mut merger| {
+        let mut merger = merger.take()?;
+        let released = merger.release(f32::MAX);
+        Some((released, merger.merged, merger.echoes_dropped))
+    })?;
+    emit(app, released);
+    info!(
+        "Merged live streams into {} utterances, dropped {} microphone echoes",
+        merged.len(),
+        echoes
+    );
+    Some(merged)
+}
+
+/// The unified transcript of the recording in progress so far.
+#[command]
+pub fn get_merged_transcript() -> Vec<Utterance> {
+    MERGER
+        .lock()
+        .ok()
+        .and_then(|merger| merger.as_ref().map(|merger| merger.merged.clone()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,391 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::{secrets, settings};
+
+/// Keychain entry holding the storage key. Losing it loses every meeting
+/// recorded with encryption on, so the webview can't replace or delete it.
+pub const KEY_SECRET: &str = "storage-encryption-key";
+/// Starts every sealed file; files without it are read as they are, so
+/// meetings stored before encryption was turned on stay readable.
+const FILE_MAGIC: &[u8] = b"MEETILY-SEALED1\n";
+/// Starts every sealed text value, such as transcript store columns.
+pub const TEXT_PREFIX: &str = "sealed:";
+/// Plaintext per frame of a sealed file. Frames are sealed as they fill, so
+/// a crash loses at most the frame being written.
+const FRAME_SIZE: usize = 64 * 1024;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionSettings {
+    /// Encrypt recorded audio, stored transcripts and derived meeting files
+    /// with AES-256-GCM as they are written. The key is kept in the OS
+    /// keychain; files written while this was off stay as they are.
+    pub enabled: bool,
+}
+
+static CIPHER: Lazy<Mutex<Option<Aes256Gcm>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn enabled() -> bool {
+    settings::get().encryption.enabled
+}
+
+/// The storage cipher. Its key is read from the keychain once, and only
+/// created when `create` is set, for sealing.
+fn cipher(create: bool) -> Result<Aes256Gcm, String> {
+    let mut cached = CIPHER.lock().map_err(|_| "Encryption key lock poisoned".to_string())?;
+    if let Some(cipher) = cached.as_ref() {
+        return Ok(cipher.clone());
+    }
+    let key = match secrets::get(KEY_SECRET)? {
+        Some(key) => hex::decode(key.trim()).map_err(|e| format!("Invalid storage encryption key: {}", e))?,
+        None if create => {
+            let mut key = vec![0u8; KEY_LEN];
+            OsRng.fill_bytes(&mut key);
+            secrets::set_keychain_secret(KEY_SECRET, &hex::encode(&key))?;
+            info!("Created storage encryption key");
+            key
+        }
+        None => return Err("The storage encryption key is missing from the keychain".to_string()),
+    };
+    let cipher =
+        Aes256Gcm::new_from_slice(&key).map_err(|_| "Storage encryption key has the wrong length".to_string())?;
+    *cached = Some(cipher.clone());
+    Ok(cipher)
+}
+
+/// The frame's position and whether it ends the stream, authenticated with
+/// it so frames can't be dropped, reordered or cut off the end unnoticed.
+fn frame_aad(index: u64, last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&index.to_le_bytes());
+    aad[8] = last as u8;
+    aad
+}
+
+/// Encrypt one frame under a fresh nonce.
+fn seal_frame(cipher: &Aes256Gcm, index: u64, last: bool, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let aad = frame_aad(index, last);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &aad })
+        .map_err(|_| "Failed to encrypt".to_string())?;
+    let mut frame = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    frame.extend_from_slice(&nonce);
+    frame.extend(ciphertext);
+    Ok(frame)
+}
+
+/// Decrypt one frame, returning its plaintext and whether it ends the stream.
+fn open_frame(cipher: &Aes256Gcm, index: u64, frame: &[u8]) -> Result<(Vec<u8>, bool), String> {
+    if frame.len() < NONCE_LEN + TAG_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (nonce, ciphertext) = frame.split_at(NONCE_LEN);
+    [false, true]
+        .into_iter()
+        .find_map(|last| {
+            let aad = frame_aad(index, last);
+            cipher
+                .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad })
+                .ok()
+                .map(|plaintext| (plaintext, last))
+        })
+        .ok_or_else(|| "Failed to decrypt: wrong key or damaged data".to_string())
+}
+
+fn io_error(e: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn truncated(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, message)
+}
+
+/// Writes a sealed file: the magic line, then frames of a length, a nonce
+/// and the ciphertext. The last frame, sealed by [`SealedWriter::finish`]
+/// or on drop, is marked as such, so a file missing its tail doesn't read
+/// as complete.
+pub struct SealedWriter<W: Write> {
+    inner: W,
+    cipher: Aes256Gcm,
+    buffer: Vec<u8>,
+    index: u64,
+    finished: bool,
+}
+
+impl<W: Write> SealedWriter<W> {
+    fn new(mut inner: W, cipher: Aes256Gcm) -> io::Result<Self> {
+        inner.write_all(FILE_MAGIC)?;
+        Ok(Self {
+            inner,
+            cipher,
+            buffer: Vec::with_capacity(FRAME_SIZE),
+            index: 0,
+            finished: false,
+        })
+    }
+
+    fn seal_buffer(&mut self, last: bool) -> io::Result<()> {
+        if self.buffer.is_empty() && !last {
+            return Ok(());
+        }
+        let frame = seal_frame(&self.cipher, self.index, last, &self.buffer).map_err(io_error)?;
+        self.inner.write_all(&(frame.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&frame)?;
+        self.index += 1;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Seal the final frame. Writes after this fail.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.seal_buffer(true)?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for SealedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Encrypted file is already finished"));
+        }
+        let taken = buf.len().min(FRAME_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..taken]);
+        if self.buffer.len() >= FRAME_SIZE {
+            self.seal_buffer(false)?;
+        }
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.finished {
+            self.seal_buffer(false)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for SealedWriter<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!("Failed to write the last encrypted frame: {}", e);
+        }
+    }
+}
+
+/// Reads the plaintext of a sealed file. A file that ends before its final
+/// frame, e.g. after a crash, fails with `UnexpectedEof` once the frames
+/// that are there have been read.
+pub struct SealedReader<R: Read> {
+    inner: R,
+    cipher: Aes256Gcm,
+    plaintext: Vec<u8>,
+    position: usize,
+    index: u64,
+    finished: bool,
+}
+
+/// Fill `buf` as far as the reader goes, returning how much was read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+impl<R: Read> SealedReader<R> {
+    fn next_frame(&mut self) -> io::Result<bool> {
+        let mut length = [0u8; 4];
+        match read_full(&mut self.inner, &mut length)? {
+            0 if self.finished => return Ok(false),
+            0 => return Err(truncated("Encrypted file ends before its final frame")),
+            4 if self.finished => return Err(io_error("Encrypted file has data after its final frame".to_string())),
+            4 => {}
+            _ => return Err(truncated("Encrypted file ends inside a frame")),
+        }
+        let length = u32::from_le_bytes(length) as usize;
+        if length > NONCE_LEN + FRAME_SIZE + TAG_LEN {
+            return Err(io_error("Encrypted file is damaged".to_string()));
+        }
+        let mut frame = vec![0u8; length];
+        if read_full(&mut self.inner, &mut frame)? < length {
+            return Err(truncated("Encrypted file ends inside a frame"));
+        }
+        let (plaintext, last) = open_frame(&self.cipher, self.index, &frame).map_err(io_error)?;
+        self.plaintext = plaintext;
+        self.finished = last;
+        self.position = 0;
+        self.index += 1;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for SealedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.plaintext.len() {
+            if !self.next_frame()? {
+                return Ok(0);
+            }
+        }
+        let read = buf.len().min(self.plaintext.len() - self.position);
+        buf[..read].copy_from_slice(&self.plaintext[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+fn sealed_reader<R: Read>(inner: R) -> io::Result<SealedReader<R>> {
+    Ok(SealedReader {
+        inner,
+        cipher: cipher(false).map_err(io_error)?,
+        plaintext: Vec::new(),
+        position: 0,
+        index: 0,
+        finished: false,
+    })
+}
+
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(FILE_MAGIC)
+}
+
+/// Create a file for streaming writes, sealed when encryption is on.
+pub fn create(path: &Path) -> io::Result<Box<dyn Write + Send>> {
+    let file = File::create(path)?;
+    if !enabled() {
+        return Ok(Box::new(BufWriter::new(file)));
+    }
+    Ok(Box::new(SealedWriter::new(file, cipher(true).map_err(io_error)?)?))
+}
+
+/// Open a file for streaming reads, decrypting it when it's sealed.
+pub fn open(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    let mut file = File::open(path)?;
+    let mut magic = vec![0u8; FILE_MAGIC.len()];
+    let read = read_full(&mut file, &mut magic)?;
+    magic.truncate(read);
+    if is_sealed(&magic) {
+        return Ok(Box::new(sealed_reader(BufReader::new(file))?));
+    }
+    Ok(Box::new(Cursor::new(magic).chain(file)))
+}
+
+/// Seal `contents` when encryption is on.
+pub fn seal(contents: &[u8]) -> io::Result<Vec<u8>> {
+    if !enabled() {
+        return Ok(contents.to_vec());
+    }
+    let mut sealed = Vec::with_capacity(FILE_MAGIC.len() + contents.len() + 64);
+    {
+        let mut writer = SealedWriter::new(&mut sealed, cipher(true).map_err(io_error)?)?;
+        writer.write_all(contents)?;
+        writer.finish()?;
+    }
+    Ok(sealed)
+}
+
+/// The plaintext of `contents`, which may or may not be sealed.
+pub fn unseal(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !is_sealed(&contents) {
+        return Ok(contents);
+    }
+    let mut plaintext = Vec::with_capacity(contents.len());
+    sealed_reader(&contents[FILE_MAGIC.len()..])?.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// `fs::write`, sealing the file when encryption is on.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    fs::write(path, seal(contents.as_ref())?)
+}
+
+/// `fs::read`, decrypting sealed files.
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    unseal(fs::read(path)?)
+}
+
+/// `fs::read_to_string`, decrypting sealed files.
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Seal a single value, whether or not encryption is on. Callers record
+/// that it is sealed themselves, e.g. in a column next to it.
+pub fn seal_value(text: &str) -> Result<String, String> {
+    let frame = seal_frame(&cipher(true)?, 0, true, text.as_bytes())?;
+    Ok(format!("{}{}", TEXT_PREFIX, BASE64.encode(frame)))
+}
+
+/// The plaintext of a value from [`seal_value`].
+pub fn open_value(value: &str) -> Result<String, String> {
+    let encoded = value
+        .strip_prefix(TEXT_PREFIX)
+        .ok_or_else(|| "Invalid encrypted value: missing prefix".to_string())?;
+    let frame = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Invalid encrypted value: {}", e))?;
+    let (plaintext, _) = open_frame(&cipher(false)?, 0, &frame)?;
+    String::from_utf8(plaintext).map_err(|e| format!("Invalid encrypted value: {}", e))
+}
+
+/// Seal a line of a log when encryption is on. Only for values that can't
+/// start with [`TEXT_PREFIX`] in plaintext, such as JSON lines; anything
+/// else needs its sealed state kept beside it and [`seal_value`].
+pub fn seal_text(text: &str) -> Result<String, String> {
+    if !enabled() {
+        return Ok(text.to_string());
+    }
+    seal_value(text)
+}
+
+/// The plaintext of a value from [`seal_text`]; other values are returned as they are.
+pub fn open_text(value: &str) -> Result<String, String> {
+    if value.starts_with(TEXT_PREFIX) {
+        open_value(value)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+#[command]
+pub fn get_encryption_settings() -> EncryptionSettings {
+    settings::get().encryption
+}
+
+#[command]
+pub fn set_encryption_settings(encryption_settings: EncryptionSettings) -> Result<EncryptionSettings, String> {
+    // Create the key up front, so a missing keychain shows here rather than
+    // as failed writes mid-meeting
+    if encryption_settings.enabled {
+        cipher(true)?;
+    }
+    settings::update(|s| s.encryption = encryption_settings).map(|s| s.encryption)
+}
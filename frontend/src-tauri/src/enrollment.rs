@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast;
+
+use crate::audio::embedding::voice_embedding;
+use crate::audio::{bluetooth, default_input_device, AudioStream};
+use crate::speaker_profiles::{self, SpeakerProfile};
+use crate::{is_recording, resample_audio, settings, WHISPER_SAMPLE_RATE};
+
+pub const PROGRESS_EVENT: &str = "voice-enrollment-progress";
+const MIN_DURATION_SECS: u64 = 15;
+const MAX_DURATION_SECS: u64 = 30;
+const DEFAULT_DURATION_SECS: u64 = 20;
+/// The sample is embedded in windows this long and the results averaged,
+/// so pauses and a cough don't dominate the voice signature.
+const WINDOW_MS: usize = 3000;
+/// Fewer voiced windows than this means the user barely spoke.
+const MIN_VOICED_WINDOWS: usize = 3;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+struct EnrollmentProgress {
+    elapsed_ms: u64,
+    duration_ms: u64,
+}
+
+async fn record<R: Runtime>(app: &AppHandle<R>, duration: Duration) -> Result<Vec<f32>, String> {
+    let mut device = default_input_device().map_err(|e| e.to_string())?;
+    if settings::get().audio.prefer_builtin_mic_on_bluetooth && bluetooth::is_bluetooth_device(&device) {
+        if let Some(builtin) = bluetooth::find_builtin_input() {
+            device = builtin;
+        }
+    }
+    let device = Arc::new(device);
+    let is_running = Arc::new(AtomicBool::new(true));
+    let stream = AudioStream::from_device(device.clone(), is_running.clone())
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", device, e))?;
+    let sample_rate = stream.device_config.sample_rate().0;
+    let mut receiver = stream.subscribe().await;
+
+    let started = tokio::time::Instant::now();
+    let deadline = started + duration;
+    let mut samples = Vec::new();
+    let mut last_report = started;
+    while !CANCELLED.load(Ordering::SeqCst) {
+        match tokio::time::timeout_at(deadline, receiver.recv()).await {
+            Ok(Ok(chunk)) => samples.extend(chunk),
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Err(_)) | Err(_) => break,
+        }
+        if last_report.elapsed() >= Duration::from_secs(1) {
+            last_report = tokio::time::Instant::now();
+            let _ = app.emit(
+                PROGRESS_EVENT,
+                EnrollmentProgress {
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    duration_ms: duration.as_millis() as u64,
+                },
+            );
+        }
+    }
+    is_running.store(false, Ordering::SeqCst);
+    if let Err(e) = stream.stop().await {
+        error!("Error stopping enrollment stream: {}", e);
+    }
+    if CANCELLED.load(Ordering::SeqCst) {
+        return Err("Voice enrollment cancelled".to_string());
+    }
+    Ok(resample_audio(&samples, sample_rate, WHISPER_SAMPLE_RATE))
+}
+
+/// Mean of the voice embeddings of each window, and how many windows had voice.
+fn enrollment_embedding(samples: &[f32]) -> Option<(Vec<f32>, usize)> {
+    let window = WINDOW_MS * WHISPER_SAMPLE_RATE as usize / 1000;
+    let embeddings: Vec<Vec<f32>> = samples
+        .chunks(window)
+        .filter_map(|chunk| voice_embedding(chunk, WHISPER_SAMPLE_RATE))
+        .collect();
+    if embeddings.len() < MIN_VOICED_WINDOWS {
+        return None;
+    }
+    let mut mean = vec![0.0f32; embeddings[0].len()];
+    for embedding in &embeddings {
+        for (total, value) in mean.iter_mut().zip(embedding) {
+            *total += value;
+        }
+    }
+    let norm = mean.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+    mean.iter_mut().for_each(|v| *v /= norm);
+    Some((mean, embeddings.len()))
+}
+
+/// Record `duration_secs` (15–30 s) of the user speaking into the microphone
+/// and store it as `name`'s voice. Microphone segments that match it are then
+/// labeled with `name` instead of "Microphone".
+#[command]
+pub async fn record_voice_enrollment<R: Runtime>(
+    app: AppHandle<R>,
+    name: String,
+    duration_secs: Option<u64>,
+) -> Result<SpeakerProfile, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() || speaker_profiles::is_unnamed(&name) {
+        return Err(format!("'{}' is not a usable speaker name", name));
+    }
+    if is_recording() {
+        return Err("Stop recording before enrolling a voice".to_string());
+    }
+    let duration_secs = duration_secs
+        .unwrap_or(DEFAULT_DURATION_SECS)
+        .clamp(MIN_DURATION_SECS, MAX_DURATION_SECS);
+
+    CANCELLED.store(false, Ordering::SeqCst);
+    info!("Recording {} s voice enrollment for {}", duration_secs, name);
+    let samples = record(&app, Duration::from_secs(duration_secs)).await?;
+    let (centroid, windows) = tokio::task::spawn_blocking(move || enrollment_embedding(&samples))
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Not enough speech in the recording; read aloud for the whole sample".to_string())?;
+
+    let profile = speaker_profiles::enroll(&app, &name, centroid, windows)?;
+    info!("Enrolled voice for {} from {} windows", name, windows);
+    Ok(profile)
+}
+
+#[command]
+pub fn cancel_voice_enrollment() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+#[command]
+pub fn list_enrolled_speakers<R: Runtime>(app: AppHandle<R>) -> Vec<SpeakerProfile> {
+    speaker_profiles::enrolled(&app)
+}
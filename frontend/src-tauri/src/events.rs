@@ -0,0 +1,97 @@
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::pipeline::PipelineEvent;
+use crate::{SessionMarker, TranscriptUpdate};
+
+/// Current version of the event envelope and payload types below. Bump it on
+/// any breaking change (renamed or removed field, changed meaning) and keep
+/// emitting the previous shape for consumers that have not migrated.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+/// Bare payloads on the original event names, as emitted before versioning.
+pub const LEGACY_SCHEMA_VERSION: u32 = 0;
+
+pub const PIPELINE_EVENT: &str = "pipeline-event";
+pub const TRANSCRIPT_UPDATE: &str = "transcript-update";
+pub const MARKER_INSERTED: &str = "marker-inserted";
+
+/// Wrapper every versioned event is delivered in.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EventEnvelope<T> {
+    /// Version of this envelope and of `payload`'s shape.
+    pub schema_version: u32,
+    /// Event name without the version suffix, e.g. `transcript-update`.
+    pub event: String,
+    /// RFC 3339 time the backend emitted the event.
+    pub emitted_at: String,
+    pub payload: T,
+}
+
+impl<T> EventEnvelope<T> {
+    pub fn new(event: &str, payload: T) -> Self {
+        Self {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event: event.to_string(),
+            emitted_at: chrono::Utc::now().to_rfc3339(),
+            payload,
+        }
+    }
+}
+
+/// Name the versioned form of `event` is emitted under, e.g. `transcript-update:v1`.
+pub fn versioned_name(event: &str) -> String {
+    format!("{}:v{}", event, EVENT_SCHEMA_VERSION)
+}
+
+/// Emit `payload` to the webview in every supported shape: the bare legacy
+/// payload under `event` and the versioned envelope under `event:vN`.
+pub fn emit<R: Runtime, T: Serialize + Clone>(app: &AppHandle<R>, event: &str, payload: T) -> tauri::Result<()> {
+    app.emit(&versioned_name(event), EventEnvelope::new(event, payload.clone()))?;
+    app.emit(event, payload)
+}
+
+/// Wrap a legacy payload in the current envelope, for consumers (webhooks,
+/// plugins) that stored or forwarded events before versioning existed.
+pub fn upgrade_legacy(event: &str, payload: Value) -> EventEnvelope<Value> {
+    EventEnvelope::new(event, payload)
+}
+
+fn payload_schemas() -> Value {
+    json!({
+        PIPELINE_EVENT: schema_for!(PipelineEvent),
+        TRANSCRIPT_UPDATE: schema_for!(TranscriptUpdate),
+        MARKER_INSERTED: schema_for!(SessionMarker),
+    })
+}
+
+fn envelope_schemas() -> Value {
+    json!({
+        versioned_name(PIPELINE_EVENT): schema_for!(EventEnvelope<PipelineEvent>),
+        versioned_name(TRANSCRIPT_UPDATE): schema_for!(EventEnvelope<TranscriptUpdate>),
+        versioned_name(MARKER_INSERTED): schema_for!(EventEnvelope<SessionMarker>),
+    })
+}
+
+/// JSON Schema for every event the backend emits, keyed by event name.
+/// Defaults to the current version; `0` describes the legacy bare payloads.
+#[command]
+pub fn get_event_schema(version: Option<u32>) -> Result<Value, String> {
+    let version = version.unwrap_or(EVENT_SCHEMA_VERSION);
+    let events = match version {
+        LEGACY_SCHEMA_VERSION => payload_schemas(),
+        EVENT_SCHEMA_VERSION => envelope_schemas(),
+        other => {
+            return Err(format!(
+                "Unsupported event schema version {} (supported: {}..={})",
+                other, LEGACY_SCHEMA_VERSION, EVENT_SCHEMA_VERSION
+            ))
+        }
+    };
+    Ok(json!({
+        "schema_version": version,
+        "current_version": EVENT_SCHEMA_VERSION,
+        "events": events,
+    }))
+}
@@ -0,0 +1,346 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+use crate::audio::text_merge;
+use crate::audio::vad::EnergyVad;
+use crate::{send_audio_chunk_to, TranscriptSegment, WHISPER_ENDPOINT, WHISPER_SAMPLE_RATE};
+
+/// VAD stage: whether chunks are gated before transcription.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VadVariant {
+    /// Send every chunk (the live pipeline's behaviour).
+    #[default]
+    None,
+    /// Skip chunks whose speech ratio is below `min_speech_ratio`.
+    Energy {
+        #[serde(default)]
+        vad: EnergyVad,
+        min_speech_ratio: f32,
+    },
+}
+
+/// Merge stage: how consecutive chunk transcripts are joined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeVariant {
+    /// Concatenate segments as returned by the engine.
+    #[default]
+    Concatenate,
+    /// Trim words repeated across the chunk boundary.
+    OverlapCleanup,
+}
+
+/// Stage overrides applied to the experimental (B) variant. Stages left as
+/// `None` behave like the live pipeline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StageOverrides {
+    pub vad: Option<VadVariant>,
+    pub merge: Option<MergeVariant>,
+    /// Alternative whisper server endpoint, e.g. one running a different model.
+    pub engine_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub name: String,
+    pub variant: StageOverrides,
+}
+
+/// One variant's output for a chunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantOutput {
+    pub text: String,
+    pub latency_ms: u64,
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+/// Baseline (A) and experimental (B) output for the same chunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentComparison {
+    pub experiment: String,
+    pub chunk_index: u64,
+    pub offset_ms: u64,
+    pub baseline: VariantOutput,
+    pub variant: VariantOutput,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentStatus {
+    pub config: Option<ExperimentConfig>,
+    pub chunks_compared: u64,
+    pub results_path: Option<String>,
+}
+
+struct ExperimentSession {
+    config: ExperimentConfig,
+    results: Option<(PathBuf, File)>,
+    started: Instant,
+    next_chunk: u64,
+    chunks_compared: u64,
+    /// Running transcript of the B variant, used for its merge stage.
+    variant_transcript: String,
+    /// Chunks whose variant finished ahead of an earlier chunk, held back so
+    /// the merge stage and the results see chunks in order.
+    unmerged: BTreeMap<u64, ExperimentComparison>,
+    /// The chunk the merge stage takes next.
+    next_merge: u64,
+}
+
+static EXPERIMENT: Lazy<Mutex<Option<ExperimentSession>>> = Lazy::new(|| Mutex::new(None));
+
+/// Join segment text the way the live accumulator cleans it.
+pub fn segments_text(segments: &[TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| {
+            segment
+                .text
+                .replace("[BLANK_AUDIO]", "")
+                .replace("[AUDIO OUT]", "")
+                .trim()
+                .to_string()
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Open the side-by-side results file for a new recording session.
+pub fn begin_session<R: Runtime>(app: &AppHandle<R>) {
+    let Ok(mut guard) = EXPERIMENT.lock() else {
+        return;
+    };
+    let Some(session) = guard.as_mut() else {
+        return;
+    };
+
+    session.started = Instant::now();
+    session.next_chunk = 0;
+    session.chunks_compared = 0;
+    session.variant_transcript.clear();
+    session.unmerged.clear();
+    session.next_merge = 0;
+    session.results = match app.path().app_data_dir() {
+        Ok(dir) => {
+            let path = dir.join("experiments").join(format!(
+                "{}-{}.jsonl",
+                session.config.name,
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            ));
+            let opened = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+            match opened {
+                Ok(file) => {
+                    info!("Recording experiment '{}' to {:?}", session.config.name, path);
+                    Some((path, file))
+                }
+                Err(e) => {
+                    warn!("Failed to open experiment results file: {}", e);
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            warn!("Failed to resolve app data directory for experiments: {}", e);
+            None
+        }
+    };
+}
+
+/// Reserve an index for the next chunk if an experiment is running.
+pub fn next_chunk() -> Option<(u64, u64)> {
+    let mut guard = EXPERIMENT.lock().ok()?;
+    let session = guard.as_mut()?;
+    let index = session.next_chunk;
+    session.next_chunk += 1;
+    Some((index, session.started.elapsed().as_millis() as u64))
+}
+
+/// Run the B variant on `samples` and record it next to the baseline output.
+/// Runs in the background so the live pipeline is never delayed by the experiment.
+pub fn compare<R: Runtime>(
+    app: AppHandle<R>,
+    chunk: (u64, u64),
+    samples: Vec<f32>,
+    baseline: VariantOutput,
+    client: reqwest::Client,
+) {
+    let Some(config) = EXPERIMENT
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|s| s.config.clone()))
+    else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let (chunk_index, offset_ms) = chunk;
+        let variant = run_variant(&config.variant, samples, &client).await;
+
+        let comparison = ExperimentComparison {
+            experiment: config.name.clone(),
+            chunk_index,
+            offset_ms,
+            baseline,
+            variant,
+        };
+
+        // Variants finish out of order; merge and record them in chunk order
+        let mut finished = Vec::new();
+        if let Ok(mut guard) = EXPERIMENT.lock() {
+            if let Some(session) = guard.as_mut().filter(|s| s.config.name == config.name) {
+                session.unmerged.insert(chunk_index, comparison);
+                while let Some(mut comparison) = session.unmerged.remove(&session.next_merge) {
+                    session.next_merge += 1;
+                    session.chunks_compared += 1;
+                    let variant = &mut comparison.variant;
+                    if config.variant.merge == Some(MergeVariant::OverlapCleanup) && !variant.skipped && variant.error.is_none() {
+                        variant.text = merge_with_previous(&mut session.variant_transcript, std::mem::take(&mut variant.text));
+                    }
+                    if let Some((_, file)) = session.results.as_mut() {
+                        let written = serde_json::to_string(&comparison)
+                            .map_err(|e| e.to_string())
+                            .and_then(|line| writeln!(file, "{}", line).map_err(|e| e.to_string()));
+                        if let Err(e) = written {
+                            error!("Failed to write experiment result: {}", e);
+                        }
+                    }
+                    finished.push(comparison);
+                }
+            }
+        }
+
+        for comparison in finished {
+            if let Err(e) = app.emit("experiment-result", comparison) {
+                error!("Failed to emit experiment result: {}", e);
+            }
+        }
+    });
+}
+
+async fn run_variant(overrides: &StageOverrides, samples: Vec<f32>, client: &reqwest::Client) -> VariantOutput {
+    let started = Instant::now();
+
+    if let Some(VadVariant::Energy { vad, min_speech_ratio }) = &overrides.vad {
+        if vad.speech_ratio(&samples, WHISPER_SAMPLE_RATE) < *min_speech_ratio {
+            return VariantOutput {
+                text: String::new(),
+                latency_ms: started.elapsed().as_millis() as u64,
+                skipped: true,
+                error: None,
+            };
+        }
+    }
+
+    let endpoint = overrides.engine_endpoint.as_deref().unwrap_or(WHISPER_ENDPOINT);
+    // The merge stage runs later, once the chunks before this one are in
+    match send_audio_chunk_to(samples, client, endpoint).await {
+        Ok(response) => VariantOutput {
+            text: segments_text(&response.segments),
+            latency_ms: started.elapsed().as_millis() as u64,
+            skipped: false,
+            error: None,
+        },
+        Err(e) => VariantOutput {
+            text: String::new(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            skipped: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Trim the overlap between the variant's running transcript and `text`.
+fn merge_with_previous(transcript: &mut String, text: String) -> String {
+    let merged = match text_merge::cleanup_overlap(transcript, &text) {
+        Some((_, current)) => current,
+        None => text,
+    };
+    if !transcript.is_empty() {
+        transcript.push(' ');
+    }
+    transcript.push_str(&merged);
+    merged
+}
+
+fn status() -> ExperimentStatus {
+    let guard = EXPERIMENT.lock().ok();
+    let session = guard.as_ref().and_then(|g| g.as_ref());
+    ExperimentStatus {
+        config: session.map(|s| s.config.clone()),
+        chunks_compared: session.map_or(0, |s| s.chunks_compared),
+        results_path: session
+            .and_then(|s| s.results.as_ref())
+            .map(|(path, _)| path.to_string_lossy().to_string()),
+    }
+}
+
+/// Enable an experiment. It applies from the next recording session, or
+/// immediately if a session is already running.
+#[command]
+pub fn start_experiment<R: Runtime>(app: AppHandle<R>, config: ExperimentConfig) -> Result<ExperimentStatus, String> {
+    if config.name.trim().is_empty()
+        || !config
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Experiment name must be non-empty and use only letters, digits, '-' or '_'".to_string());
+    }
+
+    {
+        let mut guard = EXPERIMENT
+            .lock()
+            .map_err(|e| format!("Failed to lock experiment state: {}", e))?;
+        info!("Starting experiment '{}' with overrides {:?}", config.name, config.variant);
+        *guard = Some(ExperimentSession {
+            config,
+            results: None,
+            started: Instant::now(),
+            next_chunk: 0,
+            chunks_compared: 0,
+            variant_transcript: String::new(),
+            unmerged: BTreeMap::new(),
+            next_merge: 0,
+        });
+    }
+
+    if crate::is_recording() {
+        begin_session(&app);
+    }
+    Ok(status())
+}
+
+#[command]
+pub fn stop_experiment() -> Result<ExperimentStatus, String> {
+    let final_status = status();
+    let mut guard = EXPERIMENT
+        .lock()
+        .map_err(|e| format!("Failed to lock experiment state: {}", e))?;
+    if let Some(session) = guard.take() {
+        info!(
+            "Stopped experiment '{}' after {} chunks",
+            session.config.name, session.chunks_compared
+        );
+    }
+    Ok(final_status)
+}
+
+#[command]
+pub fn get_experiment_status() -> ExperimentStatus {
+    status()
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::{CloudProvider, CloudTarget};
+use crate::secrets;
+
+/// Attempts per object before the upload is marked failed.
+pub const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY_MS: u64 = 2000;
+const UPLOAD_TIMEOUT_SECS: u64 = 300;
+
+/// HMAC access key pair stored in the secrets vault as JSON. GCS buckets use
+/// interoperability (HMAC) keys, which it accepts with AWS Signature V4.
+#[derive(Deserialize)]
+struct AccessKeys {
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Percent-encode a path per SigV4: everything but unreserved characters and `/`.
+fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn host_and_region(target: &CloudTarget) -> (String, String) {
+    let region = target.region.clone().unwrap_or_else(|| match target.provider {
+        CloudProvider::S3 => "us-east-1".to_string(),
+        CloudProvider::Gcs => "auto".to_string(),
+    });
+    let host = match (&target.endpoint, target.provider) {
+        (Some(endpoint), _) => endpoint
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/')
+            .to_string(),
+        (None, CloudProvider::S3) => format!("s3.{}.amazonaws.com", region),
+        (None, CloudProvider::Gcs) => "storage.googleapis.com".to_string(),
+    };
+    (host, region)
+}
+
+/// Sign a path-style `PUT` of `body` to `key` with AWS Signature V4.
+fn authorization(keys: &AccessKeys, host: &str, region: &str, uri: &str, payload_hash: &str, amz_date: &str) -> String {
+    let date = &amz_date[..8];
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        uri, host, payload_hash, amz_date, signed_headers, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let signing_key = [region.as_bytes(), b"s3", b"aws4_request"].iter().fold(
+        hmac_sha256(format!("AWS4{}", keys.secret_access_key).as_bytes(), date.as_bytes()),
+        |key, part| hmac_sha256(&key, part),
+    );
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        keys.access_key_id, scope, signed_headers, signature
+    )
+}
+
+enum Failure {
+    /// Worth another attempt (network error, throttling, server error).
+    Transient(String),
+    Permanent(String),
+}
+
+async fn put_once(
+    client: &reqwest::Client,
+    target: &CloudTarget,
+    keys: &AccessKeys,
+    key: &str,
+    body: &[u8],
+    content_type: &str,
+) -> Result<(), Failure> {
+    let (host, region) = host_and_region(target);
+    let uri = encode_path(&format!("/{}/{}", target.bucket, key));
+    let payload_hash = sha256_hex(body);
+    let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let scheme = match &target.endpoint {
+        Some(endpoint) if endpoint.starts_with("http://") => "http",
+        _ => "https",
+    };
+
+    let response = client
+        .put(format!("{}://{}{}", scheme, host, uri))
+        .header("x-amz-date", &amz_date)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("content-type", content_type)
+        .header(
+            "authorization",
+            authorization(keys, &host, &region, &uri, &payload_hash, &amz_date),
+        )
+        .body(body.to_vec())
+        .send()
+        .await
+        .map_err(|e| Failure::Transient(format!("Upload request failed: {}", e)))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let detail = response.text().await.unwrap_or_default();
+    let message = format!("Bucket returned {}: {}", status, detail.trim());
+    if status.is_server_error() || status.as_u16() == 429 {
+        Err(Failure::Transient(message))
+    } else {
+        Err(Failure::Permanent(message))
+    }
+}
+
+/// Upload `body` to `key` in the target bucket, retrying transient failures
+/// with exponential backoff. Returns the number of attempts made.
+pub async fn upload(target: &CloudTarget, key: &str, body: &[u8], content_type: &str) -> (u32, Result<(), String>) {
+    let keys = match secrets::require(&target.credentials_secret)
+        .and_then(|raw| serde_json::from_str::<AccessKeys>(&raw).map_err(|e| format!("Invalid bucket credentials: {}", e)))
+    {
+        Ok(keys) => keys,
+        Err(e) => return (0, Err(e)),
+    };
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(UPLOAD_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return (0, Err(format!("Failed to create HTTP client: {}", e))),
+    };
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match put_once(&client, target, &keys, key, body, content_type).await {
+            Ok(()) => {
+                info!("Uploaded {} to {} ({} bytes)", key, target.name, body.len());
+                return (attempt, Ok(()));
+            }
+            Err(Failure::Permanent(e)) => return (attempt, Err(e)),
+            Err(Failure::Transient(e)) if attempt >= MAX_ATTEMPTS => return (attempt, Err(e)),
+            Err(Failure::Transient(e)) => {
+                let delay = RETRY_BASE_DELAY_MS * 2u64.pow(attempt - 1);
+                warn!("Upload of {} to {} failed ({}), retrying in {} ms", key, target.name, e, delay);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{SecondsFormat, Utc};
+use flate2::Crc;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::recluster::{self, SegmentAssignment, ASSIGNMENTS_FILE, CONSTRAINTS_FILE};
+use crate::{settings, speaker_profiles, storage};
+
+/// Consent to export voice embeddings for research. Exports contain no audio,
+/// no text and no names, but embeddings are still biometric data, so they're
+/// off until the user turns them on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResearchExportSettings {
+    pub consented: bool,
+    /// RFC 3339, when consent was last given.
+    pub consented_at: Option<String>,
+}
+
+/// What went into an embedding export.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingExport {
+    pub path: String,
+    pub meetings: usize,
+    pub speakers: usize,
+    pub segments: usize,
+    pub dimensions: usize,
+}
+
+/// Anonymous speaker ids: named speakers keep one id across meetings, while
+/// numbered placeholders only mean something within their own meeting.
+#[derive(Default)]
+struct SpeakerIds {
+    ids: HashMap<(Option<usize>, String), i32>,
+}
+
+impl SpeakerIds {
+    fn id(&mut self, meeting: usize, speaker: &str) -> i32 {
+        let scope = speaker_profiles::is_unnamed(speaker).then_some(meeting);
+        let next = self.ids.len() as i32;
+        *self.ids.entry((scope, speaker.to_string())).or_insert(next)
+    }
+}
+
+/// A `.npy` file: the version 1.0 header padded to 64 bytes, then the data
+/// in little-endian C order.
+fn npy(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        _ => format!("({})", shape.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut file = Vec::with_capacity(10 + header.len() + data.len());
+    file.extend_from_slice(b"\x93NUMPY\x01\x00");
+    file.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    file.extend_from_slice(header.as_bytes());
+    file.extend_from_slice(data);
+    file
+}
+
+/// An `.npz` archive: an uncompressed zip of `.npy` files, as `numpy.savez` writes.
+fn npz(arrays: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in arrays {
+        let name = format!("{}.npy", name);
+        let mut crc = Crc::new();
+        crc.update(data);
+        let offset = archive.len() as u32;
+        // Version, flags, method (stored), time, date, CRC, sizes, name length, extra length
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0x21u16.to_le_bytes());
+        fields.extend_from_slice(&crc.sum().to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        archive.extend_from_slice(&fields);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&fields);
+        // Comment length, disk, internal and external attributes
+        directory.extend_from_slice(&[0u8; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0u8; 4]);
+    archive.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive
+}
+
+fn le_bytes<T: Copy, const N: usize>(values: &[T], to_bytes: impl Fn(T) -> [u8; N]) -> Vec<u8> {
+    values.iter().flat_map(|&value| to_bytes(value)).collect()
+}
+
+/// Give or withdraw consent to export voice embeddings.
+#[command]
+pub fn set_research_export_consent(consented: bool) -> Result<ResearchExportSettings, String> {
+    settings::update(|s| {
+        s.export.research = ResearchExportSettings {
+            consented,
+            consented_at: consented.then(|| Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+        }
+    })
+    .map(|s| s.export.research)
+}
+
+/// Write the voice embeddings of stored meetings on this machine, with each
+/// segment's duration and anonymous speaker and meeting ids, to an `.npz`
+/// file at `path`: `embeddings` (float32, segments × dimensions),
+/// `durations` (float32 seconds), `speakers` and `meetings` (int32; speaker
+/// -1 when the segment was never attributed). Only meetings with a raw
+/// capture can be embedded; `meeting_ids` limits the export to some of them.
+#[command]
+pub async fn export_speaker_embeddings<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    meeting_ids: Option<Vec<String>>,
+) -> Result<EmbeddingExport, String> {
+    if !settings::get().export.research.consented {
+        return Err("Consent to export voice embeddings hasn't been given".to_string());
+    }
+    let path = Path::new(&path);
+    if !path.is_absolute() || !path.parent().is_some_and(Path::is_dir) {
+        return Err(format!("{} is not a path in an existing local folder", path.display()));
+    }
+
+    let meetings: Vec<String> = match meeting_ids {
+        Some(ids) => ids,
+        None => storage::list_stored_meetings(app.clone())?
+            .into_iter()
+            .map(|manifest| manifest.id)
+            .collect(),
+    };
+    let mut speaker_ids = SpeakerIds::default();
+    let (mut embeddings, mut durations, mut speakers, mut meeting_numbers) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut dimensions = 0;
+    let mut exported_meetings = 0;
+    for meeting_id in &meetings {
+        let dir = storage::meeting_dir(&app, meeting_id)?;
+        let raw = storage::raw_session_path(&dir);
+        if !raw.exists() {
+            continue;
+        }
+        let constraints = recluster::read_derived(&dir, CONSTRAINTS_FILE).unwrap_or_default();
+        let assigned: HashMap<(u64, usize), String> =
+            recluster::read_derived::<Vec<SegmentAssignment>>(&dir, ASSIGNMENTS_FILE)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| ((a.chunk, a.segment), recluster::canonical(&a.speaker, &constraints)))
+                .collect();
+
+        let meeting = exported_meetings;
+        let mut any = false;
+        for segment in recluster::embed_segments(&raw).await? {
+            let Some(embedding) = segment.embedding else { continue };
+            if dimensions == 0 {
+                dimensions = embedding.len();
+            }
+            if embedding.len() != dimensions {
+                continue;
+            }
+            embeddings.extend(embedding);
+            durations.push(segment.duration_secs);
+            speakers.push(match assigned.get(&(segment.chunk, segment.segment)) {
+                Some(speaker) => speaker_ids.id(meeting, speaker),
+                None => -1,
+            });
+            meeting_numbers.push(meeting as i32);
+            any = true;
+        }
+        if any {
+            exported_meetings += 1;
+        }
+    }
+    if durations.is_empty() {
+        return Err("No stored meeting has speech to export".to_string());
+    }
+
+    let segments = durations.len();
+    let archive = npz(&[
+        ("embeddings", npy("<f4", &[segments, dimensions], &le_bytes(&embeddings, f32::to_le_bytes))),
+        ("durations", npy("<f4", &[segments], &le_bytes(&durations, f32::to_le_bytes))),
+        ("speakers", npy("<i4", &[segments], &le_bytes(&speakers, i32::to_le_bytes))),
+        ("meetings", npy("<i4", &[segments], &le_bytes(&meeting_numbers, i32::to_le_bytes))),
+    ]);
+    let mut file = fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(&archive)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    info!(
+        "Exported {} voice embeddings from {} meetings to {}",
+        segments,
+        exported_meetings,
+        path.display()
+    );
+    Ok(EmbeddingExport {
+        path: path.to_string_lossy().to_string(),
+        meetings: exported_meetings,
+        speakers: speaker_ids.ids.len(),
+        segments,
+        dimensions,
+    })
+}
@@ -0,0 +1,461 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+use crate::audio::decode::decode_audio_file;
+use crate::audio::voice_mask::{self, VoiceMaskSettings};
+use crate::{encryption, settings};
+use crate::transcription::{encode_wav, AudioInput};
+
+pub mod cloud;
+pub mod embeddings;
+pub mod naming;
+pub mod rules;
+pub mod subtitles;
+
+use embeddings::ResearchExportSettings;
+use naming::{ExportMeeting, NamingSettings};
+
+const STATUS_FILE: &str = "upload_status.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Transcript,
+    Summary,
+    Audio,
+}
+
+/// One file produced for a meeting. Either `content` (text produced in the
+/// webview) or `path` (a file on disk, e.g. the recording) must be set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportArtifact {
+    pub kind: ArtifactKind,
+    pub file_name: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl ExportArtifact {
+    fn bytes(&self) -> Result<Vec<u8>, String> {
+        match (&self.content, &self.path) {
+            (Some(content), _) => Ok(content.as_bytes().to_vec()),
+            (None, Some(path)) => encryption::read(path).map_err(|e| format!("Failed to read {}: {}", path, e)),
+            (None, None) => Err(format!("Artifact {} has neither content nor path", self.file_name)),
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match Path::new(&self.file_name).extension().and_then(|e| e.to_str()) {
+            Some("json") => "application/json",
+            Some("md") => "text/markdown; charset=utf-8",
+            Some("txt") | Some("srt") => "text/plain; charset=utf-8",
+            Some("vtt") => "text/vtt; charset=utf-8",
+            Some("wav") => "audio/wav",
+            Some("mp4") | Some("m4a") => "audio/mp4",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudProvider {
+    S3,
+    Gcs,
+}
+
+/// A bucket finished meetings are uploaded to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudTarget {
+    pub id: String,
+    pub name: String,
+    pub provider: CloudProvider,
+    pub bucket: String,
+    /// Defaults to `us-east-1` for S3 and `auto` for GCS.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// S3-compatible endpoint override (MinIO, R2, ...).
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Key prefix inside the bucket, e.g. `meetings/`.
+    #[serde(default)]
+    pub prefix: String,
+    /// Recordings are large; they are only uploaded when enabled.
+    #[serde(default)]
+    pub include_audio: bool,
+    /// Secrets vault entry holding `{"access_key_id", "secret_access_key"}`.
+    pub credentials_secret: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    pub cloud_targets: Vec<CloudTarget>,
+    /// Default folder for local exports.
+    pub folder: Option<String>,
+    pub naming: NamingSettings,
+    pub rules: Vec<rules::AutoExportRule>,
+    /// Only changed through `set_research_export_consent`.
+    pub research: ResearchExportSettings,
+    /// Disguise voices in exported audio.
+    pub voice_mask: VoiceMaskSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadState {
+    Pending,
+    Uploaded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub target_id: String,
+    pub artifact: ArtifactKind,
+    pub key: String,
+    pub state: UploadState,
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UploadStatusEvent<'a> {
+    meeting_id: &'a str,
+    record: &'a UploadRecord,
+}
+
+/// Serializes read-modify-write of the status file across concurrent exports.
+static STATUS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn status_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("exports").join(STATUS_FILE))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn load_status(path: &Path) -> BTreeMap<String, Vec<UploadRecord>> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// `record_status`, logging a failure: the status file is bookkeeping and
+/// mustn't stop the uploads themselves.
+fn save_status<R: Runtime>(app: &AppHandle<R>, meeting_id: &str, record: &UploadRecord) {
+    if let Err(e) = record_status(app, meeting_id, record) {
+        warn!("Failed to save upload status for {}: {}", record.key, e);
+    }
+}
+
+/// Insert or replace the record for (target, key) under `meeting_id` and persist it.
+fn record_status<R: Runtime>(app: &AppHandle<R>, meeting_id: &str, record: &UploadRecord) -> Result<(), String> {
+    let path = status_path(app)?;
+    let _guard = STATUS_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock upload status: {}", e))?;
+    let mut status = load_status(&path);
+    let records = status.entry(meeting_id.to_string()).or_default();
+    match records
+        .iter_mut()
+        .find(|r| r.target_id == record.target_id && r.key == record.key)
+    {
+        Some(existing) => *existing = record.clone(),
+        None => records.push(record.clone()),
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create exports directory: {}", e))?;
+    }
+    let content =
+        serde_json::to_string_pretty(&status).map_err(|e| format!("Failed to serialize upload status: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write upload status: {}", e))?;
+
+    let _ = app.emit("export-upload-status", UploadStatusEvent { meeting_id, record });
+    Ok(())
+}
+
+/// Decode `source`, shift its voices and encode the result as WAV.
+fn masked_wav(source: &Path, semitones: f32) -> Result<Vec<u8>, String> {
+    let (samples, sample_rate) =
+        decode_audio_file(source).map_err(|e| format!("Failed to decode {}: {}", source.display(), e))?;
+    encode_wav(&AudioInput {
+        samples: voice_mask::mask(&samples, semitones),
+        sample_rate,
+        language: None,
+        device: None,
+    })
+}
+
+/// Numbers masked copies, so exports running at once don't share a file.
+static MASKED_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// Masked copies of audio artifacts written for one export, removed afterwards.
+#[derive(Default)]
+struct MaskedFiles(Vec<PathBuf>);
+
+impl Drop for MaskedFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// The artifacts to export: audio is swapped for a voice-masked WAV copy when
+/// masking is on, so the stored recording itself is never altered.
+fn with_masked_audio(artifacts: &[ExportArtifact]) -> Result<(Vec<ExportArtifact>, MaskedFiles), String> {
+    let mask = settings::get().export.voice_mask;
+    let mut masked = MaskedFiles::default();
+    if !mask.enabled {
+        return Ok((artifacts.to_vec(), masked));
+    }
+    let mut prepared = Vec::with_capacity(artifacts.len());
+    for artifact in artifacts {
+        let source = match (&artifact.kind, &artifact.path) {
+            (ArtifactKind::Audio, Some(source)) => source,
+            _ => {
+                prepared.push(artifact.clone());
+                continue;
+            }
+        };
+        let wav = masked_wav(Path::new(source), mask.semitones)?;
+        let path = std::env::temp_dir().join(format!(
+            "meetingly-masked-{}-{}.wav",
+            std::process::id(),
+            MASKED_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&path, wav).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        masked.0.push(path.clone());
+        prepared.push(ExportArtifact {
+            file_name: Path::new(&artifact.file_name)
+                .with_extension("wav")
+                .to_string_lossy()
+                .to_string(),
+            content: None,
+            path: Some(path.to_string_lossy().to_string()),
+            ..artifact.clone()
+        });
+    }
+    Ok((prepared, masked))
+}
+
+/// Bucket keys use the naming template too; existing objects are overwritten.
+fn object_key(target: &CloudTarget, meeting: &ExportMeeting, artifact: &ExportArtifact) -> Result<String, String> {
+    let name = naming::render(&settings::get().export.naming.template, meeting, artifact)?;
+    Ok(format!("{}{}", target.prefix, name))
+}
+
+/// Write a meeting's artifacts into `folder`, named by the configured template
+/// and collision policy. Returns the paths written; skipped artifacts are omitted.
+pub fn export_to_folder(meeting: &ExportMeeting, artifacts: &[ExportArtifact], folder: &Path) -> Result<Vec<PathBuf>, String> {
+    let naming = settings::get().export.naming;
+    let (artifacts, _masked) = with_masked_audio(artifacts)?;
+    let mut written = Vec::new();
+    for artifact in &artifacts {
+        let relative = naming::render(&naming.template, meeting, artifact)?;
+        let Some(path) = naming::resolve_collision(folder, &relative, naming.collision)? else {
+            info!("Skipping export of {}, it already exists", relative);
+            continue;
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create export folder: {}", e))?;
+        }
+        let result = match (&artifact.content, &artifact.path) {
+            (Some(content), _) => fs::write(&path, content),
+            // Stored recordings may be encrypted; exports never are
+            (None, Some(source)) => encryption::read(source).and_then(|bytes| fs::write(&path, bytes)),
+            (None, None) => return Err(format!("Artifact {} has neither content nor path", artifact.file_name)),
+        };
+        result.map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        info!("Exported {:?}", path);
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Upload a meeting's artifacts to every enabled cloud target (or only
+/// `target_ids`), tracking per-object status. Failures are recorded rather
+/// than aborting the remaining uploads.
+pub async fn export_to_cloud<R: Runtime>(
+    app: &AppHandle<R>,
+    meeting: &ExportMeeting,
+    artifacts: &[ExportArtifact],
+    target_ids: Option<&[String]>,
+) -> Result<Vec<UploadRecord>, String> {
+    let meeting_id = meeting.id.as_str();
+    let targets: Vec<CloudTarget> = settings::get()
+        .export
+        .cloud_targets
+        .into_iter()
+        .filter(|t| t.enabled)
+        .filter(|t| match target_ids {
+            Some(ids) => ids.contains(&t.id),
+            None => true,
+        })
+        .collect();
+    if targets.is_empty() {
+        return Err("No enabled cloud export targets".to_string());
+    }
+
+    let (artifacts, _masked) = with_masked_audio(artifacts)?;
+    let mut results = Vec::new();
+    for target in &targets {
+        for artifact in &artifacts {
+            if artifact.kind == ArtifactKind::Audio && !target.include_audio {
+                continue;
+            }
+            let mut record = UploadRecord {
+                target_id: target.id.clone(),
+                artifact: artifact.kind,
+                key: object_key(target, meeting, artifact)?,
+                state: UploadState::Pending,
+                attempts: 0,
+                error: None,
+                updated_at: chrono::Utc::now().to_rfc3339(),
+            };
+            save_status(app, meeting_id, &record);
+
+            let (attempts, outcome) = match artifact.bytes() {
+                Ok(body) => cloud::upload(target, &record.key, &body, artifact.content_type()).await,
+                Err(e) => (0, Err(e)),
+            };
+            record.attempts = attempts;
+            record.updated_at = chrono::Utc::now().to_rfc3339();
+            match outcome {
+                Ok(()) => record.state = UploadState::Uploaded,
+                Err(e) => {
+                    error!("Failed to upload {} to {}: {}", record.key, target.name, e);
+                    record.state = UploadState::Failed;
+                    record.error = Some(e);
+                }
+            }
+            save_status(app, meeting_id, &record);
+            results.push(record);
+        }
+    }
+
+    info!(
+        "Cloud export of meeting {}: {}/{} objects uploaded",
+        meeting_id,
+        results.iter().filter(|r| r.state == UploadState::Uploaded).count(),
+        results.len()
+    );
+    Ok(results)
+}
+
+#[command]
+pub fn get_export_settings() -> ExportSettings {
+    settings::get().export
+}
+
+#[command]
+pub fn set_export_settings(export_settings: ExportSettings) -> Result<ExportSettings, String> {
+    let sample = ExportMeeting {
+        id: "preview".to_string(),
+        title: None,
+        started_at: None,
+        tags: Vec::new(),
+        metadata: Default::default(),
+    };
+    let sample_artifact = ExportArtifact {
+        kind: ArtifactKind::Transcript,
+        file_name: "transcript.txt".to_string(),
+        content: None,
+        path: None,
+    };
+    naming::render(&export_settings.naming.template, &sample, &sample_artifact)?;
+    if export_settings.voice_mask.semitones.abs() > voice_mask::MAX_SEMITONES {
+        return Err(format!("Voice masking shifts at most {} semitones", voice_mask::MAX_SEMITONES));
+    }
+    for target in &export_settings.cloud_targets {
+        if target.id.trim().is_empty() || target.bucket.trim().is_empty() {
+            return Err(format!("Cloud target '{}' needs an id and a bucket", target.name));
+        }
+    }
+    settings::update(|s| {
+        let research = std::mem::take(&mut s.export.research);
+        s.export = ExportSettings {
+            research,
+            ..export_settings
+        }
+    })
+    .map(|s| s.export)
+}
+
+#[command]
+pub async fn export_meeting<R: Runtime>(
+    app: AppHandle<R>,
+    meeting: ExportMeeting,
+    artifacts: Vec<ExportArtifact>,
+    target_ids: Option<Vec<String>>,
+) -> Result<Vec<UploadRecord>, String> {
+    export_to_cloud(&app, &meeting, &artifacts, target_ids.as_deref()).await
+}
+
+/// Write a voice-masked WAV copy of an audio file, e.g. a clip to share.
+/// Uses the configured shift unless `semitones` is given.
+#[command]
+pub fn export_masked_audio(source: String, destination: String, semitones: Option<f32>) -> Result<String, String> {
+    let semitones = semitones.unwrap_or(settings::get().export.voice_mask.semitones);
+    if semitones.abs() > voice_mask::MAX_SEMITONES {
+        return Err(format!("Voice masking shifts at most {} semitones", voice_mask::MAX_SEMITONES));
+    }
+    if Path::new(&source) == Path::new(&destination) {
+        return Err("Masked audio must not replace the original".to_string());
+    }
+    let wav = masked_wav(Path::new(&source), semitones)?;
+    fs::write(&destination, wav).map_err(|e| format!("Failed to write {}: {}", destination, e))?;
+    info!("Exported voice-masked {} to {}", source, destination);
+    Ok(destination)
+}
+
+/// Write a meeting's artifacts to `folder` (or the configured export folder).
+#[command]
+pub fn export_meeting_to_folder(
+    meeting: ExportMeeting,
+    artifacts: Vec<ExportArtifact>,
+    folder: Option<String>,
+) -> Result<Vec<String>, String> {
+    let folder = folder
+        .or_else(|| settings::get().export.folder)
+        .ok_or_else(|| "No export folder configured".to_string())?;
+    export_to_folder(&meeting, &artifacts, Path::new(&folder))
+        .map(|paths| paths.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
+
+/// Render a naming template without writing anything, for the settings UI.
+#[command]
+pub fn preview_export_name(
+    template: String,
+    meeting: ExportMeeting,
+    artifact: ExportArtifact,
+) -> Result<String, String> {
+    naming::render(&template, &meeting, &artifact)
+}
+
+/// Per-object upload status for a meeting, as last persisted.
+#[command]
+pub fn get_export_status<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<Vec<UploadRecord>, String> {
+    let path = status_path(&app)?;
+    Ok(load_status(&path).remove(&meeting_id).unwrap_or_default())
+}
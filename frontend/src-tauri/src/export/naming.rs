@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::{ArtifactKind, ExportArtifact};
+
+pub const DEFAULT_TEMPLATE: &str = "{date}_{title}_{type}.{ext}";
+/// Give up on `_N` suffixes after this many collisions.
+const MAX_SUFFIX: u32 = 999;
+
+/// What to do when a rendered file name already exists in the export folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// Append `_1`, `_2`, ... before the extension.
+    #[default]
+    Suffix,
+    Overwrite,
+    /// Leave the existing file alone and skip this artifact.
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamingSettings {
+    /// Placeholders: `{date}`, `{time}`, `{title}`, `{meeting_id}`, `{type}`, `{ext}`,
+    /// `{tags}` and `{meta:<key>}` for a custom metadata field.
+    /// `/` creates subfolders, e.g. `{date}/{title}_{type}.{ext}`.
+    pub template: String,
+    pub collision: CollisionPolicy,
+}
+
+impl Default for NamingSettings {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_TEMPLATE.to_string(),
+            collision: CollisionPolicy::Suffix,
+        }
+    }
+}
+
+/// The meeting an export belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportMeeting {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// RFC 3339; the export time is used when absent.
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Custom fields such as client name or project code.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl ArtifactKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactKind::Transcript => "transcript",
+            ArtifactKind::Summary => "summary",
+            ArtifactKind::Audio => "audio",
+        }
+    }
+}
+
+/// Make a value safe to use as a single path component: separators and
+/// characters that are invalid on common filesystems become `_`.
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_whitespace() || c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches(['.', '_']).to_string();
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned
+    }
+}
+
+fn extension(artifact: &ExportArtifact) -> String {
+    Path::new(&artifact.file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "txt".to_string())
+}
+
+/// Render `template` for one artifact of `meeting`. Returns a relative path.
+pub fn render(template: &str, meeting: &ExportMeeting, artifact: &ExportArtifact) -> Result<String, String> {
+    let started = meeting
+        .started_at
+        .as_deref()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Local))
+        .unwrap_or_else(Local::now);
+
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| format!("Unclosed placeholder in template '{}'", template))?;
+        let value = match &rest[open + 1..close] {
+            "date" => started.format("%Y-%m-%d").to_string(),
+            "time" => started.format("%H%M").to_string(),
+            "title" => sanitize(meeting.title.as_deref().unwrap_or("Untitled meeting")),
+            "meeting_id" => sanitize(&meeting.id),
+            "type" => artifact.kind.as_str().to_string(),
+            "ext" => extension(artifact),
+            "tags" if meeting.tags.is_empty() => "untagged".to_string(),
+            "tags" => sanitize(&meeting.tags.join("-")),
+            other if other.starts_with("meta:") => {
+                sanitize(meeting.metadata.get(&other["meta:".len()..]).map(String::as_str).unwrap_or(""))
+            }
+            other => return Err(format!("Unknown placeholder '{{{}}}' in template", other)),
+        };
+        rendered.push_str(&value);
+        rest = &rest[close + 1..];
+    }
+    rendered.push_str(rest);
+
+    // Subfolders are allowed, escaping the export folder is not, with either
+    // separator on Windows
+    let unusable = || format!("Template '{}' does not produce a usable file name", template);
+    let mut parts: Vec<String> = Vec::new();
+    for component in Path::new(&rendered).components() {
+        match component {
+            Component::Normal(part) => {
+                let part = part.to_string_lossy();
+                let part = part.trim();
+                if part == ".." {
+                    return Err(unusable());
+                }
+                if !part.is_empty() && part != "." {
+                    parts.push(part.to_string());
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return Err(unusable()),
+        }
+    }
+    if parts.is_empty() {
+        return Err(unusable());
+    }
+    Ok(parts.join("/"))
+}
+
+/// Where to write `relative` inside `folder` under `policy`, or `None` to skip.
+pub fn resolve_collision(folder: &Path, relative: &str, policy: CollisionPolicy) -> Result<Option<PathBuf>, String> {
+    let path = folder.join(relative);
+    if !path.exists() {
+        return Ok(Some(path));
+    }
+    match policy {
+        CollisionPolicy::Overwrite => Ok(Some(path)),
+        CollisionPolicy::Skip => Ok(None),
+        CollisionPolicy::Suffix => {
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let ext = path.extension().map(|e| e.to_string_lossy().to_string());
+            (1..=MAX_SUFFIX)
+                .map(|n| {
+                    let name = match &ext {
+                        Some(ext) => format!("{}_{}.{}", stem, n, ext),
+                        None => format!("{}_{}", stem, n),
+                    };
+                    path.with_file_name(name)
+                })
+                .find(|candidate| !candidate.exists())
+                .map(Some)
+                .ok_or_else(|| format!("Too many existing exports named like {:?}", path))
+        }
+    }
+}
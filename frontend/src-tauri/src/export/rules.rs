@@ -0,0 +1,546 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Manager, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::naming::ExportMeeting;
+use super::{export_to_cloud, export_to_folder, ArtifactKind, ExportArtifact, UploadState};
+use crate::chapters::{self, ChapterPreview};
+use crate::notifications::{self, NotificationCategory};
+use crate::paragraphs::{paragraphs, Paragraph};
+use crate::pipeline::{self, PipelineEvent};
+use crate::{secrets, settings, storage, transcript_stream, TranscriptUpdate};
+
+const HISTORY_FILE: &str = "rule_runs.json";
+/// Oldest runs are dropped beyond this.
+const MAX_HISTORY: usize = 500;
+const SLACK_TIMEOUT_SECS: u64 = 30;
+
+/// When a rule is evaluated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleTrigger {
+    /// As soon as recording stops; only the transcript is available.
+    #[default]
+    MeetingEnd,
+    /// When the frontend reports the summary via `report_summary_ready`.
+    SummaryReady,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    /// Minutes: summary (when available) followed by the transcript.
+    #[default]
+    Markdown,
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConditions {
+    pub min_duration_minutes: Option<u32>,
+    /// Case-insensitive substring of the meeting title.
+    pub title_contains: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Write a document to `folder` (or the default export folder).
+    ExportFolder {
+        #[serde(default)]
+        folder: Option<String>,
+        #[serde(default)]
+        format: DocumentFormat,
+    },
+    /// Upload a document to cloud targets (all enabled targets when empty).
+    UploadCloud {
+        #[serde(default)]
+        target_ids: Vec<String>,
+        #[serde(default)]
+        format: DocumentFormat,
+    },
+    /// Post the summary (or the opening of the transcript) to a Slack
+    /// incoming webhook whose URL is stored in the secrets vault.
+    PostSlack {
+        webhook_secret: String,
+        #[serde(default)]
+        channel: Option<String>,
+    },
+}
+
+impl RuleAction {
+    fn describe(&self) -> String {
+        match self {
+            RuleAction::ExportFolder { folder, format } => {
+                format!("export {:?} to {}", format, folder.as_deref().unwrap_or("default folder"))
+            }
+            RuleAction::UploadCloud { format, .. } => format!("upload {:?} to cloud", format),
+            RuleAction::PostSlack { channel, .. } => {
+                format!("post to Slack{}", channel.as_deref().map(|c| format!(" {}", c)).unwrap_or_default())
+            }
+        }
+    }
+}
+
+/// "After every meeting longer than 15 minutes, export Markdown minutes to
+/// folder X and post the summary to Slack."
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoExportRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub trigger: RuleTrigger,
+    #[serde(default)]
+    pub conditions: RuleConditions,
+    pub actions: Vec<RuleAction>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionOutcome {
+    pub action: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleRun {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub meeting_id: String,
+    pub trigger: RuleTrigger,
+    pub ran_at: String,
+    pub success: bool,
+    pub outcomes: Vec<ActionOutcome>,
+}
+
+/// A finished recording as seen by the backend.
+#[derive(Debug, Clone)]
+pub struct FinishedMeeting {
+    pub meeting: ExportMeeting,
+    pub duration_secs: u64,
+    pub transcript: Vec<TranscriptUpdate>,
+    pub summary: Option<String>,
+}
+
+#[derive(Default)]
+struct SessionCapture {
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// When recording stopped; the transcript keeps growing until the
+    /// session completes.
+    stopped_at: Option<chrono::DateTime<chrono::Utc>>,
+    transcript: Vec<TranscriptUpdate>,
+}
+
+static CAPTURE: Lazy<Mutex<SessionCapture>> = Lazy::new(|| Mutex::new(SessionCapture::default()));
+static LAST_MEETING: Lazy<Mutex<Option<FinishedMeeting>>> = Lazy::new(|| Mutex::new(None));
+static HISTORY_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn matches(conditions: &RuleConditions, meeting: &FinishedMeeting) -> bool {
+    if let Some(minutes) = conditions.min_duration_minutes {
+        if meeting.duration_secs < minutes as u64 * 60 {
+            return false;
+        }
+    }
+    if let Some(needle) = conditions.title_contains.as_deref().filter(|n| !n.is_empty()) {
+        let title = meeting.meeting.title.as_deref().unwrap_or_default().to_lowercase();
+        if !title.contains(&needle.to_lowercase()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Chapters worth listing: a meeting that is one chapter is its own preview.
+fn chapter_previews(transcript: &[TranscriptUpdate]) -> Vec<ChapterPreview> {
+    let previews = chapters::previews(transcript, &chapters::detect(transcript));
+    if previews.len() > 1 {
+        previews
+    } else {
+        Vec::new()
+    }
+}
+
+fn chapter_heading(chapter: &ChapterPreview, index: usize) -> String {
+    let start = chapter.start_secs as u64;
+    let title = chapter.title.clone().unwrap_or_else(|| format!("Chapter {}", index + 1));
+    format!("{} ({}:{:02})", title, start / 60, start % 60)
+}
+
+fn transcript_line(paragraph: &Paragraph) -> String {
+    format!("[{}] {}: {}", paragraph.timestamp, paragraph.speaker, paragraph.text)
+}
+
+pub fn render_document(meeting: &FinishedMeeting, format: DocumentFormat) -> ExportArtifact {
+    let title = meeting.meeting.title.as_deref().unwrap_or("Untitled meeting");
+    let chapters = chapter_previews(&meeting.transcript);
+    let (content, file_name) = match format {
+        DocumentFormat::Markdown => {
+            let mut doc = format!(
+                "# {}\n\n_{} · {} min_\n\n",
+                title,
+                meeting.meeting.started_at.as_deref().unwrap_or_default(),
+                meeting.duration_secs / 60
+            );
+            if !meeting.meeting.tags.is_empty() {
+                doc.push_str(&format!("Tags: {}\n\n", meeting.meeting.tags.join(", ")));
+            }
+            for (key, value) in &meeting.meeting.metadata {
+                doc.push_str(&format!("- **{}:** {}\n", key, value));
+            }
+            if !meeting.meeting.metadata.is_empty() {
+                doc.push('\n');
+            }
+            if let Some(summary) = &meeting.summary {
+                doc.push_str(&format!("## Summary\n\n{}\n\n", summary.trim()));
+            }
+            if !chapters.is_empty() {
+                doc.push_str("## Chapters\n\n");
+                for (index, chapter) in chapters.iter().enumerate() {
+                    doc.push_str(&format!("### {}\n\n", chapter_heading(chapter, index)));
+                    if let Some(quote) = &chapter.quote {
+                        doc.push_str(&format!("> {}\n>\n> — {}\n\n", quote.text, quote.speaker));
+                    }
+                }
+            }
+            doc.push_str("## Transcript\n\n");
+            for paragraph in paragraphs(&meeting.transcript) {
+                doc.push_str(&format!(
+                    "**{}** `{}`\n\n{}\n\n",
+                    paragraph.speaker, paragraph.timestamp, paragraph.text
+                ));
+            }
+            (doc, "minutes.md")
+        }
+        DocumentFormat::Text => {
+            let mut doc = format!("{}\n\n", title);
+            if let Some(summary) = &meeting.summary {
+                doc.push_str(&format!("Summary\n{}\n\n", summary.trim()));
+            }
+            for (index, chapter) in chapters.iter().enumerate() {
+                doc.push_str(&chapter_heading(chapter, index));
+                if let Some(quote) = &chapter.quote {
+                    doc.push_str(&format!("\n  \"{}\" ({})", quote.text, quote.speaker));
+                }
+                doc.push('\n');
+            }
+            if !chapters.is_empty() {
+                doc.push('\n');
+            }
+            for paragraph in paragraphs(&meeting.transcript) {
+                doc.push_str(&transcript_line(&paragraph));
+                doc.push_str("\n\n");
+            }
+            (doc, "transcript.txt")
+        }
+        DocumentFormat::Json => (
+            json!({
+                "meeting": meeting.meeting,
+                "duration_secs": meeting.duration_secs,
+                "summary": meeting.summary,
+                "transcript": meeting.transcript,
+                "paragraphs": paragraphs(&meeting.transcript),
+                "chapters": chapters,
+            })
+            .to_string(),
+            "transcript.json",
+        ),
+    };
+    ExportArtifact {
+        kind: if meeting.summary.is_some() && format == DocumentFormat::Markdown {
+            ArtifactKind::Summary
+        } else {
+            ArtifactKind::Transcript
+        },
+        file_name: file_name.to_string(),
+        content: Some(content),
+        path: None,
+    }
+}
+
+async fn post_slack(meeting: &FinishedMeeting, webhook_secret: &str, channel: Option<&str>) -> Result<String, String> {
+    let url = secrets::require(webhook_secret)?;
+    let title = meeting.meeting.title.as_deref().unwrap_or("Untitled meeting");
+    let body = match &meeting.summary {
+        Some(summary) => summary.trim().to_string(),
+        None => paragraphs(&meeting.transcript)
+            .iter()
+            .take(10)
+            .map(transcript_line)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    let mut payload = json!({ "text": format!("*{}* ({} min)\n{}", title, meeting.duration_secs / 60, body) });
+    if let Some(channel) = channel {
+        payload["channel"] = json!(channel);
+    }
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .timeout(Duration::from_secs(SLACK_TIMEOUT_SECS))
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Slack request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Slack returned {}", response.status()));
+    }
+    Ok("Posted".to_string())
+}
+
+async fn run_action<R: Runtime>(app: &AppHandle<R>, action: &RuleAction, meeting: &FinishedMeeting) -> Result<String, String> {
+    match action {
+        RuleAction::ExportFolder { folder, format } => {
+            let folder = folder
+                .clone()
+                .or_else(|| settings::get().export.folder)
+                .ok_or_else(|| "No export folder configured".to_string())?;
+            let written = export_to_folder(&meeting.meeting, &[render_document(meeting, *format)], Path::new(&folder))?;
+            Ok(written
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join(", "))
+        }
+        RuleAction::UploadCloud { target_ids, format } => {
+            let ids = (!target_ids.is_empty()).then_some(target_ids.as_slice());
+            let records = export_to_cloud(app, &meeting.meeting, &[render_document(meeting, *format)], ids).await?;
+            let failed: Vec<String> = records
+                .iter()
+                .filter(|r| r.state == UploadState::Failed)
+                .map(|r| format!("{}: {}", r.target_id, r.error.as_deref().unwrap_or("failed")))
+                .collect();
+            if failed.is_empty() {
+                Ok(format!("Uploaded {} object(s)", records.len()))
+            } else {
+                Err(failed.join("; "))
+            }
+        }
+        RuleAction::PostSlack { webhook_secret, channel } => post_slack(meeting, webhook_secret, channel.as_deref()).await,
+    }
+}
+
+fn history_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("exports").join(HISTORY_FILE))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn load_history(path: &Path) -> VecDeque<RuleRun> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn append_history<R: Runtime>(app: &AppHandle<R>, run: &RuleRun) -> Result<(), String> {
+    let path = history_path(app)?;
+    let _guard = HISTORY_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock rule history: {}", e))?;
+    let mut history = load_history(&path);
+    history.push_back(run.clone());
+    while history.len() > MAX_HISTORY {
+        history.pop_front();
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create exports directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&history).map_err(|e| format!("Failed to serialize rule history: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write rule history: {}", e))
+}
+
+async fn evaluate<R: Runtime>(app: &AppHandle<R>, trigger: RuleTrigger, meeting: &FinishedMeeting) {
+    let rules = settings::get().export.rules;
+    for rule in rules.iter().filter(|r| r.enabled && r.trigger == trigger) {
+        if !matches(&rule.conditions, meeting) {
+            debug!("Auto-export rule '{}' does not match meeting {}", rule.name, meeting.meeting.id);
+            continue;
+        }
+
+        info!("Running auto-export rule '{}' for meeting {}", rule.name, meeting.meeting.id);
+        let mut outcomes = Vec::new();
+        for action in &rule.actions {
+            let result = run_action(app, action, meeting).await;
+            if let Err(e) = &result {
+                error!("Auto-export rule '{}' failed to {}: {}", rule.name, action.describe(), e);
+            }
+            outcomes.push(ActionOutcome {
+                action: action.describe(),
+                ok: result.is_ok(),
+                detail: result.unwrap_or_else(|e| e),
+            });
+        }
+
+        let run = RuleRun {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            meeting_id: meeting.meeting.id.clone(),
+            trigger,
+            ran_at: chrono::Utc::now().to_rfc3339(),
+            success: outcomes.iter().all(|o| o.ok),
+            outcomes,
+        };
+        if !run.success {
+            let failed: Vec<&str> = run.outcomes.iter().filter(|o| !o.ok).map(|o| o.action.as_str()).collect();
+            notifications::notify(
+                app,
+                NotificationCategory::ExportFailed,
+                "Auto-export failed",
+                &format!("Rule \"{}\" could not {}.", rule.name, failed.join(", ")),
+            );
+        }
+        if let Err(e) = append_history(app, &run) {
+            warn!("Failed to record auto-export run: {}", e);
+        }
+    }
+}
+
+/// Capture each recording's transcript and evaluate `MeetingEnd` rules once
+/// its session completes, after the final transcript flush.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    let mut events = pipeline::subscribe();
+    let mut updates = transcript_stream::subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Ok(update) => {
+                        if let Ok(mut capture) = CAPTURE.lock() {
+                            if capture.started_at.is_some() {
+                                capture.transcript.push(update);
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Auto-export capture missed {} transcript updates", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                event = events.recv() => match event {
+                    Ok(PipelineEvent::RecordingStarted { .. }) => {
+                        if let Ok(mut capture) = CAPTURE.lock() {
+                            *capture = SessionCapture {
+                                started_at: Some(chrono::Utc::now()),
+                                stopped_at: None,
+                                transcript: Vec::new(),
+                            };
+                        }
+                    }
+                    Ok(PipelineEvent::RecordingStopped) => {
+                        if let Ok(mut capture) = CAPTURE.lock() {
+                            capture.stopped_at = Some(chrono::Utc::now());
+                        }
+                    }
+                    // Only now is the end of the meeting transcribed and flushed
+                    Ok(PipelineEvent::SessionComplete { meeting_id }) => {
+                        while let Ok(update) = updates.try_recv() {
+                            if let Ok(mut capture) = CAPTURE.lock() {
+                                capture.transcript.push(update);
+                            }
+                        }
+                        let finished = CAPTURE.lock().ok().and_then(|mut capture| {
+                            let capture = std::mem::take(&mut *capture);
+                            let stopped_at = capture.stopped_at.unwrap_or_else(chrono::Utc::now);
+                            capture.started_at.map(|started_at| FinishedMeeting {
+                                meeting: {
+                                    let id = meeting_id
+                                        .unwrap_or_else(|| started_at.format("%Y%m%dT%H%M%SZ").to_string());
+                                    let manifest = storage::meeting_dir(&app, &id)
+                                        .and_then(|dir| storage::read_manifest(&dir))
+                                        .ok();
+                                    ExportMeeting {
+                                        id,
+                                        title: manifest.as_ref().and_then(|m| m.name.clone()),
+                                        started_at: Some(started_at.to_rfc3339()),
+                                        tags: manifest.as_ref().map(|m| m.tags.clone()).unwrap_or_default(),
+                                        metadata: manifest.map(|m| m.metadata).unwrap_or_default(),
+                                    }
+                                },
+                                duration_secs: (stopped_at - started_at).num_seconds().max(0) as u64,
+                                transcript: capture.transcript,
+                                summary: None,
+                            })
+                        });
+                        if let Some(finished) = finished {
+                            if let Ok(mut last) = LAST_MEETING.lock() {
+                                *last = Some(finished.clone());
+                            }
+                            // Uploads can be slow; keep capturing events meanwhile
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                evaluate(&app, RuleTrigger::MeetingEnd, &finished).await;
+                            });
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    });
+}
+
+/// Id of the most recent recording, which a reported summary belongs to.
+pub fn last_meeting_id() -> Option<String> {
+    LAST_MEETING.lock().ok()?.as_ref().map(|finished| finished.meeting.id.clone())
+}
+
+/// Attach the summary to the most recent recording and run `SummaryReady` rules.
+pub async fn summary_ready<R: Runtime>(app: &AppHandle<R>, meeting_id: String, title: String, summary: Option<String>) {
+    let finished = LAST_MEETING.lock().ok().and_then(|last| last.clone());
+    let Some(mut finished) = finished else {
+        debug!("Summary reported with no finished recording; skipping auto-export rules");
+        return;
+    };
+    finished.meeting.id = meeting_id;
+    finished.meeting.title = Some(title);
+    finished.summary = summary;
+    evaluate(app, RuleTrigger::SummaryReady, &finished).await;
+}
+
+#[command]
+pub fn get_auto_export_rules() -> Vec<AutoExportRule> {
+    settings::get().export.rules
+}
+
+#[command]
+pub fn set_auto_export_rules(rules: Vec<AutoExportRule>) -> Result<Vec<AutoExportRule>, String> {
+    for rule in &rules {
+        if rule.id.trim().is_empty() {
+            return Err(format!("Rule '{}' needs an id", rule.name));
+        }
+        if rule.actions.is_empty() {
+            return Err(format!("Rule '{}' has no actions", rule.name));
+        }
+    }
+    settings::update(|s| s.export.rules = rules).map(|s| s.export.rules)
+}
+
+/// Past rule runs, newest first, optionally for a single rule.
+#[command]
+pub fn get_auto_export_history<R: Runtime>(app: AppHandle<R>, rule_id: Option<String>) -> Result<Vec<RuleRun>, String> {
+    let path = history_path(&app)?;
+    Ok(load_history(&path)
+        .into_iter()
+        .rev()
+        .filter(|run| rule_id.as_deref().map_or(true, |id| run.rule_id == id))
+        .collect())
+}
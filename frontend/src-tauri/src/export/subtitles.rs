@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::replay::{self, ReplayRecord};
+use crate::storage;
+
+/// Cues shorter than this are stretched so they stay readable.
+const MIN_CUE_MS: u64 = 700;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+/// One subtitle, timed from the start of the session.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Cues for every transcribed segment in a raw session, placed on the
+/// session timeline by the offset of the chunk each segment came from.
+pub fn cues_from_replay(path: &Path) -> Result<Vec<Cue>, String> {
+    let mut chunk_offsets = HashMap::new();
+    let mut cues = Vec::new();
+    for (record, _) in replay::read_replay(path)? {
+        match record {
+            ReplayRecord::Chunk { index, offset_ms, .. } => {
+                chunk_offsets.insert(index, offset_ms);
+            }
+            ReplayRecord::Response { index, segments, .. } => {
+                let offset_ms = chunk_offsets.get(&index).copied().unwrap_or(0);
+                for segment in segments {
+                    let text = segment.text.replace("[BLANK_AUDIO]", "").replace("[AUDIO OUT]", "");
+                    let text = text.trim();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let text = match segment.speaker.as_deref() {
+                        Some(speaker) => format!("{}: {}", speaker, text),
+                        None => text.to_string(),
+                    };
+                    cues.push(Cue {
+                        start_ms: offset_ms + (segment.t0.max(0.0) * 1000.0) as u64,
+                        end_ms: offset_ms + (segment.t1.max(0.0) * 1000.0) as u64,
+                        text,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    cues.sort_by_key(|cue| cue.start_ms);
+    // Players show overlapping cues stacked; end each where the next begins
+    for i in 0..cues.len() {
+        let next_start = cues.get(i + 1).map(|next| next.start_ms);
+        let cue = &mut cues[i];
+        cue.end_ms = cue.end_ms.max(cue.start_ms + MIN_CUE_MS);
+        if let Some(next_start) = next_start {
+            cue.end_ms = cue.end_ms.min(next_start.max(cue.start_ms + 1));
+        }
+    }
+    Ok(cues)
+}
+
+fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+pub fn render(cues: &[Cue], format: SubtitleFormat) -> String {
+    let mut out = String::new();
+    if format == SubtitleFormat::Vtt {
+        out.push_str("WEBVTT\n\n");
+    }
+    let separator = if format == SubtitleFormat::Srt { ',' } else { '.' };
+    for (number, cue) in cues.iter().enumerate() {
+        if format == SubtitleFormat::Srt {
+            out.push_str(&format!("{}\n", number + 1));
+        }
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(cue.start_ms, separator),
+            timestamp(cue.end_ms, separator),
+            // A blank line would end the cue early
+            cue.text.replace("\n\n", "\n")
+        ));
+    }
+    out
+}
+
+/// Write a stored session's transcript as subtitles to `path`.
+#[command]
+pub fn export_subtitles<R: Runtime>(
+    app: AppHandle<R>,
+    session_id: String,
+    format: SubtitleFormat,
+    path: String,
+) -> Result<String, String> {
+    let dir = storage::meeting_dir(&app, &session_id)?;
+    let raw = storage::raw_session_path(&dir);
+    if !raw.exists() {
+        return Err(format!("Meeting {} has no raw capture to export", session_id));
+    }
+    let cues = cues_from_replay(&raw)?;
+    let mut path = Path::new(&path).to_path_buf();
+    if path.extension().is_none() {
+        path.set_extension(format.extension());
+    }
+    fs::write(&path, render(&cues, format)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    info!("Exported {} cues for meeting {} to {}", cues.len(), session_id, path.display());
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::audio::alignment::{self, Alignment};
+use crate::audio::decode::decode_audio_file;
+use crate::replay;
+use crate::{resample_audio, send_audio_chunk, CHUNK_DURATION_MS, WHISPER_SAMPLE_RATE};
+
+/// Alignments scoring below this are reported but flagged as unreliable.
+const MIN_CONFIDENCE: f32 = 0.3;
+/// Word overlap above which two time-overlapping segments are the same speech.
+const DUPLICATE_WORD_OVERLAP: f32 = 0.6;
+
+/// A transcript segment on the meeting timeline, in seconds from meeting start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalTrackProgress {
+    pub stage: String,
+    pub completed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExternalTrackResult {
+    pub alignment: Alignment,
+    pub reliable: bool,
+    /// Segments transcribed from the external track, on the meeting timeline.
+    pub track_segments: Vec<TimelineSegment>,
+    /// `existing_segments` merged with the new track, duplicates removed.
+    pub merged: Vec<TimelineSegment>,
+}
+
+fn load_audio(path: &Path) -> Result<Vec<f32>, String> {
+    let (samples, rate) = if path.extension().and_then(|e| e.to_str()) == Some(replay::REPLAY_EXTENSION) {
+        replay::read_replay_audio(path)?
+    } else {
+        decode_audio_file(path).map_err(|e| format!("Failed to decode {:?}: {}", path, e))?
+    };
+    Ok(resample_audio(&samples, rate, WHISPER_SAMPLE_RATE))
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.chars()
+                .filter(|c| !c.is_ascii_punctuation())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn is_duplicate(a: &TimelineSegment, b: &TimelineSegment) -> bool {
+    if a.end < b.start || b.end < a.start {
+        return false;
+    }
+    let (a_words, b_words) = (words(&a.text), words(&b.text));
+    let smaller = a_words.len().min(b_words.len());
+    if smaller == 0 {
+        return false;
+    }
+    a_words.intersection(&b_words).count() as f32 / smaller as f32 >= DUPLICATE_WORD_OVERLAP
+}
+
+/// Interleave `incoming` into `existing` by start time, dropping incoming
+/// segments that repeat speech already in the transcript.
+pub fn merge_segments(existing: Vec<TimelineSegment>, incoming: Vec<TimelineSegment>) -> Vec<TimelineSegment> {
+    let mut merged = existing.clone();
+    merged.extend(
+        incoming
+            .into_iter()
+            .filter(|segment| !existing.iter().any(|e| is_duplicate(e, segment))),
+    );
+    merged.sort_by(|a, b| a.start.total_cmp(&b.start));
+    merged
+}
+
+/// Attach an externally recorded track (e.g. a handheld recorder) to a meeting.
+/// The track is aligned against the meeting's reference audio (an audio file or
+/// replay file), transcribed, and its segments merged into the meeting timeline.
+#[command]
+pub async fn attach_external_track<R: Runtime>(
+    app: AppHandle<R>,
+    track_path: String,
+    reference_path: String,
+    existing_segments: Option<Vec<TimelineSegment>>,
+    label: Option<String>,
+) -> Result<ExternalTrackResult, String> {
+    let emit_progress = |stage: &str, completed: usize, total: usize| {
+        let _ = app.emit(
+            "external-track-progress",
+            ExternalTrackProgress {
+                stage: stage.to_string(),
+                completed,
+                total,
+            },
+        );
+    };
+
+    emit_progress("aligning", 0, 1);
+    let track_file = track_path.clone();
+    let (track, alignment) = tokio::task::spawn_blocking(move || {
+        let reference = load_audio(Path::new(&reference_path))?;
+        let track = load_audio(Path::new(&track_file))?;
+        let alignment = alignment::align(&reference, &track, WHISPER_SAMPLE_RATE)
+            .ok_or_else(|| "Could not align the track with the meeting audio".to_string())?;
+        Ok::<_, String>((track, alignment))
+    })
+    .await
+    .map_err(|e| format!("Alignment task failed: {}", e))??;
+
+    let reliable = alignment.confidence >= MIN_CONFIDENCE;
+    if reliable {
+        info!("Aligned {} at {} ms (confidence {:.2})", track_path, alignment.offset_ms, alignment.confidence);
+    } else {
+        warn!("Low-confidence alignment for {} (confidence {:.2})", track_path, alignment.confidence);
+    }
+    emit_progress("aligning", 1, 1);
+
+    let source = label.unwrap_or_else(|| {
+        Path::new(&track_path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "External track".to_string())
+    });
+    let offset_secs = alignment.offset_ms as f64 / 1000.0;
+    let chunk_len = (WHISPER_SAMPLE_RATE as u64 * CHUNK_DURATION_MS as u64 / 1000) as usize;
+    let chunks: Vec<&[f32]> = track.chunks(chunk_len).collect();
+    let mut track_segments = Vec::new();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        emit_progress("transcribing", index, chunks.len());
+        let chunk_start = offset_secs + (index * chunk_len) as f64 / WHISPER_SAMPLE_RATE as f64;
+        let response = send_audio_chunk(chunk.to_vec())
+            .await
+            .map_err(|e| format!("Failed to transcribe external track: {}", e))?;
+        for segment in response.segments {
+            let text = segment
+                .text
+                .replace("[BLANK_AUDIO]", "")
+                .replace("[AUDIO OUT]", "")
+                .trim()
+                .to_string();
+            if text.is_empty() {
+                continue;
+            }
+            track_segments.push(TimelineSegment {
+                start: chunk_start + segment.t0 as f64,
+                end: chunk_start + segment.t1 as f64,
+                text,
+                source: source.clone(),
+            });
+        }
+    }
+    emit_progress("transcribing", chunks.len(), chunks.len());
+
+    let merged = merge_segments(existing_segments.unwrap_or_default(), track_segments.clone());
+    Ok(ExternalTrackResult {
+        alignment,
+        reliable,
+        track_segments,
+        merged,
+    })
+}
@@ -0,0 +1,225 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use audiopus::coder::Encoder;
+use audiopus::{Application, Channels, SampleRate};
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::audio::remote::{self, REMOTE_SAMPLE_RATE};
+use crate::audio::{default_input_device, parse_audio_device, AudioStream};
+use crate::audio::audio_processing::StreamResampler;
+
+/// 20 ms Opus frames at 48 kHz.
+const FRAME_SAMPLES: usize = 960;
+const MAX_PACKET_BYTES: usize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FederationRole {
+    /// Accepting streams from other machines (the companion server is running).
+    Host,
+    /// Streaming this machine's microphone to a host.
+    Guest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FederationStatus {
+    pub role: Option<FederationRole>,
+    pub host: Option<String>,
+    /// Machines and phones currently streaming into this instance.
+    pub peers: Vec<String>,
+}
+
+struct GuestSession {
+    host: String,
+    is_running: Arc<AtomicBool>,
+    stream: Arc<AudioStream>,
+}
+
+static GUEST: Lazy<Mutex<Option<GuestSession>>> = Lazy::new(|| Mutex::new(None));
+
+/// True while this machine is streaming its capture to a federation host.
+pub fn is_guest() -> bool {
+    GUEST.lock().map(|guest| guest.is_some()).unwrap_or(false)
+}
+
+fn machine_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Meetily guest".to_string())
+}
+
+/// Join a session hosted by another instance on the LAN. This machine's
+/// microphone is streamed to the host, which mixes and transcribes it as a
+/// remote device labelled with this machine's name.
+#[command]
+pub async fn join_federation<R: Runtime>(
+    app: AppHandle<R>,
+    host: String,
+    port: u16,
+    token: String,
+    device_name: Option<String>,
+) -> Result<(), String> {
+    if crate::is_recording() {
+        return Err("Stop the local recording before joining another machine's session".to_string());
+    }
+    if is_guest() {
+        return Err("Already streaming to a federation host".to_string());
+    }
+
+    let device = match device_name {
+        Some(name) => parse_audio_device(&name),
+        None => default_input_device(),
+    }
+    .map_err(|e| format!("Failed to open microphone: {}", e))?;
+
+    let url = format!("ws://{}:{}", host, port);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str())
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+
+    let hello = serde_json::json!({
+        "token": token,
+        "device_name": format!("{} - {}", machine_name(), device.name),
+    });
+    socket
+        .send(Message::Text(hello.to_string()))
+        .await
+        .map_err(|e| format!("Failed to send handshake: {}", e))?;
+    match socket.next().await {
+        Some(Ok(Message::Text(text))) if text.contains("\"ok\"") => {}
+        _ => return Err("Host rejected the connection; check the pairing token".to_string()),
+    }
+
+    let is_running = Arc::new(AtomicBool::new(true));
+    let stream = Arc::new(
+        AudioStream::from_device(Arc::new(device), is_running.clone())
+            .await
+            .map_err(|e| format!("Failed to start microphone: {}", e))?,
+    );
+    let capture_rate = stream.device_config.sample_rate().0;
+    let mut receiver = stream.subscribe().await;
+    // One resampler for the whole stream, so buffer boundaries don't click
+    let mut resampler = match StreamResampler::new(capture_rate, REMOTE_SAMPLE_RATE) {
+        Ok(resampler) => resampler,
+        Err(e) => {
+            is_running.store(false, Ordering::SeqCst);
+            let _ = stream.stop().await;
+            return Err(format!("Failed to resample {} Hz audio: {}", capture_rate, e));
+        }
+    };
+    let mut encoder = match Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            is_running.store(false, Ordering::SeqCst);
+            let _ = stream.stop().await;
+            return Err(format!("Failed to create Opus encoder: {}", e));
+        }
+    };
+
+    let registered = GUEST.lock().map(|mut guest| {
+        *guest = Some(GuestSession {
+            host: url.clone(),
+            is_running: is_running.clone(),
+            stream: stream.clone(),
+        })
+    });
+    // Turned into a message first: the poison error holds the lock
+    if let Err(e) = registered.map_err(|e| e.to_string()) {
+        is_running.store(false, Ordering::SeqCst);
+        let _ = stream.stop().await;
+        return Err(e);
+    }
+    info!("Joined federation host {}", url);
+
+    tokio::spawn(async move {
+        let mut pending: Vec<f32> = Vec::new();
+        let mut packet = vec![0u8; MAX_PACKET_BYTES];
+
+        'stream: while is_running.load(Ordering::SeqCst) {
+            // Time out so a leave request is noticed even when the device goes quiet
+            let received = tokio::time::timeout(Duration::from_millis(500), receiver.recv()).await;
+            let samples = match received {
+                Err(_) => continue,
+                Ok(Ok(samples)) => samples,
+                Ok(Err(RecvError::Lagged(skipped))) => {
+                    warn!("Federation stream lagged, dropped {} buffers", skipped);
+                    continue;
+                }
+                Ok(Err(RecvError::Closed)) => break,
+            };
+            pending.extend(resampler.process(&samples));
+
+            while pending.len() >= FRAME_SAMPLES {
+                let frame: Vec<f32> = pending.drain(..FRAME_SAMPLES).collect();
+                let len = match encoder.encode_float(&frame, &mut packet) {
+                    Ok(len) => len,
+                    Err(e) => {
+                        warn!("Failed to encode Opus frame: {}", e);
+                        continue;
+                    }
+                };
+                if let Err(e) = socket.send(Message::Binary(packet[..len].to_vec())).await {
+                    error!("Lost connection to federation host: {}", e);
+                    break 'stream;
+                }
+            }
+        }
+
+        let _ = socket.send(Message::Close(None)).await;
+        let guest = GUEST.lock().ok().and_then(|mut guest| guest.take());
+        if let Some(guest) = guest {
+            guest.is_running.store(false, Ordering::SeqCst);
+            if let Err(e) = guest.stream.stop().await {
+                error!("Failed to stop federation microphone: {}", e);
+            }
+        }
+        info!("Left federation host");
+        let _ = app.emit("federation-left", ());
+    });
+
+    Ok(())
+}
+
+#[command]
+pub fn leave_federation() -> Result<(), String> {
+    if let Some(guest) = GUEST.lock().map_err(|e| e.to_string())?.as_ref() {
+        guest.is_running.store(false, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_federation_status() -> FederationStatus {
+    let host = GUEST
+        .lock()
+        .ok()
+        .and_then(|guest| guest.as_ref().map(|g| g.host.clone()));
+    let role = if host.is_some() {
+        Some(FederationRole::Guest)
+    } else if remote::is_serving() {
+        Some(FederationRole::Host)
+    } else {
+        None
+    };
+    FederationStatus {
+        role,
+        host,
+        peers: remote::list_remote_devices(),
+    }
+}
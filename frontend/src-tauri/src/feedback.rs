@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::encryption;
+use crate::replay::{self, ReplayConfig};
+use crate::storage::{self, DERIVED_DIR};
+
+/// Derived file holding a meeting's segment feedback.
+pub const FEEDBACK_FILE: &str = "feedback.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+/// What was wrong with a segment rated down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Issue {
+    WrongWords,
+    WrongSpeaker,
+    /// Text that was never said.
+    Hallucination,
+}
+
+/// A user's rating of one line of a stored transcript, with the
+/// configuration that produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentFeedback {
+    /// Index of the line in the derived transcript.
+    pub line: usize,
+    pub timestamp: String,
+    pub text: String,
+    pub rating: Rating,
+    #[serde(default)]
+    pub issues: Vec<Issue>,
+    #[serde(default)]
+    pub note: Option<String>,
+    pub engine: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Capture device the line was heard on.
+    pub device: String,
+    /// RFC 3339.
+    pub created_at: String,
+}
+
+/// Feedback totals for one engine, model and device combination.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigurationQuality {
+    pub engine: String,
+    pub model: Option<String>,
+    pub device: String,
+    pub meetings: usize,
+    pub rated: usize,
+    pub up: usize,
+    pub down: usize,
+    pub wrong_words: usize,
+    pub wrong_speaker: usize,
+    pub hallucinations: usize,
+    /// Share of rated segments rated up.
+    pub approval: f32,
+}
+
+fn read_feedback(dir: &Path) -> Vec<SegmentFeedback> {
+    encryption::read_to_string(dir.join(DERIVED_DIR).join(FEEDBACK_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// The device a transcript line came from, by its source label.
+fn device_for(source: &str, config: &ReplayConfig) -> String {
+    match source {
+        "Microphone" => config.mic_device.clone(),
+        "System audio" => config.system_device.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Rate one line of a stored meeting's transcript. Rating the same line
+/// again replaces the earlier feedback.
+#[command]
+pub fn submit_segment_feedback<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    line: usize,
+    rating: Rating,
+    issues: Vec<Issue>,
+    note: Option<String>,
+) -> Result<SegmentFeedback, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    let transcript = storage::read_transcript(&dir)?;
+    let update = transcript
+        .get(line)
+        .ok_or_else(|| format!("Meeting {} has no transcript line {}", meeting_id, line))?;
+    let config = replay::read_header(&storage::raw_session_path(&dir))?;
+
+    let feedback = SegmentFeedback {
+        line,
+        timestamp: update.timestamp.clone(),
+        text: update.text.clone(),
+        rating,
+        issues: if rating == Rating::Down { issues } else { Vec::new() },
+        note: note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty()),
+        device: device_for(&update.source, &config),
+        engine: config.engine,
+        model: config.model,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut all = read_feedback(&dir);
+    all.retain(|existing| existing.line != line);
+    all.push(feedback.clone());
+    all.sort_by_key(|existing| existing.line);
+    let content = serde_json::to_string_pretty(&all).map_err(|e| format!("Failed to serialize feedback: {}", e))?;
+    storage::save_derived(app, meeting_id, FEEDBACK_FILE.to_string(), content)?;
+    Ok(feedback)
+}
+
+#[command]
+pub fn get_meeting_feedback<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<Vec<SegmentFeedback>, String> {
+    Ok(read_feedback(&storage::meeting_dir(&app, &meeting_id)?))
+}
+
+/// Feedback across all stored meetings per engine, model and device, best
+/// approval first, to see which configuration works best for this voice and room.
+#[command]
+pub fn get_quality_report<R: Runtime>(app: AppHandle<R>) -> Result<Vec<ConfigurationQuality>, String> {
+    // Keyed by engine, model and device; the set holds the meetings rated
+    let mut report: BTreeMap<(String, Option<String>, String), (ConfigurationQuality, HashSet<String>)> = BTreeMap::new();
+    for manifest in storage::list_stored_meetings(app.clone())? {
+        let dir = storage::meeting_dir(&app, &manifest.id)?;
+        for feedback in read_feedback(&dir) {
+            let key = (feedback.engine.clone(), feedback.model.clone(), feedback.device.clone());
+            let (quality, meetings) = report.entry(key).or_insert_with(|| {
+                (
+                    ConfigurationQuality {
+                        engine: feedback.engine.clone(),
+                        model: feedback.model.clone(),
+                        device: feedback.device.clone(),
+                        ..Default::default()
+                    },
+                    HashSet::new(),
+                )
+            });
+            meetings.insert(manifest.id.clone());
+            quality.rated += 1;
+            match feedback.rating {
+                Rating::Up => quality.up += 1,
+                Rating::Down => quality.down += 1,
+            }
+            for issue in &feedback.issues {
+                match issue {
+                    Issue::WrongWords => quality.wrong_words += 1,
+                    Issue::WrongSpeaker => quality.wrong_speaker += 1,
+                    Issue::Hallucination => quality.hallucinations += 1,
+                }
+            }
+        }
+    }
+
+    let mut report: Vec<ConfigurationQuality> = report
+        .into_values()
+        .map(|(mut quality, meetings)| {
+            quality.meetings = meetings.len();
+            quality.approval = quality.up as f32 / quality.rated.max(1) as f32;
+            quality
+        })
+        .collect();
+    report.sort_by(|a, b| b.approval.total_cmp(&a.approval).then_with(|| b.rated.cmp(&a.rated)));
+    Ok(report)
+}
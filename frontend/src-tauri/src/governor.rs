@@ -0,0 +1,218 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::pipeline::{self, PipelineEvent};
+use crate::settings;
+
+/// Weight of the newest chunk in the smoothed real-time factor.
+const RTF_SMOOTHING: f64 = 0.3;
+
+/// Pipeline quality levels, from full quality down to "just keep up".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityLevel {
+    #[default]
+    Full,
+    Reduced,
+    Minimal,
+}
+
+impl QualityLevel {
+    fn lower(self) -> Option<Self> {
+        match self {
+            QualityLevel::Full => Some(QualityLevel::Reduced),
+            QualityLevel::Reduced => Some(QualityLevel::Minimal),
+            QualityLevel::Minimal => None,
+        }
+    }
+
+    fn higher(self) -> Option<Self> {
+        match self {
+            QualityLevel::Full => None,
+            QualityLevel::Reduced => Some(QualityLevel::Full),
+            QualityLevel::Minimal => Some(QualityLevel::Reduced),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GovernorSettings {
+    pub enabled: bool,
+    /// Downgrade after the real-time factor has stayed above 1.0 this long.
+    pub downgrade_after_secs: u64,
+    /// Upgrade after the real-time factor has stayed below `upgrade_below_rtf` this long.
+    pub upgrade_after_secs: u64,
+    pub upgrade_below_rtf: f64,
+    /// Whisper server running a smaller model, used at `Minimal` quality.
+    /// There's no such server by default: unset, `Minimal` keeps the usual
+    /// server and only sends longer, non-silent chunks.
+    pub fallback_endpoint: Option<String>,
+}
+
+impl Default for GovernorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            downgrade_after_secs: 180,
+            upgrade_after_secs: 300,
+            upgrade_below_rtf: 0.6,
+            fallback_endpoint: None,
+        }
+    }
+}
+
+/// Pipeline knobs the capture loop reads before each chunk.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PipelineKnobs {
+    pub chunk_duration_ms: u32,
+    /// Skip chunks without speech instead of sending them to the engine.
+    pub skip_silent_chunks: bool,
+    /// Engine endpoint override; `None` uses the default whisper server.
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GovernorStatus {
+    pub level: QualityLevel,
+    pub realtime_factor: Option<f64>,
+    pub knobs: PipelineKnobs,
+}
+
+#[derive(Default)]
+struct GovernorState {
+    level: QualityLevel,
+    rtf: Option<f64>,
+    over_since: Option<Instant>,
+    under_since: Option<Instant>,
+}
+
+static STATE: Lazy<Mutex<GovernorState>> = Lazy::new(|| Mutex::new(GovernorState::default()));
+
+fn knobs_for(level: QualityLevel, base_chunk_ms: u32, config: &GovernorSettings) -> PipelineKnobs {
+    match level {
+        QualityLevel::Full => PipelineKnobs {
+            chunk_duration_ms: base_chunk_ms,
+            skip_silent_chunks: false,
+            endpoint: None,
+        },
+        // Bigger batches amortize per-request overhead; silent chunks are pure waste
+        QualityLevel::Reduced => PipelineKnobs {
+            chunk_duration_ms: base_chunk_ms * 3 / 2,
+            skip_silent_chunks: true,
+            endpoint: None,
+        },
+        QualityLevel::Minimal => PipelineKnobs {
+            chunk_duration_ms: base_chunk_ms * 2,
+            skip_silent_chunks: true,
+            endpoint: config.fallback_endpoint.clone(),
+        },
+    }
+}
+
+/// Knobs for the current quality level.
+pub fn knobs(base_chunk_ms: u32) -> PipelineKnobs {
+    let level = STATE.lock().map(|s| s.level).unwrap_or_default();
+    knobs_for(level, base_chunk_ms, &settings::get().governor)
+}
+
+/// Start a session at full quality.
+pub fn reset() {
+    if let Ok(mut state) = STATE.lock() {
+        *state = GovernorState::default();
+    }
+}
+
+/// Feed the processing time for `audio_ms` of audio into the controller and
+/// step the quality level once the real-time factor has been out of band long enough.
+pub fn observe(audio_ms: u64, processing_ms: u64) {
+    let config = settings::get().governor;
+    if !config.enabled || audio_ms == 0 {
+        return;
+    }
+
+    let Ok(mut state) = STATE.lock() else {
+        return;
+    };
+    let sample = processing_ms as f64 / audio_ms as f64;
+    let rtf = match state.rtf {
+        Some(previous) => previous + RTF_SMOOTHING * (sample - previous),
+        None => sample,
+    };
+    state.rtf = Some(rtf);
+
+    let now = Instant::now();
+    let change = if rtf > 1.0 {
+        state.under_since = None;
+        let since = *state.over_since.get_or_insert(now);
+        if now.duration_since(since) >= Duration::from_secs(config.downgrade_after_secs) {
+            state.level.lower()
+        } else {
+            None
+        }
+    } else if rtf < config.upgrade_below_rtf {
+        state.over_since = None;
+        let since = *state.under_since.get_or_insert(now);
+        if now.duration_since(since) >= Duration::from_secs(config.upgrade_after_secs) {
+            state.level.higher()
+        } else {
+            None
+        }
+    } else {
+        state.over_since = None;
+        state.under_since = None;
+        None
+    };
+
+    if let Some(level) = change {
+        if level > state.level {
+            warn!("Real-time factor {:.2}, downgrading pipeline to {:?}", rtf, level);
+            if level == QualityLevel::Minimal && config.fallback_endpoint.is_none() {
+                warn!("No fallback endpoint configured, keeping the default whisper server");
+            }
+        } else {
+            info!("Real-time factor {:.2}, upgrading pipeline to {:?}", rtf, level);
+        }
+        state.level = level;
+        // Require a full new window before the next step
+        state.over_since = None;
+        state.under_since = None;
+        drop(state);
+        pipeline::publish(PipelineEvent::QualityChanged {
+            level,
+            realtime_factor: rtf,
+        });
+    }
+}
+
+#[command]
+pub fn get_governor_status() -> GovernorStatus {
+    let (level, realtime_factor) = STATE
+        .lock()
+        .map(|s| (s.level, s.rtf))
+        .unwrap_or_default();
+    GovernorStatus {
+        level,
+        realtime_factor,
+        knobs: knobs_for(level, crate::CHUNK_DURATION_MS, &settings::get().governor),
+    }
+}
+
+#[command]
+pub fn get_governor_settings() -> GovernorSettings {
+    settings::get().governor
+}
+
+#[command]
+pub fn set_governor_settings(governor_settings: GovernorSettings) -> Result<GovernorSettings, String> {
+    if governor_settings.upgrade_below_rtf >= 1.0 {
+        return Err("upgrade_below_rtf must be below 1.0 to leave headroom".to_string());
+    }
+    settings::update(|s| s.governor = governor_settings).map(|s| s.governor)
+}
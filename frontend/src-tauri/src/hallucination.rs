@@ -0,0 +1,158 @@
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::{settings, TranscriptSegment};
+
+/// Texts shorter than this compress poorly whatever they say, so the
+/// compression check would only produce noise.
+const MIN_COMPRESSION_CHECK_CHARS: usize = 40;
+/// Longest phrase, in words, looked for by the repetition detector.
+const MAX_NGRAM: usize = 4;
+
+/// Post-decode checks for text whisper produces on near-silence or music:
+/// stock phrases like "Thank you.", low-confidence guesses and loops of the
+/// same words.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallucinationSettings {
+    pub enabled: bool,
+    /// Segments the model gives at least this chance of being silence are
+    /// suspect; suspect segments are dropped when they are also decoded with
+    /// low confidence or are one of `phrases`.
+    pub suspect_no_speech_prob: f32,
+    /// Mean token log probability below which a suspect segment is dropped.
+    pub min_avg_logprob: f32,
+    /// Text compressing better than this (zlib) is repetitive enough to be a
+    /// decoding loop, and is dropped whatever the other scores.
+    pub max_compression_ratio: f32,
+    /// The same run of up to four words repeated back to back this many
+    /// times is a loop and is dropped.
+    pub max_repeats: usize,
+    /// Stock phrases whisper emits on silence, compared without case or punctuation.
+    pub phrases: Vec<String>,
+}
+
+impl Default for HallucinationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            suspect_no_speech_prob: 0.3,
+            min_avg_logprob: -1.0,
+            max_compression_ratio: 2.4,
+            max_repeats: 4,
+            phrases: [
+                "thank you",
+                "thanks for watching",
+                "thank you for watching",
+                "please subscribe",
+                "subtitles by the amara org community",
+                "you",
+                "bye",
+            ]
+            .iter()
+            .map(|phrase| phrase.to_string())
+            .collect(),
+        }
+    }
+}
+
+/// Why a segment was judged hallucinated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hallucination {
+    StockPhrase,
+    LowConfidence,
+    Compressible,
+    Repetition,
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn compression_ratio(text: &str) -> f32 {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    if encoder.write_all(text.as_bytes()).is_err() {
+        return 0.0;
+    }
+    match encoder.finish() {
+        Ok(compressed) if !compressed.is_empty() => text.len() as f32 / compressed.len() as f32,
+        _ => 0.0,
+    }
+}
+
+/// Most times any phrase of up to `MAX_NGRAM` words repeats back to back.
+fn max_consecutive_repeats(words: &[&str]) -> usize {
+    let mut most = 1;
+    for n in 1..=MAX_NGRAM.min(words.len() / 2) {
+        for start in 0..n {
+            let mut run = 1;
+            let mut at = start;
+            while at + 2 * n <= words.len() {
+                if words[at..at + n] == words[at + n..at + 2 * n] {
+                    run += 1;
+                    most = most.max(run);
+                } else {
+                    run = 1;
+                }
+                at += n;
+            }
+        }
+    }
+    most
+}
+
+/// Whether `segment` looks hallucinated under `config`.
+pub fn check(segment: &TranscriptSegment, config: &HallucinationSettings) -> Option<Hallucination> {
+    let text = normalize(&segment.text);
+    if text.is_empty() {
+        return None;
+    }
+    let words: Vec<&str> = text.split(' ').collect();
+    if config.max_repeats > 1 && max_consecutive_repeats(&words) >= config.max_repeats {
+        return Some(Hallucination::Repetition);
+    }
+    if segment.text.len() >= MIN_COMPRESSION_CHECK_CHARS && compression_ratio(&segment.text) > config.max_compression_ratio
+    {
+        return Some(Hallucination::Compressible);
+    }
+
+    let suspect = segment
+        .no_speech_prob
+        .map(|p| p >= config.suspect_no_speech_prob)
+        .unwrap_or(false);
+    if !suspect {
+        return None;
+    }
+    if config.phrases.iter().any(|phrase| normalize(phrase) == text) {
+        return Some(Hallucination::StockPhrase);
+    }
+    match segment.avg_logprob {
+        Some(logprob) if logprob < config.min_avg_logprob => Some(Hallucination::LowConfidence),
+        _ => None,
+    }
+}
+
+#[command]
+pub fn get_hallucination_settings() -> HallucinationSettings {
+    settings::get().hallucination
+}
+
+#[command]
+pub fn set_hallucination_settings(
+    hallucination_settings: HallucinationSettings,
+) -> Result<HallucinationSettings, String> {
+    settings::update(|s| s.hallucination = hallucination_settings).map(|s| s.hallucination)
+}
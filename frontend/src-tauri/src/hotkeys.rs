@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::settings;
+
+/// Actions that can be bound to a global (system-wide) shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    ToggleSession,
+    TogglePause,
+    InsertMarker,
+    ToggleMute,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 4] = [
+        HotkeyAction::ToggleSession,
+        HotkeyAction::TogglePause,
+        HotkeyAction::InsertMarker,
+        HotkeyAction::ToggleMute,
+    ];
+
+    fn default_accelerator(&self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleSession => "CommandOrControl+Shift+R",
+            HotkeyAction::TogglePause => "CommandOrControl+Shift+P",
+            HotkeyAction::InsertMarker => "CommandOrControl+Shift+M",
+            HotkeyAction::ToggleMute => "CommandOrControl+Shift+K",
+        }
+    }
+}
+
+/// Accelerator strings (e.g. `CommandOrControl+Shift+R`) keyed by action.
+/// An action without an entry is unbound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyBindings(pub BTreeMap<HotkeyAction, String>);
+
+impl Default for HotkeyBindings {
+    fn default() -> Self {
+        HotkeyBindings(
+            HotkeyAction::ALL
+                .iter()
+                .map(|action| (*action, action.default_accelerator().to_string()))
+                .collect(),
+        )
+    }
+}
+
+/// Shortcut id -> action for everything we currently have registered with the OS.
+static REGISTERED: Lazy<Mutex<HashMap<u32, (HotkeyAction, Shortcut)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn parse_accelerator(accelerator: &str) -> Result<Shortcut, String> {
+    Shortcut::from_str(accelerator)
+        .map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))
+}
+
+/// Return the action already bound to `shortcut`, ignoring `except`.
+fn find_conflict(
+    bindings: &HotkeyBindings,
+    shortcut: &Shortcut,
+    except: HotkeyAction,
+) -> Option<HotkeyAction> {
+    bindings.0.iter().find_map(|(action, accelerator)| {
+        if *action == except {
+            return None;
+        }
+        match parse_accelerator(accelerator) {
+            Ok(existing) if existing.id() == shortcut.id() => Some(*action),
+            _ => None,
+        }
+    })
+}
+
+/// Plugin used by `run()`; routes pressed shortcuts to their bound action.
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event: ShortcutEvent| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            let action = REGISTERED
+                .lock()
+                .ok()
+                .and_then(|registered| registered.get(&shortcut.id()).map(|(action, _)| *action));
+            if let Some(action) = action {
+                dispatch(app, action);
+            }
+        })
+        .build()
+}
+
+/// Register all persisted bindings. Bindings that fail (usually because another
+/// application already owns the shortcut) are logged and skipped.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let bindings = settings::get().hotkeys;
+    for (action, accelerator) in bindings.0.iter() {
+        if let Err(e) = register(app, *action, accelerator) {
+            warn!("Failed to register hotkey for {:?}: {}", action, e);
+        }
+    }
+}
+
+fn register<R: Runtime>(
+    app: &AppHandle<R>,
+    action: HotkeyAction,
+    accelerator: &str,
+) -> Result<(), String> {
+    let shortcut = parse_accelerator(accelerator)?;
+    app.global_shortcut().register(shortcut).map_err(|e| {
+        format!(
+            "Shortcut '{}' is unavailable (it may be used by another application): {}",
+            accelerator, e
+        )
+    })?;
+    REGISTERED
+        .lock()
+        .map_err(|e| format!("Failed to lock hotkey registry: {}", e))?
+        .insert(shortcut.id(), (action, shortcut));
+    info!("Registered hotkey {} for {:?}", accelerator, action);
+    Ok(())
+}
+
+fn unregister<R: Runtime>(app: &AppHandle<R>, action: HotkeyAction) -> Result<(), String> {
+    let mut registered = REGISTERED
+        .lock()
+        .map_err(|e| format!("Failed to lock hotkey registry: {}", e))?;
+    let ids: Vec<u32> = registered
+        .iter()
+        .filter(|(_, (bound, _))| *bound == action)
+        .map(|(id, _)| *id)
+        .collect();
+    for id in ids {
+        if let Some((_, shortcut)) = registered.remove(&id) {
+            app.global_shortcut()
+                .unregister(shortcut)
+                .map_err(|e| format!("Failed to unregister shortcut: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+fn dispatch<R: Runtime>(app: &AppHandle<R>, action: HotkeyAction) {
+    info!("Hotkey triggered: {:?}", action);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match action {
+            HotkeyAction::ToggleSession => {
+                if crate::is_recording() {
+                    crate::stop_recording_session(&app, false).await
+                } else {
+                    crate::start_recording(app.clone(), None, None, None, None, None).await
+                }
+            }
+            HotkeyAction::TogglePause => {
+                if crate::is_paused() {
+                    crate::resume_recording()
+                } else {
+                    crate::pause_recording()
+                }
+            }
+            HotkeyAction::InsertMarker => crate::insert_marker(app.clone(), None).map(|_| ()),
+            HotkeyAction::ToggleMute => crate::toggle_mute().map(|_| ()),
+        };
+
+        if let Err(e) = result {
+            error!("Hotkey action {:?} failed: {}", action, e);
+        }
+        if let Err(e) = app.emit("hotkey-triggered", action) {
+            error!("Failed to emit hotkey event: {}", e);
+        }
+    });
+}
+
+#[command]
+pub fn get_hotkey_bindings() -> HotkeyBindings {
+    settings::get().hotkeys
+}
+
+/// Bind `action` to `accelerator`, or unbind it when `accelerator` is `None`.
+/// Rejects shortcuts already bound to another action or held by another application.
+#[command]
+pub fn set_hotkey_binding<R: Runtime>(
+    app: AppHandle<R>,
+    action: HotkeyAction,
+    accelerator: Option<String>,
+) -> Result<HotkeyBindings, String> {
+    let current = settings::get().hotkeys;
+
+    if let Some(accelerator) = accelerator.as_deref() {
+        let shortcut = parse_accelerator(accelerator)?;
+        if let Some(other) = find_conflict(&current, &shortcut, action) {
+            return Err(format!(
+                "Shortcut '{}' is already bound to {:?}",
+                accelerator, other
+            ));
+        }
+    }
+
+    unregister(&app, action)?;
+
+    if let Some(accelerator) = accelerator.as_deref() {
+        if let Err(e) = register(&app, action, accelerator) {
+            // Restore the previous binding so the action doesn't silently go unbound
+            if let Some(previous) = current.0.get(&action) {
+                if let Err(restore_err) = register(&app, action, previous) {
+                    warn!("Failed to restore hotkey for {:?}: {}", action, restore_err);
+                }
+            }
+            return Err(e);
+        }
+    }
+
+    let updated = settings::update(|s| match accelerator {
+        Some(accelerator) => {
+            s.hotkeys.0.insert(action, accelerator);
+        }
+        None => {
+            s.hotkeys.0.remove(&action);
+        }
+    })?;
+    Ok(updated.hotkeys)
+}
@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::future::BoxFuture;
+use log::{error, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Notify};
+
+use crate::encryption;
+use crate::pipeline::{self, PipelineEvent};
+use crate::transcription::AudioInput;
+
+/// How often a blocked submit looks for room besides being woken by a worker.
+const BLOCKED_POLL: Duration = Duration::from_millis(50);
+
+static SPILL_DIRS: AtomicU64 = AtomicU64::new(0);
+
+/// What to do with a chunk submitted while the queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait for a worker to take a chunk, holding up the capture loop.
+    #[default]
+    Block,
+    /// Discard the oldest waiting chunk to make room for the new one.
+    DropOldest,
+    /// Park chunks on disk and queue them again, in order, as room frees up.
+    SpillToDisk,
+}
+
+/// Sizing of the queues in and out of the inference workers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InferenceQueueSettings {
+    /// Chunks waiting for a worker.
+    pub input_capacity: usize,
+    /// Results waiting for the capture loop to collect them; workers pause
+    /// when it's full.
+    pub output_capacity: usize,
+    pub overflow: OverflowPolicy,
+    /// Share of `input_capacity` waiting at which a backpressure warning is
+    /// published.
+    pub high_water: f32,
+}
+
+impl Default for InferenceQueueSettings {
+    fn default() -> Self {
+        Self {
+            input_capacity: 8,
+            output_capacity: 8,
+            overflow: OverflowPolicy::Block,
+            high_water: 0.75,
+        }
+    }
+}
+
+/// A chunk handed to the pool. `ticket` comes back with its result so the
+/// caller can find the state it kept for the chunk.
+pub struct Job {
+    pub ticket: u64,
+    pub input: AudioInput,
+    /// Server the governor sends chunks to at reduced quality, instead of
+    /// the active engine.
+    pub endpoint: Option<String>,
+}
+
+/// A job's result and how long the engine took on it, excluding time spent
+/// waiting in the queue. Chunks dropped on overflow come back as errors,
+/// with `dropped` set, so they can be retried like any failed chunk.
+pub struct Finished<T> {
+    pub ticket: u64,
+    pub result: Result<T, String>,
+    pub elapsed: Duration,
+    pub dropped: bool,
+}
+
+/// A job tagged with its place in its device's stream.
+struct Queued {
+    device: String,
+    seq: u64,
+    job: Job,
+}
+
+/// A queued job whose audio was moved to disk.
+struct Spilled {
+    device: String,
+    seq: u64,
+    ticket: u64,
+    path: PathBuf,
+    sample_rate: u32,
+    language: Option<String>,
+    endpoint: Option<String>,
+}
+
+struct Done<T> {
+    device: String,
+    seq: u64,
+    finished: Finished<T>,
+}
+
+struct Queue {
+    jobs: Mutex<VecDeque<Queued>>,
+    ready: Notify,
+    /// Woken when a worker takes a job, for submits waiting on room.
+    room: Notify,
+    closed: AtomicBool,
+}
+
+impl Queue {
+    /// The next job, or `None` once the pool is dropped.
+    async fn next(&self) -> Option<Queued> {
+        loop {
+            if self.closed.load(Ordering::SeqCst) {
+                // Pass the wakeup on to the next idle worker
+                self.ready.notify_one();
+                return None;
+            }
+            if let Some(job) = self.jobs.lock().ok().and_then(|mut jobs| jobs.pop_front()) {
+                self.room.notify_one();
+                return Some(job);
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    fn waiting(&self) -> usize {
+        self.jobs.lock().map(|jobs| jobs.len()).unwrap_or(0)
+    }
+}
+
+/// Runs chunk transcription on `workers` long-lived tasks, off the capture
+/// loop, so audio keeps being collected while chunks decode. Every job goes
+/// through the same `run`, which shares the engines and HTTP client instead
+/// of setting anything up per chunk.
+///
+/// Workers finish out of order; results are handed back in the order their
+/// device submitted them, so a device's transcript never runs backwards.
+pub struct InferencePool<T> {
+    queue: Arc<Queue>,
+    finished: mpsc::Receiver<Done<T>>,
+    config: InferenceQueueSettings,
+    spilled: VecDeque<Spilled>,
+    spill_dir: PathBuf,
+    next_seq: HashMap<String, u64>,
+    /// Per device: the next sequence number to hand back, and results that
+    /// came in ahead of it.
+    reorder: HashMap<String, (u64, BTreeMap<u64, Finished<T>>)>,
+    in_flight: usize,
+    above_high_water: bool,
+}
+
+impl<T: Send + 'static> InferencePool<T> {
+    pub fn start<F>(workers: usize, config: InferenceQueueSettings, run: F) -> Self
+    where
+        F: Fn(Job) -> BoxFuture<'static, Result<T, String>> + Send + Sync + 'static,
+    {
+        let queue = Arc::new(Queue {
+            jobs: Mutex::new(VecDeque::new()),
+            ready: Notify::new(),
+            room: Notify::new(),
+            closed: AtomicBool::new(false),
+        });
+        let (finished_tx, finished) = mpsc::channel(config.output_capacity.max(1));
+        let run = Arc::new(run);
+        for _ in 0..workers.max(1) {
+            let queue = queue.clone();
+            let finished_tx = finished_tx.clone();
+            let run = run.clone();
+            tokio::spawn(async move {
+                while let Some(Queued { device, seq, job }) = queue.next().await {
+                    let ticket = job.ticket;
+                    let started = Instant::now();
+                    let result = run(job).await;
+                    let finished = Finished {
+                        ticket,
+                        result,
+                        elapsed: started.elapsed(),
+                        dropped: false,
+                    };
+                    // The capture loop is gone once the session ends
+                    if finished_tx.send(Done { device, seq, finished }).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        let spill_dir = std::env::temp_dir().join(format!(
+            "meetingly-spill-{}-{}",
+            std::process::id(),
+            SPILL_DIRS.fetch_add(1, Ordering::SeqCst)
+        ));
+        Self {
+            queue,
+            finished,
+            config,
+            spilled: VecDeque::new(),
+            spill_dir,
+            next_seq: HashMap::new(),
+            reorder: HashMap::new(),
+            in_flight: 0,
+            above_high_water: false,
+        }
+    }
+
+    /// Queue `job` behind the chunks already waiting. When the queue is full
+    /// the overflow policy applies.
+    pub async fn submit(&mut self, job: Job) {
+        let device = job.input.device.clone().unwrap_or_default();
+        let seq = self.next_seq.entry(device.clone()).or_insert(0);
+        let queued = Queued {
+            device,
+            seq: *seq,
+            job,
+        };
+        *seq += 1;
+        self.in_flight += 1;
+
+        self.refill();
+        match self.config.overflow {
+            _ if self.has_room() => self.push(queued),
+            OverflowPolicy::Block => {
+                while !self.has_room() {
+                    // Results keep flowing, or full workers could never take more
+                    self.collect();
+                    let _ = tokio::time::timeout(BLOCKED_POLL, self.queue.room.notified()).await;
+                }
+                self.push(queued);
+            }
+            OverflowPolicy::DropOldest => {
+                let oldest = self.queue.jobs.lock().ok().and_then(|mut jobs| jobs.pop_front());
+                if let Some(oldest) = oldest {
+                    warn!("Inference queue full, dropping chunk {}", oldest.job.ticket);
+                    self.drop_job(oldest, "Dropped: the inference queue was full".to_string());
+                }
+                self.push(queued);
+            }
+            OverflowPolicy::SpillToDisk => {
+                self.spill(queued);
+            }
+        }
+        self.check_high_water();
+    }
+
+    /// The next finished job whose device has no earlier job still decoding,
+    /// without waiting.
+    pub fn try_finished(&mut self) -> Option<Finished<T>> {
+        self.collect();
+        self.refill();
+        self.check_high_water();
+        for (next, waiting) in self.reorder.values_mut() {
+            if let Some(finished) = waiting.remove(next) {
+                *next += 1;
+                self.in_flight -= 1;
+                return Some(finished);
+            }
+        }
+        None
+    }
+
+    /// Whether every submitted job has been collected.
+    pub fn is_idle(&self) -> bool {
+        self.in_flight == 0
+    }
+
+    /// Chunks waiting for a worker, on disk included.
+    pub fn waiting(&self) -> usize {
+        self.queue.waiting() + self.spilled.len()
+    }
+
+    fn has_room(&self) -> bool {
+        // Spilled chunks go first, or a device's chunks would be reordered
+        self.spilled.is_empty() && self.queue.waiting() < self.config.input_capacity.max(1)
+    }
+
+    fn push(&self, queued: Queued) {
+        if let Ok(mut jobs) = self.queue.jobs.lock() {
+            jobs.push_back(queued);
+        }
+        self.queue.ready.notify_one();
+    }
+
+    fn collect(&mut self) {
+        while let Ok(done) = self.finished.try_recv() {
+            let (_, waiting) = self.reorder.entry(done.device).or_insert_with(|| (0, BTreeMap::new()));
+            waiting.insert(done.seq, done.finished);
+        }
+    }
+
+    /// Hand `queued` back as failed, in its place in its device's order.
+    fn drop_job(&mut self, queued: Queued, error: String) {
+        let (_, waiting) = self.reorder.entry(queued.device).or_insert_with(|| (0, BTreeMap::new()));
+        let finished = Finished {
+            ticket: queued.job.ticket,
+            result: Err(error),
+            elapsed: Duration::ZERO,
+            dropped: true,
+        };
+        waiting.insert(queued.seq, finished);
+    }
+
+    fn spill(&mut self, queued: Queued) {
+        let path = self.spill_dir.join(format!("{}.f32", queued.job.ticket));
+        let bytes: Vec<u8> = queued.job.input.samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let written = fs::create_dir_all(&self.spill_dir).and_then(|_| encryption::write(&path, bytes));
+        if let Err(e) = written {
+            error!("Failed to spill chunk to {}: {}", path.display(), e);
+            self.drop_job(queued, format!("Dropped: failed to spill to disk: {}", e));
+            return;
+        }
+        let Queued { device, seq, job } = queued;
+        self.spilled.push_back(Spilled {
+            device,
+            seq,
+            ticket: job.ticket,
+            path,
+            sample_rate: job.input.sample_rate,
+            language: job.input.language,
+            endpoint: job.endpoint,
+        });
+    }
+
+    /// Move spilled chunks back into the queue while there's room.
+    fn refill(&mut self) {
+        while self.queue.waiting() < self.config.input_capacity.max(1) {
+            let Some(spilled) = self.spilled.pop_front() else {
+                break;
+            };
+            let bytes = encryption::read(&spilled.path);
+            let _ = fs::remove_file(&spilled.path);
+            let samples = match &bytes {
+                Ok(bytes) => bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+            let queued = Queued {
+                device: spilled.device.clone(),
+                seq: spilled.seq,
+                job: Job {
+                    ticket: spilled.ticket,
+                    input: AudioInput {
+                        samples,
+                        sample_rate: spilled.sample_rate,
+                        language: spilled.language,
+                        device: Some(spilled.device),
+                    },
+                    endpoint: spilled.endpoint,
+                },
+            };
+            match bytes {
+                Ok(_) => self.push(queued),
+                Err(e) => {
+                    error!("Failed to read spilled chunk {}: {}", spilled.path.display(), e);
+                    self.drop_job(queued, format!("Dropped: failed to read the spilled chunk back: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Warn once each time the queue climbs past the high-water mark.
+    fn check_high_water(&mut self) {
+        let capacity = self.config.input_capacity.max(1);
+        let mark = ((capacity as f32 * self.config.high_water.clamp(0.0, 1.0)).ceil() as usize).max(1);
+        let queued = self.waiting();
+        if queued >= mark && !self.above_high_water {
+            warn!("Inference queue at {} of {} chunks", queued, capacity);
+            pipeline::publish(PipelineEvent::Backpressure {
+                queued,
+                capacity,
+                policy: self.config.overflow,
+            });
+        }
+        self.above_high_water = queued >= mark;
+    }
+}
+
+impl<T> Drop for InferencePool<T> {
+    fn drop(&mut self) {
+        self.queue.closed.store(true, Ordering::SeqCst);
+        self.queue.ready.notify_one();
+        let _ = fs::remove_dir_all(&self.spill_dir);
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::replay::{self, ReplayRecord};
+use crate::storage::{self, DERIVED_DIR, RAW_DIR, TRANSCRIPT_FILE};
+use crate::{encryption, secrets, settings, TranscriptUpdate};
+
+const LOG_FILE: &str = "integrity.log";
+const SIGNING_KEY_SECRET: &str = "integrity-signing-key";
+/// `prev_hash` of the first entry in a log.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntegritySettings {
+    /// Append hashes of raw capture and derived transcripts to a
+    /// tamper-evident log whenever a meeting is stored or regenerated.
+    pub enabled: bool,
+    /// Sign each checkpoint with a local Ed25519 key kept in the secrets vault.
+    pub sign: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    RawChunk,
+    EngineResponse,
+    Segment,
+    File,
+    /// Closes a batch of entries; its hash is the meeting hash at that point.
+    Checkpoint,
+}
+
+/// One line of `integrity.log`. `hash` covers the previous entry's hash, so
+/// editing or removing any earlier line breaks every hash after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityEntry {
+    pub seq: u64,
+    pub kind: EntryKind,
+    pub label: String,
+    pub content_hash: String,
+    pub prev_hash: String,
+    pub hash: String,
+    pub recorded_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntegrityReport {
+    pub meeting_id: String,
+    /// Every log entry links to the one before it and hashes correctly.
+    pub chain_valid: bool,
+    /// Whether the last checkpoint is signed by this device's key. `None`
+    /// when it is unsigned and signing is off.
+    pub signature_valid: Option<bool>,
+    pub meeting_hash: Option<String>,
+    pub checked: usize,
+    /// Labels whose stored data no longer matches the recorded hash.
+    pub altered: Vec<String>,
+    /// Labels recorded in the log whose data is gone.
+    pub missing: Vec<String>,
+    /// Labels in the stored data that the last checkpoint doesn't cover,
+    /// such as appended segments or files.
+    pub unrecorded: Vec<String>,
+    pub intact: bool,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn chain_hash(prev_hash: &str, kind: EntryKind, label: &str, content_hash: &str) -> String {
+    sha256_hex(format!("{}|{:?}|{}|{}", prev_hash, kind, label, content_hash).as_bytes())
+}
+
+fn samples_hash(samples: &[f32]) -> String {
+    let mut hasher = Sha256::new();
+    for sample in samples {
+        hasher.update(sample.to_le_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn segment_hash(update: &TranscriptUpdate) -> String {
+    sha256_hex(format!("{}|{}|{}", update.timestamp, update.source, update.text).as_bytes())
+}
+
+/// Hash every piece of the meeting's current data, keyed by label.
+fn current_hashes(dir: &Path) -> Vec<(EntryKind, String, String)> {
+    let mut hashes = Vec::new();
+
+    let raw = storage::raw_session_path(dir);
+    if let Ok(entries) = replay::read_replay(&raw) {
+        for (record, audio) in entries {
+            match record {
+                ReplayRecord::Chunk { index, .. } => hashes.push((
+                    EntryKind::RawChunk,
+                    format!("chunk:{}", index),
+                    samples_hash(&audio.unwrap_or_default()),
+                )),
+                ReplayRecord::Response { index, segments, .. } => hashes.push((
+                    EntryKind::EngineResponse,
+                    format!("response:{}", index),
+                    sha256_hex(serde_json::to_string(&segments).unwrap_or_default().as_bytes()),
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    let transcript = dir.join(DERIVED_DIR).join(TRANSCRIPT_FILE);
+    if let Some(updates) = encryption::read_to_string(&transcript)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Vec<TranscriptUpdate>>(&content).ok())
+    {
+        for (index, update) in updates.iter().enumerate() {
+            hashes.push((EntryKind::Segment, format!("segment:{}", index), segment_hash(update)));
+        }
+    }
+
+    for folder in [RAW_DIR, DERIVED_DIR] {
+        let mut names = storage::list_files(&dir.join(folder));
+        names.sort();
+        for name in names {
+            if let Ok(bytes) = fs::read(dir.join(folder).join(&name)) {
+                hashes.push((EntryKind::File, format!("{}/{}", folder, name), sha256_hex(&bytes)));
+            }
+        }
+    }
+    hashes
+}
+
+fn read_log(dir: &Path) -> Vec<IntegrityEntry> {
+    fs::read_to_string(dir.join(LOG_FILE))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn signing_key() -> Result<SigningKey, String> {
+    if let Some(encoded) = secrets::get(SIGNING_KEY_SECRET)? {
+        let bytes: [u8; 32] = hex::decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "Stored integrity signing key is invalid".to_string())?;
+        return Ok(SigningKey::from_bytes(&bytes));
+    }
+    let key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+    secrets::set_secret(SIGNING_KEY_SECRET.to_string(), hex::encode(key.to_bytes()))?;
+    info!("Generated integrity signing key");
+    Ok(key)
+}
+
+/// Append the meeting's current hashes and a checkpoint to its integrity log.
+pub fn seal(dir: &Path) -> Result<String, String> {
+    let config = settings::get().integrity;
+    let log = read_log(dir);
+    let mut prev_hash = log.last().map(|e| e.hash.clone()).unwrap_or_else(|| GENESIS_HASH.to_string());
+    let mut seq = log.last().map(|e| e.seq + 1).unwrap_or(0);
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let mut batch = Vec::new();
+    let mut push = |kind: EntryKind, label: String, content_hash: String| {
+        let hash = chain_hash(&prev_hash, kind, &label, &content_hash);
+        let entry = IntegrityEntry {
+            seq,
+            kind,
+            label,
+            content_hash,
+            prev_hash: prev_hash.clone(),
+            hash: hash.clone(),
+            recorded_at: now.clone(),
+            signature: None,
+            public_key: None,
+        };
+        seq += 1;
+        prev_hash = hash;
+        batch.push(entry);
+    };
+    let hashes = current_hashes(dir);
+    let count = hashes.len();
+    for (kind, label, content_hash) in hashes {
+        push(kind, label, content_hash);
+    }
+    push(EntryKind::Checkpoint, "checkpoint".to_string(), sha256_hex(count.to_string().as_bytes()));
+
+    let checkpoint = batch.last_mut().expect("checkpoint was just pushed");
+    if config.sign {
+        let key = signing_key()?;
+        checkpoint.signature = Some(hex::encode(key.sign(checkpoint.hash.as_bytes()).to_bytes()));
+        checkpoint.public_key = Some(hex::encode(key.verifying_key().to_bytes()));
+    }
+    let meeting_hash = checkpoint.hash.clone();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE))
+        .map_err(|e| format!("Failed to open integrity log: {}", e))?;
+    for entry in &batch {
+        let line = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize integrity entry: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write integrity log: {}", e))?;
+    }
+    info!("Sealed {:?}: {} hashes, meeting hash {}", dir, count, meeting_hash);
+    Ok(meeting_hash)
+}
+
+/// Seal if integrity logging is enabled; failures are logged, not fatal.
+pub fn seal_if_enabled(dir: &Path) {
+    if settings::get().integrity.enabled {
+        if let Err(e) = seal(dir) {
+            warn!("Failed to update integrity log for {:?}: {}", dir, e);
+        }
+    }
+}
+
+/// The public half of the key in the secrets vault, without creating one.
+fn trusted_key() -> Result<Option<VerifyingKey>, String> {
+    let Some(encoded) = secrets::get(SIGNING_KEY_SECRET)? else {
+        return Ok(None);
+    };
+    let bytes: [u8; 32] = hex::decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Stored integrity signing key is invalid".to_string())?;
+    Ok(Some(SigningKey::from_bytes(&bytes).verifying_key()))
+}
+
+/// Check the checkpoint's signature against this device's key. The key
+/// stored in the checkpoint is only informational: anyone rewriting the log
+/// could sign it with a key of their own.
+fn verify_signature(checkpoint: &IntegrityEntry) -> Option<bool> {
+    let Some(signature) = checkpoint.signature.as_ref() else {
+        // Signatures can be stripped along with everything else
+        return settings::get().integrity.sign.then_some(false);
+    };
+    let key = match trusted_key() {
+        Ok(Some(key)) => key,
+        Ok(None) => {
+            warn!("No integrity signing key to check the signature against");
+            return Some(false);
+        }
+        Err(e) => {
+            warn!("Failed to read integrity signing key: {}", e);
+            return Some(false);
+        }
+    };
+    let valid = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .is_some_and(|bytes| key.verify(checkpoint.hash.as_bytes(), &Signature::from_bytes(&bytes)).is_ok());
+    Some(valid)
+}
+
+/// Check a meeting's integrity log and compare the last checkpoint against
+/// the stored raw audio, engine output and derived transcript.
+#[command]
+pub fn verify_meeting_integrity<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<IntegrityReport, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    let log = read_log(&dir);
+    if log.is_empty() {
+        return Err(format!("Meeting {} has no integrity log", meeting_id));
+    }
+
+    let mut chain_valid = true;
+    let mut expected_prev = GENESIS_HASH.to_string();
+    for (index, entry) in log.iter().enumerate() {
+        let recomputed = chain_hash(&entry.prev_hash, entry.kind, &entry.label, &entry.content_hash);
+        if entry.seq != index as u64 || entry.prev_hash != expected_prev || entry.hash != recomputed {
+            warn!("Integrity log for {} breaks at entry {}", meeting_id, index);
+            chain_valid = false;
+            break;
+        }
+        expected_prev = entry.hash.clone();
+    }
+
+    // The last sealed batch describes what should be on disk now
+    let checkpoints: Vec<usize> = log
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.kind == EntryKind::Checkpoint)
+        .map(|(i, _)| i)
+        .collect();
+    let Some(&last) = checkpoints.last() else {
+        return Err(format!("Meeting {} integrity log has no checkpoint", meeting_id));
+    };
+    let batch_start = checkpoints.iter().rev().nth(1).map(|i| i + 1).unwrap_or(0);
+    let recorded = &log[batch_start..last];
+
+    let hashes = current_hashes(&dir);
+    let current: HashMap<&str, &str> = hashes
+        .iter()
+        .map(|(_, label, hash)| (label.as_str(), hash.as_str()))
+        .collect();
+    let mut altered = Vec::new();
+    let mut missing = Vec::new();
+    for entry in recorded {
+        match current.get(entry.label.as_str()) {
+            Some(hash) if *hash == entry.content_hash => {}
+            Some(_) => altered.push(entry.label.clone()),
+            None => missing.push(entry.label.clone()),
+        }
+    }
+    let recorded_labels: HashSet<&str> = recorded.iter().map(|entry| entry.label.as_str()).collect();
+    let unrecorded: Vec<String> = hashes
+        .iter()
+        .filter(|(_, label, _)| !recorded_labels.contains(label.as_str()))
+        .map(|(_, label, _)| label.clone())
+        .collect();
+
+    let signature_valid = verify_signature(&log[last]);
+    let intact = chain_valid
+        && altered.is_empty()
+        && missing.is_empty()
+        && unrecorded.is_empty()
+        && signature_valid != Some(false);
+    Ok(IntegrityReport {
+        meeting_id,
+        chain_valid,
+        signature_valid,
+        meeting_hash: Some(log[last].hash.clone()),
+        checked: recorded.len(),
+        altered,
+        missing,
+        unrecorded,
+        intact,
+    })
+}
+
+#[command]
+pub fn get_integrity_settings() -> IntegritySettings {
+    settings::get().integrity
+}
+
+#[command]
+pub fn set_integrity_settings(integrity_settings: IntegritySettings) -> Result<IntegritySettings, String> {
+    settings::update(|s| s.integrity = integrity_settings).map(|s| s.integrity)
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::audio::app_activity::AudioSource;
+use crate::audio::{parse_audio_device, AudioDevice, DeviceType};
+use crate::settings;
+
+/// A microphone dedicated to one participant.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KitChannel {
+    /// Input device name as listed by `get_audio_devices`; the `(input)`
+    /// suffix is optional.
+    pub device: String,
+    /// Everything picked up on `device` is attributed to this name.
+    pub speaker: String,
+}
+
+/// Interview kit: each participant speaks into their own microphone, so
+/// speakers are assigned by device instead of by voice. Replaces the system
+/// audio capture with the second microphone, and skips voice embeddings and
+/// engine diarization altogether.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InterviewKitSettings {
+    pub enabled: bool,
+    pub host: KitChannel,
+    pub guest: KitChannel,
+}
+
+impl Default for InterviewKitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: KitChannel {
+                device: String::new(),
+                speaker: "Host".to_string(),
+            },
+            guest: KitChannel {
+                device: String::new(),
+                speaker: "Guest".to_string(),
+            },
+        }
+    }
+}
+
+fn input_device(name: &str) -> Result<AudioDevice, String> {
+    let name = name.trim();
+    let device = if name.to_lowercase().ends_with("(input)") || name.to_lowercase().ends_with("(output)") {
+        parse_audio_device(name)
+    } else {
+        parse_audio_device(&format!("{} (input)", name))
+    }
+    .map_err(|e| format!("Invalid interview kit device '{}': {}", name, e))?;
+    if device.device_type != DeviceType::Input {
+        return Err(format!("Interview kit device '{}' is not a microphone", name));
+    }
+    Ok(device)
+}
+
+impl InterviewKitSettings {
+    fn validate(&self) -> Result<(), String> {
+        let (host, guest) = (self.host.speaker.trim(), self.guest.speaker.trim());
+        if host.is_empty() || guest.is_empty() {
+            return Err("Both interview kit speakers need a name".to_string());
+        }
+        if host.eq_ignore_ascii_case(guest) {
+            return Err("Interview kit speakers need different names".to_string());
+        }
+        if self.enabled {
+            self.devices()?;
+        }
+        Ok(())
+    }
+
+    /// The host's and the guest's microphones.
+    pub fn devices(&self) -> Result<(AudioDevice, AudioDevice), String> {
+        let host = input_device(&self.host.device)?;
+        let guest = input_device(&self.guest.device)?;
+        if host == guest {
+            return Err("The interview kit needs two different microphones".to_string());
+        }
+        Ok((host, guest))
+    }
+
+    /// Whoever is louder on their own microphone. Each microphone also picks
+    /// up the other participant, but always more quietly.
+    pub fn speaker(&self, host_rms: f32, guest_rms: f32) -> AudioSource {
+        let channel = if guest_rms > host_rms { &self.guest } else { &self.host };
+        AudioSource::Speaker(channel.speaker.trim().to_string())
+    }
+}
+
+/// Settings for the recording about to start, when the kit is on.
+pub fn active() -> Option<InterviewKitSettings> {
+    let config = settings::get().interview_kit;
+    config.enabled.then_some(config)
+}
+
+#[command]
+pub fn get_interview_kit_settings() -> InterviewKitSettings {
+    settings::get().interview_kit
+}
+
+#[command]
+pub fn set_interview_kit_settings(interview_kit_settings: InterviewKitSettings) -> Result<InterviewKitSettings, String> {
+    interview_kit_settings.validate()?;
+    settings::update(|s| s.interview_kit = interview_kit_settings).map(|s| s.interview_kit)
+}
@@ -0,0 +1,565 @@
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::settings;
+
+/// A replacement applied after the built-in normalization, e.g. currency
+/// words to symbols.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItnRule {
+    /// Language code the rule applies to, or `*` for every language.
+    pub language: String,
+    /// Regular expression; `replacement` may refer to its groups as `$1`.
+    pub pattern: String,
+    pub replacement: String,
+}
+
+/// Inverse text normalization: spelled-out numbers and dates in transcript
+/// lines are rewritten as digits, so amounts and dates can be picked out of
+/// meetings in any supported language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItnSettings {
+    pub enabled: bool,
+    /// Languages normalized by the built-in rules. Whisper already writes
+    /// English numbers as digits, so English is off unless added here.
+    pub languages: Vec<String>,
+    /// Language assumed for lines the engine didn't tag with one.
+    pub default_language: Option<String>,
+    pub rules: Vec<ItnRule>,
+}
+
+impl Default for ItnSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            languages: vec!["de".to_string(), "fr".to_string(), "es".to_string()],
+            default_language: None,
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lexeme {
+    Value(u64),
+    Hundred,
+    /// Thousand, million, ...
+    Big(u64),
+    /// "and" between parts of one number, as in "vingt et un".
+    And,
+}
+
+use Lexeme::{And, Big, Hundred, Value};
+
+struct Language {
+    code: &'static str,
+    words: &'static [(&'static str, Lexeme)],
+    /// Words for "one" that are far more often an article.
+    articles: &'static [&'static str],
+    /// Number words are written together, as in "dreiundzwanzig".
+    compounds: bool,
+    /// Dropped before a date: "le trois mars" is "3 March".
+    date_articles: &'static [&'static str],
+    /// Between day and month, or month and year: "tres de marzo de 2024".
+    date_links: &'static [&'static str],
+    months: &'static [(&'static str, usize)],
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+    "December",
+];
+
+const ENGLISH: Language = Language {
+    code: "en",
+    words: &[
+        ("zero", Value(0)), ("one", Value(1)), ("two", Value(2)), ("three", Value(3)), ("four", Value(4)),
+        ("five", Value(5)), ("six", Value(6)), ("seven", Value(7)), ("eight", Value(8)), ("nine", Value(9)),
+        ("ten", Value(10)), ("eleven", Value(11)), ("twelve", Value(12)), ("thirteen", Value(13)),
+        ("fourteen", Value(14)), ("fifteen", Value(15)), ("sixteen", Value(16)), ("seventeen", Value(17)),
+        ("eighteen", Value(18)), ("nineteen", Value(19)), ("twenty", Value(20)), ("thirty", Value(30)),
+        ("forty", Value(40)), ("fifty", Value(50)), ("sixty", Value(60)), ("seventy", Value(70)),
+        ("eighty", Value(80)), ("ninety", Value(90)), ("hundred", Hundred), ("thousand", Big(1_000)),
+        ("million", Big(1_000_000)), ("billion", Big(1_000_000_000)), ("and", And),
+    ],
+    articles: &["one"],
+    compounds: false,
+    date_articles: &["the"],
+    date_links: &["of"],
+    months: &[
+        ("january", 1), ("february", 2), ("march", 3), ("april", 4), ("may", 5), ("june", 6), ("july", 7),
+        ("august", 8), ("september", 9), ("october", 10), ("november", 11), ("december", 12),
+    ],
+};
+
+const GERMAN: Language = Language {
+    code: "de",
+    words: &[
+        ("null", Value(0)), ("eins", Value(1)), ("ein", Value(1)), ("eine", Value(1)), ("einen", Value(1)),
+        ("zwei", Value(2)), ("drei", Value(3)), ("vier", Value(4)), ("fünf", Value(5)), ("sechs", Value(6)),
+        ("sieben", Value(7)), ("acht", Value(8)), ("neun", Value(9)), ("zehn", Value(10)), ("elf", Value(11)),
+        ("zwölf", Value(12)), ("dreizehn", Value(13)), ("vierzehn", Value(14)), ("fünfzehn", Value(15)),
+        ("sechzehn", Value(16)), ("siebzehn", Value(17)), ("achtzehn", Value(18)), ("neunzehn", Value(19)),
+        ("zwanzig", Value(20)), ("dreißig", Value(30)), ("vierzig", Value(40)), ("fünfzig", Value(50)),
+        ("sechzig", Value(60)), ("siebzig", Value(70)), ("achtzig", Value(80)), ("neunzig", Value(90)),
+        ("hundert", Hundred), ("tausend", Big(1_000)), ("million", Big(1_000_000)),
+        ("millionen", Big(1_000_000)), ("milliarde", Big(1_000_000_000)), ("milliarden", Big(1_000_000_000)),
+        ("und", And),
+    ],
+    articles: &["ein", "eine", "einen"],
+    compounds: true,
+    date_articles: &["der", "den"],
+    date_links: &[],
+    months: &[
+        ("januar", 1), ("jänner", 1), ("februar", 2), ("märz", 3), ("april", 4), ("mai", 5), ("juni", 6),
+        ("juli", 7), ("august", 8), ("september", 9), ("oktober", 10), ("november", 11), ("dezember", 12),
+    ],
+};
+
+const FRENCH: Language = Language {
+    code: "fr",
+    words: &[
+        ("zéro", Value(0)), ("un", Value(1)), ("une", Value(1)), ("deux", Value(2)), ("trois", Value(3)),
+        ("quatre", Value(4)), ("cinq", Value(5)), ("six", Value(6)), ("sept", Value(7)), ("huit", Value(8)),
+        ("neuf", Value(9)), ("dix", Value(10)), ("onze", Value(11)), ("douze", Value(12)), ("treize", Value(13)),
+        ("quatorze", Value(14)), ("quinze", Value(15)), ("seize", Value(16)), ("vingt", Value(20)),
+        ("vingts", Value(20)), ("trente", Value(30)), ("quarante", Value(40)), ("cinquante", Value(50)),
+        ("soixante", Value(60)), ("cent", Hundred), ("cents", Hundred), ("mille", Big(1_000)),
+        ("million", Big(1_000_000)), ("millions", Big(1_000_000)), ("milliard", Big(1_000_000_000)),
+        ("milliards", Big(1_000_000_000)), ("et", And),
+    ],
+    articles: &["un", "une"],
+    compounds: false,
+    date_articles: &["le"],
+    date_links: &[],
+    months: &[
+        ("janvier", 1), ("février", 2), ("fevrier", 2), ("mars", 3), ("avril", 4), ("mai", 5), ("juin", 6),
+        ("juillet", 7), ("août", 8), ("aout", 8), ("septembre", 9), ("octobre", 10), ("novembre", 11),
+        ("décembre", 12), ("decembre", 12),
+    ],
+};
+
+const SPANISH: Language = Language {
+    code: "es",
+    words: &[
+        ("cero", Value(0)), ("uno", Value(1)), ("un", Value(1)), ("una", Value(1)), ("dos", Value(2)),
+        ("tres", Value(3)), ("cuatro", Value(4)), ("cinco", Value(5)), ("seis", Value(6)), ("siete", Value(7)),
+        ("ocho", Value(8)), ("nueve", Value(9)), ("diez", Value(10)), ("once", Value(11)), ("doce", Value(12)),
+        ("trece", Value(13)), ("catorce", Value(14)), ("quince", Value(15)), ("dieciséis", Value(16)),
+        ("dieciseis", Value(16)), ("diecisiete", Value(17)), ("dieciocho", Value(18)), ("diecinueve", Value(19)),
+        ("veinte", Value(20)), ("veintiuno", Value(21)), ("veintiún", Value(21)), ("veintidós", Value(22)),
+        ("veintidos", Value(22)), ("veintitrés", Value(23)), ("veintitres", Value(23)), ("veinticuatro", Value(24)),
+        ("veinticinco", Value(25)), ("veintiséis", Value(26)), ("veintiseis", Value(26)), ("veintisiete", Value(27)),
+        ("veintiocho", Value(28)), ("veintinueve", Value(29)), ("treinta", Value(30)), ("cuarenta", Value(40)),
+        ("cincuenta", Value(50)), ("sesenta", Value(60)), ("setenta", Value(70)), ("ochenta", Value(80)),
+        ("noventa", Value(90)), ("cien", Value(100)), ("ciento", Value(100)), ("doscientos", Value(200)),
+        ("doscientas", Value(200)), ("trescientos", Value(300)), ("trescientas", Value(300)),
+        ("cuatrocientos", Value(400)), ("cuatrocientas", Value(400)), ("quinientos", Value(500)),
+        ("quinientas", Value(500)), ("seiscientos", Value(600)), ("seiscientas", Value(600)),
+        ("setecientos", Value(700)), ("setecientas", Value(700)), ("ochocientos", Value(800)),
+        ("ochocientas", Value(800)), ("novecientos", Value(900)), ("novecientas", Value(900)), ("mil", Big(1_000)),
+        ("millón", Big(1_000_000)), ("millones", Big(1_000_000)), ("y", And),
+    ],
+    articles: &["un", "una", "uno"],
+    compounds: false,
+    date_articles: &["el"],
+    date_links: &["de"],
+    months: &[
+        ("enero", 1), ("febrero", 2), ("marzo", 3), ("abril", 4), ("mayo", 5), ("junio", 6), ("julio", 7),
+        ("agosto", 8), ("septiembre", 9), ("setiembre", 9), ("octubre", 10), ("noviembre", 11),
+        ("diciembre", 12),
+    ],
+};
+
+const LANGUAGES: [&Language; 4] = [&ENGLISH, &GERMAN, &FRENCH, &SPANISH];
+
+/// A number being read word by word.
+#[derive(Clone)]
+struct Reading {
+    total: u64,
+    current: u64,
+    last_big: u64,
+    lexemes: usize,
+}
+
+impl Reading {
+    fn new() -> Self {
+        Self {
+            total: 0,
+            current: 0,
+            last_big: u64::MAX,
+            lexemes: 0,
+        }
+    }
+
+    fn can_add(&self, lexeme: Lexeme) -> bool {
+        let low = self.current % 100;
+        match lexeme {
+            // Whole hundreds, as in "doscientos"
+            Value(v) if v >= 100 => self.current == 0,
+            Value(v) => {
+                low == 0
+                    || (low >= 20 && low % 10 == 0 && v < 10)
+                    // soixante-dix, quatre-vingt-douze
+                    || ((low == 60 || low == 80) && (10..20).contains(&v))
+            }
+            Hundred => self.current < 100 && (self.current > 0 || self.lexemes == 0),
+            Big(m) => m < self.last_big && (self.current > 0 || self.lexemes == 0),
+            And => false,
+        }
+    }
+
+    fn add(&mut self, lexeme: Lexeme) {
+        match lexeme {
+            Value(v) => self.current += v,
+            Hundred => self.current = self.current.max(1) * 100,
+            Big(m) => {
+                self.total += self.current.max(1) * m;
+                self.current = 0;
+                self.last_big = m;
+            }
+            And => {}
+        }
+        self.lexemes += 1;
+    }
+
+    /// "and" only joins tens to units ("vingt et un") or hundreds and
+    /// thousands to what follows ("one hundred and five").
+    fn can_join(&self, next: Lexeme) -> bool {
+        let low = self.current % 100;
+        let Value(v) = next else {
+            return false;
+        };
+        (low >= 20 && low % 10 == 0 && v < 10) || (low == 0 && self.lexemes > 0 && v < 100)
+    }
+
+    /// Add every lexeme of one word, or none of them.
+    fn add_word(&mut self, lexemes: &[Lexeme]) -> bool {
+        let mut next = self.clone();
+        let mut index = 0;
+        while index < lexemes.len() {
+            let lexeme = lexemes[index];
+            if lexeme == And {
+                match lexemes.get(index + 1) {
+                    Some(&following) if next.can_join(following) => {}
+                    _ => return false,
+                }
+            } else if !next.can_add(lexeme) {
+                return false;
+            }
+            next.add(lexeme);
+            index += 1;
+        }
+        *self = next;
+        true
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+}
+
+/// A whitespace-separated word split from its surrounding punctuation.
+struct Token<'a> {
+    original: &'a str,
+    lead: &'a str,
+    core: String,
+    trail: &'a str,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    text.split_whitespace()
+        .map(|word| {
+            let start = word.find(|c: char| c.is_alphanumeric()).unwrap_or(word.len());
+            let end = word
+                .rfind(|c: char| c.is_alphanumeric())
+                .map(|i| i + word[i..].chars().next().map_or(1, char::len_utf8))
+                .unwrap_or(start)
+                .max(start);
+            Token {
+                original: word,
+                lead: &word[..start],
+                core: word[start..end].to_lowercase(),
+                trail: &word[end..],
+            }
+        })
+        .collect()
+}
+
+impl Language {
+    fn lookup(&self, word: &str) -> Option<Lexeme> {
+        self.words.iter().find(|(w, _)| *w == word).map(|(_, lexeme)| *lexeme)
+    }
+
+    /// Split a written-together German number into its words, longest first.
+    fn split_compound(&self, mut word: &str) -> Option<Vec<Lexeme>> {
+        let mut lexemes = Vec::new();
+        while !word.is_empty() {
+            let (length, lexeme) = self
+                .words
+                .iter()
+                .filter(|(w, _)| word.starts_with(w))
+                .map(|(w, lexeme)| (w.len(), *lexeme))
+                .max_by_key(|(length, _)| *length)?;
+            lexemes.push(lexeme);
+            word = &word[length..];
+        }
+        Some(lexemes)
+    }
+
+    /// The number lexemes a word is made of, if it is a number word.
+    fn lexemes(&self, word: &str) -> Option<Vec<Lexeme>> {
+        let mut lexemes = Vec::new();
+        for part in word.split('-') {
+            match self.lookup(part) {
+                Some(lexeme) => lexemes.push(lexeme),
+                None if self.compounds => lexemes.extend(self.split_compound(part)?),
+                None => return None,
+            }
+        }
+        if lexemes.iter().all(|lexeme| *lexeme == And) {
+            return None;
+        }
+        // Units come before tens in "dreiundzwanzig"; 80 is "quatre-vingts"
+        let mut merged: Vec<Lexeme> = Vec::with_capacity(lexemes.len());
+        let mut index = 0;
+        while index < lexemes.len() {
+            match lexemes[index..] {
+                [Value(unit), And, Value(tens), ..] if unit < 10 && tens >= 20 && tens < 100 && tens % 10 == 0 => {
+                    merged.push(Value(tens + unit));
+                    index += 3;
+                }
+                [Value(4), Value(20), ..] if self.code == "fr" => {
+                    merged.push(Value(80));
+                    index += 2;
+                }
+                _ => {
+                    merged.push(lexemes[index]);
+                    index += 1;
+                }
+            }
+        }
+        Some(merged)
+    }
+
+    /// Read a number starting at token `at`: its value and the token after it.
+    fn number(&self, tokens: &[Token], at: usize) -> Option<(u64, usize)> {
+        let mut reading = Reading::new();
+        let mut end = at;
+        while end < tokens.len() {
+            let token = &tokens[end];
+            if end > at && !token.lead.is_empty() {
+                break;
+            }
+            if self.lookup(&token.core) == Some(And) {
+                // A joining word needs a number after it that it can join
+                let Some(next) = tokens.get(end + 1) else { break };
+                let Some(lexemes) = self.lexemes(&next.core) else { break };
+                if !token.trail.is_empty() || !next.lead.is_empty() || end == at {
+                    break;
+                }
+                let mut joined = vec![And];
+                joined.extend(lexemes);
+                if !reading.add_word(&joined) {
+                    break;
+                }
+                end += 2;
+            } else {
+                let Some(lexemes) = self.lexemes(&token.core) else { break };
+                if !reading.add_word(&lexemes) {
+                    break;
+                }
+                end += 1;
+            }
+            if !tokens[end - 1].trail.is_empty() {
+                break;
+            }
+        }
+        if end == at || (end == at + 1 && self.articles.contains(&tokens[at].core.as_str())) {
+            return None;
+        }
+        Some((reading.value(), end))
+    }
+
+    /// The cardinal an ordinal day word stands for, as in "dritten" or "premier".
+    fn ordinal(&self, word: &str) -> Option<u64> {
+        let single = |word: &str| {
+            let lexemes = self.lexemes(word)?;
+            let mut reading = Reading::new();
+            reading.add_word(&lexemes).then(|| reading.value())
+        };
+        match self.code {
+            "de" => ["sten", "ster", "stes", "ste", "ten", "ter", "tes", "te"].iter().find_map(|suffix| {
+                let stem = word.strip_suffix(suffix)?;
+                let stem = match stem {
+                    "ers" | "er" => "eins",
+                    "drit" => "drei",
+                    "sieb" => "sieben",
+                    "ach" => "acht",
+                    stem => stem,
+                };
+                single(stem)
+            }),
+            "fr" => match word {
+                "premier" | "1er" => Some(1),
+                _ => None,
+            },
+            "es" => match word {
+                "primero" | "primer" => Some(1),
+                _ => None,
+            },
+            _ => {
+                let (head, last) = word.rsplit_once('-').unwrap_or(("", word));
+                let last = match last {
+                    "first" => "one".to_string(),
+                    "second" => "two".to_string(),
+                    "third" => "three".to_string(),
+                    "fifth" => "five".to_string(),
+                    "eighth" => "eight".to_string(),
+                    "ninth" => "nine".to_string(),
+                    "twelfth" => "twelve".to_string(),
+                    last => match last.strip_suffix("ieth") {
+                        Some(stem) => format!("{}y", stem),
+                        None => last.strip_suffix("th")?.to_string(),
+                    },
+                };
+                single(&if head.is_empty() { last } else { format!("{}-{}", head, last) })
+            }
+        }
+    }
+
+    fn day(&self, tokens: &[Token], at: usize) -> Option<(u64, usize)> {
+        let token = tokens.get(at)?;
+        let (day, end) = if token.core.chars().all(|c| c.is_ascii_digit()) {
+            (token.core.parse().ok()?, at + 1)
+        } else if let Some(day) = self.ordinal(&token.core) {
+            (day, at + 1)
+        } else {
+            self.number(tokens, at)?
+        };
+        // "3." is how German writes the third
+        let trail = tokens[end - 1].trail;
+        if !(trail.is_empty() || (self.code == "de" && trail == ".")) {
+            return None;
+        }
+        (1..=31).contains(&day).then_some((day, end))
+    }
+
+    /// Read a date starting at token `at`, as "3 March" or "3 March 2024".
+    fn date(&self, tokens: &[Token], at: usize) -> Option<(String, usize)> {
+        let mut next = at;
+        if self.date_articles.contains(&tokens[next].core.as_str()) && tokens[next].trail.is_empty() {
+            next += 1;
+        }
+        let (day, mut next) = self.day(tokens, next)?;
+        let link = |index: usize| {
+            tokens
+                .get(index)
+                .filter(|t| t.lead.is_empty() && t.trail.is_empty() && self.date_links.contains(&t.core.as_str()))
+                .is_some()
+        };
+        if link(next) {
+            next += 1;
+        }
+        let month_token = tokens.get(next).filter(|t| t.lead.is_empty())?;
+        let month = self.months.iter().find(|(name, _)| *name == month_token.core)?.1;
+        next += 1;
+        let mut date = format!("{} {}", day, MONTH_NAMES[month - 1]);
+
+        let year_at = if link(next) { next + 1 } else { next };
+        if month_token.trail.is_empty() {
+            if let Some(token) = tokens.get(year_at).filter(|t| t.lead.is_empty()) {
+                let year = if token.core.len() == 4 && token.core.chars().all(|c| c.is_ascii_digit()) {
+                    token.core.parse().ok().map(|year| (year, year_at + 1))
+                } else {
+                    self.number(tokens, year_at)
+                };
+                if let Some((year, end)) = year.filter(|(year, _)| (1000..3000).contains(year)) {
+                    date.push_str(&format!(" {}", year));
+                    next = end;
+                }
+            }
+        }
+        Some((date, next))
+    }
+
+    fn normalize(&self, text: &str) -> Option<String> {
+        let tokens = tokenize(text);
+        let mut words: Vec<String> = Vec::with_capacity(tokens.len());
+        let mut changed = false;
+        let mut at = 0;
+        while at < tokens.len() {
+            let replaced = self
+                .date(&tokens, at)
+                .or_else(|| self.number(&tokens, at).map(|(value, end)| (value.to_string(), end)));
+            match replaced {
+                Some((digits, end)) => {
+                    words.push(format!("{}{}{}", tokens[at].lead, digits, tokens[end - 1].trail));
+                    changed = true;
+                    at = end;
+                }
+                None => {
+                    words.push(tokens[at].original.to_string());
+                    at += 1;
+                }
+            }
+        }
+        changed.then(|| words.join(" "))
+    }
+}
+
+fn base_code(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// `text` with numbers and dates in `language` (an engine language code such
+/// as `de` or `fr-FR`) written as digits, then the configured rules applied.
+pub fn normalize(text: &str, language: Option<&str>) -> String {
+    let config = settings::get().itn;
+    if !config.enabled {
+        return text.to_string();
+    }
+    let Some(code) = language.or(config.default_language.as_deref()).map(base_code) else {
+        return text.to_string();
+    };
+
+    let mut text = text.to_string();
+    if config.languages.iter().any(|l| base_code(l) == code) {
+        if let Some(language) = LANGUAGES.iter().find(|l| l.code == code) {
+            if let Some(normalized) = language.normalize(&text) {
+                text = normalized;
+            }
+        }
+    }
+    for rule in &config.rules {
+        if rule.language != "*" && base_code(&rule.language) != code {
+            continue;
+        }
+        match Regex::new(&rule.pattern) {
+            Ok(pattern) => text = pattern.replace_all(&text, rule.replacement.as_str()).into_owned(),
+            Err(e) => warn!("Skipping invalid normalization rule {}: {}", rule.pattern, e),
+        }
+    }
+    text
+}
+
+#[command]
+pub fn get_itn_settings() -> ItnSettings {
+    settings::get().itn
+}
+
+#[command]
+pub fn set_itn_settings(itn_settings: ItnSettings) -> Result<ItnSettings, String> {
+    for rule in &itn_settings.rules {
+        Regex::new(&rule.pattern).map_err(|e| format!("Invalid pattern {}: {}", rule.pattern, e))?;
+    }
+    settings::update(|s| s.itn = itn_settings).map(|s| s.itn)
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use log::{debug, warn};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::settings;
+
+/// Identify the spoken language of every chunk with whisper's language head
+/// and decode the chunk in that language, for meetings whose languages aren't
+/// known up front. Bilingual mode, when on, takes precedence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageIdSettings {
+    pub enabled: bool,
+    /// Whisper language codes a chunk may be identified as; empty allows any.
+    pub candidates: Vec<String>,
+    /// Chunks identified with less confidence than this are decoded without
+    /// a forced language, leaving whisper to pick one while decoding.
+    pub min_confidence: f32,
+}
+
+impl Default for LanguageIdSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            candidates: Vec::new(),
+            min_confidence: 0.5,
+        }
+    }
+}
+
+/// Language a segment was identified as, and the model's confidence in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DetectedLanguage {
+    pub code: String,
+    pub confidence: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Detection {
+    probabilities: HashMap<String, f32>,
+}
+
+/// The language-identification endpoint of the whisper server behind `endpoint`.
+fn detect_endpoint(endpoint: &str) -> String {
+    format!("{}/detect", endpoint.trim_end_matches('/').trim_end_matches("/stream"))
+}
+
+/// Probability of each of `candidates` (every language whisper knows when
+/// empty) being the one spoken in `samples`.
+pub async fn detect(
+    samples: &[f32],
+    client: &reqwest::Client,
+    endpoint: &str,
+    candidates: &[String],
+) -> Result<HashMap<String, f32>, String> {
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.clamp(-1.0, 1.0).to_le_bytes()).collect();
+    let part = Part::bytes(bytes)
+        .file_name("audio.raw")
+        .mime_str("audio/x-raw")
+        .map_err(|e| e.to_string())?;
+    let mut form = Form::new().part("audio", part);
+    if !candidates.is_empty() {
+        form = form.text("candidates", candidates.join(","));
+    }
+    let detection: Detection = client
+        .post(detect_endpoint(endpoint))
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Language identification request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid language identification response: {}", e))?;
+    Ok(detection.probabilities)
+}
+
+/// With automatic identification on, the most likely language of `samples`.
+/// `None` when it's off or identification failed.
+pub async fn identify(samples: &[f32], client: &reqwest::Client, endpoint: &str) -> Option<DetectedLanguage> {
+    let config = settings::get().language_id;
+    if !config.enabled {
+        return None;
+    }
+    let probabilities = match detect(samples, client, endpoint, &config.candidates).await {
+        Ok(probabilities) => probabilities,
+        Err(e) => {
+            warn!("{}; decoding without a forced language", e);
+            return None;
+        }
+    };
+    let (code, confidence) = probabilities.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    debug!("Identified chunk as {} ({:.2})", code, confidence);
+    Some(DetectedLanguage { code, confidence })
+}
+
+#[command]
+pub fn get_language_id_settings() -> LanguageIdSettings {
+    settings::get().language_id
+}
+
+#[command]
+pub fn set_language_id_settings(mut language_id_settings: LanguageIdSettings) -> Result<LanguageIdSettings, String> {
+    for code in &mut language_id_settings.candidates {
+        *code = code.trim().to_lowercase();
+    }
+    language_id_settings.candidates.retain(|code| !code.is_empty());
+    language_id_settings.candidates.dedup();
+    language_id_settings.min_confidence = language_id_settings.min_confidence.clamp(0.0, 1.0);
+    settings::update(|s| s.language_id = language_id_settings).map(|s| s.language_id)
+}
@@ -6,17 +6,100 @@ use serde::{Deserialize, Serialize};
 // Declare audio module
 pub mod audio;
 pub mod ollama;
+pub mod settings;
+pub mod hotkeys;
+pub mod pipeline;
+pub mod tray;
+pub mod notifications;
+#[cfg(feature = "soak-test")]
+pub mod soak;
+pub mod replay;
+pub mod experiments;
+pub mod governor;
+pub mod federation;
+pub mod external_track;
+pub mod transcript_stream;
+pub mod captions;
+pub mod speakers;
+pub mod api;
+pub mod events;
+pub mod secrets;
+pub mod export;
+pub mod storage;
+pub mod integrity;
+pub mod diarization;
+pub mod recluster;
+pub mod deepgram;
+pub mod transcription;
+pub mod openai;
+pub mod assemblyai;
+pub mod calibration;
+pub mod azure;
+pub mod whisper_cpp;
+pub mod paragraphs;
+pub mod speaker_profiles;
+pub mod bulk;
+pub mod enrollment;
+pub mod dual_stream;
+pub mod transcript_diff;
+pub mod feedback;
+pub mod session;
+pub mod bilingual;
+pub mod transcript_store;
+pub mod whisper_decode;
+pub mod softphone;
+pub mod quick_transcribe;
+pub mod hallucination;
+pub mod language_id;
+pub mod watch_folder;
+pub mod preroll;
+pub mod calendar;
+pub mod meeting_end;
+pub mod compute_device;
+pub mod speaker_tracking;
+pub mod models;
+pub mod live_corrections;
+pub mod screenpipe_import;
+pub mod timeline;
+pub mod metrics;
+pub mod interview_kit;
+pub mod chapters;
+pub mod itn;
+pub mod retry_queue;
+pub mod request_limit;
+pub mod connectivity;
+pub mod assurance;
+pub mod usage;
+pub mod name_bias;
+pub mod quality;
+pub mod readiness;
+pub mod encryption;
+pub mod inference;
 
 use audio::{
-    default_input_device, default_output_device, AudioStream,
-    encode_single_audio,
+    default_input_device, default_output_device, list_audio_devices, parse_audio_device,
+    AudioDevice, AudioStream, DeviceType, encode_single_audio,
 };
+use audio::app_activity::{self, AudioSource};
+use audio::remote;
+use audio::noise;
+use audio::farfield;
+use audio::metering::{compute_levels, AudioLevel};
+use audio::bluetooth;
+use audio::continuity::{Discontinuity, GlitchEvent};
 use ollama::{OllamaModel};
+use pipeline::PipelineEvent;
 use tauri::{Runtime, AppHandle, Emitter};
 use log::{info as log_info, error as log_error, debug as log_debug};
 use reqwest::multipart::{Form, Part};
+use once_cell::sync::Lazy;
 
 static RECORDING_FLAG: AtomicBool = AtomicBool::new(false);
+static PAUSED_FLAG: AtomicBool = AtomicBool::new(false);
+/// A stopped recording is still transcribing the audio it captured.
+static DRAINING_FLAG: AtomicBool = AtomicBool::new(false);
+static MIC_MUTED_FLAG: AtomicBool = AtomicBool::new(false);
+static SESSION_MARKERS: Lazy<Mutex<Vec<SessionMarker>>> = Lazy::new(|| Mutex::new(Vec::new()));
 static mut MIC_BUFFER: Option<Arc<Mutex<Vec<f32>>>> = None;
 static mut SYSTEM_BUFFER: Option<Arc<Mutex<Vec<f32>>>> = None;
 static mut MIC_STREAM: Option<Arc<AudioStream>> = None;
@@ -27,36 +110,133 @@ static mut RECORDING_START_TIME: Option<std::time::Instant> = None;
 // Audio configuration constants
 const CHUNK_DURATION_MS: u32 = 30000; // 30 seconds per chunk for better sentence processing
 const WHISPER_SAMPLE_RATE: u32 = 16000; // Whisper's required sample rate
+const WHISPER_ENDPOINT: &str = "http://127.0.0.1:8178/stream";
 const WAV_SAMPLE_RATE: u32 = 44100; // WAV file sample rate
 const WAV_CHANNELS: u16 = 2; // Stereo for WAV files
 const WHISPER_CHANNELS: u16 = 1; // Mono for Whisper API
 const SENTENCE_TIMEOUT_MS: u64 = 1000; // Emit incomplete sentence after 1 second of silence
 const MIN_CHUNK_DURATION_MS: u32 = 2000; // Minimum duration before sending chunk
 const MIN_RECORDING_DURATION_MS: u64 = 2000; // 2 seconds minimum
+const DEVICE_TEST_DURATION_MS: u64 = 5000;
+const DEFAULT_DEVICE_POLL_MS: u64 = 2000; // How often follow-default mode checks the OS defaults
+const RENEGOTIATION_COOLDOWN_MS: u64 = 10000; // Minimum time between stream renegotiations
+/// Sent once a stopped recording's captured audio has all been transcribed.
+const SESSION_COMPLETE_EVENT: &str = "session-complete";
+/// Sent when the backend stops a recording (tray, hotkey, meeting end), so
+/// the UI finishes it as if its own stop button had been pressed.
+const RECORDING_STOPPED_EVENT: &str = "recording-stopped";
 
 #[derive(Debug, Deserialize)]
 struct RecordingArgs {
     save_path: String,
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct TranscriptUpdate {
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct TranscriptUpdate {
     text: String,
     timestamp: String,
     source: String,
+    /// Application that produced the audio when it came from system loopback.
+    #[serde(default)]
+    source_app: Option<String>,
+    /// Filled in by `transcript_stream::publish` from the speaker directory.
+    #[serde(default)]
+    speaker_display: Option<speakers::SpeakerDisplay>,
+    /// Language the sentence's audio was identified as.
+    #[serde(default)]
+    language: Option<language_id::DetectedLanguage>,
+    /// Language the sentence was spoken in, when its text was translated to English.
+    #[serde(default)]
+    translated_from: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct TranscriptSegment {
-    text: String,
-    t0: f32,
-    t1: f32,
+#[derive(Debug, Serialize, Clone, schemars::JsonSchema)]
+struct SessionMarker {
+    label: String,
+    offset_ms: u64,
+    created_at: String,
+    /// External tool that annotated the session; `None` for user markers.
+    source: Option<String>,
+    link: Option<String>,
+}
+
+/// What the capture loop keeps about a chunk while the inference pool decodes it.
+struct PendingChunk {
+    started_at: std::time::Instant,
+    sources: app_activity::SourceMap,
+    sample_rate: u32,
+    len: usize,
+    audio_ms: u64,
+    replay_index: Option<u64>,
+    experiment: Option<((u64, u64), Vec<f32>)>,
+    enrollment_audio: Option<Vec<f32>>,
+    retry_audio: Option<Vec<f32>>,
+    assurance_audio: Option<Vec<f32>>,
+}
+
+/// How a stopped recording's queued audio was finished off.
+#[derive(Debug, Serialize, Clone)]
+struct SessionComplete {
+    meeting_id: Option<String>,
+    /// Chunks transcribed after recording stopped.
+    drained_chunks: usize,
+    /// Seconds of captured audio left untranscribed when the drain timed out.
+    dropped_secs: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct RecordingStopped {
+    /// Generate the summary once the transcript is saved.
+    summarize: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct DeviceSwitch {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceTestResult {
+    device: String,
+    duration_ms: u64,
+    level: AudioLevel,
+    speech_ratio: f32,
+    speech_detected: bool,
+    transcript: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub t0: f32,
+    pub t1: f32,
+    /// Speaker label from engines that diarize, e.g. "A" from AssemblyAI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// The engine's estimate that the segment is silence, when it reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_speech_prob: Option<f32>,
+    /// Mean log probability of the segment's text tokens, from whisper.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_logprob: Option<f32>,
+    /// Language the segment's chunk was identified as before decoding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<language_id::DetectedLanguage>,
+    /// Language the segment was spoken in, when its text is an English translation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_from: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
-struct TranscriptResponse {
-    segments: Vec<TranscriptSegment>,
-    buffer_size_ms: i32,
+pub struct TranscriptResponse {
+    pub segments: Vec<TranscriptSegment>,
+    #[serde(default)]
+    pub buffer_size_ms: i32,
+    /// Id of the engine that produced the transcript.
+    #[serde(default)]
+    pub engine: String,
 }
 
 // Helper struct to accumulate transcript segments
@@ -66,6 +246,10 @@ struct TranscriptAccumulator {
     sentence_start_time: f32,
     last_update_time: std::time::Instant,
     last_segment_hash: u64,
+    source: Option<AudioSource>,
+    source_app: Option<String>,
+    language: Option<language_id::DetectedLanguage>,
+    translated_from: Option<String>,
 }
 
 impl TranscriptAccumulator {
@@ -75,7 +259,29 @@ impl TranscriptAccumulator {
             sentence_start_time: 0.0,
             last_update_time: std::time::Instant::now(),
             last_segment_hash: 0,
+            source: None,
+            source_app: None,
+            language: None,
+            translated_from: None,
+        }
+    }
+
+    /// Switch the attributed source; a pending sentence from the previous source is flushed.
+    fn set_source(&mut self, source: Option<AudioSource>, source_app: Option<String>) -> Option<TranscriptUpdate> {
+        if self.source == source && self.source_app == source_app {
+            return None;
         }
+        let pending = self.flush();
+        self.source = source;
+        self.source_app = source_app;
+        pending
+    }
+
+    fn source_label(&self) -> String {
+        self.source
+            .as_ref()
+            .map(|source| source.label())
+            .unwrap_or_else(|| "Mixed Audio".to_string())
     }
 
     fn add_segment(&mut self, segment: &TranscriptSegment) -> Option<TranscriptUpdate> {
@@ -117,6 +323,14 @@ impl TranscriptAccumulator {
         // If this is the start of a new sentence, store the start time
         if self.current_sentence.is_empty() {
             self.sentence_start_time = segment.t0;
+            self.language = None;
+            self.translated_from = None;
+        }
+        if segment.language.is_some() {
+            self.language = segment.language.clone();
+        }
+        if segment.translated_from.is_some() {
+            self.translated_from = segment.translated_from.clone();
         }
 
         // Add the new text with proper spacing
@@ -129,9 +343,13 @@ impl TranscriptAccumulator {
         if clean_text.ends_with('.') || clean_text.ends_with('?') || clean_text.ends_with('!') {
             let sentence = std::mem::take(&mut self.current_sentence);
             let update = TranscriptUpdate {
-                text: sentence.trim().to_string(),
+                text: itn::normalize(sentence.trim(), self.language.as_ref().map(|l| l.code.as_str())),
                 timestamp: format!("{:.1} - {:.1}", self.sentence_start_time, segment.t1),
-                source: "Mixed Audio".to_string(),
+                source: self.source_label(),
+                source_app: self.source_app.clone(),
+                speaker_display: None,
+                language: self.language.clone(),
+                translated_from: self.translated_from.clone(),
             };
             log_info!("Generated transcript update: {:?}", update);
             Some(update)
@@ -141,14 +359,26 @@ impl TranscriptAccumulator {
     }
 
     fn check_timeout(&mut self) -> Option<TranscriptUpdate> {
-        if !self.current_sentence.is_empty() && 
-           self.last_update_time.elapsed() > Duration::from_millis(SENTENCE_TIMEOUT_MS) {
+        if self.last_update_time.elapsed() > Duration::from_millis(SENTENCE_TIMEOUT_MS) {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Emit the pending incomplete sentence regardless of how long ago it was updated.
+    fn flush(&mut self) -> Option<TranscriptUpdate> {
+        if !self.current_sentence.is_empty() {
             let sentence = std::mem::take(&mut self.current_sentence);
             let current_time = self.sentence_start_time + (SENTENCE_TIMEOUT_MS as f32 / 1000.0);
             let update = TranscriptUpdate {
-                text: sentence.trim().to_string(),
+                text: itn::normalize(sentence.trim(), self.language.as_ref().map(|l| l.code.as_str())),
                 timestamp: format!("{:.1} - {:.1}", self.sentence_start_time, current_time),
-                source: "Mixed Audio".to_string(),
+                source: self.source_label(),
+                source_app: self.source_app.clone(),
+                speaker_display: None,
+                language: self.language.clone(),
+                translated_from: self.translated_from.clone(),
             };
             Some(update)
         } else {
@@ -157,7 +387,59 @@ impl TranscriptAccumulator {
     }
 }
 
-async fn send_audio_chunk(chunk: Vec<f32>, client: &reqwest::Client) -> Result<TranscriptResponse, String> {
+/// Sample range of a segment within its chunk.
+fn segment_span(segment: &TranscriptSegment, sample_rate: u32) -> (usize, usize) {
+    let t0 = segment.t0.max(0.0);
+    let t1 = segment.t1.max(t0);
+    ((t0 * sample_rate as f32) as usize, (t1 * sample_rate as f32) as usize)
+}
+
+/// Attribute a segment to the input that dominated its span, and to the app
+/// playing system audio when that input was loopback.
+fn attribute_segment(
+    segment: &TranscriptSegment,
+    chunk_started_at: std::time::Instant,
+    sources: &app_activity::SourceMap,
+    sample_rate: u32,
+) -> (Option<AudioSource>, Option<String>) {
+    let t0 = segment.t0.max(0.0);
+    let t1 = segment.t1.max(t0);
+    let (from, to) = segment_span(segment, sample_rate);
+    let source = sources.dominant_source(from, to);
+    let source_app = match source {
+        Some(AudioSource::System) => {
+            let start = chunk_started_at + Duration::from_secs_f32(t0);
+            let end = chunk_started_at + Duration::from_secs_f32(t1);
+            Some(app_activity::dominant_app(start, end).unwrap_or_else(|| "System audio".to_string()))
+        }
+        _ => None,
+    };
+    (source, source_app)
+}
+
+/// Transcribe a chunk of 16 kHz mono audio with the active engine.
+async fn send_audio_chunk(chunk: Vec<f32>) -> Result<TranscriptResponse, String> {
+    let input = transcription::AudioInput {
+        samples: chunk,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        language: None,
+        device: None,
+    };
+    transcription::transcribe(&input).await
+}
+
+async fn send_audio_chunk_to(chunk: Vec<f32>, client: &reqwest::Client, endpoint: &str) -> Result<TranscriptResponse, String> {
+    send_audio_chunk_with(chunk, client, endpoint, &[]).await
+}
+
+/// Like `send_audio_chunk_to`, with extra form fields overriding the
+/// server's decoding options for this chunk, e.g. `language` or `prompt`.
+async fn send_audio_chunk_with(
+    chunk: Vec<f32>,
+    client: &reqwest::Client,
+    endpoint: &str,
+    fields: &[(&str, String)],
+) -> Result<TranscriptResponse, String> {
     log_debug!("Preparing to send audio chunk of size: {}", chunk.len());
     
     // Convert f32 samples to bytes
@@ -187,9 +469,11 @@ async fn send_audio_chunk(chunk: Vec<f32>, client: &reqwest::Client) -> Result<T
             .file_name("audio.raw")
             .mime_str("audio/x-raw")
             .unwrap();
-        let form = Form::new().part("audio", part);
+        let form = fields
+            .iter()
+            .fold(Form::new().part("audio", part), |form, (name, value)| form.text(name.to_string(), value.clone()));
 
-        match client.post("http://127.0.0.1:8178/stream")
+        match client.post(endpoint)
             .multipart(form)
             .send()
             .await {
@@ -214,8 +498,100 @@ async fn send_audio_chunk(chunk: Vec<f32>, client: &reqwest::Client) -> Result<T
     Err(format!("Failed after {} retries. Last error: {}", max_retries, last_error))
 }
 
+/// Reopen `device` with a freshly negotiated config and hand it to the capture loop.
+fn spawn_stream_renegotiation(
+    device: Arc<AudioDevice>,
+    is_running: Arc<AtomicBool>,
+    switch_tx: tokio::sync::mpsc::UnboundedSender<Arc<AudioStream>>,
+) {
+    tokio::spawn(async move {
+        log_info!("Renegotiating stream for {}", device);
+        match AudioStream::from_device(device.clone(), is_running).await {
+            Ok(stream) => {
+                let _ = switch_tx.send(Arc::new(stream));
+            }
+            Err(e) => log_error!("Failed to renegotiate stream for {}: {}", device, e),
+        }
+    });
+}
+
+/// The microphone to capture from when `default` is the OS default input:
+/// Bluetooth headsets drop to hands-free quality once their mic opens, so
+/// the built-in mic is used instead when the settings prefer it.
+fn preferred_input(default: &AudioDevice) -> AudioDevice {
+    if settings::get().audio.prefer_builtin_mic_on_bluetooth && bluetooth::is_bluetooth_device(default) {
+        if let Some(builtin) = bluetooth::find_builtin_input() {
+            return builtin;
+        }
+    }
+    default.clone()
+}
+
+/// Poll the OS default devices and hand a fresh stream to the capture loop
+/// whenever one changes (e.g. AirPods connecting). `mic_default` is the OS
+/// default input, which may not be the mic captured (see `preferred_input`).
+fn spawn_default_device_watcher(
+    mut mic_default: Arc<AudioDevice>,
+    mut system_device: Arc<AudioDevice>,
+    is_running: Arc<AtomicBool>,
+    switch_tx: tokio::sync::mpsc::UnboundedSender<Arc<AudioStream>>,
+) {
+    tokio::spawn(async move {
+        while is_running.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(DEFAULT_DEVICE_POLL_MS)).await;
+            if !is_running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            for (current, latest, is_input) in [
+                (&mut mic_default, default_input_device(), true),
+                (&mut system_device, default_output_device(), false),
+            ] {
+                let latest = match latest {
+                    Ok(device) => device,
+                    Err(e) => {
+                        log_debug!("Default device lookup failed: {}", e);
+                        continue;
+                    }
+                };
+                if latest == **current {
+                    continue;
+                }
+
+                log_info!("Default device changed from {} to {}", current, latest);
+                // Keep capturing from the built-in mic while the default is a Bluetooth headset
+                let target = if is_input { preferred_input(&latest) } else { latest.clone() };
+                if is_input && target == preferred_input(&**current) {
+                    *current = Arc::new(latest);
+                    continue;
+                }
+                if target != latest {
+                    log_info!("Default input {} is Bluetooth, capturing from {} instead", latest, target);
+                }
+                let target = Arc::new(target);
+                match AudioStream::from_device(target.clone(), is_running.clone()).await {
+                    Ok(stream) => {
+                        if switch_tx.send(Arc::new(stream)).is_err() {
+                            return;
+                        }
+                        *current = Arc::new(latest);
+                    }
+                    Err(e) => log_error!("Failed to open new default device {}: {}", target, e),
+                }
+            }
+        }
+    });
+}
+
 #[tauri::command]
-async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+async fn start_recording<R: Runtime>(
+    app: AppHandle<R>,
+    follow_default_devices: Option<bool>,
+    conference_mode: Option<bool>,
+    participants: Option<usize>,
+    name: Option<String>,
+    deepgram_options: Option<deepgram::DeepgramOptions>,
+) -> Result<(), String> {
     log_info!("Attempting to start recording...");
     
     if is_recording() {
@@ -223,10 +599,34 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
         return Err("Recording already in progress".to_string());
     }
 
+    if federation::is_guest() {
+        return Err("This machine is streaming to another machine's session; leave it before recording".to_string());
+    }
+
+    if DRAINING_FLAG.load(Ordering::SeqCst) {
+        return Err("The previous recording is still being transcribed".to_string());
+    }
+
+    deepgram::begin_session(deepgram_options)?;
+    name_bias::begin_session(&app);
+
+    // With the interview kit, the host's and guest's microphones take the
+    // place of the default input and system audio
+    let kit_settings = interview_kit::active();
+    let kit_devices = match &kit_settings {
+        Some(kit) => Some(kit.devices()?),
+        None => None,
+    };
+
     // Initialize recording flag and buffers
     RECORDING_FLAG.store(true, Ordering::SeqCst);
+    PAUSED_FLAG.store(false, Ordering::SeqCst);
     log_info!("Recording flag set to true");
 
+    if let Ok(mut markers) = SESSION_MARKERS.lock() {
+        markers.clear();
+    }
+
     // Store recording start time
     unsafe {
         RECORDING_START_TIME = Some(std::time::Instant::now());
@@ -240,15 +640,34 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
     }
     
     // Get default devices
-    let mic_device = Arc::new(default_input_device().map_err(|e| {
-        log_error!("Failed to get default input device: {}", e);
-        e.to_string()
-    })?);
+    let mic_default = match &kit_devices {
+        Some((host, _)) => Arc::new(host.clone()),
+        None => Arc::new(default_input_device().map_err(|e| {
+            log_error!("Failed to get default input device: {}", e);
+            e.to_string()
+        })?),
+    };
+    let mic_device = match &kit_devices {
+        Some(_) => mic_default.clone(),
+        None => Arc::new(preferred_input(&mic_default)),
+    };
+    if *mic_device != *mic_default {
+        log_info!("Default input {} is Bluetooth, capturing from {} instead", mic_default, mic_device);
+    }
     
-    let system_device = Arc::new(default_output_device().map_err(|e| {
-        log_error!("Failed to get default output device: {}", e);
-        e.to_string()
-    })?);
+    let system_device = match &kit_devices {
+        Some((_, guest)) => Arc::new(guest.clone()),
+        None => Arc::new(default_output_device().map_err(|e| {
+            log_error!("Failed to get default output device: {}", e);
+            e.to_string()
+        })?),
+    };
+    if let Some(kit) = &kit_settings {
+        log_info!(
+            "Interview kit: {} on {}, {} on {}",
+            kit.host.speaker, mic_device, kit.guest.speaker, system_device
+        );
+    }
     
     // Create audio streams
     let is_running = Arc::new(AtomicBool::new(true));
@@ -261,6 +680,15 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
             e.to_string()
         })?;
     let mic_stream = Arc::new(mic_stream);
+
+    if let Some(warning) = bluetooth::check_capture_profile(
+        &mic_stream.device,
+        mic_stream.device_config.sample_rate().0,
+    ) {
+        if let Err(e) = app.emit("audio-profile-warning", warning) {
+            log_error!("Failed to emit audio profile warning: {}", e);
+        }
+    }
     
     // Create system audio stream
     let system_stream = AudioStream::from_device(system_device.clone(), is_running.clone())
@@ -311,28 +739,207 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
     
     // Create transcript accumulator
     let mut accumulator = TranscriptAccumulator::new();
+    let mut embedding_manager = speaker_tracking::EmbeddingManager::default();
     
+    pipeline::publish(PipelineEvent::RecordingStarted {
+        engine: transcription::active().name().to_string(),
+    });
+    experiments::begin_session(&app);
+    bilingual::reset();
+    app_activity::start_monitor(is_running.clone());
+    governor::reset();
+    metrics::reset();
+    diarization::reset();
+    quality::reset();
+    farfield::begin(conference_mode.unwrap_or(false), participants);
+
     let device_config = mic_stream.device_config.clone();
     let _device_name = mic_stream.device.to_string();
     let sample_rate = device_config.sample_rate().0;
     let channels = device_config.channels();
+    // Audio from before the user pressed record, transcribed ahead of the live capture
+    let preroll = preroll::take(sample_rate).await;
+
+    // Capture this session's transcription inputs: into the meeting's raw
+    // storage when enabled (it doubles as a replay file), otherwise as a replay file
+    let replay_config = replay::ReplayConfig {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        engine: transcription::active().name().to_string(),
+        mic_device: mic_stream.device.to_string(),
+        system_device: system_stream.device.to_string(),
+        capture_sample_rate: sample_rate,
+        transcription_sample_rate: WHISPER_SAMPLE_RATE,
+        chunk_duration_ms: CHUNK_DURATION_MS,
+        model: transcription::active_model(),
+    };
+    let mut replay_recorder = storage::begin_session(&app, replay_config.clone(), name)
+        .or_else(|| replay::start_session_recorder(&app, replay_config));
+
+    // Follow-default mode: migrate capture when the OS default device changes
+    let (device_switch_tx, mut device_switch_rx) = tokio::sync::mpsc::unbounded_channel();
+    let renegotiate_tx = device_switch_tx.clone();
+    if follow_default_devices.unwrap_or(false) && kit_settings.is_none() {
+        log_info!("Follow-default device mode enabled");
+        spawn_default_device_watcher(
+            mic_default.clone(),
+            system_device.clone(),
+            is_running.clone(),
+            device_switch_tx,
+        );
+    }
+    
+    // Chunks decode on the inference pool while the loop keeps capturing
+    let inference_client = client.clone();
+    let transcription_settings = settings::get().transcription;
+    let mut inference = inference::InferencePool::start(
+        transcription_settings.transcription_workers,
+        transcription_settings.inference_queue,
+        move |job: inference::Job| {
+            let client = inference_client.clone();
+            Box::pin(async move {
+                match job.endpoint {
+                    Some(endpoint) => send_audio_chunk_to(job.input.samples, &client, &endpoint)
+                        .await
+                        .map(transcription::Transcription::Speech),
+                    None => {
+                        let device = job.input.device.clone().unwrap_or_default();
+                        transcription::transcribe_gated(&job.input, &device).await
+                    }
+                }
+            })
+        },
+    );
     
     tokio::spawn(async move {
-        let chunk_samples = (WHISPER_SAMPLE_RATE as f32 * (CHUNK_DURATION_MS as f32 / 1000.0)) as usize;
+        // Kept here: the shared start time is cleared on stop, while drained
+        // chunks still need their offsets
+        let recording_start = unsafe { RECORDING_START_TIME }.unwrap_or_else(std::time::Instant::now);
+        let mut mic_stream = mic_stream;
+        let mut system_stream = system_stream;
+        let mut sample_rate = sample_rate;
+        let mut mic_glitches = mic_stream.subscribe_glitches();
+        let mut system_glitches = system_stream.subscribe_glitches();
+        let mut last_renegotiation: Option<std::time::Instant> = None;
+        let mut knobs = governor::knobs(CHUNK_DURATION_MS);
+        let mut chunk_samples = (WHISPER_SAMPLE_RATE as f32 * (knobs.chunk_duration_ms as f32 / 1000.0)) as usize;
         let min_samples = (WHISPER_SAMPLE_RATE as f32 * (MIN_CHUNK_DURATION_MS as f32 / 1000.0)) as usize;
-        let mut current_chunk: Vec<f32> = Vec::with_capacity(chunk_samples);
+        let (mut current_chunk, mut source_map) = match preroll {
+            Some((samples, sources)) => (samples, sources),
+            None => (Vec::with_capacity(chunk_samples), app_activity::SourceMap::default()),
+        };
         let mut last_chunk_time = std::time::Instant::now();
+        let mut remote_receiver = remote::subscribe();
+        let mut remote_mixer = remote::RemoteMixer::default();
+        // Optional low-latency live captions, one stream per capture device
+        let mic_live = transcription::LiveFeed::start(
+            &app_handle,
+            mic_stream.device.to_string(),
+            mic_stream.device_config.sample_rate().0,
+        );
+        let system_live = transcription::LiveFeed::start(
+            &app_handle,
+            system_stream.device.to_string(),
+            system_stream.device_config.sample_rate().0,
+        );
+        // With both sides streaming, their finals are merged into one timeline
+        if mic_live.is_some() && system_live.is_some() {
+            dual_stream::start_session(mic_stream.device.to_string(), system_stream.device.to_string());
+        }
+        let mut mic_denoiser = noise::Denoiser::for_device(&mic_stream.device.to_string());
+        let mut system_denoiser = noise::Denoiser::for_device(&system_stream.device.to_string());
+        let mut mic_far_field = farfield::FarFieldProcessor::for_session(mic_stream.device_config.sample_rate().0);
         
         log_info!("Mic config: {} Hz, {} channels", sample_rate, channels);
+        // Once stopped, capture ends but audio already collected is still transcribed
+        let mut drain_deadline: Option<std::time::Instant> = None;
+        let mut drained_chunks = 0;
+        let mut dropped_samples = 0;
+        let mut pending_chunks: std::collections::HashMap<u64, PendingChunk> = std::collections::HashMap::new();
         
-        while is_running.load(Ordering::SeqCst) {
+        loop {
             // Check for timeout on current sentence
             if let Some(update) = accumulator.check_timeout() {
-                if let Err(e) = app_handle.emit("transcript-update", update) {
+                if let Some(recorder) = replay_recorder.as_mut() {
+                    if let Err(e) = recorder.record_flush() {
+                        log_error!("Failed to record replay flush: {}", e);
+                    }
+                }
+                if let Err(e) = transcript_stream::publish(&app_handle, update) {
                     log_error!("Failed to send timeout transcript update: {}", e);
                 }
             }
 
+            // Swap in streams for devices that became the OS default. The accumulator and
+            // pending chunk carry over so the transcript continues across the switch.
+            while let Ok(new_stream) = device_switch_rx.try_recv() {
+                let previous = match new_stream.device.device_type {
+                    DeviceType::Input => {
+                        let new_rate = new_stream.device_config.sample_rate().0;
+                        if new_rate != sample_rate {
+                            current_chunk = resample_audio(&current_chunk, sample_rate, new_rate);
+                            source_map.rescale(current_chunk.len());
+                            sample_rate = new_rate;
+                        }
+                        mic_receiver_clone = new_stream.subscribe().await;
+                        mic_glitches = new_stream.subscribe_glitches();
+                        unsafe {
+                            MIC_STREAM = Some(new_stream.clone());
+                        }
+                        std::mem::replace(&mut mic_stream, new_stream.clone())
+                    }
+                    DeviceType::Output => {
+                        system_receiver = new_stream.subscribe().await;
+                        system_glitches = new_stream.subscribe_glitches();
+                        unsafe {
+                            SYSTEM_STREAM = Some(new_stream.clone());
+                        }
+                        std::mem::replace(&mut system_stream, new_stream.clone())
+                    }
+                };
+
+                if let Err(e) = previous.stop().await {
+                    log_error!("Error stopping previous stream {}: {}", previous.device, e);
+                }
+                let switch = DeviceSwitch {
+                    from: previous.device.to_string(),
+                    to: new_stream.device.to_string(),
+                };
+                log_info!("Capture switched from {} to {}", switch.from, switch.to);
+                if let Err(e) = app_handle.emit("device-switched", switch) {
+                    log_error!("Failed to emit device switch: {}", e);
+                }
+            }
+
+            // Gaps were already zero-filled by the stream; mark them in the session and
+            // renegotiate streams whose delivered rate no longer matches their config
+            let mut glitches: Vec<(GlitchEvent, Arc<AudioDevice>)> = Vec::new();
+            while let Ok(glitch) = mic_glitches.try_recv() {
+                glitches.push((glitch, mic_stream.device.clone()));
+            }
+            while let Ok(glitch) = system_glitches.try_recv() {
+                glitches.push((glitch, system_stream.device.clone()));
+            }
+            for (glitch, device) in glitches {
+                match glitch.discontinuity {
+                    Discontinuity::Gap { missing_frames } => {
+                        let label = format!("Audio gap on {} ({} frames concealed)", glitch.device, missing_frames);
+                        push_marker(&app_handle, label, recording_start.elapsed().as_millis() as u64);
+                    }
+                    Discontinuity::RateChanged { expected, measured } => {
+                        let cooling_down = last_renegotiation
+                            .map_or(false, |at| at.elapsed() < Duration::from_millis(RENEGOTIATION_COOLDOWN_MS));
+                        if !cooling_down {
+                            log_info!("{} delivering {} Hz instead of {} Hz", device, measured, expected);
+                            last_renegotiation = Some(std::time::Instant::now());
+                            spawn_stream_renegotiation(device, is_running.clone(), renegotiate_tx.clone());
+                        }
+                    }
+                }
+                if let Err(e) = app_handle.emit("audio-glitch", glitch) {
+                    log_error!("Failed to emit audio glitch: {}", e);
+                }
+            }
+
             // Collect audio samples
             let mut new_samples = Vec::new();
             let mut mic_samples = Vec::new();
@@ -342,6 +949,15 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
             let mut got_mic_samples = false;
             while let Ok(chunk) = mic_receiver_clone.try_recv() {
                 got_mic_samples = true;
+                // Drain but discard audio while paused
+                if PAUSED_FLAG.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let chunk = if MIC_MUTED_FLAG.load(Ordering::SeqCst) {
+                    vec![0.0; chunk.len()]
+                } else {
+                    chunk
+                };
                 log_debug!("Received {} mic samples", chunk.len());
                 let chunk_clone = chunk.clone();
                 mic_samples.extend(chunk);
@@ -365,6 +981,9 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
             let mut got_system_samples = false;
             while let Ok(chunk) = system_receiver.try_recv() {
                 got_system_samples = true;
+                if PAUSED_FLAG.load(Ordering::SeqCst) {
+                    continue;
+                }
                 log_debug!("Received {} system samples", chunk.len());
                 let chunk_clone = chunk.clone();
                 system_samples.extend(chunk);
@@ -384,13 +1003,65 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
                 system_receiver = system_stream.subscribe().await;
             }
             
+            // Learn each device's background noise and optionally subtract it
+            let mic_rate = mic_stream.device_config.sample_rate().0;
+            let system_rate = system_stream.device_config.sample_rate().0;
+            noise::observe(&mic_stream.device.to_string(), &mic_samples, mic_rate);
+            noise::observe(&system_stream.device.to_string(), &system_samples, system_rate);
+            if let Some(denoiser) = mic_denoiser.as_mut() {
+                mic_samples = denoiser.process(&mic_samples, mic_rate);
+            }
+            if let Some(processor) = mic_far_field.as_mut() {
+                mic_samples = processor.process(&mic_samples);
+            }
+            if let Some(denoiser) = system_denoiser.as_mut() {
+                system_samples = denoiser.process(&system_samples, system_rate);
+            }
+
+            if let Some(live) = &mic_live {
+                live.feed(&mic_samples);
+            }
+            if let Some(live) = &system_live {
+                live.feed(&system_samples);
+            }
+
+            // Remote microphones (companion phones, federated machines) join the
+            // microphone side of the mix
+            let (local_mic_rms, _) = compute_levels(&mic_samples);
+            while let Ok(remote_audio) = remote_receiver.try_recv() {
+                if PAUSED_FLAG.load(Ordering::SeqCst) || MIC_MUTED_FLAG.load(Ordering::SeqCst) {
+                    continue;
+                }
+                remote_mixer.push(remote_audio, sample_rate);
+            }
+            let loudest_remote = remote_mixer.mix_into(&mut mic_samples);
+
             // Mix samples with debug info
             let max_len = mic_samples.len().max(system_samples.len());
+            let (system_rms, _) = compute_levels(&system_samples);
+            let mut dominant = (AudioSource::Microphone, local_mic_rms * 0.7);
+            if let Some((device, rms)) = loudest_remote {
+                if rms * 0.7 > dominant.1 {
+                    dominant = (AudioSource::Remote(device), rms * 0.7);
+                }
+            }
+            if system_rms * 0.3 > dominant.1 {
+                dominant = (AudioSource::System, system_rms * 0.3);
+            }
+            // Interview kit microphones carry one participant each, mixed evenly
+            let (mic_weight, system_weight) = match &kit_settings {
+                Some(kit) => {
+                    dominant.0 = kit.speaker(local_mic_rms, system_rms);
+                    (0.5, 0.5)
+                }
+                None => (0.7, 0.3),
+            };
+            source_map.push(max_len, dominant.0);
             for i in 0..max_len {
                 let mic_sample = if i < mic_samples.len() { mic_samples[i] } else { 0.0 };
                 let system_sample = if i < system_samples.len() { system_samples[i] } else { 0.0 };
                 // Increase mic sensitivity by giving it more weight in the mix (80% mic, 20% system)
-                new_samples.push((mic_sample * 0.7) + (system_sample * 0.3));
+                new_samples.push((mic_sample * mic_weight) + (system_sample * system_weight));
             }
             
             log_debug!("Mixed {} samples", new_samples.len());
@@ -400,17 +1071,230 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
                 current_chunk.push(sample);
             }
             
+            // Once stopped, whether the drain is done is only decided after the
+            // audio the receivers still held has joined the chunk
+            if !is_running.load(Ordering::SeqCst) {
+                let deadline = *drain_deadline.get_or_insert_with(|| {
+                    DRAINING_FLAG.store(true, Ordering::SeqCst);
+                    log_info!("Recording stopped, transcribing {} queued samples", current_chunk.len());
+                    let timeout = settings::get().transcription.drain_timeout_secs;
+                    std::time::Instant::now() + Duration::from_secs(timeout)
+                });
+                // Fragments too short to hold a word aren't worth a request
+                if current_chunk.len() < (sample_rate / 10) as usize && inference.is_idle() {
+                    break;
+                }
+                if std::time::Instant::now() >= deadline {
+                    let in_flight: usize = pending_chunks.values().map(|chunk| chunk.len).sum();
+                    log_error!(
+                        "Drain timed out, {} samples left untranscribed and {} chunks still decoding",
+                        current_chunk.len(),
+                        pending_chunks.len()
+                    );
+                    dropped_samples = current_chunk.len() + in_flight;
+                    break;
+                }
+            }
+
+            // Hand back chunks the inference pool has finished with
+            while let Some(done) = inference.try_finished() {
+                let Some(chunk) = pending_chunks.remove(&done.ticket) else {
+                    continue;
+                };
+                let result = done.result;
+                // Chunks dropped on overflow were never timed
+                if !done.dropped {
+                    governor::observe(chunk.audio_ms, done.elapsed.as_millis() as u64);
+                    metrics::record(metrics::Stage::Stt, done.elapsed);
+                    metrics::chunk_processed(chunk.audio_ms, done.elapsed.as_millis() as u64);
+                }
+                if let Some((experiment, samples)) = chunk.experiment {
+                    let baseline = experiments::VariantOutput {
+                        text: match &result {
+                            Ok(transcription::Transcription::Speech(r)) => experiments::segments_text(&r.segments),
+                            _ => String::new(),
+                        },
+                        latency_ms: done.elapsed.as_millis() as u64,
+                        skipped: matches!(result, Ok(transcription::Transcription::NoSpeech)),
+                        error: result.as_ref().err().cloned(),
+                    };
+                    experiments::compare(app_handle.clone(), experiment, samples, baseline, client.clone());
+                }
+                if let (Some(samples), Ok(transcription::Transcription::Speech(response))) = (chunk.assurance_audio, &result) {
+                    assurance::check(
+                        app_handle.clone(),
+                        chunk.started_at.saturating_duration_since(recording_start).as_secs_f64(),
+                        samples,
+                        response.engine.clone(),
+                        response.segments.clone(),
+                    );
+                }
+                match result {
+                    Ok(transcription::Transcription::NoSpeech) => {
+                        log_info!("No speech in chunk, nothing transcribed");
+                        if let (Some(recorder), Some(index)) = (replay_recorder.as_mut(), chunk.replay_index) {
+                            let latency_ms = done.elapsed.as_millis() as u64;
+                            if let Err(e) = recorder.record_response(index, latency_ms, &[]) {
+                                log_error!("Failed to record replay response: {}", e);
+                            }
+                        }
+                        pipeline::publish(PipelineEvent::ChunkTranscribed {
+                            latency_ms: done.elapsed.as_millis() as u64,
+                            segments: 0,
+                        });
+                    }
+                    Ok(transcription::Transcription::Speech(response)) => {
+                        log_info!("Received {} transcript segments from {}", response.segments.len(), response.engine);
+                        quality::observe_segments(&response.segments);
+                        if let (Some(recorder), Some(index)) = (replay_recorder.as_mut(), chunk.replay_index) {
+                            let latency_ms = done.elapsed.as_millis() as u64;
+                            if let Err(e) = recorder.record_response(index, latency_ms, &response.segments) {
+                                log_error!("Failed to record replay response: {}", e);
+                            }
+                        }
+                        pipeline::publish(PipelineEvent::ChunkTranscribed {
+                            latency_ms: done.elapsed.as_millis() as u64,
+                            segments: response.segments.len(),
+                        });
+                        let excluded_apps = settings::get().audio.excluded_source_apps;
+                        for (segment_index, segment) in response.segments.into_iter().enumerate() {
+                            log_info!("Processing segment: {} ({:.1}s - {:.1}s)", 
+                                     segment.text.trim(), segment.t0, segment.t1);
+                            let attributing_since = std::time::Instant::now();
+                            let (source, source_app) = attribute_segment(&segment, chunk.started_at, &chunk.sources, chunk.sample_rate);
+                            if let Some(app_name) = source_app.as_deref() {
+                                let lower = app_name.to_lowercase();
+                                if excluded_apps.iter().any(|excluded| lower.contains(&excluded.to_lowercase())) {
+                                    log_info!("Dropping segment attributed to excluded app {}", app_name);
+                                    continue;
+                                }
+                            }
+                            let source = match (source, chunk.enrollment_audio.as_deref()) {
+                                (Some(AudioSource::Microphone), Some(audio)) => {
+                                    let (from, to) = segment_span(&segment, WHISPER_SAMPLE_RATE);
+                                    let to = to.min(audio.len());
+                                    let voice = audio::embedding::voice_embedding(&audio[from.min(to)..to], WHISPER_SAMPLE_RATE);
+                                    let at = speaker_tracking::SegmentRef {
+                                        offset_ms: ((chunk.started_at.saturating_duration_since(recording_start).as_secs_f32() + segment.t0.max(0.0)) * 1000.0) as u64,
+                                        duration_ms: ((segment.t1 - segment.t0).max(0.0) * 1000.0) as u64,
+                                        chunk: chunk.replay_index,
+                                        segment: segment_index,
+                                    };
+                                    let (speaker, reassignment) = match voice {
+                                        Some(voice) => embedding_manager.assign(voice, at),
+                                        None => (None, None),
+                                    };
+                                    if let Some(reassignment) = reassignment {
+                                        if let Err(e) = app_handle.emit(speaker_tracking::SPEAKER_REASSIGNED_EVENT, reassignment) {
+                                            log_error!("Failed to emit speaker reassignment: {}", e);
+                                        }
+                                    }
+                                    match speaker {
+                                        Some(name) => Some(AudioSource::Speaker(name)),
+                                        None => Some(AudioSource::Microphone),
+                                    }
+                                }
+                                (source, _) => source,
+                            };
+                            let (source, source_app) = softphone::attribute(source, source_app);
+                            let (from, to) = segment_span(&segment, chunk.sample_rate);
+                            let local = source_app
+                                .clone()
+                                .or_else(|| source.as_ref().map(|s| s.label()))
+                                .unwrap_or_default();
+                            // Engines that diarize name the voice; locally it's a recognized
+                            // voice or the side of the call it came from
+                            let recognized = matches!(source, Some(AudioSource::Speaker(_)));
+                            let engine_speaker = segment.speaker.as_deref().filter(|_| kit_settings.is_none());
+                            let (source, source_app) = match engine_speaker {
+                                Some(label) => (
+                                    Some(diarization::reconcile(label, &local, recognized, segment.t1 - segment.t0)),
+                                    None,
+                                ),
+                                None => (source, source_app),
+                            };
+                            let speaker = source_app
+                                .clone()
+                                .or_else(|| source.as_ref().map(|s| s.label()))
+                                .unwrap_or_default();
+                            if kit_settings.is_none() {
+                                diarization::observe(&chunk.sources, from, to, &speaker);
+                            }
+                            metrics::record(metrics::Stage::Diarization, attributing_since.elapsed());
+                            if let Some(update) = accumulator.set_source(source, source_app) {
+                                if let Err(e) = transcript_stream::publish(&app_handle, update) {
+                                    log_error!("Failed to emit transcript update: {}", e);
+                                }
+                            }
+                            // Add segment to accumulator and check for complete sentence
+                            if let Some(update) = accumulator.add_segment(&segment) {
+                                // Emit the update
+                                if let Err(e) = transcript_stream::publish(&app_handle, update) {
+                                    log_error!("Failed to emit transcript update: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        log_error!("Transcription error: {}", e);
+                        if let (Some(recorder), Some(index)) = (replay_recorder.as_mut(), chunk.replay_index) {
+                            if let Err(e) = recorder.record_failure(index, &e) {
+                                log_error!("Failed to record replay failure: {}", e);
+                            }
+                        }
+                        metrics::failure(metrics::Stage::Stt, &e);
+                        let kind = transcription::TranscriptionError::classify(e.clone());
+                        if let (Some(audio), Some(session_id)) = (chunk.retry_audio, storage::current_session()) {
+                            let source = chunk.sources
+                                .dominant_source(0, chunk.len)
+                                .map(|source| source.label())
+                                .unwrap_or_else(|| "Mixed Audio".to_string());
+                            if let Err(e) = retry_queue::enqueue(
+                                &app_handle,
+                                &session_id,
+                                &audio,
+                                WHISPER_SAMPLE_RATE,
+                                chunk.started_at.saturating_duration_since(recording_start).as_secs_f64(),
+                                source,
+                                kind.clone(),
+                            ) {
+                                log_error!("Failed to queue chunk for retry: {}", e);
+                            }
+                        }
+                        pipeline::publish(PipelineEvent::TranscriptionFailed { kind, error: e });
+                    }
+                }
+            }
+
             // Check if we should send the chunk based on size or time
+            // Pick up quality changes from the real-time factor governor
+            let latest_knobs = governor::knobs(CHUNK_DURATION_MS);
+            if latest_knobs != knobs {
+                log_info!("Pipeline knobs changed: {:?}", latest_knobs);
+                chunk_samples = (WHISPER_SAMPLE_RATE as f32 * (latest_knobs.chunk_duration_ms as f32 / 1000.0)) as usize;
+                knobs = latest_knobs;
+            }
+
             let should_send = current_chunk.len() >= chunk_samples || 
                             (current_chunk.len() >= min_samples && 
-                             last_chunk_time.elapsed() >= Duration::from_millis(CHUNK_DURATION_MS as u64));
+                             last_chunk_time.elapsed() >= Duration::from_millis(knobs.chunk_duration_ms as u64)) ||
+                            (drain_deadline.is_some() && current_chunk.len() >= (sample_rate / 10) as usize);
             
             if should_send {
                 log_info!("Should send chunk with {} samples", current_chunk.len());
-                let chunk_to_send = current_chunk.clone();
-                current_chunk.clear();
+                // A backlog (pre-roll) is sent one chunk at a time
+                let send_len = current_chunk.len().min(chunk_samples);
+                let chunk_to_send: Vec<f32> = current_chunk.drain(..send_len).collect();
+                metrics::queue_depth(current_chunk.len(), sample_rate);
+                let chunk_started_at = last_chunk_time;
+                let chunk_sources = source_map.take_front(send_len);
+                let chunk_sample_rate = sample_rate;
                 last_chunk_time = std::time::Instant::now();
                 
+                if drain_deadline.is_some() {
+                    drained_chunks += 1;
+                }
+
                 // Save debug chunks
                 let chunk_num = chunk_counter_clone.fetch_add(1, Ordering::SeqCst);
                 log_info!("Processing chunk {}", chunk_num);
@@ -522,37 +1406,142 @@ async fn start_recording<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
                     chunk_to_send
                 };
 
-                // Send chunk for transcription
-                match send_audio_chunk(whisper_samples, &client).await {
-                    Ok(response) => {
-                        log_info!("Received {} transcript segments", response.segments.len());
-                        for segment in response.segments {
-                            log_info!("Processing segment: {} ({:.1}s - {:.1}s)", 
-                                     segment.text.trim(), segment.t0, segment.t1);
-                            // Add segment to accumulator and check for complete sentence
-                            if let Some(update) = accumulator.add_segment(&segment) {
-                                // Emit the update
-                                if let Err(e) = app_handle.emit("transcript-update", update) {
-                                    log_error!("Failed to emit transcript update: {}", e);
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log_error!("Transcription error: {}", e);
+                if knobs.skip_silent_chunks {
+                    let checked_at = std::time::Instant::now();
+                    let speech_ratio = metrics::time(metrics::Stage::Vad, || {
+                        noise::vad_for(&mic_stream.device.to_string()).speech_ratio(&whisper_samples, WHISPER_SAMPLE_RATE)
+                    });
+                    if speech_ratio == 0.0 {
+                        log_info!("Skipping silent chunk at reduced quality");
+                        // Skipped audio is handled in the time the check took, which
+                        // the governor needs to see to step back up
+                        let audio_ms = whisper_samples.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64;
+                        governor::observe(audio_ms, checked_at.elapsed().as_millis() as u64);
+                        continue;
                     }
                 }
+
+                // Send chunk for transcription
+                pipeline::publish(PipelineEvent::ChunkQueued { samples: whisper_samples.len() });
+                let replay_index = replay_recorder.as_mut().and_then(|recorder| {
+                    recorder
+                        .record_chunk(&whisper_samples)
+                        .map_err(|e| log_error!("Failed to record replay chunk: {}", e))
+                        .ok()
+                });
+                let experiment_chunk = experiments::next_chunk().map(|chunk| (chunk, whisper_samples.clone()));
+                // Microphone segments are matched against enrolled voices, which needs the chunk's audio
+                // The interview kit already knows who is speaking from the device
+                let enrolled = if kit_settings.is_some() {
+                    Vec::new()
+                } else {
+                    speaker_profiles::enrolled(&app_handle)
+                };
+                embedding_manager.sync(&enrolled);
+                name_bias::observe_speakers(&enrolled);
+                let enrollment_audio = (!enrolled.is_empty()).then(|| whisper_samples.clone());
+                let retry_audio = settings::get().retry.enabled.then(|| whisper_samples.clone());
+                let assurance_audio = assurance::enabled().then(|| whisper_samples.clone());
+                quality::observe_audio(&whisper_samples);
+                let audio_ms = whisper_samples.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64;
+                let ticket = chunk_num as u64;
+                pending_chunks.insert(ticket, PendingChunk {
+                    started_at: chunk_started_at,
+                    sources: chunk_sources,
+                    sample_rate: chunk_sample_rate,
+                    len: send_len,
+                    audio_ms,
+                    replay_index,
+                    experiment: experiment_chunk,
+                    enrollment_audio,
+                    retry_audio,
+                    assurance_audio,
+                });
+                inference.submit(inference::Job {
+                    ticket,
+                    input: transcription::AudioInput {
+                        samples: whisper_samples,
+                        sample_rate: WHISPER_SAMPLE_RATE,
+                        language: None,
+                        device: Some(mic_stream.device.to_string()),
+                    },
+                    endpoint: knobs.endpoint.clone(),
+                }).await;
             }
             
             tokio::time::sleep(Duration::from_millis(10)).await;
         }
         
-        // Emit any remaining transcript when recording stops
-        if let Some(update) = accumulator.check_timeout() {
-            if let Err(e) = app_handle.emit("transcript-update", update) {
+        // Emit the trailing partial sentence when recording stops, however recent
+        if let Some(update) = accumulator.flush() {
+            if let Some(recorder) = replay_recorder.as_mut() {
+                if let Err(e) = recorder.record_flush() {
+                    log_error!("Failed to record replay flush: {}", e);
+                }
+            }
+            if let Err(e) = transcript_stream::publish(&app_handle, update) {
                 log_error!("Failed to send final transcript update: {}", e);
             }
         }
+
+        if let Some(recorder) = replay_recorder {
+            match recorder.finish() {
+                Ok(path) => {
+                    if let Err(e) = app_handle.emit("replay-saved", path.to_string_lossy().to_string()) {
+                        log_error!("Failed to emit replay saved event: {}", e);
+                    }
+                }
+                Err(e) => log_error!("Failed to finish replay file: {}", e),
+            }
+            storage::finish_session(&app_handle).await;
+            // Rebuilding the transcript replays the original failures
+            if let Some(meeting_id) = storage::current_session() {
+                if let Err(e) = retry_queue::merge_recovered(&app_handle, &meeting_id) {
+                    log_error!("Failed to merge recovered transcript lines: {}", e);
+                }
+            }
+        }
+        let diarization_quality = diarization::finish(&app_handle);
+        quality::finish(&app_handle, diarization_quality.as_ref());
+        if let (Some(merged), Some(meeting_id)) = (dual_stream::finish(&app_handle), storage::current_session()) {
+            match serde_json::to_string_pretty(&merged) {
+                Ok(content) => {
+                    if let Err(e) = storage::save_derived(app_handle.clone(), meeting_id, dual_stream::MERGED_FILE.to_string(), content) {
+                        log_error!("Failed to store session transcript: {}", e);
+                    }
+                }
+                Err(e) => log_error!("Failed to serialize session transcript: {}", e),
+            }
+        }
+        noise::save_learned();
+        // The in-room head count seeds re-clustering of the stored meeting
+        let participants = farfield::active().and_then(|session| session.participants);
+        if let (Some(expected), Some(meeting_id)) = (participants, storage::current_session()) {
+            let constraints = recluster::SpeakerConstraints {
+                expected_speakers: Some(expected),
+                ..Default::default()
+            };
+            if let Err(e) = recluster::set_speaker_constraints(app_handle.clone(), meeting_id, constraints) {
+                log_error!("Failed to store participant count: {}", e);
+            }
+        }
+        farfield::end();
+        deepgram::end_session();
+        name_bias::end_session();
+        usage::flush();
+
+        let complete = SessionComplete {
+            meeting_id: storage::current_session(),
+            drained_chunks,
+            dropped_secs: dropped_samples as f32 / sample_rate as f32,
+        };
+        DRAINING_FLAG.store(false, Ordering::SeqCst);
+        pipeline::publish(PipelineEvent::SessionComplete {
+            meeting_id: complete.meeting_id.clone(),
+        });
+        if let Err(e) = app_handle.emit(SESSION_COMPLETE_EVENT, complete) {
+            log_error!("Failed to emit session complete: {}", e);
+        }
         
         log_info!("Transcription task ended");
     });
@@ -570,63 +1559,8 @@ async fn stop_recording(args: RecordingArgs) -> Result<(), String> {
         return Ok(());
     }
 
-    // Check minimum recording duration
-    let elapsed_ms = unsafe {
-        RECORDING_START_TIME
-            .map(|start| start.elapsed().as_millis() as u64)
-            .unwrap_or(0)
-    };
-
-    if elapsed_ms < MIN_RECORDING_DURATION_MS {
-        let remaining = MIN_RECORDING_DURATION_MS - elapsed_ms;
-        log_info!("Waiting for minimum recording duration ({} ms remaining)...", remaining);
-        tokio::time::sleep(Duration::from_millis(remaining)).await;
-    }
+    stop_capture().await;
 
-    // First set the recording flag to false to prevent new data from being processed
-    RECORDING_FLAG.store(false, Ordering::SeqCst);
-    log_info!("Recording flag set to false");
-    
-    unsafe {
-        // Stop the running flag for audio streams first
-        if let Some(is_running) = &IS_RUNNING {
-            // Set running flag to false first to stop the tokio task
-            is_running.store(false, Ordering::SeqCst);
-            log_info!("Set recording flag to false, waiting for streams to stop...");
-            
-            // Give the tokio task time to finish and release its references
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            
-            // Stop mic stream if it exists
-            if let Some(mic_stream) = &MIC_STREAM {
-                log_info!("Stopping microphone stream...");
-                if let Err(e) = mic_stream.stop().await {
-                    log_error!("Error stopping mic stream: {}", e);
-                } else {
-                    log_info!("Microphone stream stopped successfully");
-                }
-            }
-            
-            // Stop system stream if it exists
-            if let Some(system_stream) = &SYSTEM_STREAM {
-                log_info!("Stopping system stream...");
-                if let Err(e) = system_stream.stop().await {
-                    log_error!("Error stopping system stream: {}", e);
-                } else {
-                    log_info!("System stream stopped successfully");
-                }
-            }
-            
-            // Clear the stream references
-            MIC_STREAM = None;
-            SYSTEM_STREAM = None;
-            IS_RUNNING = None;
-            
-            // Give streams time to fully clean up
-            tokio::time::sleep(Duration::from_millis(100)).await;
-        }
-    }
-    
     // Get final buffers
     let mic_data = unsafe {
         if let Some(buffer) = &MIC_BUFFER {
@@ -745,7 +1679,74 @@ async fn stop_recording(args: RecordingArgs) -> Result<(), String> {
     }
     */
     
-    // Clean up
+    clear_recording_state();
+    
+    Ok(())
+}
+
+/// Stop capture once the minimum recording duration has elapsed and tear down the streams.
+async fn stop_capture() {
+    // Check minimum recording duration
+    let elapsed_ms = unsafe {
+        RECORDING_START_TIME
+            .map(|start| start.elapsed().as_millis() as u64)
+            .unwrap_or(0)
+    };
+
+    if elapsed_ms < MIN_RECORDING_DURATION_MS {
+        let remaining = MIN_RECORDING_DURATION_MS - elapsed_ms;
+        log_info!("Waiting for minimum recording duration ({} ms remaining)...", remaining);
+        tokio::time::sleep(Duration::from_millis(remaining)).await;
+    }
+
+    // First set the recording flag to false to prevent new data from being processed
+    RECORDING_FLAG.store(false, Ordering::SeqCst);
+    log_info!("Recording flag set to false");
+    pipeline::publish(PipelineEvent::RecordingStopped);
+    
+    unsafe {
+        // Stop the running flag for audio streams first
+        if let Some(is_running) = &IS_RUNNING {
+            // Set running flag to false first to stop the tokio task
+            is_running.store(false, Ordering::SeqCst);
+            log_info!("Set recording flag to false, waiting for streams to stop...");
+            
+            // Give the tokio task time to finish and release its references
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            
+            // Stop mic stream if it exists
+            if let Some(mic_stream) = &MIC_STREAM {
+                log_info!("Stopping microphone stream...");
+                if let Err(e) = mic_stream.stop().await {
+                    log_error!("Error stopping mic stream: {}", e);
+                } else {
+                    log_info!("Microphone stream stopped successfully");
+                }
+            }
+            
+            // Stop system stream if it exists
+            if let Some(system_stream) = &SYSTEM_STREAM {
+                log_info!("Stopping system stream...");
+                if let Err(e) = system_stream.stop().await {
+                    log_error!("Error stopping system stream: {}", e);
+                } else {
+                    log_info!("System stream stopped successfully");
+                }
+            }
+            
+            // Clear the stream references
+            MIC_STREAM = None;
+            SYSTEM_STREAM = None;
+            IS_RUNNING = None;
+            
+            // Give streams time to fully clean up
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+fn clear_recording_state() {
+    PAUSED_FLAG.store(false, Ordering::SeqCst);
     unsafe {
         MIC_BUFFER = None;
         SYSTEM_BUFFER = None;
@@ -754,7 +1755,18 @@ async fn stop_recording(args: RecordingArgs) -> Result<(), String> {
         IS_RUNNING = None;
         RECORDING_START_TIME = None;
     }
-    
+}
+
+/// Stop the current session from the backend (hotkeys, tray) without a save path.
+async fn stop_recording_session<R: Runtime>(app: &AppHandle<R>, summarize: bool) -> Result<(), String> {
+    if !RECORDING_FLAG.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    stop_capture().await;
+    clear_recording_state();
+    if let Err(e) = app.emit(RECORDING_STOPPED_EVENT, RecordingStopped { summarize }) {
+        log_error!("Failed to emit recording stopped event: {}", e);
+    }
     Ok(())
 }
 
@@ -763,9 +1775,208 @@ fn is_recording() -> bool {
     RECORDING_FLAG.load(Ordering::SeqCst)
 }
 
+#[tauri::command]
+async fn get_audio_devices() -> Result<Vec<String>, String> {
+    let mut devices: Vec<String> = list_audio_devices()
+        .await
+        .map(|devices| devices.iter().map(|d| d.to_string()).collect())
+        .map_err(|e| e.to_string())?;
+    devices.extend(remote::connected_devices().iter().map(|d| d.to_string()));
+    Ok(devices)
+}
+
+/// The microphone a recording started now would capture from, so the
+/// webview's mic preview can use its VAD tuning.
+#[tauri::command]
+fn get_preview_device() -> Result<String, String> {
+    let default = default_input_device().map_err(|e| e.to_string())?;
+    Ok(preferred_input(&default).to_string())
+}
+
+/// Record a few seconds from `device_id` and run it through VAD and the
+/// transcription server, as a one-click "is my setup working" check.
+#[tauri::command]
+async fn test_device(device_id: String) -> Result<DeviceTestResult, String> {
+    log_info!("Testing device: {}", device_id);
+    let device = Arc::new(parse_audio_device(&device_id).map_err(|e| e.to_string())?);
+
+    let is_running = Arc::new(AtomicBool::new(true));
+    let stream = AudioStream::from_device(device.clone(), is_running.clone())
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", device, e))?;
+    let sample_rate = stream.device_config.sample_rate().0;
+    let mut receiver = stream.subscribe().await;
+
+    let mut samples: Vec<f32> = Vec::new();
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(DEVICE_TEST_DURATION_MS);
+    while let Ok(chunk) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+        match chunk {
+            Ok(chunk) => samples.extend(chunk),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(_) => break,
+        }
+    }
+
+    is_running.store(false, Ordering::SeqCst);
+    if let Err(e) = stream.stop().await {
+        log_error!("Error stopping test stream: {}", e);
+    }
+
+    let level = AudioLevel::from_samples(&device.to_string(), &samples);
+    let samples = resample_audio(&samples, sample_rate, WHISPER_SAMPLE_RATE);
+    let speech_ratio = noise::vad_for(&device.to_string()).speech_ratio(&samples, WHISPER_SAMPLE_RATE);
+    let speech_detected = speech_ratio > 0.0;
+
+    let mut result = DeviceTestResult {
+        device: device.to_string(),
+        duration_ms: samples.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64,
+        level,
+        speech_ratio,
+        speech_detected,
+        transcript: None,
+        error: None,
+    };
+
+    if samples.is_empty() {
+        result.error = Some("No audio was received from the device".to_string());
+    } else if !speech_detected {
+        result.error = Some("No speech detected; check the input level or speak closer to the microphone".to_string());
+    } else {
+        match send_audio_chunk(samples).await {
+            Ok(response) => {
+                let text = response
+                    .segments
+                    .iter()
+                    .map(|segment| segment.text.trim())
+                    .filter(|text| !text.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                result.transcript = Some(text);
+            }
+            Err(e) => result.error = Some(format!("Transcription failed: {}", e)),
+        }
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+fn is_paused() -> bool {
+    PAUSED_FLAG.load(Ordering::SeqCst)
+}
+
+#[tauri::command]
+fn pause_recording() -> Result<(), String> {
+    if !is_recording() {
+        return Err("No recording in progress".to_string());
+    }
+    PAUSED_FLAG.store(true, Ordering::SeqCst);
+    log_info!("Recording paused");
+    pipeline::publish(PipelineEvent::Paused);
+    Ok(())
+}
+
+#[tauri::command]
+fn resume_recording() -> Result<(), String> {
+    if !is_recording() {
+        return Err("No recording in progress".to_string());
+    }
+    PAUSED_FLAG.store(false, Ordering::SeqCst);
+    log_info!("Recording resumed");
+    pipeline::publish(PipelineEvent::Resumed);
+    Ok(())
+}
+
+/// Toggle microphone mute; system audio keeps being captured. Returns the new mute state.
+#[tauri::command]
+fn toggle_mute() -> Result<bool, String> {
+    let muted = !MIC_MUTED_FLAG.fetch_xor(true, Ordering::SeqCst);
+    log_info!("Microphone {}", if muted { "muted" } else { "unmuted" });
+    Ok(muted)
+}
+
+#[tauri::command]
+fn insert_marker<R: Runtime>(app: AppHandle<R>, label: Option<String>) -> Result<SessionMarker, String> {
+    add_marker(&app, label, None, None)
+}
+
+/// Record a marker at the current recording offset and show it in the transcript.
+fn add_marker<R: Runtime>(
+    app: &AppHandle<R>,
+    label: Option<String>,
+    source: Option<String>,
+    link: Option<String>,
+) -> Result<SessionMarker, String> {
+    if let Some(link) = &link {
+        check_marker_link(link)?;
+    }
+    let offset_ms = unsafe {
+        RECORDING_START_TIME
+            .map(|start| start.elapsed().as_millis() as u64)
+            .ok_or_else(|| "No recording in progress".to_string())?
+    };
+
+    let mut markers = SESSION_MARKERS
+        .lock()
+        .map_err(|e| format!("Failed to lock markers: {}", e))?;
+    let marker = SessionMarker {
+        label: label.unwrap_or_else(|| format!("Marker {}", markers.len() + 1)),
+        offset_ms,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        source,
+        link,
+    };
+    markers.push(marker.clone());
+    drop(markers);
+    announce_marker(app, &marker);
+    Ok(marker)
+}
+
+/// Record a marker the capture task placed itself. It keeps its own start
+/// time, so this also works while queued audio drains after a stop.
+fn push_marker<R: Runtime>(app: &AppHandle<R>, label: String, offset_ms: u64) {
+    let marker = SessionMarker {
+        label,
+        offset_ms,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        source: None,
+        link: None,
+    };
+    match SESSION_MARKERS.lock() {
+        Ok(mut markers) => markers.push(marker.clone()),
+        Err(e) => log_error!("Failed to lock markers: {}", e),
+    }
+    announce_marker(app, &marker);
+}
+
+fn announce_marker<R: Runtime>(app: &AppHandle<R>, marker: &SessionMarker) {
+    log_info!("Inserted marker '{}' at {} ms", marker.label, marker.offset_ms);
+    if let Err(e) = events::emit(app, events::MARKER_INSERTED, marker.clone()) {
+        log_error!("Failed to emit marker event: {}", e);
+    }
+}
+
+/// Marker links are opened from the main webview, so only web pages are
+/// allowed; a `javascript:` link would run with full IPC access.
+pub fn check_marker_link(link: &str) -> Result<(), String> {
+    let url = url::Url::parse(link).map_err(|e| format!("Invalid link: {}", e))?;
+    match url.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(format!("Links must use http or https, not {}", scheme)),
+    }
+}
+
+#[tauri::command]
+fn get_session_markers() -> Vec<SessionMarker> {
+    SESSION_MARKERS
+        .lock()
+        .map(|markers| markers.clone())
+        .unwrap_or_default()
+}
+
 #[tauri::command]
 fn read_audio_file(file_path: String) -> Result<Vec<u8>, String> {
-    match std::fs::read(&file_path) {
+    match encryption::read(&file_path) {
         Ok(data) => Ok(data),
         Err(e) => Err(format!("Failed to read audio file: {}", e))
     }
@@ -788,6 +1999,7 @@ async fn save_transcript(file_path: String, content: String) -> Result<(), Strin
         .map_err(|e| format!("Failed to write transcript: {}", e))?;
 
     log::info!("Transcript saved successfully");
+    pipeline::publish(PipelineEvent::TranscriptSaved { path: file_path });
     Ok(())
 }
 
@@ -803,11 +2015,272 @@ fn stereo_to_mono(stereo: &[i16]) -> Vec<i16> {
     mono
 }
 
+/// `tauri::generate_handler!` over every command, plus `$extra` commands
+/// that only some builds have.
+macro_rules! app_handler {
+    ($($extra:tt)*) => {
+        tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            is_recording,
+            read_audio_file,
+            save_transcript,
+            is_paused,
+            pause_recording,
+            resume_recording,
+            toggle_mute,
+            insert_marker,
+            get_session_markers,
+            hotkeys::get_hotkey_bindings,
+            hotkeys::set_hotkey_binding,
+            pipeline::get_pipeline_status,
+            notifications::get_notification_settings,
+            notifications::set_notification_settings,
+            notifications::report_summary_ready,
+            audio::permissions::audio_preflight,
+            audio::permissions::check_audio_permission,
+            audio::permissions::request_audio_permission,
+            audio::permissions::open_permission_settings,
+            get_audio_devices,
+            get_preview_device,
+            audio::metering::start_level_metering,
+            audio::metering::stop_level_metering,
+            audio::vad::get_preview_vad,
+            audio::noise::get_noise_settings,
+            audio::noise::set_noise_settings,
+            audio::noise::reset_noise_profile,
+            audio::farfield::get_conference_settings,
+            audio::farfield::set_conference_settings,
+            audio::farfield::get_conference_session,
+            audio::time_stretch::get_time_stretch_settings,
+            audio::time_stretch::set_time_stretch_settings,
+            test_device,
+            settings::get_audio_settings,
+            settings::set_audio_settings,
+            replay::replay_session,
+            replay::get_replay_settings,
+            replay::set_replay_settings,
+            experiments::start_experiment,
+            experiments::stop_experiment,
+            experiments::get_experiment_status,
+            governor::get_governor_status,
+            governor::get_governor_settings,
+            governor::set_governor_settings,
+            audio::remote::start_companion_server,
+            audio::remote::stop_companion_server,
+            audio::remote::list_remote_devices,
+            federation::join_federation,
+            federation::leave_federation,
+            federation::get_federation_status,
+            external_track::attach_external_track,
+            transcript_stream::subscribe_transcript,
+            transcript_stream::unsubscribe_transcript,
+            captions::open_caption_window,
+            captions::close_caption_window,
+            captions::get_caption_settings,
+            captions::set_caption_settings,
+            speakers::list_speaker_display,
+            speakers::set_speaker_display,
+            speakers::remove_speaker_display,
+            api::start_local_api,
+            api::stop_local_api,
+            api::get_local_api_info,
+            events::get_event_schema,
+            secrets::set_secret,
+            secrets::delete_secret,
+            secrets::list_secret_names,
+            secrets::set_api_key,
+            secrets::has_api_key,
+            export::get_export_settings,
+            export::set_export_settings,
+            export::export_meeting,
+            export::get_export_status,
+            export::export_meeting_to_folder,
+            export::export_masked_audio,
+            export::preview_export_name,
+            export::subtitles::export_subtitles,
+            export::embeddings::set_research_export_consent,
+            export::embeddings::export_speaker_embeddings,
+            export::rules::get_auto_export_rules,
+            export::rules::set_auto_export_rules,
+            export::rules::get_auto_export_history,
+            storage::regenerate_derived,
+            storage::save_derived,
+            storage::get_stored_transcript,
+            storage::list_stored_meetings,
+            storage::search_meetings,
+            storage::set_meeting_metadata,
+            storage::list_meeting_tags,
+            bulk::start_bulk_operation,
+            bulk::cancel_bulk_operation,
+            bulk::list_bulk_operations,
+            storage::get_storage_settings,
+            storage::set_storage_settings,
+            integrity::verify_meeting_integrity,
+            integrity::get_integrity_settings,
+            integrity::set_integrity_settings,
+            diarization::get_diarization_quality,
+            recluster::recluster_meeting,
+            recluster::get_speaker_constraints,
+            recluster::set_speaker_constraints,
+            speaker_profiles::list_speaker_profiles,
+            speaker_profiles::rename_speaker_profile,
+            speaker_profiles::merge_speaker_profiles,
+            speaker_profiles::delete_speaker_profile,
+            speaker_profiles::get_speaker_profile_settings,
+            speaker_profiles::set_speaker_profile_settings,
+            enrollment::record_voice_enrollment,
+            enrollment::cancel_voice_enrollment,
+            enrollment::list_enrolled_speakers,
+            dual_stream::get_merged_transcript,
+            transcript_diff::list_transcript_versions,
+            transcript_diff::diff_transcript,
+            transcript_diff::apply_transcript_diff,
+            feedback::submit_segment_feedback,
+            feedback::get_meeting_feedback,
+            feedback::get_quality_report,
+            session::start_session,
+            session::stop_session,
+            session::get_active_session,
+            bilingual::get_bilingual_settings,
+            bilingual::set_bilingual_settings,
+            transcript_store::search_transcripts,
+            transcript_store::get_session_transcript,
+            whisper_decode::get_whisper_decode_options,
+            whisper_decode::set_whisper_decode_options,
+            softphone::start_call,
+            softphone::set_call_caller,
+            softphone::lookup_caller,
+            softphone::get_active_call,
+            softphone::get_softphone_settings,
+            softphone::set_softphone_settings,
+            quick_transcribe::quick_transcribe,
+            hallucination::get_hallucination_settings,
+            hallucination::set_hallucination_settings,
+            language_id::get_language_id_settings,
+            language_id::set_language_id_settings,
+            watch_folder::get_watch_folder_settings,
+            watch_folder::set_watch_folder_settings,
+            preroll::get_preroll_settings,
+            preroll::set_preroll_settings,
+            calendar::list_calendar_events,
+            calendar::refresh_calendar,
+            calendar::arm_calendar_event,
+            calendar::get_scheduled_recording,
+            calendar::get_calendar_settings,
+            calendar::set_calendar_settings,
+            meeting_end::get_pending_meeting_end,
+            meeting_end::keep_meeting_going,
+            meeting_end::get_meeting_end_settings,
+            meeting_end::set_meeting_end_settings,
+            compute_device::get_compute_device,
+            compute_device::get_compute_device_settings,
+            compute_device::set_compute_device_settings,
+            deepgram::get_deepgram_settings,
+            deepgram::set_deepgram_settings,
+            transcription::list_transcription_engines,
+            transcription::get_transcription_settings,
+            transcription::set_transcription_settings,
+            openai::get_openai_settings,
+            openai::set_openai_settings,
+            assemblyai::get_assemblyai_settings,
+            assemblyai::set_assemblyai_settings,
+            calibration::start_calibration,
+            calibration::run_calibration_capture,
+            calibration::analyze_calibration,
+            calibration::apply_calibration,
+            calibration::cancel_calibration,
+            azure::get_azure_settings,
+            azure::set_azure_settings,
+            whisper_cpp::list_whisper_cpp_models,
+            whisper_cpp::list_whisper_cpp_catalog,
+            models::list_models,
+            models::download_model,
+            models::verify_model,
+            models::delete_model,
+            models::check_local_models,
+            models::get_model_settings,
+            models::set_model_settings,
+            screenpipe_import::import_screenpipe,
+            timeline::get_meeting_timeline,
+            metrics::get_pipeline_metrics,
+            metrics::get_metrics_settings,
+            metrics::set_metrics_settings,
+            interview_kit::get_interview_kit_settings,
+            interview_kit::set_interview_kit_settings,
+            chapters::get_chapter_previews,
+            itn::get_itn_settings,
+            itn::set_itn_settings,
+            retry_queue::list_failed_segments,
+            retry_queue::retry_failed_segments,
+            retry_queue::get_retry_settings,
+            retry_queue::set_retry_settings,
+            request_limit::get_request_limit_settings,
+            request_limit::set_request_limit_settings,
+            connectivity::get_connectivity_status,
+            connectivity::get_connectivity_settings,
+            connectivity::set_connectivity_settings,
+            assurance::get_assurance_review,
+            assurance::get_assurance_settings,
+            assurance::set_assurance_settings,
+            usage::get_usage_stats,
+            usage::get_usage_settings,
+            usage::set_usage_settings,
+            name_bias::get_session_names,
+            name_bias::get_name_bias_settings,
+            name_bias::set_name_bias_settings,
+            quality::get_meeting_quality,
+            readiness::check_readiness,
+            encryption::get_encryption_settings,
+            encryption::set_encryption_settings,
+            whisper_cpp::get_whisper_cpp_settings,
+            whisper_cpp::set_whisper_cpp_settings,
+            $($extra)*
+        ]
+    };
+}
+
+// The soak test harness is left out of release builds entirely
+#[cfg(feature = "soak-test")]
+fn invoke_handler<R: Runtime>() -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    app_handler![soak::start_soak_test, soak::stop_soak_test,]
+}
+
+#[cfg(not(feature = "soak-test"))]
+fn invoke_handler<R: Runtime>() -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    app_handler![]
+}
+
 pub fn run() {
     log::set_max_level(log::LevelFilter::Info);
     
     tauri::Builder::default()
-        .setup(|_app| {
+        .plugin(hotkeys::plugin())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .setup(|app| {
+            settings::init(app.handle());
+            secrets::init(app.handle());
+            hotkeys::init(app.handle());
+            pipeline::init(app.handle());
+            metrics::init(app.handle());
+            connectivity::init();
+            usage::init(app.handle());
+            notifications::init(app.handle());
+            export::rules::init(app.handle());
+            transcript_store::init(app.handle());
+            softphone::init(app.handle());
+            watch_folder::init(app.handle());
+            preroll::init();
+            calendar::init(app.handle());
+            meeting_end::init(app.handle());
+            compute_device::init();
+            if let Err(e) = tray::init(app.handle()) {
+                log::error!("Failed to initialize system tray: {}", e);
+            }
+
             log::info!("Application setup complete");
 
             // Trigger microphone permission request on startup
@@ -817,31 +2290,36 @@ pub fn run() {
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            start_recording,
-            stop_recording,
-            is_recording,
-            read_audio_file,
-            save_transcript,
-        ])
+        .invoke_handler(invoke_handler())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 // Helper function to resample audio
-fn resample_audio(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+pub fn resample_audio(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == 0 || to_rate == 0 {
+        log_error!("Invalid sample rates for resampling: {} -> {}", from_rate, to_rate);
+        return Vec::new();
+    }
     if from_rate == to_rate {
-        return samples.to_vec();
+        return audio::audio_processing::sanitize_samples(samples);
     }
     
     let ratio = to_rate as f32 / from_rate as f32;
+    if ratio as f64 > audio::audio_processing::MAX_RESAMPLE_RATIO
+        || (ratio as f64) < 1.0 / audio::audio_processing::MAX_RESAMPLE_RATIO
+    {
+        log_error!("Resample ratio {} -> {} is out of range", from_rate, to_rate);
+        return Vec::new();
+    }
     let new_len = (samples.len() as f32 * ratio) as usize;
     let mut resampled = Vec::with_capacity(new_len);
     
     for i in 0..new_len {
         let src_idx = (i as f32 / ratio) as usize;
         if src_idx < samples.len() {
-            resampled.push(samples[src_idx]);
+            let sample = samples[src_idx];
+            resampled.push(if sample.is_finite() { sample } else { 0.0 });
         }
     }
     
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::error;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::transcript_store;
+use crate::transcription::TranscriptionResult;
+
+/// Emitted with a [`Correction`] whenever a streaming engine revises a segment.
+pub const LIVE_CORRECTION_EVENT: &str = "live-correction";
+/// Segments per stream that can still be revised; older ones are settled.
+const MAX_TRACKED: usize = 64;
+
+/// Segment ids are unique across streams so stored rows can't be confused.
+static NEXT_SEGMENT: AtomicU64 = AtomicU64::new(1);
+
+/// Replace words `from..to` of segment `segment`'s previous hypothesis with
+/// `words`. A new segment arrives as an insertion at `0..0`; applying every
+/// correction of a segment in order yields its latest hypothesis.
+#[derive(Debug, Clone, Serialize)]
+pub struct Correction {
+    pub device: String,
+    pub session_id: Option<String>,
+    pub segment: u64,
+    pub from: usize,
+    pub to: usize,
+    pub words: Vec<String>,
+    /// Seconds since the stream opened, of the whole revised segment.
+    pub start: f32,
+    pub end: f32,
+    /// The engine won't revise the segment further, short of reformatting it.
+    pub is_final: bool,
+}
+
+/// Apply a correction to a segment's words.
+pub fn apply(words: &mut Vec<String>, correction: &Correction) {
+    let to = correction.to.min(words.len());
+    let from = correction.from.min(to);
+    words.splice(from..to, correction.words.iter().cloned());
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_string).collect()
+}
+
+/// The smallest `from..to` of `old` to replace, and what to replace it with,
+/// to turn `old` into `new`.
+fn diff(old: &[String], new: &[String]) -> (usize, usize, Vec<String>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix, old.len() - suffix, new[prefix..new.len() - suffix].to_vec())
+}
+
+struct Tracked {
+    /// The engine's own key for the segment: start time or turn number.
+    key: u64,
+    segment: u64,
+    words: Vec<String>,
+    is_final: bool,
+    /// Transcript database row once the segment has been stored.
+    row: Option<i64>,
+}
+
+/// Turns one streaming engine's successive hypotheses into corrections, so
+/// captions and stored segments only ever change by the words that changed.
+#[derive(Default)]
+pub struct CorrectionTracker {
+    tracked: VecDeque<Tracked>,
+}
+
+impl CorrectionTracker {
+    /// Diff `result` against the last hypothesis of the segment the engine
+    /// identifies by `key`, emit the correction and apply it to storage once
+    /// the segment is final.
+    pub fn publish<R: Runtime>(&mut self, app: &AppHandle<R>, key: u64, result: &TranscriptionResult) {
+        let index = match self.tracked.iter().position(|tracked| tracked.key == key) {
+            Some(index) => index,
+            None => {
+                if self.tracked.len() == MAX_TRACKED {
+                    self.tracked.pop_front();
+                }
+                self.tracked.push_back(Tracked {
+                    key,
+                    segment: NEXT_SEGMENT.fetch_add(1, Ordering::Relaxed),
+                    words: Vec::new(),
+                    is_final: false,
+                    row: None,
+                });
+                self.tracked.len() - 1
+            }
+        };
+        let tracked = &mut self.tracked[index];
+        let words = split_words(&result.text);
+        if words == tracked.words && (tracked.is_final || !result.is_final) {
+            return;
+        }
+
+        let (from, to, replacement) = diff(&tracked.words, &words);
+        let correction = Correction {
+            device: result.device.clone(),
+            session_id: result.session_id.clone(),
+            segment: tracked.segment,
+            from,
+            to,
+            words: replacement,
+            start: result.start,
+            end: result.end,
+            is_final: result.is_final || tracked.is_final,
+        };
+        apply(&mut tracked.words, &correction);
+        tracked.is_final = correction.is_final;
+        if let Err(e) = app.emit(LIVE_CORRECTION_EVENT, &correction) {
+            error!("Failed to emit live correction: {}", e);
+        }
+
+        if !correction.is_final {
+            return;
+        }
+        match tracked.row {
+            Some(row) => transcript_store::revise_live(row, &correction, result.confidence),
+            None => tracked.row = transcript_store::record_live(app, result),
+        }
+    }
+}
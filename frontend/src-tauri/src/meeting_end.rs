@@ -0,0 +1,276 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio::app_activity::active_audio_apps;
+use crate::audio::remote;
+use crate::notifications::{self, NotificationCategory};
+use crate::{calendar, is_recording, settings, stop_recording_session, storage, transcript_stream};
+
+/// Sent when a recording looks finished and again when the end is cancelled
+/// or carried out; the payload is the pending end, or `null` once resolved.
+pub const MEETING_ENDING_EVENT: &str = "meeting-ending";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// End recordings on their own when the meeting is evidently over, after a
+/// grace period announced with an "are you still meeting?" notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingEndSettings {
+    pub enabled: bool,
+    /// End once every meeting application that played audio during the
+    /// recording has stopped.
+    pub when_app_closes: bool,
+    /// Application names (case-insensitive substrings) treated as meeting apps.
+    pub meeting_apps: Vec<String>,
+    /// End once the calendar event being recorded is over and nothing has
+    /// been said for `silence_secs`.
+    pub when_event_ends: bool,
+    pub silence_secs: u64,
+    /// End once every remote microphone that joined the recording disconnects.
+    pub when_remote_audio_stops: bool,
+    /// Seconds between the notification and the recording being stopped.
+    pub grace_secs: u64,
+}
+
+impl Default for MeetingEndSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            when_app_closes: true,
+            meeting_apps: ["zoom", "Teams", "Webex", "Slack", "Discord", "FaceTime", "Skype", "GoTo"]
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            when_event_ends: true,
+            silence_secs: 120,
+            when_remote_audio_stops: true,
+            grace_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndReason {
+    AppClosed,
+    EventEnded,
+    RemoteAudioStopped,
+}
+
+impl EndReason {
+    fn describe(self) -> &'static str {
+        match self {
+            EndReason::AppClosed => "The meeting app has closed",
+            EndReason::EventEnded => "The calendar event is over and it has gone quiet",
+            EndReason::RemoteAudioStopped => "All remote microphones have disconnected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingEnd {
+    pub reason: EndReason,
+    /// When the recording will be stopped unless the end is cancelled.
+    pub stops_at: DateTime<Utc>,
+    #[serde(skip)]
+    since: Instant,
+}
+
+/// What has been seen of the current recording.
+#[derive(Debug, Default)]
+struct Watch {
+    session_id: Option<String>,
+    started: Option<Instant>,
+    saw_meeting_app: bool,
+    saw_remote_audio: bool,
+    event_end: Option<DateTime<Utc>>,
+    pending: Option<PendingEnd>,
+    /// Reasons the user said to ignore for the rest of the recording.
+    dismissed: Vec<EndReason>,
+}
+
+static WATCH: Lazy<Mutex<Option<Watch>>> = Lazy::new(|| Mutex::new(None));
+static LAST_HEARD: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+fn is_meeting_app(app: &str, config: &MeetingEndSettings) -> bool {
+    let app = app.to_lowercase();
+    config.meeting_apps.iter().any(|name| app.contains(&name.to_lowercase()))
+}
+
+/// Why the recording looks over, if it does.
+fn end_reason(watch: &mut Watch, config: &MeetingEndSettings, playing: Option<Vec<String>>) -> Option<EndReason> {
+    let mut reasons = Vec::new();
+
+    if let Some(playing) = playing {
+        let meeting_app_playing = playing.iter().any(|app| is_meeting_app(app, config));
+        watch.saw_meeting_app |= meeting_app_playing;
+        if config.when_app_closes && watch.saw_meeting_app && !meeting_app_playing {
+            reasons.push(EndReason::AppClosed);
+        }
+    }
+
+    if watch.event_end.is_none() {
+        watch.event_end = calendar::event_at(Utc::now()).map(|event| event.end);
+    }
+    if let (true, Some(end)) = (config.when_event_ends, watch.event_end) {
+        let started = watch.started.unwrap_or_else(Instant::now);
+        let quiet_since = LAST_HEARD
+            .lock()
+            .ok()
+            .and_then(|heard| *heard)
+            .map_or(started, |heard| heard.max(started));
+        if Utc::now() >= end && quiet_since.elapsed() >= Duration::from_secs(config.silence_secs) {
+            reasons.push(EndReason::EventEnded);
+        }
+    }
+
+    let remote_connected = !remote::connected_devices().is_empty();
+    watch.saw_remote_audio |= remote_connected;
+    if config.when_remote_audio_stops && watch.saw_remote_audio && !remote_connected {
+        reasons.push(EndReason::RemoteAudioStopped);
+    }
+
+    reasons.into_iter().find(|reason| !watch.dismissed.contains(reason))
+}
+
+async fn poll<R: Runtime>(app: &AppHandle<R>, config: &MeetingEndSettings) {
+    if !is_recording() {
+        if let Ok(mut watch) = WATCH.lock() {
+            *watch = None;
+        }
+        return;
+    }
+    let playing = tokio::task::spawn_blocking(active_audio_apps).await.ok().flatten();
+    let session_id = storage::current_session();
+
+    let (announce, stop) = {
+        let Ok(mut guard) = WATCH.lock() else {
+            return;
+        };
+        // A new recording starts with a clean slate
+        if guard.as_ref().map_or(true, |watch| watch.session_id != session_id) {
+            *guard = Some(Watch {
+                session_id: session_id.clone(),
+                started: Some(Instant::now()),
+                ..Watch::default()
+            });
+        }
+        let Some(watch) = guard.as_mut() else {
+            return;
+        };
+        let reason = end_reason(watch, config, playing);
+        match (reason, watch.pending.as_ref()) {
+            (Some(reason), None) => {
+                let pending = PendingEnd {
+                    reason,
+                    stops_at: Utc::now() + chrono::Duration::seconds(config.grace_secs as i64),
+                    since: Instant::now(),
+                };
+                watch.pending = Some(pending.clone());
+                (Some(Some(pending)), false)
+            }
+            (Some(_), Some(pending)) => (None, pending.since.elapsed() >= Duration::from_secs(config.grace_secs)),
+            // The meeting picked up again
+            (None, Some(_)) => {
+                watch.pending = None;
+                (Some(None), false)
+            }
+            (None, None) => (None, false),
+        }
+    };
+
+    if let Some(pending) = announce {
+        if let Some(pending) = &pending {
+            info!("Meeting looks over ({:?}), stopping in {} s", pending.reason, config.grace_secs);
+            notifications::notify(
+                app,
+                NotificationCategory::MeetingEnding,
+                "Are you still meeting?",
+                &format!(
+                    "{}. Recording stops in {} seconds unless you keep it going.",
+                    pending.reason.describe(),
+                    config.grace_secs
+                ),
+            );
+        }
+        let _ = app.emit(MEETING_ENDING_EVENT, pending);
+    }
+    if stop {
+        info!("Ending recording after the grace period");
+        if let Err(e) = stop_recording_session(app, false).await {
+            error!("Failed to end recording: {}", e);
+        }
+        if let Ok(mut watch) = WATCH.lock() {
+            *watch = None;
+        }
+        let _ = app.emit(MEETING_ENDING_EVENT, None::<PendingEnd>);
+    }
+}
+
+/// Watch recordings for signs the meeting has ended.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut updates = transcript_stream::subscribe();
+        loop {
+            match updates.recv().await {
+                Ok(_) => {
+                    if let Ok(mut heard) = LAST_HEARD.lock() {
+                        *heard = Some(Instant::now());
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let config = settings::get().meeting_end;
+            if config.enabled {
+                poll(&app, &config).await;
+            }
+        }
+    });
+}
+
+/// The automatic end under way, if any.
+#[command]
+pub fn get_pending_meeting_end() -> Option<PendingEnd> {
+    WATCH.lock().ok()?.as_ref()?.pending.clone()
+}
+
+/// Answer "still meeting": cancel the pending end and ignore its reason for
+/// the rest of the recording.
+#[command]
+pub fn keep_meeting_going<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    let mut guard = WATCH.lock().map_err(|e| e.to_string())?;
+    let Some(watch) = guard.as_mut() else {
+        return Ok(());
+    };
+    if let Some(pending) = watch.pending.take() {
+        info!("Keeping the recording going despite {:?}", pending.reason);
+        watch.dismissed.push(pending.reason);
+        let _ = app.emit(MEETING_ENDING_EVENT, None::<PendingEnd>);
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_meeting_end_settings() -> MeetingEndSettings {
+    settings::get().meeting_end
+}
+
+#[command]
+pub fn set_meeting_end_settings(meeting_end_settings: MeetingEndSettings) -> Result<MeetingEndSettings, String> {
+    settings::update(|s| s.meeting_end = meeting_end_settings).map(|s| s.meeting_end)
+}
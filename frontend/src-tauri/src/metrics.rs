@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::error;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::{pipeline, request_limit, settings};
+
+/// Emitted with [`PipelineMetrics`] every `emit_interval_secs` while recording.
+pub const PIPELINE_METRICS_EVENT: &str = "pipeline-metrics";
+/// Latencies kept per stage for the percentiles.
+const WINDOW: usize = 200;
+/// Weight of the newest chunk in the smoothed real-time factor.
+const RTF_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsSettings {
+    /// Emit metrics this often while recording; 0 turns the event off.
+    pub emit_interval_secs: u64,
+}
+
+/// Instrumented steps of the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Speech detection deciding whether a chunk is worth transcribing.
+    Vad,
+    /// Attributing a transcribed segment to a speaker.
+    Diarization,
+    /// A chunk's round trip through the transcription engine.
+    Stt,
+    /// Waiting for a slot to send a request to a cloud engine.
+    CloudQueue,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StageMetrics {
+    pub count: u64,
+    pub failures: u64,
+    pub last_ms: f64,
+    /// Over the last few hundred runs.
+    pub mean_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Where time goes in the current (or last) recording, for diagnosing a
+/// transcript that lags behind the conversation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PipelineMetrics {
+    pub recording: bool,
+    pub elapsed_ms: u64,
+    /// Audio captured but not yet sent for transcription.
+    pub queue_depth_secs: f64,
+    pub max_queue_depth_secs: f64,
+    /// Age of the chunk the engine is working on, if any.
+    pub queue_lag_ms: u64,
+    pub chunks: u64,
+    pub audio_secs: f64,
+    /// Processing time over audio time: above 1.0 the pipeline falls behind.
+    pub realtime_factor: Option<f64>,
+    pub session_realtime_factor: Option<f64>,
+    pub vad: StageMetrics,
+    pub diarization: StageMetrics,
+    pub stt: StageMetrics,
+    pub cloud_queue: StageMetrics,
+    /// Requests waiting for a cloud slot right now, by device.
+    pub cloud_waiting: BTreeMap<String, usize>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct StageWindow {
+    count: u64,
+    failures: u64,
+    max_ms: f64,
+    recent: VecDeque<f64>,
+}
+
+impl StageWindow {
+    fn record(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.count += 1;
+        self.max_ms = self.max_ms.max(ms);
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(ms);
+    }
+
+    fn snapshot(&self) -> StageMetrics {
+        let mut sorted: Vec<f64> = self.recent.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let p95 = match sorted.len() {
+            0 => 0.0,
+            n => sorted[((n as f64 * 0.95).ceil() as usize).clamp(1, n) - 1],
+        };
+        StageMetrics {
+            count: self.count,
+            failures: self.failures,
+            last_ms: self.recent.back().copied().unwrap_or(0.0),
+            mean_ms: if sorted.is_empty() { 0.0 } else { sorted.iter().sum::<f64>() / sorted.len() as f64 },
+            p95_ms: p95,
+            max_ms: self.max_ms,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MetricsState {
+    queue_depth_secs: f64,
+    max_queue_depth_secs: f64,
+    chunks: u64,
+    audio_ms: u64,
+    processing_ms: u64,
+    rtf: Option<f64>,
+    vad: StageWindow,
+    diarization: StageWindow,
+    stt: StageWindow,
+    cloud_queue: StageWindow,
+    last_error: Option<String>,
+}
+
+impl MetricsState {
+    fn stage(&mut self, stage: Stage) -> &mut StageWindow {
+        match stage {
+            Stage::Vad => &mut self.vad,
+            Stage::Diarization => &mut self.diarization,
+            Stage::Stt => &mut self.stt,
+            Stage::CloudQueue => &mut self.cloud_queue,
+        }
+    }
+}
+
+static STATE: Lazy<Mutex<MetricsState>> = Lazy::new(|| Mutex::new(MetricsState::default()));
+
+/// Start a recording with empty metrics.
+pub fn reset() {
+    if let Ok(mut state) = STATE.lock() {
+        *state = MetricsState::default();
+    }
+}
+
+/// Time one run of `stage`.
+pub fn record(stage: Stage, elapsed: Duration) {
+    if let Ok(mut state) = STATE.lock() {
+        state.stage(stage).record(elapsed);
+    }
+}
+
+/// Run `f` as one run of `stage`.
+pub fn time<T>(stage: Stage, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record(stage, started.elapsed());
+    result
+}
+
+pub fn failure(stage: Stage, error: &str) {
+    if let Ok(mut state) = STATE.lock() {
+        state.stage(stage).failures += 1;
+        state.last_error = Some(error.to_string());
+    }
+}
+
+/// Audio waiting in the capture buffer after a chunk was taken from it.
+pub fn queue_depth(samples: usize, sample_rate: u32) {
+    let secs = samples as f64 / sample_rate.max(1) as f64;
+    if let Ok(mut state) = STATE.lock() {
+        state.queue_depth_secs = secs;
+        state.max_queue_depth_secs = state.max_queue_depth_secs.max(secs);
+    }
+}
+
+/// A chunk of `audio_ms` took `processing_ms` to come back from the engine.
+pub fn chunk_processed(audio_ms: u64, processing_ms: u64) {
+    if audio_ms == 0 {
+        return;
+    }
+    if let Ok(mut state) = STATE.lock() {
+        let sample = processing_ms as f64 / audio_ms as f64;
+        state.rtf = Some(match state.rtf {
+            Some(previous) => previous + RTF_SMOOTHING * (sample - previous),
+            None => sample,
+        });
+        state.chunks += 1;
+        state.audio_ms += audio_ms;
+        state.processing_ms += processing_ms;
+    }
+}
+
+pub fn snapshot() -> PipelineMetrics {
+    let status = pipeline::status();
+    let Ok(state) = STATE.lock() else {
+        return PipelineMetrics::default();
+    };
+    PipelineMetrics {
+        recording: status.recording,
+        elapsed_ms: status.elapsed_ms,
+        queue_depth_secs: state.queue_depth_secs,
+        max_queue_depth_secs: state.max_queue_depth_secs,
+        queue_lag_ms: status.queue_lag_ms,
+        chunks: state.chunks,
+        audio_secs: state.audio_ms as f64 / 1000.0,
+        realtime_factor: state.rtf,
+        session_realtime_factor: (state.audio_ms > 0)
+            .then(|| state.processing_ms as f64 / state.audio_ms as f64),
+        vad: state.vad.snapshot(),
+        diarization: state.diarization.snapshot(),
+        stt: state.stt.snapshot(),
+        cloud_queue: state.cloud_queue.snapshot(),
+        cloud_waiting: request_limit::waiting(),
+        last_error: state.last_error.clone(),
+    }
+}
+
+/// Emit metrics periodically while recording, when enabled.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = settings::get().metrics.emit_interval_secs;
+            tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+            if interval == 0 || !pipeline::status().recording {
+                continue;
+            }
+            if let Err(e) = app.emit(PIPELINE_METRICS_EVENT, snapshot()) {
+                error!("Failed to emit pipeline metrics: {}", e);
+            }
+        }
+    });
+}
+
+#[command]
+pub fn get_pipeline_metrics() -> PipelineMetrics {
+    snapshot()
+}
+
+#[command]
+pub fn get_metrics_settings() -> MetricsSettings {
+    settings::get().metrics
+}
+
+#[command]
+pub fn set_metrics_settings(metrics_settings: MetricsSettings) -> Result<MetricsSettings, String> {
+    settings::update(|s| s.metrics = metrics_settings).map(|s| s.metrics)
+}
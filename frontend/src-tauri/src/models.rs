@@ -0,0 +1,713 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+use tokio::io::AsyncWriteExt;
+
+use crate::{secrets, settings, whisper_cpp};
+
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "model-download-progress";
+const MODELS_DIR: &str = "models";
+const MANIFEST_FILE: &str = "manifest.json";
+/// Downloads in progress are kept under the final name plus this extension
+/// and picked up where they stopped next time.
+const PARTIAL_EXTENSION: &str = "part";
+/// Progress is reported about this often.
+const PROGRESS_STEP_BYTES: u64 = 1 << 20;
+/// Secrets vault entry of the Hugging Face access token for gated models.
+pub const HF_TOKEN_SECRET: &str = "huggingface-token";
+const HUGGING_FACE: &str = "https://huggingface.co";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    /// GGML/GGUF speech recognition models run by the whisper.cpp server.
+    Whisper,
+    /// Speaker segmentation and embedding networks (ONNX).
+    Pyannote,
+    /// Neural voice activity detection (ONNX).
+    Silero,
+}
+
+impl ModelKind {
+    fn folder(self) -> &'static str {
+        match self {
+            ModelKind::Whisper => "whisper",
+            ModelKind::Pyannote => "pyannote",
+            ModelKind::Silero => "silero",
+        }
+    }
+}
+
+/// Where models come from. Air-gapped machines point `local_dir` at a copy of
+/// the model files and turn on `offline_mode` so nothing is ever downloaded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    /// Checked before the app's own model folders. Files may sit directly in
+    /// it or in a `whisper`, `pyannote` or `silero` subfolder.
+    pub local_dir: Option<String>,
+    /// Never hit the network for models; missing files are an error.
+    pub offline_mode: bool,
+    /// Used instead of https://huggingface.co, e.g. an internal mirror.
+    pub mirror_url: Option<String>,
+    /// HTTP(S) proxy for model downloads, e.g. `http://proxy.corp:3128`.
+    /// Without one the system's `HTTPS_PROXY`/`HTTP_PROXY` are used.
+    pub proxy: Option<String>,
+}
+
+impl ModelSettings {
+    fn client(&self) -> Result<reqwest::Client, String> {
+        self.client_with(reqwest::redirect::Policy::default())
+    }
+
+    fn client_with(&self, redirect: reqwest::redirect::Policy) -> Result<reqwest::Client, String> {
+        let mut builder = reqwest::Client::builder().redirect(redirect);
+        if let Some(proxy) = self.proxy.as_deref().filter(|proxy| !proxy.trim().is_empty()) {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?);
+        }
+        builder.build().map_err(|e| format!("Failed to create download client: {}", e))
+    }
+
+    /// Where to fetch `url` from, and whether it's a Hugging Face download the
+    /// access token goes with.
+    fn resolve(&self, url: &str) -> (String, bool) {
+        match url.strip_prefix(HUGGING_FACE) {
+            Some(path) => {
+                let base = self.mirror_url.as_deref().filter(|base| !base.trim().is_empty());
+                (format!("{}{}", base.unwrap_or(HUGGING_FACE).trim_end_matches('/'), path), true)
+            }
+            None => (url.to_string(), false),
+        }
+    }
+}
+
+/// A model file that wasn't found, and every place it was looked for.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingModel {
+    pub kind: ModelKind,
+    pub file: String,
+    pub searched: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModelError {
+    /// Offline mode is on and these files aren't in any local model folder.
+    Offline { missing: Vec<MissingModel> },
+    /// Reading, downloading or verifying a model failed.
+    Failed { message: String },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Offline { missing } => {
+                write!(f, "Offline mode is on and these model files are missing:")?;
+                for model in missing {
+                    write!(f, " {} (looked in {});", model.file, model.searched.join(", "))?;
+                }
+                Ok(())
+            }
+            ModelError::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<String> for ModelError {
+    fn from(message: String) -> Self {
+        ModelError::Failed { message }
+    }
+}
+
+/// A model file the manager knows how to fetch.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSpec {
+    pub kind: ModelKind,
+    pub file: String,
+    pub size_mb: u32,
+    #[serde(skip)]
+    url: String,
+    /// Checksum the download must have, for hosts that don't publish one.
+    #[serde(skip)]
+    sha256: Option<&'static str>,
+}
+
+/// ONNX models: kind, file, size in MB, URL and the SHA-256 the file is
+/// pinned to. GitHub doesn't publish checksums, so a download is only
+/// verified against a pinned one.
+// TODO: pin these once computed from known-good copies; until then the files
+// are kept unverified and listed as such.
+const ONNX_MODELS: &[(ModelKind, &str, u32, &str, Option<&str>)] = &[
+    (
+        ModelKind::Pyannote,
+        "segmentation-3.0.onnx",
+        6,
+        "https://github.com/mediar-ai/screenpipe/raw/main/screenpipe-audio/models/pyannote/segmentation-3.0.onnx",
+        None,
+    ),
+    (
+        ModelKind::Pyannote,
+        "wespeaker_en_voxceleb_CAM++.onnx",
+        28,
+        "https://github.com/mediar-ai/screenpipe/raw/main/screenpipe-audio/models/pyannote/wespeaker_en_voxceleb_CAM++.onnx",
+        None,
+    ),
+    (
+        ModelKind::Silero,
+        "silero_vad.onnx",
+        2,
+        "https://raw.githubusercontent.com/snakers4/silero-vad/master/src/silero_vad/data/silero_vad.onnx",
+        None,
+    ),
+];
+
+/// Every downloadable model: the whisper.cpp catalog plus the ONNX models.
+pub fn catalog() -> Vec<ModelSpec> {
+    let whisper = whisper_cpp::CATALOG.iter().map(|variant| ModelSpec {
+        kind: ModelKind::Whisper,
+        file: variant.file.to_string(),
+        size_mb: variant.size_mb,
+        url: variant.url(),
+        sha256: None,
+    });
+    let onnx = ONNX_MODELS.iter().map(|(kind, file, size_mb, url, sha256)| ModelSpec {
+        kind: *kind,
+        file: file.to_string(),
+        size_mb: *size_mb,
+        url: url.to_string(),
+        sha256: *sha256,
+    });
+    whisper.chain(onnx).collect()
+}
+
+fn spec(kind: ModelKind, file: &str) -> Result<ModelSpec, String> {
+    catalog()
+        .into_iter()
+        .find(|spec| spec.kind == kind && spec.file == file)
+        .ok_or_else(|| format!("{} is not a known {:?} model", file, kind))
+}
+
+fn models_root<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(MODELS_DIR))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// Folder `kind`'s models live in. Whisper models go to the whisper.cpp
+/// models folder when one is set, so the engine finds them.
+pub fn dir_for<R: Runtime>(app: &AppHandle<R>, kind: ModelKind) -> Result<PathBuf, String> {
+    if kind == ModelKind::Whisper {
+        if let Some(dir) = settings::get().whisper_cpp.models_dir {
+            return Ok(PathBuf::from(dir));
+        }
+    }
+    Ok(models_root(app)?.join(kind.folder()))
+}
+
+/// Folders `kind`'s model files are looked for in, the configured local model
+/// folder first.
+fn search_dirs(kind: ModelKind, managed: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(local) = settings::get().models.local_dir {
+        let local = PathBuf::from(local);
+        dirs.push(local.join(kind.folder()));
+        dirs.push(local);
+    }
+    dirs.extend(managed);
+    dirs
+}
+
+/// Find a model file on disk without touching the network. `managed` is the
+/// folder the kind is normally kept in (see [`dir_for`]).
+pub fn find_local(kind: ModelKind, file: &str, managed: Option<PathBuf>) -> Result<PathBuf, MissingModel> {
+    let dirs = search_dirs(kind, managed);
+    dirs.iter().map(|dir| dir.join(file)).find(|path| path.is_file()).ok_or_else(|| MissingModel {
+        kind,
+        file: file.to_string(),
+        searched: dirs.iter().map(|dir| dir.display().to_string()).collect(),
+    })
+}
+
+/// Every one of `models` on disk, or in offline mode an error listing all
+/// that are missing at once.
+pub fn require_local<R: Runtime>(app: &AppHandle<R>, models: &[(ModelKind, &str)]) -> Result<Vec<PathBuf>, ModelError> {
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    for (kind, file) in models {
+        match find_local(*kind, file, Some(dir_for(app, *kind)?)) {
+            Ok(path) => found.push(path),
+            Err(model) => missing.push(model),
+        }
+    }
+    if missing.is_empty() {
+        Ok(found)
+    } else {
+        Err(ModelError::Offline { missing })
+    }
+}
+
+/// Path of a model, downloading it first if it isn't on disk. In offline mode
+/// only the local folders are searched.
+pub async fn get_or_download_model<R: Runtime>(
+    app: &AppHandle<R>,
+    kind: ModelKind,
+    file: &str,
+) -> Result<PathBuf, ModelError> {
+    let dir = dir_for(app, kind)?;
+    let missing = match find_local(kind, file, Some(dir.clone())) {
+        Ok(path) => return Ok(path),
+        Err(missing) => missing,
+    };
+    if settings::get().models.offline_mode {
+        return Err(ModelError::Offline { missing: vec![missing] });
+    }
+    let spec = spec(kind, file)?;
+    let key = manifest_key(kind, file);
+    if !DOWNLOADING.lock().map_err(|e| e.to_string())?.insert(key.clone()) {
+        return Err(format!("{} is already downloading", file).into());
+    }
+    info!("Downloading {:?} model {} ({} MB)", kind, file, spec.size_mb);
+    let result = fetch(app, &spec, &dir).await;
+    if let Ok(mut downloading) = DOWNLOADING.lock() {
+        downloading.remove(&key);
+    }
+    Ok(result?)
+}
+
+/// Checksums recorded for downloaded files, keyed by `kind/file`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    sha256: String,
+    size_bytes: u64,
+    /// The checksum was published by the download server, rather than only
+    /// taken from the file as it arrived.
+    published: bool,
+    /// RFC 3339.
+    downloaded_at: String,
+}
+
+type Manifest = BTreeMap<String, ManifestEntry>;
+
+static MANIFEST_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+static DOWNLOADING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn manifest_key(kind: ModelKind, file: &str) -> String {
+    format!("{}/{}", kind.folder(), file)
+}
+
+fn load_manifest<R: Runtime>(app: &AppHandle<R>) -> Manifest {
+    models_root(app)
+        .ok()
+        .and_then(|root| fs::read_to_string(root.join(MANIFEST_FILE)).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn modify_manifest<R: Runtime>(app: &AppHandle<R>, change: impl FnOnce(&mut Manifest)) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().map_err(|e| e.to_string())?;
+    let root = models_root(app)?;
+    fs::create_dir_all(&root).map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+    let mut manifest = load_manifest(app);
+    change(&mut manifest);
+    let content = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(root.join(MANIFEST_FILE), content).map_err(|e| format!("Failed to write model manifest: {}", e))
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+async fn hash_file(path: PathBuf) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// The SHA-256 Hugging Face publishes for large files, as their linked ETag.
+/// Only its redirect to the CDN carries that header, so it's asked for
+/// without following redirects. Mirrors and other hosts don't publish one.
+async fn published_sha256(config: &ModelSettings, url: &str, token: Option<&str>) -> Option<String> {
+    let mut request = config.client_with(reqwest::redirect::Policy::none()).ok()?.head(url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.ok()?;
+    let etag = response.headers().get("x-linked-etag")?.to_str().ok()?;
+    let etag = etag.trim_start_matches("W/").trim_matches('"').to_lowercase();
+    (etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit())).then_some(etag)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgress {
+    kind: ModelKind,
+    file: String,
+    downloaded: u64,
+    total: Option<u64>,
+    /// `downloading`, `verifying` or `done`.
+    stage: &'static str,
+}
+
+fn report<R: Runtime>(app: &AppHandle<R>, spec: &ModelSpec, downloaded: u64, total: Option<u64>, stage: &'static str) {
+    let _ = app.emit(
+        DOWNLOAD_PROGRESS_EVENT,
+        DownloadProgress {
+            kind: spec.kind,
+            file: spec.file.clone(),
+            downloaded,
+            total,
+            stage,
+        },
+    );
+}
+
+/// Fetch `spec` into `dir`, continuing a partial download if one is there,
+/// and check it against its pinned checksum, or the one the server
+/// publishes. Without either the file is kept, but logged and listed as
+/// unverified.
+async fn fetch<R: Runtime>(app: &AppHandle<R>, spec: &ModelSpec, dir: &Path) -> Result<PathBuf, String> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(&spec.file);
+    let partial = path.with_file_name(format!("{}.{}", spec.file, PARTIAL_EXTENSION));
+    let resume_from = tokio::fs::metadata(&partial).await.map(|m| m.len()).unwrap_or(0);
+
+    let config = settings::get().models;
+    let (url, hugging_face) = config.resolve(&spec.url);
+    let token = if hugging_face { secrets::get(HF_TOKEN_SECRET)? } else { None };
+    let expected = match spec.sha256 {
+        Some(pinned) => Some(pinned.to_string()),
+        None if hugging_face => published_sha256(&config, &url, token.as_deref()).await,
+        None => None,
+    };
+    let mut request = config.client()?.get(&url);
+    if let Some(token) = &token {
+        request = request.bearer_auth(token);
+    }
+    if resume_from > 0 {
+        request = request.header(RANGE, format!("bytes={}-", resume_from));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", spec.file, e))?;
+    let (mut downloaded, mut file) = match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            info!("Resuming {} from {} bytes", spec.file, resume_from);
+            let file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&partial)
+                .await
+                .map_err(|e| format!("Failed to open {}: {}", partial.display(), e))?;
+            (resume_from, Some(file))
+        }
+        // The partial file already holds everything
+        StatusCode::RANGE_NOT_SATISFIABLE if resume_from > 0 => (resume_from, None),
+        status if status.is_success() => {
+            let file = tokio::fs::File::create(&partial)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+            (0, Some(file))
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN if hugging_face => {
+            return Err(format!(
+                "{} is gated on Hugging Face: accept its terms there and save an access token as the {} secret",
+                spec.file, HF_TOKEN_SECRET
+            ))
+        }
+        status => return Err(format!("Failed to download {} from {}: HTTP {}", spec.file, url, status)),
+    };
+    let total = match file {
+        Some(_) => response.content_length().map(|length| length + downloaded),
+        None => Some(downloaded),
+    };
+
+    if let Some(file) = file.as_mut() {
+        let mut reported = downloaded;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Download of {} interrupted, retry to resume: {}", spec.file, e))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+            downloaded += chunk.len() as u64;
+            if downloaded - reported >= PROGRESS_STEP_BYTES {
+                reported = downloaded;
+                report(app, spec, downloaded, total, "downloading");
+            }
+        }
+        file.flush().await.map_err(|e| e.to_string())?;
+    }
+    drop(file);
+    if total.is_some_and(|total| total != downloaded) {
+        return Err(format!("Download of {} stopped early, retry to resume", spec.file));
+    }
+
+    report(app, spec, downloaded, total, "verifying");
+    let sha256 = hash_file(partial.clone()).await?;
+    match &expected {
+        Some(expected) if *expected != sha256 => {
+            // A corrupt partial would fail the same way on every resume
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(format!(
+                "{} failed verification (SHA-256 {} instead of {}); download it again",
+                spec.file, sha256, expected
+            ));
+        }
+        Some(_) => {}
+        None => warn!(
+            "{} has no pinned or published checksum at {}; keeping it unverified (SHA-256 {})",
+            spec.file, url, sha256
+        ),
+    }
+    tokio::fs::rename(&partial, &path)
+        .await
+        .map_err(|e| format!("Failed to move {} into place: {}", path.display(), e))?;
+    modify_manifest(app, |manifest| {
+        manifest.insert(
+            manifest_key(spec.kind, &spec.file),
+            ManifestEntry {
+                sha256,
+                size_bytes: downloaded,
+                published: expected.is_some(),
+                downloaded_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+    })?;
+    report(app, spec, downloaded, Some(downloaded), "done");
+    Ok(path)
+}
+
+/// A model file on disk, or one that can be downloaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEntry {
+    pub kind: ModelKind,
+    pub file: String,
+    /// Download size from the catalog; `None` for files the catalog doesn't list.
+    pub size_mb: Option<u32>,
+    pub installed: bool,
+    pub size_bytes: u64,
+    /// Bytes of an interrupted download that the next download continues from.
+    pub partial_bytes: u64,
+    /// Checksum recorded when the file was downloaded.
+    pub sha256: Option<String>,
+    /// The download matched a checksum published by the server. Files from
+    /// mirrors and other hosts, or added by hand, aren't verified.
+    pub verified: bool,
+}
+
+/// Every catalog model plus any other model files in the model folders, with
+/// what's installed and how much space it takes.
+#[command]
+pub fn list_models<R: Runtime>(app: AppHandle<R>) -> Result<Vec<ModelEntry>, String> {
+    let manifest = load_manifest(&app);
+    let mut entries = Vec::new();
+    for kind in [ModelKind::Whisper, ModelKind::Pyannote, ModelKind::Silero] {
+        let dir = dir_for(&app, kind)?;
+        let size_of = |file: &str| fs::metadata(dir.join(file)).map(|m| m.len()).ok();
+        let known: Vec<ModelSpec> = catalog().into_iter().filter(|spec| spec.kind == kind).collect();
+        for spec in &known {
+            let size = find_local(kind, &spec.file, Some(dir.clone()))
+                .ok()
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|m| m.len());
+            entries.push(ModelEntry {
+                kind,
+                file: spec.file.clone(),
+                size_mb: Some(spec.size_mb),
+                installed: size.is_some(),
+                size_bytes: size.unwrap_or(0),
+                partial_bytes: size_of(&format!("{}.{}", spec.file, PARTIAL_EXTENSION)).unwrap_or(0),
+                sha256: manifest.get(&manifest_key(kind, &spec.file)).map(|e| e.sha256.clone()),
+                verified: manifest.get(&manifest_key(kind, &spec.file)).is_some_and(|e| e.published),
+            });
+        }
+        // Models added by hand
+        let Ok(files) = fs::read_dir(&dir) else { continue };
+        for file in files.flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+            let is_model = Path::new(&name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| ["bin", "gguf", "onnx"].contains(&e.to_lowercase().as_str()));
+            if is_model && !known.iter().any(|spec| spec.file == name) {
+                entries.push(ModelEntry {
+                    kind,
+                    size_mb: None,
+                    installed: true,
+                    size_bytes: file.metadata().map(|m| m.len()).unwrap_or(0),
+                    partial_bytes: 0,
+                    sha256: manifest.get(&manifest_key(kind, &name)).map(|e| e.sha256.clone()),
+                    verified: manifest.get(&manifest_key(kind, &name)).is_some_and(|e| e.published),
+                    file: name,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Download a catalog model, reporting progress on `model-download-progress`
+/// and continuing an earlier interrupted download. A model already in a local
+/// folder isn't downloaded again. A downloaded whisper model becomes the
+/// whisper.cpp model when none is selected yet.
+#[command]
+pub async fn download_model<R: Runtime>(
+    app: AppHandle<R>,
+    kind: ModelKind,
+    file: String,
+) -> Result<ModelEntry, ModelError> {
+    let spec = spec(kind, &file)?;
+    let path = get_or_download_model(&app, kind, &file).await?;
+
+    if kind == ModelKind::Whisper {
+        let dir = path.parent().map(|dir| dir.to_string_lossy().to_string());
+        settings::update(|s| {
+            if s.whisper_cpp.models_dir.is_none() {
+                s.whisper_cpp.models_dir = dir;
+            }
+            if s.whisper_cpp.model.is_none() {
+                s.whisper_cpp.model = Some(file.clone());
+            }
+        })?;
+    }
+    let recorded = load_manifest(&app).remove(&manifest_key(kind, &file));
+    Ok(ModelEntry {
+        kind,
+        size_mb: Some(spec.size_mb),
+        installed: true,
+        size_bytes: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        partial_bytes: 0,
+        verified: recorded.as_ref().is_some_and(|e| e.published),
+        sha256: recorded.map(|e| e.sha256),
+        file,
+    })
+}
+
+/// Check that every model the current configuration needs is on disk, so an
+/// air-gapped install can be verified before its first meeting.
+#[command]
+pub fn check_local_models<R: Runtime>(app: AppHandle<R>) -> Result<Vec<String>, ModelError> {
+    let config = settings::get();
+    let mut needed: Vec<(ModelKind, &str)> = Vec::new();
+    if let Some(model) = config.whisper_cpp.model.as_deref() {
+        needed.push((ModelKind::Whisper, model));
+    }
+    let paths = require_local(&app, &needed)?;
+    Ok(paths.iter().map(|path| path.to_string_lossy().to_string()).collect())
+}
+
+#[command]
+pub fn get_model_settings() -> ModelSettings {
+    settings::get().models
+}
+
+#[command]
+pub fn set_model_settings(model_settings: ModelSettings) -> Result<ModelSettings, String> {
+    if let Some(dir) = model_settings.local_dir.as_deref() {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("{} is not a folder", dir));
+        }
+    }
+    model_settings.client()?;
+    if let Some(mirror) = model_settings.mirror_url.as_deref().filter(|mirror| !mirror.trim().is_empty()) {
+        if !mirror.starts_with("https://") && !mirror.starts_with("http://") {
+            return Err(format!("Mirror {} must be an http(s) URL", mirror));
+        }
+    }
+    settings::update(|s| s.models = model_settings).map(|s| s.models)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    pub file: String,
+    pub sha256: String,
+    /// `None` when there's no recorded checksum to compare against.
+    pub matches: Option<bool>,
+    /// The recorded checksum came from the download server.
+    pub published: bool,
+}
+
+/// Model file names come from the webview; they must stay inside the folder.
+fn check_file_name(file: &str) -> Result<(), String> {
+    if file.contains(['/', '\\']) || file.starts_with('.') {
+        return Err(format!("Invalid model file name {}", file));
+    }
+    Ok(())
+}
+
+/// Re-hash an installed model and compare it with the checksum recorded when
+/// it was downloaded.
+#[command]
+pub async fn verify_model<R: Runtime>(app: AppHandle<R>, kind: ModelKind, file: String) -> Result<Verification, String> {
+    check_file_name(&file)?;
+    let path =
+        find_local(kind, &file, Some(dir_for(&app, kind)?)).map_err(|_| format!("{} is not installed", file))?;
+    let sha256 = hash_file(path).await?;
+    let recorded = load_manifest(&app).remove(&manifest_key(kind, &file));
+    let matches = recorded.as_ref().map(|entry| entry.sha256 == sha256);
+    if matches == Some(false) {
+        warn!("{:?} model {} no longer matches its recorded checksum", kind, file);
+    }
+    Ok(Verification {
+        file,
+        sha256,
+        matches,
+        published: recorded.is_some_and(|entry| entry.published),
+    })
+}
+
+/// Delete a model (and any partial download of it) to free disk space.
+/// Returns the bytes freed. The selected whisper.cpp model is deselected.
+#[command]
+pub fn delete_model<R: Runtime>(app: AppHandle<R>, kind: ModelKind, file: String) -> Result<u64, String> {
+    check_file_name(&file)?;
+    let key = manifest_key(kind, &file);
+    if DOWNLOADING.lock().map_err(|e| e.to_string())?.contains(&key) {
+        return Err(format!("{} is downloading", file));
+    }
+    let dir = dir_for(&app, kind)?;
+    let mut freed = 0;
+    for path in [dir.join(&file), dir.join(format!("{}.{}", file, PARTIAL_EXTENSION))] {
+        if let Ok(metadata) = fs::metadata(&path) {
+            fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+            freed += metadata.len();
+        }
+    }
+    modify_manifest(&app, |manifest| {
+        manifest.remove(&key);
+    })?;
+    if kind == ModelKind::Whisper && settings::get().whisper_cpp.model.as_deref() == Some(file.as_str()) {
+        settings::update(|s| s.whisper_cpp.model = None)?;
+    }
+    info!("Deleted {:?} model {}, freed {} bytes", kind, file, freed);
+    Ok(freed)
+}
@@ -0,0 +1,155 @@
+use std::sync::Mutex;
+
+use chrono::Utc;
+use log::info;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::speaker_profiles::{self, SpeakerProfile};
+use crate::{calendar, settings};
+
+/// Bias engines toward the spelling of the names of people expected in the
+/// meeting, so names like "Siobhan" and "Nguyen" come out right without
+/// maintaining vocabulary by hand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NameBiasSettings {
+    pub enabled: bool,
+    /// Attendees of the calendar event being recorded.
+    pub from_calendar: bool,
+    /// Everyone with an enrolled voice profile.
+    pub from_speaker_profiles: bool,
+    /// Prompts and keyword lists are limited in size; calendar attendees
+    /// come first.
+    pub max_names: usize,
+    /// Boost for engines that weight keywords, such as Deepgram.
+    pub keyword_boost: f32,
+}
+
+impl Default for NameBiasSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            from_calendar: true,
+            from_speaker_profiles: true,
+            max_names: 30,
+            keyword_boost: 1.5,
+        }
+    }
+}
+
+/// Names for the recording in progress.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionNames {
+    pub attendees: Vec<String>,
+    pub speakers: Vec<String>,
+}
+
+static SESSION: Lazy<Mutex<Option<SessionNames>>> = Lazy::new(|| Mutex::new(None));
+
+fn speaker_names(profiles: &[SpeakerProfile]) -> Vec<String> {
+    profiles
+        .iter()
+        .map(|profile| profile.name.trim().to_string())
+        .filter(|name| !name.is_empty() && !speaker_profiles::is_unnamed(name))
+        .collect()
+}
+
+/// Collect the names for a recording that's starting: the attendees of the
+/// event under way or about to start, and the enrolled speakers.
+pub fn begin_session<R: Runtime>(app: &AppHandle<R>) {
+    let config = settings::get();
+    let mut names = SessionNames::default();
+    if config.name_bias.from_calendar {
+        // Scheduled recordings start a little ahead of their event
+        let lead = chrono::Duration::seconds(config.calendar.lead_secs as i64);
+        let now = Utc::now();
+        if let Some(event) = calendar::event_at(now).or_else(|| calendar::event_at(now + lead)) {
+            names.attendees = event.attendees;
+        }
+    }
+    if config.name_bias.from_speaker_profiles {
+        names.speakers = speaker_names(&speaker_profiles::enrolled(app));
+    }
+    if config.name_bias.enabled {
+        info!(
+            "Biasing toward {} attendee and {} speaker names",
+            names.attendees.len(),
+            names.speakers.len()
+        );
+    }
+    if let Ok(mut session) = SESSION.lock() {
+        *session = Some(names);
+    }
+}
+
+/// Keep the speaker names current as voices are enrolled or renamed during
+/// the recording.
+pub fn observe_speakers(profiles: &[SpeakerProfile]) {
+    if !settings::get().name_bias.from_speaker_profiles {
+        return;
+    }
+    let speakers = speaker_names(profiles);
+    if let Ok(mut session) = SESSION.lock() {
+        if let Some(names) = session.as_mut().filter(|names| names.speakers != speakers) {
+            names.speakers = speakers;
+        }
+    }
+}
+
+pub fn end_session() {
+    if let Ok(mut session) = SESSION.lock() {
+        *session = None;
+    }
+}
+
+/// Full names to bias toward in the current recording, most relevant first.
+pub fn names() -> Vec<String> {
+    let config = settings::get().name_bias;
+    if !config.enabled {
+        return Vec::new();
+    }
+    let Some(session) = SESSION.lock().ok().and_then(|session| session.clone()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = Vec::new();
+    for name in session.attendees.into_iter().chain(session.speakers) {
+        if !names.iter().any(|kept| kept.eq_ignore_ascii_case(&name)) {
+            names.push(name);
+        }
+    }
+    names.truncate(config.max_names);
+    names
+}
+
+/// The words of [`names`], for engines that boost single words.
+pub fn words() -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in names().iter().flat_map(|name| name.split_whitespace()) {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        // Initials carry no spelling to learn
+        if word.chars().count() > 1 && !words.iter().any(|kept| kept == word) {
+            words.push(word.to_string());
+        }
+    }
+    words
+}
+
+#[command]
+pub fn get_session_names() -> Vec<String> {
+    names()
+}
+
+#[command]
+pub fn get_name_bias_settings() -> NameBiasSettings {
+    settings::get().name_bias
+}
+
+#[command]
+pub fn set_name_bias_settings(name_bias_settings: NameBiasSettings) -> Result<NameBiasSettings, String> {
+    if !(name_bias_settings.keyword_boost.is_finite() && name_bias_settings.keyword_boost > 0.0) {
+        return Err("Keyword boost must be above 0".to_string());
+    }
+    settings::update(|s| s.name_bias = name_bias_settings).map(|s| s.name_bias)
+}
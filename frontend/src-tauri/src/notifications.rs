@@ -0,0 +1,263 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::{debug, error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::broadcast;
+
+use crate::export::rules;
+use crate::governor::QualityLevel;
+use crate::pipeline::{self, PipelineEvent};
+use crate::settings;
+
+/// Warn once transcription falls this far behind capture.
+const FALL_BEHIND_THRESHOLD_MS: u64 = 45_000;
+/// Don't repeat the fall-behind warning more often than this.
+const FALL_BEHIND_COOLDOWN: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    MeetingDetected,
+    FallingBehind,
+    SummaryReady,
+    ActionItems,
+    ExportFailed,
+    MeetingEnding,
+}
+
+/// Per-category toggles for native notifications.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub meeting_detected: bool,
+    pub falling_behind: bool,
+    pub summary_ready: bool,
+    pub action_items: bool,
+    pub export_failed: bool,
+    pub meeting_ending: bool,
+    /// Suppress everything while the OS reports Do Not Disturb / Focus.
+    pub respect_do_not_disturb: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            meeting_detected: true,
+            falling_behind: true,
+            summary_ready: true,
+            action_items: true,
+            export_failed: true,
+            meeting_ending: true,
+            respect_do_not_disturb: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    fn is_enabled(&self, category: NotificationCategory) -> bool {
+        match category {
+            NotificationCategory::MeetingDetected => self.meeting_detected,
+            NotificationCategory::FallingBehind => self.falling_behind,
+            NotificationCategory::SummaryReady => self.summary_ready,
+            NotificationCategory::ActionItems => self.action_items,
+            NotificationCategory::ExportFailed => self.export_failed,
+            NotificationCategory::MeetingEnding => self.meeting_ending,
+        }
+    }
+}
+
+static LAST_FALL_BEHIND: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Best-effort check of the OS Do Not Disturb / Focus state.
+#[cfg(target_os = "macos")]
+fn do_not_disturb_active() -> bool {
+    // Focus modes write active assertions here; an empty record list means no Focus is on
+    let Some(home) = dirs::home_dir() else {
+        return false;
+    };
+    let path = home.join("Library/DoNotDisturb/DB/Assertions.json");
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return false;
+    };
+    json["data"]
+        .as_array()
+        .map(|data| {
+            data.iter().any(|entry| {
+                entry["storeAssertionRecords"]
+                    .as_array()
+                    .map(|records| !records.is_empty())
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn do_not_disturb_active() -> bool {
+    // GNOME turns banners off while Do Not Disturb is enabled
+    std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "false")
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn do_not_disturb_active() -> bool {
+    // Focus Assist state isn't exposed through a public API on Windows;
+    // the OS queues toasts itself while it is on.
+    false
+}
+
+/// Show a native notification if its category is enabled and DND allows it.
+pub fn notify<R: Runtime>(
+    app: &AppHandle<R>,
+    category: NotificationCategory,
+    title: &str,
+    body: &str,
+) {
+    let config = settings::get().notifications;
+    if !config.is_enabled(category) {
+        debug!("Notification category {:?} disabled, skipping", category);
+        return;
+    }
+    if config.respect_do_not_disturb && do_not_disturb_active() {
+        info!("Do Not Disturb active, suppressing {:?} notification", category);
+        return;
+    }
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        error!("Failed to show notification: {}", e);
+    }
+}
+
+fn check_fall_behind<R: Runtime>(app: &AppHandle<R>, lag_ms: u64) {
+    if lag_ms < FALL_BEHIND_THRESHOLD_MS {
+        return;
+    }
+    if let Ok(mut last) = LAST_FALL_BEHIND.lock() {
+        if last.map_or(false, |at| at.elapsed() < FALL_BEHIND_COOLDOWN) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    notify(
+        app,
+        NotificationCategory::FallingBehind,
+        "Transcription is falling behind",
+        &format!(
+            "Live transcript is {}s behind the recording. It will catch up, but captions may be delayed.",
+            lag_ms / 1000
+        ),
+    );
+}
+
+/// Translate pipeline events into notifications.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    let mut events = pipeline::subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(PipelineEvent::MeetingDetected { app_name }) => notify(
+                        &app,
+                        NotificationCategory::MeetingDetected,
+                        "Meeting detected",
+                        &format!("{} looks like it's in a meeting. Start recording?", app_name),
+                    ),
+                    Ok(PipelineEvent::ChunkTranscribed { latency_ms, .. }) => {
+                        check_fall_behind(&app, latency_ms)
+                    }
+                    Ok(PipelineEvent::QualityChanged { level, realtime_factor }) => {
+                        let (title, body) = if level == QualityLevel::Full {
+                            ("Transcription quality restored", "Live transcription has caught up and is back at full quality.".to_string())
+                        } else {
+                            (
+                                "Transcription quality reduced",
+                                format!(
+                                    "Transcription was running at {:.1}x real time, so quality was lowered to keep up. It will be restored when there is headroom.",
+                                    realtime_factor
+                                ),
+                            )
+                        };
+                        notify(&app, NotificationCategory::FallingBehind, title, &body)
+                    }
+                    Ok(PipelineEvent::Backpressure { queued, capacity, .. }) => notify(
+                        &app,
+                        NotificationCategory::FallingBehind,
+                        "Transcription is backing up",
+                        &format!("{} of {} queued chunks are waiting to be transcribed.", queued, capacity),
+                    ),
+                    Ok(PipelineEvent::SummaryReady { title, .. }) => notify(
+                        &app,
+                        NotificationCategory::SummaryReady,
+                        "Summary ready",
+                        &format!("The summary for \"{}\" is ready.", title),
+                    ),
+                    Ok(PipelineEvent::ActionItemsExtracted { title, count, .. }) if count > 0 => {
+                        notify(
+                            &app,
+                            NotificationCategory::ActionItems,
+                            "Action items extracted",
+                            &format!("{} action item(s) found in \"{}\".", count, title),
+                        )
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                // Catch a chunk that is still in flight long after it was queued
+                _ = ticker.tick() => check_fall_behind(&app, pipeline::status().queue_lag_ms),
+            }
+        }
+    });
+}
+
+#[command]
+pub fn get_notification_settings() -> NotificationSettings {
+    settings::get().notifications
+}
+
+#[command]
+pub fn set_notification_settings(
+    notification_settings: NotificationSettings,
+) -> Result<NotificationSettings, String> {
+    settings::update(|s| s.notifications = notification_settings).map(|s| s.notifications)
+}
+
+/// Summaries are generated outside this process; the frontend reports completion
+/// here so the notification and pipeline event still originate from the backend.
+/// Passing the summary text lets auto-export rules include it. Without a
+/// meeting id the summary belongs to the most recent recording.
+#[command]
+pub fn report_summary_ready<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: Option<String>,
+    title: String,
+    action_items: usize,
+    summary: Option<String>,
+) -> Result<(), String> {
+    let meeting_id = meeting_id.or_else(rules::last_meeting_id).unwrap_or_default();
+    pipeline::publish(PipelineEvent::SummaryReady {
+        meeting_id: meeting_id.clone(),
+        title: title.clone(),
+    });
+    pipeline::publish(PipelineEvent::ActionItemsExtracted {
+        meeting_id: meeting_id.clone(),
+        title: title.clone(),
+        count: action_items,
+    });
+    tauri::async_runtime::spawn(async move {
+        rules::summary_ready(&app, meeting_id, title, summary).await;
+    });
+    Ok(())
+}
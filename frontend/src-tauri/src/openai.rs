@@ -0,0 +1,185 @@
+use futures_util::future::BoxFuture;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::transcription::{encode_wav, AudioInput, Transcript, TranscriptionEngine, TranscriptionMode};
+use crate::{name_bias, secrets, settings, TranscriptSegment};
+
+pub const API_KEY_SECRET: &str = "openai-api-key";
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiSettings {
+    /// `whisper-1` returns timed segments; `gpt-4o-transcribe` and
+    /// `gpt-4o-mini-transcribe` return one block of text per chunk.
+    pub model: String,
+    /// ISO-639-1 code; detected automatically when `None`.
+    pub language: Option<String>,
+    /// For OpenAI-compatible servers and proxies.
+    pub base_url: String,
+}
+
+impl Default for OpenAiSettings {
+    fn default() -> Self {
+        Self {
+            model: "whisper-1".to_string(),
+            language: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+        }
+    }
+}
+
+/// Only this model translates.
+const TRANSLATION_MODEL: &str = "whisper-1";
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+    #[serde(default)]
+    segments: Vec<ResponseSegment>,
+    /// Spoken language, by name (e.g. `"german"`), in `verbose_json` responses.
+    #[serde(default)]
+    language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseSegment {
+    start: f32,
+    end: f32,
+    text: String,
+}
+
+/// OpenAI's hosted transcription endpoint, one upload per chunk.
+#[derive(Default)]
+pub struct OpenAiEngine {
+    client: reqwest::Client,
+}
+
+impl OpenAiEngine {
+    pub const ID: &'static str = "openai";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn transcribe_chunk(&self, audio: &AudioInput) -> Result<Transcript, String> {
+        let config = settings::get().openai;
+        let api_key = secrets::require(API_KEY_SECRET)?;
+        let translate = settings::get().transcription.mode == TranscriptionMode::Translate;
+        let model = if translate {
+            TRANSLATION_MODEL.to_string()
+        } else {
+            config.model.clone()
+        };
+        // Only whisper-1 can return segment timings
+        let timed = model == "whisper-1";
+
+        let file = Part::bytes(encode_wav(audio)?)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| format!("Failed to build upload: {}", e))?;
+        let mut form = Form::new()
+            .part("file", file)
+            .text("model", model)
+            .text("response_format", if timed { "verbose_json" } else { "json" });
+        // Translations always come out in English; the source language is detected
+        if let Some(language) = audio.language.clone().or(config.language).filter(|_| !translate) {
+            form = form.text("language", language);
+        }
+        let names = name_bias::names();
+        if !names.is_empty() {
+            form = form.text("prompt", names.join(", "));
+        }
+
+        let endpoint = if translate { "translations" } else { "transcriptions" };
+        let url = format!("{}/audio/{}", config.base_url.trim_end_matches('/'), endpoint);
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("OpenAI request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("OpenAI returned {}: {}", status, body));
+        }
+        let parsed: TranscriptionResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid OpenAI response: {}", e))?;
+
+        let translated_from = parsed
+            .language
+            .clone()
+            .filter(|language| translate && !language.eq_ignore_ascii_case("english"));
+        let mut segments: Vec<TranscriptSegment> = parsed
+            .segments
+            .into_iter()
+            .map(|s| TranscriptSegment {
+                text: s.text,
+                t0: s.start,
+                t1: s.end,
+                speaker: None,
+                no_speech_prob: None,
+                avg_logprob: None,
+                language: None,
+                translated_from: translated_from.clone(),
+            })
+            .collect();
+        if segments.is_empty() && !parsed.text.trim().is_empty() {
+            let duration = audio.samples.len() as f32 / audio.sample_rate.max(1) as f32;
+            segments.push(TranscriptSegment {
+                text: parsed.text,
+                t0: 0.0,
+                t1: duration,
+                speaker: None,
+                no_speech_prob: None,
+                avg_logprob: None,
+                language: None,
+                translated_from,
+            });
+        }
+        Ok(Transcript {
+            segments,
+            buffer_size_ms: 0,
+            engine: Self::ID.to_string(),
+        })
+    }
+}
+
+impl TranscriptionEngine for OpenAiEngine {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn name(&self) -> &str {
+        "OpenAI"
+    }
+
+    fn is_cloud(&self) -> bool {
+        true
+    }
+
+    fn health_url(&self) -> Option<String> {
+        Some(settings::get().openai.base_url.trim_end_matches('/').to_string())
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>> {
+        Box::pin(self.transcribe_chunk(audio))
+    }
+}
+
+#[command]
+pub fn get_openai_settings() -> OpenAiSettings {
+    settings::get().openai
+}
+
+#[command]
+pub fn set_openai_settings(openai_settings: OpenAiSettings) -> Result<OpenAiSettings, String> {
+    settings::update(|s| s.openai = openai_settings).map(|s| s.openai)
+}
@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+use crate::transcript_stream::speaker_of;
+use crate::TranscriptUpdate;
+
+/// A paragraph this long is closed even if the same speaker keeps talking.
+const MAX_PARAGRAPH_CHARS: usize = 1200;
+
+/// Consecutive transcript lines from one speaker, read as a single block.
+#[derive(Debug, Clone, Serialize)]
+pub struct Paragraph {
+    pub speaker: String,
+    /// Range from the first line's start to the last line's end, in the
+    /// same `"start - end"` form as the lines themselves.
+    pub timestamp: String,
+    pub text: String,
+    /// How many transcript lines were joined.
+    pub lines: usize,
+}
+
+fn bounds(timestamp: &str) -> (&str, &str) {
+    match timestamp.split_once(" - ") {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => (timestamp.trim(), timestamp.trim()),
+    }
+}
+
+/// Group `updates` into speaker turns.
+pub fn paragraphs(updates: &[TranscriptUpdate]) -> Vec<Paragraph> {
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    let mut start = "";
+    for update in updates {
+        let text = update.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let speaker = speaker_of(update);
+        let (line_start, line_end) = bounds(&update.timestamp);
+        match paragraphs.last_mut() {
+            Some(last) if last.speaker == speaker && last.text.len() + text.len() < MAX_PARAGRAPH_CHARS => {
+                last.text.push(' ');
+                last.text.push_str(text);
+                last.timestamp = format!("{} - {}", start, line_end);
+                last.lines += 1;
+            }
+            _ => {
+                start = line_start;
+                paragraphs.push(Paragraph {
+                    speaker,
+                    timestamp: update.timestamp.clone(),
+                    text: text.to_string(),
+                    lines: 1,
+                });
+            }
+        }
+    }
+    paragraphs
+}
@@ -0,0 +1,174 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+use log::{debug, error};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use schemars::JsonSchema;
+use tauri::{command, AppHandle, Runtime};
+use tokio::sync::broadcast;
+
+use crate::events;
+use crate::governor::QualityLevel;
+use crate::inference::OverflowPolicy;
+use crate::transcription::TranscriptionError;
+
+/// Events published by the recording/transcription pipeline itself, as opposed
+/// to state tracked by the webview. Backend consumers (tray, notifications)
+/// subscribe to these; they are also forwarded to the frontend as `pipeline-event`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PipelineEvent {
+    RecordingStarted { engine: String },
+    RecordingStopped,
+    /// A stopped recording's queued audio is transcribed and its transcript
+    /// flushed; nothing more will be added to `meeting_id`.
+    SessionComplete { meeting_id: Option<String> },
+    Paused,
+    Resumed,
+    ChunkQueued { samples: usize },
+    ChunkTranscribed { latency_ms: u64, segments: usize },
+    TranscriptionFailed { error: String, kind: TranscriptionError },
+    TranscriptSaved { path: String },
+    MeetingDetected { app_name: String },
+    SummaryReady { meeting_id: String, title: String },
+    ActionItemsExtracted { meeting_id: String, title: String, count: usize },
+    QualityChanged { level: QualityLevel, realtime_factor: f64 },
+    /// Chunks go to `to` instead of `from`: `online` is false when a cloud
+    /// engine became unreachable and true once it's back.
+    EngineSwitched { from: String, to: String, online: bool },
+    /// This month's spending on paid engines reached the budget; chunks go
+    /// to the local engine until the month ends or the budget is raised.
+    BudgetExceeded { month: String, spent: f64, budget: f64 },
+    /// Chunks are waiting for transcription faster than it keeps up:
+    /// `queued` of `capacity` slots are taken.
+    Backpressure { queued: usize, capacity: usize, policy: OverflowPolicy },
+}
+
+/// Point-in-time view of the pipeline derived from the events above.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PipelineStatus {
+    pub recording: bool,
+    pub paused: bool,
+    pub elapsed_ms: u64,
+    pub engine: Option<String>,
+    /// Age of the oldest chunk handed to the engine that has not come back yet.
+    pub queue_lag_ms: u64,
+    pub last_transcript_path: Option<String>,
+}
+
+#[derive(Default)]
+struct PipelineState {
+    started_at: Option<Instant>,
+    paused: bool,
+    engine: Option<String>,
+    pending_since: Option<Instant>,
+    last_transcript_path: Option<String>,
+}
+
+static EVENTS: Lazy<broadcast::Sender<PipelineEvent>> = Lazy::new(|| broadcast::channel(256).0);
+static STATE: Lazy<Mutex<PipelineState>> = Lazy::new(|| Mutex::new(PipelineState::default()));
+
+/// Record `event` in the pipeline state and broadcast it to subscribers.
+pub fn publish(event: PipelineEvent) {
+    if let Ok(mut state) = STATE.lock() {
+        match &event {
+            PipelineEvent::RecordingStarted { engine } => {
+                state.started_at = Some(Instant::now());
+                state.paused = false;
+                state.engine = Some(engine.clone());
+                state.pending_since = None;
+            }
+            PipelineEvent::RecordingStopped => {
+                state.started_at = None;
+                state.paused = false;
+                state.pending_since = None;
+            }
+            PipelineEvent::Paused => state.paused = true,
+            PipelineEvent::Resumed => state.paused = false,
+            PipelineEvent::ChunkQueued { .. } => {
+                if state.pending_since.is_none() {
+                    state.pending_since = Some(Instant::now());
+                }
+            }
+            PipelineEvent::ChunkTranscribed { .. } | PipelineEvent::TranscriptionFailed { .. } => {
+                state.pending_since = None;
+            }
+            PipelineEvent::TranscriptSaved { path } => {
+                state.last_transcript_path = Some(path.clone());
+            }
+            PipelineEvent::EngineSwitched { to, .. } => {
+                if state.started_at.is_some() {
+                    state.engine = Some(to.clone());
+                }
+            }
+            PipelineEvent::SessionComplete { .. }
+            | PipelineEvent::MeetingDetected { .. }
+            | PipelineEvent::SummaryReady { .. }
+            | PipelineEvent::ActionItemsExtracted { .. }
+            | PipelineEvent::QualityChanged { .. }
+            | PipelineEvent::BudgetExceeded { .. }
+            | PipelineEvent::Backpressure { .. } => {}
+        }
+    }
+
+    debug!("Pipeline event: {:?}", event);
+    // No receivers is fine; nobody may be listening yet
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<PipelineEvent> {
+    EVENTS.subscribe()
+}
+
+/// Events still queued for the slowest subscriber.
+pub fn queued_events() -> usize {
+    EVENTS.len()
+}
+
+pub fn status() -> PipelineStatus {
+    let state = match STATE.lock() {
+        Ok(state) => state,
+        Err(_) => return PipelineStatus::default(),
+    };
+    PipelineStatus {
+        recording: state.started_at.is_some(),
+        paused: state.paused,
+        elapsed_ms: state
+            .started_at
+            .map(|start| start.elapsed().as_millis() as u64)
+            .unwrap_or(0),
+        engine: state.engine.clone(),
+        queue_lag_ms: state
+            .pending_since
+            .map(|since| since.elapsed().as_millis() as u64)
+            .unwrap_or(0),
+        last_transcript_path: state.last_transcript_path.clone(),
+    }
+}
+
+/// Forward pipeline events to the webview.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    let mut receiver = subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Err(e) = events::emit(&app, events::PIPELINE_EVENT, event) {
+                        error!("Failed to emit pipeline event: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Pipeline event forwarder lagged, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[command]
+pub fn get_pipeline_status() -> PipelineStatus {
+    status()
+}
@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio::app_activity::{AudioSource, SourceMap};
+use crate::audio::metering::compute_levels;
+use crate::audio::{default_input_device, default_output_device, AudioStream};
+use crate::{is_recording, resample_audio, settings};
+
+const MAX_SECONDS: u32 = 120;
+/// How often the buffer is started or stopped to follow the settings and
+/// the recording state.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Mixed pre-roll is attributed to its louder input per block of this length.
+const SOURCE_BLOCK_MS: usize = 100;
+
+/// Keep the last few seconds of the default microphone and system audio in
+/// memory while not recording, so a recording started late still transcribes
+/// the start of the meeting. Nothing is written to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrerollSettings {
+    pub enabled: bool,
+    pub seconds: u32,
+}
+
+impl Default for PrerollSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seconds: 30,
+        }
+    }
+}
+
+/// The last `capacity` samples of one device.
+struct Ring {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+    capacity: usize,
+}
+
+impl Ring {
+    fn new(sample_rate: u32, seconds: u32) -> Self {
+        let capacity = sample_rate as usize * seconds as usize;
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            sample_rate,
+            capacity,
+        }
+    }
+
+    fn extend(&mut self, samples: &[f32]) {
+        self.samples.extend(samples);
+        let excess = self.samples.len().saturating_sub(self.capacity);
+        self.samples.drain(..excess);
+    }
+
+    fn at_rate(&self, sample_rate: u32) -> Vec<f32> {
+        let samples: Vec<f32> = self.samples.iter().copied().collect();
+        resample_audio(&samples, self.sample_rate, sample_rate)
+    }
+}
+
+#[derive(Clone)]
+struct Capture {
+    mic: Arc<AudioStream>,
+    system: Arc<AudioStream>,
+    is_running: Arc<AtomicBool>,
+    mic_ring: Arc<Mutex<Ring>>,
+    system_ring: Arc<Mutex<Ring>>,
+}
+
+static CAPTURE: Lazy<Mutex<Option<Capture>>> = Lazy::new(|| Mutex::new(None));
+
+async fn buffer_into(stream: &AudioStream, ring: Arc<Mutex<Ring>>, is_running: Arc<AtomicBool>) {
+    let mut receiver = stream.subscribe().await;
+    tokio::spawn(async move {
+        while is_running.load(Ordering::SeqCst) {
+            match tokio::time::timeout(POLL_INTERVAL, receiver.recv()).await {
+                Ok(Ok(samples)) => {
+                    if let Ok(mut ring) = ring.lock() {
+                        ring.extend(&samples);
+                    }
+                }
+                Ok(Err(RecvError::Closed)) => break,
+                Ok(Err(RecvError::Lagged(_))) | Err(_) => continue,
+            }
+        }
+    });
+}
+
+async fn start(seconds: u32) -> Result<(), String> {
+    let mic_device = Arc::new(default_input_device().map_err(|e| e.to_string())?);
+    let system_device = Arc::new(default_output_device().map_err(|e| e.to_string())?);
+    let is_running = Arc::new(AtomicBool::new(true));
+    let mic = AudioStream::from_device(mic_device, is_running.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    let system = match AudioStream::from_device(system_device, is_running.clone()).await {
+        Ok(system) => system,
+        Err(e) => {
+            // Nothing else holds the mic stream yet
+            is_running.store(false, Ordering::SeqCst);
+            if let Err(e) = mic.stop().await {
+                warn!("Failed to stop pre-roll stream {}: {}", mic.device, e);
+            }
+            return Err(e.to_string());
+        }
+    };
+    let mic_ring = Arc::new(Mutex::new(Ring::new(mic.device_config.sample_rate().0, seconds)));
+    let system_ring = Arc::new(Mutex::new(Ring::new(system.device_config.sample_rate().0, seconds)));
+    buffer_into(&mic, mic_ring.clone(), is_running.clone()).await;
+    buffer_into(&system, system_ring.clone(), is_running.clone()).await;
+    info!("Pre-roll buffering the last {} s of {} and {}", seconds, mic.device, system.device);
+
+    let capture = Capture {
+        mic: Arc::new(mic),
+        system: Arc::new(system),
+        is_running,
+        mic_ring,
+        system_ring,
+    };
+    let previous = CAPTURE.lock().map_err(|e| e.to_string())?.replace(capture);
+    if let Some(previous) = previous {
+        stop(&previous).await;
+    }
+    Ok(())
+}
+
+async fn stop(capture: &Capture) {
+    capture.is_running.store(false, Ordering::SeqCst);
+    for stream in [&capture.mic, &capture.system] {
+        if let Err(e) = stream.stop().await {
+            warn!("Failed to stop pre-roll stream {}: {}", stream.device, e);
+        }
+    }
+}
+
+/// Stop buffering and return the buffered audio mixed like a recording, at
+/// `sample_rate`, with the input that dominated each block of it.
+pub async fn take(sample_rate: u32) -> Option<(Vec<f32>, SourceMap)> {
+    let capture = CAPTURE.lock().ok()?.take()?;
+    stop(&capture).await;
+    let mic = capture.mic_ring.lock().ok()?.at_rate(sample_rate);
+    let system = capture.system_ring.lock().ok()?.at_rate(sample_rate);
+
+    // Both buffers end now; align them at the end
+    let len = mic.len().max(system.len());
+    if len == 0 {
+        return None;
+    }
+    let padded = |samples: Vec<f32>| {
+        let mut out = vec![0.0; len - samples.len()];
+        out.extend(samples);
+        out
+    };
+    let (mic, system) = (padded(mic), padded(system));
+    let block = (sample_rate as usize * SOURCE_BLOCK_MS / 1000).max(1);
+    let mut sources = SourceMap::default();
+    for (mic_block, system_block) in mic.chunks(block).zip(system.chunks(block)) {
+        let (mic_rms, _) = compute_levels(mic_block);
+        let (system_rms, _) = compute_levels(system_block);
+        let source = if system_rms * 0.3 > mic_rms * 0.7 {
+            AudioSource::System
+        } else {
+            AudioSource::Microphone
+        };
+        sources.push(mic_block.len(), source);
+    }
+    let mixed = mic.iter().zip(&system).map(|(m, s)| m * 0.7 + s * 0.3).collect();
+    info!("Prepending {:.1} s of pre-roll", len as f32 / sample_rate as f32);
+    Some((mixed, sources))
+}
+
+/// Buffer while enabled and not recording.
+pub fn init() {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut seconds = 0;
+        loop {
+            interval.tick().await;
+            let config = settings::get().preroll;
+            let running = CAPTURE.lock().map(|capture| capture.is_some()).unwrap_or(false);
+            let wanted = config.enabled && !is_recording();
+            if running && (!wanted || config.seconds != seconds) {
+                let capture = CAPTURE.lock().ok().and_then(|mut capture| capture.take());
+                if let Some(capture) = capture {
+                    stop(&capture).await;
+                }
+            } else if !running && wanted {
+                seconds = config.seconds;
+                if let Err(e) = start(seconds).await {
+                    error!("Failed to start pre-roll buffer: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[command]
+pub fn get_preroll_settings() -> PrerollSettings {
+    settings::get().preroll
+}
+
+#[command]
+pub fn set_preroll_settings(preroll_settings: PrerollSettings) -> Result<PrerollSettings, String> {
+    if preroll_settings.seconds == 0 || preroll_settings.seconds > MAX_SECONDS {
+        return Err(format!("Pre-roll must be between 1 and {} seconds", MAX_SECONDS));
+    }
+    settings::update(|s| s.preroll = preroll_settings).map(|s| s.preroll)
+}
@@ -0,0 +1,165 @@
+use std::sync::Mutex;
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::diarization::DiarizationQuality;
+use crate::{recluster, storage, TranscriptSegment, WHISPER_SAMPLE_RATE};
+
+pub const QUALITY_FILE: &str = "meeting_quality.json";
+/// Energy frames for the SNR estimate.
+const FRAME_MS: usize = 20;
+/// A chunk's quietest frames are its noise and its loudest its speech.
+const NOISE_PERCENTILE: f32 = 0.1;
+const SPEECH_PERCENTILE: f32 = 0.9;
+/// SNR mapped onto the score: speech this far above the noise is unusable...
+const POOR_SNR_DB: f32 = 5.0;
+/// ...and this far above is as clean as it gets.
+const CLEAN_SNR_DB: f32 = 30.0;
+/// Below this overall score, the transcript should be checked against the recording.
+const REVIEW_SCORE: f32 = 0.6;
+
+/// How good a recorded meeting's audio and transcript are, stored with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingQuality {
+    /// 0.0 (unusable) to 1.0 (clean audio, confident transcript and speakers),
+    /// averaged over the measures below that were available.
+    pub score: f32,
+    /// Median signal-to-noise ratio of the chunks.
+    pub snr_db: Option<f32>,
+    /// Mean token probability of the transcript, from engines that report it.
+    pub transcription_confidence: Option<f32>,
+    /// The diarization quality score.
+    pub diarization_confidence: Option<f32>,
+    pub chunks: usize,
+    pub segments: usize,
+    pub needs_review: bool,
+}
+
+#[derive(Default)]
+struct QualityTracker {
+    snr_db: Vec<f32>,
+    /// Per segment, its token probability and length in seconds.
+    confidences: Vec<(f32, f32)>,
+    segments: usize,
+}
+
+static TRACKER: Lazy<Mutex<QualityTracker>> = Lazy::new(|| Mutex::new(QualityTracker::default()));
+
+pub fn reset() {
+    if let Ok(mut tracker) = TRACKER.lock() {
+        *tracker = QualityTracker::default();
+    }
+}
+
+fn percentile(sorted: &[f32], share: f32) -> f32 {
+    sorted[((sorted.len() - 1) as f32 * share).round() as usize]
+}
+
+/// Signal-to-noise ratio of a chunk, from the spread of its frame energies;
+/// `None` when it is too short or silent to tell.
+pub fn snr_db(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let frame = (sample_rate as usize * FRAME_MS / 1000).max(1);
+    let mut energies: Vec<f32> = samples
+        .chunks_exact(frame)
+        .map(|chunk| chunk.iter().map(|s| s * s).sum::<f32>() / frame as f32)
+        .collect();
+    if energies.len() < 10 {
+        return None;
+    }
+    energies.sort_by(f32::total_cmp);
+    let noise = percentile(&energies, NOISE_PERCENTILE).max(1e-10);
+    let speech = percentile(&energies, SPEECH_PERCENTILE);
+    (speech > 1e-8).then(|| (10.0 * (speech / noise).log10()).min(60.0))
+}
+
+/// Measure a chunk of 16 kHz audio sent for transcription.
+pub fn observe_audio(samples: &[f32]) {
+    let Some(snr) = snr_db(samples, WHISPER_SAMPLE_RATE) else {
+        return;
+    };
+    if let Ok(mut tracker) = TRACKER.lock() {
+        tracker.snr_db.push(snr);
+    }
+}
+
+/// Record how sure the engine was of the segments it transcribed.
+pub fn observe_segments(segments: &[TranscriptSegment]) {
+    let Ok(mut tracker) = TRACKER.lock() else {
+        return;
+    };
+    for segment in segments.iter().filter(|segment| !segment.text.trim().is_empty()) {
+        tracker.segments += 1;
+        if let Some(logprob) = segment.avg_logprob {
+            let secs = (segment.t1 - segment.t0).max(0.1);
+            tracker.confidences.push((logprob.exp().clamp(0.0, 1.0), secs));
+        }
+    }
+}
+
+fn summarize(tracker: &QualityTracker, diarization: Option<&DiarizationQuality>) -> Option<MeetingQuality> {
+    if tracker.snr_db.is_empty() && tracker.segments == 0 {
+        return None;
+    }
+    let snr_db = (!tracker.snr_db.is_empty()).then(|| {
+        let mut sorted = tracker.snr_db.clone();
+        sorted.sort_by(f32::total_cmp);
+        percentile(&sorted, 0.5)
+    });
+    let total_secs: f32 = tracker.confidences.iter().map(|(_, secs)| secs).sum();
+    let transcription_confidence = (total_secs > 0.0).then(|| {
+        tracker.confidences.iter().map(|(confidence, secs)| confidence * secs).sum::<f32>() / total_secs
+    });
+    let diarization_confidence = diarization.map(|quality| quality.score);
+
+    let measures: Vec<f32> = [
+        snr_db.map(|snr| ((snr - POOR_SNR_DB) / (CLEAN_SNR_DB - POOR_SNR_DB)).clamp(0.0, 1.0)),
+        transcription_confidence,
+        diarization_confidence,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let score = if measures.is_empty() {
+        0.0
+    } else {
+        measures.iter().sum::<f32>() / measures.len() as f32
+    };
+    Some(MeetingQuality {
+        score,
+        snr_db,
+        transcription_confidence,
+        diarization_confidence,
+        chunks: tracker.snr_db.len(),
+        segments: tracker.segments,
+        needs_review: score < REVIEW_SCORE,
+    })
+}
+
+/// Score the session that just ended, store it with the meeting and tell the UI.
+pub fn finish<R: Runtime>(app: &AppHandle<R>, diarization: Option<&DiarizationQuality>) -> Option<MeetingQuality> {
+    let quality = TRACKER.lock().ok().and_then(|tracker| summarize(&tracker, diarization))?;
+    info!(
+        "Meeting quality {:.2} (SNR {:?} dB, transcription {:?}, diarization {:?})",
+        quality.score, quality.snr_db, quality.transcription_confidence, quality.diarization_confidence
+    );
+
+    if let Some(meeting_id) = storage::current_session() {
+        let stored = serde_json::to_string_pretty(&quality)
+            .map_err(|e| e.to_string())
+            .and_then(|content| storage::save_derived(app.clone(), meeting_id, QUALITY_FILE.to_string(), content));
+        if let Err(e) = stored {
+            warn!("Failed to store meeting quality: {}", e);
+        }
+    }
+    let _ = app.emit("meeting-quality", &quality);
+    Some(quality)
+}
+
+#[command]
+pub fn get_meeting_quality<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<Option<MeetingQuality>, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    Ok(recluster::read_derived(&dir, QUALITY_FILE))
+}
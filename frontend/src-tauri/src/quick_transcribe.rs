@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use log::{error, info};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::audio::decode::decode_audio_file;
+use crate::{is_recording, resample_audio, send_audio_chunk, CHUNK_DURATION_MS, WHISPER_SAMPLE_RATE};
+
+/// Sent with the result of a quick transcription started from the tray.
+pub const QUICK_TRANSCRIPT_EVENT: &str = "quick-transcript";
+/// Longer files belong in a meeting import, not the quick path.
+const MAX_DURATION_SECS: f32 = 600.0;
+pub const AUDIO_EXTENSIONS: [&str; 8] = ["wav", "mp3", "m4a", "aac", "ogg", "opus", "flac", "mp4"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickTranscript {
+    pub path: String,
+    pub text: String,
+    pub duration_secs: f32,
+    pub copied_to_clipboard: bool,
+}
+
+/// Transcribe a short file (voicemail, voice note) and return its text. Nothing
+/// is stored and no session is created; with `copy_to_clipboard` the text is
+/// also put on the clipboard.
+#[command]
+pub async fn quick_transcribe<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    copy_to_clipboard: Option<bool>,
+) -> Result<QuickTranscript, String> {
+    // The whisper server keeps one rolling buffer, which a recording is using
+    if is_recording() {
+        return Err("Quick transcription isn't available while recording".to_string());
+    }
+    let file = PathBuf::from(&path);
+    let samples = tokio::task::spawn_blocking(move || {
+        let (samples, rate) = decode_audio_file(&file).map_err(|e| format!("Failed to decode {:?}: {}", file, e))?;
+        Ok::<_, String>(resample_audio(&samples, rate, WHISPER_SAMPLE_RATE))
+    })
+    .await
+    .map_err(|e| format!("Decode task failed: {}", e))??;
+    let duration_secs = samples.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+    if duration_secs > MAX_DURATION_SECS {
+        return Err(format!(
+            "{:.0} s is too long for quick transcription (limit {:.0} s); import it as a meeting instead",
+            duration_secs, MAX_DURATION_SECS
+        ));
+    }
+
+    let chunk_len = (WHISPER_SAMPLE_RATE as u64 * CHUNK_DURATION_MS as u64 / 1000) as usize;
+    let mut parts = Vec::new();
+    for chunk in samples.chunks(chunk_len) {
+        let response = send_audio_chunk(chunk.to_vec()).await?;
+        parts.extend(response.segments.into_iter().filter_map(|segment| {
+            let text = segment
+                .text
+                .replace("[BLANK_AUDIO]", "")
+                .replace("[AUDIO OUT]", "")
+                .trim()
+                .to_string();
+            (!text.is_empty()).then_some(text)
+        }));
+    }
+    let text = parts.join(" ");
+
+    let copied_to_clipboard = copy_to_clipboard.unwrap_or(false) && !text.is_empty();
+    if copied_to_clipboard {
+        app.clipboard()
+            .write_text(text.clone())
+            .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+    }
+    info!("Quick-transcribed {} ({:.1} s)", path, duration_secs);
+    Ok(QuickTranscript {
+        path,
+        text,
+        duration_secs,
+        copied_to_clipboard,
+    })
+}
+
+/// Tray action: pick an audio file, transcribe it and copy the text. The
+/// result is also sent as `QUICK_TRANSCRIPT_EVENT`.
+pub fn pick_and_transcribe<R: Runtime>(app: &AppHandle<R>) {
+    let handle = app.clone();
+    app.dialog()
+        .file()
+        .add_filter("Audio", &AUDIO_EXTENSIONS)
+        .pick_file(move |picked| {
+            let Some(path) = picked.and_then(|picked| picked.into_path().ok()) else {
+                return;
+            };
+            tauri::async_runtime::spawn(async move {
+                match quick_transcribe(handle.clone(), path.to_string_lossy().to_string(), Some(true)).await {
+                    Ok(transcript) => {
+                        let _ = handle.emit(QUICK_TRANSCRIPT_EVENT, transcript);
+                    }
+                    Err(e) => error!("Quick transcription failed: {}", e),
+                }
+            });
+        });
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use crate::audio::metering::AudioLevel;
+use crate::audio::permissions::{self, PermissionState};
+use crate::audio::{default_input_device, AudioStream};
+use crate::transcription::{self, WhisperEngine};
+use crate::{calibration, compute_device, connectivity, settings, whisper_cpp};
+
+/// How long the microphone is listened to for its level.
+const LEVEL_SAMPLE: Duration = Duration::from_millis(1500);
+/// Even a quiet room is louder than this through a working microphone.
+const MUTED_DB: f32 = -70.0;
+/// Peaks this close to full scale are clipping.
+const CLIPPING_PEAK: f32 = 0.99;
+/// Free space needed to record; an hour of meeting audio and its transcripts
+/// take a few hundred megabytes.
+const MIN_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessCheckKind {
+    Permissions,
+    InputDevice,
+    OutputDevice,
+    InputLevel,
+    Model,
+    DiskSpace,
+    Engine,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessCheck {
+    pub kind: ReadinessCheckKind,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub checks: Vec<ReadinessCheck>,
+    pub ready: bool,
+}
+
+fn check(kind: ReadinessCheckKind, result: Result<String, String>) -> ReadinessCheck {
+    match result {
+        Ok(detail) => ReadinessCheck { kind, ok: true, detail },
+        Err(detail) => ReadinessCheck { kind, ok: false, detail },
+    }
+}
+
+/// Listen to the default microphone briefly: it should pick up the room
+/// without clipping.
+async fn input_level() -> Result<String, String> {
+    let device = Arc::new(default_input_device().map_err(|e| e.to_string())?);
+    let is_running = Arc::new(AtomicBool::new(true));
+    let stream = AudioStream::from_device(device.clone(), is_running.clone())
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", device, e))?;
+    let samples = calibration::collect(stream.subscribe().await, tokio::time::Instant::now() + LEVEL_SAMPLE).await;
+    is_running.store(false, Ordering::SeqCst);
+    if let Err(e) = stream.stop().await {
+        warn!("Failed to stop readiness stream for {}: {}", device, e);
+    }
+
+    if samples.is_empty() {
+        return Err(format!("No audio from {}", device));
+    }
+    let level = AudioLevel::from_samples(&device.to_string(), &samples);
+    if level.rms_db < MUTED_DB {
+        return Err(format!("{} is silent ({:.0} dB); is it muted?", device, level.rms_db));
+    }
+    if level.peak >= CLIPPING_PEAK {
+        return Err(format!("{} is clipping; lower its input gain", device));
+    }
+    Ok(format!("{} at {:.0} dB, peaking at {:.0} dB", device, level.rms_db, level.peak_db))
+}
+
+/// The active engine's model is loaded, for engines that run locally.
+async fn model() -> Result<String, String> {
+    let engine = transcription::active();
+    match engine.id() {
+        _ if engine.is_cloud() => Ok(format!("{} runs in the cloud", engine.name())),
+        WhisperEngine::ID => compute_device::get_compute_device()
+            .await
+            .map(|device| format!("{} loaded on {:?}", device.model, device.backend)),
+        whisper_cpp::WhisperCppEngine::ID => settings::get()
+            .whisper_cpp
+            .model_path()
+            .map(|path| format!("{} is available", path.display())),
+        _ => Ok(format!("{} needs no model", engine.name())),
+    }
+}
+
+/// Reachability of the active engine's API, and whether chunks go elsewhere.
+async fn engine() -> Result<String, String> {
+    let engine = transcription::active();
+    if !engine.is_cloud() {
+        return Ok(format!("{} runs locally", engine.name()));
+    }
+    if !connectivity::reachable(&reqwest::Client::new(), engine.as_ref()).await {
+        return Err(format!("{} can't be reached", engine.name()));
+    }
+    if connectivity::offline() {
+        return Err(format!("{} is reachable, but chunks still go to the local engine", engine.name()));
+    }
+    Ok(format!("{} is reachable", engine.name()))
+}
+
+fn disk_space(dir: &Path) -> Result<String, String> {
+    // The data directory may not exist yet before the first recording
+    let existing = dir.ancestors().find(|path| path.exists()).unwrap_or(dir);
+    let free = fs2::available_space(existing).map_err(|e| format!("Failed to check free space: {}", e))?;
+    let detail = format!("{:.1} GB free", free as f64 / 1024f64.powi(3));
+    if free < MIN_FREE_BYTES {
+        return Err(format!("Only {}", detail));
+    }
+    Ok(detail)
+}
+
+/// Everything that has to work before joining a meeting: permissions,
+/// devices, microphone level, the model, disk space and the engine.
+#[command]
+pub async fn check_readiness<R: Runtime>(app: AppHandle<R>) -> Result<ReadinessReport, String> {
+    let preflight = permissions::audio_preflight().await?;
+    let missing: Vec<String> = preflight
+        .permissions
+        .iter()
+        .filter(|p| !matches!(p.state, PermissionState::Granted | PermissionState::Unknown))
+        .map(|p| format!("{:?}", p.kind))
+        .collect();
+    let device_result = |access: &permissions::DeviceAccessCheck| match (access.ok, &access.device) {
+        (true, device) => Ok(device.clone().unwrap_or_default()),
+        (false, _) => Err(access.reason.clone().unwrap_or_else(|| "Not available".to_string())),
+    };
+
+    let mut checks = vec![
+        check(
+            ReadinessCheckKind::Permissions,
+            match missing.is_empty() {
+                true => Ok("Granted".to_string()),
+                false => Err(format!("Not granted: {}", missing.join(", "))),
+            },
+        ),
+        check(ReadinessCheckKind::InputDevice, device_result(&preflight.input)),
+        check(ReadinessCheckKind::OutputDevice, device_result(&preflight.output)),
+    ];
+    // The level can only be measured from a microphone that opens
+    if preflight.input.ok {
+        checks.push(check(ReadinessCheckKind::InputLevel, input_level().await));
+    }
+    checks.push(check(ReadinessCheckKind::Model, model().await));
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    checks.push(check(ReadinessCheckKind::DiskSpace, disk_space(&data_dir)));
+    checks.push(check(ReadinessCheckKind::Engine, engine().await));
+
+    let ready = checks.iter().all(|check| check.ok);
+    if !ready {
+        warn!("Readiness check failed: {:?}", checks.iter().filter(|check| !check.ok).collect::<Vec<_>>());
+    }
+    Ok(ReadinessReport { checks, ready })
+}
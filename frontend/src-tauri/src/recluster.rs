@@ -0,0 +1,442 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::audio::app_activity::AudioSource;
+use crate::audio::embedding::{self, similarity};
+use crate::replay::{self, ReplayMode, ReplayRecord};
+use crate::storage::{self, DERIVED_DIR, TRANSCRIPT_FILE};
+use crate::{encryption, retry_queue, segment_span, speaker_profiles};
+
+pub const CONSTRAINTS_FILE: &str = "speaker_constraints.json";
+pub const ASSIGNMENTS_FILE: &str = "speaker_assignments.json";
+/// Without an expected speaker count, clusters closer than this are merged.
+const MERGE_SIMILARITY: f32 = 0.9;
+/// Agglomerative clustering compares every pair of clusters for each merge,
+/// so longer meetings are first pre-grouped down to about this many.
+const MAX_AGGLOMERATIVE_CLUSTERS: usize = 400;
+
+/// Corrections to respect when re-clustering a meeting's speakers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeakerConstraints {
+    /// Stop merging once this many speakers remain.
+    pub expected_speakers: Option<usize>,
+    /// Current speaker label to the name it should carry from now on.
+    pub renames: BTreeMap<String, String>,
+    /// Groups of labels that are the same person; their segments always end
+    /// up in one cluster.
+    pub merges: Vec<Vec<String>>,
+}
+
+/// Which speaker one engine segment was attributed to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentAssignment {
+    pub chunk: u64,
+    pub segment: usize,
+    pub speaker: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReclusterResult {
+    pub meeting_id: String,
+    pub segments: usize,
+    pub speakers: Vec<String>,
+    /// Segments whose speaker differs from the previous attribution.
+    pub reassigned: usize,
+    pub archived_to: Option<String>,
+}
+
+pub fn read_derived<T: for<'de> Deserialize<'de>>(dir: &Path, name: &str) -> Option<T> {
+    encryption::read_to_string(dir.join(DERIVED_DIR).join(name))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// Map a previous label through the renames and onto its merge group's first name.
+pub fn canonical(label: &str, constraints: &SpeakerConstraints) -> String {
+    let rename = |l: &str| constraints.renames.get(l).cloned().unwrap_or_else(|| l.to_string());
+    let renamed = rename(label);
+    constraints
+        .merges
+        .iter()
+        .find(|group| group.iter().any(|member| member == label || rename(member) == renamed))
+        .and_then(|group| group.first())
+        .map(|first| rename(first))
+        .unwrap_or(renamed)
+}
+
+struct Cluster {
+    members: Vec<usize>,
+    centroid: Vec<f32>,
+}
+
+fn centroid(members: &[usize], embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let mut sum = vec![0.0f32; embeddings[members[0]].len()];
+    for &member in members {
+        for (total, value) in sum.iter_mut().zip(&embeddings[member]) {
+            *total += value;
+        }
+    }
+    let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+    sum.iter_mut().for_each(|v| *v /= norm);
+    sum
+}
+
+/// Fold each cluster into the most similar one kept so far when they're
+/// close enough, comparing against the kept clusters only rather than every
+/// pair. The threshold is lowered until at most `limit` remain.
+fn pre_group(mut clusters: Vec<Cluster>, embeddings: &[Vec<f32>], limit: usize) -> Vec<Cluster> {
+    let mut threshold = MERGE_SIMILARITY;
+    while clusters.len() > limit && threshold > -1.0 {
+        // Running member sums, so a merge doesn't re-add every member
+        let mut kept: Vec<(Cluster, Vec<f32>)> = Vec::new();
+        for cluster in clusters {
+            let nearest = kept
+                .iter()
+                .enumerate()
+                .map(|(index, (other, _))| (index, similarity(&cluster.centroid, &other.centroid)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            match nearest {
+                Some((index, score)) if score >= threshold => {
+                    let (target, sum) = &mut kept[index];
+                    for &member in &cluster.members {
+                        sum.iter_mut().zip(&embeddings[member]).for_each(|(total, value)| *total += value);
+                    }
+                    let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+                    target.centroid = sum.iter().map(|v| v / norm).collect();
+                    target.members.extend(cluster.members);
+                }
+                _ => {
+                    let mut sum = vec![0.0f32; cluster.centroid.len()];
+                    for &member in &cluster.members {
+                        sum.iter_mut().zip(&embeddings[member]).for_each(|(total, value)| *total += value);
+                    }
+                    kept.push((cluster, sum));
+                }
+            }
+        }
+        clusters = kept.into_iter().map(|(cluster, _)| cluster).collect();
+        threshold -= 0.05;
+    }
+    clusters
+}
+
+/// Agglomerative clustering by centroid similarity, starting from `seeds`
+/// (groups that must stay together) plus a singleton for every other segment.
+/// CPU-bound on long meetings; run it off the async runtime.
+fn cluster(embeddings: &[Vec<f32>], seeds: Vec<Vec<usize>>, expected: Option<usize>) -> Vec<Vec<usize>> {
+    let clusters: Vec<Cluster> = seeds
+        .into_iter()
+        .filter(|members| !members.is_empty())
+        .map(|members| Cluster {
+            centroid: centroid(&members, embeddings),
+            members,
+        })
+        .collect();
+    let limit = MAX_AGGLOMERATIVE_CLUSTERS.max(expected.unwrap_or(1));
+    let mut clusters = pre_group(clusters, embeddings, limit);
+    let target = expected.unwrap_or(1).max(1);
+
+    while clusters.len() > target {
+        let mut best: Option<(usize, usize, f32)> = None;
+        for i in 0..clusters.len() {
+            for j in i + 1..clusters.len() {
+                let score = similarity(&clusters[i].centroid, &clusters[j].centroid);
+                let better = match best {
+                    Some((_, _, s)) => score > s,
+                    None => true,
+                };
+                if better {
+                    best = Some((i, j, score));
+                }
+            }
+        }
+        let Some((i, j, score)) = best else { break };
+        if expected.is_none() && score < MERGE_SIMILARITY {
+            break;
+        }
+        let merged = clusters.swap_remove(j);
+        clusters[i].members.extend(merged.members);
+        clusters[i].centroid = centroid(&clusters[i].members, embeddings);
+    }
+    clusters.into_iter().map(|c| c.members).collect()
+}
+
+/// One engine segment of a raw capture with its voice embedding, `None` when
+/// the segment was too short to embed.
+pub struct EmbeddedSegment {
+    pub chunk: u64,
+    pub segment: usize,
+    pub duration_secs: f32,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Embed every engine segment of a raw capture from the chunk audio it came
+/// from, in capture order.
+pub async fn embed_segments(raw: &Path) -> Result<Vec<EmbeddedSegment>, String> {
+    let entries = replay::read_replay(raw)?;
+    let sample_rate = match entries.first() {
+        Some((ReplayRecord::Header { config, .. }, _)) => config.transcription_sample_rate,
+        _ => return Err("Raw capture has no header".to_string()),
+    };
+    let mut audio: HashMap<u64, Vec<f32>> = HashMap::new();
+    let mut pending = Vec::new();
+    for (record, samples) in entries {
+        match record {
+            ReplayRecord::Chunk { index, .. } => {
+                audio.insert(index, samples.unwrap_or_default());
+            }
+            ReplayRecord::Response { index, segments, .. } => {
+                // Each chunk gets one response, so its audio moves to the blocking
+                // pool and chunks are embedded in parallel while the rest is read
+                let chunk = audio.remove(&index).unwrap_or_default();
+                let spans: Vec<(usize, usize)> = segments
+                    .iter()
+                    .map(|segment| {
+                        let (from, to) = segment_span(segment, sample_rate);
+                        let to = to.min(chunk.len());
+                        (from.min(to), to)
+                    })
+                    .collect();
+                pending.push((
+                    index,
+                    tokio::task::spawn_blocking(move || {
+                        spans
+                            .iter()
+                            .map(|(from, to)| {
+                                let duration_secs = (to - from) as f32 / sample_rate as f32;
+                                (duration_secs, embedding::voice_embedding(&chunk[*from..*to], sample_rate))
+                            })
+                            .collect::<Vec<_>>()
+                    }),
+                ));
+            }
+            _ => {}
+        }
+    }
+    let mut embedded = Vec::new();
+    for (index, task) in pending {
+        let chunk_embeddings = task.await.map_err(|e| format!("Embedding failed: {}", e))?;
+        for (position, (duration_secs, embedding)) in chunk_embeddings.into_iter().enumerate() {
+            embedded.push(EmbeddedSegment {
+                chunk: index,
+                segment: position,
+                duration_secs,
+                embedding,
+            });
+        }
+    }
+    Ok(embedded)
+}
+
+/// Re-run speaker assignment over a stored meeting's voice embeddings using
+/// its saved constraints, and rewrite the derived transcript's attributions.
+/// The previous derived version is archived to its history first.
+#[command]
+pub async fn recluster_meeting<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<ReclusterResult, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    let raw = storage::raw_session_path(&dir);
+    if !raw.exists() {
+        return Err(format!("Meeting {} has no raw capture to re-cluster", meeting_id));
+    }
+    let constraints: SpeakerConstraints = read_derived(&dir, CONSTRAINTS_FILE).unwrap_or_default();
+    let previous: HashMap<(u64, usize), String> = read_derived::<Vec<SegmentAssignment>>(&dir, ASSIGNMENTS_FILE)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| ((a.chunk, a.segment), canonical(&a.speaker, &constraints)))
+        .collect();
+
+    let mut keys: Vec<(u64, usize)> = Vec::new();
+    let mut embeddings: Vec<Vec<f32>> = Vec::new();
+    let mut embedded: Vec<Option<usize>> = Vec::new();
+    for segment in embed_segments(&raw).await? {
+        keys.push((segment.chunk, segment.segment));
+        embedded.push(segment.embedding.map(|e| {
+            embeddings.push(e);
+            embeddings.len() - 1
+        }));
+    }
+    if embeddings.is_empty() {
+        return Err(format!("Meeting {} has no speech long enough to cluster", meeting_id));
+    }
+
+    // Segments that already share a merged speaker start out together
+    let merged: Vec<String> = constraints
+        .merges
+        .iter()
+        .filter_map(|group| group.first().map(|first| canonical(first, &constraints)))
+        .collect();
+    let mut seeds: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut singles = Vec::new();
+    for (key, slot) in keys.iter().zip(&embedded) {
+        let Some(index) = *slot else { continue };
+        match previous.get(key).filter(|speaker| merged.contains(speaker)) {
+            Some(speaker) => seeds.entry(speaker.clone()).or_default().push(index),
+            None => singles.push(vec![index]),
+        }
+    }
+    let seeds: Vec<Vec<usize>> = seeds.into_values().chain(singles).collect();
+    let expected = constraints.expected_speakers;
+    let (embeddings, clusters) = tokio::task::spawn_blocking(move || {
+        let clusters = cluster(&embeddings, seeds, expected);
+        (embeddings, clusters)
+    })
+    .await
+    .map_err(|e| format!("Clustering failed: {}", e))?;
+
+    // Keep the names users gave: each cluster takes its most common previous
+    // speaker, largest clusters first; anything left is numbered.
+    let mut label_of_embedding = vec![0usize; embeddings.len()];
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    order.sort_by_key(|&c| std::cmp::Reverse(clusters[c].len()));
+    let mut names: Vec<Option<String>> = vec![None; clusters.len()];
+    for &c in &order {
+        let mut votes: HashMap<&String, usize> = HashMap::new();
+        for (key, slot) in keys.iter().zip(&embedded) {
+            if slot.is_some_and(|index| clusters[c].contains(&index)) {
+                if let Some(speaker) = previous.get(key) {
+                    *votes.entry(speaker).or_default() += 1;
+                }
+            }
+        }
+        let mut ranked: Vec<(&String, usize)> = votes.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let name = ranked
+            .into_iter()
+            .map(|(speaker, _)| speaker.clone())
+            .find(|speaker| !names.contains(&Some(speaker.clone())));
+        names[c] = name;
+        for &index in &clusters[c] {
+            label_of_embedding[index] = c;
+        }
+    }
+    // Voices remembered from earlier meetings keep their names
+    for &c in &order {
+        if names[c].is_some() {
+            continue;
+        }
+        if let Some((name, score)) = speaker_profiles::identify(&app, &centroid(&clusters[c], &embeddings)) {
+            if !names.contains(&Some(name.clone())) {
+                info!("Matched a speaker in meeting {} to profile {} ({:.2})", meeting_id, name, score);
+                names[c] = Some(name);
+            }
+        }
+    }
+    let mut next_number = 1;
+    let mut first_seen: Vec<usize> = Vec::new();
+    for slot in embedded.iter().flatten() {
+        let c = label_of_embedding[*slot];
+        if !first_seen.contains(&c) {
+            first_seen.push(c);
+        }
+    }
+    for c in first_seen {
+        if names[c].is_none() {
+            loop {
+                let candidate = format!("Speaker {}", next_number);
+                next_number += 1;
+                if !names.contains(&Some(candidate.clone())) {
+                    names[c] = Some(candidate);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Too-short segments follow the speaker before them
+    let mut assignments = Vec::with_capacity(keys.len());
+    let mut last: Option<String> = None;
+    for (key, slot) in keys.iter().zip(&embedded) {
+        let speaker = slot
+            .and_then(|index| names[label_of_embedding[index]].clone())
+            .or_else(|| last.clone())
+            .or_else(|| names.iter().flatten().next().cloned())
+            .unwrap_or_else(|| "Speaker 1".to_string());
+        last = Some(speaker.clone());
+        assignments.push(SegmentAssignment {
+            chunk: key.0,
+            segment: key.1,
+            speaker,
+        });
+    }
+    let reassigned = assignments
+        .iter()
+        .filter(|a| previous.get(&(a.chunk, a.segment)) != Some(&a.speaker))
+        .count();
+
+    let lookup: HashMap<(u64, usize), String> = assignments
+        .iter()
+        .map(|a| ((a.chunk, a.segment), a.speaker.clone()))
+        .collect();
+    let mut result = replay::run_replay_with(
+        &raw,
+        ReplayMode::Recorded,
+        |chunk, segment, _| lookup.get(&(chunk, segment)).cloned().map(AudioSource::Speaker),
+        |_| {},
+    )
+    .await?;
+    // The raw capture still holds the original failures
+    retry_queue::add_recovered(&app, &meeting_id, &mut result.updates)?;
+
+    let voices: Vec<(String, Vec<f32>, usize)> = clusters
+        .iter()
+        .zip(&names)
+        .filter_map(|(members, name)| Some((name.clone()?, centroid(members, &embeddings), members.len())))
+        .collect();
+    if let Err(e) = speaker_profiles::learn(&app, &voices) {
+        warn!("Failed to update speaker profiles: {}", e);
+    }
+
+    let mut speakers: Vec<String> = names.into_iter().flatten().collect();
+    speakers.sort();
+    let assignments_json = serde_json::to_string_pretty(&assignments)
+        .map_err(|e| format!("Failed to serialize speaker assignments: {}", e))?;
+    let archived_to = storage::replace_derived(
+        &dir,
+        &[
+            (TRANSCRIPT_FILE, storage::transcript_json(&result.updates)?),
+            (ASSIGNMENTS_FILE, assignments_json),
+        ],
+    )?;
+
+    info!(
+        "Re-clustered meeting {}: {} segments across {} speakers, {} reassigned",
+        meeting_id,
+        assignments.len(),
+        speakers.len(),
+        reassigned
+    );
+    Ok(ReclusterResult {
+        meeting_id,
+        segments: assignments.len(),
+        speakers,
+        reassigned,
+        archived_to,
+    })
+}
+
+#[command]
+pub fn get_speaker_constraints<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<SpeakerConstraints, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    Ok(read_derived(&dir, CONSTRAINTS_FILE).unwrap_or_default())
+}
+
+/// Save corrections for `recluster_meeting` to apply.
+#[command]
+pub fn set_speaker_constraints<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    constraints: SpeakerConstraints,
+) -> Result<SpeakerConstraints, String> {
+    if constraints.expected_speakers == Some(0) {
+        return Err("Expected speaker count must be at least 1".to_string());
+    }
+    let content = serde_json::to_string_pretty(&constraints)
+        .map_err(|e| format!("Failed to serialize speaker constraints: {}", e))?;
+    storage::save_derived(app, meeting_id, CONSTRAINTS_FILE.to_string(), content)?;
+    Ok(constraints)
+}
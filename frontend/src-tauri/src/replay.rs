@@ -0,0 +1,426 @@
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+use crate::audio::app_activity::AudioSource;
+use crate::{encryption, send_audio_chunk, settings, TranscriptAccumulator, TranscriptSegment, TranscriptUpdate};
+
+const REPLAY_MAGIC: &str = "MEETILY-REPLAY";
+const REPLAY_VERSION: u32 = 1;
+pub const REPLAY_EXTENSION: &str = "mreplay";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplaySettings {
+    /// Write every session's transcription inputs to a replay file.
+    pub capture_sessions: bool,
+    /// Where replay files go; defaults to `<app data>/replays`.
+    pub directory: Option<String>,
+}
+
+/// Session configuration captured at the start of a replay file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    pub app_version: String,
+    pub engine: String,
+    pub mic_device: String,
+    pub system_device: String,
+    pub capture_sample_rate: u32,
+    pub transcription_sample_rate: u32,
+    pub chunk_duration_ms: u32,
+    /// Model of the engine, for engines that offer a choice.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// One line of a replay file. `Chunk` lines are immediately followed by
+/// `samples` little-endian f32 values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayRecord {
+    Header {
+        version: u32,
+        created_at: String,
+        config: ReplayConfig,
+    },
+    Chunk {
+        index: u64,
+        offset_ms: u64,
+        samples: usize,
+    },
+    Response {
+        index: u64,
+        latency_ms: u64,
+        segments: Vec<TranscriptSegment>,
+    },
+    Failure {
+        index: u64,
+        error: String,
+    },
+    /// The accumulator emitted an incomplete sentence on timeout.
+    Flush { offset_ms: u64 },
+}
+
+/// How to source transcription results when replaying.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayMode {
+    /// Use the recorded engine responses; fully deterministic.
+    #[default]
+    Recorded,
+    /// Re-send the recorded audio to the engine.
+    Retranscribe,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayResult {
+    pub config: ReplayConfig,
+    pub chunks: u64,
+    pub failures: u64,
+    pub updates: Vec<TranscriptUpdate>,
+}
+
+/// Appends a session's transcription inputs to a replay file as they happen,
+/// encrypted when storage encryption is on.
+pub struct ReplayRecorder {
+    writer: Box<dyn Write + Send>,
+    path: PathBuf,
+    started: Instant,
+    next_index: u64,
+}
+
+impl ReplayRecorder {
+    pub fn create(path: PathBuf, config: ReplayConfig) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create replay directory: {}", e))?;
+        }
+        let writer = encryption::create(&path).map_err(|e| format!("Failed to create replay file: {}", e))?;
+        let mut recorder = Self {
+            writer,
+            path,
+            started: Instant::now(),
+            next_index: 0,
+        };
+        writeln!(recorder.writer, "{}", REPLAY_MAGIC).map_err(|e| e.to_string())?;
+        recorder.write_record(&ReplayRecord::Header {
+            version: REPLAY_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            config,
+        })?;
+        info!("Recording replay to {:?}", recorder.path);
+        Ok(recorder)
+    }
+
+    fn offset_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write_record(&mut self, record: &ReplayRecord) -> Result<(), String> {
+        let line = serde_json::to_string(record)
+            .map_err(|e| format!("Failed to serialize replay record: {}", e))?;
+        writeln!(self.writer, "{}", line).map_err(|e| format!("Failed to write replay record: {}", e))
+    }
+
+    /// Record a chunk about to be sent for transcription and return its index.
+    pub fn record_chunk(&mut self, samples: &[f32]) -> Result<u64, String> {
+        let index = self.next_index;
+        self.next_index += 1;
+        self.write_record(&ReplayRecord::Chunk {
+            index,
+            offset_ms: self.offset_ms(),
+            samples: samples.len(),
+        })?;
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        self.writer
+            .write_all(&bytes)
+            .map_err(|e| format!("Failed to write replay audio: {}", e))?;
+        Ok(index)
+    }
+
+    pub fn record_response(&mut self, index: u64, latency_ms: u64, segments: &[TranscriptSegment]) -> Result<(), String> {
+        self.write_record(&ReplayRecord::Response {
+            index,
+            latency_ms,
+            segments: segments.to_vec(),
+        })
+    }
+
+    pub fn record_failure(&mut self, index: u64, error: &str) -> Result<(), String> {
+        self.write_record(&ReplayRecord::Failure {
+            index,
+            error: error.to_string(),
+        })
+    }
+
+    pub fn record_flush(&mut self) -> Result<(), String> {
+        let offset_ms = self.offset_ms();
+        self.write_record(&ReplayRecord::Flush { offset_ms })
+    }
+
+    pub fn finish(mut self) -> Result<PathBuf, String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("Failed to flush replay file: {}", e))?;
+        info!("Replay saved to {:?}", self.path);
+        Ok(self.path)
+    }
+}
+
+/// Start a replay file for a new session if capture is enabled in settings.
+pub fn start_session_recorder<R: Runtime>(app: &AppHandle<R>, config: ReplayConfig) -> Option<ReplayRecorder> {
+    let replay_settings = settings::get().replay;
+    if !replay_settings.capture_sessions {
+        return None;
+    }
+    let directory = match replay_settings.directory {
+        Some(directory) => PathBuf::from(directory),
+        None => match app.path().app_data_dir() {
+            Ok(dir) => dir.join("replays"),
+            Err(e) => {
+                error!("Failed to resolve app data directory for replays: {}", e);
+                return None;
+            }
+        },
+    };
+    let file_name = format!(
+        "session-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        REPLAY_EXTENSION
+    );
+    match ReplayRecorder::create(directory.join(file_name), config) {
+        Ok(recorder) => Some(recorder),
+        Err(e) => {
+            warn!("Replay capture disabled for this session: {}", e);
+            None
+        }
+    }
+}
+
+/// A parsed record plus the audio that follows `Chunk` records.
+pub type ReplayEntry = (ReplayRecord, Option<Vec<f32>>);
+
+pub fn read_replay(path: &Path) -> Result<Vec<ReplayEntry>, String> {
+    let file = encryption::open(path).map_err(|e| format!("Failed to open replay file: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    if line.trim_end() != REPLAY_MAGIC {
+        return Err(format!("{:?} is not a replay file", path));
+    }
+
+    let mut entries = Vec::new();
+    loop {
+        line.clear();
+        let read = match reader.read_line(&mut line) {
+            Ok(read) => read,
+            // A session that crashed never sealed its final frame
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                warn!("Replay file is cut short: {}", e);
+                break;
+            }
+            Err(e) => return Err(format!("Failed to read replay file: {}", e)),
+        };
+        if read == 0 {
+            break;
+        }
+        let record: ReplayRecord = match serde_json::from_str(line.trim_end()) {
+            Ok(record) => record,
+            Err(e) => {
+                // A session that crashed mid-write leaves a truncated final record
+                warn!("Stopping at malformed replay record: {}", e);
+                break;
+            }
+        };
+        let audio = match &record {
+            ReplayRecord::Chunk { samples, .. } => {
+                let mut bytes = vec![0u8; samples * 4];
+                if reader.read_exact(&mut bytes).is_err() {
+                    warn!("Replay file ends inside an audio chunk");
+                    break;
+                }
+                Some(
+                    bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                )
+            }
+            _ => None,
+        };
+        entries.push((record, audio));
+    }
+    Ok(entries)
+}
+
+/// The configuration a replay file was recorded with, without reading its audio.
+pub fn read_header(path: &Path) -> Result<ReplayConfig, String> {
+    let file = encryption::open(path).map_err(|e| format!("Failed to open replay file: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut next = || lines.next().and_then(|line| line.ok()).unwrap_or_default();
+    if next().trim_end() != REPLAY_MAGIC {
+        return Err(format!("{:?} is not a replay file", path));
+    }
+    match serde_json::from_str(next().trim_end()) {
+        Ok(ReplayRecord::Header { config, .. }) => Ok(config),
+        _ => Err(format!("{:?} has no replay header", path)),
+    }
+}
+
+/// The session audio stored in a replay file, concatenated at the
+/// transcription sample rate.
+pub fn read_replay_audio(path: &Path) -> Result<(Vec<f32>, u32), String> {
+    let entries = read_replay(path)?;
+    let sample_rate = match entries.first() {
+        Some((ReplayRecord::Header { config, .. }, _)) => config.transcription_sample_rate,
+        _ => return Err("Replay file has no header".to_string()),
+    };
+    let samples = entries
+        .into_iter()
+        .filter_map(|(_, audio)| audio)
+        .flatten()
+        .collect();
+    Ok((samples, sample_rate))
+}
+
+/// Re-run transcript accumulation for a recorded session in recorded order.
+/// `Recorded` mode reuses the captured engine responses and timeout flushes,
+/// so the output is identical on every run.
+#[command]
+pub async fn replay_session<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    mode: Option<ReplayMode>,
+) -> Result<ReplayResult, String> {
+    run_replay(Path::new(&path), mode.unwrap_or_default(), |update| {
+        if let Err(e) = app.emit("replay-transcript-update", update) {
+            error!("Failed to emit replay transcript update: {}", e);
+        }
+    })
+    .await
+}
+
+/// Replay `path`, calling `on_update` for each transcript update as it is produced.
+pub async fn run_replay<F>(path: &Path, mode: ReplayMode, on_update: F) -> Result<ReplayResult, String>
+where
+    F: FnMut(&TranscriptUpdate),
+{
+    // Keep speaker labels from engines that diarize
+    let engine_speaker = |_: u64, _: usize, segment: &TranscriptSegment| {
+        segment
+            .speaker
+            .as_ref()
+            .map(|label| AudioSource::Speaker(format!("Speaker {}", label)))
+    };
+    run_replay_with(path, mode, engine_speaker, on_update).await
+}
+
+/// Like [`run_replay`], but `assign(chunk_index, segment_index, segment)`
+/// picks the source each segment is attributed to.
+pub async fn run_replay_with<A, F>(
+    path: &Path,
+    mode: ReplayMode,
+    mut assign: A,
+    mut on_update: F,
+) -> Result<ReplayResult, String>
+where
+    A: FnMut(u64, usize, &TranscriptSegment) -> Option<AudioSource>,
+    F: FnMut(&TranscriptUpdate),
+{
+    let entries = read_replay(path)?;
+    info!("Replaying {} records from {:?} in {:?} mode", entries.len(), path, mode);
+
+    let config = match entries.first() {
+        Some((ReplayRecord::Header { version, config, .. }, _)) => {
+            if *version > REPLAY_VERSION {
+                return Err(format!("Replay file version {} is not supported", version));
+            }
+            config.clone()
+        }
+        _ => return Err("Replay file has no header".to_string()),
+    };
+
+    let mut accumulator = TranscriptAccumulator::new();
+    let mut result = ReplayResult {
+        config,
+        chunks: 0,
+        failures: 0,
+        updates: Vec::new(),
+    };
+
+    let mut emit = |update: TranscriptUpdate, result: &mut ReplayResult| {
+        on_update(&update);
+        result.updates.push(update);
+    };
+
+    for (record, audio) in entries.into_iter().skip(1) {
+        let (index, segments) = match (record, mode) {
+            (ReplayRecord::Chunk { index, .. }, ReplayMode::Retranscribe) => {
+                result.chunks += 1;
+                match send_audio_chunk(audio.unwrap_or_default()).await {
+                    Ok(response) => (index, response.segments),
+                    Err(e) => {
+                        result.failures += 1;
+                        warn!("Replay transcription failed: {}", e);
+                        continue;
+                    }
+                }
+            }
+            (ReplayRecord::Chunk { .. }, ReplayMode::Recorded) => {
+                result.chunks += 1;
+                continue;
+            }
+            (ReplayRecord::Response { index, segments, .. }, ReplayMode::Recorded) => (index, segments),
+            (ReplayRecord::Failure { .. }, ReplayMode::Recorded) => {
+                result.failures += 1;
+                continue;
+            }
+            (ReplayRecord::Flush { .. }, _) => {
+                if let Some(update) = accumulator.flush() {
+                    emit(update, &mut result);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        for (position, segment) in segments.iter().enumerate() {
+            if let Some(source) = assign(index, position, segment) {
+                if let Some(update) = accumulator.set_source(Some(source), None) {
+                    emit(update, &mut result);
+                }
+            }
+            if let Some(update) = accumulator.add_segment(segment) {
+                emit(update, &mut result);
+            }
+        }
+    }
+
+    if let Some(update) = accumulator.flush() {
+        emit(update, &mut result);
+    }
+
+    info!(
+        "Replay finished: {} chunks, {} failures, {} transcript updates",
+        result.chunks,
+        result.failures,
+        result.updates.len()
+    );
+    Ok(result)
+}
+
+#[command]
+pub fn get_replay_settings() -> ReplaySettings {
+    settings::get().replay
+}
+
+#[command]
+pub fn set_replay_settings(replay_settings: ReplaySettings) -> Result<ReplaySettings, String> {
+    settings::update(|s| s.replay = replay_settings).map(|s| s.replay)
+}
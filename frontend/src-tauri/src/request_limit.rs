@@ -0,0 +1,159 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use tokio::sync::oneshot;
+
+use crate::{metrics, settings};
+
+/// Requests for audio not tied to a capture device share this queue.
+const UNATTRIBUTED: &str = "other";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestLimitSettings {
+    /// Requests to cloud engines in flight at once; 0 removes the limit.
+    pub max_parallel_cloud_requests: usize,
+}
+
+impl Default for RequestLimitSettings {
+    fn default() -> Self {
+        Self {
+            max_parallel_cloud_requests: 4,
+        }
+    }
+}
+
+/// Requests waiting for a slot, queued per device and served round-robin so a
+/// device producing many chunks can't starve the others.
+#[derive(Default)]
+struct Slots {
+    in_flight: usize,
+    waiting: BTreeMap<String, VecDeque<oneshot::Sender<CloudPermit>>>,
+    last_served: Option<String>,
+}
+
+impl Slots {
+    /// The next waiter after the device served last, in device order.
+    fn next_waiter(&mut self) -> Option<oneshot::Sender<CloudPermit>> {
+        let device = {
+            let after = self.last_served.as_deref().unwrap_or_default();
+            self.waiting
+                .keys()
+                .find(|device| device.as_str() > after)
+                .or_else(|| self.waiting.keys().next())?
+                .clone()
+        };
+        let queue = self.waiting.get_mut(&device)?;
+        let waiter = queue.pop_front();
+        if queue.is_empty() {
+            self.waiting.remove(&device);
+        }
+        self.last_served = Some(device);
+        waiter
+    }
+}
+
+static SLOTS: Lazy<Mutex<Slots>> = Lazy::new(|| Mutex::new(Slots::default()));
+
+fn slots() -> MutexGuard<'static, Slots> {
+    SLOTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn limit() -> usize {
+    match settings::get().request_limit.max_parallel_cloud_requests {
+        0 => usize::MAX,
+        limit => limit,
+    }
+}
+
+/// A slot for one cloud request, handed to the next waiter when dropped.
+pub struct CloudPermit {
+    _private: (),
+}
+
+impl Drop for CloudPermit {
+    fn drop(&mut self) {
+        let waiter = {
+            let mut slots = slots();
+            let waiter = slots.next_waiter();
+            if waiter.is_none() {
+                slots.in_flight = slots.in_flight.saturating_sub(1);
+            }
+            waiter
+        };
+        // A waiter that gave up hands the slot straight on when this is dropped
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(CloudPermit { _private: () });
+        }
+    }
+}
+
+/// Wait for a slot to send a request for audio from `device` to a cloud engine.
+pub async fn acquire(device: Option<&str>) -> CloudPermit {
+    let started = Instant::now();
+    let receiver = {
+        let mut slots = slots();
+        if slots.waiting.is_empty() && slots.in_flight < limit() {
+            slots.in_flight += 1;
+            None
+        } else {
+            let (sender, receiver) = oneshot::channel();
+            slots
+                .waiting
+                .entry(device.unwrap_or(UNATTRIBUTED).to_string())
+                .or_default()
+                .push_back(sender);
+            Some(receiver)
+        }
+    };
+    let permit = match receiver {
+        None => CloudPermit { _private: () },
+        // Senders are only dropped by sending, so this always gets a permit
+        Some(receiver) => receiver.await.unwrap_or(CloudPermit { _private: () }),
+    };
+    metrics::record(metrics::Stage::CloudQueue, started.elapsed());
+    permit
+}
+
+/// Admit waiters up to the current limit, after it was raised.
+fn admit() {
+    loop {
+        let waiter = {
+            let mut slots = slots();
+            if slots.in_flight >= limit() {
+                return;
+            }
+            let Some(waiter) = slots.next_waiter() else {
+                return;
+            };
+            slots.in_flight += 1;
+            waiter
+        };
+        let _ = waiter.send(CloudPermit { _private: () });
+    }
+}
+
+/// Requests waiting for a slot, by device.
+pub fn waiting() -> BTreeMap<String, usize> {
+    slots()
+        .waiting
+        .iter()
+        .map(|(device, queue)| (device.clone(), queue.len()))
+        .collect()
+}
+
+#[command]
+pub fn get_request_limit_settings() -> RequestLimitSettings {
+    settings::get().request_limit
+}
+
+#[command]
+pub fn set_request_limit_settings(request_limit_settings: RequestLimitSettings) -> Result<RequestLimitSettings, String> {
+    let updated = settings::update(|s| s.request_limit = request_limit_settings).map(|s| s.request_limit)?;
+    admit();
+    Ok(updated)
+}
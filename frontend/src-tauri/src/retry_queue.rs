@@ -0,0 +1,390 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::transcription::{self, AudioInput, TranscriptionError};
+use crate::{encryption, pipeline, settings, storage, transcript_stream, TranscriptUpdate};
+
+/// Folder in a meeting's storage holding the audio of failed chunks.
+const FAILED_DIR: &str = "failed";
+const QUEUE_FILE: &str = "queue.json";
+
+/// Chunks the engine failed on are kept and sent again later, so a network
+/// hiccup or a rate limit doesn't lose what was said.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySettings {
+    pub enabled: bool,
+    /// Automatic attempts per chunk, not counting the one that failed.
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled after every failed attempt.
+    pub initial_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 5,
+            initial_backoff_secs: 2,
+            max_backoff_secs: 120,
+        }
+    }
+}
+
+impl RetrySettings {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let secs = self.initial_backoff_secs.max(1).saturating_mul(1 << attempt.min(16));
+        Duration::from_secs(secs.min(self.max_backoff_secs.max(1)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentStatus {
+    /// Waiting for its next automatic attempt.
+    Pending,
+    /// Out of automatic attempts, or failed in a way retrying won't fix;
+    /// only `retry_failed_segments` sends it again.
+    GaveUp,
+    Recovered,
+}
+
+/// A chunk of a meeting that could not be transcribed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedSegment {
+    pub id: u64,
+    /// Where the chunk starts in the recording.
+    pub offset_secs: f64,
+    pub duration_secs: f64,
+    pub sample_rate: u32,
+    /// Who the chunk was attributed to when it was captured.
+    pub source: String,
+    pub error: TranscriptionError,
+    pub attempts: u32,
+    pub status: SegmentStatus,
+    /// Transcript lines recovered by a retry.
+    #[serde(default)]
+    pub recovered: Vec<TranscriptUpdate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrySummary {
+    pub attempted: usize,
+    pub recovered: usize,
+    /// Segments still without a transcript.
+    pub remaining: usize,
+}
+
+/// Serializes read-modify-write of queue files between retry tasks.
+static QUEUE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn failed_dir<R: Runtime>(app: &AppHandle<R>, session_id: &str) -> Result<PathBuf, String> {
+    storage::meeting_dir(app, session_id).map(|dir| dir.join(FAILED_DIR))
+}
+
+fn audio_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{}.pcm", id))
+}
+
+fn read_queue(dir: &Path) -> Vec<FailedSegment> {
+    fs::read_to_string(dir.join(QUEUE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_queue(dir: &Path, queue: &[FailedSegment]) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(queue).map_err(|e| format!("Failed to serialize retry queue: {}", e))?;
+    fs::write(dir.join(QUEUE_FILE), content).map_err(|e| format!("Failed to write retry queue: {}", e))
+}
+
+/// Apply `f` to the stored queue under the lock.
+fn modify_queue<T>(dir: &Path, f: impl FnOnce(&mut Vec<FailedSegment>) -> T) -> Result<T, String> {
+    let _guard = QUEUE_LOCK.lock().map_err(|_| "Retry queue lock poisoned".to_string())?;
+    let mut queue = read_queue(dir);
+    let result = f(&mut queue);
+    write_queue(dir, &queue)?;
+    Ok(result)
+}
+
+fn write_audio(path: &Path, samples: &[f32]) -> Result<(), String> {
+    let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    encryption::write(path, bytes).map_err(|e| format!("Failed to save failed chunk audio: {}", e))
+}
+
+fn read_audio(path: &Path) -> Result<Vec<f32>, String> {
+    let bytes = encryption::read(path).map_err(|e| format!("Failed to read failed chunk audio: {}", e))?;
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// Keep a chunk of `session_id` the engine failed on, and retry it in the
+/// background when the failure looks transient.
+pub fn enqueue<R: Runtime>(
+    app: &AppHandle<R>,
+    session_id: &str,
+    samples: &[f32],
+    sample_rate: u32,
+    offset_secs: f64,
+    source: String,
+    error: TranscriptionError,
+) -> Result<(), String> {
+    let dir = failed_dir(app, session_id)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create retry queue: {}", e))?;
+    let transient = error.is_transient();
+    let id = modify_queue(&dir, |queue| {
+        let id = queue.iter().map(|segment| segment.id + 1).max().unwrap_or(0);
+        queue.push(FailedSegment {
+            id,
+            offset_secs,
+            duration_secs: samples.len() as f64 / sample_rate.max(1) as f64,
+            sample_rate,
+            source,
+            error,
+            attempts: 0,
+            status: if transient { SegmentStatus::Pending } else { SegmentStatus::GaveUp },
+            recovered: Vec::new(),
+        });
+        id
+    })?;
+    write_audio(&audio_path(&dir, id), samples)?;
+    info!("Queued failed chunk {} of meeting {} for retry", id, session_id);
+
+    if transient {
+        let app = app.clone();
+        let session_id = session_id.to_string();
+        tauri::async_runtime::spawn(async move { retry_with_backoff(app, session_id, id).await });
+    }
+    Ok(())
+}
+
+async fn retry_with_backoff<R: Runtime>(app: AppHandle<R>, session_id: String, id: u64) {
+    let config = settings::get().retry;
+    for attempt in 0..config.max_attempts {
+        tokio::time::sleep(config.backoff(attempt)).await;
+        match retry_segment(&app, &session_id, id, false).await {
+            Ok(Some(SegmentStatus::Pending)) => continue,
+            Ok(_) => return,
+            Err(e) => {
+                error!("Retry of chunk {} of meeting {} failed: {}", id, session_id, e);
+                return;
+            }
+        }
+    }
+    let given_up = failed_dir(&app, &session_id).and_then(|dir| {
+        modify_queue(&dir, |queue| {
+            if let Some(segment) = queue.iter_mut().find(|s| s.id == id && s.status == SegmentStatus::Pending) {
+                segment.status = SegmentStatus::GaveUp;
+            }
+        })
+    });
+    if let Err(e) = given_up {
+        error!("Failed to update retry queue: {}", e);
+    }
+    warn!("Gave up retrying chunk {} of meeting {}", id, session_id);
+}
+
+/// Lines for the segments of a retried chunk, timed from the recording start.
+fn recovered_lines(segment: &FailedSegment, transcript: transcription::Transcript) -> Vec<TranscriptUpdate> {
+    transcript
+        .segments
+        .into_iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .map(|s| TranscriptUpdate {
+            text: s.text.trim().to_string(),
+            timestamp: format!(
+                "{:.1} - {:.1}",
+                segment.offset_secs + s.t0 as f64,
+                segment.offset_secs + s.t1 as f64
+            ),
+            source: segment.source.clone(),
+            source_app: None,
+            speaker_display: None,
+            language: s.language.clone(),
+            translated_from: s.translated_from.clone(),
+        })
+        .collect()
+}
+
+/// Send one queued chunk to the engine again. `force` retries chunks that were
+/// given up on. Returns the chunk's status afterwards, or `None` if there's
+/// nothing to retry.
+async fn retry_segment<R: Runtime>(
+    app: &AppHandle<R>,
+    session_id: &str,
+    id: u64,
+    force: bool,
+) -> Result<Option<SegmentStatus>, String> {
+    let dir = failed_dir(app, session_id)?;
+    let Some(segment) = read_queue(&dir).into_iter().find(|s| s.id == id) else {
+        return Ok(None);
+    };
+    let retryable = match segment.status {
+        SegmentStatus::Pending => true,
+        SegmentStatus::GaveUp => force,
+        SegmentStatus::Recovered => false,
+    };
+    if !retryable {
+        return Ok(None);
+    }
+
+    let input = AudioInput {
+        samples: read_audio(&audio_path(&dir, id))?,
+        sample_rate: segment.sample_rate,
+        language: None,
+        device: None,
+    };
+    let outcome = transcription::transcribe(&input).await;
+    let max_attempts = settings::get().retry.max_attempts;
+    let (status, lines) = modify_queue(&dir, |queue| {
+        let Some(entry) = queue.iter_mut().find(|s| s.id == id) else {
+            return (None, Vec::new());
+        };
+        entry.attempts += 1;
+        match outcome {
+            Ok(transcript) => {
+                entry.recovered = recovered_lines(entry, transcript);
+                entry.status = SegmentStatus::Recovered;
+            }
+            Err(e) => {
+                entry.error = TranscriptionError::classify(e);
+                entry.status = if entry.error.is_transient() && (force || entry.attempts < max_attempts) {
+                    SegmentStatus::Pending
+                } else {
+                    SegmentStatus::GaveUp
+                };
+            }
+        }
+        (Some(entry.status), entry.recovered.clone())
+    })?;
+
+    if status == Some(SegmentStatus::Recovered) {
+        info!("Recovered chunk {} of meeting {} ({} lines)", id, session_id, lines.len());
+        let _ = fs::remove_file(audio_path(&dir, id));
+        if is_live(session_id) {
+            for line in lines {
+                if let Err(e) = transcript_stream::publish(app, line) {
+                    error!("Failed to emit recovered transcript update: {}", e);
+                }
+            }
+        } else {
+            merge_recovered(app, session_id)?;
+        }
+    }
+    Ok(status)
+}
+
+fn is_live(session_id: &str) -> bool {
+    pipeline::status().recording && storage::current_session().as_deref() == Some(session_id)
+}
+
+/// Add the lines every retry of `session_id` recovered to `transcript`,
+/// skipping lines it already has, in timestamp order. Anything that rebuilds
+/// a transcript from raw capture calls this, since the rebuild replays the
+/// original failures. Returns how many lines were added.
+pub fn add_recovered<R: Runtime>(
+    app: &AppHandle<R>,
+    session_id: &str,
+    transcript: &mut Vec<TranscriptUpdate>,
+) -> Result<usize, String> {
+    let dir = failed_dir(app, session_id)?;
+    let queue = {
+        let _guard = QUEUE_LOCK.lock().map_err(|_| "Retry queue lock poisoned".to_string())?;
+        read_queue(&dir)
+    };
+    let mut added = 0;
+    for line in queue
+        .into_iter()
+        .filter(|s| s.status == SegmentStatus::Recovered)
+        .flat_map(|s| s.recovered)
+    {
+        let present = transcript
+            .iter()
+            .any(|existing| existing.timestamp == line.timestamp && existing.text == line.text);
+        if !present {
+            transcript.push(line);
+            added += 1;
+        }
+    }
+    if added > 0 {
+        let start = |update: &TranscriptUpdate| {
+            update
+                .timestamp
+                .split(" - ")
+                .next()
+                .and_then(|start| start.trim().parse::<f64>().ok())
+                .unwrap_or(0.0)
+        };
+        transcript.sort_by(|a, b| start(a).total_cmp(&start(b)));
+    }
+    Ok(added)
+}
+
+/// Add lines recovered by retries to the meeting's stored transcript, once
+/// it has one.
+pub fn merge_recovered<R: Runtime>(app: &AppHandle<R>, session_id: &str) -> Result<usize, String> {
+    let meeting = storage::meeting_dir(app, session_id)?;
+    let Ok(mut transcript) = storage::read_transcript(&meeting) else {
+        return Ok(0);
+    };
+    let merged = add_recovered(app, session_id, &mut transcript)?;
+    if merged == 0 {
+        return Ok(0);
+    }
+    storage::replace_derived(&meeting, &[(storage::TRANSCRIPT_FILE, storage::transcript_json(&transcript)?)])?;
+    info!("Merged {} recovered lines into meeting {}", merged, session_id);
+    Ok(merged)
+}
+
+/// Chunks of a meeting that failed to transcribe, and what became of them.
+#[command]
+pub fn list_failed_segments<R: Runtime>(app: AppHandle<R>, session_id: String) -> Result<Vec<FailedSegment>, String> {
+    Ok(read_queue(&failed_dir(&app, &session_id)?))
+}
+
+/// Send every chunk of a meeting that still has no transcript to the engine
+/// again now, including those automatic retries gave up on.
+#[command]
+pub async fn retry_failed_segments<R: Runtime>(app: AppHandle<R>, session_id: String) -> Result<RetrySummary, String> {
+    let dir = failed_dir(&app, &session_id)?;
+    let ids: Vec<u64> = read_queue(&dir)
+        .iter()
+        .filter(|s| s.status != SegmentStatus::Recovered)
+        .map(|s| s.id)
+        .collect();
+    let mut recovered = 0;
+    for id in &ids {
+        if retry_segment(&app, &session_id, *id, true).await? == Some(SegmentStatus::Recovered) {
+            recovered += 1;
+        }
+    }
+    Ok(RetrySummary {
+        attempted: ids.len(),
+        recovered,
+        remaining: ids.len() - recovered,
+    })
+}
+
+#[command]
+pub fn get_retry_settings() -> RetrySettings {
+    settings::get().retry
+}
+
+#[command]
+pub fn set_retry_settings(retry_settings: RetrySettings) -> Result<RetrySettings, String> {
+    if retry_settings.initial_backoff_secs > retry_settings.max_backoff_secs {
+        return Err("Initial backoff can't exceed the maximum backoff".to_string());
+    }
+    settings::update(|s| s.retry = retry_settings).map(|s| s.retry)
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::{info, warn};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use tauri::{command, AppHandle, Runtime};
+
+use crate::storage::{self, MeetingManifest};
+use crate::{transcript_store, TranscriptUpdate};
+
+/// Derived file listing the Screenpipe audio files an imported meeting plays from.
+pub const AUDIO_FILES_FILE: &str = "screenpipe_audio.json";
+pub const IMPORT_TAG: &str = "screenpipe";
+/// Screenpipe records around the clock; a silence this long between chunks
+/// starts a new meeting.
+const DEFAULT_GAP_MINUTES: u32 = 5;
+
+/// One Screenpipe audio chunk referenced by an imported meeting, in place.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedAudio {
+    pub chunk_id: i64,
+    pub file_path: String,
+    /// Seconds from the start of the meeting.
+    pub offset_secs: f64,
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScreenpipeImport {
+    pub meetings: Vec<String>,
+    /// Meetings already imported earlier.
+    pub skipped: usize,
+    pub transcriptions: usize,
+    /// Audio chunks whose file is no longer on disk.
+    pub missing_audio: usize,
+}
+
+struct Transcription {
+    chunk_id: i64,
+    file_path: String,
+    chunk_at: DateTime<Utc>,
+    text: String,
+    device: Option<String>,
+    is_input: Option<bool>,
+    speaker: Option<String>,
+    start: Option<f64>,
+    end: Option<f64>,
+}
+
+fn default_database() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".screenpipe").join("db.sqlite"))
+}
+
+/// Screenpipe has stored timestamps both as RFC 3339 and as SQLite's
+/// `YYYY-MM-DD HH:MM:SS[.fff]` (UTC).
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+fn columns(connection: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = statement.query_map([], |row| row.get::<_, String>("name"))?.collect();
+    names
+}
+
+/// Every transcription with its chunk, oldest first. Columns added in later
+/// Screenpipe versions read as `NULL` from older databases.
+fn read_transcriptions(connection: &Connection) -> Result<Vec<Transcription>, String> {
+    let error = |e: rusqlite::Error| format!("Failed to read Screenpipe database: {}", e);
+    let available = columns(connection, "audio_transcriptions").map_err(error)?;
+    if available.is_empty() {
+        return Err("Not a Screenpipe database: it has no audio_transcriptions table".to_string());
+    }
+    let column = |name: &str| {
+        if available.iter().any(|c| c == name) {
+            format!("t.{}", name)
+        } else {
+            "NULL".to_string()
+        }
+    };
+    let has_speakers = !columns(connection, "speakers").map_err(error)?.is_empty();
+    let speaker_name = if has_speakers && available.iter().any(|c| c == "speaker_id") {
+        "(SELECT s.name FROM speakers s WHERE s.id = t.speaker_id)"
+    } else {
+        "NULL"
+    };
+    let query = format!(
+        "SELECT c.id, c.file_path, c.timestamp, t.transcription, {device}, {is_input}, {speaker_id}, {speaker_name},
+                {start}, {end}
+         FROM audio_transcriptions t JOIN audio_chunks c ON c.id = t.audio_chunk_id
+         ORDER BY c.timestamp, c.id, {start}, t.id",
+        device = column("device"),
+        is_input = column("is_input_device"),
+        speaker_id = column("speaker_id"),
+        speaker_name = speaker_name,
+        start = column("start_time"),
+        end = column("end_time"),
+    );
+
+    let mut statement = connection.prepare(&query).map_err(error)?;
+    let rows = statement
+        .query_map([], |row| {
+            let chunk_id: i64 = row.get(0)?;
+            let timestamp: String = row.get(2)?;
+            let Some(chunk_at) = parse_timestamp(&timestamp) else {
+                warn!("Skipping Screenpipe chunk {} with unreadable timestamp {}", chunk_id, timestamp);
+                return Ok(None);
+            };
+            let speaker_id: Option<i64> = row.get(6)?;
+            let speaker_name: Option<String> = row.get(7)?;
+            Ok(Some(Transcription {
+                chunk_id,
+                file_path: row.get(1)?,
+                chunk_at,
+                text: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                device: row.get(4)?,
+                is_input: row.get(5)?,
+                speaker: speaker_name
+                    .filter(|name| !name.trim().is_empty())
+                    .or_else(|| speaker_id.map(|id| format!("Speaker {}", id))),
+                start: row.get(8)?,
+                end: row.get(9)?,
+            }))
+        })
+        .map_err(error)?;
+
+    let mut transcriptions = Vec::new();
+    for row in rows {
+        if let Some(transcription) = row.map_err(error)? {
+            if !transcription.text.trim().is_empty() {
+                transcriptions.push(transcription);
+            }
+        }
+    }
+    // Timestamps of mixed formats don't order as text
+    transcriptions.sort_by_key(|transcription| (transcription.chunk_at, transcription.chunk_id));
+    Ok(transcriptions)
+}
+
+/// Split transcriptions into meetings wherever no chunk started for `gap`.
+fn group_meetings(transcriptions: Vec<Transcription>, gap: chrono::Duration) -> Vec<Vec<Transcription>> {
+    let mut meetings: Vec<Vec<Transcription>> = Vec::new();
+    for transcription in transcriptions {
+        match meetings.last_mut() {
+            Some(meeting) if transcription.chunk_at - meeting[meeting.len() - 1].chunk_at <= gap => {
+                meeting.push(transcription)
+            }
+            _ => meetings.push(vec![transcription]),
+        }
+    }
+    meetings
+}
+
+fn transcript(meeting: &[Transcription], started: DateTime<Utc>) -> Vec<TranscriptUpdate> {
+    meeting
+        .iter()
+        .map(|transcription| {
+            let offset = (transcription.chunk_at - started).num_milliseconds() as f64 / 1000.0;
+            let start = offset + transcription.start.unwrap_or(0.0);
+            let end = offset + transcription.end.unwrap_or(transcription.start.unwrap_or(0.0));
+            let source = transcription.speaker.clone().unwrap_or_else(|| {
+                match (transcription.is_input, transcription.device.as_deref()) {
+                    (_, Some(device)) if !device.is_empty() => device.to_string(),
+                    (Some(false), _) => "Speaker".to_string(),
+                    _ => "Microphone".to_string(),
+                }
+            });
+            TranscriptUpdate {
+                text: transcription.text.trim().to_string(),
+                timestamp: format!("{:.1} - {:.1}", start, end.max(start)),
+                source,
+                source_app: None,
+                speaker_display: None,
+                language: None,
+                translated_from: None,
+            }
+        })
+        .collect()
+}
+
+fn audio_files(meeting: &[Transcription], started: DateTime<Utc>) -> Vec<ImportedAudio> {
+    let mut files: Vec<ImportedAudio> = Vec::new();
+    for transcription in meeting {
+        if files.iter().any(|file| file.chunk_id == transcription.chunk_id) {
+            continue;
+        }
+        files.push(ImportedAudio {
+            chunk_id: transcription.chunk_id,
+            file_path: transcription.file_path.clone(),
+            offset_secs: (transcription.chunk_at - started).num_milliseconds() as f64 / 1000.0,
+            exists: Path::new(&transcription.file_path).is_file(),
+        });
+    }
+    files
+}
+
+/// Import the recordings of a Screenpipe database (`~/.screenpipe/db.sqlite`
+/// by default) as stored meetings, one per stretch of audio without a gap of
+/// `gap_minutes`. Transcripts are taken as Screenpipe made them and its audio
+/// files are referenced where they are, so nothing is re-transcribed or
+/// copied. Meetings imported before are skipped, so it can be run again
+/// after using Screenpipe some more.
+#[command]
+pub fn import_screenpipe<R: Runtime>(
+    app: AppHandle<R>,
+    database_path: Option<String>,
+    gap_minutes: Option<u32>,
+) -> Result<ScreenpipeImport, String> {
+    let path = database_path
+        .map(PathBuf::from)
+        .or_else(default_database)
+        .ok_or_else(|| "No Screenpipe database given".to_string())?;
+    if !path.is_file() {
+        return Err(format!("No Screenpipe database at {}", path.display()));
+    }
+    let connection = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let transcriptions = read_transcriptions(&connection)?;
+    let gap = chrono::Duration::minutes(gap_minutes.unwrap_or(DEFAULT_GAP_MINUTES).max(1) as i64);
+
+    let mut result = ScreenpipeImport::default();
+    for meeting in group_meetings(transcriptions, gap) {
+        let started = meeting[0].chunk_at;
+        let meeting_id = format!("screenpipe-{}", started.format("%Y%m%d-%H%M%S"));
+        if storage::meeting_dir(&app, &meeting_id)?.exists() {
+            result.skipped += 1;
+            continue;
+        }
+        let updates = transcript(&meeting, started);
+        let audio = audio_files(&meeting, started);
+        result.missing_audio += audio.iter().filter(|file| !file.exists).count();
+
+        let mut metadata = BTreeMap::new();
+        metadata.insert("source".to_string(), IMPORT_TAG.to_string());
+        metadata.insert("screenpipe_database".to_string(), path.to_string_lossy().to_string());
+        let manifest = MeetingManifest {
+            id: meeting_id.clone(),
+            name: Some(format!("Screenpipe {}", started.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"))),
+            created_at: started.to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            raw_files: Vec::new(),
+            derived_files: Vec::new(),
+            derived_at: None,
+            derived_by: None,
+            metadata,
+            tags: vec![IMPORT_TAG.to_string()],
+        };
+        let audio_json =
+            serde_json::to_string_pretty(&audio).map_err(|e| format!("Failed to serialize audio files: {}", e))?;
+        storage::import_meeting(&app, manifest, &updates, &[(AUDIO_FILES_FILE, audio_json)])?;
+        transcript_store::record_imported(&meeting_id, &updates, started);
+
+        result.transcriptions += updates.len();
+        result.meetings.push(meeting_id);
+    }
+
+    info!(
+        "Imported {} meetings ({} transcriptions) from Screenpipe at {}; {} already imported, {} audio files missing",
+        result.meetings.len(),
+        result.transcriptions,
+        path.display(),
+        result.skipped,
+        result.missing_audio
+    );
+    Ok(result)
+}
@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use crate::{assemblyai, azure, deepgram, encryption, openai};
+
+/// Names of the stored secrets; the keychain can't be listed.
+const INDEX_FILE: &str = "secret-names.json";
+/// Where secrets were kept in plaintext, before the keychain, and still are
+/// on systems without one.
+const FALLBACK_FILE: &str = "secrets.json";
+/// Keychain service the secrets are filed under.
+const KEYCHAIN_SERVICE: &str = "com.meetily.ai";
+
+/// Credentials for integrations (cloud buckets, webhooks, engines), kept out of
+/// `settings.json` so settings can be shared or logged without leaking them.
+/// Values live in the OS keychain (macOS Keychain, Windows Credential Manager,
+/// Secret Service on Linux) and are only readable by the backend; the webview
+/// can set and list names.
+static VAULT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    match app.path().app_config_dir() {
+        Ok(dir) => {
+            if let Ok(mut guard) = VAULT_DIR.lock() {
+                *guard = Some(dir);
+            }
+            if let Err(e) = migrate() {
+                error!("Failed to move secrets to the keychain: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to resolve app config directory for secrets: {}", e),
+    }
+}
+
+fn vault_path(file: &str) -> Result<PathBuf, String> {
+    VAULT_DIR
+        .lock()
+        .map_err(|e| format!("Failed to lock secrets path: {}", e))?
+        .as_ref()
+        .map(|dir| dir.join(file))
+        .ok_or_else(|| "Secrets vault has not been initialized".to_string())
+}
+
+fn read_json<T: serde::de::DeserializeOwned + Default>(file: &str) -> Result<T, String> {
+    let path = vault_path(file)?;
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| {
+            warn!("Secrets file {:?} is unreadable: {}", path, e);
+            format!("Failed to parse secrets: {}", e)
+        }),
+        Err(_) => Ok(T::default()),
+    }
+}
+
+fn write_json<T: serde::Serialize>(file: &str, value: &T) -> Result<(), String> {
+    let path = vault_path(file)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create secrets directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // Created private, so the file is never readable by others, even briefly
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut out = options.open(&path).map_err(|e| format!("Failed to write secrets: {}", e))?;
+
+    // `mode` only applies to new files; tighten ones written by older versions
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        out.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict secrets file permissions: {}", e))?;
+    }
+    out.write_all(content.as_bytes()).map_err(|e| format!("Failed to write secrets: {}", e))?;
+    Ok(())
+}
+
+fn index() -> Result<BTreeSet<String>, String> {
+    read_json(INDEX_FILE)
+}
+
+fn fallback() -> Result<BTreeMap<String, String>, String> {
+    read_json(FALLBACK_FILE)
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, keyring::Error> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name)
+}
+
+/// Whether a keychain error means there's no keychain to use at all, as on a
+/// Linux desktop without a Secret Service provider.
+fn no_keychain(error: &keyring::Error) -> bool {
+    matches!(error, keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_))
+}
+
+/// Keep `value` in the keychain, or in the fallback file without one.
+fn put(name: &str, value: &str) -> Result<(), String> {
+    match entry(name).and_then(|entry| entry.set_password(value)) {
+        Ok(()) => Ok(()),
+        Err(e) if no_keychain(&e) => {
+            warn!("No keychain available ({}), storing secret '{}' in a file", e, name);
+            let mut secrets = fallback()?;
+            secrets.insert(name.to_string(), value.to_string());
+            write_json(FALLBACK_FILE, &secrets)
+        }
+        Err(e) => Err(format!("Failed to store secret '{}' in the keychain: {}", name, e)),
+    }
+}
+
+/// Move secrets kept in plaintext by earlier versions into the keychain.
+fn migrate() -> Result<(), String> {
+    let mut secrets = fallback()?;
+    if secrets.is_empty() {
+        return Ok(());
+    }
+    let mut names = index()?;
+    let mut moved = 0;
+    for (name, value) in secrets.clone() {
+        match entry(&name).and_then(|entry| entry.set_password(&value)) {
+            Ok(()) => {
+                secrets.remove(&name);
+                names.insert(name);
+                moved += 1;
+            }
+            Err(e) if no_keychain(&e) => {
+                names.extend(secrets.keys().cloned());
+                write_json(INDEX_FILE, &names)?;
+                return Ok(());
+            }
+            Err(e) => warn!("Failed to move secret '{}' to the keychain: {}", name, e),
+        }
+    }
+    write_json(INDEX_FILE, &names)?;
+    if secrets.is_empty() {
+        fs::remove_file(vault_path(FALLBACK_FILE)?).map_err(|e| format!("Failed to remove old secrets file: {}", e))?;
+    } else {
+        write_json(FALLBACK_FILE, &secrets)?;
+    }
+    info!("Moved {} secrets to the keychain", moved);
+    Ok(())
+}
+
+/// Look up a secret by name.
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    match entry(name).and_then(|entry| entry.get_password()) {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(fallback()?.remove(name)),
+        Err(e) if no_keychain(&e) => Ok(fallback()?.remove(name)),
+        Err(e) => Err(format!("Failed to read secret '{}' from the keychain: {}", name, e)),
+    }
+}
+
+/// Look up a secret that an integration cannot work without.
+pub fn require(name: &str) -> Result<String, String> {
+    get(name)?.ok_or_else(|| format!("Secret '{}' is not set", name))
+}
+
+fn add_to_index(name: &str) -> Result<(), String> {
+    let mut names = index()?;
+    if names.insert(name.to_string()) {
+        write_json(INDEX_FILE, &names)?;
+    }
+    Ok(())
+}
+
+/// Secrets the backend manages itself; replacing or deleting them from the
+/// webview would make data unreadable.
+fn ensure_not_managed(name: &str) -> Result<(), String> {
+    if name == encryption::KEY_SECRET {
+        return Err(format!("Secret '{}' is managed by the app", name));
+    }
+    Ok(())
+}
+
+/// Store a secret that must not end up in a plaintext file, failing when
+/// there's no keychain.
+pub fn set_keychain_secret(name: &str, value: &str) -> Result<(), String> {
+    entry(name)
+        .and_then(|entry| entry.set_password(value))
+        .map_err(|e| format!("Failed to store secret '{}' in the keychain: {}", name, e))?;
+    add_to_index(name)?;
+    info!("Stored secret '{}' in the keychain", name);
+    Ok(())
+}
+
+#[command]
+pub fn set_secret(name: String, value: String) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Secret name cannot be empty".to_string());
+    }
+    ensure_not_managed(&name)?;
+    put(&name, &value)?;
+    add_to_index(&name)?;
+    info!("Stored secret '{}'", name);
+    Ok(())
+}
+
+#[command]
+pub fn delete_secret(name: String) -> Result<(), String> {
+    ensure_not_managed(&name)?;
+    match entry(&name).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) if no_keychain(&e) => {}
+        Err(e) => return Err(format!("Failed to delete secret '{}' from the keychain: {}", name, e)),
+    }
+    let mut secrets = fallback()?;
+    if secrets.remove(&name).is_some() {
+        write_json(FALLBACK_FILE, &secrets)?;
+    }
+    let mut names = index()?;
+    if names.remove(&name) {
+        write_json(INDEX_FILE, &names)?;
+        info!("Deleted secret '{}'", name);
+    }
+    Ok(())
+}
+
+/// Names of stored secrets; values never leave the backend.
+#[command]
+pub fn list_secret_names() -> Result<Vec<String>, String> {
+    let mut names = index()?;
+    names.extend(fallback()?.into_keys());
+    Ok(names.into_iter().collect())
+}
+
+/// The secret holding the API key of a transcription provider.
+fn api_key_secret(provider: &str) -> Result<&'static str, String> {
+    match provider {
+        deepgram::DeepgramEngine::ID => Ok(deepgram::API_KEY_SECRET),
+        azure::AzureSpeechEngine::ID => Ok(azure::API_KEY_SECRET),
+        assemblyai::AssemblyAiEngine::ID => Ok(assemblyai::API_KEY_SECRET),
+        openai::OpenAiEngine::ID => Ok(openai::API_KEY_SECRET),
+        _ => Err(format!("Unknown provider '{}'", provider)),
+    }
+}
+
+/// Store a provider's API key, or remove it when `api_key` is empty.
+#[command]
+pub fn set_api_key(provider: String, api_key: String) -> Result<(), String> {
+    let name = api_key_secret(&provider)?;
+    match api_key.trim() {
+        "" => delete_secret(name.to_string()),
+        api_key => set_secret(name.to_string(), api_key.to_string()),
+    }
+}
+
+#[command]
+pub fn has_api_key(provider: String) -> Result<bool, String> {
+    Ok(get(api_key_secret(&provider)?)?.is_some_and(|key| !key.is_empty()))
+}
@@ -0,0 +1,70 @@
+use log::info;
+use serde::Serialize;
+use tauri::{command, AppHandle, Runtime};
+
+use crate::deepgram::DeepgramOptions;
+use crate::storage;
+use crate::{is_recording, start_recording, stop_recording_session};
+
+/// A recording session: one meeting, stored in its own directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    /// Meeting id; live results carry it as `session_id`.
+    pub id: String,
+    pub name: Option<String>,
+    /// RFC 3339.
+    pub started_at: String,
+    /// Directory holding the session's raw capture and derived files.
+    pub output_dir: String,
+    pub active: bool,
+}
+
+fn session_info<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<SessionInfo, String> {
+    let dir = storage::meeting_dir(app, id)?;
+    let manifest = storage::read_manifest(&dir)?;
+    Ok(SessionInfo {
+        id: manifest.id,
+        name: manifest.name,
+        started_at: manifest.created_at,
+        output_dir: dir.to_string_lossy().to_string(),
+        active: is_recording() && storage::current_session().as_deref() == Some(id),
+    })
+}
+
+/// Start recording a new session called `name`, with its own Deepgram
+/// options if given. Returns the session with the id to pass to `stop_session`.
+#[command]
+pub async fn start_session<R: Runtime>(
+    app: AppHandle<R>,
+    name: Option<String>,
+    follow_default_devices: Option<bool>,
+    conference_mode: Option<bool>,
+    participants: Option<usize>,
+    deepgram_options: Option<DeepgramOptions>,
+) -> Result<SessionInfo, String> {
+    let name = name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+    start_recording(app.clone(), follow_default_devices, conference_mode, participants, name, deepgram_options).await?;
+    let id = storage::current_session().ok_or_else(|| "Recording started without session storage".to_string())?;
+    info!("Started session {}", id);
+    session_info(&app, &id)
+}
+
+/// Stop the session `session_id`, which must be the one being recorded.
+#[command]
+pub async fn stop_session<R: Runtime>(app: AppHandle<R>, session_id: String) -> Result<SessionInfo, String> {
+    if !is_recording() || storage::current_session().as_deref() != Some(session_id.as_str()) {
+        return Err(format!("Session {} is not being recorded", session_id));
+    }
+    stop_recording_session(&app, false).await?;
+    info!("Stopped session {}", session_id);
+    session_info(&app, &session_id)
+}
+
+/// The session being recorded, if any.
+#[command]
+pub fn get_active_session<R: Runtime>(app: AppHandle<R>) -> Option<SessionInfo> {
+    if !is_recording() {
+        return None;
+    }
+    session_info(&app, &storage::current_session()?).ok()
+}
@@ -0,0 +1,203 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::assemblyai::AssemblyAiSettings;
+use crate::audio::farfield::ConferenceSettings;
+use crate::audio::noise::NoiseSettings;
+use crate::assurance::AssuranceSettings;
+use crate::audio::time_stretch::TimeStretchSettings;
+use crate::azure::AzureSpeechSettings;
+use crate::bilingual::BilingualSettings;
+use crate::calendar::CalendarSettings;
+use crate::captions::CaptionSettings;
+use crate::compute_device::ComputeDeviceSettings;
+use crate::connectivity::ConnectivitySettings;
+use crate::deepgram::DeepgramSettings;
+use crate::encryption::EncryptionSettings;
+use crate::export::ExportSettings;
+use crate::governor::GovernorSettings;
+use crate::hallucination::HallucinationSettings;
+use crate::integrity::IntegritySettings;
+use crate::interview_kit::InterviewKitSettings;
+use crate::itn::ItnSettings;
+use crate::hotkeys::HotkeyBindings;
+use crate::language_id::LanguageIdSettings;
+use crate::meeting_end::MeetingEndSettings;
+use crate::metrics::MetricsSettings;
+use crate::models::ModelSettings;
+use crate::name_bias::NameBiasSettings;
+use crate::notifications::NotificationSettings;
+use crate::openai::OpenAiSettings;
+use crate::preroll::PrerollSettings;
+use crate::replay::ReplaySettings;
+use crate::request_limit::RequestLimitSettings;
+use crate::retry_queue::RetrySettings;
+use crate::softphone::SoftphoneSettings;
+use crate::speaker_profiles::SpeakerProfileSettings;
+use crate::speakers::SpeakerDirectory;
+use crate::storage::StorageSettings;
+use crate::transcription::TranscriptionSettings;
+use crate::usage::UsageSettings;
+use crate::watch_folder::WatchFolderSettings;
+use crate::whisper_cpp::WhisperCppSettings;
+use crate::whisper_decode::WhisperDecodeOptions;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Backend-owned settings persisted as JSON in the app config directory.
+///
+/// Every field uses `#[serde(default)]` so settings files written by older
+/// versions keep loading after new fields are added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub hotkeys: HotkeyBindings,
+    pub notifications: NotificationSettings,
+    pub audio: AudioSettings,
+    pub replay: ReplaySettings,
+    pub governor: GovernorSettings,
+    pub captions: CaptionSettings,
+    pub speakers: SpeakerDirectory,
+    pub speaker_profiles: SpeakerProfileSettings,
+    pub export: ExportSettings,
+    pub storage: StorageSettings,
+    pub integrity: IntegritySettings,
+    pub deepgram: DeepgramSettings,
+    pub noise: NoiseSettings,
+    pub transcription: TranscriptionSettings,
+    pub conference: ConferenceSettings,
+    pub openai: OpenAiSettings,
+    pub assemblyai: AssemblyAiSettings,
+    pub azure: AzureSpeechSettings,
+    pub whisper_cpp: WhisperCppSettings,
+    pub bilingual: BilingualSettings,
+    pub whisper_decode: WhisperDecodeOptions,
+    pub softphone: SoftphoneSettings,
+    pub hallucination: HallucinationSettings,
+    pub language_id: LanguageIdSettings,
+    pub watch_folders: WatchFolderSettings,
+    pub preroll: PrerollSettings,
+    pub calendar: CalendarSettings,
+    pub meeting_end: MeetingEndSettings,
+    pub compute_device: ComputeDeviceSettings,
+    pub models: ModelSettings,
+    pub metrics: MetricsSettings,
+    pub interview_kit: InterviewKitSettings,
+    pub itn: ItnSettings,
+    pub retry: RetrySettings,
+    pub request_limit: RequestLimitSettings,
+    pub connectivity: ConnectivitySettings,
+    pub time_stretch: TimeStretchSettings,
+    pub assurance: AssuranceSettings,
+    pub usage: UsageSettings,
+    pub name_bias: NameBiasSettings,
+    pub encryption: EncryptionSettings,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Capture from the built-in mic when the default input is a Bluetooth
+    /// headset, leaving playback on the headset.
+    pub prefer_builtin_mic_on_bluetooth: bool,
+    /// Drop system-audio segments attributed to these apps (case-insensitive
+    /// substring match), e.g. a browser playing a video during the meeting.
+    pub excluded_source_apps: Vec<String>,
+}
+
+static SETTINGS: Lazy<Mutex<AppSettings>> = Lazy::new(|| Mutex::new(AppSettings::default()));
+static SETTINGS_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Resolve the settings file location and load it into memory.
+/// Falls back to defaults if the file is missing or unreadable.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let path = match app.path().app_config_dir() {
+        Ok(dir) => dir.join(SETTINGS_FILE),
+        Err(e) => {
+            error!("Failed to resolve app config directory: {}", e);
+            return;
+        }
+    };
+
+    let settings = match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<AppSettings>(&content) {
+            Ok(mut settings) => {
+                info!("Loaded settings from {:?}", path);
+                settings.transcription.upgrade();
+                settings
+            }
+            Err(e) => {
+                warn!("Failed to parse settings file {:?}, using defaults: {}", path, e);
+                AppSettings::default()
+            }
+        },
+        Err(_) => {
+            info!("No settings file at {:?}, using defaults", path);
+            AppSettings::default()
+        }
+    };
+
+    if let Ok(mut guard) = SETTINGS.lock() {
+        *guard = settings;
+    }
+    if let Ok(mut guard) = SETTINGS_PATH.lock() {
+        *guard = Some(path);
+    }
+}
+
+#[tauri::command]
+pub fn get_audio_settings() -> AudioSettings {
+    get().audio
+}
+
+#[tauri::command]
+pub fn set_audio_settings(audio_settings: AudioSettings) -> Result<AudioSettings, String> {
+    update(|s| s.audio = audio_settings).map(|s| s.audio)
+}
+
+/// Snapshot of the current settings.
+pub fn get() -> AppSettings {
+    SETTINGS
+        .lock()
+        .map(|guard| guard.clone())
+        .unwrap_or_default()
+}
+
+/// Apply `f` to the in-memory settings and persist the result.
+pub fn update<F>(f: F) -> Result<AppSettings, String>
+where
+    F: FnOnce(&mut AppSettings),
+{
+    let mut guard = SETTINGS
+        .lock()
+        .map_err(|e| format!("Failed to lock settings: {}", e))?;
+    f(&mut guard);
+    save(&guard)?;
+    Ok(guard.clone())
+}
+
+fn save(settings: &AppSettings) -> Result<(), String> {
+    let path = SETTINGS_PATH
+        .lock()
+        .map_err(|e| format!("Failed to lock settings path: {}", e))?
+        .clone()
+        .ok_or_else(|| "Settings have not been initialized".to_string())?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write settings: {}", e))?;
+
+    info!("Settings saved to {:?}", path);
+    Ok(())
+}
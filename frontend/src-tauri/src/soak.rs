@@ -0,0 +1,211 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::Mutex;
+
+use crate::audio::vad::EnergyVad;
+use crate::pipeline::{self, PipelineEvent};
+use crate::transcription::TranscriptionError;
+use crate::{resample_audio, send_audio_chunk, TranscriptAccumulator, WHISPER_SAMPLE_RATE};
+
+/// Synthetic capture rate, resampled like a real 48 kHz device.
+const SYNTH_SAMPLE_RATE: u32 = 48000;
+const SYNTH_CHUNK_SECS: u32 = 30;
+/// Don't judge RSS growth until the process has warmed up.
+const LEAK_WARMUP_SECS: f64 = 1800.0;
+/// Sustained RSS growth above this is reported as a probable leak.
+const LEAK_THRESHOLD_BYTES_PER_HOUR: f64 = 50.0 * 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakSample {
+    pub elapsed_secs: f64,
+    pub iterations: u64,
+    pub failed_transcriptions: u64,
+    pub rss_bytes: Option<u64>,
+    pub open_handles: Option<u64>,
+    pub pipeline_queue_depth: usize,
+    /// Least-squares RSS slope over the run so far.
+    pub rss_growth_bytes_per_hour: Option<f64>,
+    pub leak_suspected: bool,
+}
+
+static SOAK_RUNNING: Lazy<Mutex<Option<Arc<AtomicBool>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Speech-like test signal: voiced bursts (harmonic stack with syllable-rate
+/// envelope) separated by pauses, so VAD and the engine see realistic input.
+fn synthetic_chunk(seed: u64) -> Vec<f32> {
+    let len = (SYNTH_SAMPLE_RATE * SYNTH_CHUNK_SECS) as usize;
+    let fundamental = 110.0 + (seed % 7) as f32 * 15.0;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SYNTH_SAMPLE_RATE as f32;
+            // 3 s of "speech" followed by 1 s of silence
+            if t % 4.0 >= 3.0 {
+                return 0.0;
+            }
+            let envelope = (std::f32::consts::PI * 4.0 * t).sin().abs();
+            let voiced: f32 = (1..=5)
+                .map(|h| (2.0 * std::f32::consts::PI * fundamental * h as f32 * t).sin() / h as f32)
+                .sum();
+            0.2 * envelope * voiced
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn resident_set_bytes() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: sysconf only reads a system constant
+    let page_size = u64::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).ok()?;
+    Some(pages * page_size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_set_bytes() -> Option<u64> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()?;
+    let kb: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(unix)]
+fn open_handles() -> Option<u64> {
+    let dir = if cfg!(target_os = "linux") {
+        "/proc/self/fd"
+    } else {
+        "/dev/fd"
+    };
+    std::fs::read_dir(dir).ok().map(|entries| entries.count() as u64)
+}
+
+#[cfg(not(unix))]
+fn open_handles() -> Option<u64> {
+    None
+}
+
+fn rss_slope_per_hour(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 3 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    Some(covariance / variance * 3600.0)
+}
+
+/// Run the capture-side pipeline (resample, VAD, transcription, accumulation)
+/// on looped synthetic audio, reporting resource usage as `soak-report` events.
+#[command]
+pub async fn start_soak_test<R: Runtime>(
+    app: AppHandle<R>,
+    duration_hours: f64,
+    report_interval_secs: Option<u64>,
+    speed: Option<f64>,
+) -> Result<(), String> {
+    let mut running = SOAK_RUNNING.lock().await;
+    if running.is_some() {
+        return Err("Soak test already running".to_string());
+    }
+    let flag = Arc::new(AtomicBool::new(true));
+    *running = Some(flag.clone());
+    drop(running);
+
+    let report_interval = Duration::from_secs(report_interval_secs.unwrap_or(60));
+    let chunk_pause = Duration::from_secs_f64(SYNTH_CHUNK_SECS as f64 / speed.unwrap_or(1.0).max(0.01));
+    let deadline = Instant::now() + Duration::from_secs_f64(duration_hours.max(0.0) * 3600.0);
+    info!("Starting soak test for {} hours", duration_hours);
+
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let vad = EnergyVad::default();
+        let mut accumulator = TranscriptAccumulator::new();
+        let mut rss_points: Vec<(f64, f64)> = Vec::new();
+        let mut last_report = Instant::now();
+        let mut iterations = 0u64;
+        let mut failed = 0u64;
+
+        while flag.load(Ordering::SeqCst) && Instant::now() < deadline {
+            let chunk = synthetic_chunk(iterations);
+            let samples = resample_audio(&chunk, SYNTH_SAMPLE_RATE, WHISPER_SAMPLE_RATE);
+            if vad.speech_ratio(&samples, WHISPER_SAMPLE_RATE) > 0.0 {
+                pipeline::publish(PipelineEvent::ChunkQueued { samples: samples.len() });
+                let sent_at = Instant::now();
+                match send_audio_chunk(samples).await {
+                    Ok(response) => {
+                        pipeline::publish(PipelineEvent::ChunkTranscribed {
+                            latency_ms: sent_at.elapsed().as_millis() as u64,
+                            segments: response.segments.len(),
+                        });
+                        for segment in response.segments {
+                            accumulator.add_segment(&segment);
+                        }
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        pipeline::publish(PipelineEvent::TranscriptionFailed {
+                            kind: TranscriptionError::classify(e.clone()),
+                            error: e,
+                        });
+                    }
+                }
+            }
+            accumulator.check_timeout();
+            iterations += 1;
+
+            if last_report.elapsed() >= report_interval {
+                last_report = Instant::now();
+                let elapsed_secs = started.elapsed().as_secs_f64();
+                let rss_bytes = resident_set_bytes();
+                if let Some(rss) = rss_bytes {
+                    rss_points.push((elapsed_secs, rss as f64));
+                }
+                let slope = rss_slope_per_hour(&rss_points);
+                let leak_suspected = elapsed_secs > LEAK_WARMUP_SECS
+                    && slope.map_or(false, |s| s > LEAK_THRESHOLD_BYTES_PER_HOUR);
+                let sample = SoakSample {
+                    elapsed_secs,
+                    iterations,
+                    failed_transcriptions: failed,
+                    rss_bytes,
+                    open_handles: open_handles(),
+                    pipeline_queue_depth: pipeline::queued_events(),
+                    rss_growth_bytes_per_hour: slope,
+                    leak_suspected,
+                };
+                if leak_suspected {
+                    warn!("Soak test: probable leak, RSS growing {:?} bytes/hour", slope);
+                }
+                info!("Soak test report: {:?}", sample);
+                let _ = app.emit("soak-report", sample);
+            }
+
+            tokio::time::sleep(chunk_pause).await;
+        }
+
+        info!("Soak test finished after {} iterations", iterations);
+        *SOAK_RUNNING.lock().await = None;
+    });
+
+    Ok(())
+}
+
+#[command]
+pub async fn stop_soak_test() -> Result<(), String> {
+    if let Some(flag) = SOAK_RUNNING.lock().await.as_ref() {
+        flag.store(false, Ordering::SeqCst);
+    }
+    Ok(())
+}
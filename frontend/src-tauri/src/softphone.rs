@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::audio::app_activity::{active_audio_apps, AudioSource};
+use crate::pipeline::{self, PipelineEvent};
+use crate::session::{self, SessionInfo};
+use crate::storage::{self, MeetingManifest};
+use crate::{is_recording, secrets, settings};
+
+/// Tag given to every session recorded from a softphone call.
+pub const CALL_TAG: &str = "call";
+/// Vault entry sent as a bearer token with CRM lookups, when set.
+pub const CRM_API_KEY_SECRET: &str = "crm_api_key";
+/// How often playing applications are checked for a softphone.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoftphoneSettings {
+    pub enabled: bool,
+    /// Start a session when a softphone starts playing audio, and stop it
+    /// once the softphone has been silent for `end_after_secs`.
+    pub auto_start: bool,
+    /// Application names (case-insensitive substrings) treated as softphones.
+    pub apps: Vec<String>,
+    pub end_after_secs: u64,
+    /// Label for the microphone side of calls; the enrolled voice or
+    /// "Microphone" when unset.
+    pub local_name: Option<String>,
+    /// CRM endpoint returning caller details as JSON, with `{number}` replaced
+    /// by the caller's number, e.g. `https://crm.example.com/contacts?phone={number}`.
+    pub crm_lookup_url: Option<String>,
+}
+
+impl Default for SoftphoneSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_start: true,
+            apps: ["Zoiper", "Bria", "MicroSIP", "Linphone", "3CX", "Jitsi", "Dialpad", "RingCentral", "Zoom Phone"]
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            end_after_secs: 10,
+            local_name: None,
+            crm_lookup_url: None,
+        }
+    }
+}
+
+/// Who is on the other end of a call.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CallerInfo {
+    pub name: Option<String>,
+    pub number: Option<String>,
+    pub company: Option<String>,
+}
+
+impl CallerInfo {
+    /// How the far side of the call is labeled in the transcript.
+    fn label(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.number.clone())
+            .unwrap_or_else(|| "Caller".to_string())
+    }
+
+    fn metadata(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("caller_name".to_string(), self.name.clone().unwrap_or_default()),
+            ("caller_number".to_string(), self.number.clone().unwrap_or_default()),
+            ("caller_company".to_string(), self.company.clone().unwrap_or_default()),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveCall {
+    pub session_id: String,
+    pub softphone: String,
+    pub caller: CallerInfo,
+    /// Started by softphone detection, and so stopped by it too.
+    pub auto_started: bool,
+    #[serde(skip)]
+    last_heard: Instant,
+}
+
+static CALL: Lazy<Mutex<Option<ActiveCall>>> = Lazy::new(|| Mutex::new(None));
+/// Softphone last seen playing, so each call is announced once.
+static ANNOUNCED: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+fn active_call() -> Option<ActiveCall> {
+    CALL.lock().ok().and_then(|call| call.clone())
+}
+
+fn find_softphone(playing: &[String], config: &SoftphoneSettings) -> Option<String> {
+    playing
+        .iter()
+        .find(|app| {
+            let app = app.to_lowercase();
+            config.apps.iter().any(|softphone| app.contains(&softphone.to_lowercase()))
+        })
+        .cloned()
+}
+
+async fn begin_call<R: Runtime>(
+    app: &AppHandle<R>,
+    softphone: String,
+    caller: CallerInfo,
+    auto_started: bool,
+) -> Result<SessionInfo, String> {
+    let name = if caller.name.is_some() || caller.number.is_some() {
+        format!("Call with {}", caller.label())
+    } else {
+        format!("{} call", softphone)
+    };
+    let session = session::start_session(app.clone(), Some(name), None, None, None).await?;
+    storage::update_tags(app, &session.id, &[CALL_TAG.to_string()], &[])?;
+    let mut metadata = caller.metadata();
+    metadata.insert("softphone".to_string(), softphone.clone());
+    storage::merge_metadata(app, &session.id, metadata)?;
+    info!("Recording call from {} as session {}", softphone, session.id);
+    if let Ok(mut call) = CALL.lock() {
+        *call = Some(ActiveCall {
+            session_id: session.id.clone(),
+            softphone,
+            caller,
+            auto_started,
+            last_heard: Instant::now(),
+        });
+    }
+    Ok(session)
+}
+
+/// Offer to record a call that isn't recorded automatically, once when its
+/// softphone starts playing.
+fn announce(softphone: Option<&str>, config: &SoftphoneSettings) {
+    let Ok(mut announced) = ANNOUNCED.lock() else {
+        return;
+    };
+    if announced.as_deref() == softphone {
+        return;
+    }
+    *announced = softphone.map(str::to_string);
+    if let Some(app_name) = softphone {
+        if !config.auto_start && !is_recording() {
+            pipeline::publish(PipelineEvent::MeetingDetected {
+                app_name: app_name.to_string(),
+            });
+        }
+    }
+}
+
+async fn poll<R: Runtime>(app: &AppHandle<R>, config: &SoftphoneSettings) {
+    let call = active_call();
+    if call.is_some() && !is_recording() {
+        // Stopped from the UI or a hotkey
+        if let Ok(mut call) = CALL.lock() {
+            *call = None;
+        }
+        return;
+    }
+    let Ok(Some(playing)) = tokio::task::spawn_blocking(active_audio_apps).await else {
+        return;
+    };
+    let softphone = find_softphone(&playing, config);
+    announce(softphone.as_deref(), config);
+    match (softphone, call) {
+        (Some(softphone), None) if config.auto_start && !is_recording() => {
+            if let Err(e) = begin_call(app, softphone, CallerInfo::default(), true).await {
+                error!("Failed to start call recording: {}", e);
+            }
+        }
+        (Some(_), Some(_)) => {
+            if let Ok(mut call) = CALL.lock() {
+                if let Some(call) = call.as_mut() {
+                    call.last_heard = Instant::now();
+                }
+            }
+        }
+        (None, Some(call))
+            if call.auto_started && call.last_heard.elapsed() >= Duration::from_secs(config.end_after_secs) =>
+        {
+            info!("{} went quiet, ending call session {}", call.softphone, call.session_id);
+            if let Err(e) = session::stop_session(app.clone(), call.session_id).await {
+                warn!("Failed to stop call session: {}", e);
+            }
+            if let Ok(mut call) = CALL.lock() {
+                *call = None;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Watch playing applications for softphones.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let config = settings::get().softphone;
+            if config.enabled {
+                poll(&app, &config).await;
+            }
+        }
+    });
+}
+
+/// Label both sides of an active call: system audio is the caller and the
+/// microphone, when a local name is set, the user.
+pub fn attribute(source: Option<AudioSource>, source_app: Option<String>) -> (Option<AudioSource>, Option<String>) {
+    let Some(call) = active_call() else {
+        return (source, source_app);
+    };
+    match source {
+        Some(AudioSource::System) => (Some(AudioSource::Speaker(call.caller.label())), None),
+        Some(AudioSource::Microphone) => match settings::get().softphone.local_name {
+            Some(name) => (Some(AudioSource::Speaker(name)), None),
+            None => (source, source_app),
+        },
+        _ => (source, source_app),
+    }
+}
+
+/// Caller details from the configured CRM. The response may be a contact
+/// object or a list of them, of which the first is used.
+async fn crm_lookup(number: &str) -> Result<CallerInfo, String> {
+    let template = settings::get()
+        .softphone
+        .crm_lookup_url
+        .ok_or_else(|| "No CRM lookup URL configured".to_string())?;
+    let url = template.replace("{number}", &urlencode(number));
+    let mut request = reqwest::Client::new().get(&url);
+    if let Some(api_key) = secrets::get(CRM_API_KEY_SECRET)? {
+        request = request.bearer_auth(api_key);
+    }
+    let response = request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("CRM lookup failed: {}", e))?;
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid CRM response: {}", e))?;
+    let contact = match &body {
+        serde_json::Value::Array(contacts) => contacts.first().cloned().unwrap_or_default(),
+        other => other.clone(),
+    };
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| contact.get(*key).and_then(|v| v.as_str()))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    Ok(CallerInfo {
+        name: field(&["name", "full_name", "display_name"]),
+        number: Some(number.to_string()),
+        company: field(&["company", "organization", "account"]),
+    })
+}
+
+fn urlencode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Start recording a call by hand, e.g. from a softphone detection can't see.
+#[command]
+pub async fn start_call<R: Runtime>(
+    app: AppHandle<R>,
+    softphone: Option<String>,
+    caller: Option<CallerInfo>,
+) -> Result<SessionInfo, String> {
+    let softphone = softphone.unwrap_or_else(|| "Phone".to_string());
+    begin_call(&app, softphone, caller.unwrap_or_default(), false).await
+}
+
+/// Record who a call was with. With only a number and a CRM configured, the
+/// name and company are looked up.
+#[command]
+pub async fn set_call_caller<R: Runtime>(
+    app: AppHandle<R>,
+    session_id: String,
+    mut caller: CallerInfo,
+) -> Result<MeetingManifest, String> {
+    let has_crm = settings::get().softphone.crm_lookup_url.is_some();
+    if let (None, Some(number), true) = (&caller.name, caller.number.clone(), has_crm) {
+        match crm_lookup(&number).await {
+            Ok(found) => {
+                caller.name = found.name;
+                caller.company = caller.company.or(found.company);
+            }
+            Err(e) => warn!("{}", e),
+        }
+    }
+    let manifest = storage::merge_metadata(&app, &session_id, caller.metadata())?;
+    if let Ok(mut call) = CALL.lock() {
+        if let Some(call) = call.as_mut().filter(|call| call.session_id == session_id) {
+            call.caller = caller;
+        }
+    }
+    Ok(manifest)
+}
+
+#[command]
+pub async fn lookup_caller(number: String) -> Result<CallerInfo, String> {
+    crm_lookup(number.trim()).await
+}
+
+#[command]
+pub fn get_active_call() -> Option<ActiveCall> {
+    active_call()
+}
+
+#[command]
+pub fn get_softphone_settings() -> SoftphoneSettings {
+    settings::get().softphone
+}
+
+#[command]
+pub fn set_softphone_settings(softphone_settings: SoftphoneSettings) -> Result<SoftphoneSettings, String> {
+    settings::update(|s| s.softphone = softphone_settings).map(|s| s.softphone)
+}
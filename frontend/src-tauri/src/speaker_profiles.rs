@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::info;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+use crate::audio::embedding::similarity;
+use crate::settings;
+
+const PROFILES_FILE: &str = "speaker_profiles.json";
+/// Numbered placeholders aren't identities worth remembering.
+const UNNAMED_PREFIX: &str = "Speaker ";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeakerProfileSettings {
+    /// Name speakers in new meetings after matching stored profiles.
+    pub enabled: bool,
+    /// Minimum cosine similarity between a speaker and a profile to match.
+    pub match_threshold: f32,
+    /// Follow enrolled voices as they change over a recording and hand back
+    /// segments missed while they drifted.
+    pub track_drift: bool,
+    /// Weight of each new segment in a speaker's running voice once it's
+    /// settled; higher follows changes faster but is easier to throw off.
+    pub drift_rate: f32,
+}
+
+impl Default for SpeakerProfileSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            match_threshold: 0.85,
+            track_drift: true,
+            drift_rate: 0.05,
+        }
+    }
+}
+
+/// A named voice remembered across meetings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerProfile {
+    pub name: String,
+    /// Normalized mean of every voice embedding learned for this speaker.
+    pub centroid: Vec<f32>,
+    /// How many segments the centroid was built from.
+    pub samples: usize,
+    /// RFC 3339.
+    pub updated_at: String,
+    /// Recorded through voice enrollment; live microphone audio matching it
+    /// is labeled with this name.
+    #[serde(default)]
+    pub enrolled: bool,
+}
+
+/// Stored profiles, keyed by speaker name.
+pub type SpeakerProfiles = BTreeMap<String, SpeakerProfile>;
+
+/// Serializes read-modify-write cycles of the profiles file.
+static PROFILES_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn profiles_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(PROFILES_FILE))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn load<R: Runtime>(app: &AppHandle<R>) -> Result<SpeakerProfiles, String> {
+    Ok(fs::read_to_string(profiles_path(app)?)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default())
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, profiles: &SpeakerProfiles) -> Result<(), String> {
+    let path = profiles_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let content =
+        serde_json::to_string_pretty(profiles).map_err(|e| format!("Failed to serialize speaker profiles: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write speaker profiles: {}", e))?;
+    let _ = app.emit("speaker-profiles-changed", profiles);
+    Ok(())
+}
+
+/// Load, change and save the profiles under the file lock.
+fn modify<R: Runtime, T>(
+    app: &AppHandle<R>,
+    change: impl FnOnce(&mut SpeakerProfiles) -> Result<T, String>,
+) -> Result<T, String> {
+    let _guard = PROFILES_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock speaker profiles: {}", e))?;
+    let mut profiles = load(app)?;
+    let result = change(&mut profiles)?;
+    save(app, &profiles)?;
+    Ok(result)
+}
+
+fn weighted_centroid(a: &[f32], a_samples: usize, b: &[f32], b_samples: usize) -> Vec<f32> {
+    let total = (a_samples + b_samples).max(1) as f32;
+    let mut mean: Vec<f32> = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (x * a_samples as f32 + y * b_samples as f32) / total)
+        .collect();
+    let norm = mean.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+    mean.iter_mut().for_each(|v| *v /= norm);
+    mean
+}
+
+pub fn is_unnamed(speaker: &str) -> bool {
+    speaker
+        .strip_prefix(UNNAMED_PREFIX)
+        .map(|number| number.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false)
+}
+
+/// The stored profile closest to `centroid`, if it clears the match threshold.
+pub fn identify<R: Runtime>(app: &AppHandle<R>, centroid: &[f32]) -> Option<(String, f32)> {
+    let config = settings::get().speaker_profiles;
+    if !config.enabled {
+        return None;
+    }
+    load(app)
+        .ok()?
+        .into_values()
+        .filter(|profile| profile.centroid.len() == centroid.len())
+        .map(|profile| {
+            let score = similarity(&profile.centroid, centroid);
+            (profile.name, score)
+        })
+        .filter(|(_, score)| *score >= config.match_threshold)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Profiles recorded through voice enrollment.
+pub fn enrolled<R: Runtime>(app: &AppHandle<R>) -> Vec<SpeakerProfile> {
+    if !settings::get().speaker_profiles.enabled {
+        return Vec::new();
+    }
+    load(app)
+        .map(|profiles| profiles.into_values().filter(|profile| profile.enrolled).collect())
+        .unwrap_or_default()
+}
+
+/// The enrolled speaker `embedding` belongs to, if any clears the match threshold.
+pub fn match_enrolled<'a>(profiles: &'a [SpeakerProfile], embedding: &[f32]) -> Option<&'a str> {
+    let threshold = settings::get().speaker_profiles.match_threshold;
+    profiles
+        .iter()
+        .filter(|profile| profile.centroid.len() == embedding.len())
+        .map(|profile| (profile, similarity(&profile.centroid, embedding)))
+        .filter(|(_, score)| *score >= threshold)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(profile, _)| profile.name.as_str())
+}
+
+/// Store `centroid` from an enrollment recording as `name`'s voice, replacing
+/// whatever was learned for that name from meetings.
+pub fn enroll<R: Runtime>(
+    app: &AppHandle<R>,
+    name: &str,
+    centroid: Vec<f32>,
+    samples: usize,
+) -> Result<SpeakerProfile, String> {
+    modify(app, |profiles| {
+        let profile = SpeakerProfile {
+            name: name.to_string(),
+            centroid,
+            samples,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+            enrolled: true,
+        };
+        profiles.insert(name.to_string(), profile.clone());
+        Ok(profile)
+    })
+}
+
+/// Fold a named speaker's voice from one meeting into their profile,
+/// creating it on first sight. Numbered placeholder names are ignored.
+pub fn learn<R: Runtime>(app: &AppHandle<R>, speakers: &[(String, Vec<f32>, usize)]) -> Result<usize, String> {
+    if !settings::get().speaker_profiles.enabled {
+        return Ok(0);
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    modify(app, |profiles| {
+        let mut learned = 0;
+        for (name, centroid, samples) in speakers {
+            if is_unnamed(name) || *samples == 0 {
+                continue;
+            }
+            let profile = profiles.entry(name.clone()).or_insert_with(|| SpeakerProfile {
+                name: name.clone(),
+                centroid: centroid.clone(),
+                samples: 0,
+                updated_at: now.clone(),
+                enrolled: false,
+            });
+            if profile.centroid.len() != centroid.len() {
+                profile.centroid = centroid.clone();
+                profile.samples = 0;
+            }
+            profile.centroid = weighted_centroid(&profile.centroid, profile.samples, centroid, *samples);
+            profile.samples += samples;
+            profile.updated_at = now.clone();
+            learned += 1;
+        }
+        Ok(learned)
+    })
+}
+
+#[command]
+pub fn list_speaker_profiles<R: Runtime>(app: AppHandle<R>) -> Result<Vec<SpeakerProfile>, String> {
+    let mut profiles: Vec<SpeakerProfile> = load(&app)?.into_values().collect();
+    profiles.sort_by(|a, b| b.samples.cmp(&a.samples).then_with(|| a.name.cmp(&b.name)));
+    Ok(profiles)
+}
+
+/// Rename a profile so the speaker carries the new name in future meetings.
+#[command]
+pub fn rename_speaker_profile<R: Runtime>(
+    app: AppHandle<R>,
+    name: String,
+    new_name: String,
+) -> Result<SpeakerProfile, String> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() || is_unnamed(&new_name) {
+        return Err(format!("'{}' is not a usable speaker name", new_name));
+    }
+    modify(&app, |profiles| {
+        if new_name != name && profiles.contains_key(&new_name) {
+            return Err(format!(
+                "A speaker named {} already exists; merge them instead",
+                new_name
+            ));
+        }
+        let mut profile = profiles
+            .remove(&name)
+            .ok_or_else(|| format!("No speaker profile named {}", name))?;
+        info!("Renaming speaker profile {} to {}", name, new_name);
+        profile.name = new_name.clone();
+        profile.updated_at = chrono::Utc::now().to_rfc3339();
+        profiles.insert(new_name, profile.clone());
+        Ok(profile)
+    })
+}
+
+/// Fold the `from` profiles into `into`, for one person learned under several names.
+#[command]
+pub fn merge_speaker_profiles<R: Runtime>(
+    app: AppHandle<R>,
+    into: String,
+    from: Vec<String>,
+) -> Result<SpeakerProfile, String> {
+    modify(&app, |profiles| {
+        let mut target = profiles
+            .remove(&into)
+            .ok_or_else(|| format!("No speaker profile named {}", into))?;
+        for name in from.iter().filter(|name| **name != into) {
+            let Some(source) = profiles.remove(name) else {
+                continue;
+            };
+            if source.centroid.len() == target.centroid.len() {
+                target.centroid = weighted_centroid(&target.centroid, target.samples, &source.centroid, source.samples);
+                target.samples += source.samples;
+            }
+        }
+        info!("Merged speaker profiles {:?} into {}", from, into);
+        target.updated_at = chrono::Utc::now().to_rfc3339();
+        profiles.insert(into.clone(), target.clone());
+        Ok(target)
+    })
+}
+
+#[command]
+pub fn delete_speaker_profile<R: Runtime>(app: AppHandle<R>, name: String) -> Result<(), String> {
+    modify(&app, |profiles| {
+        profiles
+            .remove(&name)
+            .map(|_| ())
+            .ok_or_else(|| format!("No speaker profile named {}", name))
+    })
+}
+
+#[command]
+pub fn get_speaker_profile_settings() -> SpeakerProfileSettings {
+    settings::get().speaker_profiles
+}
+
+#[command]
+pub fn set_speaker_profile_settings(
+    speaker_profile_settings: SpeakerProfileSettings,
+) -> Result<SpeakerProfileSettings, String> {
+    if !(0.0..=1.0).contains(&speaker_profile_settings.match_threshold) {
+        return Err("Match threshold must be between 0 and 1".to_string());
+    }
+    settings::update(|s| s.speaker_profiles = speaker_profile_settings).map(|s| s.speaker_profiles)
+}
@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::audio::embedding::similarity;
+use crate::settings;
+use crate::speaker_profiles::SpeakerProfile;
+
+/// Emitted with a [`Reassignment`] when earlier segments turn out to belong to
+/// an enrolled speaker after all.
+pub const SPEAKER_REASSIGNED_EVENT: &str = "speaker-reassigned";
+/// Unmatched segments kept around in case their speaker is recognized later.
+const MAX_UNMATCHED: usize = 200;
+/// A running voice this far (1 - cosine similarity) from its enrollment is
+/// reported as drifted.
+const DRIFT_WARNING: f32 = 0.1;
+/// Unmatched segments needed before they're considered one speaker's new voice.
+const SPLIT_SEGMENTS: usize = 4;
+/// How far below the match threshold a group of unmatched segments may sit
+/// from a quiet speaker's voice and still be taken for it.
+const SPLIT_MARGIN: f32 = 0.1;
+
+/// Where a segment sits in the recording.
+#[derive(Debug, Clone, Serialize)]
+pub struct SegmentRef {
+    /// Start, from the beginning of the recording.
+    pub offset_ms: u64,
+    pub duration_ms: u64,
+    /// Raw capture chunk and segment index within it, when the session is recorded.
+    pub chunk: Option<u64>,
+    pub segment: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReassignReason {
+    /// The voice drifted gradually and caught up with segments it had missed.
+    Drift,
+    /// The voice changed abruptly (new headset, different room) and split off
+    /// as an unrecognized speaker until enough of it was heard.
+    Split,
+}
+
+/// Segments already published under the microphone that belong to `speaker`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Reassignment {
+    pub speaker: String,
+    pub reason: ReassignReason,
+    pub segments: Vec<SegmentRef>,
+}
+
+struct TrackedSpeaker {
+    name: String,
+    /// Voice from enrollment; never updated.
+    anchor: Vec<f32>,
+    /// Voice as heard so far in this recording.
+    centroid: Vec<f32>,
+    samples: usize,
+    /// End of the last segment matched to this speaker.
+    last_heard_ms: u64,
+    drifted: bool,
+}
+
+struct Unmatched {
+    at: SegmentRef,
+    embedding: Vec<f32>,
+}
+
+fn normalized(mut values: Vec<f32>) -> Vec<f32> {
+    let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+    values.iter_mut().for_each(|v| *v /= norm);
+    values
+}
+
+fn mean(embeddings: &[&Vec<f32>]) -> Vec<f32> {
+    let mut sum = vec![0.0f32; embeddings[0].len()];
+    for embedding in embeddings {
+        for (total, value) in sum.iter_mut().zip(embedding.iter()) {
+            *total += value;
+        }
+    }
+    normalized(sum)
+}
+
+/// Matches live microphone segments to enrolled speakers over one recording.
+/// Each speaker's voice is updated as they talk, so fatigue or a headset
+/// change over a long meeting doesn't stop them being recognized, and
+/// segments missed while the voice moved are handed back to them.
+#[derive(Default)]
+pub struct EmbeddingManager {
+    speakers: Vec<TrackedSpeaker>,
+    unmatched: VecDeque<Unmatched>,
+}
+
+impl EmbeddingManager {
+    /// Follow the current enrolled profiles, keeping what's been learned about
+    /// speakers that are still enrolled.
+    pub fn sync(&mut self, enrolled: &[SpeakerProfile]) {
+        self.speakers
+            .retain(|speaker| enrolled.iter().any(|profile| profile.name == speaker.name));
+        for profile in enrolled {
+            match self.speakers.iter_mut().find(|speaker| speaker.name == profile.name) {
+                // Re-enrolled mid-meeting: start over from the new voice
+                Some(speaker) if speaker.anchor != profile.centroid => {
+                    speaker.anchor = profile.centroid.clone();
+                    speaker.centroid = profile.centroid.clone();
+                    speaker.samples = 0;
+                    speaker.drifted = false;
+                }
+                Some(_) => {}
+                None => self.speakers.push(TrackedSpeaker {
+                    name: profile.name.clone(),
+                    anchor: profile.centroid.clone(),
+                    centroid: profile.centroid.clone(),
+                    samples: 0,
+                    last_heard_ms: 0,
+                    drifted: false,
+                }),
+            }
+        }
+    }
+
+    /// The enrolled speaker of the segment at `at`, and any earlier segments
+    /// recognizing it showed to be theirs.
+    pub fn assign(&mut self, embedding: Vec<f32>, at: SegmentRef) -> (Option<String>, Option<Reassignment>) {
+        let config = settings::get().speaker_profiles;
+        let best = self
+            .speakers
+            .iter()
+            .enumerate()
+            .filter(|(_, speaker)| speaker.anchor.len() == embedding.len())
+            .map(|(index, speaker)| {
+                let score = if config.track_drift {
+                    similarity(&speaker.centroid, &embedding).max(similarity(&speaker.anchor, &embedding))
+                } else {
+                    similarity(&speaker.anchor, &embedding)
+                };
+                (index, score)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((index, score)) = best.filter(|(_, score)| *score >= config.match_threshold) {
+            if !config.track_drift {
+                return (Some(self.speakers[index].name.clone()), None);
+            }
+            self.update(index, &embedding, &at, config.drift_rate);
+            let reclaimed = self.reclaim(index, config.match_threshold);
+            let speaker = &self.speakers[index];
+            debug!("Matched {} ({:.2})", speaker.name, score);
+            return (Some(speaker.name.clone()), reclaimed);
+        }
+
+        if !config.track_drift || embedding.is_empty() {
+            return (None, None);
+        }
+        self.unmatched.push_back(Unmatched { at, embedding });
+        if self.unmatched.len() > MAX_UNMATCHED {
+            self.unmatched.pop_front();
+        }
+        match self.split(config.match_threshold) {
+            Some(reassignment) => (Some(reassignment.speaker.clone()), Some(reassignment)),
+            None => (None, None),
+        }
+    }
+
+    /// Move a speaker's running voice toward a newly matched segment: a plain
+    /// mean (counting enrollment as one segment) at first, then an exponential
+    /// average so it keeps following them.
+    fn update(&mut self, index: usize, embedding: &[f32], at: &SegmentRef, drift_rate: f32) {
+        let speaker = &mut self.speakers[index];
+        let rate = (1.0 / (speaker.samples + 2) as f32).max(drift_rate);
+        speaker.centroid = normalized(
+            speaker
+                .centroid
+                .iter()
+                .zip(embedding)
+                .map(|(old, new)| old * (1.0 - rate) + new * rate)
+                .collect(),
+        );
+        speaker.samples += 1;
+        speaker.last_heard_ms = speaker.last_heard_ms.max(at.offset_ms + at.duration_ms);
+        let drift = 1.0 - similarity(&speaker.centroid, &speaker.anchor);
+        if drift >= DRIFT_WARNING && !speaker.drifted {
+            info!("Voice of {} has drifted {:.2} from enrollment", speaker.name, drift);
+            speaker.drifted = true;
+        }
+    }
+
+    /// Unmatched segments that the speaker's updated voice now matches.
+    fn reclaim(&mut self, index: usize, threshold: f32) -> Option<Reassignment> {
+        let speaker = &self.speakers[index];
+        let (matched, rest): (Vec<Unmatched>, Vec<Unmatched>) = self
+            .unmatched
+            .drain(..)
+            .partition(|segment| similarity(&speaker.centroid, &segment.embedding) >= threshold);
+        self.unmatched = rest.into();
+        if matched.is_empty() {
+            return None;
+        }
+        info!("Reassigning {} earlier segments to {} after drift", matched.len(), speaker.name);
+        Some(Reassignment {
+            speaker: speaker.name.clone(),
+            reason: ReassignReason::Drift,
+            segments: matched.into_iter().map(|segment| segment.at).collect(),
+        })
+    }
+
+    /// Whether the newest unmatched segments form one consistent voice that is
+    /// close to an enrolled speaker who went quiet right when it appeared: the
+    /// same person split off as a new identity. If so the speaker takes that
+    /// voice and its segments.
+    fn split(&mut self, threshold: f32) -> Option<Reassignment> {
+        let newest = self.unmatched.back()?;
+        let group: Vec<usize> = (0..self.unmatched.len())
+            .filter(|&i| similarity(&self.unmatched[i].embedding, &newest.embedding) >= threshold)
+            .collect();
+        if group.len() < SPLIT_SEGMENTS {
+            return None;
+        }
+        let voice = mean(&group.iter().map(|&i| &self.unmatched[i].embedding).collect::<Vec<_>>());
+        let began_ms = group.iter().map(|&i| self.unmatched[i].at.offset_ms).min()?;
+        let (index, score) = self
+            .speakers
+            .iter()
+            .enumerate()
+            // Someone can't be talking in two voices at once
+            .filter(|(_, speaker)| speaker.samples > 0 && speaker.last_heard_ms <= began_ms)
+            .filter(|(_, speaker)| speaker.centroid.len() == voice.len())
+            .map(|(index, speaker)| (index, similarity(&speaker.centroid, &voice)))
+            .filter(|(_, score)| *score >= threshold - SPLIT_MARGIN)
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        let mut segments = Vec::with_capacity(group.len());
+        for &i in group.iter().rev() {
+            if let Some(segment) = self.unmatched.remove(i) {
+                segments.push(segment.at);
+            }
+        }
+        segments.reverse();
+        let speaker = &mut self.speakers[index];
+        info!(
+            "Unrecognized voice matches {} ({:.2}) who went quiet when it appeared; reassigning {} segments",
+            speaker.name,
+            score,
+            segments.len()
+        );
+        speaker.centroid = voice;
+        speaker.samples = segments.len();
+        speaker.last_heard_ms = segments
+            .iter()
+            .map(|segment| segment.offset_ms + segment.duration_ms)
+            .max()
+            .unwrap_or(speaker.last_heard_ms);
+        speaker.drifted = true;
+        Some(Reassignment {
+            speaker: speaker.name.clone(),
+            reason: ReassignReason::Split,
+            segments,
+        })
+    }
+}
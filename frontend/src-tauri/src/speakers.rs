@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use log::info;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+use crate::settings;
+
+/// Colors handed out to speakers without a configured one.
+const PALETTE: &[&str] = &[
+    "#2563eb", "#dc2626", "#16a34a", "#9333ea", "#ea580c", "#0891b2", "#c026d3", "#65a30d",
+];
+
+/// How a speaker is rendered by every consumer (main window, captions, exports).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SpeakerDisplay {
+    /// CSS hex color, e.g. `#2563eb`.
+    pub color: String,
+    pub initials: String,
+    #[serde(default)]
+    pub role: Option<String>,
+}
+
+/// Configured display metadata, keyed by speaker label.
+pub type SpeakerDirectory = BTreeMap<String, SpeakerDisplay>;
+
+fn default_initials(speaker: &str) -> String {
+    let initials: String = speaker
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter_map(|word| word.chars().next())
+        .filter(|c| c.is_alphanumeric())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+/// FNV-1a, so unconfigured speakers keep the same color across runs.
+fn palette_color(speaker: &str) -> &'static str {
+    let hash = speaker
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    PALETTE[(hash % PALETTE.len() as u64) as usize]
+}
+
+/// Display metadata for `speaker`: the configured entry, or a stable default.
+pub fn display_for(speaker: &str) -> SpeakerDisplay {
+    settings::get()
+        .speakers
+        .get(speaker)
+        .cloned()
+        .unwrap_or_else(|| SpeakerDisplay {
+            color: palette_color(speaker).to_string(),
+            initials: default_initials(speaker),
+            role: None,
+        })
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .map(|hex| matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+#[command]
+pub fn list_speaker_display() -> SpeakerDirectory {
+    settings::get().speakers
+}
+
+#[command]
+pub fn set_speaker_display<R: Runtime>(
+    app: AppHandle<R>,
+    speaker: String,
+    display: SpeakerDisplay,
+) -> Result<SpeakerDirectory, String> {
+    if speaker.trim().is_empty() {
+        return Err("Speaker label cannot be empty".to_string());
+    }
+    if !is_hex_color(&display.color) {
+        return Err(format!("Invalid color '{}', expected #rgb or #rrggbb", display.color));
+    }
+    if display.initials.trim().is_empty() || display.initials.chars().count() > 3 {
+        return Err("Initials must be 1 to 3 characters".to_string());
+    }
+
+    info!("Updating display metadata for speaker {}", speaker);
+    let speakers = settings::update(|s| {
+        s.speakers.insert(speaker, display);
+    })?
+    .speakers;
+    let _ = app.emit("speaker-display-changed", &speakers);
+    Ok(speakers)
+}
+
+#[command]
+pub fn remove_speaker_display<R: Runtime>(app: AppHandle<R>, speaker: String) -> Result<SpeakerDirectory, String> {
+    let speakers = settings::update(|s| {
+        s.speakers.remove(&speaker);
+    })?
+    .speakers;
+    let _ = app.emit("speaker-display-changed", &speakers);
+    Ok(speakers)
+}
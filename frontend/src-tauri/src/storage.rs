@@ -0,0 +1,576 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use crate::{encryption, integrity};
+use crate::paragraphs::{paragraphs, Paragraph};
+use crate::replay::{self, ReplayConfig, ReplayMode, ReplayRecorder};
+use crate::{retry_queue, settings, transcript_store, TranscriptUpdate};
+
+const MEETINGS_DIR: &str = "meetings";
+/// Archived meetings are moved here, out of listings and searches.
+const ARCHIVE_DIR: &str = "archive";
+pub const RAW_DIR: &str = "raw";
+pub const DERIVED_DIR: &str = "derived";
+/// Previous derived versions are moved here before regeneration.
+const HISTORY_DIR: &str = "history";
+const MANIFEST_FILE: &str = "meeting.json";
+const RAW_SESSION_FILE: &str = "session";
+pub const TRANSCRIPT_FILE: &str = "transcript.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Keep each meeting's captured audio and original engine output so
+    /// derived data can be regenerated later.
+    pub keep_raw: bool,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self { keep_raw: true }
+    }
+}
+
+/// Per-meeting record of what is stored. Raw files are write-once; derived
+/// files can be rebuilt from them at any time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingManifest {
+    pub id: String,
+    /// Name given when the session was started, e.g. "Weekly sync".
+    #[serde(default)]
+    pub name: Option<String>,
+    pub created_at: String,
+    pub app_version: String,
+    pub raw_files: Vec<String>,
+    pub derived_files: Vec<String>,
+    /// When derived data was last generated, and by which app version.
+    pub derived_at: Option<String>,
+    pub derived_by: Option<String>,
+    /// Organization-specific fields such as client name or project code.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Criteria for `search_meetings`; every given criterion must match.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MeetingFilter {
+    /// Meetings carrying all of these tags (case-insensitive).
+    pub tags: Vec<String>,
+    /// Metadata fields that must equal these values (values case-insensitive).
+    pub metadata: BTreeMap<String, String>,
+    /// Case-insensitive substring of the derived transcript.
+    pub text: Option<String>,
+}
+
+impl MeetingFilter {
+    fn matches(&self, manifest: &MeetingManifest, dir: &Path) -> bool {
+        let has_tags = self
+            .tags
+            .iter()
+            .all(|tag| manifest.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())));
+        let has_metadata = self.metadata.iter().all(|(key, value)| {
+            manifest
+                .metadata
+                .get(key)
+                .map(|v| v.eq_ignore_ascii_case(value.trim()))
+                .unwrap_or(false)
+        });
+        if !has_tags || !has_metadata {
+            return false;
+        }
+        match self.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(text) => encryption::read_to_string(dir.join(DERIVED_DIR).join(TRANSCRIPT_FILE))
+                .map(|transcript| transcript.to_lowercase().contains(&text.to_lowercase()))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegenerateResult {
+    pub meeting_id: String,
+    pub updates: usize,
+    /// Where the replaced derived files were archived, if there were any.
+    pub archived_to: Option<String>,
+}
+
+static CURRENT_SESSION: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+fn meetings_root<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(MEETINGS_DIR))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+pub fn meeting_dir<R: Runtime>(app: &AppHandle<R>, meeting_id: &str) -> Result<PathBuf, String> {
+    if meeting_id.is_empty() || meeting_id.contains(['/', '\\']) || meeting_id.starts_with('.') {
+        return Err(format!("Invalid meeting id '{}'", meeting_id));
+    }
+    Ok(meetings_root(app)?.join(meeting_id))
+}
+
+fn ensure_not_recording(meeting_id: &str) -> Result<(), String> {
+    if current_session().as_deref() == Some(meeting_id) {
+        return Err(format!("Meeting {} is still being recorded", meeting_id));
+    }
+    Ok(())
+}
+
+/// Permanently remove a stored meeting.
+pub fn delete_meeting<R: Runtime>(app: &AppHandle<R>, meeting_id: &str) -> Result<(), String> {
+    ensure_not_recording(meeting_id)?;
+    let dir = meeting_dir(app, meeting_id)?;
+    read_manifest(&dir)?;
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to delete meeting {}: {}", meeting_id, e))?;
+    transcript_store::delete_session(meeting_id);
+    info!("Deleted stored meeting {}", meeting_id);
+    Ok(())
+}
+
+/// Move a stored meeting into the archive folder. Returns its new location.
+pub fn archive_meeting<R: Runtime>(app: &AppHandle<R>, meeting_id: &str) -> Result<PathBuf, String> {
+    ensure_not_recording(meeting_id)?;
+    let dir = meeting_dir(app, meeting_id)?;
+    read_manifest(&dir)?;
+    let archive = app
+        .path()
+        .app_data_dir()
+        .map(|root| root.join(ARCHIVE_DIR))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&archive).map_err(|e| format!("Failed to create archive directory: {}", e))?;
+    let target = archive.join(meeting_id);
+    if target.exists() {
+        return Err(format!("Meeting {} is already archived", meeting_id));
+    }
+    fs::rename(&dir, &target).map_err(|e| format!("Failed to archive meeting {}: {}", meeting_id, e))?;
+    info!("Archived stored meeting {} to {}", meeting_id, target.display());
+    Ok(target)
+}
+
+pub fn raw_session_path(dir: &Path) -> PathBuf {
+    dir.join(RAW_DIR)
+        .join(format!("{}.{}", RAW_SESSION_FILE, replay::REPLAY_EXTENSION))
+}
+
+pub fn read_manifest(dir: &Path) -> Result<MeetingManifest, String> {
+    let content = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("No stored meeting at {:?}: {}", dir, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid meeting manifest: {}", e))
+}
+
+fn write_manifest(dir: &Path, manifest: &MeetingManifest) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(dir.join(MANIFEST_FILE), content).map_err(|e| format!("Failed to write manifest: {}", e))
+}
+
+pub fn list_files(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Mark raw files read-only so nothing downstream can rewrite the originals.
+fn seal_raw(dir: &Path) {
+    for name in list_files(&dir.join(RAW_DIR)) {
+        let path = dir.join(RAW_DIR).join(name);
+        match fs::metadata(&path) {
+            Ok(metadata) => {
+                let mut permissions = metadata.permissions();
+                permissions.set_readonly(true);
+                if let Err(e) = fs::set_permissions(&path, permissions) {
+                    warn!("Failed to seal raw file {:?}: {}", path, e);
+                }
+            }
+            Err(e) => warn!("Failed to read raw file {:?}: {}", path, e),
+        }
+    }
+}
+
+/// Create the meeting's storage, which becomes the current session, and a
+/// recorder writing raw capture into it. Returns `None` when raw storage is
+/// disabled or could not be set up.
+pub fn begin_session<R: Runtime>(
+    app: &AppHandle<R>,
+    config: ReplayConfig,
+    name: Option<String>,
+) -> Option<ReplayRecorder> {
+    if let Ok(mut current) = CURRENT_SESSION.lock() {
+        *current = None;
+    }
+    let meeting_id = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let dir = match meeting_dir(app, &meeting_id) {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("Raw capture disabled for this session: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = fs::create_dir_all(dir.join(DERIVED_DIR)) {
+        error!("Failed to create meeting storage {:?}: {}", dir, e);
+        return None;
+    }
+
+    let manifest = MeetingManifest {
+        id: meeting_id.clone(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: config.app_version.clone(),
+        raw_files: Vec::new(),
+        derived_files: Vec::new(),
+        derived_at: None,
+        derived_by: None,
+        metadata: BTreeMap::new(),
+        tags: Vec::new(),
+        name,
+    };
+    if let Err(e) = write_manifest(&dir, &manifest) {
+        error!("Meeting storage disabled for this session: {}", e);
+        return None;
+    }
+    if let Ok(mut current) = CURRENT_SESSION.lock() {
+        *current = Some(meeting_id.clone());
+    }
+    if !settings::get().storage.keep_raw {
+        return None;
+    }
+
+    match ReplayRecorder::create(raw_session_path(&dir), config) {
+        Ok(recorder) => {
+            info!("Capturing raw data for meeting {} in {:?}", meeting_id, dir);
+            Some(recorder)
+        }
+        Err(e) => {
+            warn!("Raw capture disabled for this session: {}", e);
+            None
+        }
+    }
+}
+
+/// Store a meeting recorded outside this app, with its transcript and any
+/// other derived files. There's no raw capture to regenerate it from.
+pub fn import_meeting<R: Runtime>(
+    app: &AppHandle<R>,
+    manifest: MeetingManifest,
+    transcript: &[TranscriptUpdate],
+    derived: &[(&str, String)],
+) -> Result<PathBuf, String> {
+    let dir = meeting_dir(app, &manifest.id)?;
+    if dir.exists() {
+        return Err(format!("Meeting {} already exists", manifest.id));
+    }
+    fs::create_dir_all(dir.join(DERIVED_DIR)).map_err(|e| format!("Failed to create meeting storage: {}", e))?;
+    write_manifest(&dir, &manifest)?;
+    let mut files = vec![(TRANSCRIPT_FILE, transcript_json(transcript)?)];
+    files.extend(derived.iter().map(|(name, content)| (*name, content.clone())));
+    replace_derived(&dir, &files)?;
+    Ok(dir)
+}
+
+/// Id of the meeting currently (or most recently) recorded.
+pub fn current_session() -> Option<String> {
+    CURRENT_SESSION.lock().ok().and_then(|current| current.clone())
+}
+
+/// Seal the raw data of the session that just ended and build its derived transcript.
+pub async fn finish_session<R: Runtime>(app: &AppHandle<R>) {
+    let Some(meeting_id) = current_session() else {
+        return;
+    };
+    match meeting_dir(app, &meeting_id) {
+        Ok(dir) if !raw_session_path(&dir).exists() => {
+            info!("Meeting {} has no raw capture to derive a transcript from", meeting_id);
+        }
+        Ok(dir) => {
+            seal_raw(&dir);
+            if let Err(e) = regenerate(app, &meeting_id, ReplayMode::Recorded).await {
+                error!("Failed to derive transcript for meeting {}: {}", meeting_id, e);
+            }
+        }
+        Err(e) => error!("Failed to finish meeting storage: {}", e),
+    }
+}
+
+/// Rebuild derived data from raw capture, archiving the previous version.
+async fn regenerate<R: Runtime>(app: &AppHandle<R>, meeting_id: &str, mode: ReplayMode) -> Result<RegenerateResult, String> {
+    let dir = meeting_dir(app, meeting_id)?;
+    let mut manifest = read_manifest(&dir)?;
+    let raw = raw_session_path(&dir);
+    if !raw.exists() {
+        return Err(format!("Meeting {} has no raw capture to regenerate from", meeting_id));
+    }
+
+    let mut result = replay::run_replay(&raw, mode, |_| {}).await?;
+    // The raw capture still holds the original failures
+    retry_queue::add_recovered(app, meeting_id, &mut result.updates)?;
+
+    manifest.raw_files = list_files(&dir.join(RAW_DIR));
+    write_manifest(&dir, &manifest)?;
+    let archived_to = replace_derived(&dir, &[(TRANSCRIPT_FILE, transcript_json(&result.updates)?)])?;
+
+    info!(
+        "Regenerated derived data for meeting {} ({} updates, {:?} mode)",
+        meeting_id,
+        result.updates.len(),
+        mode
+    );
+    Ok(RegenerateResult {
+        meeting_id: meeting_id.to_string(),
+        updates: result.updates.len(),
+        archived_to,
+    })
+}
+
+/// Copy the meeting's current derived files into a timestamped history folder.
+fn archive_derived(dir: &Path) -> Result<Option<String>, String> {
+    let derived = dir.join(DERIVED_DIR);
+    let existing = list_files(&derived);
+    if existing.is_empty() {
+        return Ok(None);
+    }
+    let archive = derived
+        .join(HISTORY_DIR)
+        .join(chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string());
+    fs::create_dir_all(&archive).map_err(|e| format!("Failed to create derived history: {}", e))?;
+    for name in &existing {
+        fs::copy(derived.join(name), archive.join(name))
+            .map_err(|e| format!("Failed to archive derived file {}: {}", name, e))?;
+    }
+    Ok(Some(archive.to_string_lossy().to_string()))
+}
+
+/// Archive the current derived data, then write `files` as its new version.
+/// Returns where the previous version was archived, if there was one.
+pub fn replace_derived(dir: &Path, files: &[(&str, String)]) -> Result<Option<String>, String> {
+    let mut manifest = read_manifest(dir)?;
+    let archived_to = archive_derived(dir)?;
+    let derived = dir.join(DERIVED_DIR);
+    for (name, content) in files {
+        write_derived_file(&derived, name, content)?;
+    }
+
+    manifest.derived_files = list_files(&derived);
+    manifest.derived_at = Some(chrono::Utc::now().to_rfc3339());
+    manifest.derived_by = Some(env!("CARGO_PKG_VERSION").to_string());
+    write_manifest(dir, &manifest)?;
+    integrity::seal_if_enabled(dir);
+    Ok(archived_to)
+}
+
+pub fn transcript_json(updates: &[TranscriptUpdate]) -> Result<String, String> {
+    serde_json::to_string_pretty(updates).map_err(|e| format!("Failed to serialize transcript: {}", e))
+}
+
+fn write_derived_file(derived: &Path, name: &str, content: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid derived file name '{}'", name));
+    }
+    fs::create_dir_all(derived).map_err(|e| format!("Failed to create derived directory: {}", e))?;
+    encryption::write(derived.join(name), content).map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+/// Rebuild a meeting's derived data (merged transcript) from its raw capture,
+/// e.g. after merge or accumulation improvements. `retranscribe` re-sends the
+/// raw audio to the engine instead of reusing its original output.
+#[command]
+pub async fn regenerate_derived<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    retranscribe: Option<bool>,
+) -> Result<RegenerateResult, String> {
+    let mode = if retranscribe.unwrap_or(false) {
+        ReplayMode::Retranscribe
+    } else {
+        ReplayMode::Recorded
+    };
+    regenerate(&app, &meeting_id, mode).await
+}
+
+/// Store a derived artifact produced outside the backend (summary, edits).
+#[command]
+pub fn save_derived<R: Runtime>(app: AppHandle<R>, meeting_id: String, name: String, content: String) -> Result<(), String> {
+    let dir = meeting_dir(&app, &meeting_id)?;
+    let mut manifest = read_manifest(&dir)?;
+    write_derived_file(&dir.join(DERIVED_DIR), &name, &content)?;
+    manifest.derived_files = list_files(&dir.join(DERIVED_DIR));
+    write_manifest(&dir, &manifest)?;
+    integrity::seal_if_enabled(&dir);
+    Ok(())
+}
+
+/// A stored meeting's derived transcript, grouped into speaker turns.
+#[command]
+pub fn get_stored_transcript<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<Vec<Paragraph>, String> {
+    read_transcript(&meeting_dir(&app, &meeting_id)?).map(|updates| paragraphs(&updates))
+}
+
+/// The derived transcript of the meeting stored in `dir`.
+pub fn read_transcript(dir: &Path) -> Result<Vec<TranscriptUpdate>, String> {
+    read_transcript_file(&dir.join(DERIVED_DIR).join(TRANSCRIPT_FILE))
+}
+
+/// Archived derived versions of the meeting stored in `dir`, newest first.
+pub fn derived_versions(dir: &Path) -> Vec<String> {
+    let mut versions: Vec<String> = fs::read_dir(dir.join(DERIVED_DIR).join(HISTORY_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().join(TRANSCRIPT_FILE).is_file())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    versions.sort_by(|a, b| b.cmp(a));
+    versions
+}
+
+/// The transcript as it was in an archived derived `version`.
+pub fn read_archived_transcript(dir: &Path, version: &str) -> Result<Vec<TranscriptUpdate>, String> {
+    if version.is_empty() || version.contains(['/', '\\']) || version.starts_with('.') {
+        return Err(format!("Invalid derived version '{}'", version));
+    }
+    read_transcript_file(&dir.join(DERIVED_DIR).join(HISTORY_DIR).join(version).join(TRANSCRIPT_FILE))
+}
+
+fn read_transcript_file(path: &Path) -> Result<Vec<TranscriptUpdate>, String> {
+    let content =
+        encryption::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid transcript {}: {}", path.display(), e))
+}
+
+#[command]
+pub fn list_stored_meetings<R: Runtime>(app: AppHandle<R>) -> Result<Vec<MeetingManifest>, String> {
+    search_meetings(app, MeetingFilter::default())
+}
+
+/// Stored meetings matching `filter`, newest first.
+#[command]
+pub fn search_meetings<R: Runtime>(app: AppHandle<R>, filter: MeetingFilter) -> Result<Vec<MeetingManifest>, String> {
+    let root = meetings_root(&app)?;
+    let mut manifests: Vec<MeetingManifest> = fs::read_dir(&root)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let dir = entry.path();
+                    read_manifest(&dir).ok().filter(|manifest| filter.matches(manifest, &dir))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    manifests.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(manifests)
+}
+
+/// Replace a meeting's custom metadata and tags. Empty keys and tags are
+/// dropped and tags are de-duplicated case-insensitively.
+#[command]
+pub fn set_meeting_metadata<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    metadata: BTreeMap<String, String>,
+    tags: Vec<String>,
+) -> Result<MeetingManifest, String> {
+    let dir = meeting_dir(&app, &meeting_id)?;
+    let mut manifest = read_manifest(&dir)?;
+    manifest.metadata = metadata
+        .into_iter()
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+    manifest.tags.clear();
+    add_tags(&mut manifest, &tags);
+    write_manifest(&dir, &manifest)?;
+    integrity::seal_if_enabled(&dir);
+    Ok(manifest)
+}
+
+fn add_tags(manifest: &mut MeetingManifest, tags: &[String]) {
+    for tag in tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        if !manifest.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            manifest.tags.push(tag.to_string());
+        }
+    }
+}
+
+/// Add and remove tags on a meeting, keeping the rest.
+pub fn update_tags<R: Runtime>(
+    app: &AppHandle<R>,
+    meeting_id: &str,
+    add: &[String],
+    remove: &[String],
+) -> Result<MeetingManifest, String> {
+    let dir = meeting_dir(app, meeting_id)?;
+    let mut manifest = read_manifest(&dir)?;
+    manifest
+        .tags
+        .retain(|tag| !remove.iter().any(|r| r.trim().eq_ignore_ascii_case(tag)));
+    add_tags(&mut manifest, add);
+    write_manifest(&dir, &manifest)?;
+    integrity::seal_if_enabled(&dir);
+    Ok(manifest)
+}
+
+/// Set metadata values on a meeting, keeping its other keys. Empty values
+/// remove their key.
+pub fn merge_metadata<R: Runtime>(
+    app: &AppHandle<R>,
+    meeting_id: &str,
+    values: BTreeMap<String, String>,
+) -> Result<MeetingManifest, String> {
+    let dir = meeting_dir(app, meeting_id)?;
+    let mut manifest = read_manifest(&dir)?;
+    for (key, value) in values {
+        let (key, value) = (key.trim().to_string(), value.trim().to_string());
+        if key.is_empty() {
+            continue;
+        }
+        if value.is_empty() {
+            manifest.metadata.remove(&key);
+        } else {
+            manifest.metadata.insert(key, value);
+        }
+    }
+    write_manifest(&dir, &manifest)?;
+    integrity::seal_if_enabled(&dir);
+    Ok(manifest)
+}
+
+/// Every tag in use, with how many meetings carry it.
+#[command]
+pub fn list_meeting_tags<R: Runtime>(app: AppHandle<R>) -> Result<BTreeMap<String, usize>, String> {
+    let mut counts = BTreeMap::new();
+    for manifest in search_meetings(app, MeetingFilter::default())? {
+        for tag in manifest.tags {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+#[command]
+pub fn get_storage_settings() -> StorageSettings {
+    settings::get().storage
+}
+
+#[command]
+pub fn set_storage_settings(storage_settings: StorageSettings) -> Result<StorageSettings, String> {
+    settings::update(|s| s.storage = storage_settings).map(|s| s.storage)
+}
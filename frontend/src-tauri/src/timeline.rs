@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::audio::vad::EnergyVad;
+use crate::recluster::read_derived;
+use crate::replay;
+use crate::storage;
+use crate::transcript_stream::speaker_of;
+use crate::TranscriptUpdate;
+
+/// Derived file caching a meeting's full-resolution timeline.
+pub const TIMELINE_FILE: &str = "timeline.json";
+/// Resolution of the cached timeline, in seconds of compressed time.
+const BUCKET_SECS: f64 = 0.25;
+/// Silences at least this long are shortened on the timeline...
+const MIN_GAP_SECS: f64 = 3.0;
+/// ...to this long, so a pause still shows as a pause.
+const KEPT_SILENCE_SECS: f64 = 1.0;
+
+/// A silence cut out of the timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineGap {
+    /// Where on the compressed timeline the cut is.
+    pub at_secs: f64,
+    /// The meeting time removed.
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimelineBucket {
+    /// Largest absolute sample, 0.0..=1.0.
+    pub peak: f32,
+    /// Fraction of the bucket that is speech.
+    pub speech: f32,
+    /// Index into `speakers` of whoever spoke most in the bucket.
+    pub speaker: Option<usize>,
+}
+
+/// Waveform peaks and a speech/speaker heatmap of a meeting, with long
+/// silences shortened, for drawing a timeline scrubber. Meeting time `t`
+/// sits at `t` minus the length of every gap ending before it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingTimeline {
+    pub meeting_id: String,
+    pub duration_secs: f64,
+    pub compressed_secs: f64,
+    /// Compressed seconds each bucket covers.
+    pub bucket_secs: f64,
+    pub speakers: Vec<String>,
+    pub buckets: Vec<TimelineBucket>,
+    pub gaps: Vec<TimelineGap>,
+    /// Without raw capture the heatmap comes from the transcript alone and
+    /// every peak is zero.
+    pub has_audio: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedTimeline {
+    /// The meeting's `derived_at` when the timeline was computed; a
+    /// regenerated transcript makes the cache stale.
+    derived_at: Option<String>,
+    timeline: MeetingTimeline,
+}
+
+fn span(update: &TranscriptUpdate) -> (f64, f64) {
+    let (start, end) = match update.timestamp.split_once(" - ") {
+        Some((start, end)) => (start.trim().parse().unwrap_or(0.0), end.trim().parse().unwrap_or(0.0)),
+        None => (update.timestamp.trim().parse().unwrap_or(0.0), 0.0),
+    };
+    (start, f64::max(start, end))
+}
+
+/// Stretches of meeting time kept on the timeline, with where each starts on it.
+struct Kept {
+    spans: Vec<(f64, f64, f64)>,
+    gaps: Vec<TimelineGap>,
+}
+
+impl Kept {
+    /// Keep everything but the middle of each silence of `MIN_GAP_SECS` or more.
+    fn new(speech: &[bool], frame_secs: f64, duration: f64) -> Self {
+        let mut spans = Vec::new();
+        let mut gaps = Vec::new();
+        let (mut kept_from, mut removed) = (0.0, 0.0);
+        let mut frame = 0;
+        while frame < speech.len() {
+            if speech[frame] {
+                frame += 1;
+                continue;
+            }
+            let silent_from = frame;
+            while frame < speech.len() && !speech[frame] {
+                frame += 1;
+            }
+            let (start, end) = (silent_from as f64 * frame_secs, (frame as f64 * frame_secs).min(duration));
+            if end - start < MIN_GAP_SECS {
+                continue;
+            }
+            let (cut_start, cut_end) = (start + KEPT_SILENCE_SECS / 2.0, end - KEPT_SILENCE_SECS / 2.0);
+            spans.push((kept_from, cut_start, kept_from - removed));
+            gaps.push(TimelineGap {
+                at_secs: cut_start - removed,
+                start_secs: cut_start,
+                end_secs: cut_end,
+            });
+            removed += cut_end - cut_start;
+            kept_from = cut_end;
+        }
+        spans.push((kept_from, duration, kept_from - removed));
+        Self { spans, gaps }
+    }
+
+    fn compressed_secs(&self) -> f64 {
+        self.spans.last().map(|(start, end, at)| at + (end - start)).unwrap_or(0.0)
+    }
+
+    /// Compressed position of meeting time `t`, if it wasn't cut.
+    fn position(&self, t: f64) -> Option<f64> {
+        self.spans
+            .iter()
+            .find(|(start, end, _)| t >= *start && t < *end)
+            .map(|(start, _, at)| at + (t - start))
+    }
+
+    /// The parts of `start..end` that were kept, as compressed ranges.
+    fn overlaps(&self, start: f64, end: f64) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.spans.iter().filter_map(move |(kept_start, kept_end, at)| {
+            let (from, to) = (start.max(*kept_start), end.min(*kept_end));
+            (to > from).then(|| (at + (from - kept_start), at + (to - kept_start)))
+        })
+    }
+}
+
+fn compute(meeting_id: &str, audio: Option<(Vec<f32>, u32)>, transcript: &[TranscriptUpdate]) -> MeetingTimeline {
+    let vad = EnergyVad::default();
+    let frame_secs = vad.frame_ms.max(1) as f64 / 1000.0;
+    let spans: Vec<(f64, f64)> = transcript
+        .iter()
+        .filter(|update| !update.text.trim().is_empty())
+        .map(span)
+        .collect();
+    let transcript_end = spans.iter().map(|(_, end)| *end).fold(0.0, f64::max);
+    let duration = match &audio {
+        Some((samples, rate)) => samples.len() as f64 / *rate as f64,
+        None => transcript_end,
+    };
+
+    // Speech is whatever the VAD hears or the transcript covers
+    let mut speech = match &audio {
+        Some((samples, rate)) => vad.frames(samples, *rate),
+        None => vec![false; (duration / frame_secs).ceil() as usize],
+    };
+    for (start, end) in &spans {
+        let from = (start / frame_secs) as usize;
+        let to = ((end / frame_secs).ceil() as usize).min(speech.len());
+        for frame in speech.iter_mut().take(to).skip(from) {
+            *frame = true;
+        }
+    }
+
+    let kept = Kept::new(&speech, frame_secs, duration);
+    let compressed = kept.compressed_secs();
+    let mut buckets = vec![TimelineBucket::default(); (compressed / BUCKET_SECS).ceil() as usize];
+    if buckets.is_empty() {
+        return MeetingTimeline {
+            meeting_id: meeting_id.to_string(),
+            duration_secs: duration,
+            compressed_secs: 0.0,
+            bucket_secs: BUCKET_SECS,
+            speakers: Vec::new(),
+            buckets,
+            gaps: kept.gaps,
+            has_audio: audio.is_some(),
+        };
+    }
+    let last = buckets.len() - 1;
+    let bucket_of = |t: f64| ((t / BUCKET_SECS) as usize).min(last);
+
+    if let Some((samples, rate)) = &audio {
+        let rate = *rate as f64;
+        for (start, end, at) in &kept.spans {
+            let from = (start * rate) as usize;
+            let to = ((end * rate) as usize).min(samples.len());
+            for (i, sample) in samples.iter().enumerate().take(to).skip(from) {
+                let bucket = &mut buckets[bucket_of(at + (i as f64 / rate - start))];
+                bucket.peak = bucket.peak.max(sample.abs().min(1.0));
+            }
+        }
+    }
+
+    let mut speech_frames = vec![0usize; buckets.len()];
+    let mut frames = vec![0usize; buckets.len()];
+    for (frame, is_speech) in speech.iter().enumerate() {
+        if let Some(at) = kept.position(frame as f64 * frame_secs) {
+            let bucket = bucket_of(at);
+            frames[bucket] += 1;
+            speech_frames[bucket] += *is_speech as usize;
+        }
+    }
+    for (bucket, (speech, total)) in buckets.iter_mut().zip(speech_frames.iter().zip(&frames)) {
+        if *total > 0 {
+            bucket.speech = *speech as f32 / *total as f32;
+        }
+    }
+
+    // Seconds each speaker talked in each bucket
+    let mut speakers: Vec<String> = Vec::new();
+    let mut talked: Vec<HashMap<usize, f64>> = vec![HashMap::new(); buckets.len()];
+    for (update, (start, end)) in transcript.iter().filter(|update| !update.text.trim().is_empty()).zip(&spans) {
+        let name = speaker_of(update);
+        let speaker = match speakers.iter().position(|s| *s == name) {
+            Some(index) => index,
+            None => {
+                speakers.push(name);
+                speakers.len() - 1
+            }
+        };
+        for (from, to) in kept.overlaps(*start, *end) {
+            let mut t = from;
+            while t < to {
+                let bucket = bucket_of(t);
+                let bucket_end = ((bucket + 1) as f64 * BUCKET_SECS).min(to);
+                *talked[bucket].entry(speaker).or_default() += (bucket_end - t).max(0.0);
+                if bucket_end <= t {
+                    break;
+                }
+                t = bucket_end;
+            }
+        }
+    }
+    for (bucket, talked) in buckets.iter_mut().zip(talked) {
+        bucket.speaker = talked
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(speaker, _)| speaker);
+    }
+
+    MeetingTimeline {
+        meeting_id: meeting_id.to_string(),
+        duration_secs: duration,
+        compressed_secs: compressed,
+        bucket_secs: BUCKET_SECS,
+        speakers,
+        buckets,
+        gaps: kept.gaps,
+        has_audio: audio.is_some(),
+    }
+}
+
+/// Merge the cached buckets into at most `count`, for drawing at a given width.
+fn downsample(mut timeline: MeetingTimeline, count: usize) -> MeetingTimeline {
+    let count = count.max(1);
+    if timeline.buckets.len() <= count {
+        return timeline;
+    }
+    let per = timeline.buckets.len().div_ceil(count);
+    timeline.bucket_secs *= per as f64;
+    timeline.buckets = timeline
+        .buckets
+        .chunks(per)
+        .map(|group| {
+            let mut votes: HashMap<usize, usize> = HashMap::new();
+            for speaker in group.iter().filter_map(|bucket| bucket.speaker) {
+                *votes.entry(speaker).or_default() += 1;
+            }
+            TimelineBucket {
+                peak: group.iter().map(|bucket| bucket.peak).fold(0.0, f32::max),
+                speech: group.iter().map(|bucket| bucket.speech).sum::<f32>() / group.len() as f32,
+                speaker: votes.into_iter().max_by_key(|(_, votes)| *votes).map(|(speaker, _)| speaker),
+            }
+        })
+        .collect();
+    timeline
+}
+
+fn load_audio(dir: &Path) -> Result<Option<(Vec<f32>, u32)>, String> {
+    let raw = storage::raw_session_path(dir);
+    if !raw.exists() {
+        return Ok(None);
+    }
+    replay::read_replay_audio(&raw).map(Some)
+}
+
+/// A stored meeting's waveform peaks and speech/speaker heatmap, merged into
+/// at most `buckets` buckets when given. It's computed from the raw capture
+/// on first request and cached with the meeting's derived files, so the
+/// webview never has to load the audio itself.
+#[command]
+pub async fn get_meeting_timeline<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    buckets: Option<usize>,
+) -> Result<MeetingTimeline, String> {
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    let manifest = storage::read_manifest(&dir)?;
+    let cached = read_derived::<CachedTimeline>(&dir, TIMELINE_FILE)
+        .filter(|cached| cached.derived_at == manifest.derived_at);
+    let timeline = match cached {
+        Some(cached) => cached.timeline,
+        None => {
+            let transcript = storage::read_transcript(&dir).unwrap_or_default();
+            let id = meeting_id.clone();
+            let source = dir.clone();
+            let timeline = tokio::task::spawn_blocking(move || {
+                load_audio(&source).map(|audio| compute(&id, audio, &transcript))
+            })
+            .await
+            .map_err(|e| format!("Timeline task failed: {}", e))??;
+
+            let cache = CachedTimeline {
+                derived_at: manifest.derived_at,
+                timeline,
+            };
+            let content =
+                serde_json::to_string(&cache).map_err(|e| format!("Failed to serialize timeline: {}", e))?;
+            storage::save_derived(app.clone(), meeting_id.clone(), TIMELINE_FILE.to_string(), content)?;
+            info!(
+                "Computed timeline for meeting {} ({} buckets, {} gaps)",
+                meeting_id,
+                cache.timeline.buckets.len(),
+                cache.timeline.gaps.len()
+            );
+            cache.timeline
+        }
+    };
+    Ok(match buckets {
+        Some(count) => downsample(timeline, count),
+        None => timeline,
+    })
+}
@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+
+use crate::storage::{self, TRANSCRIPT_FILE};
+use crate::TranscriptUpdate;
+
+/// Lines less alike than this are reported as a removal plus an addition
+/// rather than as one changed line.
+const MIN_PAIR_SIMILARITY: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Unchanged,
+    Changed,
+    Added,
+    Removed,
+}
+
+/// One line of the aligned transcripts.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffEntry {
+    /// Position in the diff; pass it to `apply_transcript_diff` to reject this change.
+    pub id: usize,
+    pub kind: ChangeKind,
+    pub old: Option<TranscriptUpdate>,
+    pub new: Option<TranscriptUpdate>,
+    /// Word overlap of the old and new text, 0 when either side is missing.
+    pub similarity: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptDiff {
+    pub meeting_id: String,
+    /// Archived derived version the current transcript is compared against.
+    pub base_version: String,
+    pub changed: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub entries: Vec<DiffEntry>,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Dice coefficient of the two texts' words, counting repeats.
+fn similarity(a: &[String], b: &[String]) -> f32 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in a {
+        *counts.entry(word).or_default() += 1;
+    }
+    let shared = b
+        .iter()
+        .filter(|word| match counts.get_mut(word.as_str()) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        })
+        .count();
+    2.0 * shared as f32 / (a.len() + b.len()) as f32
+}
+
+/// Align `old` and `new` line by line, maximizing the total similarity of
+/// paired lines, and classify every line.
+fn align(old: &[TranscriptUpdate], new: &[TranscriptUpdate]) -> Vec<DiffEntry> {
+    let old_words: Vec<Vec<String>> = old.iter().map(|u| words(&u.text)).collect();
+    let new_words: Vec<Vec<String>> = new.iter().map(|u| words(&u.text)).collect();
+    let (n, m) = (old.len(), new.len());
+    let pair = |i: usize, j: usize| {
+        let score = similarity(&old_words[i], &new_words[j]);
+        (score >= MIN_PAIR_SIMILARITY).then_some(score)
+    };
+
+    // best[i][j]: best total for old[i..] against new[j..]
+    let mut best = vec![vec![0.0f32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let paired = pair(i, j).map(|score| score + best[i + 1][j + 1]).unwrap_or(f32::MIN);
+            best[i][j] = paired.max(best[i + 1][j]).max(best[i][j + 1]);
+        }
+    }
+
+    let mut entries = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let paired = if i < n && j < m { pair(i, j) } else { None };
+        let entry = match paired {
+            Some(score) if score + best[i + 1][j + 1] >= best[i][j] => {
+                let kind = if old[i].text.trim() == new[j].text.trim() {
+                    ChangeKind::Unchanged
+                } else {
+                    ChangeKind::Changed
+                };
+                let entry = (kind, Some(old[i].clone()), Some(new[j].clone()), score);
+                i += 1;
+                j += 1;
+                entry
+            }
+            _ if j < m && (i == n || best[i][j + 1] >= best[i + 1][j]) => {
+                j += 1;
+                (ChangeKind::Added, None, Some(new[j - 1].clone()), 0.0)
+            }
+            _ => {
+                i += 1;
+                (ChangeKind::Removed, Some(old[i - 1].clone()), None, 0.0)
+            }
+        };
+        entries.push(DiffEntry {
+            id: entries.len(),
+            kind: entry.0,
+            old: entry.1,
+            new: entry.2,
+            similarity: entry.3,
+        });
+    }
+    entries
+}
+
+fn build_diff<R: Runtime>(
+    app: &AppHandle<R>,
+    meeting_id: &str,
+    base_version: Option<String>,
+) -> Result<TranscriptDiff, String> {
+    let dir = storage::meeting_dir(app, meeting_id)?;
+    let base_version = match base_version {
+        Some(version) => version,
+        None => storage::derived_versions(&dir)
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Meeting {} has no earlier transcript to compare with", meeting_id))?,
+    };
+    let old = storage::read_archived_transcript(&dir, &base_version)?;
+    let new = storage::read_transcript(&dir)?;
+    let entries = align(&old, &new);
+    let count = |kind: ChangeKind| entries.iter().filter(|entry| entry.kind == kind).count();
+    Ok(TranscriptDiff {
+        meeting_id: meeting_id.to_string(),
+        changed: count(ChangeKind::Changed),
+        added: count(ChangeKind::Added),
+        removed: count(ChangeKind::Removed),
+        base_version,
+        entries,
+    })
+}
+
+/// Archived transcript versions of a meeting, newest first.
+#[command]
+pub fn list_transcript_versions<R: Runtime>(app: AppHandle<R>, meeting_id: String) -> Result<Vec<String>, String> {
+    Ok(storage::derived_versions(&storage::meeting_dir(&app, &meeting_id)?))
+}
+
+/// Compare a meeting's current transcript, e.g. after re-transcribing with a
+/// better model, against an archived version (the newest when `None`).
+#[command]
+pub fn diff_transcript<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    base_version: Option<String>,
+) -> Result<TranscriptDiff, String> {
+    build_diff(&app, &meeting_id, base_version)
+}
+
+/// Keep the current transcript except for the `rejected` diff entries, which
+/// go back to their text in `base_version`. The current transcript is
+/// archived first. Returns how many entries were reverted.
+#[command]
+pub fn apply_transcript_diff<R: Runtime>(
+    app: AppHandle<R>,
+    meeting_id: String,
+    base_version: String,
+    rejected: Vec<usize>,
+) -> Result<usize, String> {
+    let diff = build_diff(&app, &meeting_id, Some(base_version.clone()))?;
+    let rejected: HashSet<usize> = rejected.into_iter().collect();
+    let mut reverted = 0;
+    let mut updates = Vec::with_capacity(diff.entries.len());
+    for entry in diff.entries {
+        let keep_old = rejected.contains(&entry.id) && entry.kind != ChangeKind::Unchanged;
+        if keep_old {
+            reverted += 1;
+        }
+        if let Some(update) = if keep_old { entry.old } else { entry.new } {
+            updates.push(update);
+        }
+    }
+    if reverted == 0 {
+        return Ok(0);
+    }
+
+    let dir = storage::meeting_dir(&app, &meeting_id)?;
+    storage::replace_derived(&dir, &[(TRANSCRIPT_FILE, storage::transcript_json(&updates)?)])?;
+    info!(
+        "Reverted {} transcript changes in meeting {} to version {}",
+        reverted, meeting_id, base_version
+    );
+    Ok(reverted)
+}
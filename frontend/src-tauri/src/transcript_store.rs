@@ -0,0 +1,515 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::live_corrections::{self, Correction};
+use crate::transcript_stream::{self, speaker_of};
+use crate::transcription::TranscriptionResult;
+use crate::{encryption, storage, TranscriptUpdate};
+
+const DATABASE_FILE: &str = "transcripts.db";
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+/// Words around a match in snippets of encrypted segments, as with FTS5's `snippet`.
+const SNIPPET_WORDS: usize = 12;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS segments (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT,
+    device TEXT NOT NULL,
+    speaker TEXT,
+    text TEXT NOT NULL,
+    start_secs REAL NOT NULL,
+    end_secs REAL NOT NULL,
+    confidence REAL,
+    audio_path TEXT,
+    live INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    sealed INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS segments_session ON segments(session_id, start_secs);
+CREATE INDEX IF NOT EXISTS segments_created ON segments(created_at);
+CREATE VIRTUAL TABLE IF NOT EXISTS segments_fts USING fts5(
+    text, speaker, content='segments', content_rowid='id'
+);
+";
+/// Encrypted rows are kept out of the full-text index, which would
+/// otherwise hold their plaintext.
+const TRIGGERS: &str = "
+DROP TRIGGER IF EXISTS segments_ai;
+DROP TRIGGER IF EXISTS segments_ad;
+DROP TRIGGER IF EXISTS segments_au;
+CREATE TRIGGER segments_ai AFTER INSERT ON segments BEGIN
+    INSERT INTO segments_fts(rowid, text, speaker) SELECT new.id, new.text, new.speaker
+    WHERE new.sealed = 0;
+END;
+CREATE TRIGGER segments_ad AFTER DELETE ON segments BEGIN
+    INSERT INTO segments_fts(segments_fts, rowid, text, speaker) SELECT 'delete', old.id, old.text, old.speaker
+    WHERE old.sealed = 0;
+END;
+CREATE TRIGGER segments_au AFTER UPDATE ON segments BEGIN
+    INSERT INTO segments_fts(segments_fts, rowid, text, speaker) SELECT 'delete', old.id, old.text, old.speaker
+    WHERE old.sealed = 0;
+    INSERT INTO segments_fts(rowid, text, speaker) SELECT new.id, new.text, new.speaker
+    WHERE new.sealed = 0;
+END;
+";
+/// Matches encrypted values (`encryption::TEXT_PREFIX`), to flag the rows
+/// stored before the `sealed` column was added.
+const SEALED_PATTERN: &str = "sealed:%";
+
+/// One transcribed segment as stored in the transcript database.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredSegment {
+    pub id: i64,
+    /// Meeting id of the recording session, when meeting storage was on.
+    pub session_id: Option<String>,
+    /// Capture device, or the transcript source label for chunk transcripts.
+    pub device: String,
+    pub speaker: Option<String>,
+    pub text: String,
+    /// Seconds since the recording (or live stream) started.
+    pub start: f32,
+    pub end: f32,
+    pub confidence: Option<f32>,
+    /// Raw capture of the session the segment was heard in.
+    pub audio_path: Option<String>,
+    /// From a streaming engine rather than chunk transcription.
+    pub live: bool,
+    /// RFC 3339, UTC.
+    pub created_at: String,
+    /// Text and speaker are encrypted in the database.
+    #[serde(skip)]
+    pub sealed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub segment: StoredSegment,
+    /// The matching text with matches wrapped in `[` and `]`.
+    pub snippet: String,
+}
+
+/// Inclusive RFC 3339 bounds; either end may be open.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DateRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+static DATABASE: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+fn database_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(DATABASE_FILE))
+}
+
+/// Add the `sealed` column to a database from before it, flagging the rows
+/// that were encrypted then by their prefix.
+fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    let has_sealed: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('segments') WHERE name = 'sealed'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_sealed {
+        connection.execute("ALTER TABLE segments ADD COLUMN sealed INTEGER NOT NULL DEFAULT 0", [])?;
+        connection.execute("UPDATE segments SET sealed = 1 WHERE text LIKE ?1", [SEALED_PATTERN])?;
+    }
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS segments_sealed ON segments(created_at) WHERE sealed = 1",
+        [],
+    )?;
+    Ok(())
+}
+
+fn open<R: Runtime>(app: &AppHandle<R>) -> Result<Connection, String> {
+    let path = database_path(app)?;
+    let connection = Connection::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    connection
+        .execute_batch(SCHEMA)
+        .and_then(|_| migrate(&connection))
+        .and_then(|_| connection.execute_batch(TRIGGERS))
+        .map_err(|e| format!("Failed to create transcript tables: {}", e))?;
+    Ok(connection)
+}
+
+fn with_database<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let database = DATABASE.lock().map_err(|e| format!("Failed to lock transcript database: {}", e))?;
+    let connection = database
+        .as_ref()
+        .ok_or_else(|| "Transcript database is not available".to_string())?;
+    f(connection).map_err(|e| format!("Transcript database error: {}", e))
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Normalize a bound to the stored form so text comparison orders correctly.
+fn normalize_bound(bound: Option<String>) -> Result<Option<String>, String> {
+    bound
+        .map(|bound| {
+            DateTime::parse_from_rfc3339(&bound)
+                .map(|time| time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Millis, true))
+                .map_err(|e| format!("Invalid date '{}': {}", bound, e))
+        })
+        .transpose()
+}
+
+/// Quote every word so user input is matched literally rather than parsed
+/// as FTS5 query syntax.
+fn match_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn audio_path<R: Runtime>(app: &AppHandle<R>, session_id: Option<&str>) -> Option<String> {
+    let path = storage::raw_session_path(&storage::meeting_dir(app, session_id?).ok()?);
+    path.exists().then(|| path.to_string_lossy().to_string())
+}
+
+fn insert(connection: &Connection, segment: &StoredSegment) -> rusqlite::Result<usize> {
+    connection.execute(
+        "INSERT INTO segments (session_id, device, speaker, text, start_secs, end_secs, confidence, audio_path, live, created_at, sealed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            segment.session_id,
+            segment.device,
+            segment.speaker,
+            segment.text,
+            segment.start,
+            segment.end,
+            segment.confidence,
+            segment.audio_path,
+            segment.live,
+            segment.created_at,
+            segment.sealed,
+        ],
+    )
+}
+
+/// The segment with its text and speaker encrypted, when encryption is on.
+fn sealed(segment: StoredSegment) -> Result<StoredSegment, String> {
+    if !encryption::enabled() {
+        return Ok(segment);
+    }
+    Ok(StoredSegment {
+        text: encryption::seal_value(&segment.text)?,
+        speaker: segment.speaker.as_deref().map(encryption::seal_value).transpose()?,
+        sealed: true,
+        ..segment
+    })
+}
+
+/// Store a segment, returning its row id.
+fn store(segment: StoredSegment) -> Option<i64> {
+    if segment.text.trim().is_empty() {
+        return None;
+    }
+    let segment = match sealed(segment) {
+        Ok(segment) => segment,
+        Err(e) => {
+            warn!("Failed to encrypt transcript segment: {}", e);
+            return None;
+        }
+    };
+    match with_database(|connection| insert(connection, &segment).map(|_| connection.last_insert_rowid())) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("Failed to store transcript segment: {}", e);
+            None
+        }
+    }
+}
+
+/// A text column that holds an encrypted value when the row is sealed.
+fn unsealed(row: &Row, column: &str) -> rusqlite::Result<Option<String>> {
+    let Some(value) = row.get::<_, Option<String>>(column)? else {
+        return Ok(None);
+    };
+    if !row.get::<_, bool>("sealed")? {
+        return Ok(Some(value));
+    }
+    encryption::open_value(&value)
+        .map(Some)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(row.as_ref().column_index(column).unwrap_or(0), Type::Text, e.into()))
+}
+
+fn segment_from_row(row: &Row) -> rusqlite::Result<StoredSegment> {
+    Ok(StoredSegment {
+        id: row.get("id")?,
+        session_id: row.get("session_id")?,
+        device: row.get("device")?,
+        speaker: unsealed(row, "speaker")?,
+        text: unsealed(row, "text")?.unwrap_or_default(),
+        start: row.get("start_secs")?,
+        end: row.get("end_secs")?,
+        confidence: row.get("confidence")?,
+        audio_path: row.get("audio_path")?,
+        live: row.get("live")?,
+        created_at: row.get("created_at")?,
+        sealed: row.get("sealed")?,
+    })
+}
+
+/// Store a final result from a streaming engine, returning its row id so
+/// later revisions can be applied to it. Interim results are skipped.
+pub fn record_live<R: Runtime>(app: &AppHandle<R>, result: &TranscriptionResult) -> Option<i64> {
+    if !result.is_final {
+        return None;
+    }
+    store(StoredSegment {
+        id: 0,
+        session_id: result.session_id.clone(),
+        device: result.device.clone(),
+        speaker: result.speaker.clone(),
+        text: result.text.trim().to_string(),
+        start: result.start,
+        end: result.end,
+        confidence: Some(result.confidence),
+        audio_path: audio_path(app, result.session_id.as_deref()),
+        live: true,
+        created_at: now(),
+        sealed: false,
+    })
+}
+
+/// Apply a streaming engine's revision of a segment that was already stored
+/// as final, e.g. its formatted version.
+pub fn revise_live(id: i64, correction: &Correction, confidence: f32) {
+    // The row stays sealed or not as it was stored, like its speaker
+    let revised = with_database(|connection| {
+        connection.query_row("SELECT text, sealed FROM segments WHERE id = ?1", [id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+        })
+    })
+    .and_then(|(text, sealed)| {
+        let text = if sealed { encryption::open_value(&text)? } else { text };
+        let mut words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+        live_corrections::apply(&mut words, correction);
+        let text = words.join(" ");
+        if sealed {
+            encryption::seal_value(&text)
+        } else {
+            Ok(text)
+        }
+    })
+    .and_then(|text| {
+        with_database(|connection| {
+            connection.execute(
+                "UPDATE segments SET text = ?1, start_secs = ?2, end_secs = ?3, confidence = ?4 WHERE id = ?5",
+                params![text, correction.start, correction.end, confidence, id],
+            )
+        })
+    });
+    if let Err(e) = revised {
+        warn!("Failed to revise stored segment {}: {}", id, e);
+    }
+}
+
+fn update_segment(update: &TranscriptUpdate, session_id: Option<String>, audio_path: Option<String>) -> StoredSegment {
+    let (start, end) = match update.timestamp.split_once(" - ") {
+        Some((start, end)) => (start.trim().parse().unwrap_or(0.0), end.trim().parse().unwrap_or(0.0)),
+        None => (update.timestamp.trim().parse().unwrap_or(0.0), 0.0),
+    };
+    StoredSegment {
+        id: 0,
+        session_id,
+        device: update.source.clone(),
+        speaker: Some(speaker_of(update)),
+        text: update.text.trim().to_string(),
+        start,
+        end: f32::max(start, end),
+        confidence: None,
+        audio_path,
+        live: false,
+        created_at: now(),
+        sealed: false,
+    }
+}
+
+fn record_update<R: Runtime>(app: &AppHandle<R>, update: &TranscriptUpdate) {
+    let session_id = storage::current_session();
+    let audio_path = audio_path(app, session_id.as_deref());
+    store(update_segment(update, session_id, audio_path));
+}
+
+/// Store the transcript of a meeting imported from elsewhere, dated when it
+/// was originally recorded.
+pub fn record_imported(session_id: &str, updates: &[TranscriptUpdate], recorded_at: DateTime<Utc>) {
+    let created_at = recorded_at.to_rfc3339_opts(SecondsFormat::Millis, true);
+    for update in updates {
+        store(StoredSegment {
+            created_at: created_at.clone(),
+            ..update_segment(update, Some(session_id.to_string()), None)
+        });
+    }
+}
+
+/// Drop every stored segment of a session, e.g. when its meeting is deleted.
+pub fn delete_session(session_id: &str) {
+    match with_database(|connection| connection.execute("DELETE FROM segments WHERE session_id = ?1", [session_id])) {
+        Ok(removed) if removed > 0 => info!("Removed {} stored segments of session {}", removed, session_id),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to remove stored segments of session {}: {}", session_id, e),
+    }
+}
+
+/// Open the transcript database and store every transcript update published
+/// from the chunk pipeline.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    match open(app) {
+        Ok(connection) => {
+            if let Ok(mut database) = DATABASE.lock() {
+                *database = Some(connection);
+            }
+        }
+        Err(e) => {
+            error!("Transcript search disabled: {}", e);
+            return;
+        }
+    }
+
+    let app = app.clone();
+    let mut updates = transcript_stream::subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match updates.recv().await {
+                Ok(update) => record_update(&app, &update),
+                Err(RecvError::Lagged(skipped)) => warn!("Transcript store missed {} updates", skipped),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn normalized(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+/// A hit when every word of the query is in the segment's text or speaker,
+/// with a snippet around the first match.
+fn sealed_hit(segment: StoredSegment, words: &[String]) -> Option<SearchHit> {
+    let tokens: Vec<&str> = segment.text.split_whitespace().collect();
+    let matched: Vec<bool> = tokens.iter().map(|token| words.contains(&normalized(token))).collect();
+    let speaker: Vec<String> = segment.speaker.iter().flat_map(|s| s.split_whitespace()).map(normalized).collect();
+    let found = words
+        .iter()
+        .all(|word| tokens.iter().any(|token| normalized(token) == *word) || speaker.contains(word));
+    if !found {
+        return None;
+    }
+    let first = matched.iter().position(|matched| *matched).unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_WORDS / 4);
+    let end = (start + SNIPPET_WORDS).min(tokens.len());
+    let mut snippet = tokens[start..end]
+        .iter()
+        .zip(&matched[start..end])
+        .map(|(token, matched)| if *matched { format!("[{}]", token) } else { token.to_string() })
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < tokens.len() {
+        snippet.push('…');
+    }
+    Some(SearchHit { segment, snippet })
+}
+
+/// Encrypted segments are kept out of the full-text index, so they're
+/// decrypted and matched here instead, newest first. The `segments_sealed`
+/// index narrows the scan to sealed rows in the date range, but every one of
+/// those is still decrypted on each query, so searches without a range get
+/// slower as encrypted meetings pile up.
+fn search_sealed(query: &str, from: &Option<String>, to: &Option<String>, limit: usize) -> Result<Vec<SearchHit>, String> {
+    let words: Vec<String> = query.split_whitespace().map(normalized).filter(|word| !word.is_empty()).collect();
+    if words.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+    let segments: Vec<StoredSegment> = with_database(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT * FROM segments
+             WHERE sealed = 1
+               AND (?1 IS NULL OR created_at >= ?1)
+               AND (?2 IS NULL OR created_at <= ?2)
+             ORDER BY created_at DESC",
+        )?;
+        let segments = statement.query_map(params![from, to], segment_from_row)?.collect();
+        segments
+    })?;
+    Ok(segments
+        .into_iter()
+        .filter_map(|segment| sealed_hit(segment, &words))
+        .take(limit)
+        .collect())
+}
+
+/// Full-text search over every stored segment, best match first.
+#[command]
+pub fn search_transcripts(
+    query: String,
+    date_range: Option<DateRange>,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    let expression = match_expression(&query);
+    if expression.is_empty() {
+        return Ok(Vec::new());
+    }
+    let range = date_range.unwrap_or_default();
+    let from = normalize_bound(range.from)?;
+    let to = normalize_bound(range.to)?;
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64;
+
+    let mut hits: Vec<SearchHit> = with_database(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT segments.*, snippet(segments_fts, 0, '[', ']', '…', 12) AS snippet
+             FROM segments_fts JOIN segments ON segments.id = segments_fts.rowid
+             WHERE segments_fts MATCH ?1
+               AND (?2 IS NULL OR segments.created_at >= ?2)
+               AND (?3 IS NULL OR segments.created_at <= ?3)
+             ORDER BY rank
+             LIMIT ?4",
+        )?;
+        let hits = statement
+            .query_map(params![expression, from, to, limit], |row| {
+                Ok(SearchHit {
+                    segment: segment_from_row(row)?,
+                    snippet: row.get("snippet")?,
+                })
+            })?
+            .collect();
+        hits
+    })?;
+    match search_sealed(&query, &from, &to, (limit as usize).saturating_sub(hits.len())) {
+        Ok(sealed) => hits.extend(sealed),
+        Err(e) => warn!("Failed to search encrypted segments: {}", e),
+    }
+    Ok(hits)
+}
+
+/// Every stored segment of a session, in timeline order.
+#[command]
+pub fn get_session_transcript(session_id: String) -> Result<Vec<StoredSegment>, String> {
+    with_database(|connection| {
+        let mut statement = connection.prepare("SELECT * FROM segments WHERE session_id = ?1 ORDER BY start_secs, id")?;
+        let segments = statement.query_map([&session_id], segment_from_row)?.collect();
+        segments
+    })
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::ipc::Channel;
+use tauri::{command, AppHandle, Runtime};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::events;
+use crate::speakers::{self, SpeakerDisplay};
+use crate::TranscriptUpdate;
+
+/// What a subscriber receives from the live transcript.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Every transcript update.
+    #[default]
+    Full,
+    /// Only speaker-change boundaries with the first sentence of each turn,
+    /// for ticker-style displays.
+    SpeakerChanges,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptStreamEvent {
+    Update(TranscriptUpdate),
+    SpeakerChange {
+        speaker: String,
+        display: SpeakerDisplay,
+        first_sentence: String,
+        timestamp: String,
+    },
+}
+
+static UPDATES: Lazy<broadcast::Sender<TranscriptUpdate>> = Lazy::new(|| broadcast::channel(256).0);
+static SUBSCRIBERS: Lazy<Mutex<HashMap<u64, JoinHandle<()>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_SUBSCRIBER: AtomicU64 = AtomicU64::new(1);
+
+/// Emit a live transcript update to the webview and to stream subscribers.
+pub fn publish<R: Runtime>(app: &AppHandle<R>, mut update: TranscriptUpdate) -> tauri::Result<()> {
+    update.speaker_display = Some(speakers::display_for(&speaker_of(&update)));
+    // No receivers just means nobody subscribed to the stream
+    let _ = UPDATES.send(update.clone());
+    events::emit(app, events::TRANSCRIPT_UPDATE, update)
+}
+
+pub fn subscribe() -> broadcast::Receiver<TranscriptUpdate> {
+    UPDATES.subscribe()
+}
+
+/// Who is talking, as far as the pipeline can tell.
+pub fn speaker_of(update: &TranscriptUpdate) -> String {
+    update
+        .source_app
+        .clone()
+        .unwrap_or_else(|| update.source.clone())
+}
+
+fn first_sentence(text: &str) -> String {
+    match text.find(['.', '?', '!']) {
+        Some(end) => text[..=end].trim().to_string(),
+        None => text.trim().to_string(),
+    }
+}
+
+/// Reduces the update stream to speaker-change boundaries.
+#[derive(Debug, Default)]
+pub struct SpeakerChangeFilter {
+    current: Option<String>,
+}
+
+impl SpeakerChangeFilter {
+    pub fn observe(&mut self, update: &TranscriptUpdate) -> Option<TranscriptStreamEvent> {
+        let speaker = speaker_of(update);
+        if self.current.as_deref() == Some(speaker.as_str()) {
+            return None;
+        }
+        self.current = Some(speaker.clone());
+        Some(TranscriptStreamEvent::SpeakerChange {
+            display: update
+                .speaker_display
+                .clone()
+                .unwrap_or_else(|| speakers::display_for(&speaker)),
+            speaker,
+            first_sentence: first_sentence(&update.text),
+            timestamp: update.timestamp.clone(),
+        })
+    }
+}
+
+/// Stream the live transcript to `on_event` in the requested mode.
+/// Returns an id for `unsubscribe_transcript`.
+#[command]
+pub fn subscribe_transcript(mode: Option<OutputMode>, on_event: Channel<TranscriptStreamEvent>) -> Result<u64, String> {
+    let mode = mode.unwrap_or_default();
+    let id = NEXT_SUBSCRIBER.fetch_add(1, Ordering::SeqCst);
+    let mut receiver = subscribe();
+
+    // Held until the task is registered, so one that ends at once can't
+    // remove its entry before it's inserted and leave the handle behind
+    let mut subscribers = SUBSCRIBERS
+        .lock()
+        .map_err(|e| format!("Failed to lock subscribers: {}", e))?;
+    let task = tauri::async_runtime::spawn(async move {
+        let mut speaker_changes = SpeakerChangeFilter::default();
+        loop {
+            let update = match receiver.recv().await {
+                Ok(update) => update,
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Transcript subscriber {} lagged, skipped {} updates", id, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let event = match mode {
+                OutputMode::Full => Some(TranscriptStreamEvent::Update(update)),
+                OutputMode::SpeakerChanges => speaker_changes.observe(&update),
+            };
+            if let Some(event) = event {
+                if on_event.send(event).is_err() {
+                    // The webview that owned the channel is gone
+                    break;
+                }
+            }
+        }
+        if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+            subscribers.remove(&id);
+        }
+    });
+
+    subscribers.insert(id, task);
+    info!("Transcript subscriber {} added in {:?} mode", id, mode);
+    Ok(id)
+}
+
+#[command]
+pub fn unsubscribe_transcript(id: u64) -> Result<(), String> {
+    if let Some(task) = SUBSCRIBERS
+        .lock()
+        .map_err(|e| format!("Failed to lock subscribers: {}", e))?
+        .remove(&id)
+    {
+        task.abort();
+    }
+    Ok(())
+}
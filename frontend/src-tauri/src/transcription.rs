@@ -0,0 +1,585 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime};
+use tokio::sync::mpsc;
+
+use crate::audio::{noise, time_stretch};
+use crate::inference::InferenceQueueSettings;
+use crate::language_id::DetectedLanguage;
+use crate::{assemblyai, azure, connectivity, deepgram, hallucination, metrics, openai, request_limit, settings, usage, whisper_cpp, whisper_decode, TranscriptResponse, WHISPER_ENDPOINT};
+
+pub const LIVE_EVENT: &str = "live-transcription";
+
+/// What an engine returns: timed segments relative to the start of the input.
+pub type Transcript = TranscriptResponse;
+
+/// Mono audio handed to an engine.
+#[derive(Debug, Clone)]
+pub struct AudioInput {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    /// Language to decode in, overriding the engine's own language setting.
+    pub language: Option<String>,
+    /// Device the audio was captured from, which cloud request slots are
+    /// shared fairly between.
+    pub device: Option<String>,
+}
+
+/// A speech-to-text backend. Register additional engines with
+/// [`register_engine`]; the active one is chosen by id in settings.
+pub trait TranscriptionEngine: Send + Sync {
+    /// Stable identifier stored in settings, e.g. `"whisper"`.
+    fn id(&self) -> &str;
+    /// Human-readable name, recorded with sessions.
+    fn name(&self) -> &str;
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>>;
+    /// Whether requests leave the machine, and so count against
+    /// `max_parallel_cloud_requests`.
+    fn is_cloud(&self) -> bool {
+        false
+    }
+    /// URL answering any request while the engine's API is reachable.
+    fn health_url(&self) -> Option<String> {
+        None
+    }
+    /// Client to probe [`Self::health_url`] with, when the engine's API needs
+    /// its own TLS settings.
+    fn health_client(&self) -> Option<reqwest::Client> {
+        None
+    }
+}
+
+/// One live result from a streaming engine. Interim results for the same
+/// stretch of speech are replaced by later ones until a final result arrives.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionResult {
+    pub device: String,
+    pub text: String,
+    pub is_final: bool,
+    /// Seconds since the stream opened.
+    pub start: f32,
+    pub end: f32,
+    pub confidence: f32,
+    /// Speaker label when the engine diarizes the stream.
+    pub speaker: Option<String>,
+    /// Meeting id of the recording session the result belongs to.
+    pub session_id: Option<String>,
+    /// Spoken language, when the engine or language identification reports it.
+    pub language: Option<DetectedLanguage>,
+    /// Language the speech was in, when `text` is its English translation.
+    pub translated_from: Option<String>,
+    /// Id of the engine that produced `text`.
+    pub engine: String,
+}
+
+/// Why a chunk or segment could not be transcribed, so the UI can say what
+/// to do about it and retries can tell transient failures from lasting ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptionError {
+    /// A speech, VAD or embedding model could not be found or loaded.
+    ModelLoad { message: String },
+    Resample { message: String },
+    /// Speech detection or segmentation failed.
+    Vad { message: String },
+    /// Speaker embedding or attribution failed.
+    Diarization { message: String },
+    /// The engine did not answer in time.
+    EngineTimeout { message: String },
+    /// The engine rejected the credentials: a missing, wrong or expired key.
+    NetworkAuth { message: String },
+    /// The engine asked for fewer requests.
+    RateLimited { message: String },
+    /// The engine could not be reached.
+    Network { message: String },
+    /// Any other engine failure.
+    Engine { message: String },
+}
+
+impl TranscriptionError {
+    /// Sort an engine's error message into a kind.
+    pub fn classify(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let lower = message.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+        if has(&["timed out", "timeout", "deadline"]) {
+            TranscriptionError::EngineTimeout { message }
+        } else if has(&["401", "403", "unauthorized", "forbidden", "api key", "invalid key", "authentication"]) {
+            TranscriptionError::NetworkAuth { message }
+        } else if has(&["429", "rate limit", "too many requests", "quota"]) {
+            TranscriptionError::RateLimited { message }
+        } else if has(&["connect", "dns", "network", "unreachable", "error sending request"]) {
+            TranscriptionError::Network { message }
+        } else if has(&["resampl"]) {
+            TranscriptionError::Resample { message }
+        } else if has(&["model"]) && has(&["load", "missing", "not found", "download"]) {
+            TranscriptionError::ModelLoad { message }
+        } else {
+            TranscriptionError::Engine { message }
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            TranscriptionError::ModelLoad { message }
+            | TranscriptionError::Resample { message }
+            | TranscriptionError::Vad { message }
+            | TranscriptionError::Diarization { message }
+            | TranscriptionError::EngineTimeout { message }
+            | TranscriptionError::NetworkAuth { message }
+            | TranscriptionError::RateLimited { message }
+            | TranscriptionError::Network { message }
+            | TranscriptionError::Engine { message } => message,
+        }
+    }
+
+    /// Whether the same audio may well succeed if sent again later.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            TranscriptionError::EngineTimeout { .. }
+                | TranscriptionError::RateLimited { .. }
+                | TranscriptionError::Network { .. }
+                | TranscriptionError::Engine { .. }
+        )
+    }
+}
+
+impl fmt::Display for TranscriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for TranscriptionError {}
+
+impl From<String> for TranscriptionError {
+    fn from(message: String) -> Self {
+        TranscriptionError::classify(message)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionSettings {
+    /// Id of the engine used for chunk transcription.
+    pub engine: String,
+    /// Engines tried in order when the selected one fails, e.g.
+    /// `["openai", "whisper"]`. Empty turns fallback off.
+    pub fallback_chain: Vec<String>,
+    /// Give up on an engine after this many seconds, by engine id. Engines
+    /// without an entry are waited on for as long as they take.
+    pub engine_timeouts: BTreeMap<String, u64>,
+    /// Skip decoding chunks with too little speech, and drop segments the
+    /// engine itself rates as silence, instead of keeping hallucinated text.
+    pub no_speech_gate: bool,
+    /// Share of voiced frames below which a chunk isn't decoded at all.
+    pub min_speech_ratio: f32,
+    /// Segments the engine rates above this no-speech probability are dropped.
+    pub no_speech_threshold: f32,
+    pub mode: TranscriptionMode,
+    /// After recording stops, keep transcribing audio already captured for
+    /// up to this long before giving up on the rest.
+    pub drain_timeout_secs: u64,
+    /// Chunks decoded at once. More than one helps engines that take longer
+    /// than a chunk lasts; each device's results still arrive in order.
+    pub transcription_workers: usize,
+    pub inference_queue: InferenceQueueSettings,
+    /// The on/off switch `fallback_chain` replaced, read from older settings
+    /// files by `upgrade` and never written back.
+    #[serde(skip_serializing)]
+    fallback_to_whisper: Option<bool>,
+}
+
+/// Whether speech is written down as spoken or translated into English.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionMode {
+    #[default]
+    Transcribe,
+    /// Whisper's translate task: non-English speech produces English text.
+    Translate,
+}
+
+/// Engines that can translate; the rest only transcribe.
+const TRANSLATING_ENGINES: [&str; 3] = [WhisperEngine::ID, whisper_cpp::WhisperCppEngine::ID, openai::OpenAiEngine::ID];
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        Self {
+            engine: WhisperEngine::ID.to_string(),
+            fallback_chain: vec![WhisperEngine::ID.to_string()],
+            engine_timeouts: BTreeMap::new(),
+            no_speech_gate: true,
+            min_speech_ratio: 0.02,
+            no_speech_threshold: 0.6,
+            mode: TranscriptionMode::Transcribe,
+            drain_timeout_secs: 60,
+            transcription_workers: 2,
+            inference_queue: InferenceQueueSettings::default(),
+            fallback_to_whisper: None,
+        }
+    }
+}
+
+impl TranscriptionSettings {
+    /// Carry settings saved before `fallback_chain` over: fallback that was
+    /// turned off stays off.
+    pub fn upgrade(&mut self) {
+        if self.fallback_to_whisper.take() == Some(false) {
+            self.fallback_chain.clear();
+        }
+    }
+}
+
+/// What a gated transcription found in a chunk.
+#[derive(Debug)]
+pub enum Transcription {
+    Speech(Transcript),
+    /// The chunk was silence: either it wasn't decoded, or every segment the
+    /// engine returned was rated as no speech.
+    NoSpeech,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineInfo {
+    pub id: String,
+    pub name: String,
+    pub active: bool,
+}
+
+/// The local whisper server, or another server speaking its protocol.
+pub struct WhisperEngine {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl WhisperEngine {
+    pub const ID: &'static str = "whisper";
+
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl TranscriptionEngine for WhisperEngine {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn name(&self) -> &str {
+        "Whisper (local server)"
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>> {
+        Box::pin(whisper_decode::send(audio, &self.client, &self.endpoint))
+    }
+}
+
+static ENGINES: Lazy<Mutex<Vec<Arc<dyn TranscriptionEngine>>>> = Lazy::new(|| {
+    Mutex::new(vec![
+        Arc::new(WhisperEngine::new(WHISPER_ENDPOINT)),
+        Arc::new(deepgram::DeepgramEngine::new()),
+        Arc::new(openai::OpenAiEngine::new()),
+        Arc::new(assemblyai::AssemblyAiEngine::new()),
+        Arc::new(azure::AzureSpeechEngine::new()),
+        Arc::new(whisper_cpp::WhisperCppEngine::new()),
+    ])
+});
+
+/// Make an engine selectable. An engine with the same id is replaced.
+pub fn register_engine(engine: Arc<dyn TranscriptionEngine>) {
+    if let Ok(mut engines) = ENGINES.lock() {
+        engines.retain(|existing| existing.id() != engine.id());
+        info!("Registered transcription engine {}", engine.id());
+        engines.push(engine);
+    }
+}
+
+/// The registered engine with id `id`.
+pub fn engine(id: &str) -> Option<Arc<dyn TranscriptionEngine>> {
+    ENGINES
+        .lock()
+        .ok()
+        .and_then(|engines| engines.iter().find(|engine| engine.id() == id).cloned())
+}
+
+/// The engine selected in settings, falling back to the local whisper server.
+pub fn active() -> Arc<dyn TranscriptionEngine> {
+    let id = settings::get().transcription.engine;
+    match engine(&id) {
+        Some(engine) => engine,
+        None => {
+            warn!("Transcription engine '{}' is not registered, using whisper", id);
+            Arc::new(WhisperEngine::new(WHISPER_ENDPOINT))
+        }
+    }
+}
+
+/// The engine used while cloud engines are unreachable: the first local one
+/// in the fallback chain, or the local whisper server.
+pub fn local_engine_id() -> String {
+    settings::get()
+        .transcription
+        .fallback_chain
+        .into_iter()
+        .find(|id| id == WhisperEngine::ID || engine(id).is_some_and(|engine| !engine.is_cloud()))
+        .unwrap_or_else(|| WhisperEngine::ID.to_string())
+}
+
+/// The model the active engine is configured with, for engines that offer a choice.
+pub fn active_model() -> Option<String> {
+    let config = settings::get();
+    match config.transcription.engine.as_str() {
+        deepgram::DeepgramEngine::ID => Some(deepgram::options().model),
+        openai::OpenAiEngine::ID => Some(config.openai.model),
+        whisper_cpp::WhisperCppEngine::ID => config.whisper_cpp.model,
+        _ => None,
+    }
+}
+
+/// A live streaming connection for one capture device, to whichever
+/// provider has streaming enabled. Dropping it ends the stream once buffered
+/// audio has been sent.
+pub struct LiveFeed {
+    audio_tx: mpsc::UnboundedSender<Vec<f32>>,
+}
+
+impl LiveFeed {
+    pub fn start<R: Runtime>(app: &AppHandle<R>, device: String, sample_rate: u32) -> Option<Self> {
+        let config = settings::get();
+        let provider = if config.deepgram.streaming {
+            deepgram::DeepgramEngine::ID
+        } else if config.assemblyai.streaming {
+            assemblyai::AssemblyAiEngine::ID
+        } else {
+            return None;
+        };
+        if usage::over_budget() {
+            info!("Monthly budget spent, not streaming to {}", provider);
+            return None;
+        }
+
+        let (audio_tx, audio_rx) = mpsc::unbounded_channel();
+        let app = app.clone();
+        tokio::spawn(async move {
+            let result = if provider == deepgram::DeepgramEngine::ID {
+                deepgram::run_live(app, device.clone(), sample_rate, audio_rx).await
+            } else {
+                assemblyai::run_live(app, device.clone(), sample_rate, audio_rx).await
+            };
+            if let Err(e) = result {
+                error!("Live {} stream for {} ended: {}", provider, device, e);
+            }
+        });
+        Some(Self { audio_tx })
+    }
+
+    pub fn feed(&self, samples: &[f32]) {
+        if !samples.is_empty() {
+            let _ = self.audio_tx.send(samples.to_vec());
+        }
+    }
+}
+
+/// 16-bit mono WAV, the format every hosted engine accepts.
+pub fn encode_wav(audio: &AudioInput) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: audio.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut cursor, spec).map_err(|e| format!("Failed to encode audio: {}", e))?;
+    for sample in &audio.samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(|e| format!("Failed to encode audio: {}", e))?;
+    }
+    writer.finalize().map_err(|e| format!("Failed to encode audio: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Transcribe with the active engine, falling back to the local whisper
+/// server when another engine fails and fallback is enabled.
+pub async fn transcribe(audio: &AudioInput) -> Result<Transcript, String> {
+    transcribe_with(active(), audio).await
+}
+
+/// Like [`transcribe`], with `engine` instead of the active one. When it
+/// fails, the engines of the fallback chain are tried in turn; the transcript
+/// records which engine produced it.
+pub async fn transcribe_with(engine: Arc<dyn TranscriptionEngine>, audio: &AudioInput) -> Result<Transcript, String> {
+    let config = settings::get().transcription;
+    let mut chain = vec![engine];
+    for id in &config.fallback_chain {
+        if chain.iter().any(|engine| engine.id() == id) {
+            continue;
+        }
+        match self::engine(id) {
+            Some(engine) => chain.push(engine),
+            None if id == WhisperEngine::ID => chain.push(Arc::new(WhisperEngine::new(WHISPER_ENDPOINT))),
+            None => warn!("Fallback engine '{}' is not registered, skipping it", id),
+        }
+    }
+    if connectivity::offline() || usage::over_budget() {
+        chain.retain(|engine| !engine.is_cloud());
+        if chain.is_empty() {
+            let local = local_engine_id();
+            chain.push(self::engine(&local).unwrap_or_else(|| Arc::new(WhisperEngine::new(WHISPER_ENDPOINT))));
+        }
+    }
+
+    let mut failures = Vec::new();
+    for (index, engine) in chain.iter().enumerate() {
+        let _permit = if engine.is_cloud() {
+            Some(request_limit::acquire(audio.device.as_deref()).await)
+        } else {
+            None
+        };
+        let attempt = engine.transcribe(audio);
+        let result = match config.engine_timeouts.get(engine.id()).filter(|secs| **secs > 0) {
+            Some(&secs) => tokio::time::timeout(Duration::from_secs(secs), attempt)
+                .await
+                .unwrap_or_else(|_| Err(format!("{} timed out after {}s", engine.name(), secs))),
+            None => attempt.await,
+        };
+        if engine.is_cloud() {
+            connectivity::observe(engine.as_ref(), result.as_ref().map(|_| ()).map_err(String::as_str));
+        }
+        match result {
+            Ok(mut transcript) => {
+                if engine.is_cloud() {
+                    usage::record(engine.id(), audio.samples.len() as f64 / audio.sample_rate.max(1) as f64);
+                }
+                if index > 0 {
+                    info!("Transcribed with fallback engine {}", engine.name());
+                }
+                transcript.engine = engine.id().to_string();
+                return Ok(transcript);
+            }
+            Err(e) => {
+                if let Some(next) = chain.get(index + 1) {
+                    warn!("{} failed ({}), falling back to {}", engine.name(), e, next.name());
+                }
+                failures.push(e);
+            }
+        }
+    }
+    // The first engine's error says most about why the chunk failed
+    Err(match failures.len() {
+        0 | 1 => failures.pop().unwrap_or_default(),
+        n => format!("{} (and {} fallback engines failed)", failures.swap_remove(0), n - 1),
+    })
+}
+
+/// Like [`transcribe`], but gated on speech: chunks from `device` whose
+/// energy VAD finds too little speech are never sent to the engine, and
+/// segments the engine rates as silence or that look hallucinated are dropped.
+pub async fn transcribe_gated(audio: &AudioInput, device: &str) -> Result<Transcription, String> {
+    let config = settings::get().transcription;
+    if config.no_speech_gate {
+        let speech_ratio = metrics::time(metrics::Stage::Vad, || {
+            noise::vad_for(device).speech_ratio(&audio.samples, audio.sample_rate)
+        });
+        if speech_ratio < config.min_speech_ratio {
+            debug!("Skipping chunk with {:.1}% speech", speech_ratio * 100.0);
+            return Ok(Transcription::NoSpeech);
+        }
+    }
+
+    // Fast speech is slowed down for the engine, then timed on the original audio
+    let mut transcript = match time_stretch::speed_for(&audio.samples, audio.sample_rate) {
+        Some(speed) => {
+            let stretched = AudioInput {
+                samples: time_stretch::wsola(&audio.samples, audio.sample_rate, speed),
+                ..audio.clone()
+            };
+            let mut transcript = transcribe(&stretched).await?;
+            time_stretch::restore_timestamps(&mut transcript, speed);
+            transcript
+        }
+        None => transcribe(audio).await?,
+    };
+    let before = transcript.segments.len();
+    if config.no_speech_gate {
+        transcript
+            .segments
+            .retain(|segment| !matches!(segment.no_speech_prob, Some(p) if p > config.no_speech_threshold));
+        if transcript.segments.len() < before {
+            debug!("Dropped {} segments rated as no speech", before - transcript.segments.len());
+        }
+    }
+    let filters = settings::get().hallucination;
+    if filters.enabled {
+        transcript.segments.retain(|segment| match hallucination::check(segment, &filters) {
+            Some(reason) => {
+                debug!("Dropped hallucinated segment ({:?}): {}", reason, segment.text.trim());
+                false
+            }
+            None => true,
+        });
+    }
+    if transcript.segments.is_empty() && before > 0 {
+        return Ok(Transcription::NoSpeech);
+    }
+    Ok(Transcription::Speech(transcript))
+}
+
+#[command]
+pub fn list_transcription_engines() -> Vec<EngineInfo> {
+    let active = settings::get().transcription.engine;
+    ENGINES
+        .lock()
+        .map(|engines| {
+            engines
+                .iter()
+                .map(|engine| EngineInfo {
+                    id: engine.id().to_string(),
+                    name: engine.name().to_string(),
+                    active: engine.id() == active,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[command]
+pub fn get_transcription_settings() -> TranscriptionSettings {
+    settings::get().transcription
+}
+
+#[command]
+pub fn set_transcription_settings(transcription_settings: TranscriptionSettings) -> Result<TranscriptionSettings, String> {
+    let known = ENGINES
+        .lock()
+        .map(|engines| engines.iter().any(|engine| engine.id() == transcription_settings.engine))
+        .unwrap_or(false);
+    if !known {
+        return Err(format!("Unknown transcription engine '{}'", transcription_settings.engine));
+    }
+    for id in &transcription_settings.fallback_chain {
+        if id != WhisperEngine::ID && engine(id).is_none() {
+            return Err(format!("Unknown fallback engine '{}'", id));
+        }
+    }
+    if transcription_settings.mode == TranscriptionMode::Translate
+        && !TRANSLATING_ENGINES.contains(&transcription_settings.engine.as_str())
+    {
+        return Err(format!("The '{}' engine can't translate", transcription_settings.engine));
+    }
+    settings::update(|s| s.transcription = transcription_settings).map(|s| s.transcription)
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use log::{error, info};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::broadcast;
+
+use crate::pipeline::{self, PipelineStatus};
+use crate::quick_transcribe;
+
+const TRAY_ID: &str = "main";
+
+/// Menu items whose labels follow the pipeline status.
+struct TrayItems<R: Runtime> {
+    status: MenuItem<R>,
+    engine: MenuItem<R>,
+    lag: MenuItem<R>,
+    pause: MenuItem<R>,
+    stop: MenuItem<R>,
+    open_last: MenuItem<R>,
+}
+
+fn format_elapsed(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+impl<R: Runtime> TrayItems<R> {
+    fn refresh(&self, tray: &TrayIcon<R>, status: &PipelineStatus) -> tauri::Result<()> {
+        let status_text = if !status.recording {
+            "Not recording".to_string()
+        } else if status.paused {
+            format!("⏸ Paused {}", format_elapsed(status.elapsed_ms))
+        } else {
+            format!("● Recording {}", format_elapsed(status.elapsed_ms))
+        };
+        self.status.set_text(&status_text)?;
+        self.engine.set_text(format!(
+            "Engine: {}",
+            status.engine.as_deref().unwrap_or("-")
+        ))?;
+        self.lag.set_text(format!(
+            "Queue lag: {:.1}s",
+            status.queue_lag_ms as f64 / 1000.0
+        ))?;
+        self.pause
+            .set_text(if status.paused { "Resume" } else { "Pause" })?;
+        self.pause.set_enabled(status.recording)?;
+        self.stop.set_enabled(status.recording)?;
+        self.open_last
+            .set_enabled(status.last_transcript_path.is_some())?;
+        tray.set_tooltip(Some(format!("meetily - {}", status_text)))?;
+        Ok(())
+    }
+}
+
+fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, id: &str) {
+    let app = app.clone();
+    match id {
+        "pause" => {
+            let result = if crate::is_paused() {
+                crate::resume_recording()
+            } else {
+                crate::pause_recording()
+            };
+            if let Err(e) = result {
+                error!("Tray pause action failed: {}", e);
+            }
+        }
+        "stop_summarize" => {
+            tauri::async_runtime::spawn(async move {
+                // The UI saves the transcript and generates the summary
+                if let Err(e) = crate::stop_recording_session(&app, true).await {
+                    error!("Tray stop action failed: {}", e);
+                }
+            });
+        }
+        "open_last_transcript" => {
+            if let Some(path) = pipeline::status().last_transcript_path {
+                if let Err(e) = app.opener().open_path(path, None::<&str>) {
+                    error!("Failed to open last transcript: {}", e);
+                }
+            }
+        }
+        "quick_transcribe" => quick_transcribe::pick_and_transcribe(&app),
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Build the tray icon and keep it in sync with pipeline events.
+pub fn init<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
+    let items = TrayItems {
+        status: MenuItem::with_id(app, "status", "Not recording", false, None::<&str>)?,
+        engine: MenuItem::with_id(app, "engine", "Engine: -", false, None::<&str>)?,
+        lag: MenuItem::with_id(app, "lag", "Queue lag: 0.0s", false, None::<&str>)?,
+        pause: MenuItem::with_id(app, "pause", "Pause", false, None::<&str>)?,
+        stop: MenuItem::with_id(app, "stop_summarize", "Stop & Summarize", false, None::<&str>)?,
+        open_last: MenuItem::with_id(
+            app,
+            "open_last_transcript",
+            "Open Last Transcript",
+            false,
+            None::<&str>,
+        )?,
+    };
+    let quick = MenuItem::with_id(app, "quick_transcribe", "Quick Transcribe File…", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &items.status,
+            &items.engine,
+            &items.lag,
+            &PredefinedMenuItem::separator(app)?,
+            &items.pause,
+            &items.stop,
+            &items.open_last,
+            &quick,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("meetily")
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    let tray = builder.build(app)?;
+    info!("System tray initialized");
+
+    let mut events = pipeline::subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    if let Err(broadcast::error::RecvError::Closed) = event {
+                        break;
+                    }
+                }
+                // Elapsed time and queue lag advance without new events
+                _ = ticker.tick() => {
+                    if !pipeline::status().recording {
+                        continue;
+                    }
+                }
+            }
+            if let Err(e) = items.refresh(&tray, &pipeline::status()) {
+                error!("Failed to refresh tray menu: {}", e);
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use crate::pipeline::{self, PipelineEvent};
+use crate::{assemblyai, azure, deepgram, openai, settings, storage};
+
+const USAGE_FILE: &str = "usage.json";
+/// Streams record audio many times a second, so the ledger is written at most
+/// this often, and when a recording ends.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What paid engines cost, and how much may be spent on them each month
+/// before chunks go to the local engine instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageSettings {
+    /// Price per audio minute by engine id, in the currency of the budget.
+    pub price_per_minute: BTreeMap<String, f64>,
+    /// Spending limit for the calendar month; no limit when `None`.
+    pub monthly_budget: Option<f64>,
+}
+
+impl Default for UsageSettings {
+    fn default() -> Self {
+        Self {
+            // List prices in USD for pay-as-you-go plans
+            price_per_minute: BTreeMap::from([
+                (deepgram::DeepgramEngine::ID.to_string(), 0.0043),
+                (assemblyai::AssemblyAiEngine::ID.to_string(), 0.0062),
+                (azure::AzureSpeechEngine::ID.to_string(), 0.0167),
+                (openai::OpenAiEngine::ID.to_string(), 0.006),
+            ]),
+            monthly_budget: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EngineUsage {
+    /// Minutes of audio sent, by chunk request or live stream.
+    pub minutes: f64,
+    /// Chunk requests; live streams don't count.
+    pub requests: u64,
+    /// At the price when the audio was sent.
+    pub cost: f64,
+}
+
+impl EngineUsage {
+    fn add(&mut self, other: &EngineUsage) {
+        self.minutes += other.minutes;
+        self.requests += other.requests;
+        self.cost += other.cost;
+    }
+}
+
+type UsageByEngine = BTreeMap<String, EngineUsage>;
+
+/// Usage kept across restarts, by calendar month (`YYYY-MM`) and by meeting.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Ledger {
+    months: BTreeMap<String, UsageByEngine>,
+    sessions: BTreeMap<String, UsageByEngine>,
+}
+
+#[derive(Default)]
+struct State {
+    path: Option<PathBuf>,
+    ledger: Ledger,
+    dirty: bool,
+    saved_at: Option<Instant>,
+    /// The month the budget ran out in, once that's been announced.
+    exceeded: Option<String>,
+}
+
+static STATE: Lazy<Mutex<State>> = Lazy::new(|| Mutex::new(State::default()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageStats {
+    pub month: String,
+    pub this_month: UsageByEngine,
+    pub month_cost: f64,
+    pub monthly_budget: Option<f64>,
+    /// Paid engines are skipped for the rest of the month.
+    pub over_budget: bool,
+    /// The requested meeting, or the one being recorded.
+    pub session_id: Option<String>,
+    pub session: UsageByEngine,
+    /// Since usage was first tracked.
+    pub total: UsageByEngine,
+}
+
+/// Load the ledger kept in the app data directory.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let path = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(USAGE_FILE),
+        Err(e) => {
+            error!("Failed to resolve app data directory for usage: {}", e);
+            return;
+        }
+    };
+    let ledger = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Usage file {:?} is unreadable, starting over: {}", path, e);
+            Ledger::default()
+        }),
+        Err(_) => Ledger::default(),
+    };
+    if let Ok(mut state) = STATE.lock() {
+        state.ledger = ledger;
+        state.path = Some(path);
+    }
+}
+
+fn month() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+fn cost(usage: &UsageByEngine) -> f64 {
+    usage.values().map(|usage| usage.cost).sum()
+}
+
+fn save(state: &mut State) {
+    let Some(path) = state.path.clone() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            error!("Failed to create usage directory: {}", e);
+            return;
+        }
+    }
+    let result = serde_json::to_string_pretty(&state.ledger)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(&path, content).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => {
+            state.dirty = false;
+            state.saved_at = Some(Instant::now());
+        }
+        Err(e) => error!("Failed to save usage: {}", e),
+    }
+}
+
+fn add(engine: &str, secs: f64, requests: u64) {
+    let config = settings::get().usage;
+    let minutes = secs / 60.0;
+    let usage = EngineUsage {
+        minutes,
+        requests,
+        cost: minutes * config.price_per_minute.get(engine).copied().unwrap_or(0.0),
+    };
+    let month = month();
+    let Ok(mut state) = STATE.lock() else {
+        return;
+    };
+    state.ledger.months.entry(month.clone()).or_default().entry(engine.to_string()).or_default().add(&usage);
+    if let Some(session_id) = storage::current_session() {
+        state.ledger.sessions.entry(session_id).or_default().entry(engine.to_string()).or_default().add(&usage);
+    }
+    state.dirty = true;
+    if state.saved_at.map_or(true, |at| at.elapsed() >= SAVE_INTERVAL) {
+        save(&mut state);
+    }
+
+    let spent = state.ledger.months.get(&month).map(cost).unwrap_or(0.0);
+    if let Some(budget) = config.monthly_budget.filter(|budget| spent >= *budget) {
+        if state.exceeded.as_deref() != Some(month.as_str()) {
+            state.exceeded = Some(month.clone());
+            drop(state);
+            warn!("Spent {:.2} of the {:.2} budget for {}, using local transcription", spent, budget, month);
+            pipeline::publish(PipelineEvent::BudgetExceeded { month, spent, budget });
+        }
+    }
+}
+
+/// Count a chunk of `secs` seconds transcribed by `engine`.
+pub fn record(engine: &str, secs: f64) {
+    add(engine, secs, 1);
+}
+
+/// Count `secs` seconds streamed live to `engine`.
+pub fn record_streamed(engine: &str, secs: f64) {
+    add(engine, secs, 0);
+}
+
+/// Write unsaved usage, e.g. when a recording ends.
+pub fn flush() {
+    if let Ok(mut state) = STATE.lock() {
+        if state.dirty {
+            save(&mut state);
+        }
+    }
+}
+
+/// Whether this month's budget is spent, so paid engines should be skipped.
+pub fn over_budget() -> bool {
+    let Some(budget) = settings::get().usage.monthly_budget else {
+        return false;
+    };
+    STATE
+        .lock()
+        .map(|state| state.ledger.months.get(&month()).map(cost).unwrap_or(0.0) >= budget)
+        .unwrap_or(false)
+}
+
+#[command]
+pub fn get_usage_stats(session_id: Option<String>) -> Result<UsageStats, String> {
+    let state = STATE.lock().map_err(|_| "Usage lock poisoned".to_string())?;
+    let month = month();
+    let this_month = state.ledger.months.get(&month).cloned().unwrap_or_default();
+    let session_id = session_id.or_else(storage::current_session);
+    let session = session_id
+        .as_ref()
+        .and_then(|id| state.ledger.sessions.get(id))
+        .cloned()
+        .unwrap_or_default();
+    let mut total = UsageByEngine::new();
+    for (engine, usage) in state.ledger.months.values().flatten() {
+        total.entry(engine.clone()).or_default().add(usage);
+    }
+    let monthly_budget = settings::get().usage.monthly_budget;
+    let month_cost = cost(&this_month);
+    Ok(UsageStats {
+        month,
+        over_budget: monthly_budget.is_some_and(|budget| month_cost >= budget),
+        this_month,
+        month_cost,
+        monthly_budget,
+        session_id,
+        session,
+        total,
+    })
+}
+
+#[command]
+pub fn get_usage_settings() -> UsageSettings {
+    settings::get().usage
+}
+
+#[command]
+pub fn set_usage_settings(usage_settings: UsageSettings) -> Result<UsageSettings, String> {
+    if usage_settings.price_per_minute.values().any(|price| !price.is_finite() || *price < 0.0) {
+        return Err("Prices must be zero or more".to_string());
+    }
+    if usage_settings.monthly_budget.is_some_and(|budget| !budget.is_finite() || budget < 0.0) {
+        return Err("Monthly budget must be zero or more".to_string());
+    }
+    let updated = settings::update(|s| s.usage = usage_settings).map(|s| s.usage)?;
+    if !over_budget() {
+        if let Ok(mut state) = STATE.lock() {
+            if state.exceeded.take().is_some() {
+                info!("Monthly budget raised, paid engines are used again");
+            }
+        }
+    }
+    Ok(updated)
+}
@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use log::{debug, error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::mpsc;
+
+use crate::audio::decode::decode_audio_file;
+use crate::export::naming::ExportMeeting;
+use crate::export::rules::{render_document, DocumentFormat, FinishedMeeting};
+use crate::export::subtitles::{self, Cue, SubtitleFormat};
+use crate::quick_transcribe::AUDIO_EXTENSIONS;
+use crate::transcription::{self, AudioInput};
+use crate::{
+    is_recording, resample_audio, settings, TranscriptSegment, TranscriptUpdate, CHUNK_DURATION_MS, WHISPER_SAMPLE_RATE,
+};
+
+/// Sent with the outcome of every watched file.
+pub const WATCH_FOLDER_EVENT: &str = "watch-folder-processed";
+/// How often watched folders are scanned for new files.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A folder whose audio files are transcribed as soon as they appear.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchFolder {
+    pub path: String,
+    pub enabled: bool,
+    /// Engine id to transcribe with; the active engine when unset.
+    pub engine: Option<String>,
+    /// Language to decode in; the engine's own setting when unset.
+    pub language: Option<String>,
+    /// Document written next to each source file.
+    pub format: DocumentFormat,
+    /// Subtitles also written next to each source file.
+    pub subtitles: Option<SubtitleFormat>,
+    /// Move source files, with their exports, into this subfolder once
+    /// transcribed; left in place when unset.
+    pub processed_subfolder: Option<String>,
+}
+
+impl Default for WatchFolder {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            enabled: true,
+            engine: None,
+            language: None,
+            format: DocumentFormat::Text,
+            subtitles: None,
+            processed_subfolder: Some("processed".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchFolderSettings {
+    pub enabled: bool,
+    pub folders: Vec<WatchFolder>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedFile {
+    pub source: String,
+    /// Files written for the source, at their final location.
+    pub outputs: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileState {
+    /// Size at the last scan; a file is queued once its size stops changing.
+    Growing(u64),
+    Queued,
+    /// Transcribed or failed; not picked up again unless modified.
+    Done(SystemTime),
+}
+
+struct Job {
+    path: PathBuf,
+    folder: WatchFolder,
+}
+
+static FILES: Lazy<Mutex<HashMap<PathBuf, FileState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Queue files in `folder` that have finished being written.
+fn scan(folder: &WatchFolder, jobs: &mpsc::UnboundedSender<Job>) {
+    let entries = match fs::read_dir(&folder.path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Can't read watch folder {}: {}", folder.path, e);
+            return;
+        }
+    };
+    let Ok(mut files) = FILES.lock() else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() || !is_audio(&path) {
+            continue;
+        }
+        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let next = match files.get(&path) {
+            Some(FileState::Queued) => continue,
+            Some(FileState::Done(at)) if *at == modified => continue,
+            Some(FileState::Growing(size)) if *size == meta.len() && meta.len() > 0 => {
+                let _ = jobs.send(Job {
+                    path: path.clone(),
+                    folder: folder.clone(),
+                });
+                FileState::Queued
+            }
+            _ => FileState::Growing(meta.len()),
+        };
+        files.insert(path, next);
+    }
+}
+
+/// Transcript lines for segments paired with the offset of their chunk.
+fn transcript_lines(segments: &[(f32, TranscriptSegment)]) -> Vec<TranscriptUpdate> {
+    segments
+        .iter()
+        .filter(|(_, segment)| !segment.text.trim().is_empty())
+        .map(|(offset, segment)| TranscriptUpdate {
+            text: segment.text.trim().to_string(),
+            timestamp: format!("{:.1} - {:.1}", offset + segment.t0, offset + segment.t1),
+            source: segment
+                .speaker
+                .as_ref()
+                .map(|speaker| format!("Speaker {}", speaker))
+                .unwrap_or_else(|| "Speaker".to_string()),
+            source_app: None,
+            speaker_display: None,
+            language: segment.language.clone(),
+            translated_from: segment.translated_from.clone(),
+        })
+        .collect()
+}
+
+async fn transcribe_file(path: &Path, folder: &WatchFolder) -> Result<Vec<PathBuf>, String> {
+    let engine = match &folder.engine {
+        Some(id) => transcription::engine(id).ok_or_else(|| format!("Unknown transcription engine '{}'", id))?,
+        None => transcription::active(),
+    };
+    let file = path.to_path_buf();
+    let samples = tokio::task::spawn_blocking(move || {
+        let (samples, rate) = decode_audio_file(&file).map_err(|e| format!("Failed to decode {:?}: {}", file, e))?;
+        Ok::<_, String>(resample_audio(&samples, rate, WHISPER_SAMPLE_RATE))
+    })
+    .await
+    .map_err(|e| format!("Decode task failed: {}", e))??;
+    let duration_secs = samples.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+    info!("Transcribing {:?} ({:.0} s) with {}", path, duration_secs, engine.name());
+
+    let chunk_len = (WHISPER_SAMPLE_RATE as u64 * CHUNK_DURATION_MS as u64 / 1000) as usize;
+    let mut segments = Vec::new();
+    for (index, chunk) in samples.chunks(chunk_len).enumerate() {
+        let input = AudioInput {
+            samples: chunk.to_vec(),
+            sample_rate: WHISPER_SAMPLE_RATE,
+            language: folder.language.clone(),
+            device: None,
+        };
+        let offset = (index * chunk_len) as f32 / WHISPER_SAMPLE_RATE as f32;
+        let transcript = transcription::transcribe_with(engine.clone(), &input).await?;
+        segments.extend(transcript.segments.into_iter().map(|segment| (offset, segment)));
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "transcript".to_string());
+    let meeting = FinishedMeeting {
+        meeting: ExportMeeting {
+            id: stem.clone(),
+            title: Some(stem.clone()),
+            started_at: None,
+            tags: Vec::new(),
+            metadata: BTreeMap::from([("source_file".to_string(), path.to_string_lossy().to_string())]),
+        },
+        duration_secs: duration_secs as u64,
+        transcript: transcript_lines(&segments),
+        summary: None,
+    };
+    let document = render_document(&meeting, folder.format);
+    let extension = Path::new(&document.file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "txt".to_string());
+    let mut outputs = vec![(format!("{}.{}", stem, extension), document.content.unwrap_or_default())];
+    if let Some(format) = folder.subtitles {
+        let cues: Vec<Cue> = segments
+            .iter()
+            .filter(|(_, segment)| !segment.text.trim().is_empty())
+            .map(|(offset, segment)| Cue {
+                start_ms: ((offset + segment.t0) * 1000.0) as u64,
+                end_ms: ((offset + segment.t1) * 1000.0) as u64,
+                text: segment.text.trim().to_string(),
+            })
+            .collect();
+        outputs.push((format!("{}.{}", stem, format.extension()), subtitles::render(&cues, format)));
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let target_dir = match &folder.processed_subfolder {
+        Some(sub) => {
+            let target = dir.join(sub);
+            fs::create_dir_all(&target).map_err(|e| format!("Failed to create {:?}: {}", target, e))?;
+            target
+        }
+        None => dir.to_path_buf(),
+    };
+    let mut written = Vec::new();
+    for (name, content) in outputs {
+        let out = target_dir.join(name);
+        fs::write(&out, content).map_err(|e| format!("Failed to write {:?}: {}", out, e))?;
+        written.push(out);
+    }
+    if folder.processed_subfolder.is_some() {
+        if let Some(name) = path.file_name() {
+            let moved = target_dir.join(name);
+            fs::rename(path, &moved).map_err(|e| format!("Failed to move {:?}: {}", path, e))?;
+        }
+    }
+    Ok(written)
+}
+
+async fn process<R: Runtime>(app: &AppHandle<R>, job: Job) {
+    // Recordings share the whisper server's rolling buffer; wait them out
+    while is_recording() {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    let result = transcribe_file(&job.path, &job.folder).await;
+    let outcome = match result {
+        Ok(outputs) => {
+            info!("Transcribed watched file {:?}", job.path);
+            WatchedFile {
+                source: job.path.to_string_lossy().to_string(),
+                outputs: outputs.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                error: None,
+            }
+        }
+        Err(e) => {
+            error!("Failed to transcribe watched file {:?}: {}", job.path, e);
+            WatchedFile {
+                source: job.path.to_string_lossy().to_string(),
+                outputs: Vec::new(),
+                error: Some(e),
+            }
+        }
+    };
+    // A file left in place keeps its entry so it isn't transcribed again
+    if let Ok(mut files) = FILES.lock() {
+        match fs::metadata(&job.path).and_then(|meta| meta.modified()) {
+            Ok(modified) => files.insert(job.path.clone(), FileState::Done(modified)),
+            Err(_) => files.remove(&job.path),
+        };
+    }
+    let _ = app.emit(WATCH_FOLDER_EVENT, outcome);
+}
+
+/// Scan watched folders and transcribe new files one at a time.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let (jobs_tx, mut jobs_rx) = mpsc::unbounded_channel::<Job>();
+    let worker = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(job) = jobs_rx.recv().await {
+            process(&worker, job).await;
+        }
+    });
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let config = settings::get().watch_folders;
+            if !config.enabled {
+                continue;
+            }
+            for folder in config.folders.iter().filter(|folder| folder.enabled) {
+                scan(folder, &jobs_tx);
+            }
+        }
+    });
+}
+
+#[command]
+pub fn get_watch_folder_settings() -> WatchFolderSettings {
+    settings::get().watch_folders
+}
+
+#[command]
+pub fn set_watch_folder_settings(mut watch_folder_settings: WatchFolderSettings) -> Result<WatchFolderSettings, String> {
+    for folder in &mut watch_folder_settings.folders {
+        folder.path = folder.path.trim().to_string();
+        if !Path::new(&folder.path).is_dir() {
+            return Err(format!("Watch folder '{}' is not a directory", folder.path));
+        }
+        if let Some(id) = folder.engine.as_deref() {
+            if transcription::engine(id).is_none() {
+                return Err(format!("Unknown transcription engine '{}'", id));
+            }
+        }
+        folder.language = folder
+            .language
+            .as_ref()
+            .map(|code| code.trim().to_lowercase())
+            .filter(|code| !code.is_empty());
+        folder.processed_subfolder = folder
+            .processed_subfolder
+            .as_ref()
+            .map(|sub| sub.trim().to_string())
+            .filter(|sub| !sub.is_empty());
+        let single_folder = |sub: &str| {
+            let mut components = Path::new(sub).components();
+            !sub.contains(['/', '\\'])
+                && matches!(components.next(), Some(Component::Normal(_)))
+                && components.next().is_none()
+        };
+        if folder.processed_subfolder.as_deref().is_some_and(|sub| !single_folder(sub)) {
+            return Err("The processed subfolder must be a single folder name".to_string());
+        }
+    }
+    settings::update(|s| s.watch_folders = watch_folder_settings).map(|s| s.watch_folders)
+}
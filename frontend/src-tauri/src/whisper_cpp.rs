@@ -0,0 +1,275 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use futures_util::future::BoxFuture;
+use log::info;
+use once_cell::sync::Lazy;
+use reqwest::multipart::Form;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::models::{self, ModelError, ModelKind};
+use crate::transcription::{AudioInput, Transcript, TranscriptionEngine};
+use crate::{settings, whisper_decode};
+
+const MODEL_EXTENSIONS: [&str; 2] = ["bin", "gguf"];
+
+/// A model build that can be downloaded from Hugging Face with the model manager.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelVariant {
+    pub file: &'static str,
+    /// Model it's a build of, e.g. `small`, `large-v3-turbo`, `distil-large-v3`.
+    pub family: &'static str,
+    /// `None` for full-precision weights.
+    pub quantization: Option<&'static str>,
+    pub size_mb: u32,
+    /// Rough decoding speed relative to full large-v3 on the same machine.
+    pub relative_speed: f32,
+    pub english_only: bool,
+    #[serde(skip)]
+    repo: &'static str,
+}
+
+impl ModelVariant {
+    pub fn url(&self) -> String {
+        format!("https://huggingface.co/{}/resolve/main/{}", self.repo, self.file)
+    }
+}
+
+const fn variant(
+    file: &'static str,
+    family: &'static str,
+    quantization: Option<&'static str>,
+    size_mb: u32,
+    relative_speed: f32,
+) -> ModelVariant {
+    ModelVariant {
+        file,
+        family,
+        quantization,
+        size_mb,
+        relative_speed,
+        english_only: false,
+        repo: "ggerganov/whisper.cpp",
+    }
+}
+
+/// Builds offered for download, smallest first within each family. Quantized
+/// builds decode at about the speed of their full-precision model with a
+/// fraction of the memory; turbo and distil-whisper trade a little accuracy
+/// for large-v3 quality at several times its speed.
+pub const CATALOG: &[ModelVariant] = &[
+    variant("ggml-tiny-q5_1.bin", "tiny", Some("q5_1"), 31, 32.0),
+    variant("ggml-tiny-q8_0.bin", "tiny", Some("q8_0"), 42, 32.0),
+    variant("ggml-tiny.bin", "tiny", None, 75, 32.0),
+    variant("ggml-base-q5_1.bin", "base", Some("q5_1"), 57, 16.0),
+    variant("ggml-base-q8_0.bin", "base", Some("q8_0"), 78, 16.0),
+    variant("ggml-base.bin", "base", None, 142, 16.0),
+    ModelVariant {
+        english_only: true,
+        ..variant("ggml-base.en-q5_1.bin", "base.en", Some("q5_1"), 57, 16.0)
+    },
+    variant("ggml-small-q5_1.bin", "small", Some("q5_1"), 181, 6.0),
+    variant("ggml-small-q8_0.bin", "small", Some("q8_0"), 252, 6.0),
+    variant("ggml-small.bin", "small", None, 466, 6.0),
+    variant("ggml-medium-q5_0.bin", "medium", Some("q5_0"), 514, 2.0),
+    variant("ggml-medium-q8_0.bin", "medium", Some("q8_0"), 785, 2.0),
+    variant("ggml-medium.bin", "medium", None, 1500, 2.0),
+    variant("ggml-large-v3-turbo-q5_0.bin", "large-v3-turbo", Some("q5_0"), 547, 8.0),
+    variant("ggml-large-v3-turbo-q8_0.bin", "large-v3-turbo", Some("q8_0"), 834, 8.0),
+    variant("ggml-large-v3-turbo.bin", "large-v3-turbo", None, 1620, 8.0),
+    ModelVariant {
+        repo: "distil-whisper/distil-large-v3-ggml",
+        ..variant("ggml-distil-large-v3.bin", "distil-large-v3", None, 1520, 6.0)
+    },
+    variant("ggml-large-v3-q5_0.bin", "large-v3", Some("q5_0"), 1080, 1.0),
+    variant("ggml-large-v3.bin", "large-v3", None, 3100, 1.0),
+];
+
+fn catalog_variant(file: &str) -> Option<&'static ModelVariant> {
+    CATALOG.iter().find(|variant| variant.file == file)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WhisperCppSettings {
+    /// Base URL of the whisper.cpp server.
+    pub server_url: String,
+    /// Folder holding GGML/GGUF model files.
+    pub models_dir: Option<String>,
+    /// File name of the model to load, e.g. `ggml-base.en-q5_1.bin`.
+    pub model: Option<String>,
+}
+
+impl Default for WhisperCppSettings {
+    fn default() -> Self {
+        Self {
+            server_url: "http://127.0.0.1:8178".to_string(),
+            models_dir: None,
+            model: None,
+        }
+    }
+}
+
+impl WhisperCppSettings {
+    /// The selected model on disk: in the local model folder, or else the
+    /// models folder. The error lists everywhere it was looked for.
+    pub(crate) fn model_path(&self) -> Result<PathBuf, String> {
+        let file = self
+            .model
+            .as_deref()
+            .ok_or_else(|| "Choose a whisper.cpp model first".to_string())?;
+        models::find_local(ModelKind::Whisper, file, self.models_dir.as_deref().map(PathBuf::from)).map_err(|missing| {
+            if settings::get().models.offline_mode {
+                ModelError::Offline { missing: vec![missing] }.to_string()
+            } else {
+                format!("Model {} not found in {}", missing.file, missing.searched.join(", "))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModel {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Quantized weights (`q4_0`, `q5_1`, `q8_0`, ...) need far less memory.
+    pub quantized: bool,
+    /// Size and speed details when the file is one of the downloadable builds.
+    pub variant: Option<ModelVariant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    #[serde(flatten)]
+    pub variant: ModelVariant,
+    pub installed: bool,
+}
+
+/// The model the server was last asked to load, so it's only reloaded on change.
+/// The server holds one model at a time, which the default engine then shares.
+static LOADED: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// A quantized GGML/GGUF model run by the whisper.cpp server, for machines
+/// without the memory for full-precision weights. Responses have the same
+/// shape as the default whisper engine's.
+#[derive(Default)]
+pub struct WhisperCppEngine {
+    client: reqwest::Client,
+}
+
+impl WhisperCppEngine {
+    pub const ID: &'static str = "whisper-cpp";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn ensure_loaded(&self, config: &WhisperCppSettings) -> Result<(), String> {
+        let path = config.model_path()?;
+        if LOADED.lock().map(|loaded| loaded.as_ref() == Some(&path)).unwrap_or(false) {
+            return Ok(());
+        }
+
+        info!("Loading whisper.cpp model {}", path.display());
+        let form = Form::new().text("model", path.to_string_lossy().to_string());
+        let response = self
+            .client
+            .post(format!("{}/load", config.server_url.trim_end_matches('/')))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("whisper.cpp load request failed: {}", e))?;
+        let body = response.text().await.unwrap_or_default();
+        if body.contains("error") {
+            return Err(format!("whisper.cpp failed to load {}: {}", path.display(), body));
+        }
+        if let Ok(mut loaded) = LOADED.lock() {
+            *loaded = Some(path);
+        }
+        Ok(())
+    }
+
+    async fn transcribe_chunk(&self, audio: &AudioInput) -> Result<Transcript, String> {
+        let config = settings::get().whisper_cpp;
+        self.ensure_loaded(&config).await?;
+        let endpoint = format!("{}/stream", config.server_url.trim_end_matches('/'));
+        whisper_decode::send(audio, &self.client, &endpoint).await
+    }
+}
+
+impl TranscriptionEngine for WhisperCppEngine {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn name(&self) -> &str {
+        "whisper.cpp (quantized)"
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a AudioInput) -> BoxFuture<'a, Result<Transcript, String>> {
+        Box::pin(self.transcribe_chunk(audio))
+    }
+}
+
+fn is_model_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| MODEL_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+#[command]
+pub fn list_whisper_cpp_models() -> Result<Vec<LocalModel>, String> {
+    let Some(dir) = settings::get().whisper_cpp.models_dir else {
+        return Ok(Vec::new());
+    };
+    let entries = std::fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir, e))?;
+    let mut models: Vec<LocalModel> = entries
+        .flatten()
+        .filter(|entry| is_model_file(&entry.path()))
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            LocalModel {
+                quantized: name.contains("-q"),
+                size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                variant: catalog_variant(&name).cloned(),
+                name,
+            }
+        })
+        .collect();
+    models.sort_by_key(|model| model.size_bytes);
+    Ok(models)
+}
+
+/// Every downloadable build with its size and speed, and whether it's in the
+/// models folder already.
+#[command]
+pub fn list_whisper_cpp_catalog() -> Vec<CatalogEntry> {
+    let dir = settings::get().whisper_cpp.models_dir;
+    CATALOG
+        .iter()
+        .map(|variant| CatalogEntry {
+            installed: dir
+                .as_deref()
+                .is_some_and(|dir| Path::new(dir).join(variant.file).is_file()),
+            variant: variant.clone(),
+        })
+        .collect()
+}
+
+#[command]
+pub fn get_whisper_cpp_settings() -> WhisperCppSettings {
+    settings::get().whisper_cpp
+}
+
+#[command]
+pub fn set_whisper_cpp_settings(whisper_cpp_settings: WhisperCppSettings) -> Result<WhisperCppSettings, String> {
+    if whisper_cpp_settings.model.is_some() {
+        let path = whisper_cpp_settings.model_path()?;
+        if !is_model_file(&path) {
+            return Err(format!("{} is not a GGML/GGUF model file", path.display()));
+        }
+    }
+    settings::update(|s| s.whisper_cpp = whisper_cpp_settings).map(|s| s.whisper_cpp)
+}
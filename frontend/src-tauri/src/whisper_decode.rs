@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::transcription::{AudioInput, Transcript, TranscriptionMode};
+use crate::language_id::{self, DetectedLanguage};
+use crate::{bilingual, name_bias, send_audio_chunk_with, settings};
+
+const MAX_BEAM_SIZE: u32 = 8;
+
+/// How the whisper server decodes each chunk: trade accuracy for speed with
+/// the beam size and temperature schedule, and bias the model toward domain
+/// vocabulary with the initial prompt or away from banned words and phrases.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WhisperDecodeOptions {
+    /// 1 decodes greedily; wider beams are slower and usually more accurate.
+    pub beam_size: u32,
+    /// Sampling temperature of the first decode attempt; 0 is deterministic.
+    pub temperature: f32,
+    /// How much the temperature rises on each retry of a decode that looks
+    /// like a repetition loop or gibberish; 0 disables retries.
+    pub temperature_increment: f32,
+    /// Segments the model rates as silence above this probability are dropped.
+    pub no_speech_threshold: f32,
+    /// Text given to the decoder as preceding context, e.g. names, product
+    /// terms and acronyms expected in the meeting.
+    pub initial_prompt: Option<String>,
+    /// Words and phrases the decoder may never produce, e.g. a competitor's
+    /// brand spelled the way it's often misheard.
+    pub suppress_tokens: Vec<String>,
+    /// Suppress non-speech tokens such as "[Music]" and "(coughs)" that some
+    /// microphones make the model emit on noise.
+    pub suppress_non_speech: bool,
+}
+
+impl Default for WhisperDecodeOptions {
+    fn default() -> Self {
+        Self {
+            beam_size: 1,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            no_speech_threshold: 0.6,
+            initial_prompt: None,
+            suppress_tokens: Vec::new(),
+            suppress_non_speech: false,
+        }
+    }
+}
+
+impl WhisperDecodeOptions {
+    /// Form fields for a chunk request, forcing `language`'s code and adding
+    /// its vocabulary to the prompt when the chunk's language was identified,
+    /// along with the names of the people in the meeting.
+    fn form_fields(&self, language: Option<bilingual::LanguageProfile>) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("beam_size", self.beam_size.to_string()),
+            ("temperature", self.temperature.to_string()),
+            ("temperature_inc", self.temperature_increment.to_string()),
+            ("no_speech_thold", self.no_speech_threshold.to_string()),
+            ("suppress_nst", self.suppress_non_speech.to_string()),
+        ];
+        if !self.suppress_tokens.is_empty() {
+            fields.push(("suppress_tokens", self.suppress_tokens.join("\n")));
+        }
+        let mut prompt: Vec<String> = self.initial_prompt.iter().cloned().collect();
+        if let Some(language) = language {
+            fields.push(("language", language.code));
+            prompt.extend(language.vocabulary);
+        }
+        prompt.extend(name_bias::names());
+        if !prompt.is_empty() {
+            fields.push(("prompt", prompt.join(", ")));
+        }
+        fields
+    }
+}
+
+/// The language a chunk should be decoded in, if one was identified
+/// confidently enough to force, and the language to record on its segments.
+async fn chunk_language(
+    samples: &[f32],
+    client: &reqwest::Client,
+    endpoint: &str,
+) -> (Option<bilingual::LanguageProfile>, Option<DetectedLanguage>) {
+    if let Some((profile, confidence)) = bilingual::language_for(samples, client, endpoint).await {
+        let detected = DetectedLanguage {
+            code: profile.code.clone(),
+            confidence,
+        };
+        return (Some(profile), Some(detected));
+    }
+    let Some(detected) = language_id::identify(samples, client, endpoint).await else {
+        return (None, None);
+    };
+    let forced = (detected.confidence >= settings::get().language_id.min_confidence).then(|| {
+        bilingual::LanguageProfile {
+            code: detected.code.clone(),
+            vocabulary: Vec::new(),
+        }
+    });
+    (forced, Some(detected))
+}
+
+/// Send a chunk to a whisper server, decoded with the configured options in
+/// the input's language or, when it has none, its identified language. Each
+/// segment records the language it was identified as.
+pub async fn send(audio: &AudioInput, client: &reqwest::Client, endpoint: &str) -> Result<Transcript, String> {
+    let (forced, detected) = match &audio.language {
+        Some(code) => {
+            let profile = bilingual::LanguageProfile {
+                code: code.clone(),
+                vocabulary: Vec::new(),
+            };
+            (Some(profile), None)
+        }
+        None => chunk_language(&audio.samples, client, endpoint).await,
+    };
+    let config = settings::get();
+    let mut fields = config.whisper_decode.form_fields(forced);
+    if config.transcription.mode == TranscriptionMode::Translate {
+        fields.push(("translate", "true".to_string()));
+    }
+    let mut transcript = send_audio_chunk_with(audio.samples.clone(), client, endpoint, &fields).await?;
+    if detected.is_some() {
+        for segment in &mut transcript.segments {
+            segment.language = detected.clone();
+        }
+    }
+    Ok(transcript)
+}
+
+#[command]
+pub fn get_whisper_decode_options() -> WhisperDecodeOptions {
+    settings::get().whisper_decode
+}
+
+#[command]
+pub fn set_whisper_decode_options(mut options: WhisperDecodeOptions) -> Result<WhisperDecodeOptions, String> {
+    if options.beam_size == 0 || options.beam_size > MAX_BEAM_SIZE {
+        return Err(format!("Beam size must be between 1 and {}", MAX_BEAM_SIZE));
+    }
+    if !(0.0..=1.0).contains(&options.temperature) || !(0.0..=1.0).contains(&options.temperature_increment) {
+        return Err("Temperature and its increment must be between 0 and 1".to_string());
+    }
+    options.no_speech_threshold = options.no_speech_threshold.clamp(0.0, 1.0);
+    options.initial_prompt = options
+        .initial_prompt
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty());
+    let mut suppress_tokens: Vec<String> = Vec::new();
+    // One phrase per line on the wire, so line breaks within one are spaces
+    for phrase in options.suppress_tokens.iter().map(|phrase| phrase.split_whitespace().collect::<Vec<_>>().join(" ")) {
+        if !phrase.is_empty() && !suppress_tokens.contains(&phrase) {
+            suppress_tokens.push(phrase);
+        }
+    }
+    options.suppress_tokens = suppress_tokens;
+    settings::update(|s| s.whisper_decode = options).map(|s| s.whisper_decode)
+}
@@ -0,0 +1,168 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_lib::inference::{Finished, InferencePool, InferenceQueueSettings, Job, OverflowPolicy};
+use app_lib::pipeline::{self, PipelineEvent};
+use app_lib::transcription::AudioInput;
+use futures_util::FutureExt;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+fn job(ticket: u64, device: &str) -> Job {
+    Job {
+        ticket,
+        input: AudioInput {
+            samples: vec![ticket as f32; 160],
+            sample_rate: 16000,
+            language: None,
+            device: Some(device.to_string()),
+        },
+        endpoint: None,
+    }
+}
+
+fn queue(input_capacity: usize, overflow: OverflowPolicy) -> InferenceQueueSettings {
+    InferenceQueueSettings {
+        input_capacity,
+        overflow,
+        ..Default::default()
+    }
+}
+
+/// A pool whose jobs wait for a permit from `gate` and then return the
+/// sample they carried, so spilled audio is checked on the way back.
+fn gated_pool(workers: usize, config: InferenceQueueSettings, gate: Arc<Semaphore>) -> InferencePool<u64> {
+    InferencePool::start(workers, config, move |job: Job| {
+        let gate = gate.clone();
+        async move {
+            let _permit = gate.acquire().await.map_err(|e| e.to_string())?;
+            Ok(job.input.samples[0] as u64)
+        }
+        .boxed()
+    })
+}
+
+async fn collect(pool: &mut InferencePool<u64>, count: usize) -> Vec<Finished<u64>> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut finished = Vec::new();
+    while finished.len() < count {
+        assert!(Instant::now() < deadline, "only {} of {} jobs finished", finished.len(), count);
+        match pool.try_finished() {
+            Some(done) => finished.push(done),
+            None => tokio::time::sleep(Duration::from_millis(1)).await,
+        }
+    }
+    finished
+}
+
+#[tokio::test]
+async fn results_come_back_in_order_per_device() {
+    // Later chunks decode faster, so workers finish them first
+    let mut pool = InferencePool::start(4, InferenceQueueSettings::default(), |job: Job| {
+        async move {
+            tokio::time::sleep(Duration::from_millis(40 - job.ticket * 4)).await;
+            Ok(job.ticket)
+        }
+        .boxed()
+    });
+    for ticket in 0..8 {
+        let device = if ticket % 2 == 0 { "mic" } else { "system" };
+        pool.submit(job(ticket, device)).await;
+    }
+
+    let finished = collect(&mut pool, 8).await;
+    let mic: Vec<u64> = finished.iter().map(|done| done.ticket).filter(|ticket| ticket % 2 == 0).collect();
+    let system: Vec<u64> = finished.iter().map(|done| done.ticket).filter(|ticket| ticket % 2 == 1).collect();
+    assert_eq!(mic, vec![0, 2, 4, 6]);
+    assert_eq!(system, vec![1, 3, 5, 7]);
+    assert!(pool.is_idle());
+}
+
+#[tokio::test]
+async fn drop_oldest_fails_the_oldest_waiting_chunk() {
+    let gate = Arc::new(Semaphore::new(0));
+    let mut pool = gated_pool(1, queue(2, OverflowPolicy::DropOldest), gate.clone());
+    pool.submit(job(0, "mic")).await;
+    // Let the worker take the first chunk
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    for ticket in 1..4 {
+        pool.submit(job(ticket, "mic")).await;
+    }
+    assert_eq!(pool.waiting(), 2);
+
+    gate.add_permits(1);
+    let finished = collect(&mut pool, 4).await;
+    let tickets: Vec<u64> = finished.iter().map(|done| done.ticket).collect();
+    assert_eq!(tickets, vec![0, 1, 2, 3]);
+    assert!(finished[1].dropped);
+    assert!(finished[1].result.is_err());
+    assert!(finished.iter().filter(|done| done.ticket != 1).all(|done| done.result == Ok(done.ticket)));
+}
+
+#[tokio::test]
+async fn spill_to_disk_keeps_every_chunk_in_order() {
+    let gate = Arc::new(Semaphore::new(0));
+    let mut pool = gated_pool(1, queue(1, OverflowPolicy::SpillToDisk), gate.clone());
+    pool.submit(job(0, "mic")).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    for ticket in 1..5 {
+        pool.submit(job(ticket, "mic")).await;
+    }
+    assert_eq!(pool.waiting(), 4);
+
+    gate.add_permits(1);
+    let finished = collect(&mut pool, 5).await;
+    let tickets: Vec<u64> = finished.iter().map(|done| done.ticket).collect();
+    assert_eq!(tickets, vec![0, 1, 2, 3, 4]);
+    assert!(finished.iter().all(|done| !done.dropped && done.result == Ok(done.ticket)));
+}
+
+#[tokio::test]
+async fn block_waits_for_a_worker_to_take_a_chunk() {
+    let gate = Arc::new(Semaphore::new(0));
+    let mut pool = gated_pool(1, queue(1, OverflowPolicy::Block), gate.clone());
+    pool.submit(job(0, "mic")).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    pool.submit(job(1, "mic")).await;
+
+    let release = gate.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        release.add_permits(1);
+    });
+    let started = Instant::now();
+    pool.submit(job(2, "mic")).await;
+    assert!(started.elapsed() >= Duration::from_millis(80));
+
+    let tickets: Vec<u64> = collect(&mut pool, 3).await.iter().map(|done| done.ticket).collect();
+    assert_eq!(tickets, vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn backpressure_is_published_at_the_high_water_mark() {
+    let mut events = pipeline::subscribe();
+    let gate = Arc::new(Semaphore::new(0));
+    let config = InferenceQueueSettings {
+        input_capacity: 6,
+        high_water: 0.5,
+        ..Default::default()
+    };
+    let mut pool = gated_pool(1, config, gate.clone());
+    pool.submit(job(0, "mic")).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    for ticket in 1..4 {
+        pool.submit(job(ticket, "mic")).await;
+    }
+
+    // Other tests publish too; this pool is the only one with six slots
+    let warning = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            if let Ok(PipelineEvent::Backpressure { queued, capacity: 6, .. }) = events.recv().await {
+                return queued;
+            }
+        }
+    })
+    .await;
+    assert_eq!(warning, Ok(3));
+    gate.add_permits(1);
+}
@@ -0,0 +1,68 @@
+use app_lib::audio::audio_processing::{resample, StreamResampler, MAX_RESAMPLE_RATIO};
+use app_lib::resample_audio;
+use proptest::prelude::*;
+
+fn sample() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        8 => -1.0f32..1.0,
+        1 => Just(f32::NAN),
+        1 => Just(f32::INFINITY),
+        1 => Just(f32::NEG_INFINITY),
+    ]
+}
+
+fn rate() -> impl Strategy<Value = u32> {
+    prop_oneof![
+        Just(0u32),
+        Just(1u32),
+        Just(8000u32),
+        Just(16000u32),
+        Just(44100u32),
+        Just(48000u32),
+        Just(192000u32),
+        Just(u32::MAX),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn resample_output_is_finite(input in prop::collection::vec(sample(), 0..2048), from in rate(), to in rate()) {
+        match resample(&input, from, to) {
+            Ok(output) => prop_assert!(output.iter().all(|s| s.is_finite())),
+            Err(_) => {
+                let ratio = to as f64 / from as f64;
+                prop_assert!(from == 0 || to == 0 || !(1.0 / MAX_RESAMPLE_RATIO..=MAX_RESAMPLE_RATIO).contains(&ratio));
+            }
+        }
+    }
+
+    #[test]
+    fn linear_resample_output_is_finite(input in prop::collection::vec(sample(), 0..4096), from in rate(), to in rate()) {
+        let output = resample_audio(&input, from, to);
+        prop_assert!(output.iter().all(|s| s.is_finite()));
+    }
+
+    #[test]
+    fn stream_resample_ignores_piece_boundaries(input in prop::collection::vec(-1.0f32..1.0, 0..4096), cut in 1usize..700) {
+        let mut whole = StreamResampler::new(48000, 16000).unwrap();
+        let mut pieces = StreamResampler::new(48000, 16000).unwrap();
+        let expected = whole.process(&input);
+        let output: Vec<f32> = input.chunks(cut).flat_map(|piece| pieces.process(piece)).collect();
+        prop_assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn empty_input_resamples_to_empty() {
+    assert!(resample(&[], 48000, 16000).unwrap().is_empty());
+    assert!(resample_audio(&[], 48000, 16000).is_empty());
+}
+
+#[test]
+fn zero_rates_are_rejected() {
+    assert!(resample(&[0.0; 16], 0, 16000).is_err());
+    assert!(resample(&[0.0; 16], 48000, 0).is_err());
+    assert!(resample_audio(&[0.0; 16], 0, 16000).is_empty());
+}
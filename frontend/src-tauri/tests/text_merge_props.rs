@@ -0,0 +1,64 @@
+use app_lib::audio::text_merge::{
+    cleanup_overlap, cleanup_overlap_heuristic, longest_common_word_substring,
+};
+use proptest::prelude::*;
+
+fn transcript() -> impl Strategy<Value = String> {
+    // Small vocabulary so overlaps actually happen; include punctuation and odd whitespace
+    prop::collection::vec(
+        prop_oneof![
+            Just("the"),
+            Just("meeting"),
+            Just("starts"),
+            Just("now"),
+            Just("Now."),
+            Just("..."),
+            Just("-"),
+            Just("ünïcode"),
+            Just("\t"),
+            Just(""),
+        ],
+        0..200,
+    )
+    .prop_map(|words| words.join(" "))
+}
+
+proptest! {
+    #[test]
+    fn common_substring_indices_are_in_bounds(a in transcript(), b in transcript()) {
+        if let Some((i, j)) = longest_common_word_substring(&a, &b) {
+            prop_assert!(i < a.split_whitespace().count());
+            prop_assert!(j < b.split_whitespace().count());
+        }
+    }
+
+    #[test]
+    fn common_substring_points_at_matching_words(a in transcript(), b in transcript()) {
+        if let Some((i, j)) = longest_common_word_substring(&a, &b) {
+            let normalize = |w: &str| {
+                w.chars().filter(|c| !c.is_ascii_punctuation()).collect::<String>().to_lowercase()
+            };
+            let wa = normalize(a.split_whitespace().nth(i).unwrap());
+            let wb = normalize(b.split_whitespace().nth(j).unwrap());
+            prop_assert!(!wa.is_empty());
+            prop_assert_eq!(wa, wb);
+        }
+    }
+
+    #[test]
+    fn cleanup_never_grows_text(a in transcript(), b in transcript()) {
+        for result in [cleanup_overlap(&a, &b), cleanup_overlap_heuristic(&a, &b)] {
+            if let Some((prev, cur)) = result {
+                prop_assert!(prev.split_whitespace().count() <= a.split_whitespace().count());
+                prop_assert!(cur.split_whitespace().count() <= b.split_whitespace().count());
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_unicode_does_not_panic(a in "\\PC*", b in "\\PC*") {
+        let _ = longest_common_word_substring(&a, &b);
+        let _ = cleanup_overlap(&a, &b);
+        let _ = cleanup_overlap_heuristic(&a, &b);
+    }
+}
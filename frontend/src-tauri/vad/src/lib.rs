@@ -0,0 +1,105 @@
+//! Level metering and voice activity detection shared by the app and its
+//! webview. The app uses it directly; the webview loads the WASM build (see
+//! [`wasm`]) for mic previews before the capture pipeline is running, so
+//! both decide what counts as speech the same way.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub const SILENCE_FLOOR_DB: f32 = -120.0;
+
+/// RMS and absolute peak of `samples`; non-finite samples are ignored.
+pub fn compute_levels(samples: &[f32]) -> (f32, f32) {
+    let mut sum_squares = 0.0f64;
+    let mut peak = 0.0f32;
+    let mut count = 0usize;
+    for &sample in samples.iter().filter(|s| s.is_finite()) {
+        sum_squares += (sample as f64) * (sample as f64);
+        peak = peak.max(sample.abs());
+        count += 1;
+    }
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+    (((sum_squares / count as f64).sqrt()) as f32, peak)
+}
+
+pub fn to_db(level: f32) -> f32 {
+    if level <= 0.0 {
+        SILENCE_FLOOR_DB
+    } else {
+        (20.0 * level.log10()).max(SILENCE_FLOOR_DB)
+    }
+}
+
+/// Frame-energy voice activity detector.
+///
+/// Frames whose RMS exceeds `threshold_db` are speech; `hangover_frames` keeps
+/// a segment open through short pauses between words.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EnergyVad {
+    pub threshold_db: f32,
+    pub frame_ms: u32,
+    pub hangover_frames: usize,
+}
+
+impl Default for EnergyVad {
+    fn default() -> Self {
+        Self {
+            threshold_db: -45.0,
+            frame_ms: 30,
+            hangover_frames: 10,
+        }
+    }
+}
+
+/// Hangover carried from frame to frame, so audio can be fed in pieces.
+#[derive(Debug, Clone, Default)]
+pub struct VadState {
+    hangover: usize,
+}
+
+impl VadState {
+    /// Whether `frame`, the next frame of the stream, is speech.
+    pub fn is_speech(&mut self, vad: &EnergyVad, frame: &[f32]) -> bool {
+        let (rms, _) = compute_levels(frame);
+        if to_db(rms) >= vad.threshold_db {
+            self.hangover = vad.hangover_frames;
+            true
+        } else if self.hangover > 0 {
+            self.hangover -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl EnergyVad {
+    pub fn frame_len(&self, sample_rate: u32) -> usize {
+        ((sample_rate as u64 * self.frame_ms.max(1) as u64) / 1000).max(1) as usize
+    }
+
+    /// Per-frame speech decision for `samples`.
+    pub fn frames(&self, samples: &[f32], sample_rate: u32) -> Vec<bool> {
+        let mut state = VadState::default();
+        samples
+            .chunks(self.frame_len(sample_rate))
+            .map(|frame| state.is_speech(self, frame))
+            .collect()
+    }
+
+    /// Fraction of frames classified as speech, 0.0..=1.0.
+    pub fn speech_ratio(&self, samples: &[f32], sample_rate: u32) -> f32 {
+        let frames = self.frames(samples, sample_rate);
+        if frames.is_empty() {
+            return 0.0;
+        }
+        frames.iter().filter(|speech| **speech).count() as f32 / frames.len() as f32
+    }
+}
@@ -0,0 +1,70 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{compute_levels, to_db, EnergyVad, VadState};
+
+/// Streaming VAD for the webview's mic preview. Feed it Web Audio buffers
+/// as they arrive; it keeps partial frames and hangover between calls.
+#[wasm_bindgen]
+pub struct PreviewVad {
+    vad: EnergyVad,
+    state: VadState,
+    frame_len: usize,
+    pending: Vec<f32>,
+    speech: bool,
+    rms_db: f32,
+    peak_db: f32,
+}
+
+#[wasm_bindgen]
+impl PreviewVad {
+    /// Use the parameters from the backend's `get_preview_vad`, so the
+    /// preview agrees with the recording pipeline for the same device.
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: u32, threshold_db: f32, frame_ms: u32, hangover_frames: usize) -> PreviewVad {
+        let vad = EnergyVad {
+            threshold_db,
+            frame_ms,
+            hangover_frames,
+        };
+        PreviewVad {
+            frame_len: vad.frame_len(sample_rate),
+            vad,
+            state: VadState::default(),
+            pending: Vec::new(),
+            speech: false,
+            rms_db: crate::SILENCE_FLOOR_DB,
+            peak_db: crate::SILENCE_FLOOR_DB,
+        }
+    }
+
+    /// Process the next samples; returns whether the latest complete frame
+    /// is speech. Levels are measured over `samples`.
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        let (rms, peak) = compute_levels(samples);
+        self.rms_db = to_db(rms);
+        self.peak_db = to_db(peak);
+
+        self.pending.extend_from_slice(samples);
+        let complete = self.pending.len() - self.pending.len() % self.frame_len;
+        for frame in self.pending[..complete].chunks(self.frame_len) {
+            self.speech = self.state.is_speech(&self.vad, frame);
+        }
+        self.pending.drain(..complete);
+        self.speech
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn speech(&self) -> bool {
+        self.speech
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rms_db(&self) -> f32 {
+        self.rms_db
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn peak_db(&self) -> f32 {
+        self.peak_db
+    }
+}
@@ -0,0 +1,103 @@
+'use client';
+
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/core';
+import { SpeakerDisplay } from '@/types';
+
+interface CaptionLine {
+  speaker: string;
+  display: SpeakerDisplay;
+  text: string;
+}
+
+interface TranscriptionResult {
+  device: string;
+  text: string;
+  is_final: boolean;
+  start: number;
+  end: number;
+  confidence: number;
+}
+
+interface CaptionSettings {
+  font_size: number;
+  high_contrast: boolean;
+  background_opacity: number;
+  max_lines: number;
+  show_speaker: boolean;
+  position: 'top' | 'bottom';
+}
+
+export default function CaptionsPage() {
+  const [lines, setLines] = useState<CaptionLine[]>([]);
+  const [settings, setSettings] = useState<CaptionSettings | null>(null);
+  // In-progress speech per device from live streaming, until the final transcript arrives
+  const [live, setLive] = useState<Record<string, string>>({});
+
+  useEffect(() => {
+    invoke<CaptionSettings>('get_caption_settings').then(setSettings).catch(console.error);
+
+    const unlistenLines = listen<CaptionLine[]>('caption-update', (event) => {
+      setLines(event.payload);
+    });
+    const unlistenLive = listen<TranscriptionResult>('live-transcription', (event) => {
+      const { device, text, is_final } = event.payload;
+      setLive((current) => {
+        const next = { ...current };
+        if (is_final) {
+          delete next[device];
+        } else {
+          next[device] = text;
+        }
+        return next;
+      });
+    });
+    const unlistenSettings = listen<CaptionSettings>('caption-settings', (event) => {
+      setSettings(event.payload);
+    });
+
+    return () => {
+      unlistenLines.then((fn) => fn());
+      unlistenLive.then((fn) => fn());
+      unlistenSettings.then((fn) => fn());
+    };
+  }, []);
+
+  if (!settings) {
+    return null;
+  }
+
+  const background = settings.high_contrast
+    ? 'rgb(0, 0, 0)'
+    : `rgba(0, 0, 0, ${settings.background_opacity})`;
+
+  return (
+    <div
+      data-tauri-drag-region
+      className="fixed inset-0 z-50 flex flex-col justify-end px-6 py-4 rounded-lg"
+      style={{
+        background,
+        color: '#ffffff',
+        fontSize: `${settings.font_size}px`,
+        lineHeight: 1.4,
+      }}
+    >
+      {lines.map((line, index) => (
+        <p key={index} className="font-semibold">
+          {settings.show_speaker && line.speaker && (
+            <span className="mr-2" style={{ color: settings.high_contrast ? undefined : line.display.color }}>
+              {line.speaker}:
+            </span>
+          )}
+          {line.text}
+        </p>
+      ))}
+      {Object.entries(live).map(([device, text]) => (
+        <p key={device} className="opacity-70 italic">
+          {text}
+        </p>
+      ))}
+    </div>
+  );
+}
@@ -1,7 +1,7 @@
 'use client';
 
-import { useState, useEffect, useContext, useCallback } from 'react';
-import { Transcript, Summary, SummaryResponse } from '@/types';
+import { useState, useEffect, useContext, useCallback, useRef } from 'react';
+import { Transcript, Summary, SummaryResponse, SpeakerDisplay } from '@/types';
 import { EditableTitle } from '@/components/EditableTitle';
 import { TranscriptView } from '@/components/TranscriptView';
 import { RecordingControls } from '@/components/RecordingControls';
@@ -20,6 +20,16 @@ interface TranscriptUpdate {
   text: string;
   timestamp: string;
   source: string;
+  source_app?: string | null;
+  speaker_display?: SpeakerDisplay | null;
+}
+
+interface SessionMarker {
+  label: string;
+  offset_ms: number;
+  created_at: string;
+  source?: string | null;
+  link?: string | null;
 }
 
 interface ModelConfig {
@@ -30,6 +40,18 @@ interface ModelConfig {
 
 type SummaryStatus = 'idle' | 'processing' | 'summarizing' | 'regenerating' | 'completed' | 'error';
 
+// Lets the backend notify and run summary-ready export rules for the latest recording
+const reportSummaryReady = (summary: Summary, title: string) => {
+  const actionItems = Object.entries(summary)
+    .filter(([key, section]) => /action/i.test(key) || /action/i.test(section.title))
+    .reduce((count, [, section]) => count + section.blocks.length, 0);
+  const text = Object.values(summary)
+    .map(section => [`## ${section.title}`, ...section.blocks.map(block => `- ${block.content}`)].join('\n'))
+    .join('\n\n');
+  invoke('report_summary_ready', { meetingId: null, title, actionItems, summary: text })
+    .catch(error => console.error('Failed to report summary:', error));
+};
+
 interface OllamaModel {
   name: string;
   id: string;
@@ -163,6 +185,23 @@ export default function Home() {
     }
   }, [isRecording]);
 
+  useEffect(() => {
+    // External tools annotate the session through the local API; show them inline
+    const unlisten = listen<SessionMarker>('marker-inserted', (event) => {
+      const marker = event.payload;
+      setTranscripts(prev => [...prev, {
+        id: `marker-${marker.created_at}`,
+        text: marker.label,
+        timestamp: `${(marker.offset_ms / 1000).toFixed(1)}`,
+        marker: { source: marker.source, link: marker.link },
+      }]);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   useEffect(() => {
     let unlistenFn: (() => void) | undefined;
     let transcriptCounter = 0;  // Counter for unique IDs
@@ -176,6 +215,8 @@ export default function Home() {
             id: `${Date.now()}-${transcriptCounter++}`,  // Combine timestamp with counter for uniqueness
             text: event.payload.text,
             timestamp: event.payload.timestamp,
+            speaker: event.payload.source_app ?? event.payload.source,
+            speaker_display: event.payload.speaker_display,
           };
           setTranscripts(prev => {
             // Check if this transcript already exists
@@ -339,7 +380,7 @@ export default function Home() {
     }
   };
 
-  const handleRecordingStop2 = async (isCallApi: boolean) => {
+  const handleRecordingStop2 = async (isCallApi: boolean, alreadyStopped = false) => {
     try {
       console.log('Stopping recording (new implementation)...');
       const { invoke } = await import('@tauri-apps/api/core');
@@ -350,14 +391,16 @@ export default function Home() {
       const transcriptPath = `${dataDir}transcript-${timestamp}.txt`;
       const audioPath = `${dataDir}recording-${timestamp}.wav`;
       
-      // Stop recording and get audio path
-      await invoke('stop_recording', { 
-        args: { 
-          model_config: modelConfig,
-          save_path: audioPath
-        }
-      });
-      console.log('Recording stopped successfully');
+      // Stop recording and get audio path, unless the backend already stopped it
+      if (!alreadyStopped) {
+        await invoke('stop_recording', { 
+          args: { 
+            model_config: modelConfig,
+            save_path: audioPath
+          }
+        });
+        console.log('Recording stopped successfully');
+      }
 
       // Save to SQLite
       if (isCallApi) {
@@ -513,6 +556,7 @@ export default function Home() {
 
             setAiSummary(formattedSummary);
             setSummaryStatus('completed');
+            reportSummaryReady(formattedSummary, MeetingName || meetingTitle);
           }
         } catch (error) {
           console.error('Failed to get summary status:', error);
@@ -538,7 +582,7 @@ export default function Home() {
       }
       setSummaryStatus('error');
     }
-  }, [transcripts, modelConfig]);
+  }, [transcripts, modelConfig, meetingTitle]);
 
   const handleSummary = useCallback((summary: any) => {
     setAiSummary(summary);
@@ -576,9 +620,11 @@ export default function Home() {
   const handleDownloadTranscript = async () => {
     try {
       // Create transcript object with metadata
+      const speakers = await invoke<Record<string, SpeakerDisplay>>('list_speaker_display').catch(() => ({}));
       const transcriptData = {
         title: meetingTitle,
         timestamp: new Date().toISOString(),
+        speakers,
         transcripts: transcripts
       };
 
@@ -708,6 +754,7 @@ export default function Home() {
 
             setAiSummary(formattedSummary);
             setSummaryStatus('completed');
+            reportSummaryReady(formattedSummary, MeetingName || meetingTitle);
           } else if (result.status === 'error') {
             clearInterval(pollInterval);
             throw new Error(result.error || 'Failed to generate summary');
@@ -736,7 +783,7 @@ export default function Home() {
       setSummaryStatus('error');
       setAiSummary(null);
     }
-  }, [originalTranscript, modelConfig]);
+  }, [originalTranscript, modelConfig, meetingTitle]);
 
   const handleCopyTranscript = useCallback(() => {
     const fullTranscript = transcripts
@@ -763,6 +810,27 @@ export default function Home() {
     }
   }, [transcripts, generateAISummary]);
 
+  // Recording stopped from the tray, a hotkey or the meeting-end detector
+  const backendStopRef = useRef<(summarize: boolean) => void>(() => {});
+  backendStopRef.current = (summarize: boolean) => {
+    if (summarize) {
+      // Stay here so the summary shows next to the transcript
+      handleRecordingStop2(false, true).then(() => handleGenerateSummary());
+    } else {
+      handleRecordingStop2(true, true);
+    }
+  };
+
+  useEffect(() => {
+    const unlisten = listen<{ summarize: boolean }>('recording-stopped', (event) => {
+      backendStopRef.current(event.payload.summarize);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const isSummaryLoading = summaryStatus === 'processing' || summaryStatus === 'summarizing' || summaryStatus === 'regenerating';
 
   return (
@@ -5,6 +5,7 @@ import { appDataDir } from '@tauri-apps/api/path';
 import { useCallback, useEffect, useState, useRef } from 'react';
 import { Play, Pause, Square, Mic } from 'lucide-react';
 import { ProcessRequest, SummaryResponse } from '@/types/summary';
+import { useMicPreview } from '@/hooks/useMicPreview';
 
 interface RecordingControlsProps {
   isRecording: boolean;
@@ -30,7 +31,14 @@ export const RecordingControls: React.FC<RecordingControlsProps> = ({
   const [stopCountdown, setStopCountdown] = useState(5);
   const countdownInterval = useRef<NodeJS.Timeout | null>(null);
   const stopTimeoutRef = useRef<{ stop: () => void } | null>(null);
+  const [previewDevice, setPreviewDevice] = useState<string | null>(null);
   const MIN_RECORDING_DURATION = 2000; // 2 seconds minimum recording time
+  const PREVIEW_FLOOR_DB = -60;
+
+  // The preview releases the mic before the recording opens it
+  const { level: previewLevel } = useMicPreview(
+    isRecording || isStarting ? null : previewDevice,
+  );
 
   const currentTime = 0;
   const duration = 0;
@@ -56,6 +64,20 @@ export const RecordingControls: React.FC<RecordingControlsProps> = ({
     checkTauri();
   }, []);
 
+  useEffect(() => {
+    if (isRecording) return;
+    invoke<string>('get_preview_device')
+      .then(setPreviewDevice)
+      .catch((error) => console.error('Failed to get the preview device:', error));
+  }, [isRecording]);
+
+  const idleBarHeight = (index: number) => {
+    if (!previewLevel) return '4px';
+    const loudness = Math.max(0, previewLevel.rmsDb - PREVIEW_FLOOR_DB) / -PREVIEW_FLOOR_DB;
+    const wave = 0.6 + 0.4 * Math.sin(index + 1);
+    return `${Math.max(4, Math.round(24 * loudness * wave))}px`;
+  };
+
   const handleStartRecording = useCallback(async () => {
     if (isStarting) return;
     console.log('Starting recording...');
@@ -248,13 +270,18 @@ export const RecordingControls: React.FC<RecordingControlsProps> = ({
                   )}
                 </button>
 
-                <div className="flex items-center space-x-1 mx-4">
+                <div
+                  className="flex items-center space-x-1 mx-4"
+                  title={!isRecording && previewLevel ? (previewLevel.speech ? 'Speech detected' : 'No speech detected') : undefined}
+                >
                   {barHeights.map((height, index) => (
                     <div
                       key={index}
-                      className="w-1 bg-red-500 rounded-full transition-all duration-200"
+                      className={`w-1 rounded-full transition-all duration-200 ${
+                        isRecording || previewLevel?.speech ? 'bg-red-500' : 'bg-gray-300'
+                      }`}
                       style={{
-                        height: isRecording ? height : '4px',
+                        height: isRecording ? height : idleBarHeight(index),
                       }}
                     />
                   ))}
@@ -3,6 +3,16 @@
 import { Transcript } from '@/types';
 import { useEffect, useRef } from 'react';
 
+// Marker links come from external tools; only web pages may be opened
+const isWebLink = (link: string) => {
+  try {
+    const { protocol } = new URL(link);
+    return protocol === 'http:' || protocol === 'https:';
+  } catch {
+    return false;
+  }
+};
+
 interface TranscriptViewProps {
   transcripts: Transcript[];
 }
@@ -18,7 +28,21 @@ export const TranscriptView: React.FC<TranscriptViewProps> = ({ transcripts }) =
 
   return (
     <div ref={containerRef} className="h-full overflow-y-auto px-4 py-2">
-      {transcripts?.map((transcript) => (
+      {transcripts?.map((transcript) => transcript.marker ? (
+        <div key={transcript.id} className="mb-3 px-2 py-1 border-l-4 border-amber-400 bg-amber-50 rounded">
+          <span className="text-xs text-gray-500 mr-2">{transcript.timestamp}s</span>
+          {transcript.marker.source && (
+            <span className="text-xs font-medium text-amber-700 mr-2">{transcript.marker.source}</span>
+          )}
+          {transcript.marker.link && isWebLink(transcript.marker.link) ? (
+            <a href={transcript.marker.link} target="_blank" rel="noreferrer" className="text-sm text-amber-900 underline">
+              {transcript.text}
+            </a>
+          ) : (
+            <span className="text-sm text-amber-900">{transcript.text}</span>
+          )}
+        </div>
+      ) : (
         <div key={transcript.id + Math.random().toString(36).substring(2, 9)} className="mb-3 p-2 bg-gray-50 rounded-lg">
           <span className="text-xs text-gray-500 block mb-1">{transcript.timestamp}</span>
           <p className="text-sm text-gray-800">{transcript.text}</p>
@@ -0,0 +1,100 @@
+import { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+// Built from src-tauri/vad by `npm run build:vad`; the same detector the
+// recording pipeline uses, so the preview and the recording agree.
+const VAD_MODULE = '/vad/meetingly_vad.js';
+const BUFFER_SIZE = 2048;
+
+interface EnergyVad {
+  threshold_db: number;
+  frame_ms: number;
+  hangover_frames: number;
+}
+
+interface PreviewVad {
+  process(samples: Float32Array): boolean;
+  readonly rms_db: number;
+  readonly peak_db: number;
+  free(): void;
+}
+
+interface VadModule {
+  default: () => Promise<unknown>;
+  PreviewVad: new (sampleRate: number, thresholdDb: number, frameMs: number, hangoverFrames: number) => PreviewVad;
+}
+
+export interface MicPreviewLevel {
+  speech: boolean;
+  rmsDb: number;
+  peakDb: number;
+}
+
+/**
+ * Live speech and level feedback for a microphone before recording starts,
+ * without starting the Rust capture pipeline. `device` is the backend device
+ * name the VAD is tuned for; `deviceId` picks the browser input, defaulting
+ * to the system microphone.
+ */
+export const useMicPreview = (device: string | null, deviceId?: string) => {
+  const [level, setLevel] = useState<MicPreviewLevel | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const contextRef = useRef<AudioContext | null>(null);
+
+  useEffect(() => {
+    if (!device) {
+      setLevel(null);
+      return;
+    }
+    let cancelled = false;
+    let stream: MediaStream | null = null;
+    let processor: ScriptProcessorNode | null = null;
+    let vad: PreviewVad | null = null;
+
+    const start = async () => {
+      try {
+        const module: VadModule = await import(/* webpackIgnore: true */ VAD_MODULE);
+        await module.default();
+        const params = await invoke<EnergyVad>('get_preview_vad', { device });
+        if (cancelled) return;
+        stream = await navigator.mediaDevices.getUserMedia({
+          audio: deviceId ? { deviceId: { exact: deviceId } } : true,
+        });
+        if (cancelled) {
+          stream.getTracks().forEach((track) => track.stop());
+          return;
+        }
+
+        const context = new AudioContext();
+        contextRef.current = context;
+        vad = new module.PreviewVad(context.sampleRate, params.threshold_db, params.frame_ms, params.hangover_frames);
+        const source = context.createMediaStreamSource(stream);
+        processor = context.createScriptProcessor(BUFFER_SIZE, 1, 1);
+        processor.onaudioprocess = (event) => {
+          if (!vad) return;
+          const speech = vad.process(event.inputBuffer.getChannelData(0));
+          setLevel({ speech, rmsDb: vad.rms_db, peakDb: vad.peak_db });
+        };
+        source.connect(processor);
+        processor.connect(context.destination);
+        setError(null);
+      } catch (err) {
+        console.error('Failed to start mic preview:', err);
+        setError(err instanceof Error ? err.message : String(err));
+      }
+    };
+    start();
+
+    return () => {
+      cancelled = true;
+      processor?.disconnect();
+      stream?.getTracks().forEach((track) => track.stop());
+      contextRef.current?.close();
+      contextRef.current = null;
+      vad?.free();
+      vad = null;
+    };
+  }, [device, deviceId]);
+
+  return { level, error };
+};
@@ -4,10 +4,20 @@ export interface Message {
   timestamp: string;
 }
 
+export interface SpeakerDisplay {
+  color: string;
+  initials: string;
+  role?: string | null;
+}
+
 export interface Transcript {
   id: string;
   text: string;
   timestamp: string;
+  speaker?: string;
+  speaker_display?: SpeakerDisplay | null;
+  /** Set when this entry is a session marker or external annotation rather than speech. */
+  marker?: { source?: string | null; link?: string | null };
 }
 
 export interface Block {